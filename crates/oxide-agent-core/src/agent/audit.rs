@@ -0,0 +1,241 @@
+//! Tool audit log
+//!
+//! Appends one JSON line per tool execution to `AUDIT_LOG_PATH`, giving a
+//! durable record of what agents did for security review. Arguments and
+//! results are redacted and truncated before they are written. User
+//! feedback on answers goes to the same file, keyed by task ID.
+
+use super::tool_error::ToolError;
+use crate::redaction::redact_secrets;
+use crate::utils::truncate_str;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use tracing::{info, warn};
+
+/// Maximum number of characters of tool arguments kept in a record
+const AUDIT_ARGUMENTS_MAX_CHARS: usize = 2000;
+/// Maximum number of characters of tool output kept in a record
+const AUDIT_RESULT_MAX_CHARS: usize = 500;
+
+/// A single tool execution
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a> {
+    /// When the tool finished
+    pub timestamp: DateTime<Utc>,
+    /// Session owner
+    pub user_id: i64,
+    /// Task the call belongs to
+    pub task_id: &'a str,
+    /// Executed tool
+    pub tool_name: &'a str,
+    /// Redacted, truncated arguments
+    pub arguments: String,
+    /// Redacted, truncated tool output
+    pub result_summary: String,
+    /// Error category when the tool failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<&'static str>,
+    /// Execution time in milliseconds
+    pub duration_ms: u64,
+}
+
+impl<'a> AuditRecord<'a> {
+    /// Build a record, redacting secrets from arguments and output
+    #[must_use]
+    pub fn new(
+        user_id: i64,
+        task_id: &'a str,
+        tool_name: &'a str,
+        arguments: &str,
+        output: &str,
+        error: Option<&ToolError>,
+        duration_ms: u64,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            user_id,
+            task_id,
+            tool_name,
+            // Redact before truncating: a cut secret no longer matches its pattern
+            arguments: truncate_str(redact_secrets(arguments), AUDIT_ARGUMENTS_MAX_CHARS),
+            result_summary: truncate_str(redact_secrets(output), AUDIT_RESULT_MAX_CHARS),
+            error_kind: error.map(|e| e.kind.as_str()),
+            duration_ms,
+        }
+    }
+}
+
+/// A user's 👍/👎 rating of a task's answer
+#[derive(Debug, Serialize)]
+pub struct FeedbackRecord<'a> {
+    /// When the rating was given
+    pub timestamp: DateTime<Utc>,
+    /// User who rated the answer
+    pub user_id: i64,
+    /// Rated task
+    pub task_id: &'a str,
+    /// `true` for 👍, `false` for 👎
+    pub helpful: bool,
+}
+
+impl<'a> FeedbackRecord<'a> {
+    /// Build a record timestamped now
+    #[must_use]
+    pub fn new(user_id: i64, task_id: &'a str, helpful: bool) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            user_id,
+            task_id,
+            helpful,
+        }
+    }
+}
+
+/// Append-only JSONL audit sink
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open (or create) the audit log for appending
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append a record as a single JSON line
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or the write fails.
+    pub fn append(&self, record: &impl Serialize) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+}
+
+static AUDIT_LOG: LazyLock<Option<AuditLog>> = LazyLock::new(|| {
+    let path = crate::config::get_audit_log_path()?;
+    match AuditLog::open(&path) {
+        Ok(log) => {
+            info!(path = %path, "Tool audit log enabled");
+            Some(log)
+        }
+        Err(e) => {
+            warn!(path = %path, error = %e, "Failed to open tool audit log, auditing disabled");
+            None
+        }
+    }
+});
+
+/// Record a tool execution in the audit log, if `AUDIT_LOG_PATH` is set
+pub fn record(record: &AuditRecord<'_>) {
+    if let Some(log) = AUDIT_LOG.as_ref() {
+        if let Err(e) = log.append(record) {
+            warn!(error = %e, tool_name = record.tool_name, "Failed to write audit record");
+        }
+    }
+}
+
+/// Record answer feedback in the audit log, if `AUDIT_LOG_PATH` is set
+pub fn record_feedback(record: &FeedbackRecord<'_>) {
+    info!(
+        user_id = record.user_id,
+        task_id = record.task_id,
+        helpful = record.helpful,
+        "Answer feedback received"
+    );
+    if let Some(log) = AUDIT_LOG.as_ref() {
+        if let Err(e) = log.append(record) {
+            warn!(error = %e, task_id = record.task_id, "Failed to write feedback record");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::tool_error::ToolErrorKind;
+
+    #[test]
+    fn test_append_writes_redacted_json_lines() -> std::io::Result<()> {
+        let path = std::env::temp_dir().join(format!("oxide-audit-{}.jsonl", std::process::id()));
+        let log = AuditLog::open(&path)?;
+
+        let error = ToolError::new(ToolErrorKind::Internal, "boom");
+        log.append(&AuditRecord::new(
+            42,
+            "task-1",
+            "execute_command",
+            r#"{"command":"echo R2_SECRET_ACCESS_KEY=hunter2"}"#,
+            "boom",
+            Some(&error),
+            15,
+        ))?;
+        log.append(&AuditRecord::new(
+            42,
+            "task-1",
+            "read_file",
+            "{}",
+            "ok",
+            None,
+            3,
+        ))?;
+
+        let contents = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["tool_name"], "execute_command");
+        assert_eq!(lines[0]["error_kind"], "internal");
+        assert!(lines[0]["arguments"]
+            .as_str()
+            .is_some_and(|args| args.contains("[MASKED]") && !args.contains("hunter2")));
+        assert!(lines[1].get("error_kind").is_none());
+        assert_eq!(lines[1]["duration_ms"], 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_secret_at_truncation_boundary_is_masked() {
+        let output = format!("{}sk-{}", "x ".repeat(245), "a1".repeat(15));
+        let record = AuditRecord::new(42, "task-1", "execute_command", "{}", &output, None, 1);
+        assert!(!record.result_summary.contains("sk-a1"));
+        assert!(record.result_summary.ends_with("[MASKED]"));
+    }
+
+    #[test]
+    fn test_feedback_is_appended_with_task_id() -> std::io::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("oxide-audit-feedback-{}.jsonl", std::process::id()));
+        let log = AuditLog::open(&path)?;
+        log.append(&FeedbackRecord::new(42, "task-1", false))?;
+
+        let contents = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+
+        let record: serde_json::Value = serde_json::from_str(contents.trim())?;
+        assert_eq!(record["user_id"], 42);
+        assert_eq!(record["task_id"], "task-1");
+        assert_eq!(record["helpful"], false);
+        assert!(record.get("timestamp").is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! Folding of repetitive tool output (`COMPRESS_TOOL_OUTPUT`)
+//!
+//! Directory listings, logs and JSON metadata repeat the same structure many
+//! times and fill the context long before the information runs out. Large
+//! results are folded before they reach the conversation: runs of lines with
+//! the same shape keep their first and last lines, and long JSON arrays keep
+//! their first items. The full result is written to the sandbox, so the agent
+//! can still read it or hand it over with `send_file_to_user`. Results are
+//! only folded when the sandbox is already running; folding never starts a
+//! container.
+
+use crate::config::get_compress_tool_output_min_chars;
+use crate::sandbox::SandboxHandle;
+use anyhow::Result;
+use serde_json::Value;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Sandbox directory that keeps the full output of folded results
+pub const FULL_OUTPUT_DIR: &str = "/workspace/.tool_outputs";
+
+/// Tools whose output the agent asked for verbatim
+const EXEMPT_TOOLS: &[&str] = &["read_file", "ask_user", "write_todos"];
+/// Lines kept at the start and the end of a folded run
+const KEEP_HEAD_LINES: usize = 3;
+const KEEP_TAIL_LINES: usize = 1;
+/// Shortest run of similar lines worth folding
+const MIN_FOLDED_RUN: usize = KEEP_HEAD_LINES + KEEP_TAIL_LINES + 2;
+/// Items kept from a long JSON array
+const KEEP_ARRAY_ITEMS: usize = 3;
+
+/// Folds large tool results and keeps the originals in the sandbox
+pub struct OutputCompressor {
+    sandbox: SandboxHandle,
+    min_chars: usize,
+}
+
+impl OutputCompressor {
+    /// Compressor storing full outputs in `sandbox`
+    ///
+    /// Environment variable: `COMPRESS_TOOL_OUTPUT_MIN_CHARS`
+    #[must_use]
+    pub fn new(sandbox: SandboxHandle) -> Self {
+        Self {
+            sandbox,
+            min_chars: get_compress_tool_output_min_chars(),
+        }
+    }
+
+    /// Folded `output` of `tool_name`, or `output` itself when folding would
+    /// not help, no sandbox is running or the full result cannot be stored
+    pub async fn compress(&self, tool_name: &str, output: String) -> String {
+        if EXEMPT_TOOLS.contains(&tool_name) || output.len() < self.min_chars {
+            return output;
+        }
+        let Some(folded) = fold_output(&output) else {
+            return output;
+        };
+
+        let id = Uuid::new_v4().simple().to_string();
+        let path = format!("{FULL_OUTPUT_DIR}/{tool_name}-{}.txt", &id[..8]);
+        if let Err(e) = self.save(&path, &output).await {
+            warn!(tool_name, error = %e, "Cannot store full tool output, keeping it uncompressed");
+            return output;
+        }
+
+        debug!(
+            tool_name,
+            original_chars = output.len(),
+            folded_chars = folded.len(),
+            "Compressed tool output"
+        );
+        format!(
+            "{folded}\n\n[Repetitive output folded from {} to {} chars. \
+             Full output: {path} (use read_file or send_file_to_user)]",
+            output.len(),
+            folded.len()
+        )
+    }
+
+    async fn save(&self, path: &str, output: &str) -> Result<()> {
+        let Some(sandbox) = self.sandbox.existing().await else {
+            anyhow::bail!("no running sandbox");
+        };
+        sandbox.upload_file(path, output.as_bytes()).await
+    }
+}
+
+/// Fold JSON arrays or runs of similar lines; `None` if that saves under 20%
+pub(crate) fn fold_output(output: &str) -> Option<String> {
+    let folded = fold_json(output).unwrap_or_else(|| fold_lines(output));
+    (folded.len() * 5 <= output.len() * 4).then_some(folded)
+}
+
+/// Keep the first items of long arrays, at any depth
+fn fold_json(output: &str) -> Option<String> {
+    let mut value: Value = serde_json::from_str(output.trim()).ok()?;
+    if !fold_value(&mut value) {
+        return None;
+    }
+    if output.trim().contains('\n') {
+        serde_json::to_string_pretty(&value).ok()
+    } else {
+        serde_json::to_string(&value).ok()
+    }
+}
+
+fn fold_value(value: &mut Value) -> bool {
+    let mut folded = false;
+    match value {
+        Value::Array(items) => {
+            if items.len() > KEEP_ARRAY_ITEMS + 1 {
+                let omitted = items.len() - KEEP_ARRAY_ITEMS;
+                items.truncate(KEEP_ARRAY_ITEMS);
+                items.push(Value::String(format!("[... {omitted} more items folded]")));
+                folded = true;
+            }
+            for item in items {
+                folded |= fold_value(item);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                folded |= fold_value(item);
+            }
+        }
+        _ => {}
+    }
+    folded
+}
+
+/// Replace the middle of runs of similar lines with a marker
+fn fold_lines(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut folded: Vec<String> = Vec::with_capacity(lines.len());
+
+    let mut start = 0;
+    while start < lines.len() {
+        let anchor = line_shape(lines[start]);
+        let end = lines[start..]
+            .iter()
+            .position(|line| !is_similar(&anchor, &line_shape(line)))
+            .map_or(lines.len(), |len| start + len);
+
+        let run = &lines[start..end];
+        if run.len() >= MIN_FOLDED_RUN {
+            folded.extend(run[..KEEP_HEAD_LINES].iter().map(ToString::to_string));
+            folded.push(format!(
+                "[... {} similar lines folded ...]",
+                run.len() - KEEP_HEAD_LINES - KEEP_TAIL_LINES
+            ));
+            folded.extend(
+                run[run.len() - KEEP_TAIL_LINES..]
+                    .iter()
+                    .map(ToString::to_string),
+            );
+        } else {
+            folded.extend(run.iter().map(ToString::to_string));
+        }
+        start = end;
+    }
+
+    folded.join("\n")
+}
+
+/// Tokens of a line with digit runs replaced by `#`
+fn line_shape(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        .map(|token| {
+            let mut shape = String::with_capacity(token.len());
+            for c in token.chars() {
+                if !c.is_ascii_digit() {
+                    shape.push(c);
+                } else if !shape.ends_with('#') {
+                    shape.push('#');
+                }
+            }
+            shape
+        })
+        .collect()
+}
+
+/// Same number of tokens and at most a third of them (at least one) differ
+fn is_similar(a: &[String], b: &[String]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let differing = a.iter().zip(b).filter(|(x, y)| x != y).count();
+    differing <= (a.len() / 3).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_listing_is_folded() {
+        let listing: Vec<String> = (1..=40)
+            .map(|i| {
+                format!(
+                    "-rw-r--r-- 1 root root {} Jan {i} 12:00 file_{i}.txt",
+                    i * 100
+                )
+            })
+            .collect();
+        let output = format!("total 160\n{}\ndone", listing.join("\n"));
+
+        let Some(folded) = fold_output(&output) else {
+            panic!("listing should fold");
+        };
+        assert!(folded.starts_with("total 160\n-rw-r--r-- 1 root root 100 Jan 1 12:00 file_1.txt"));
+        assert!(folded.contains("file_3.txt\n[... 36 similar lines folded ...]\n"));
+        assert!(folded.ends_with("file_40.txt\ndone"));
+    }
+
+    #[test]
+    fn test_long_json_arrays_keep_first_items() {
+        let formats: Vec<String> = (0..50)
+            .map(|i| {
+                format!(
+                    r#"{{"format_id": "{i}", "height": {}, "ext": "mp4"}}"#,
+                    i * 10
+                )
+            })
+            .collect();
+        let output = format!(
+            r#"{{"title": "Video", "formats": [{}]}}"#,
+            formats.join(",")
+        );
+
+        let Some(folded) = fold_output(&output) else {
+            panic!("metadata should fold");
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&folded) else {
+            panic!("folded JSON should stay valid: {folded}");
+        };
+        assert_eq!(value["title"], "Video");
+        assert_eq!(value["formats"][2]["format_id"], "2");
+        assert_eq!(value["formats"][3], "[... 47 more items folded]");
+    }
+
+    #[test]
+    fn test_unique_output_is_left_alone() {
+        let prose = "The build failed because the linker could not find libssl.\n\
+                     Install the development headers and run the command again.\n\
+                     Afterwards the tests should pass on this machine as well.";
+        assert_eq!(fold_output(prose), None);
+        assert_eq!(fold_output(r#"{"items": [1, 2, 3, 4]}"#), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Session state dump for the `/debug` command
+//!
+//! [`AgentExecutor::debug_info`](super::AgentExecutor::debug_info) reads the
+//! session, but the executor stays locked while a task runs. So the runner
+//! also publishes its iteration count and last tool call to a process-wide
+//! table keyed by session, which [`run_progress`] reads without the lock.
+
+use super::providers::TodoList;
+use super::session::AgentStatus;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+/// Progress of the latest run of a session
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunProgress {
+    /// LLM iterations started
+    pub iterations: usize,
+    /// Name of the last tool the agent called
+    pub last_tool: Option<String>,
+}
+
+/// Snapshot of an agent session
+#[derive(Debug, Clone)]
+pub struct SessionDebugInfo {
+    /// Session status
+    pub status: AgentStatus,
+    /// Progress of the latest run
+    pub run: RunProgress,
+    /// Messages in memory
+    pub messages: usize,
+    /// Estimated tokens in memory
+    pub tokens: usize,
+    /// Memory token limit
+    pub max_tokens: usize,
+    /// Skills loaded into the prompt, sorted
+    pub loaded_skills: Vec<String>,
+    /// Current todo list
+    pub todos: TodoList,
+    /// Whether loop detection is off for the current or next run
+    pub loop_detection_disabled: bool,
+}
+
+static RUNS: LazyLock<Mutex<HashMap<i64, RunProgress>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn runs() -> std::sync::MutexGuard<'static, HashMap<i64, RunProgress>> {
+    RUNS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Forget the previous run of a session and start tracking a new one
+pub fn start_run(session_id: i64) {
+    runs().insert(session_id, RunProgress::default());
+}
+
+/// Record the number of iterations started so far
+pub fn record_iteration(session_id: i64, iterations: usize) {
+    runs().entry(session_id).or_default().iterations = iterations;
+}
+
+/// Record a tool call of the running task
+pub fn record_tool_call(session_id: i64, name: &str) {
+    runs().entry(session_id).or_default().last_tool = Some(name.to_string());
+}
+
+/// Progress of the session's latest run, if it ran since the last reset
+#[must_use]
+pub fn run_progress(session_id: i64) -> Option<RunProgress> {
+    runs().get(&session_id).cloned()
+}
+
+/// Drop the tracked run, e.g. when the session is reset
+pub fn clear_run_progress(session_id: i64) {
+    runs().remove(&session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_progress_tracks_latest_run() {
+        let session_id = -8_000_002;
+        assert_eq!(run_progress(session_id), None);
+
+        start_run(session_id);
+        record_iteration(session_id, 3);
+        record_tool_call(session_id, "execute_command");
+        assert_eq!(
+            run_progress(session_id),
+            Some(RunProgress {
+                iterations: 3,
+                last_tool: Some("execute_command".to_string()),
+            })
+        );
+
+        start_run(session_id);
+        assert_eq!(run_progress(session_id), Some(RunProgress::default()));
+
+        clear_run_progress(session_id);
+        assert_eq!(run_progress(session_id), None);
+    }
+}
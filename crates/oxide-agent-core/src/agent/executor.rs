@@ -3,28 +3,42 @@
 //! Handles orchestration around the core agent runner, including
 //! session lifecycle, skill prompts, and tool registry setup.
 
+use super::compression::OutputCompressor;
+use super::debug::SessionDebugInfo;
 use super::hooks::{
-    CompletionCheckHook, DelegationGuardHook, SearchBudgetHook, TimeoutReportHook,
+    BudgetGuardHook, CompletionCheckHook, DelegationGuardHook, SearchBudgetHook, TimeoutReportHook,
     WorkloadDistributorHook,
 };
+use super::loop_detection::ContentLoopOverride;
 use super::memory::AgentMessage;
+use super::messages::AgentLanguage;
+use super::preprocessor::normalize_task;
 use super::prompt::create_agent_system_prompt;
 use super::providers::{
-    DelegationProvider, FileHosterProvider, SandboxProvider, TodosProvider, YtdlpProvider,
+    clarification, ClarificationProvider, DateTimeProvider, DelegationProvider, FileHosterProvider,
+    FinishTaskProvider, MediaProvider, SandboxProvider, TodosProvider, YtdlpProvider,
 };
 use super::registry::ToolRegistry;
-use super::runner::{AgentRunner, AgentRunnerConfig, AgentRunnerContext};
+use super::runner::{AgentRunResult, AgentRunner, AgentRunnerConfig, AgentRunnerContext, Outcome};
 use super::session::AgentSession;
 use super::skills::SkillRegistry;
+use super::tokenizer::tokenizer_for_model;
 use crate::agent::progress::AgentEvent;
-use crate::config::{get_agent_search_limit, AGENT_TIMEOUT_SECS};
+use crate::config::{
+    get_agent_max_tool_calls, get_agent_search_limit, get_agent_token_budget,
+    get_compress_tool_output, get_tool_allowlist, get_workload_drip_feed_iterations,
+    get_workload_drip_feed_max_calls, ToolOutputLimits, AGENT_TIMEOUT_SECS,
+};
 use crate::llm::LlmClient;
-use anyhow::{anyhow, Result};
+use crate::sandbox::SandboxHandle;
+use anyhow::Result;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tokio::time::{timeout, Duration};
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::{timeout, Duration, Instant};
 use tracing::{info, warn};
 
+#[cfg(feature = "brave")]
+use super::providers::BraveSearchProvider;
 #[cfg(feature = "crawl4ai")]
 use super::providers::Crawl4aiProvider;
 #[cfg(feature = "tavily")]
@@ -39,6 +53,8 @@ pub struct AgentExecutor {
     session: AgentSession,
     skill_registry: Option<SkillRegistry>,
     settings: Arc<crate::config::AgentSettings>,
+    /// `MAX_PARALLEL_TOOLS` permits shared by the agent and its sub-agents
+    tool_permits: Arc<Semaphore>,
 }
 
 impl AgentExecutor {
@@ -50,8 +66,20 @@ impl AgentExecutor {
         settings: Arc<crate::config::AgentSettings>,
     ) -> Self {
         let mut runner = AgentRunner::new(llm_client.clone());
+        // Registered first: it resets its counters on BeforeAgent, which other
+        // hooks may short-circuit.
+        let budget_guard =
+            BudgetGuardHook::new(get_agent_token_budget(), get_agent_max_tool_calls());
+        if budget_guard.is_enabled() {
+            runner.register_hook(Box::new(budget_guard));
+        }
         runner.register_hook(Box::new(CompletionCheckHook::new()));
-        runner.register_hook(Box::new(WorkloadDistributorHook::new()));
+        runner.register_hook(Box::new(
+            WorkloadDistributorHook::new().with_batching_policy(
+                get_workload_drip_feed_iterations(),
+                get_workload_drip_feed_max_calls(),
+            ),
+        ));
         runner.register_hook(Box::new(DelegationGuardHook::new()));
         runner.register_hook(Box::new(SearchBudgetHook::new(get_agent_search_limit())));
         runner.register_hook(Box::new(TimeoutReportHook::new()));
@@ -79,6 +107,23 @@ impl AgentExecutor {
             session,
             skill_registry,
             settings,
+            tool_permits: Arc::new(Semaphore::new(
+                crate::config::get_max_parallel_tools().max(1),
+            )),
+        }
+    }
+
+    /// Count memory and skill tokens with the agent model's tokenizer and
+    /// compact memory within the model's context window
+    fn use_model_limits(&mut self) {
+        let (model_id, _, max_tokens) = self.settings.get_configured_agent_model();
+        let tokenizer = tokenizer_for_model(&model_id);
+        self.session.memory.set_tokenizer(tokenizer);
+        self.session
+            .memory
+            .set_context_window(self.settings.get_agent_context_window(), max_tokens);
+        if let Some(registry) = self.skill_registry.as_mut() {
+            registry.set_tokenizer(tokenizer);
         }
     }
 
@@ -98,6 +143,11 @@ impl AgentExecutor {
         self.runner.disable_loop_detection_next_run();
     }
 
+    /// Use different content loop settings for the next execution attempt.
+    pub fn override_content_loop_next_run(&mut self, overrides: ContentLoopOverride) {
+        self.runner.override_content_loop_next_run(overrides);
+    }
+
     /// Get the last task text, if available.
     #[must_use]
     pub fn last_task(&self) -> Option<&str> {
@@ -111,28 +161,54 @@ impl AgentExecutor {
     ) -> ToolRegistry {
         let mut registry = ToolRegistry::new();
         registry.register(Box::new(TodosProvider::new(Arc::clone(&todos_arc))));
+        registry.register(Box::new(DateTimeProvider::new()));
+        registry.register(Box::new(FinishTaskProvider::new()));
 
         let session_id = self.session.session_id.as_i64();
+        // One container for every tool of the task, so their files meet in /workspace
+        let sandbox = SandboxHandle::new(session_id);
+        let sandbox_provider = SandboxProvider::new(session_id).with_sandbox(sandbox.clone());
         let sandbox_provider = if let Some(tx) = progress_tx {
-            SandboxProvider::new(session_id).with_progress_tx(tx.clone())
+            sandbox_provider.with_progress_tx(tx.clone())
         } else {
-            SandboxProvider::new(session_id)
+            sandbox_provider
         };
         registry.register(Box::new(sandbox_provider));
-        registry.register(Box::new(FileHosterProvider::new(session_id)));
+        registry.register(Box::new(
+            FileHosterProvider::new(session_id).with_sandbox(sandbox.clone()),
+        ));
+        // Questions need a transport to reach the user
+        if progress_tx.is_some() {
+            registry.register(Box::new(ClarificationProvider::new(session_id)));
+        }
 
+        let (_, _, max_tokens) = self.settings.get_configured_agent_model();
+        let output_limits = ToolOutputLimits::for_max_tokens(max_tokens);
+        let ytdlp_provider = YtdlpProvider::new(session_id)
+            .with_sandbox(sandbox.clone())
+            .with_output_limits(output_limits);
         let ytdlp_provider = if let Some(tx) = progress_tx {
-            YtdlpProvider::new(session_id).with_progress_tx(tx.clone())
+            ytdlp_provider.with_progress_tx(tx.clone())
         } else {
-            YtdlpProvider::new(session_id)
+            ytdlp_provider
         };
         registry.register(Box::new(ytdlp_provider));
 
-        registry.register(Box::new(DelegationProvider::new(
-            self.runner.llm_client(),
-            session_id,
-            self.settings.clone(),
-        )));
+        let llm_client = self.runner.llm_client();
+        if llm_client.is_multimodal_available() {
+            registry.register(Box::new(
+                MediaProvider::new(llm_client, session_id).with_sandbox(sandbox.clone()),
+            ));
+        }
+
+        // Keyed on the requester: in a group session each member keeps their own tools
+        let allowlist = get_tool_allowlist(self.session.requester_id());
+        registry.register(Box::new(
+            DelegationProvider::new(self.runner.llm_client(), session_id, self.settings.clone())
+                .with_sandbox(sandbox.clone())
+                .with_allowlist(allowlist.clone())
+                .with_tool_permits(Arc::clone(&self.tool_permits)),
+        ));
 
         // Register web search provider based on configuration
         let search_provider = crate::config::get_search_provider();
@@ -149,11 +225,27 @@ impl AgentExecutor {
                 #[cfg(not(feature = "tavily"))]
                 warn!("Tavily requested but feature not enabled");
             }
+            "brave" => {
+                #[cfg(feature = "brave")]
+                if let Ok(brave_key) = std::env::var("BRAVE_API_KEY") {
+                    if !brave_key.is_empty() {
+                        if let Ok(p) = BraveSearchProvider::new(&brave_key) {
+                            registry.register(Box::new(p));
+                        }
+                    }
+                }
+                #[cfg(not(feature = "brave"))]
+                warn!("Brave Search requested but feature not enabled");
+            }
             "crawl4ai" => {
                 #[cfg(feature = "crawl4ai")]
                 if let Ok(url) = std::env::var("CRAWL4AI_URL") {
                     if !url.is_empty() {
-                        registry.register(Box::new(Crawl4aiProvider::new(&url)));
+                        registry.register(Box::new(
+                            Crawl4aiProvider::new(&url)
+                                .with_sandbox(sandbox.clone())
+                                .with_output_limits(output_limits),
+                        ));
                     }
                 }
                 #[cfg(not(feature = "crawl4ai"))]
@@ -162,7 +254,9 @@ impl AgentExecutor {
             _ => unreachable!(), // get_search_provider() guarantees valid value
         }
 
-        registry
+        registry.with_allowlist(allowlist).with_output_compressor(
+            get_compress_tool_output().then(|| OutputCompressor::new(sandbox.clone())),
+        )
     }
 
     /// Execute a task with iterative tool calling (agentic loop)
@@ -170,12 +264,43 @@ impl AgentExecutor {
     /// # Errors
     ///
     /// Returns an error if the LLM call fails, tool execution fails, or the iteration/timeout limits are exceeded.
-    #[tracing::instrument(skip(self, progress_tx), fields(session_id = %self.session.session_id))]
     pub async fn execute(
         &mut self,
         task: &str,
         progress_tx: Option<tokio::sync::mpsc::Sender<AgentEvent>>,
     ) -> Result<String> {
+        self.execute_detailed(task, progress_tx)
+            .await
+            .and_then(AgentRunResult::into_response)
+    }
+
+    /// Execute a task and report iterations, tools, token usage and how it ended
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an LLM call or a hook fails. Cancellation, loops,
+    /// timeouts and limits are reported through [`AgentRunResult::outcome`].
+    #[tracing::instrument(skip(self, progress_tx), fields(session_id = %self.session.session_id))]
+    pub async fn execute_detailed(
+        &mut self,
+        task: &str,
+        progress_tx: Option<tokio::sync::mpsc::Sender<AgentEvent>>,
+    ) -> Result<AgentRunResult> {
+        let Some(task) = normalize_task(task) else {
+            anyhow::bail!(AgentLanguage::current().empty_task());
+        };
+        let task = task.as_str();
+        let window = Duration::from_secs(crate::config::get_agent_followup_window_secs());
+        let previous_task = self
+            .session
+            .is_follow_up(window)
+            .then(|| self.session.last_task.clone())
+            .flatten();
+        // Todos belong to a single task; memory and sandbox carry over
+        self.session.clear_todos();
+        crate::agent::instructions::clear_instructions(self.session.session_id.as_i64());
+        self.use_model_limits();
+
         self.session.start_task();
         let task_id = self.session.current_task_id.clone().unwrap_or_default();
         self.session.remember_task(task);
@@ -184,26 +309,35 @@ impl AgentExecutor {
             task_id = %task_id,
             memory_messages = self.session.memory.get_messages().len(),
             memory_tokens = self.session.memory.token_count(),
+            follow_up = previous_task.is_some(),
             "Starting agent task"
         );
 
-        self.session.memory.add_message(AgentMessage::user(task));
+        // Only the current task stays pinned; earlier prompts may be summarized
+        self.session.memory.unpin_user_messages();
+        self.session
+            .memory
+            .add_pinned_message(AgentMessage::user(task));
 
         let todos_arc = Arc::new(Mutex::new(self.session.memory.todos.clone()));
 
         let registry = self.build_tool_registry(Arc::clone(&todos_arc), progress_tx.as_ref());
 
         let tools = registry.all_tools();
-        let (_, provider, _) = self.settings.get_configured_agent_model();
+        let (_, provider, max_tokens) = self.settings.get_configured_agent_model();
         let structured_output = !provider.eq_ignore_ascii_case("zai");
-        let system_prompt = create_agent_system_prompt(
+        let mut system_prompt = create_agent_system_prompt(
             task,
             &tools,
             structured_output,
+            max_tokens,
             self.skill_registry.as_mut(),
             &mut self.session,
         )
         .await;
+        if let Some(previous_task) = previous_task.as_deref() {
+            system_prompt.push_str(&follow_up_context(previous_task));
+        }
         let mut messages =
             AgentRunner::convert_memory_to_messages(self.session.memory.get_messages());
 
@@ -215,41 +349,47 @@ impl AgentExecutor {
             progress_tx: progress_tx.as_ref(),
             todos_arc: &todos_arc,
             task_id: &task_id,
+            user_id: self.session.session_id.as_i64(),
             messages: &mut messages,
             agent: &mut self.session,
             skill_registry: self.skill_registry.as_mut(),
-            config: {
-                let (model_id, _, _) = self.settings.get_configured_agent_model();
-                AgentRunnerConfig::new(
-                    model_id,
-                    crate::config::AGENT_MAX_ITERATIONS,
-                    crate::config::AGENT_CONTINUATION_LIMIT,
-                    self.settings.get_agent_timeout_secs(),
-                )
-            },
+            config: runner_config(&self.settings).with_tool_permits(Arc::clone(&self.tool_permits)),
         };
 
         let timeout_duration = Duration::from_secs(AGENT_TIMEOUT_SECS);
-        match timeout(timeout_duration, self.runner.run(&mut ctx)).await {
-            Ok(inner) => match inner {
-                Ok(res) => {
+        let session_id = ctx.user_id;
+        let run = self.runner.run_detailed(&mut ctx);
+        let result = match timeout_excluding_waits(timeout_duration, session_id, run).await {
+            Ok(Ok(result)) => {
+                if result.is_answer() {
                     self.session.complete();
-                    Ok(res)
+                } else {
+                    self.session.fail(result.response.clone());
                 }
-                Err(e) => {
-                    self.session.fail(e.to_string());
-                    Err(e)
-                }
-            },
-            Err(_) => {
+                result
+            }
+            Ok(Err(e)) => {
+                self.session.fail(e.to_string());
+                return Err(e);
+            }
+            Err(()) => {
                 self.session.timeout();
                 let limit_mins = self.settings.get_agent_timeout_secs() / 60;
-                Err(anyhow!(
-                    "Task exceeded timeout limit ({} minutes)",
-                    limit_mins
-                ))
+                self.runner.interrupted_run_result(
+                    Outcome::Timeout,
+                    AgentLanguage::current().timeout(limit_mins),
+                )
             }
-        }
+        };
+
+        info!(
+            outcome = ?result.outcome,
+            iterations = result.iterations,
+            tool_calls = result.tool_calls.len(),
+            total_tokens = result.usage.total_tokens,
+            "Agent task finished"
+        );
+        Ok(result)
     }
 
     /// Check if the task has been cancelled
@@ -262,6 +402,25 @@ impl AgentExecutor {
     pub fn reset(&mut self) {
         self.session.reset();
         self.runner.reset();
+        super::debug::clear_run_progress(self.session.session_id.as_i64());
+        super::providers::clear_working_dir(self.session.session_id.as_i64());
+        super::providers::clear_session_env(self.session.session_id.as_i64());
+    }
+
+    /// Snapshot of the session for the `/debug` command
+    #[must_use]
+    pub fn debug_info(&self) -> SessionDebugInfo {
+        let memory = &self.session.memory;
+        SessionDebugInfo {
+            status: self.session.status.clone(),
+            run: super::debug::run_progress(self.session.session_id.as_i64()).unwrap_or_default(),
+            messages: memory.get_messages().len(),
+            tokens: memory.token_count(),
+            max_tokens: memory.max_tokens(),
+            loaded_skills: self.session.loaded_skills(),
+            todos: memory.todos.clone(),
+            loop_detection_disabled: self.runner.loop_detection_disabled(),
+        }
     }
 
     /// Check if the session is timed out
@@ -271,4 +430,47 @@ impl AgentExecutor {
     }
 }
 
+/// Runner limits for a top-level task.
+fn runner_config(settings: &crate::config::AgentSettings) -> AgentRunnerConfig {
+    let (model_id, _, _) = settings.get_configured_agent_model();
+    AgentRunnerConfig::new(
+        model_id,
+        crate::config::AGENT_MAX_ITERATIONS,
+        crate::config::get_agent_continuation_limit(),
+        settings.get_agent_timeout_secs(),
+    )
+}
+
+/// Await `run` for at most `limit` of active time; time the session spends
+/// waiting for the user to answer `ask_user` does not count.
+async fn timeout_excluding_waits<F: std::future::Future>(
+    limit: Duration,
+    session_id: i64,
+    run: F,
+) -> Result<F::Output, ()> {
+    let started = Instant::now();
+    tokio::pin!(run);
+    loop {
+        let active = started
+            .elapsed()
+            .saturating_sub(clarification::time_waited(session_id));
+        let remaining = limit.saturating_sub(active);
+        if remaining.is_zero() {
+            return Err(());
+        }
+        if let Ok(output) = timeout(remaining, &mut run).await {
+            return Ok(output);
+        }
+    }
+}
+
+/// System prompt addendum for a task that continues the previous one.
+fn follow_up_context(previous_task: &str) -> String {
+    format!(
+        "\n\n## Follow-up\nThe user is following up on the previous task: \"{previous_task}\".\n\
+         Its results, the conversation history and all files in /workspace are still available. \
+         Resolve references like \"that file\" or \"the result\" against them instead of starting over."
+    )
+}
+
 // All tests have been moved to recovery.rs and other specific modules
@@ -0,0 +1,171 @@
+//! Budget Guard Hook.
+//!
+//! Ends a task once it has spent its token budget or tool-call allowance.
+//! Counters are reset on every `BeforeAgent` event, so the limits apply per task.
+
+use super::registry::Hook;
+use super::types::{HookContext, HookEvent, HookResult};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Hook that stops the agent when a token or tool-call budget is exhausted.
+pub struct BudgetGuardHook {
+    token_budget: Option<usize>,
+    max_tool_calls: Option<usize>,
+    token_baseline: AtomicUsize,
+    tool_calls: AtomicUsize,
+}
+
+impl BudgetGuardHook {
+    /// Create a new budget guard hook. `None` disables the corresponding limit.
+    #[must_use]
+    pub const fn new(token_budget: Option<usize>, max_tool_calls: Option<usize>) -> Self {
+        Self {
+            token_budget,
+            max_tool_calls,
+            token_baseline: AtomicUsize::new(0),
+            tool_calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether at least one limit is configured.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.token_budget.is_some() || self.max_tool_calls.is_some()
+    }
+
+    fn check_tokens(&self, context: &HookContext) -> HookResult {
+        let Some(budget) = self.token_budget else {
+            return HookResult::Continue;
+        };
+
+        let spent = context
+            .memory
+            .spent_tokens()
+            .saturating_sub(self.token_baseline.load(Ordering::SeqCst));
+        if spent < budget {
+            return HookResult::Continue;
+        }
+
+        HookResult::Finish(format!(
+            "Budget exhausted: this task used {spent} tokens of its {budget} token budget. \
+             Stopped before completing; ask again to continue from the current state."
+        ))
+    }
+
+    fn check_tool_calls(&self) -> HookResult {
+        let Some(limit) = self.max_tool_calls else {
+            return HookResult::Continue;
+        };
+
+        let current = self.tool_calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if current <= limit {
+            return HookResult::Continue;
+        }
+
+        HookResult::Finish(format!(
+            "Budget exhausted: this task reached its limit of {limit} tool calls. \
+             Stopped before completing; ask again to continue from the current state."
+        ))
+    }
+}
+
+impl Hook for BudgetGuardHook {
+    fn name(&self) -> &'static str {
+        "budget_guard"
+    }
+
+    fn handle(&self, event: &HookEvent, context: &HookContext) -> HookResult {
+        match event {
+            HookEvent::BeforeAgent { .. } => {
+                self.token_baseline
+                    .store(context.memory.spent_tokens(), Ordering::SeqCst);
+                self.tool_calls.store(0, Ordering::SeqCst);
+                HookResult::Continue
+            }
+            HookEvent::BeforeIteration { .. } => self.check_tokens(context),
+            HookEvent::BeforeTool { .. } => match self.check_tokens(context) {
+                HookResult::Continue => self.check_tool_calls(),
+                result => result,
+            },
+            _ => HookResult::Continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::AgentMemory;
+    use crate::agent::providers::TodoList;
+
+    fn before_tool() -> HookEvent {
+        HookEvent::BeforeTool {
+            tool_name: "execute_command".to_string(),
+            arguments: "{}".to_string(),
+        }
+    }
+
+    fn before_agent() -> HookEvent {
+        HookEvent::BeforeAgent {
+            prompt: "task".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tool_call_limit_finishes_task() {
+        let hook = BudgetGuardHook::new(None, Some(2));
+        let todos = TodoList::new();
+        let memory = AgentMemory::new(1000);
+        let context = HookContext::new(&todos, &memory, 0, 0, 0);
+
+        hook.handle(&before_agent(), &context);
+        assert!(matches!(
+            hook.handle(&before_tool(), &context),
+            HookResult::Continue
+        ));
+        assert!(matches!(
+            hook.handle(&before_tool(), &context),
+            HookResult::Continue
+        ));
+        match hook.handle(&before_tool(), &context) {
+            HookResult::Finish(report) => assert!(report.contains("Budget exhausted")),
+            other => panic!("expected Finish, got {other:?}"),
+        }
+
+        // A new task starts with a fresh allowance
+        hook.handle(&before_agent(), &context);
+        assert!(matches!(
+            hook.handle(&before_tool(), &context),
+            HookResult::Continue
+        ));
+    }
+
+    #[test]
+    fn test_token_budget_counts_only_current_task() {
+        let hook = BudgetGuardHook::new(Some(1000), None);
+        let todos = TodoList::new();
+        let mut memory = AgentMemory::new(100_000);
+        memory.record_token_usage(5000);
+
+        let context = HookContext::new(&todos, &memory, 0, 0, 0);
+        hook.handle(&before_agent(), &context);
+        let iteration = HookEvent::BeforeIteration { iteration: 1 };
+        assert!(matches!(
+            hook.handle(&iteration, &context),
+            HookResult::Continue
+        ));
+
+        memory.record_token_usage(1200);
+        let context = HookContext::new(&todos, &memory, 1, 0, 0);
+        match hook.handle(&iteration, &context) {
+            HookResult::Finish(report) => assert!(report.contains("1200 tokens")),
+            other => panic!("expected Finish, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_disabled_without_limits() {
+        assert!(!BudgetGuardHook::new(None, None).is_enabled());
+        assert!(BudgetGuardHook::new(Some(1), None).is_enabled());
+    }
+}
@@ -5,6 +5,7 @@
 
 use super::registry::Hook;
 use super::types::{HookContext, HookEvent, HookResult};
+use crate::agent::messages::AgentLanguage;
 use tracing::info;
 
 /// Hook that checks if all todos are completed
@@ -75,9 +76,7 @@ impl Hook for CompletionCheckHook {
         let total = context.todos.items.len();
         let completed = context.todos.completed_count();
 
-        let reason = format!(
-            "Not all tasks are completed ({completed}/{total} done, {pending} remaining). Continue working on remaining tasks."
-        );
+        let reason = AgentLanguage::current().todos_incomplete(completed, total, pending);
 
         let todo_context = context.todos.to_context_string();
 
@@ -3,6 +3,7 @@
 //! Provides a hook system for intercepting and customizing agent behavior
 //! at various points in the agent lifecycle.
 
+pub mod budget_guard;
 pub mod completion;
 pub mod delegation_guard;
 pub mod registry;
@@ -12,6 +13,7 @@ pub mod timeout_report;
 pub mod types;
 pub mod workload;
 
+pub use budget_guard::BudgetGuardHook;
 pub use completion::CompletionCheckHook;
 pub use delegation_guard::DelegationGuardHook;
 pub use registry::{Hook, HookRegistry};
@@ -7,21 +7,85 @@
 //!    If the Main Agent tries to `git clone` or `grep -r`, it gets blocked and told to delegate.
 //! 2. **Context Injection:** Analyzes the user's prompt complexity and injects strict instructions
 //!    to delegate routine work, replacing the older `ComplexityAnalyzerHook`.
+//! 3. **Batching Policy:** Detects the model drip-feeding one tiny tool call per iteration
+//!    and forces it to plan and batch independent calls.
 
 use super::registry::Hook;
 use super::types::{HookContext, HookEvent, HookResult};
+use crate::config::{WORKLOAD_DRIP_FEED_ITERATIONS, WORKLOAD_DRIP_FEED_MAX_CALLS};
 use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Hook that distributes workload by blocking manual labor and encouraging delegation.
 pub struct WorkloadDistributorHook {
     min_word_count: usize,
+    drip_feed_iterations: usize,
+    drip_feed_max_calls: usize,
+    calls_in_iteration: AtomicUsize,
+    drip_feed_streak: AtomicUsize,
 }
 
 impl WorkloadDistributorHook {
     /// Create a new workload distributor hook.
     #[must_use]
     pub const fn new() -> Self {
-        Self { min_word_count: 60 } // Slightly lower threshold than the old analyzer
+        Self {
+            min_word_count: 60, // Slightly lower threshold than the old analyzer
+            drip_feed_iterations: WORKLOAD_DRIP_FEED_ITERATIONS,
+            drip_feed_max_calls: WORKLOAD_DRIP_FEED_MAX_CALLS,
+            calls_in_iteration: AtomicUsize::new(0),
+            drip_feed_streak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Configure the batching policy.
+    ///
+    /// After `iterations` consecutive iterations with at most `max_calls` tool
+    /// calls each, the hook forces an iteration with batching guidance.
+    /// `iterations == 0` disables the policy.
+    #[must_use]
+    pub const fn with_batching_policy(mut self, iterations: usize, max_calls: usize) -> Self {
+        self.drip_feed_iterations = iterations;
+        self.drip_feed_max_calls = max_calls;
+        self
+    }
+
+    /// Close the previous iteration and check whether the model is drip-feeding.
+    fn check_batching(&self) -> HookResult {
+        let calls = self.calls_in_iteration.swap(0, Ordering::SeqCst);
+        if self.drip_feed_iterations == 0 {
+            return HookResult::Continue;
+        }
+
+        // Iterations without tool calls (final answers, parse retries) break the pattern
+        if calls == 0 || calls > self.drip_feed_max_calls {
+            self.drip_feed_streak.store(0, Ordering::SeqCst);
+            return HookResult::Continue;
+        }
+
+        let streak = self.drip_feed_streak.fetch_add(1, Ordering::SeqCst) + 1;
+        if streak < self.drip_feed_iterations {
+            return HookResult::Continue;
+        }
+
+        self.drip_feed_streak.store(0, Ordering::SeqCst);
+        HookResult::ForceIteration {
+            reason: format!("Drip-feed tool usage detected ({streak} iterations)"),
+            context: Some(format!(
+                "[SYSTEM NOTICE: Batch Your Tool Calls]\n\
+                The last {streak} iterations each made only {} tool call(s). \
+                This wastes iterations and context.\n\
+                ACTION REQUIRED: Plan the remaining steps, then issue all independent tool calls \
+                (reads, searches, downloads, commands) together in a single response. \
+                Only sequence calls that depend on each other's results.",
+                self.drip_feed_max_calls
+            )),
+        }
+    }
+
+    fn reset_batching(&self) {
+        self.calls_in_iteration.store(0, Ordering::SeqCst);
+        self.drip_feed_streak.store(0, Ordering::SeqCst);
     }
 
     fn is_heavy_command(&self, command: &str) -> Option<&'static str> {
@@ -112,23 +176,30 @@ impl Hook for WorkloadDistributorHook {
     }
 
     fn handle(&self, event: &HookEvent, context: &HookContext) -> HookResult {
+        match event {
+            HookEvent::BeforeAgent { .. } => self.reset_batching(),
+            HookEvent::BeforeIteration { .. } => return self.check_batching(),
+            HookEvent::BeforeTool { .. } => {
+                self.calls_in_iteration.fetch_add(1, Ordering::SeqCst);
+            }
+            _ => {}
+        }
+
         match event {
             // 1. Context Injection for Complex Prompts
-            HookEvent::BeforeAgent { prompt } => {
-                if self.is_complex_prompt(prompt) {
-                    return HookResult::InjectContext(
-                        "[SYSTEM NOTICE: High Complexity Detected]\n\
-                        You must SPLIT your workflow to handle this request efficiently:\n\
-                        1. 🟢 DELEGATE retrieval tasks (git clone, grep, find, cat, deep_crawl, web_markdown) to `delegate_to_sub_agent`.\n\
-                           - Goal: Get raw data/files/web content.\n\
-                           - Forbidden for sub-agent: analysis, reasoning, explaining \"why\".\n\
-                        2. 🧠 RETAIN analysis tasks for yourself.\n\
-                           - Goal: Read the files/content returned by the sub-agent and perform high-level reasoning.\n\
-                        Example of GOOD delegation: \"Use deep_crawl to find news about X\".\n\
-                        Example of BAD delegation: \"Analyze why project X is failing\"."
-                            .to_string(),
-                    );
-                }
+            HookEvent::BeforeAgent { prompt } if self.is_complex_prompt(prompt) => {
+                return HookResult::InjectContext(
+                    "[SYSTEM NOTICE: High Complexity Detected]\n\
+                    You must SPLIT your workflow to handle this request efficiently:\n\
+                    1. 🟢 DELEGATE retrieval tasks (git clone, grep, find, cat, deep_crawl, web_markdown) to `delegate_to_sub_agent`.\n\
+                       - Goal: Get raw data/files/web content.\n\
+                       - Forbidden for sub-agent: analysis, reasoning, explaining \"why\".\n\
+                    2. 🧠 RETAIN analysis tasks for yourself.\n\
+                       - Goal: Read the files/content returned by the sub-agent and perform high-level reasoning.\n\
+                    Example of GOOD delegation: \"Use deep_crawl to find news about X\".\n\
+                    Example of BAD delegation: \"Analyze why project X is failing\"."
+                        .to_string(),
+                );
             }
 
             // 2. Hard Blocking of Heavy Commands and Direct Search
@@ -182,3 +253,93 @@ impl Hook for WorkloadDistributorHook {
         HookResult::Continue
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::AgentMemory;
+    use crate::agent::providers::TodoList;
+
+    fn tool_call() -> HookEvent {
+        HookEvent::BeforeTool {
+            tool_name: "read_file".to_string(),
+            arguments: r#"{"path": "a.txt"}"#.to_string(),
+        }
+    }
+
+    fn run_iteration(
+        hook: &WorkloadDistributorHook,
+        context: &HookContext,
+        iteration: usize,
+        calls: usize,
+    ) -> HookResult {
+        let result = hook.handle(&HookEvent::BeforeIteration { iteration }, context);
+        for _ in 0..calls {
+            hook.handle(&tool_call(), context);
+        }
+        result
+    }
+
+    #[test]
+    fn test_drip_feed_forces_batching_guidance() {
+        let hook = WorkloadDistributorHook::new().with_batching_policy(3, 1);
+        let todos = TodoList::new();
+        let memory = AgentMemory::new(1000);
+        let context = HookContext::new(&todos, &memory, 0, 0, 0);
+
+        // Iteration 0 has no previous iteration; 1..=3 close drip-feed iterations
+        for iteration in 0..3 {
+            let result = run_iteration(&hook, &context, iteration, 1);
+            assert!(matches!(result, HookResult::Continue));
+        }
+
+        match run_iteration(&hook, &context, 3, 1) {
+            HookResult::ForceIteration {
+                reason,
+                context: Some(guidance),
+            } => {
+                assert!(reason.contains("Drip-feed"));
+                assert!(guidance.contains("Batch Your Tool Calls"));
+            }
+            other => panic!("expected ForceIteration with guidance, got {other:?}"),
+        }
+
+        // The streak restarts after the nudge
+        let result = run_iteration(&hook, &context, 4, 1);
+        assert!(matches!(result, HookResult::Continue));
+    }
+
+    #[test]
+    fn test_batched_iterations_reset_streak() {
+        let hook = WorkloadDistributorHook::new().with_batching_policy(3, 1);
+        let todos = TodoList::new();
+        let memory = AgentMemory::new(1000);
+        let context = HookContext::new(&todos, &memory, 0, 0, 0);
+
+        run_iteration(&hook, &context, 0, 1);
+        run_iteration(&hook, &context, 1, 1);
+        run_iteration(&hook, &context, 2, 4);
+        for iteration in 3..6 {
+            let result = run_iteration(&hook, &context, iteration, 1);
+            assert!(matches!(result, HookResult::Continue));
+        }
+    }
+
+    #[test]
+    fn test_batching_policy_disabled() {
+        let todos = TodoList::new();
+        let memory = AgentMemory::new(1000);
+        let context = HookContext::new(&todos, &memory, 0, 0, 0);
+
+        // Off when configured with zero and by default
+        for hook in [
+            WorkloadDistributorHook::new().with_batching_policy(0, 1),
+            WorkloadDistributorHook::new(),
+        ] {
+            for iteration in 0..10 {
+                let result = run_iteration(&hook, &context, iteration, 1);
+                assert!(matches!(result, HookResult::Continue));
+            }
+        }
+    }
+}
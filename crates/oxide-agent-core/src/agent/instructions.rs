@@ -0,0 +1,62 @@
+//! Instructions the user sends while a task is running
+//!
+//! Transports queue a message with [`submit_instruction`] instead of starting
+//! a new task; the runner picks the queue up at the next iteration boundary
+//! and adds the instructions to the conversation. Queues are per session in
+//! a process-wide table.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+/// Instructions waiting for the next iteration, keyed by session
+static PENDING: LazyLock<Mutex<HashMap<i64, Vec<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn pending() -> std::sync::MutexGuard<'static, HashMap<i64, Vec<String>>> {
+    PENDING.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Queue an instruction for the session's running task
+///
+/// Blank instructions are ignored; returns whether it was queued.
+pub fn submit_instruction(session_id: i64, instruction: impl Into<String>) -> bool {
+    let instruction = instruction.into();
+    if instruction.trim().is_empty() {
+        return false;
+    }
+    pending().entry(session_id).or_default().push(instruction);
+    true
+}
+
+/// Take all queued instructions of a session, oldest first
+#[must_use]
+pub fn take_instructions(session_id: i64) -> Vec<String> {
+    pending().remove(&session_id).unwrap_or_default()
+}
+
+/// Drop queued instructions, e.g. left over from a finished task
+pub fn clear_instructions(session_id: i64) {
+    pending().remove(&session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instructions_are_taken_once_in_order() {
+        let session_id = -8_000_001;
+        assert!(submit_instruction(session_id, "keep it under 100 lines"));
+        assert!(!submit_instruction(session_id, "   "));
+        assert!(submit_instruction(session_id, "use Python"));
+
+        assert_eq!(
+            take_instructions(session_id),
+            vec![
+                "keep it under 100 lines".to_string(),
+                "use Python".to_string()
+            ]
+        );
+        assert!(take_instructions(session_id).is_empty());
+    }
+}
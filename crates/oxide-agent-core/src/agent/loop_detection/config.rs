@@ -2,6 +2,7 @@
 
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use tracing::warn;
 
 /// Loop detection configuration loaded from env/files.
@@ -14,6 +15,12 @@ pub struct LoopDetectionConfig {
     /// Tool call repetition threshold
     #[serde(rename = "loop_tool_call_threshold")]
     pub tool_call_threshold: usize,
+    /// Comma-separated tool names exempt from tool call loop detection
+    #[serde(rename = "loop_tool_allowlist")]
+    pub tool_allowlist: String,
+    /// Consecutive identical fatal tool errors before stopping
+    #[serde(rename = "loop_fatal_error_threshold")]
+    pub fatal_error_threshold: usize,
 
     /// Content chunk size (characters)
     #[serde(rename = "loop_content_chunk_size")]
@@ -24,6 +31,12 @@ pub struct LoopDetectionConfig {
     /// Max content history length (characters)
     #[serde(rename = "loop_max_history_length")]
     pub max_history_length: usize,
+    /// Share (0.0-1.0) of recent chunks that must repeat earlier content
+    #[serde(rename = "loop_content_min_similarity")]
+    pub content_min_similarity: f64,
+    /// Number of recent chunks the similarity is measured over
+    #[serde(rename = "loop_content_similarity_window")]
+    pub content_similarity_window: usize,
 
     /// Min turns before LLM checks start
     #[serde(rename = "loop_llm_check_after_turns")]
@@ -47,9 +60,13 @@ impl Default for LoopDetectionConfig {
         Self {
             enabled: true,
             tool_call_threshold: 5,
+            tool_allowlist: String::new(),
+            fatal_error_threshold: 3,
             content_chunk_size: 50,
             content_loop_threshold: 10,
             max_history_length: 5000,
+            content_min_similarity: 0.8,
+            content_similarity_window: 100,
             llm_check_after_turns: 30,
             llm_check_interval: 3,
             llm_confidence_threshold: 0.95,
@@ -80,6 +97,13 @@ impl LoopDetectionConfig {
                     defaults.tool_call_threshold as u64,
                 )
             })
+            .and_then(|b| b.set_default("loop_tool_allowlist", defaults.tool_allowlist.clone()))
+            .and_then(|b| {
+                b.set_default(
+                    "loop_fatal_error_threshold",
+                    defaults.fatal_error_threshold as u64,
+                )
+            })
             .and_then(|b| {
                 b.set_default(
                     "loop_content_chunk_size",
@@ -98,6 +122,18 @@ impl LoopDetectionConfig {
                     defaults.max_history_length as u64,
                 )
             })
+            .and_then(|b| {
+                b.set_default(
+                    "loop_content_min_similarity",
+                    defaults.content_min_similarity,
+                )
+            })
+            .and_then(|b| {
+                b.set_default(
+                    "loop_content_similarity_window",
+                    defaults.content_similarity_window as u64,
+                )
+            })
             .and_then(|b| {
                 b.set_default(
                     "loop_llm_check_after_turns",
@@ -142,4 +178,44 @@ impl LoopDetectionConfig {
         warn!(error = %err, "Failed to load loop detection config, using defaults");
         Self::default()
     }
+
+    /// Tool names exempt from tool call loop detection.
+    #[must_use]
+    pub fn exempt_tools(&self) -> HashSet<String> {
+        self.tool_allowlist
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Config with the content loop settings of `overrides` applied.
+    #[must_use]
+    pub fn with_content_override(&self, overrides: &ContentLoopOverride) -> Self {
+        Self {
+            content_loop_threshold: overrides.threshold.unwrap_or(self.content_loop_threshold),
+            content_min_similarity: overrides
+                .min_similarity
+                .unwrap_or(self.content_min_similarity),
+            content_similarity_window: overrides
+                .similarity_window
+                .unwrap_or(self.content_similarity_window),
+            ..self.clone()
+        }
+    }
+}
+
+/// Per-task override of the content loop settings; unset fields keep the config value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentLoopOverride {
+    /// Content loop repetition threshold
+    #[serde(default)]
+    pub threshold: Option<usize>,
+    /// Share (0.0-1.0) of recent chunks that must repeat earlier content
+    #[serde(default)]
+    pub min_similarity: Option<f64>,
+    /// Number of recent chunks the similarity is measured over
+    #[serde(default)]
+    pub similarity_window: Option<usize>,
 }
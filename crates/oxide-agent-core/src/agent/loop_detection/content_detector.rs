@@ -2,7 +2,7 @@
 
 use lazy_regex::lazy_regex;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use tracing::debug;
 
 const DEFAULT_MAX_DISTANCE_MULTIPLIER: usize = 5;
@@ -12,6 +12,11 @@ static RE_BLOCKQUOTE: lazy_regex::Lazy<regex::Regex> = lazy_regex!(r"(?m)(^|\n)\
 static RE_DIVIDER: lazy_regex::Lazy<regex::Regex> = lazy_regex!(r"(?m)(^|\n)\s*[-=]{3,}\s*$");
 
 /// Detects repeated content chunks within a sliding window.
+///
+/// A chunk seen `loop_threshold` times close together is only reported when
+/// at least `min_similarity` of the last `similarity_window` chunks repeat
+/// earlier content. Text that reuses phrases while progressing (numbered
+/// steps, calculations) keeps the share low; a real loop drives it to 1.0.
 pub struct ContentLoopDetector {
     history: Vec<char>,
     chunk_stats: HashMap<String, Vec<usize>>,
@@ -21,6 +26,9 @@ pub struct ContentLoopDetector {
     loop_threshold: usize,
     max_history_length: usize,
     max_distance_multiplier: usize,
+    min_similarity: f64,
+    similarity_window: usize,
+    recent_repeats: VecDeque<bool>,
 }
 
 impl ContentLoopDetector {
@@ -36,9 +44,20 @@ impl ContentLoopDetector {
             loop_threshold: loop_threshold.max(2),
             max_history_length: max_history_length.max(1),
             max_distance_multiplier: DEFAULT_MAX_DISTANCE_MULTIPLIER,
+            min_similarity: 0.0,
+            similarity_window: 1,
+            recent_repeats: VecDeque::new(),
         }
     }
 
+    /// Require `min_similarity` of the last `window` chunks to be repeats.
+    #[must_use]
+    pub fn with_similarity(mut self, min_similarity: f64, window: usize) -> Self {
+        self.min_similarity = min_similarity.clamp(0.0, 1.0);
+        self.similarity_window = window.max(1);
+        self
+    }
+
     /// Check a new content fragment for looping behavior.
     pub fn check(&mut self, content: &str) -> bool {
         let has_code_fence = content.contains("```");
@@ -85,19 +104,12 @@ impl ContentLoopDetector {
         false
     }
 
-    /// Reset the detector state (including history).
-    pub fn reset(&mut self) {
-        self.history.clear();
-        self.chunk_stats.clear();
-        self.last_index = 0;
-        self.in_code_block = false;
-    }
-
     /// Reset tracking state but preserve code block context.
     pub fn reset_tracking(&mut self) {
         self.history.clear();
         self.chunk_stats.clear();
         self.last_index = 0;
+        self.recent_repeats.clear();
     }
 
     #[cfg(test)]
@@ -162,9 +174,11 @@ impl ContentLoopDetector {
 
         if let Some(first_pos) = first_pos {
             if self.chunk_at(first_pos) != chunk {
+                self.record_repeat(false);
                 return false;
             }
         }
+        self.record_repeat(first_pos.is_some());
 
         let positions = self.chunk_stats.entry(hash.to_string()).or_default();
         positions.push(position);
@@ -181,7 +195,8 @@ impl ContentLoopDetector {
         let avg_distance = total_distance / (self.loop_threshold - 1);
         let max_distance = self.chunk_size * self.max_distance_multiplier;
 
-        let is_loop = avg_distance <= max_distance;
+        let similarity = self.recent_similarity();
+        let is_loop = avg_distance <= max_distance && similarity >= self.min_similarity;
 
         if occurrences >= self.loop_threshold {
             debug!(
@@ -190,6 +205,7 @@ impl ContentLoopDetector {
                 threshold = self.loop_threshold,
                 avg_distance,
                 max_distance,
+                similarity,
                 is_loop,
                 "content_detector: chunk threshold check"
             );
@@ -197,6 +213,22 @@ impl ContentLoopDetector {
 
         is_loop
     }
+
+    fn record_repeat(&mut self, repeated: bool) {
+        self.recent_repeats.push_back(repeated);
+        while self.recent_repeats.len() > self.similarity_window {
+            self.recent_repeats.pop_front();
+        }
+    }
+
+    /// Share of the recent chunks that repeat earlier content.
+    fn recent_similarity(&self) -> f64 {
+        if self.recent_repeats.is_empty() {
+            return 0.0;
+        }
+        let repeats = self.recent_repeats.iter().filter(|r| **r).count();
+        repeats as f64 / self.recent_repeats.len() as f64
+    }
 }
 
 #[cfg(test)]
@@ -243,6 +275,35 @@ mod tests {
         assert_eq!(detector.history_len(), 0);
     }
 
+    #[test]
+    fn detects_repetition_with_similarity_gate() {
+        let mut detector = ContentLoopDetector::new(10, 4, 500).with_similarity(0.8, 100);
+        let detected = (0..20).any(|_| detector.check("I will check the file again. "));
+        assert!(detected);
+    }
+
+    #[test]
+    fn progressing_steps_are_not_a_loop() {
+        let steps: Vec<String> = (1..=12)
+            .map(|n| {
+                format!(
+                    "Step {n}: multiply both sides by {}, so x = {}. ",
+                    n + 1,
+                    n * (n + 1)
+                )
+            })
+            .collect();
+
+        // Without the similarity gate the shared phrasing alone trips the detector
+        let mut ungated = ContentLoopDetector::new(10, 4, 5000);
+        assert!(steps.iter().any(|step| ungated.check(step)));
+
+        let mut detector = ContentLoopDetector::new(10, 4, 5000).with_similarity(0.8, 100);
+        for step in &steps {
+            assert!(!detector.check(step), "flagged: {step}");
+        }
+    }
+
     #[test]
     fn reset_tracking_clears_history() {
         let mut detector = ContentLoopDetector::new(10, 4, 200);
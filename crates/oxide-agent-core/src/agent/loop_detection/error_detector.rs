@@ -0,0 +1,85 @@
+//! Repeated fatal tool error detector.
+
+use crate::agent::tool_error::ToolError;
+use tracing::debug;
+
+/// Detects the same non-recoverable tool error repeating back to back.
+pub struct FatalErrorDetector {
+    last_key: Option<String>,
+    repetition_count: usize,
+    threshold: usize,
+}
+
+impl FatalErrorDetector {
+    /// Create a new fatal error detector with a threshold.
+    #[must_use]
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            last_key: None,
+            repetition_count: 0,
+            threshold: threshold.max(1),
+        }
+    }
+
+    /// Record a tool outcome and check whether the same fatal error keeps repeating.
+    ///
+    /// Successful calls and recoverable errors break the streak.
+    pub fn check(&mut self, tool_name: &str, error: Option<&ToolError>) -> bool {
+        let Some(error) = error.filter(|error| !error.is_recoverable()) else {
+            self.reset();
+            return false;
+        };
+
+        let key = format!("{tool_name}:{}:{}", error.kind, error.message);
+        if self.last_key.as_deref() == Some(key.as_str()) {
+            self.repetition_count = self.repetition_count.saturating_add(1);
+        } else {
+            self.last_key = Some(key);
+            self.repetition_count = 1;
+        }
+
+        debug!(
+            tool_name,
+            error_kind = %error.kind,
+            repetition_count = self.repetition_count,
+            threshold = self.threshold,
+            "error_detector: fatal tool error recorded"
+        );
+
+        self.repetition_count >= self.threshold
+    }
+
+    /// Reset the detector state.
+    pub fn reset(&mut self) {
+        self.last_key = None;
+        self.repetition_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FatalErrorDetector;
+    use crate::agent::tool_error::{ToolError, ToolErrorKind};
+
+    #[test]
+    fn detects_repeated_fatal_errors() {
+        let mut detector = FatalErrorDetector::new(3);
+        let error = ToolError::new(ToolErrorKind::PermissionDenied, "denied");
+        assert!(!detector.check("read_file", Some(&error)));
+        assert!(!detector.check("read_file", Some(&error)));
+        assert!(detector.check("read_file", Some(&error)));
+    }
+
+    #[test]
+    fn ignores_recoverable_errors_and_successes() {
+        let mut detector = FatalErrorDetector::new(2);
+        let fatal = ToolError::new(ToolErrorKind::Unavailable, "Unknown tool: foo");
+        let recoverable = ToolError::new(ToolErrorKind::NotFound, "missing.txt");
+
+        assert!(!detector.check("foo", Some(&fatal)));
+        assert!(!detector.check("read_file", None));
+        assert!(!detector.check("foo", Some(&fatal)));
+        assert!(!detector.check("read_file", Some(&recoverable)));
+        assert!(!detector.check("read_file", Some(&recoverable)));
+    }
+}
@@ -4,11 +4,12 @@
 
 mod config;
 mod content_detector;
+mod error_detector;
 mod llm_detector;
 mod service;
 mod tool_detector;
 mod types;
 
-pub use config::LoopDetectionConfig;
+pub use config::{ContentLoopOverride, LoopDetectionConfig};
 pub use service::LoopDetectionService;
 pub use types::{LoopDetectedEvent, LoopDetectionError, LoopType};
@@ -1,11 +1,13 @@
 //! Loop detection service coordinating multiple detectors.
 
-use super::config::LoopDetectionConfig;
+use super::config::{ContentLoopOverride, LoopDetectionConfig};
 use super::content_detector::ContentLoopDetector;
+use super::error_detector::FatalErrorDetector;
 use super::llm_detector::{LlmLoopDetector, LoopScoutClient};
 use super::tool_detector::ToolCallDetector;
 use super::types::{LoopDetectedEvent, LoopDetectionError, LoopType};
 use crate::agent::memory::AgentMemory;
+use crate::agent::tool_error::ToolError;
 use chrono::Utc;
 use std::sync::Arc;
 use tracing::{debug, warn};
@@ -17,6 +19,7 @@ pub struct LoopDetectionService {
     loop_detected: bool,
     disabled_for_session: bool,
     tool_detector: ToolCallDetector,
+    error_detector: FatalErrorDetector,
     content_detector: ContentLoopDetector,
     llm_detector: LlmLoopDetector,
 }
@@ -26,12 +29,10 @@ impl LoopDetectionService {
     #[must_use]
     pub fn new(client: Arc<dyn LoopScoutClient>, config: Arc<LoopDetectionConfig>) -> Self {
         Self {
-            tool_detector: ToolCallDetector::new(config.tool_call_threshold),
-            content_detector: ContentLoopDetector::new(
-                config.content_chunk_size,
-                config.content_loop_threshold,
-                config.max_history_length,
-            ),
+            tool_detector: ToolCallDetector::new(config.tool_call_threshold)
+                .with_exempt_tools(config.exempt_tools()),
+            error_detector: FatalErrorDetector::new(config.fatal_error_threshold),
+            content_detector: content_detector(&config),
             llm_detector: LlmLoopDetector::new(client, &config),
             config,
             session_id: String::new(),
@@ -44,7 +45,8 @@ impl LoopDetectionService {
     pub fn reset(&mut self, session_id: String) {
         self.session_id = session_id;
         self.tool_detector.reset();
-        self.content_detector.reset();
+        self.error_detector.reset();
+        self.content_detector = content_detector(&self.config);
         self.llm_detector.reset(&self.config);
         self.loop_detected = false;
         self.disabled_for_session = false;
@@ -55,6 +57,12 @@ impl LoopDetectionService {
         self.disabled_for_session = true;
     }
 
+    /// Use different content loop settings until the next reset.
+    pub fn override_content_detection(&mut self, overrides: &ContentLoopOverride) {
+        debug!(session_id = %self.session_id, ?overrides, "loop_service: content override");
+        self.content_detector = content_detector(&self.config.with_content_override(overrides));
+    }
+
     /// Reset content tracking state without affecting tool/LLM detectors.
     pub fn reset_content_tracking(&mut self) {
         self.content_detector.reset_tracking();
@@ -91,6 +99,34 @@ impl LoopDetectionService {
         Ok(detected)
     }
 
+    /// Check a tool result for the same fatal error repeating.
+    pub fn check_tool_result(
+        &mut self,
+        tool_name: &str,
+        error: Option<&ToolError>,
+    ) -> Result<bool, LoopDetectionError> {
+        if !self.is_enabled() {
+            return Ok(false);
+        }
+        if self.loop_detected {
+            return Ok(true);
+        }
+
+        let detected = self.error_detector.check(tool_name, error);
+
+        if detected {
+            warn!(
+                session_id = %self.session_id,
+                tool_name,
+                loop_type = "FatalErrorLoop",
+                "loop_service: LOOP DETECTED via error_detector"
+            );
+        }
+
+        self.loop_detected = detected;
+        Ok(detected)
+    }
+
     /// Check content for repetition loops.
     pub fn check_content(&mut self, content: &str) -> Result<bool, LoopDetectionError> {
         if !self.is_enabled() {
@@ -183,6 +219,18 @@ impl LoopDetectionService {
     }
 }
 
+fn content_detector(config: &LoopDetectionConfig) -> ContentLoopDetector {
+    ContentLoopDetector::new(
+        config.content_chunk_size,
+        config.content_loop_threshold,
+        config.max_history_length,
+    )
+    .with_similarity(
+        config.content_min_similarity,
+        config.content_similarity_window,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::LoopDetectionService;
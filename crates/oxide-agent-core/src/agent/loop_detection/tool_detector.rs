@@ -1,13 +1,20 @@
 //! Tool call loop detector.
 
+use serde_json::Value;
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use tracing::debug;
 
 /// Detects consecutive identical tool calls using hashing.
+///
+/// Arguments are compared as compact JSON with sorted object keys, so
+/// reordered or reformatted calls still count as repeats.
+/// Exempt tools (e.g. polling a job status) are never reported.
 pub struct ToolCallDetector {
     last_key: Option<String>,
     repetition_count: usize,
     threshold: usize,
+    exempt_tools: HashSet<String>,
 }
 
 impl ToolCallDetector {
@@ -18,11 +25,25 @@ impl ToolCallDetector {
             last_key: None,
             repetition_count: 0,
             threshold: threshold.max(1),
+            exempt_tools: HashSet::new(),
         }
     }
 
+    /// Never report loops for these tools.
+    #[must_use]
+    pub fn with_exempt_tools(mut self, exempt_tools: HashSet<String>) -> Self {
+        self.exempt_tools = exempt_tools;
+        self
+    }
+
     /// Check if the given tool call forms a loop.
     pub fn check(&mut self, tool_name: &str, args: &str) -> bool {
+        if self.exempt_tools.contains(tool_name) {
+            debug!(tool_name, "tool_detector: skipping (exempt tool)");
+            self.reset();
+            return false;
+        }
+
         let key = Self::hash_tool_call(tool_name, args);
         let prev_key = self
             .last_key
@@ -83,15 +104,39 @@ impl ToolCallDetector {
     }
 
     fn normalize_args(args: &str) -> String {
-        serde_json::from_str::<serde_json::Value>(args)
-            .map(|value| value.to_string())
-            .unwrap_or_else(|_| args.to_string())
+        serde_json::from_str::<Value>(args)
+            .map(|value| canonical_json(&value))
+            .unwrap_or_else(|_| args.trim().to_string())
+    }
+}
+
+/// Compact JSON with sorted object keys; string contents are kept as is,
+/// since whitespace in commands or code can change what a call does
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::from(key.as_str()), canonical_json(value))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::ToolCallDetector;
+    use std::collections::HashSet;
 
     #[test]
     fn detects_at_threshold() {
@@ -118,4 +163,50 @@ mod tests {
         assert!(!detector.check("tool_a", r#"{"a":2}"#));
         assert_eq!(detector.repetition_count(), 1);
     }
+
+    #[test]
+    fn reordered_and_reformatted_args_are_repeats() {
+        let mut detector = ToolCallDetector::new(3);
+        assert!(!detector.check("execute_command", r#"{"command": "ls -la", "timeout": 30}"#));
+        assert!(!detector.check("execute_command", r#"{"timeout":30,"command":"ls -la"}"#));
+        assert!(detector.check(
+            "execute_command",
+            "{\n  \"timeout\": 30,\n  \"command\": \"ls -la\"\n}"
+        ));
+    }
+
+    #[test]
+    fn whitespace_inside_strings_is_significant() {
+        let mut detector = ToolCallDetector::new(2);
+        assert!(!detector.check(
+            "write_file",
+            r#"{"path": "a.py", "content": "if x:\n    y()"}"#
+        ));
+        assert!(!detector.check(
+            "write_file",
+            r#"{"path": "a.py", "content": "if x:\n        y()"}"#
+        ));
+        assert_eq!(detector.repetition_count(), 1);
+    }
+
+    #[test]
+    fn paginated_searches_are_not_repeats() {
+        let mut detector = ToolCallDetector::new(2);
+        for page in 1..=5 {
+            let args = format!(r#"{{"query": "rust async", "page": {page}}}"#);
+            assert!(!detector.check("web_search", &args));
+        }
+        assert!(detector.check("web_search", r#"{"page": 5, "query": "rust async"}"#));
+    }
+
+    #[test]
+    fn exempt_tools_never_trigger() {
+        let exempt = HashSet::from(["check_job".to_string()]);
+        let mut detector = ToolCallDetector::new(2).with_exempt_tools(exempt);
+        for _ in 0..5 {
+            assert!(!detector.check("check_job", r#"{"id":1}"#));
+        }
+        assert!(!detector.check("tool_a", "{}"));
+        assert!(detector.check("tool_a", "{}"));
+    }
 }
@@ -14,6 +14,8 @@ pub enum LoopType {
     ContentLoop,
     /// LLM-detected cognitive loop.
     CognitiveLoop,
+    /// The same fatal tool error repeated.
+    FatalErrorLoop,
 }
 
 /// Loop detection event metadata.
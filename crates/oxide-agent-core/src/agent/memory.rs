@@ -1,13 +1,20 @@
 //! Agent memory management with auto-compaction
 //!
 //! Provides conversation memory for the agent with automatic compaction
-//! when token count approaches the limit. Uses tiktoken for token counting.
+//! when token count approaches the limit. Tokens are counted with the
+//! tokenizer of the agent model (see [`crate::agent::tokenizer`]).
+//!
+//! Pinned messages (the task itself, the user's answers to `ask_user`,
+//! `set_env` results) survive compaction verbatim, so long tasks keep their
+//! goal and constraints after the older context is summarized. Pins may
+//! take at most `AGENT_PINNED_PERCENT` of the budget; the oldest pins beyond
+//! that are summarized like any other message.
 
 use crate::agent::providers::TodoList;
-use crate::config::AGENT_COMPACT_THRESHOLD;
+use crate::agent::tokenizer::{default_tokenizer, Tokenizer};
+use crate::config::{AGENT_COMPACT_PERCENT, AGENT_PINNED_PERCENT};
 use crate::llm::ToolCall;
 use serde::{Deserialize, Serialize};
-use tiktoken_rs::cl100k_base;
 use tracing::info;
 
 /// A message in the agent's conversation memory
@@ -26,6 +33,9 @@ pub struct AgentMessage {
     pub tool_name: Option<String>,
     /// Tool calls made by assistant
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Pinned messages are never summarized away by compaction
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 /// Role of a message sender in agent memory
@@ -51,6 +61,7 @@ impl AgentMessage {
             tool_call_id: None,
             tool_name: None,
             tool_calls: None,
+            pinned: false,
         }
     }
 
@@ -63,6 +74,7 @@ impl AgentMessage {
             tool_call_id: None,
             tool_name: None,
             tool_calls: None,
+            pinned: false,
         }
     }
 
@@ -75,6 +87,7 @@ impl AgentMessage {
             tool_call_id: None,
             tool_name: None,
             tool_calls: None,
+            pinned: false,
         }
     }
 
@@ -90,6 +103,7 @@ impl AgentMessage {
             tool_call_id: None,
             tool_name: None,
             tool_calls: None,
+            pinned: false,
         }
     }
 
@@ -102,6 +116,7 @@ impl AgentMessage {
             tool_call_id: Some(tool_call_id.to_string()),
             tool_name: Some(name.to_string()),
             tool_calls: None,
+            pinned: false,
         }
     }
 
@@ -114,8 +129,16 @@ impl AgentMessage {
             tool_call_id: None,
             tool_name: None,
             tool_calls: Some(tool_calls),
+            pinned: false,
         }
     }
+
+    /// Mark the message as pinned so compaction keeps it
+    #[must_use]
+    pub const fn pin(mut self) -> Self {
+        self.pinned = true;
+        self
+    }
 }
 
 /// Agent memory with auto-compaction support
@@ -130,6 +153,12 @@ pub struct AgentMemory {
     /// Last synchronized token count from API
     #[serde(default)]
     last_api_token_count: Option<usize>,
+    /// Cumulative tokens reported by the API across all calls
+    #[serde(default)]
+    spent_tokens: usize,
+    /// Tokenizer of the model the memory is sent to
+    #[serde(skip, default = "default_tokenizer")]
+    tokenizer: &'static dyn Tokenizer,
 }
 
 impl AgentMemory {
@@ -141,21 +170,40 @@ impl AgentMemory {
             todos: TodoList::new(),
             token_count: 0,
             max_tokens,
-            compact_threshold: AGENT_COMPACT_THRESHOLD,
+            compact_threshold: compact_threshold(max_tokens),
             last_api_token_count: None,
+            spent_tokens: 0,
+            tokenizer: default_tokenizer(),
         }
     }
 
-    /// Add a message to memory, triggering compaction if needed
-    pub fn add_message(&mut self, msg: AgentMessage) {
-        let mut msg_tokens = Self::count_tokens(&msg.content);
+    /// Size the memory to a model's input budget: its context window minus
+    /// the tokens reserved for the reply. Compacts right away if the stored
+    /// messages no longer fit.
+    pub fn set_context_window(&mut self, context_window: u32, max_output_tokens: u32) {
+        let budget = input_budget(context_window, max_output_tokens);
+        if budget == self.max_tokens {
+            return;
+        }
+        self.max_tokens = budget;
+        self.compact_threshold = compact_threshold(budget);
+        if self.token_count > self.compact_threshold {
+            self.compact();
+        }
+    }
 
-        // Also count reasoning tokens (GLM-4.7 thinking process)
-        if let Some(ref reasoning) = msg.reasoning {
-            msg_tokens += Self::count_tokens(reasoning);
+    /// Count tokens with `tokenizer` from now on and recount the stored messages
+    pub fn set_tokenizer(&mut self, tokenizer: &'static dyn Tokenizer) {
+        if tokenizer.name() == self.tokenizer.name() {
+            return;
         }
+        self.tokenizer = tokenizer;
+        self.token_count = self.count_stored_tokens();
+    }
 
-        self.token_count += msg_tokens;
+    /// Add a message to memory, triggering compaction if needed
+    pub fn add_message(&mut self, msg: AgentMessage) {
+        self.token_count += self.message_tokens(&msg);
         self.messages.push(msg);
 
         // Check if we need to compact
@@ -164,6 +212,44 @@ impl AgentMemory {
         }
     }
 
+    /// Add a message that compaction must keep
+    pub fn add_pinned_message(&mut self, msg: AgentMessage) {
+        self.add_message(msg.pin());
+    }
+
+    /// Pin the message at `index`. Returns `false` if there is no such message.
+    pub fn pin_message(&mut self, index: usize) -> bool {
+        let Some(msg) = self.messages.get_mut(index) else {
+            return false;
+        };
+        msg.pinned = true;
+        true
+    }
+
+    /// Unpin the message at `index`. Returns `false` if there is no such message.
+    pub fn unpin_message(&mut self, index: usize) -> bool {
+        let Some(msg) = self.messages.get_mut(index) else {
+            return false;
+        };
+        msg.pinned = false;
+        true
+    }
+
+    /// Unpin every pinned user message, e.g. the prompt of a finished task
+    pub fn unpin_user_messages(&mut self) {
+        let indices: Vec<usize> = (0..self.messages.len())
+            .filter(|&i| self.messages[i].pinned && self.messages[i].role == MessageRole::User)
+            .collect();
+        for index in indices {
+            self.unpin_message(index);
+        }
+    }
+
+    /// Messages that survive compaction
+    pub fn pinned_messages(&self) -> impl Iterator<Item = &AgentMessage> {
+        self.messages.iter().filter(|m| m.pinned)
+    }
+
     /// Get all messages in memory
     #[must_use]
     pub fn get_messages(&self) -> &[AgentMessage] {
@@ -213,19 +299,38 @@ impl AgentMemory {
         self.last_api_token_count = Some(real_total_tokens);
     }
 
+    /// Get the cumulative number of tokens reported by the API
+    #[must_use]
+    pub const fn spent_tokens(&self) -> usize {
+        self.spent_tokens
+    }
+
+    /// Add the usage of one API call to the cumulative token spend
+    pub const fn record_token_usage(&mut self, total_tokens: usize) {
+        self.spent_tokens = self.spent_tokens.saturating_add(total_tokens);
+    }
+
     /// Clear all messages from memory
     pub fn clear(&mut self) {
         self.messages.clear();
         self.todos.clear();
         self.token_count = 0;
         self.last_api_token_count = None;
+        self.spent_tokens = 0;
     }
 
-    /// Count tokens in a string using cl100k tokenizer (GPT-4/Claude compatible)
-    fn count_tokens(text: &str) -> usize {
-        cl100k_base().map_or(text.len() / 4, |bpe| {
-            bpe.encode_with_special_tokens(text).len()
-        })
+    /// Tokens of a message, including reasoning (GLM-4.7 thinking process)
+    fn message_tokens(&self, msg: &AgentMessage) -> usize {
+        let content = self.tokenizer.count_tokens(&msg.content);
+        let reasoning = msg
+            .reasoning
+            .as_deref()
+            .map_or(0, |r| self.tokenizer.count_tokens(r));
+        content + reasoning
+    }
+
+    fn count_stored_tokens(&self) -> usize {
+        self.messages.iter().map(|m| self.message_tokens(m)).sum()
     }
 
     /// Compact memory by summarizing older messages
@@ -233,6 +338,8 @@ impl AgentMemory {
     /// Strategy:
     /// 1. Keep the last 20% of messages intact (recent context)
     /// 2. Summarize the first 80% into a single system message
+    /// 3. Keep pinned messages from the first 80% right after the summary,
+    ///    summarizing the oldest ones once pins exceed `AGENT_PINNED_PERCENT`
     ///
     /// Note: In this iteration, we use a simple truncation strategy.
     /// Full LLM-based summarization will be added when MCP tools are integrated.
@@ -255,29 +362,21 @@ impl AgentMemory {
             return;
         }
 
-        // Extract messages to summarize
-        let to_summarize: Vec<_> = self.messages.drain(..split_at).collect();
+        // Extract messages to summarize, setting pinned ones aside
+        let mut old: Vec<AgentMessage> = self.messages.drain(..split_at).collect();
+        self.unpin_over_budget(&mut old);
+        let (pinned, to_summarize): (Vec<_>, Vec<_>) = old.into_iter().partition(|m| m.pinned);
 
         // Create a summary of the old messages (simple version)
         let summary = Self::create_simple_summary(&to_summarize);
         let summary_msg = AgentMessage::system(format!("[Previous context compressed]\n{summary}"));
 
-        // Insert summary at the beginning
-        self.messages.insert(0, summary_msg);
+        // Insert summary at the beginning, followed by the pinned messages
+        let head = std::iter::once(summary_msg).chain(pinned.into_iter().map(Self::detach_tool));
+        self.messages.splice(0..0, head);
 
         // Recalculate token count
-        self.token_count = self
-            .messages
-            .iter()
-            .map(|m| {
-                let mut tokens = Self::count_tokens(&m.content);
-                // Also count reasoning tokens (GLM-4.7 thinking process)
-                if let Some(ref reasoning) = m.reasoning {
-                    tokens += Self::count_tokens(reasoning);
-                }
-                tokens
-            })
-            .sum();
+        self.token_count = self.count_stored_tokens();
 
         info!(
             "Memory compacted: {} tokens, {} messages remaining",
@@ -289,6 +388,43 @@ impl AgentMemory {
         self.last_api_token_count = None;
     }
 
+    /// Unpin the oldest of `messages` until the pinned ones fit in
+    /// `AGENT_PINNED_PERCENT` of the budget
+    fn unpin_over_budget(&self, messages: &mut [AgentMessage]) {
+        let limit = self.max_tokens * AGENT_PINNED_PERCENT / 100;
+        let mut pinned_tokens: usize = messages
+            .iter()
+            .filter(|m| m.pinned)
+            .map(|m| self.message_tokens(m))
+            .sum();
+        let mut unpinned = 0;
+        for msg in messages.iter_mut().filter(|m| m.pinned) {
+            if pinned_tokens <= limit {
+                break;
+            }
+            pinned_tokens -= self.message_tokens(msg);
+            msg.pinned = false;
+            unpinned += 1;
+        }
+        if unpinned > 0 {
+            info!(
+                unpinned,
+                limit, "Pinned messages over budget, summarizing the oldest"
+            );
+        }
+    }
+
+    /// Turn a pinned tool result into a system note: the assistant message
+    /// that called the tool has been summarized, and a tool result without
+    /// its call is rejected by the APIs.
+    fn detach_tool(msg: AgentMessage) -> AgentMessage {
+        if msg.role != MessageRole::Tool {
+            return msg;
+        }
+        let name = msg.tool_name.as_deref().unwrap_or("tool");
+        AgentMessage::system(format!("[Pinned {name} result]\n{}", msg.content)).pin()
+    }
+
     /// Create a simple summary of messages (no LLM, just extraction of key points)
     fn create_simple_summary(messages: &[AgentMessage]) -> String {
         let mut summary_parts = Vec::new();
@@ -362,6 +498,20 @@ impl AgentMemory {
     }
 }
 
+/// Tokens left for the conversation once the reply is reserved
+///
+/// The reservation is capped at a quarter of the window: `max_tokens` is an
+/// upper bound rarely reached by a single reply, and reserving all of it
+/// would start compaction far below the window.
+fn input_budget(context_window: u32, max_output_tokens: u32) -> usize {
+    let reserved = max_output_tokens.min(context_window / 4);
+    (context_window - reserved) as usize
+}
+
+fn compact_threshold(max_tokens: usize) -> usize {
+    max_tokens * AGENT_COMPACT_PERCENT / 100
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -374,6 +524,111 @@ mod tests {
         assert!(memory.token_count() > 0);
     }
 
+    #[test]
+    fn test_set_tokenizer_recounts_messages() {
+        let mut memory = AgentMemory::new(100_000);
+        memory.add_message(AgentMessage::user("a".repeat(400)));
+        memory.set_tokenizer(&crate::agent::tokenizer::HeuristicTokenizer);
+        assert_eq!(memory.token_count(), 100);
+    }
+
+    #[test]
+    fn test_context_window_sets_compaction_budget() {
+        let mut memory = AgentMemory::new(100_000);
+        memory.set_tokenizer(&crate::agent::tokenizer::HeuristicTokenizer);
+        memory.set_context_window(2_500, 500);
+        assert_eq!(memory.max_tokens(), 2_000);
+
+        for _ in 0..17 {
+            memory.add_message(AgentMessage::user("a".repeat(400)));
+        }
+        assert_eq!(memory.get_messages().len(), 17);
+        assert!(!memory.needs_compaction());
+
+        // Shrinking the window compacts the stored messages right away
+        memory.set_context_window(1_600, 400);
+        assert!(memory.get_messages().len() < 17);
+        assert!(memory.token_count() < 1_700);
+    }
+
+    #[test]
+    fn test_output_reservation_is_capped_at_quarter_window() {
+        let mut memory = AgentMemory::new(100_000);
+        memory.set_context_window(200_000, 128_000);
+        assert_eq!(memory.max_tokens(), 150_000);
+
+        memory.set_context_window(131_072, 200_000);
+        assert_eq!(memory.max_tokens(), 98_304);
+    }
+
+    #[test]
+    fn test_compaction_keeps_pinned_messages() {
+        let mut memory = AgentMemory::new(100_000);
+        memory.set_tokenizer(&crate::agent::tokenizer::HeuristicTokenizer);
+        memory.set_context_window(2_500, 500);
+
+        memory.add_pinned_message(AgentMessage::user("Original task"));
+        memory.add_message(AgentMessage::tool("call-1", "ask_user", "Use Python 3.12"));
+        assert!(memory.pin_message(1));
+        assert!(!memory.pin_message(99));
+        for _ in 0..20 {
+            memory.add_message(AgentMessage::assistant("a".repeat(400)));
+        }
+
+        let messages = memory.get_messages();
+        assert!(messages.len() < 22);
+        assert!(messages[0]
+            .content
+            .starts_with("[Previous context compressed]"));
+        assert_eq!(messages[1].content, "Original task");
+        assert_eq!(messages[1].role, MessageRole::User);
+        assert_eq!(messages[2].role, MessageRole::System);
+        assert_eq!(
+            messages[2].content,
+            "[Pinned ask_user result]\nUse Python 3.12"
+        );
+        assert_eq!(memory.pinned_messages().count(), 2);
+    }
+
+    #[test]
+    fn test_compaction_caps_pinned_share() {
+        let mut memory = AgentMemory::new(100_000);
+        memory.set_tokenizer(&crate::agent::tokenizer::HeuristicTokenizer);
+        memory.set_context_window(2_500, 500);
+
+        for i in 0..4 {
+            memory.add_pinned_message(AgentMessage::user(format!("pin {i} {}", "p".repeat(800))));
+        }
+        for _ in 0..20 {
+            memory.add_message(AgentMessage::assistant("a".repeat(400)));
+        }
+
+        let pinned: usize = memory
+            .pinned_messages()
+            .map(|m| memory.message_tokens(m))
+            .sum();
+        assert!(pinned <= memory.max_tokens() * AGENT_PINNED_PERCENT / 100);
+        assert!(memory
+            .pinned_messages()
+            .all(|m| !m.content.starts_with("pin 0")));
+    }
+
+    #[test]
+    fn test_unpin_user_messages() {
+        let mut memory = AgentMemory::new(100_000);
+        memory.add_pinned_message(AgentMessage::user("First task"));
+        memory.add_pinned_message(AgentMessage::system("[Pinned set_env result]\nok"));
+        assert!(!memory.unpin_message(99));
+
+        memory.unpin_user_messages();
+        memory.add_pinned_message(AgentMessage::user("Second task"));
+        let pinned: Vec<_> = memory
+            .pinned_messages()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(pinned, ["[Pinned set_env result]\nok", "Second task"]);
+    }
+
     #[test]
     fn test_memory_clear() {
         let mut memory = AgentMemory::new(100_000);
@@ -0,0 +1,241 @@
+//! Localized system-level agent messages
+//!
+//! Strings the executor injects into the conversation or returns as task
+//! errors (continuation nudges, limits, cancellation) and the fallback
+//! system prompt. The language is chosen with `AGENT_LANGUAGE`.
+
+use std::sync::LazyLock;
+
+/// Language of injected system messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AgentLanguage {
+    /// English (default)
+    #[default]
+    English,
+    /// Russian
+    Russian,
+}
+
+static CURRENT: LazyLock<AgentLanguage> =
+    LazyLock::new(|| AgentLanguage::parse(&crate::config::get_agent_language()));
+
+impl AgentLanguage {
+    /// Language configured via `AGENT_LANGUAGE`
+    #[must_use]
+    pub fn current() -> Self {
+        *CURRENT
+    }
+
+    /// Parse a language code (`en`, `ru`, `russian`, ...); unknown values fall back to English
+    #[must_use]
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ru" | "rus" | "russian" | "ru-ru" | "ru_ru" => Self::Russian,
+            _ => Self::English,
+        }
+    }
+
+    /// Error returned when the agent runs out of iterations
+    #[must_use]
+    pub fn iteration_limit(self, max_iterations: usize) -> String {
+        match self {
+            Self::English => format!("Agent exceeded iteration limit ({max_iterations})."),
+            Self::Russian => format!("Превышен лимит итераций агента ({max_iterations})."),
+        }
+    }
+
+    /// Error returned when the task runs out of time
+    #[must_use]
+    pub fn timeout(self, minutes: u64) -> String {
+        match self {
+            Self::English => format!("Task exceeded timeout limit ({minutes} minutes)"),
+            Self::Russian => format!("Превышен лимит времени задачи ({minutes} мин.)"),
+        }
+    }
+
+    /// Error returned when the user cancels the task
+    #[must_use]
+    pub const fn cancelled(self) -> &'static str {
+        match self {
+            Self::English => "Task cancelled by user",
+            Self::Russian => "Задача отменена пользователем",
+        }
+    }
+
+    /// Error returned when a loop stops the task
+    #[must_use]
+    pub fn loop_detected(self, loop_type: &str) -> String {
+        match self {
+            Self::English => format!("Loop detected: {loop_type}"),
+            Self::Russian => format!("Обнаружено зацикливание: {loop_type}"),
+        }
+    }
+
+    /// Error returned when the user does not answer an `ask_user` question in time
+    #[must_use]
+    pub fn clarification_timeout(self, secs: u64) -> String {
+        match self {
+            Self::English => format!("No answer to the agent's question within {secs} seconds"),
+            Self::Russian => format!("Нет ответа на вопрос агента в течение {secs} сек."),
+        }
+    }
+
+    /// Error returned for a task without an actual instruction
+    #[must_use]
+    pub const fn empty_task(self) -> &'static str {
+        match self {
+            Self::English => "The task is empty. Describe what the agent should do.",
+            Self::Russian => "Задача пуста. Опишите, что должен сделать агент.",
+        }
+    }
+
+    /// Status shown while the model prepares its answer
+    #[must_use]
+    pub const fn thinking_placeholder(self) -> &'static str {
+        match self {
+            Self::English => "🤔 Thinking...",
+            Self::Russian => "🤔 Думаю...",
+        }
+    }
+
+    /// Continuation reason after an invalid structured response
+    #[must_use]
+    pub const fn invalid_json_retry(self) -> &'static str {
+        match self {
+            Self::English => "Invalid JSON response, retrying...",
+            Self::Russian => "Некорректный JSON-ответ, повторная попытка...",
+        }
+    }
+
+    /// Continuation reason when the raw response is accepted after repeated JSON errors
+    #[must_use]
+    pub const fn json_fallback(self) -> &'static str {
+        match self {
+            Self::English => "Too many JSON errors, falling back to raw response",
+            Self::Russian => "Слишком много ошибок JSON, используется исходный ответ",
+        }
+    }
+
+    /// System message asking the model to fix its structured output
+    #[must_use]
+    pub fn structured_output_nudge(self, error: &str, response_preview: &str) -> String {
+        match self {
+            Self::English => format!(
+                "[SYSTEM: Your previous response does not follow the strict JSON schema.\nError: {error}\nResponse: {response_preview}\nReturn ONLY valid JSON according to the schema without markdown, XML, or text outside JSON.]"
+            ),
+            Self::Russian => format!(
+                "[SYSTEM: Твой предыдущий ответ не соответствует строгой JSON-схеме.\nОшибка: {error}\nОтвет: {response_preview}\nВерни ТОЛЬКО валидный JSON по схеме, без markdown, XML и текста вне JSON.]"
+            ),
+        }
+    }
+
+    /// Conversation message carrying an instruction the user sent mid-task
+    #[must_use]
+    pub fn mid_task_instruction(self, instruction: &str) -> String {
+        match self {
+            Self::English => format!(
+                "[The user added an instruction while you were working. Take it into account for the rest of the task:]\n{instruction}"
+            ),
+            Self::Russian => format!(
+                "[Пользователь добавил указание во время работы. Учитывай его до конца задачи:]\n{instruction}"
+            ),
+        }
+    }
+
+    /// Continuation reason when todos are still open
+    #[must_use]
+    pub fn todos_incomplete(self, completed: usize, total: usize, pending: usize) -> String {
+        match self {
+            Self::English => format!(
+                "Not all tasks are completed ({completed}/{total} done, {pending} remaining). Continue working on remaining tasks."
+            ),
+            Self::Russian => format!(
+                "Не все задачи выполнены ({completed}/{total} готово, осталось {pending}). Продолжай работу над оставшимися задачами."
+            ),
+        }
+    }
+
+    /// Name of a day of the week
+    #[must_use]
+    pub const fn weekday_name(self, weekday: chrono::Weekday) -> &'static str {
+        use chrono::Weekday::{Fri, Mon, Sat, Sun, Thu, Tue, Wed};
+        match (self, weekday) {
+            (Self::English, Mon) => "Monday",
+            (Self::English, Tue) => "Tuesday",
+            (Self::English, Wed) => "Wednesday",
+            (Self::English, Thu) => "Thursday",
+            (Self::English, Fri) => "Friday",
+            (Self::English, Sat) => "Saturday",
+            (Self::English, Sun) => "Sunday",
+            (Self::Russian, Mon) => "понедельник",
+            (Self::Russian, Tue) => "вторник",
+            (Self::Russian, Wed) => "среда",
+            (Self::Russian, Thu) => "четверг",
+            (Self::Russian, Fri) => "пятница",
+            (Self::Russian, Sat) => "суббота",
+            (Self::Russian, Sun) => "воскресенье",
+        }
+    }
+
+    /// System prompt used when neither skills nor `AGENT.md` are available
+    #[must_use]
+    pub const fn fallback_prompt(self) -> &'static str {
+        match self {
+            Self::English => {
+                r"You are an AI agent with access to a sandbox environment and web search.
+## Available Tools (Basic Examples):
+- **execute_command**: execute bash command in sandbox (available: python3, pip, ffmpeg, yt-dlp, curl, wget, date, cat, ls, grep and other standard utilities)
+- **write_file**: write content to file
+- **read_file**: read file content
+- **web_search**: search information on the web
+- **web_extract**: extract text from web pages
+- **write_todos**: create or update todo list
+- **update_todo**: change the status of one task in the list
+- **get_datetime**: current date and time, optionally in another timezone
+- **finish_task**: end the task with a final message; success=false if it cannot be done
+## Important Rules:
+- If real data is needed - USE TOOLS
+- Use Python for calculations
+- After receiving tool result - analyze it and continue working
+- For COMPLEX requests, YOU MUST use write_todos to create a plan"
+            }
+            Self::Russian => {
+                r"Ты — ИИ-агент с доступом к песочнице и веб-поиску.
+## Доступные инструменты (основные):
+- **execute_command**: выполнить bash-команду в песочнице (доступны: python3, pip, ffmpeg, yt-dlp, curl, wget, date, cat, ls, grep и другие стандартные утилиты)
+- **write_file**: записать содержимое в файл
+- **read_file**: прочитать содержимое файла
+- **web_search**: поиск информации в интернете
+- **web_extract**: извлечь текст с веб-страниц
+- **write_todos**: создать или обновить список задач
+- **update_todo**: изменить статус одной задачи в списке
+- **get_datetime**: текущие дата и время, при необходимости в другом часовом поясе
+- **finish_task**: завершить задачу итоговым сообщением; success=false, если она невыполнима
+## Важные правила:
+- Если нужны реальные данные — ИСПОЛЬЗУЙ ИНСТРУМЕНТЫ
+- Для вычислений используй Python
+- Получив результат инструмента — проанализируй его и продолжай работу
+- Для СЛОЖНЫХ запросов ОБЯЗАТЕЛЬНО составь план через write_todos
+- Отвечай пользователю на русском языке"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(AgentLanguage::parse("ru"), AgentLanguage::Russian);
+        assert_eq!(AgentLanguage::parse(" Russian "), AgentLanguage::Russian);
+        assert_eq!(AgentLanguage::parse("en"), AgentLanguage::English);
+        assert_eq!(AgentLanguage::parse("klingon"), AgentLanguage::English);
+        assert_eq!(
+            AgentLanguage::English.iteration_limit(5),
+            "Agent exceeded iteration limit (5)."
+        );
+        assert!(AgentLanguage::Russian.cancelled().contains("отменена"));
+    }
+}
@@ -6,16 +6,26 @@
 //! - Report progress via transport adapters
 //! - Manage conversation memory with auto-compaction
 
+/// Durable audit log of tool executions
+pub mod audit;
+/// Folding of repetitive tool output
+pub mod compression;
 /// Context abstractions for runner execution
 pub mod context;
+/// Session state dump for debugging stuck agents
+pub mod debug;
 /// Executor for iterative task processing
 pub mod executor;
 /// Hook system for intercepting agent events
 pub mod hooks;
 /// Transport-agnostic agent identity types
 pub mod identity;
+/// Instructions the user adds while a task is running
+pub mod instructions;
 /// Memory management with auto-compaction
 pub mod memory;
+/// Localized system-level messages (`AGENT_LANGUAGE`)
+pub mod messages;
 /// Preprocessor for different input types (voice, photo, etc)
 pub mod preprocessor;
 /// Prompt composition for system prompts
@@ -36,8 +46,12 @@ pub mod session;
 pub mod skills;
 /// Structured output parsing and validation
 pub mod structured_output;
+/// Token counting per model family
+pub mod tokenizer;
 /// Tool execution bridge with timeout and cancellation
 pub mod tool_bridge;
+/// Structured tool error taxonomy
+pub mod tool_error;
 
 /// Agent thought inference from tool calls
 pub mod thoughts;
@@ -62,6 +76,7 @@ pub use provider::ToolProvider;
 pub use providers::{TodoItem, TodoList, TodoStatus, TodosProvider};
 pub use recovery::sanitize_xml_tags;
 pub use registry::ToolRegistry;
-pub use runner::{AgentRunner, AgentRunnerConfig, AgentRunnerContext};
+pub use runner::{AgentRunResult, AgentRunner, AgentRunnerConfig, AgentRunnerContext, Outcome};
 pub use session::{AgentSession, AgentStatus};
 pub use skills::SkillRegistry;
+pub use tool_error::{ToolError, ToolErrorKind};
@@ -12,10 +12,60 @@ use tracing::info;
 /// Upload limit: 1 GB per session
 const UPLOAD_LIMIT_BYTES: u64 = 1024 * 1024 * 1024;
 
+/// Trim a task and tidy its whitespace; `None` if it carries no instruction
+///
+/// Line endings become `\n`, trailing spaces and invisible characters are
+/// dropped and runs of blank lines are collapsed to one. A task needs at least
+/// one letter or digit: blank, punctuation-only and emoji-only messages are
+/// rejected before they start a sandbox or an LLM call.
+///
+/// # Examples
+///
+/// ```
+/// use oxide_agent_core::agent::preprocessor::normalize_task;
+///
+/// assert_eq!(
+///     normalize_task("  Fix the build \r\n\r\n\r\n\r\nthen deploy\u{200b} "),
+///     Some("Fix the build\n\nthen deploy".to_string())
+/// );
+/// assert_eq!(normalize_task(" \n\t "), None);
+/// assert_eq!(normalize_task("👍🔥"), None);
+/// ```
+#[must_use]
+pub fn normalize_task(task: &str) -> Option<String> {
+    let cleaned: String = task
+        .chars()
+        .filter(|c| matches!(c, '\n' | '\t') || !(c.is_control() || is_invisible(*c)))
+        .collect();
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in cleaned.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    let normalized = lines.join("\n").trim().to_string();
+
+    normalized
+        .chars()
+        .any(char::is_alphanumeric)
+        .then_some(normalized)
+}
+
+/// Zero-width and formatting characters that render as nothing
+const fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200b}'..='\u{200f}' | '\u{2060}'..='\u{2064}' | '\u{feff}' | '\u{00ad}'
+    )
+}
+
 /// Preprocessor for converting multimodal inputs to text
 pub struct Preprocessor {
     llm_client: Arc<LlmClient>,
     user_id: i64,
+    language: Option<String>,
 }
 
 impl Preprocessor {
@@ -38,9 +88,17 @@ impl Preprocessor {
         Self {
             llm_client,
             user_id,
+            language: None,
         }
     }
 
+    /// Set the language hint used for voice transcription (`None` = autodetect)
+    #[must_use]
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
     /// Transcribe voice audio to text using the configured multimodal model
     ///
     /// # Examples
@@ -82,7 +140,7 @@ impl Preprocessor {
 
         let transcription = self
             .llm_client
-            .transcribe_audio(audio_bytes, mime_type, model_name)
+            .transcribe_audio(audio_bytes, mime_type, self.language.as_deref(), model_name)
             .await
             .map_err(|e| anyhow::anyhow!("Transcription failed: {e}"))?;
 
@@ -175,7 +233,49 @@ impl Preprocessor {
         caption: Option<String>,
     ) -> Result<String> {
         let upload_path = format!("/workspace/uploads/{}", Self::sanitize_filename(&file_name));
+        let manager = self.upload_sandbox(bytes.len() as u64).await?;
+        manager.upload_file(&upload_path, &bytes).await?;
+
+        Ok(Self::describe_upload(
+            &upload_path,
+            bytes.len(),
+            &file_name,
+            mime_type,
+            caption,
+        ))
+    }
+
+    /// Copy a document saved on the host into the sandbox, then delete the local copy
+    async fn process_local_document(
+        &self,
+        path: &std::path::Path,
+        file_name: String,
+        mime_type: Option<String>,
+        caption: Option<String>,
+    ) -> Result<String> {
+        let result = async {
+            let size = tokio::fs::metadata(path).await?.len();
+            let upload_path = format!("/workspace/uploads/{}", Self::sanitize_filename(&file_name));
+            let manager = self.upload_sandbox(size).await?;
+            manager.upload_local_file(&upload_path, path).await?;
+            Ok(Self::describe_upload(
+                &upload_path,
+                usize::try_from(size).unwrap_or(usize::MAX),
+                &file_name,
+                mime_type,
+                caption,
+            ))
+        }
+        .await;
+
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to remove downloaded document");
+        }
+        result
+    }
 
+    /// Sandbox to upload `size` more bytes into, created on demand
+    async fn upload_sandbox(&self, size: u64) -> Result<SandboxManager> {
         // Lazy-create sandbox
         let mut manager = SandboxManager::new(self.user_id).await?;
         if !manager.is_running() {
@@ -184,7 +284,7 @@ impl Preprocessor {
 
         // Check upload limit
         let current_size = manager.get_uploads_size().await.unwrap_or(0);
-        let new_size = current_size + bytes.len() as u64;
+        let new_size = current_size + size;
 
         if new_size > UPLOAD_LIMIT_BYTES {
             anyhow::bail!(
@@ -192,11 +292,18 @@ impl Preprocessor {
                 new_size as f64 / 1024.0 / 1024.0 / 1024.0
             );
         }
+        Ok(manager)
+    }
 
-        manager.upload_file(&upload_path, &bytes).await?;
-
-        let size_str = Self::format_file_size(bytes.len());
-        let hint = Self::get_file_type_hint(&file_name);
+    fn describe_upload(
+        upload_path: &str,
+        size: usize,
+        file_name: &str,
+        mime_type: Option<String>,
+        caption: Option<String>,
+    ) -> String {
+        let size_str = Self::format_file_size(size);
+        let hint = Self::get_file_type_hint(file_name);
 
         let mut parts = vec![
             "📎 **User uploaded a file:**".to_string(),
@@ -218,7 +325,7 @@ impl Preprocessor {
             parts.push("_User did not leave a comment._".to_string());
         }
 
-        Ok(parts.join("\n"))
+        parts.join("\n")
     }
 
     /// Sanitize a filename by replacing dangerous characters
@@ -332,6 +439,15 @@ impl Preprocessor {
                 self.process_document(bytes, file_name, mime_type, caption)
                     .await
             }
+            AgentInput::LocalDocument {
+                path,
+                file_name,
+                mime_type,
+                caption,
+            } => {
+                self.process_local_document(&path, file_name, mime_type, caption)
+                    .await
+            }
         }
     }
 }
@@ -372,12 +488,37 @@ pub enum AgentInput {
         /// Optional caption from the user
         caption: Option<String>,
     },
+    /// Document uploaded by user and saved to a local file (large uploads)
+    ///
+    /// The file is deleted once it has been copied into the sandbox.
+    LocalDocument {
+        /// Path of the downloaded file on the host
+        path: std::path::PathBuf,
+        /// Original filename
+        file_name: String,
+        /// MIME type of the file
+        mime_type: Option<String>,
+        /// Optional caption from the user
+        caption: Option<String>,
+    },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_task_needs_letters_or_digits() {
+        assert_eq!(normalize_task("Привет"), Some("Привет".to_string()));
+        assert_eq!(normalize_task("42"), Some("42".to_string()));
+        assert_eq!(normalize_task("?! ..."), None);
+        assert_eq!(normalize_task("\u{feff}\u{200b}"), None);
+        assert_eq!(
+            normalize_task("step 1\n   \nstep 2"),
+            Some("step 1\n\nstep 2".to_string())
+        );
+    }
+
     #[test]
     fn test_sanitize_filename_basic() {
         assert_eq!(Preprocessor::sanitize_filename("file.txt"), "file.txt");
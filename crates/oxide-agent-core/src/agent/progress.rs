@@ -27,6 +27,9 @@ pub enum AgentEvent {
         name: String,
         /// Tool execution output
         output: String,
+        /// Wall-clock time spent executing the tool (milliseconds)
+        #[serde(default)]
+        duration_ms: u64,
     },
     /// Agent is continuing work due to incomplete todos
     Continuation {
@@ -35,6 +38,12 @@ pub enum AgentEvent {
         /// Number of continuations so far
         count: usize,
     },
+    /// Todos are still open after the last allowed continuation; the agent
+    /// finishes with its most complete partial answer
+    ContinuationLimitReached {
+        /// Continuation limit that was reached
+        limit: usize,
+    },
     /// Todos list was updated
     TodosUpdated {
         /// Updated list of tasks
@@ -77,6 +86,11 @@ pub enum AgentEvent {
         /// Short summary of reasoning
         summary: String,
     },
+    /// Full reasoning of a step, emitted only when `SHOW_REASONING` enables it for the user
+    ReasoningDetails {
+        /// Reasoning text with XML-like tags stripped
+        content: String,
+    },
     /// Loop detected during execution
     LoopDetected {
         /// Type of loop detected
@@ -84,6 +98,16 @@ pub enum AgentEvent {
         /// Iteration when detected
         iteration: usize,
     },
+    /// Agent asked the user a question and waits for the answer
+    ClarificationNeeded {
+        /// Question to show to the user
+        question: String,
+    },
+    /// An instruction the user sent mid-task was added to the conversation
+    InstructionInjected {
+        /// Instruction text
+        instruction: String,
+    },
     /// Narrative update from sidecar LLM
     Narrative {
         /// Short action-oriented headline
@@ -114,6 +138,8 @@ pub struct ProgressState {
     pub narrative_headline: Option<String>,
     /// Narrative content from sidecar LLM
     pub narrative_content: Option<String>,
+    /// Question the agent is waiting for the user to answer
+    pub pending_question: Option<String>,
 }
 
 /// A single step in the agent's execution process
@@ -127,6 +153,8 @@ pub struct Step {
     pub tokens: Option<usize>,
     /// Tool name for grouping (None for non-tool steps like Thinking)
     pub tool_name: Option<String>,
+    /// Tool execution time in milliseconds (set once the tool result arrives)
+    pub duration_ms: Option<u64>,
 }
 
 /// Possible statuses for an execution step
@@ -161,8 +189,14 @@ impl ProgressState {
                 input,
                 command_preview,
             } => self.handle_tool_call(name, input, command_preview),
-            AgentEvent::ToolResult { .. } => self.complete_last_step(),
+            AgentEvent::ToolResult { duration_ms, .. } => {
+                self.pending_question = None;
+                self.handle_tool_result(duration_ms);
+            }
             AgentEvent::Continuation { reason, count } => self.handle_continuation(reason, count),
+            AgentEvent::ContinuationLimitReached { limit } => {
+                self.handle_continuation_limit(limit);
+            }
             AgentEvent::TodosUpdated { todos } => self.handle_todos_update(todos),
             AgentEvent::FileToSend { file_name, .. } => self.handle_file_send(file_name),
             AgentEvent::FileToSendWithConfirmation { file_name, .. } => {
@@ -173,10 +207,18 @@ impl ProgressState {
             AgentEvent::Cancelled => self.handle_cancelled(),
             AgentEvent::Error(e) => self.handle_error(e),
             AgentEvent::Reasoning { summary } => self.handle_reasoning(summary),
+            // Delivered as a separate message by the transport; not part of the progress view
+            AgentEvent::ReasoningDetails { .. } => {}
             AgentEvent::LoopDetected {
                 loop_type,
                 iteration,
             } => self.handle_loop_detected(loop_type, iteration),
+            AgentEvent::ClarificationNeeded { question } => {
+                self.pending_question = Some(question);
+            }
+            AgentEvent::InstructionInjected { instruction } => {
+                self.handle_instruction_injected(&instruction);
+            }
             AgentEvent::Narrative { headline, content } => self.handle_narrative(headline, content),
         }
     }
@@ -199,6 +241,15 @@ impl ProgressState {
         }
     }
 
+    fn handle_tool_result(&mut self, duration_ms: u64) {
+        if let Some(last) = self.steps.last_mut() {
+            if last.status == StepStatus::InProgress && last.tool_name.is_some() {
+                last.duration_ms = Some(duration_ms);
+            }
+        }
+        self.complete_last_step();
+    }
+
     fn handle_thinking(&mut self, tokens: usize) {
         self.current_iteration += 1;
         self.complete_last_step();
@@ -210,6 +261,7 @@ impl ProgressState {
             status: StepStatus::InProgress,
             tokens: Some(tokens),
             tool_name: None,
+            duration_ms: None,
         });
     }
 
@@ -236,6 +288,7 @@ impl ProgressState {
             status: StepStatus::InProgress,
             tokens: None,
             tool_name: Some(name),
+            duration_ms: None,
         });
     }
 
@@ -245,12 +298,26 @@ impl ProgressState {
             description: format!(
                 "🔄 Continuation ({}/{}): {}",
                 count,
-                crate::config::AGENT_CONTINUATION_LIMIT,
+                crate::config::get_agent_continuation_limit(),
                 crate::utils::truncate_str(reason, 50)
             ),
             status: StepStatus::InProgress,
             tokens: None,
             tool_name: None,
+            duration_ms: None,
+        });
+    }
+
+    fn handle_continuation_limit(&mut self, limit: usize) {
+        self.complete_last_step();
+        self.steps.push(Step {
+            description: format!(
+                "⚠️ Continuation limit reached ({limit}): finishing with the best partial answer"
+            ),
+            status: StepStatus::Completed,
+            tokens: None,
+            tool_name: None,
+            duration_ms: None,
         });
     }
 
@@ -277,11 +344,26 @@ impl ProgressState {
             status: StepStatus::Completed,
             tokens: None,
             tool_name: Some("file_send".to_string()),
+            duration_ms: None,
+        });
+    }
+
+    fn handle_instruction_injected(&mut self, instruction: &str) {
+        self.steps.push(Step {
+            description: format!(
+                "📝 Instruction received: {}",
+                crate::utils::truncate_str(instruction, 60)
+            ),
+            status: StepStatus::Completed,
+            tokens: None,
+            tool_name: None,
+            duration_ms: None,
         });
     }
 
     fn handle_finish(&mut self) {
         self.is_finished = true;
+        self.pending_question = None;
         self.current_thought = None; // Clear thought on finish
         for step in &mut self.steps {
             if step.status == StepStatus::InProgress {
@@ -310,17 +392,20 @@ impl ProgressState {
                 status: StepStatus::InProgress,
                 tokens: None,
                 tool_name: None,
+                duration_ms: None,
             });
         }
     }
 
     fn handle_cancelled(&mut self) {
         self.error = Some("Task cancelled by user".to_string());
+        self.pending_question = None;
         self.fail_last_step();
     }
 
     fn handle_error(&mut self, e: String) {
         self.error = Some(e);
+        self.pending_question = None;
         self.fail_last_step();
     }
 
@@ -329,6 +414,7 @@ impl ProgressState {
             LoopType::ToolCallLoop => "Recurring calls",
             LoopType::ContentLoop => "Recurring text",
             LoopType::CognitiveLoop => "Stuck",
+            LoopType::FatalErrorLoop => "Recurring fatal error",
         };
         self.error = Some(format!("Loop detected: {label} (iteration {iteration})"));
         self.fail_last_step();
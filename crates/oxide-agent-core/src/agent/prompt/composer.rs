@@ -3,39 +3,46 @@
 //! Handles construction of system prompts for the agent, including skill-based
 //! prompts, date context, and fallback prompts.
 
+use crate::agent::messages::AgentLanguage;
 use crate::agent::session::AgentSession;
+use crate::agent::skills::types::count_tokens;
 use crate::agent::skills::{SkillContext, SkillRegistry};
 use crate::llm::ToolDefinition;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use std::fmt::Display;
 use tracing::{error, info, warn};
 
-/// Build the date context block for the system prompt
+/// Build the date context block for the system prompt, in `AGENT_TIMEZONE`
+/// or the server's local time
 fn build_date_context() -> String {
-    let now = chrono::Local::now();
-    let current_date = now.format("%Y-%m-%d %H:%M:%S").to_string();
-    let current_day = now.format("%A").to_string();
+    let language = AgentLanguage::current();
+    match crate::config::get_agent_timezone() {
+        Some(tz) => format_date_context(&Utc::now().with_timezone(&tz), tz.name(), language),
+        None => format_date_context(&chrono::Local::now(), "server time", language),
+    }
+}
+
+fn format_date_context<Z: TimeZone>(
+    now: &DateTime<Z>,
+    zone: &str,
+    language: AgentLanguage,
+) -> String
+where
+    Z::Offset: Display,
+{
+    let current_date = now.format("%Y-%m-%d %H:%M:%S");
+    let current_day = language.weekday_name(now.weekday());
+    let offset = now.format("%:z");
 
     format!(
-        "### CURRENT DATE AND TIME\nToday: {current_date}, {current_day}\nIMPORTANT: Always use this date as the current date. If search results (web_search) contain phrases like 'today', 'tomorrow', or dates contradicting this, consider the search results outdated and interpret them relative to the date above.\n\n"
+        "### CURRENT DATE AND TIME\nToday: {current_date}, {current_day} ({zone}, UTC{offset})\nIMPORTANT: Always use this date as the current date. If search results (web_search) contain phrases like 'today', 'tomorrow', or dates contradicting this, consider the search results outdated and interpret them relative to the date above.\n\n"
     )
 }
 
 /// Get the fallback prompt when AGENT.md is missing
 #[must_use]
 pub fn get_fallback_prompt() -> String {
-    r"You are an AI agent with access to a sandbox environment and web search.
-## Available Tools (Basic Examples):
-- **execute_command**: execute bash command in sandbox (available: python3, pip, ffmpeg, yt-dlp, curl, wget, date, cat, ls, grep and other standard utilities)
-- **write_file**: write content to file
-- **read_file**: read file content
-- **web_search**: search information on the web
-- **web_extract**: extract text from web pages
-- **write_todos**: create or update todo list
-## Important Rules:
-- If real data is needed - USE TOOLS
-- Use Python for calculations
-- After receiving tool result - analyze it and continue working
-- For COMPLEX requests, YOU MUST use write_todos to create a plan"
-        .to_string()
+    AgentLanguage::current().fallback_prompt().to_string()
 }
 
 /// Build instructions for mandatory structured output (JSON).
@@ -85,21 +92,94 @@ fn strip_structured_output_requirement(prompt: &str) -> String {
         .join("\n")
 }
 
+/// Appended to a base prompt cut down to the system prompt budget
+const TRUNCATION_NOTICE: &str = "\n\n[... prompt truncated to fit the system prompt budget]";
+
+/// Token budget for the whole system prompt of a model with `max_tokens`
+fn system_prompt_token_budget(max_tokens: u32) -> usize {
+    let fraction = crate::config::get_system_prompt_budget_fraction();
+    (f64::from(max_tokens) * fraction) as usize
+}
+
+/// Cut `base_prompt` so that it plus `fixed_tokens` fits into `budget` tokens
+///
+/// Returns `None` when the prompt already fits.
+fn truncate_to_token_budget(
+    base_prompt: &str,
+    fixed_tokens: usize,
+    budget: usize,
+) -> Option<String> {
+    let base_tokens = count_tokens(base_prompt);
+    if fixed_tokens + base_tokens <= budget {
+        return None;
+    }
+
+    let allowed = budget.saturating_sub(fixed_tokens + count_tokens(TRUNCATION_NOTICE));
+    let total_chars = base_prompt.chars().count();
+    let mut keep_chars = total_chars.saturating_mul(allowed) / base_tokens.max(1);
+    let mut truncated = crate::utils::truncate_str(base_prompt, keep_chars);
+    while keep_chars > 0 && count_tokens(&truncated) > allowed {
+        keep_chars = keep_chars * 9 / 10;
+        truncated = crate::utils::truncate_str(base_prompt, keep_chars);
+    }
+    truncated.push_str(TRUNCATION_NOTICE);
+    Some(truncated)
+}
+
+/// Keep the system prompt within the budget derived from `max_tokens`
+///
+/// Only AGENT.md (or the fallback) is truncated; skill prompts are already
+/// capped by `SKILL_TOKEN_BUDGET`, so an oversized skills section is logged
+/// but left intact. `max_tokens == 0` means the limit is unknown.
+fn fit_base_prompt(
+    base_prompt: String,
+    fixed_tokens: usize,
+    max_tokens: u32,
+    from_skills: bool,
+) -> String {
+    if max_tokens == 0 {
+        return base_prompt;
+    }
+    let budget = system_prompt_token_budget(max_tokens);
+    if from_skills {
+        let total = fixed_tokens + count_tokens(&base_prompt);
+        if total > budget {
+            warn!(total_tokens = total, budget, "System prompt exceeds its budget; skills are kept intact, consider lowering SKILL_TOKEN_BUDGET");
+        }
+        return base_prompt;
+    }
+
+    match truncate_to_token_budget(&base_prompt, fixed_tokens, budget) {
+        Some(truncated) => {
+            warn!(
+                total_tokens = fixed_tokens + count_tokens(&base_prompt),
+                budget,
+                kept_tokens = count_tokens(&truncated),
+                "System prompt exceeds its budget, AGENT.md was truncated; trim the prompt or raise SYSTEM_PROMPT_BUDGET_FRACTION"
+            );
+            truncated
+        }
+        None => base_prompt,
+    }
+}
+
 /// Create the system prompt for the agent
 ///
 /// This function builds the complete system prompt by:
 /// 1. Adding date/time context
 /// 2. Either loading skill-based prompts or falling back to AGENT.md
+/// 3. Truncating AGENT.md when the prompt exceeds its share of `max_tokens`
 pub async fn create_agent_system_prompt(
     task: &str,
     tools: &[ToolDefinition],
     structured_output: bool,
+    max_tokens: u32,
     skill_registry: Option<&mut SkillRegistry>,
     session: &mut AgentSession,
 ) -> String {
     let date_context = build_date_context();
 
-    let base_prompt = if let Some(registry) = skill_registry {
+    let skills_prompt = if let Some(registry) = skill_registry {
         match registry.build_prompt(task).await {
             Ok(skill_prompt) if !skill_prompt.content.is_empty() => {
                 session.set_loaded_skills(&skill_prompt.skills);
@@ -107,6 +187,7 @@ pub async fn create_agent_system_prompt(
                     skills = ?skill_prompt.skills,
                     total_tokens = skill_prompt.token_count,
                     skipped = ?skill_prompt.skipped,
+                    matcher = skill_prompt.matcher.as_str(),
                     "Skills loaded for request"
                 );
                 skill_prompt.content
@@ -124,8 +205,9 @@ pub async fn create_agent_system_prompt(
         String::new()
     };
 
-    let base_prompt = if !base_prompt.is_empty() {
-        base_prompt
+    let from_skills = !skills_prompt.is_empty();
+    let base_prompt = if from_skills {
+        skills_prompt
     } else {
         let empty_skills: [SkillContext; 0] = [];
         session.set_loaded_skills(&empty_skills);
@@ -145,11 +227,14 @@ pub async fn create_agent_system_prompt(
         strip_structured_output_requirement(&base_prompt)
     };
 
-    if structured_output {
-        let structured_output = build_structured_output_instructions(tools);
-        format!("{date_context}{base_prompt}\n\n{structured_output}")
-    } else {
-        format!("{date_context}{base_prompt}")
+    let structured_section = structured_output.then(|| build_structured_output_instructions(tools));
+    let fixed_tokens =
+        count_tokens(&date_context) + structured_section.as_deref().map_or(0, count_tokens);
+    let base_prompt = fit_base_prompt(base_prompt, fixed_tokens, max_tokens, from_skills);
+
+    match structured_section {
+        Some(structured_output) => format!("{date_context}{base_prompt}\n\n{structured_output}"),
+        None => format!("{date_context}{base_prompt}"),
     }
 }
 
@@ -202,6 +287,25 @@ mod tests {
         assert!(context.contains("Today:"));
     }
 
+    #[test]
+    fn test_date_context_uses_timezone_and_language() {
+        let Ok(tz) = "Asia/Vladivostok".parse::<chrono_tz::Tz>() else {
+            panic!("Asia/Vladivostok should parse");
+        };
+        // Still Thursday in UTC, already Friday in Vladivostok
+        let Some(utc) = Utc.with_ymd_and_hms(2026, 10, 15, 20, 30, 0).single() else {
+            panic!("valid date");
+        };
+        let now = utc.with_timezone(&tz);
+
+        let context = format_date_context(&now, tz.name(), AgentLanguage::English);
+        assert!(
+            context.contains("Today: 2026-10-16 06:30:00, Friday (Asia/Vladivostok, UTC+10:00)")
+        );
+        let context = format_date_context(&now, tz.name(), AgentLanguage::Russian);
+        assert!(context.contains("Today: 2026-10-16 06:30:00, пятница (Asia/Vladivostok"));
+    }
+
     #[test]
     fn test_fallback_prompt_contains_tools() {
         let prompt = get_fallback_prompt();
@@ -209,4 +313,17 @@ mod tests {
         assert!(prompt.contains("write_file"));
         assert!(prompt.contains("read_file"));
     }
+
+    #[test]
+    fn test_truncate_to_token_budget_fits_budget() {
+        let prompt = "Follow the rules carefully. ".repeat(500);
+        assert!(truncate_to_token_budget(&prompt, 100, 100_000).is_none());
+
+        let Some(truncated) = truncate_to_token_budget(&prompt, 100, 600) else {
+            panic!("prompt over budget must be truncated");
+        };
+        assert!(truncated.ends_with(TRUNCATION_NOTICE));
+        assert!(truncated.starts_with("Follow the rules"));
+        assert!(100 + count_tokens(&truncated) <= 600);
+    }
 }
@@ -0,0 +1,211 @@
+//! Brave Search provider - web search via the Brave Search API
+//!
+//! Provides the same `web_search` tool as the Tavily provider, so deployments
+//! can switch backends with `SEARCH_BACKEND=brave` without touching prompts.
+
+use super::search_cache::{cache_search, cached_search, search_cache_key};
+use super::web_search::{format_search_results, web_search_tool, SearchHit, WebSearchArgs};
+use crate::agent::provider::ToolProvider;
+use crate::llm::ToolDefinition;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::debug;
+
+/// Brave Search web endpoint
+const BRAVE_SEARCH_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+/// Timeout for a single search request
+const BRAVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Provider for Brave web search
+pub struct BraveSearchProvider {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl BraveSearchProvider {
+    /// Create a new Brave Search provider with the given API key
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn new(api_key: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(BRAVE_TIMEOUT)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to create Brave Search client: {e}"))?;
+
+        Ok(Self {
+            client,
+            api_key: api_key.to_string(),
+        })
+    }
+
+    /// One page of results; Brave counts `offset` in pages of `count` results
+    async fn search(&self, args: &WebSearchArgs) -> Result<BraveResponse> {
+        let count = args.max_results().to_string();
+        let offset = (args.page() - 1).to_string();
+        let response = self
+            .client
+            .get(BRAVE_SEARCH_URL)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .query(&[
+                ("q", args.query.as_str()),
+                ("count", &count),
+                ("offset", &offset),
+            ])
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("HTTP {status}: {}", body.trim());
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+/// Relevant part of a Brave Search response
+#[derive(Debug, Deserialize)]
+struct BraveResponse {
+    query: Option<BraveQuery>,
+    web: Option<BraveWebResults>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveQuery {
+    #[serde(default)]
+    more_results_available: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveWebResults {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+impl BraveResponse {
+    fn has_more(&self) -> bool {
+        self.query
+            .as_ref()
+            .is_some_and(|q| q.more_results_available)
+    }
+
+    fn into_hits(self) -> Vec<SearchHit> {
+        self.web
+            .map(|web| web.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|result| SearchHit {
+                title: result.title,
+                url: result.url,
+                content: result.description,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ToolProvider for BraveSearchProvider {
+    fn name(&self) -> &'static str {
+        "brave"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![web_search_tool()]
+    }
+
+    fn can_handle(&self, tool_name: &str) -> bool {
+        tool_name == "web_search"
+    }
+
+    async fn execute(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        _progress_tx: Option<&tokio::sync::mpsc::Sender<crate::agent::progress::AgentEvent>>,
+        _cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        debug!(tool = tool_name, "Executing Brave Search tool");
+
+        if tool_name != "web_search" {
+            anyhow::bail!("Unknown Brave Search tool: {tool_name}");
+        }
+
+        let args: WebSearchArgs = serde_json::from_str(arguments)?;
+        let cache_key = search_cache_key("brave", &args.query, args.max_results(), args.page());
+        if let Some(cached) = cached_search(&cache_key).await {
+            debug!(query = %args.query, "Brave web search served from cache");
+            return Ok(format!("(cached)\n{cached}"));
+        }
+
+        debug!(
+            query = %args.query,
+            max_results = args.max_results(),
+            page = args.page(),
+            "Brave web search"
+        );
+
+        match self.search(&args).await {
+            Ok(response) => {
+                let has_more = response.has_more();
+                let output = format_search_results(&args, &response.into_hits(), has_more);
+                cache_search(cache_key, output.clone()).await;
+                Ok(output)
+            }
+            Err(e) => Ok(format!("Search error: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: &str) -> BraveResponse {
+        match serde_json::from_str(body) {
+            Ok(response) => response,
+            Err(e) => panic!("response should parse: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_response_is_converted_to_hits() {
+        let response = parse(
+            r#"{
+                "query": {"original": "rust", "more_results_available": true},
+                "web": {"results": [
+                    {"title": "The Rust Language", "url": "https://www.rust-lang.org/", "description": "A language empowering everyone", "age": "1 day"},
+                    {"url": "https://doc.rust-lang.org/"}
+                ]}
+            }"#,
+        );
+        assert!(response.has_more());
+
+        let hits = response.into_hits();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].content, "A language empowering everyone");
+        assert_eq!(hits[1].url, "https://doc.rust-lang.org/");
+        assert!(hits[1].title.is_empty());
+    }
+
+    #[test]
+    fn test_missing_sections_mean_no_results() {
+        let response = parse(r#"{"type": "search"}"#);
+        assert!(!response.has_more());
+        assert!(response.into_hits().is_empty());
+    }
+}
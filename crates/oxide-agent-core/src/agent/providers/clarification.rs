@@ -0,0 +1,328 @@
+//! Clarification provider - lets the agent ask the user a question mid-task
+//!
+//! `ask_user` emits [`AgentEvent::ClarificationNeeded`] and waits for the
+//! transport to hand the user's next message back via [`submit_answer`].
+//! Pending questions are tracked per session in a process-wide table, along
+//! with the time spent waiting so the task timer can leave it out.
+
+use crate::agent::progress::AgentEvent;
+use crate::agent::provider::ToolProvider;
+use crate::agent::recovery::sanitize_xml_tags;
+use crate::agent::tool_error::{ToolError, ToolErrorKind};
+use crate::llm::ToolDefinition;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, info};
+
+/// Name of the clarification tool
+pub const ASK_USER_TOOL: &str = "ask_user";
+
+/// Answer channels of questions waiting for the user, keyed by session
+static PENDING: LazyLock<Mutex<HashMap<i64, oneshot::Sender<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn pending() -> std::sync::MutexGuard<'static, HashMap<i64, oneshot::Sender<String>>> {
+    PENDING.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Time a session has spent waiting for answers
+#[derive(Default)]
+struct WaitClock {
+    total: Duration,
+    since: Option<Instant>,
+}
+
+/// Wait clocks of the current tasks, keyed by session
+static WAITS: LazyLock<Mutex<HashMap<i64, WaitClock>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn waits() -> std::sync::MutexGuard<'static, HashMap<i64, WaitClock>> {
+    WAITS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Total time the session has spent waiting for answers, including a wait in progress
+#[must_use]
+pub fn time_waited(session_id: i64) -> Duration {
+    waits().get(&session_id).map_or(Duration::ZERO, |clock| {
+        clock.total + clock.since.map_or(Duration::ZERO, |since| since.elapsed())
+    })
+}
+
+/// Forget the session's wait time; called when a new task starts
+pub fn reset_time_waited(session_id: i64) {
+    waits().remove(&session_id);
+}
+
+/// Deliver the user's answer to the question the session is waiting on
+///
+/// Returns `false` when the session has no pending question (it was never
+/// asked, already answered, timed out or the task was cancelled).
+pub fn submit_answer(session_id: i64, answer: impl Into<String>) -> bool {
+    let Some(tx) = pending().remove(&session_id) else {
+        return false;
+    };
+    tx.send(answer.into()).is_ok()
+}
+
+/// Whether the session's agent is waiting for an answer
+#[must_use]
+pub fn is_awaiting_answer(session_id: i64) -> bool {
+    pending().get(&session_id).is_some_and(|tx| !tx.is_closed())
+}
+
+/// Receiving side of a pending question; unregisters itself when dropped
+struct PendingAnswer {
+    session_id: i64,
+    rx: oneshot::Receiver<String>,
+}
+
+impl PendingAnswer {
+    fn register(session_id: i64) -> Self {
+        let (tx, rx) = oneshot::channel();
+        pending().insert(session_id, tx);
+        waits().entry(session_id).or_default().since = Some(Instant::now());
+        Self { session_id, rx }
+    }
+}
+
+impl Drop for PendingAnswer {
+    fn drop(&mut self) {
+        self.rx.close();
+        if let Some(clock) = waits().get_mut(&self.session_id) {
+            if let Some(since) = clock.since.take() {
+                clock.total += since.elapsed();
+            }
+        }
+        let mut pending = pending();
+        // Only remove our own entry, never a newer question of the same session
+        if pending
+            .get(&self.session_id)
+            .is_some_and(oneshot::Sender::is_closed)
+        {
+            pending.remove(&self.session_id);
+        }
+    }
+}
+
+/// Arguments for `ask_user`
+#[derive(Debug, Deserialize)]
+struct AskUserArgs {
+    question: String,
+}
+
+/// Provider for the `ask_user` tool
+pub struct ClarificationProvider {
+    session_id: i64,
+    timeout: Duration,
+}
+
+impl ClarificationProvider {
+    /// Create a provider for a session; the wait time comes from `AGENT_CLARIFICATION_TIMEOUT_SECS`
+    #[must_use]
+    pub fn new(session_id: i64) -> Self {
+        Self {
+            session_id,
+            timeout: Duration::from_secs(crate::config::get_agent_clarification_timeout_secs()),
+        }
+    }
+
+    /// Override how long to wait for the answer
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn ask(
+        &self,
+        question: &str,
+        progress_tx: &tokio::sync::mpsc::Sender<AgentEvent>,
+    ) -> Result<String> {
+        let mut pending = PendingAnswer::register(self.session_id);
+        progress_tx
+            .send(AgentEvent::ClarificationNeeded {
+                question: question.to_string(),
+            })
+            .await
+            .map_err(|_| {
+                ToolError::new(
+                    ToolErrorKind::Unavailable,
+                    "ask_user: progress channel closed",
+                )
+            })?;
+
+        info!(
+            session_id = self.session_id,
+            "Waiting for user clarification"
+        );
+        match tokio::time::timeout(self.timeout, &mut pending.rx).await {
+            Ok(Ok(answer)) => Ok(format!("User answered: {answer}")),
+            Ok(Err(_)) => Err(ToolError::new(
+                ToolErrorKind::Unavailable,
+                "ask_user: the question was withdrawn before the user answered",
+            )
+            .into()),
+            Err(_) => Err(ToolError::new(
+                ToolErrorKind::Timeout,
+                format!(
+                    "ask_user: the user did not answer within {} seconds",
+                    self.timeout.as_secs()
+                ),
+            )
+            .into()),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolProvider for ClarificationProvider {
+    fn name(&self) -> &'static str {
+        "clarification"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![ToolDefinition {
+            name: ASK_USER_TOOL.to_string(),
+            description: "Ask the user a clarifying question and wait for the answer. \
+                Use it only when the task is ambiguous and a wrong guess would waste \
+                significant work; ask one short, specific question."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "question": {
+                        "type": "string",
+                        "description": "Question to show to the user"
+                    }
+                },
+                "required": ["question"]
+            }),
+        }]
+    }
+
+    fn can_handle(&self, tool_name: &str) -> bool {
+        tool_name == ASK_USER_TOOL
+    }
+
+    async fn execute(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        progress_tx: Option<&tokio::sync::mpsc::Sender<AgentEvent>>,
+        _cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        debug!(tool = tool_name, "Executing clarification tool");
+
+        if tool_name != ASK_USER_TOOL {
+            anyhow::bail!("Unknown clarification tool: {tool_name}");
+        }
+
+        let args: AskUserArgs = serde_json::from_str(arguments)?;
+        let question = sanitize_xml_tags(args.question.trim());
+        if question.is_empty() {
+            return Err(ToolError::new(
+                ToolErrorKind::InvalidArguments,
+                "ask_user requires a non-empty question",
+            )
+            .into());
+        }
+
+        let Some(tx) = progress_tx else {
+            return Err(ToolError::new(
+                ToolErrorKind::Unavailable,
+                "ask_user is not available: no user is attached to this task",
+            )
+            .into());
+        };
+        self.ask(&question, tx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_answer_is_returned_as_tool_result() -> Result<()> {
+        let session_id = -9_000_001;
+        let provider = ClarificationProvider::new(session_id);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+
+        let answering = tokio::spawn(async move {
+            let Some(AgentEvent::ClarificationNeeded { question }) = rx.recv().await else {
+                panic!("expected a clarification event");
+            };
+            assert!(is_awaiting_answer(session_id));
+            assert!(submit_answer(session_id, "CSV, please"));
+            question
+        });
+
+        let output = provider
+            .execute(
+                ASK_USER_TOOL,
+                r#"{"question": "CSV or JSON?"}"#,
+                Some(&tx),
+                None,
+            )
+            .await?;
+        assert_eq!(answering.await?, "CSV or JSON?");
+        assert_eq!(output, "User answered: CSV, please");
+        assert!(!is_awaiting_answer(session_id));
+        assert!(!submit_answer(session_id, "late"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_question_times_out() {
+        let session_id = -9_000_002;
+        let provider =
+            ClarificationProvider::new(session_id).with_timeout(Duration::from_millis(20));
+        let (tx, _rx) = tokio::sync::mpsc::channel(4);
+
+        let result = provider
+            .execute(
+                ASK_USER_TOOL,
+                r#"{"question": "Which year?"}"#,
+                Some(&tx),
+                None,
+            )
+            .await;
+        match result {
+            Err(e) => assert_eq!(ToolError::classify(&e).kind, ToolErrorKind::Timeout),
+            Ok(output) => panic!("expected a timeout, got {output}"),
+        }
+        assert!(!is_awaiting_answer(session_id));
+    }
+
+    #[tokio::test]
+    async fn test_wait_time_is_recorded_until_reset() {
+        let session_id = -9_000_003;
+        reset_time_waited(session_id);
+        let provider =
+            ClarificationProvider::new(session_id).with_timeout(Duration::from_millis(30));
+        let (tx, _rx) = tokio::sync::mpsc::channel(4);
+
+        let _ = provider
+            .execute(
+                ASK_USER_TOOL,
+                r#"{"question": "Which year?"}"#,
+                Some(&tx),
+                None,
+            )
+            .await;
+        let waited = time_waited(session_id);
+        assert!(waited >= Duration::from_millis(30));
+        // A finished wait no longer grows
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(time_waited(session_id), waited);
+
+        reset_time_waited(session_id);
+        assert_eq!(time_waited(session_id), Duration::ZERO);
+    }
+}
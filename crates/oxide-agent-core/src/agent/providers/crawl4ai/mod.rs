@@ -3,31 +3,63 @@
 //! Provides `deep_crawl`, `web_markdown`, and `web_pdf` tools via a Crawl4AI sidecar.
 
 mod response;
+mod robots;
 
 #[cfg(test)]
 mod tests;
 
 use crate::agent::provider::ToolProvider;
-use crate::config::get_crawl4ai_timeout;
+use crate::config::{
+    get_crawl4ai_crawl_delay_ms, get_crawl4ai_max_retries, get_crawl4ai_pdf_max_bytes,
+    get_crawl4ai_respect_robots, get_crawl4ai_timeout, ToolOutputLimits,
+};
 use crate::llm::ToolDefinition;
+use crate::sandbox::SandboxHandle;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use reqwest::header::CONTENT_TYPE;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
-use tracing::debug;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
 
 use response::{
     build_crawl_body, format_crawl_output, format_http_error, format_markdown_output,
-    format_pdf_output, is_json_response, is_pdf_response, ResponsePayload,
+    format_pdf_bytes, format_pdf_json, is_json_response, is_pdf_response, pdf_bytes_from_json,
+    pdf_file_name, truncate_output, ResponsePayload, MAX_PDF_BYTES, PDF_SANDBOX_DIR,
 };
+use robots::{is_path_allowed, ROBOTS_USER_AGENT};
+
+/// Timeout for fetching robots.txt files
+const ROBOTS_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Initial backoff between Crawl4AI retries (doubled on each attempt)
+const RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(1000);
+
+/// Failure of a single Crawl4AI request attempt
+enum AttemptError {
+    /// 5xx response or connection failure; worth retrying
+    Transient(String),
+    /// 4xx response or unreadable body; retrying won't help
+    Fatal(String),
+}
 
 /// Provider for Crawl4AI tools.
 pub struct Crawl4aiProvider {
     base_url: String,
     client: reqwest::Client,
     timeout: Duration,
+    max_retries: usize,
+    retry_backoff: Duration,
+    pdf_max_bytes: usize,
+    max_output_chars: usize,
+    sandbox: Option<SandboxHandle>,
+    respect_robots: bool,
+    crawl_delay_ms: u64,
+    /// robots.txt bodies per origin (`None` when unavailable)
+    robots_cache: Arc<Mutex<HashMap<String, Option<String>>>>,
 }
 
 impl Crawl4aiProvider {
@@ -48,29 +80,94 @@ impl Crawl4aiProvider {
             base_url: base_url.trim_end_matches('/').to_string(),
             client,
             timeout,
+            max_retries: get_crawl4ai_max_retries(),
+            retry_backoff: RETRY_INITIAL_BACKOFF,
+            pdf_max_bytes: get_crawl4ai_pdf_max_bytes(),
+            max_output_chars: ToolOutputLimits::default().crawl4ai_output_chars,
+            sandbox: None,
+            respect_robots: get_crawl4ai_respect_robots(),
+            crawl_delay_ms: get_crawl4ai_crawl_delay_ms(),
+            robots_cache: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Set the maximum size of text returned to the model
+    #[must_use]
+    pub const fn with_output_limits(mut self, limits: ToolOutputLimits) -> Self {
+        self.max_output_chars = limits.crawl4ai_output_chars;
+        self
+    }
+
+    /// Allow `web_pdf` to store large PDFs in the user's sandbox (lazily created).
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: SandboxHandle) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
     fn endpoint_url(&self, path: &str) -> String {
         format!("{}/{}", self.base_url, path.trim_start_matches('/'))
     }
 
+    /// POST to Crawl4AI, retrying transient failures with exponential backoff.
+    ///
+    /// All Crawl4AI endpoints used here are read-only, so retries are safe.
     async fn post(&self, path: &str, body: Value) -> Result<ResponsePayload> {
         let url = self.endpoint_url(path);
-        debug!(url = %url, timeout_secs = self.timeout.as_secs(), "Crawl4AI request");
 
-        let response = self
-            .client
-            .post(url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Crawl4AI request failed: {e}"))?;
+        for attempt in 0..=self.max_retries {
+            debug!(
+                url = %url,
+                timeout_secs = self.timeout.as_secs(),
+                attempt = attempt + 1,
+                "Crawl4AI request"
+            );
+
+            match self.post_once(&url, &body).await {
+                Ok(payload) => return Ok(payload),
+                Err(AttemptError::Transient(message)) if attempt < self.max_retries => {
+                    let backoff = self.retry_backoff * 2u32.pow(attempt.min(16) as u32);
+                    warn!(
+                        url = %url,
+                        attempt = attempt + 1,
+                        max_attempts = self.max_retries + 1,
+                        backoff_ms = backoff.as_millis(),
+                        error = %message,
+                        "Retrying Crawl4AI request"
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(AttemptError::Transient(message) | AttemptError::Fatal(message)) => {
+                    return Err(anyhow!(message));
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "Crawl4AI request failed: all retry attempts exhausted"
+        ))
+    }
+
+    async fn post_once(&self, url: &str, body: &Value) -> Result<ResponsePayload, AttemptError> {
+        let response = self.client.post(url).json(body).send().await.map_err(|e| {
+            let message = format!("Crawl4AI request failed: {e}");
+            // A timed-out crawl would likely time out again; don't multiply the wait
+            if e.is_timeout() {
+                AttemptError::Fatal(message)
+            } else {
+                AttemptError::Transient(message)
+            }
+        })?;
 
         let status = response.status();
         if !status.is_success() {
             let text = response.text().await.unwrap_or_default();
-            return Err(anyhow!(format_http_error(status, &text)));
+            let message = format_http_error(status, &text);
+            return Err(if status.is_server_error() {
+                AttemptError::Transient(message)
+            } else {
+                AttemptError::Fatal(message)
+            });
         }
 
         let content_type = response
@@ -83,7 +180,7 @@ impl Crawl4aiProvider {
         let bytes = response
             .bytes()
             .await
-            .map_err(|e| anyhow!("Crawl4AI response read failed: {e}"))?;
+            .map_err(|e| AttemptError::Fatal(format!("Crawl4AI response read failed: {e}")))?;
 
         if is_pdf_response(&content_type, bytes.as_ref()) {
             return Ok(ResponsePayload::Pdf(bytes.to_vec()));
@@ -118,6 +215,158 @@ struct WebMarkdownArgs {
 #[derive(Debug, Deserialize)]
 struct WebPdfArgs {
     url: String,
+    #[serde(default)]
+    save_to_sandbox: bool,
+}
+
+impl Crawl4aiProvider {
+    async fn handle_deep_crawl(&self, arguments: &str) -> Result<String> {
+        let args: DeepCrawlArgs = serde_json::from_str(arguments)?;
+        if args.urls.is_empty() {
+            return Err(anyhow!("deep_crawl requires at least one URL"));
+        }
+
+        let (urls, skipped) = if self.respect_robots {
+            self.filter_by_robots(args.urls).await
+        } else {
+            (args.urls, Vec::new())
+        };
+
+        let notes: String = skipped
+            .iter()
+            .map(|url| format!("Skipped (disallowed by robots.txt): {url}\n"))
+            .collect();
+        if urls.is_empty() {
+            return Ok(format!("{notes}No URLs left to crawl."));
+        }
+
+        let body = build_crawl_body(
+            urls,
+            args.max_depth,
+            self.respect_robots,
+            self.crawl_delay_ms,
+        );
+        let payload = self.post("/crawl", body).await?;
+        let output = format_crawl_output(payload, self.max_output_chars);
+
+        if notes.is_empty() {
+            Ok(output)
+        } else {
+            Ok(format!("{notes}\n{output}"))
+        }
+    }
+
+    /// Split URLs into crawlable ones and ones disallowed by their robots.txt.
+    async fn filter_by_robots(&self, urls: Vec<String>) -> (Vec<String>, Vec<String>) {
+        let mut allowed = Vec::new();
+        let mut skipped = Vec::new();
+
+        for url in urls {
+            // Unparseable URLs are passed through so Crawl4AI reports the error
+            let Ok(parsed) = reqwest::Url::parse(&url) else {
+                allowed.push(url);
+                continue;
+            };
+
+            let origin = parsed.origin().ascii_serialization();
+            let mut path = parsed.path().to_string();
+            if let Some(query) = parsed.query() {
+                path.push('?');
+                path.push_str(query);
+            }
+
+            match self.robots_txt(&origin).await {
+                Some(robots) if !is_path_allowed(&robots, ROBOTS_USER_AGENT, &path) => {
+                    skipped.push(url);
+                }
+                _ => allowed.push(url),
+            }
+        }
+
+        (allowed, skipped)
+    }
+
+    /// Fetch robots.txt for an origin, caching the result for the provider lifetime.
+    async fn robots_txt(&self, origin: &str) -> Option<String> {
+        if let Some(cached) = self.robots_cache.lock().await.get(origin) {
+            return cached.clone();
+        }
+
+        let url = format!("{origin}/robots.txt");
+        let robots = match self
+            .client
+            .get(&url)
+            .timeout(ROBOTS_FETCH_TIMEOUT)
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => response.text().await.ok(),
+            Ok(response) => {
+                debug!(url = %url, status = %response.status(), "robots.txt not available");
+                None
+            }
+            Err(e) => {
+                debug!(url = %url, error = %e, "robots.txt fetch failed");
+                None
+            }
+        };
+
+        self.robots_cache
+            .lock()
+            .await
+            .insert(origin.to_string(), robots.clone());
+        robots
+    }
+
+    async fn handle_web_pdf(&self, arguments: &str) -> Result<String> {
+        let args: WebPdfArgs = serde_json::from_str(arguments)?;
+        if args.url.trim().is_empty() {
+            return Err(anyhow!("web_pdf requires a URL"));
+        }
+
+        let body = json!({ "url": args.url });
+        let bytes = match self.post("/pdf", body).await? {
+            ResponsePayload::Pdf(bytes) => bytes,
+            ResponsePayload::Json(value) => match pdf_bytes_from_json(&value) {
+                Some(bytes) => bytes,
+                None => return Ok(format_pdf_json(&value, self.max_output_chars)),
+            },
+            ResponsePayload::Text(text) => return Ok(truncate_output(text, self.max_output_chars)),
+        };
+
+        if bytes.len() > MAX_PDF_BYTES {
+            return Err(anyhow!(
+                "PDF response too large: {} bytes (limit {} bytes)",
+                bytes.len(),
+                MAX_PDF_BYTES
+            ));
+        }
+
+        if !args.save_to_sandbox && bytes.len() <= self.pdf_max_bytes {
+            return Ok(format_pdf_bytes(&bytes));
+        }
+
+        self.save_pdf_to_sandbox(&args.url, &bytes).await
+    }
+
+    async fn save_pdf_to_sandbox(&self, url: &str, bytes: &[u8]) -> Result<String> {
+        let Some(handle) = &self.sandbox else {
+            return Err(anyhow!(
+                "PDF is {} bytes, above the inline limit of {} bytes, and no sandbox is available to store it",
+                bytes.len(),
+                self.pdf_max_bytes
+            ));
+        };
+
+        let sandbox = handle.get().await?;
+        let path = format!("{PDF_SANDBOX_DIR}/{}", pdf_file_name(url));
+        sandbox.upload_file(&path, bytes).await?;
+
+        Ok(format!(
+            "PDF saved to sandbox: {path} ({} bytes). Use send_file_to_user to deliver it.",
+            bytes.len()
+        ))
+    }
 }
 
 #[async_trait]
@@ -163,13 +412,17 @@ impl ToolProvider for Crawl4aiProvider {
             },
             ToolDefinition {
                 name: "web_pdf".to_string(),
-                description: "Export webpage to PDF. Returns base64 PDF when possible.".to_string(),
+                description: "Export webpage to PDF. Small PDFs are returned as base64; large ones (or with save_to_sandbox) are saved under /workspace/pdf and the path is returned for send_file_to_user.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
                         "url": {
                             "type": "string",
                             "description": "URL to export"
+                        },
+                        "save_to_sandbox": {
+                            "type": "boolean",
+                            "description": "Always save the PDF to the sandbox instead of returning base64"
                         }
                     },
                     "required": ["url"]
@@ -196,16 +449,7 @@ impl ToolProvider for Crawl4aiProvider {
         }
 
         match tool_name {
-            "deep_crawl" => {
-                let args: DeepCrawlArgs = serde_json::from_str(arguments)?;
-                if args.urls.is_empty() {
-                    return Err(anyhow!("deep_crawl requires at least one URL"));
-                }
-
-                let body = build_crawl_body(args.urls, args.max_depth);
-                let payload = self.post("/crawl", body).await?;
-                Ok(format_crawl_output(payload))
-            }
+            "deep_crawl" => self.handle_deep_crawl(arguments).await,
             "web_markdown" => {
                 let args: WebMarkdownArgs = serde_json::from_str(arguments)?;
                 if args.url.trim().is_empty() {
@@ -217,18 +461,9 @@ impl ToolProvider for Crawl4aiProvider {
                     "f": "fit"
                 });
                 let payload = self.post("/md", body).await?;
-                Ok(format_markdown_output(payload))
-            }
-            "web_pdf" => {
-                let args: WebPdfArgs = serde_json::from_str(arguments)?;
-                if args.url.trim().is_empty() {
-                    return Err(anyhow!("web_pdf requires a URL"));
-                }
-
-                let body = json!({ "url": args.url });
-                let payload = self.post("/pdf", body).await?;
-                format_pdf_output(payload)
+                Ok(format_markdown_output(payload, self.max_output_chars))
             }
+            "web_pdf" => self.handle_web_pdf(arguments).await,
             _ => Err(anyhow!("Unknown Crawl4AI tool: {tool_name}")),
         }
     }
@@ -1,10 +1,11 @@
-use anyhow::{anyhow, Result};
 use base64::Engine as _;
 use reqwest::StatusCode;
 use serde_json::{json, Map, Value};
 
-const MAX_PDF_BYTES: usize = 8 * 1024 * 1024;
-const MAX_OUTPUT_CHARS: usize = 20_000;
+/// Hard limit for PDF responses, inline or saved to the sandbox
+pub(super) const MAX_PDF_BYTES: usize = 8 * 1024 * 1024;
+/// Sandbox directory for PDFs that are too large to return inline
+pub(super) const PDF_SANDBOX_DIR: &str = "/workspace/pdf";
 
 pub(super) enum ResponsePayload {
     Json(Value),
@@ -12,10 +13,25 @@ pub(super) enum ResponsePayload {
     Pdf(Vec<u8>),
 }
 
-pub(super) fn build_crawl_body(urls: Vec<String>, max_depth: Option<u8>) -> Value {
+pub(super) fn build_crawl_body(
+    urls: Vec<String>,
+    max_depth: Option<u8>,
+    respect_robots: bool,
+    crawl_delay_ms: u64,
+) -> Value {
     let mut config_params = Map::new();
     config_params.insert("cache_mode".to_string(), json!("bypass"));
 
+    // Pages discovered during a deep crawl are checked by Crawl4AI itself
+    if respect_robots {
+        config_params.insert("check_robots_txt".to_string(), json!(true));
+    }
+    if crawl_delay_ms > 0 {
+        let delay_secs = std::time::Duration::from_millis(crawl_delay_ms).as_secs_f64();
+        config_params.insert("mean_delay".to_string(), json!(delay_secs));
+        config_params.insert("max_range".to_string(), json!(0));
+    }
+
     if let Some(depth) = max_depth {
         let mut strategy_params = Map::new();
         strategy_params.insert("max_depth".to_string(), json!(depth));
@@ -74,67 +90,84 @@ pub(super) fn format_http_error(status: StatusCode, body: &str) -> String {
     }
 }
 
-pub(super) fn format_crawl_output(payload: ResponsePayload) -> String {
+pub(super) fn format_crawl_output(payload: ResponsePayload, max_chars: usize) -> String {
     match payload {
-        ResponsePayload::Json(value) => format_crawl_results(&value),
-        ResponsePayload::Text(text) => truncate_output(text),
+        ResponsePayload::Json(value) => format_crawl_results(&value, max_chars),
+        ResponsePayload::Text(text) => truncate_output(text, max_chars),
         ResponsePayload::Pdf(_) => "Crawl response unexpectedly returned PDF bytes.".to_string(),
     }
 }
 
-pub(super) fn format_markdown_output(payload: ResponsePayload) -> String {
+pub(super) fn format_markdown_output(payload: ResponsePayload, max_chars: usize) -> String {
     match payload {
         ResponsePayload::Json(value) => extract_markdown(&value).unwrap_or_else(|| {
             let formatted = format_json(&value);
-            truncate_output(formatted)
+            truncate_output(formatted, max_chars)
         }),
-        ResponsePayload::Text(text) => truncate_output(text),
+        ResponsePayload::Text(text) => truncate_output(text, max_chars),
         ResponsePayload::Pdf(_) => "Markdown response unexpectedly returned PDF bytes.".to_string(),
     }
 }
 
-pub(super) fn format_pdf_output(payload: ResponsePayload) -> Result<String> {
-    match payload {
-        ResponsePayload::Pdf(bytes) => format_pdf_bytes(&bytes),
-        ResponsePayload::Json(value) => Ok(format_pdf_json(&value)),
-        ResponsePayload::Text(text) => Ok(truncate_output(text)),
-    }
+pub(super) fn format_pdf_bytes(bytes: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    format!("PDF (base64, {} bytes):\n{}", bytes.len(), encoded)
 }
 
-fn format_pdf_bytes(bytes: &[u8]) -> Result<String> {
-    if bytes.len() > MAX_PDF_BYTES {
-        return Err(anyhow!(
-            "PDF response too large ({} bytes, limit {} bytes)",
-            bytes.len(),
-            MAX_PDF_BYTES
-        ));
-    }
-
-    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
-    Ok(format!("PDF (base64, {} bytes):\n{}", bytes.len(), encoded))
+/// Decode an inline base64 PDF from a JSON response, if present.
+pub(super) fn pdf_bytes_from_json(value: &Value) -> Option<Vec<u8>> {
+    ["pdf", "data"]
+        .iter()
+        .find_map(|key| value.get(*key).and_then(|v| v.as_str()))
+        .and_then(|data| {
+            base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .ok()
+        })
+        .filter(|bytes| bytes.starts_with(b"%PDF-"))
 }
 
-fn format_pdf_json(value: &Value) -> String {
+pub(super) fn format_pdf_json(value: &Value, max_chars: usize) -> String {
     if let Some(url) = value.get("url").and_then(|v| v.as_str()) {
         return format!("PDF URL: {url}");
     }
     if let Some(url) = value.get("download_url").and_then(|v| v.as_str()) {
         return format!("PDF URL: {url}");
     }
-    if let Some(data) = value.get("pdf").and_then(|v| v.as_str()) {
-        return truncate_output(format!("PDF (base64):\n{data}"));
-    }
-    if let Some(data) = value.get("data").and_then(|v| v.as_str()) {
-        return truncate_output(format!("PDF (base64):\n{data}"));
-    }
 
-    truncate_output(format_json(value))
+    truncate_output(format_json(value), max_chars)
 }
 
-fn format_crawl_results(value: &Value) -> String {
+/// Build a filesystem-safe PDF file name from the source URL.
+pub(super) fn pdf_file_name(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let without_query = without_scheme.split(['?', '#']).next().unwrap_or_default();
+
+    let sanitized: String = without_query
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(80)
+        .collect();
+    let stem = sanitized.trim_matches(|c| c == '_' || c == '.');
+    let stem = stem.strip_suffix(".pdf").unwrap_or(stem);
+
+    if stem.is_empty() {
+        "page.pdf".to_string()
+    } else {
+        format!("{stem}.pdf")
+    }
+}
+
+fn format_crawl_results(value: &Value, max_chars: usize) -> String {
     let results = match value.get("results").and_then(|v| v.as_array()) {
         Some(results) => results,
-        None => return truncate_output(format_json(value)),
+        None => return truncate_output(format_json(value), max_chars),
     };
 
     if results.is_empty() {
@@ -161,7 +194,7 @@ fn format_crawl_results(value: &Value) -> String {
         ));
     }
 
-    truncate_output(output)
+    truncate_output(output, max_chars)
 }
 
 fn extract_markdown(value: &Value) -> Option<String> {
@@ -185,12 +218,6 @@ fn format_json(value: &Value) -> String {
     serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
 }
 
-fn truncate_output(text: String) -> String {
-    if text.len() <= MAX_OUTPUT_CHARS {
-        return text;
-    }
-
-    let mut truncated = text.chars().take(MAX_OUTPUT_CHARS).collect::<String>();
-    truncated.push_str("\n\n... (truncated)");
-    truncated
+pub(super) fn truncate_output(text: String, max_chars: usize) -> String {
+    crate::utils::truncate_with_notice(text, max_chars)
 }
@@ -0,0 +1,107 @@
+//! Minimal robots.txt support for `deep_crawl`.
+//!
+//! Only `User-agent`, `Allow` and `Disallow` are interpreted. Rule matching
+//! follows RFC 9309: the longest matching pattern wins and `Allow` wins ties.
+
+/// Product token used to select a robots.txt group (falls back to `*`)
+pub(super) const ROBOTS_USER_AGENT: &str = "crawl4ai";
+
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+/// Check whether `path` (path + query) may be crawled according to `robots_txt`.
+pub(super) fn is_path_allowed(robots_txt: &str, user_agent: &str, path: &str) -> bool {
+    let rules = select_rules(robots_txt, user_agent);
+
+    let best = rules
+        .iter()
+        .filter(|rule| pattern_matches(&rule.pattern, path))
+        .max_by_key(|rule| (rule.pattern.len(), rule.allow));
+
+    best.is_none_or(|rule| rule.allow)
+}
+
+/// Collect the rules of the group matching `user_agent`, or of the `*` group.
+fn select_rules(robots_txt: &str, user_agent: &str) -> Vec<Rule> {
+    let user_agent = user_agent.to_ascii_lowercase();
+    let mut specific = Vec::new();
+    let mut wildcard = Vec::new();
+    let mut found_specific = false;
+
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+
+        match key.as_str() {
+            "user-agent" => {
+                if in_rules {
+                    group_agents.clear();
+                    in_rules = false;
+                }
+                group_agents.push(value.to_ascii_lowercase());
+            }
+            "allow" | "disallow" => {
+                in_rules = true;
+                // An empty Disallow means "allow everything" and adds no rule
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = || Rule {
+                    allow: key == "allow",
+                    pattern: value.to_string(),
+                };
+                if group_agents.contains(&user_agent) {
+                    found_specific = true;
+                    specific.push(rule());
+                }
+                if group_agents.iter().any(|agent| agent == "*") {
+                    wildcard.push(rule());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if found_specific {
+        specific
+    } else {
+        wildcard
+    }
+}
+
+/// Match a robots.txt path pattern supporting `*` wildcards and a `$` end anchor.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(stripped) => (stripped, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        let is_last = index + 1 == parts.len();
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
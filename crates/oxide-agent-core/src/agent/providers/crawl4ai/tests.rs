@@ -35,3 +35,187 @@ fn test_http_error_formatting() {
     assert!(msg.contains("400"));
     assert!(msg.contains("bad request"));
 }
+
+#[test]
+fn test_pdf_args_save_to_sandbox_default() {
+    let args: Result<WebPdfArgs, _> = serde_json::from_str(r#"{"url":"https://example.com"}"#);
+    assert!(args.is_ok_and(|args| !args.save_to_sandbox));
+}
+
+#[test]
+fn test_pdf_file_name() {
+    use super::response::pdf_file_name;
+
+    assert_eq!(
+        pdf_file_name("https://example.com/docs/page?x=1#top"),
+        "example.com_docs_page.pdf"
+    );
+    assert_eq!(
+        pdf_file_name("https://example.com/report.pdf"),
+        "example.com_report.pdf"
+    );
+    assert_eq!(pdf_file_name("https://"), "page.pdf");
+}
+
+#[test]
+fn test_crawl_body_politeness_options() {
+    use super::response::build_crawl_body;
+
+    let body = build_crawl_body(vec!["https://example.com".to_string()], None, false, 0);
+    let params = &body["crawler_config"]["params"];
+    assert!(params.get("check_robots_txt").is_none());
+    assert!(params.get("mean_delay").is_none());
+
+    let body = build_crawl_body(vec!["https://example.com".to_string()], None, true, 1500);
+    let params = &body["crawler_config"]["params"];
+    assert_eq!(params["check_robots_txt"], json!(true));
+    assert_eq!(params["mean_delay"], json!(1.5));
+}
+
+#[test]
+fn test_robots_rules() {
+    use super::robots::is_path_allowed;
+
+    let robots = "\
+User-agent: *
+Disallow: /private/
+Allow: /private/public
+Disallow: /*.json$
+
+User-agent: crawl4ai
+Disallow: /no-bots
+";
+    assert!(is_path_allowed(robots, "other", "/index.html"));
+    assert!(!is_path_allowed(robots, "other", "/private/data"));
+    assert!(is_path_allowed(robots, "other", "/private/public/page"));
+    assert!(!is_path_allowed(robots, "other", "/api/items.json"));
+    assert!(is_path_allowed(robots, "other", "/api/items.json?x=1"));
+
+    // A matching specific group replaces the wildcard group entirely
+    assert!(is_path_allowed(robots, "crawl4ai", "/private/data"));
+    assert!(!is_path_allowed(robots, "crawl4ai", "/no-bots/page"));
+
+    assert!(is_path_allowed(
+        "User-agent: *\nDisallow:",
+        "other",
+        "/anything"
+    ));
+}
+
+/// Serve canned HTTP responses (one per connection) and count the requests.
+async fn spawn_mock_server(
+    responses: Vec<(&'static str, &'static str)>,
+) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = match tokio::net::TcpListener::bind("127.0.0.1:0").await {
+        Ok(listener) => listener,
+        Err(e) => panic!("failed to bind mock server: {e}"),
+    };
+    let addr = match listener.local_addr() {
+        Ok(addr) => addr,
+        Err(e) => panic!("failed to read mock server address: {e}"),
+    };
+    let hits = std::sync::Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        for (status, body) in responses {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            counter.fetch_add(1, Ordering::SeqCst);
+
+            // Read the request headers and body before answering
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stream.read(&mut buf).await {
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())
+                                .flatten()
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+
+    (format!("http://{addr}"), hits)
+}
+
+#[tokio::test]
+async fn test_post_retries_transient_errors() {
+    use std::sync::atomic::Ordering;
+
+    let (url, hits) = spawn_mock_server(vec![
+        ("503 Service Unavailable", "{}"),
+        ("502 Bad Gateway", "{}"),
+        ("200 OK", r#"{"markdown":"hello"}"#),
+    ])
+    .await;
+
+    let mut provider = Crawl4aiProvider::with_timeout(&url, Duration::from_secs(5));
+    provider.max_retries = 2;
+    provider.retry_backoff = Duration::from_millis(1);
+
+    let result = provider
+        .execute(
+            "web_markdown",
+            r#"{"url":"https://example.com"}"#,
+            None,
+            None,
+        )
+        .await;
+    assert!(matches!(result.as_deref(), Ok("hello")), "{result:?}");
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_post_does_not_retry_client_errors() {
+    use std::sync::atomic::Ordering;
+
+    let (url, hits) = spawn_mock_server(vec![
+        ("400 Bad Request", r#"{"detail":"bad url"}"#),
+        ("200 OK", r#"{"markdown":"hello"}"#),
+    ])
+    .await;
+
+    let mut provider = Crawl4aiProvider::with_timeout(&url, Duration::from_secs(5));
+    provider.max_retries = 2;
+    provider.retry_backoff = Duration::from_millis(1);
+
+    let result = provider
+        .execute(
+            "web_markdown",
+            r#"{"url":"https://example.com"}"#,
+            None,
+            None,
+        )
+        .await;
+    match result {
+        Ok(output) => panic!("expected an error, got {output}"),
+        Err(e) => assert!(e.to_string().contains("400")),
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
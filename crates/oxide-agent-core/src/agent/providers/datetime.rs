@@ -0,0 +1,175 @@
+//! Date/time provider - current time without the sandbox
+//!
+//! The system prompt only carries the date the task started at. `get_datetime`
+//! lets the agent check the clock mid-task or convert to another timezone
+//! without shelling out to `date`, so it works even when the sandbox is down.
+
+use crate::agent::progress::AgentEvent;
+use crate::agent::provider::ToolProvider;
+use crate::agent::tool_error::{ToolError, ToolErrorKind};
+use crate::llm::ToolDefinition;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt::Display;
+use tracing::debug;
+
+/// Name of the date/time tool
+pub const GET_DATETIME_TOOL: &str = "get_datetime";
+
+/// Arguments for `get_datetime`
+#[derive(Debug, Default, Deserialize)]
+struct GetDateTimeArgs {
+    #[serde(default)]
+    timezone: Option<String>,
+}
+
+/// Provider for the `get_datetime` tool
+#[derive(Debug, Default)]
+pub struct DateTimeProvider;
+
+impl DateTimeProvider {
+    /// Create a new `DateTimeProvider`
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+/// Parse an IANA timezone name such as `Europe/Moscow`
+fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>().map_err(|_| {
+        ToolError::new(
+            ToolErrorKind::InvalidArguments,
+            format!(
+                "Unknown timezone '{name}': use an IANA name such as \
+                 Europe/Moscow, America/New_York or UTC"
+            ),
+        )
+        .into()
+    })
+}
+
+/// ISO-8601, human-readable, UTC and Unix forms of `now`
+fn format_datetime<Z: TimeZone>(now: &DateTime<Z>, zone: &str) -> String
+where
+    Z::Offset: Display,
+{
+    format!(
+        "Current date and time ({zone}):\n\
+         - ISO 8601: {}\n\
+         - Human: {}\n\
+         - UTC: {}\n\
+         - Unix timestamp: {}",
+        now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        now.format("%A, %-d %B %Y, %H:%M:%S %Z (UTC%:z)"),
+        now.with_timezone(&Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        now.timestamp()
+    )
+}
+
+#[async_trait]
+impl ToolProvider for DateTimeProvider {
+    fn name(&self) -> &'static str {
+        "datetime"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![ToolDefinition {
+            name: GET_DATETIME_TOOL.to_string(),
+            description: "Get the current date and time, optionally in another timezone. \
+                Use it instead of `date` in the sandbox whenever the exact current time \
+                matters or a time must be converted between timezones."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "timezone": {
+                        "type": "string",
+                        "description": "IANA timezone name, e.g. Europe/Moscow or America/New_York (default: AGENT_TIMEZONE or server time)"
+                    }
+                }
+            }),
+        }]
+    }
+
+    fn can_handle(&self, tool_name: &str) -> bool {
+        tool_name == GET_DATETIME_TOOL
+    }
+
+    async fn execute(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        _progress_tx: Option<&tokio::sync::mpsc::Sender<AgentEvent>>,
+        _cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        debug!(tool = tool_name, "Executing date/time tool");
+
+        if tool_name != GET_DATETIME_TOOL {
+            anyhow::bail!("Unknown date/time tool: {tool_name}");
+        }
+
+        let args: GetDateTimeArgs = if arguments.trim().is_empty() {
+            GetDateTimeArgs::default()
+        } else {
+            serde_json::from_str(arguments)?
+        };
+        match args.timezone.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => {
+                let tz = parse_timezone(name)?;
+                Ok(format_datetime(&Utc::now().with_timezone(&tz), tz.name()))
+            }
+            _ => match crate::config::get_agent_timezone() {
+                Some(tz) => Ok(format_datetime(&Utc::now().with_timezone(&tz), tz.name())),
+                None => Ok(format_datetime(&Local::now(), "server time")),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_time_in_requested_zone() {
+        let Ok(tz) = parse_timezone("Europe/Berlin") else {
+            panic!("Europe/Berlin should parse");
+        };
+        let Some(utc) = Utc.with_ymd_and_hms(2026, 10, 16, 12, 3, 7).single() else {
+            panic!("valid date");
+        };
+        let output = format_datetime(&utc.with_timezone(&tz), tz.name());
+
+        assert!(output.starts_with("Current date and time (Europe/Berlin):"));
+        assert!(output.contains("- ISO 8601: 2026-10-16T14:03:07+02:00"));
+        assert!(output.contains("- Human: Friday, 16 October 2026, 14:03:07 CEST (UTC+02:00)"));
+        assert!(output.contains("- UTC: 2026-10-16T12:03:07Z"));
+        assert!(output.contains(&format!("- Unix timestamp: {}", utc.timestamp())));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_timezone_is_rejected() {
+        let provider = DateTimeProvider::new();
+        let result = provider
+            .execute(
+                GET_DATETIME_TOOL,
+                r#"{"timezone": "Mars/Olympus"}"#,
+                None,
+                None,
+            )
+            .await;
+        let Err(e) = result else {
+            panic!("unknown timezone should fail");
+        };
+        assert!(e.to_string().contains("IANA"));
+
+        let output = provider.execute(GET_DATETIME_TOOL, "{}", None, None).await;
+        assert!(output.is_ok_and(|o| o.starts_with("Current date and time (")));
+    }
+}
@@ -3,6 +3,7 @@
 //! Exposes `delegate_to_sub_agent` tool that runs an isolated agent loop
 //! with a lightweight model and restricted toolset.
 
+use crate::agent::compression::OutputCompressor;
 use crate::agent::context::{AgentContext, EphemeralSession};
 use crate::agent::hooks::{
     CompletionCheckHook, SearchBudgetHook, SubAgentSafetyConfig, SubAgentSafetyHook,
@@ -12,31 +13,39 @@ use crate::agent::memory::{AgentMemory, AgentMessage, MessageRole};
 use crate::agent::progress::AgentEvent;
 use crate::agent::prompt::create_sub_agent_system_prompt;
 use crate::agent::provider::ToolProvider;
-use crate::agent::providers::{FileHosterProvider, SandboxProvider, TodosProvider, YtdlpProvider};
+use crate::agent::providers::{
+    DateTimeProvider, FileHosterProvider, FinishTaskProvider, SandboxProvider, TodosProvider,
+    YtdlpProvider,
+};
 use crate::agent::registry::ToolRegistry;
 use crate::agent::runner::{AgentRunner, AgentRunnerConfig, AgentRunnerContext};
+use crate::agent::tokenizer::tokenizer_for_model;
 use crate::config::{
-    get_agent_search_limit, AGENT_CONTINUATION_LIMIT, SUB_AGENT_MAX_ITERATIONS,
-    SUB_AGENT_MAX_TOKENS,
+    get_agent_continuation_limit, get_agent_search_limit, ToolOutputLimits,
+    SUB_AGENT_MAX_ITERATIONS, SUB_AGENT_MAX_TOKENS,
 };
 use crate::llm::ToolDefinition;
+use crate::sandbox::SandboxHandle;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 use tokio::time::{timeout, Duration};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+#[cfg(feature = "brave")]
+use crate::agent::providers::BraveSearchProvider;
 #[cfg(feature = "crawl4ai")]
 use crate::agent::providers::Crawl4aiProvider;
 #[cfg(feature = "tavily")]
 use crate::agent::providers::TavilyProvider;
 
-const BLOCKED_SUB_AGENT_TOOLS: &[&str] = &["delegate_to_sub_agent", "send_file_to_user"];
+const BLOCKED_SUB_AGENT_TOOLS: &[&str] =
+    &["delegate_to_sub_agent", "send_file_to_user", "zip_and_send"];
 const SUB_AGENT_REPORT_MAX_MESSAGES: usize = 6;
 const SUB_AGENT_REPORT_MAX_CHARS: usize = 800;
 
@@ -44,7 +53,10 @@ const SUB_AGENT_REPORT_MAX_CHARS: usize = 800;
 pub struct DelegationProvider {
     llm_client: Arc<crate::llm::LlmClient>,
     user_id: i64,
+    sandbox: SandboxHandle,
     settings: Arc<crate::config::AgentSettings>,
+    allowlist: Option<HashSet<String>>,
+    tool_permits: Option<Arc<Semaphore>>,
 }
 
 impl DelegationProvider {
@@ -58,10 +70,34 @@ impl DelegationProvider {
         Self {
             llm_client,
             user_id,
+            sandbox: SandboxHandle::new(user_id),
             settings,
+            allowlist: crate::config::get_tool_allowlist(user_id),
+            tool_permits: None,
         }
     }
 
+    /// Limit sub-agents to the tools the parent's requester may use
+    #[must_use]
+    pub fn with_allowlist(mut self, allowlist: Option<HashSet<String>>) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Run sub-agent tool calls on the parent's tool permits
+    #[must_use]
+    pub fn with_tool_permits(mut self, tool_permits: Arc<Semaphore>) -> Self {
+        self.tool_permits = Some(tool_permits);
+        self
+    }
+
+    /// Run sub-agents in the parent's sandbox
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: SandboxHandle) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
     fn blocked_tool_set() -> HashSet<String> {
         BLOCKED_SUB_AGENT_TOOLS
             .iter()
@@ -74,21 +110,30 @@ impl DelegationProvider {
         todos_arc: Arc<Mutex<crate::agent::providers::TodoList>>,
         progress_tx: Option<&tokio::sync::mpsc::Sender<AgentEvent>>,
     ) -> Vec<Box<dyn ToolProvider>> {
+        let sandbox_provider =
+            SandboxProvider::new(self.user_id).with_sandbox(self.sandbox.clone());
         let sandbox_provider = if let Some(tx) = progress_tx {
-            SandboxProvider::new(self.user_id).with_progress_tx(tx.clone())
+            sandbox_provider.with_progress_tx(tx.clone())
         } else {
-            SandboxProvider::new(self.user_id)
+            sandbox_provider
         };
+        let (_, _, max_tokens) = self.settings.get_configured_sub_agent_model();
+        let output_limits = ToolOutputLimits::for_max_tokens(max_tokens);
+        let ytdlp_provider = YtdlpProvider::new(self.user_id)
+            .with_sandbox(self.sandbox.clone())
+            .with_output_limits(output_limits);
         let ytdlp_provider = if let Some(tx) = progress_tx {
-            YtdlpProvider::new(self.user_id).with_progress_tx(tx.clone())
+            ytdlp_provider.with_progress_tx(tx.clone())
         } else {
-            YtdlpProvider::new(self.user_id)
+            ytdlp_provider
         };
 
         let mut providers: Vec<Box<dyn ToolProvider>> = vec![
             Box::new(TodosProvider::new(todos_arc)),
+            Box::new(DateTimeProvider::new()),
+            Box::new(FinishTaskProvider::new()),
             Box::new(sandbox_provider),
-            Box::new(FileHosterProvider::new(self.user_id)),
+            Box::new(FileHosterProvider::new(self.user_id).with_sandbox(self.sandbox.clone())),
             Box::new(ytdlp_provider),
         ];
 
@@ -107,11 +152,27 @@ impl DelegationProvider {
                 #[cfg(not(feature = "tavily"))]
                 warn!("Tavily requested but feature not enabled");
             }
+            "brave" => {
+                #[cfg(feature = "brave")]
+                if let Ok(brave_key) = std::env::var("BRAVE_API_KEY") {
+                    if !brave_key.is_empty() {
+                        if let Ok(provider) = BraveSearchProvider::new(&brave_key) {
+                            providers.push(Box::new(provider));
+                        }
+                    }
+                }
+                #[cfg(not(feature = "brave"))]
+                warn!("Brave Search requested but feature not enabled");
+            }
             "crawl4ai" => {
                 #[cfg(feature = "crawl4ai")]
                 if let Ok(url) = std::env::var("CRAWL4AI_URL") {
                     if !url.is_empty() {
-                        providers.push(Box::new(Crawl4aiProvider::new(&url)));
+                        providers.push(Box::new(
+                            Crawl4aiProvider::new(&url)
+                                .with_sandbox(self.sandbox.clone())
+                                .with_output_limits(output_limits),
+                        ));
                     }
                 }
                 #[cfg(not(feature = "crawl4ai"))]
@@ -136,7 +197,13 @@ impl DelegationProvider {
                 Arc::clone(&allowed),
             )));
         }
+        // Sub-agents never get tools the parent's user is not permitted to use
         registry
+            .with_allowlist(self.allowlist.clone())
+            .with_output_compressor(
+                crate::config::get_compress_tool_output()
+                    .then(|| OutputCompressor::new(self.sandbox.clone())),
+            )
     }
 
     fn filter_allowed_tools(
@@ -170,6 +237,27 @@ impl DelegationProvider {
         Ok(allowed)
     }
 
+    /// Sub-session linked to the parent's cancellation token, counting tokens
+    /// with the sub-agent model's tokenizer within its context window
+    ///
+    /// When the parent is cancelled (including on loop detection), the sub-agent stops too.
+    fn create_sub_session(
+        &self,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> EphemeralSession {
+        let mut sub_session = match cancellation_token {
+            Some(parent_token) => {
+                EphemeralSession::with_parent_token(SUB_AGENT_MAX_TOKENS, parent_token)
+            }
+            None => EphemeralSession::new(SUB_AGENT_MAX_TOKENS),
+        };
+        let (model_id, _, max_tokens) = self.settings.get_configured_sub_agent_model();
+        let memory = sub_session.memory_mut();
+        memory.set_tokenizer(tokenizer_for_model(&model_id));
+        memory.set_context_window(self.settings.get_sub_agent_context_window(), max_tokens);
+        sub_session
+    }
+
     fn create_sub_agent_runner(&self, blocked: HashSet<String>) -> AgentRunner {
         let mut runner = AgentRunner::new(self.llm_client.clone());
         runner.register_hook(Box::new(CompletionCheckHook::new()));
@@ -251,17 +339,10 @@ If the sub-agent doesn't finish, a partial report will be returned."
 
         let task_id = format!("sub-{}", Uuid::new_v4());
 
-        // Create sub-session linked to parent's cancellation token.
-        // When parent is cancelled (including on loop detection), sub-agent stops too.
-        let mut sub_session = match cancellation_token {
-            Some(parent_token) => {
-                EphemeralSession::with_parent_token(SUB_AGENT_MAX_TOKENS, parent_token)
-            }
-            None => EphemeralSession::new(SUB_AGENT_MAX_TOKENS),
-        };
+        let mut sub_session = self.create_sub_session(cancellation_token);
         sub_session
             .memory_mut()
-            .add_message(AgentMessage::user(task.as_str()));
+            .add_pinned_message(AgentMessage::user(task.as_str()));
 
         let todos_arc = Arc::new(Mutex::new(sub_session.memory().todos.clone()));
         let providers = self.build_sub_agent_providers(Arc::clone(&todos_arc), progress_tx);
@@ -297,18 +378,23 @@ If the sub-agent doesn't finish, a partial report will be returned."
             progress_tx,
             todos_arc: &todos_arc,
             task_id: &task_id,
+            user_id: self.user_id,
             messages: &mut messages,
             agent: &mut sub_session,
             skill_registry: None,
             config: {
                 let (model_id, _, _) = self.settings.get_configured_sub_agent_model();
-                AgentRunnerConfig::new(
+                let config = AgentRunnerConfig::new(
                     model_id,
                     SUB_AGENT_MAX_ITERATIONS,
-                    AGENT_CONTINUATION_LIMIT,
+                    get_agent_continuation_limit(),
                     self.settings.get_sub_agent_timeout_secs(),
                 )
-                .with_sub_agent(true)
+                .with_sub_agent(true);
+                match &self.tool_permits {
+                    Some(permits) => config.with_tool_permits(Arc::clone(permits)),
+                    None => config,
+                }
             },
         };
 
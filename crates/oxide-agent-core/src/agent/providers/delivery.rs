@@ -0,0 +1,103 @@
+//! Idempotency for file deliveries to the user
+//!
+//! A delivery is keyed by the session and the SHA-256 of the file content.
+//! Once a file has been queued for sending, the same file is not sent again
+//! within `FILE_DELIVERY_DEDUP_SECS` unless the transport reported a definite
+//! failure. This covers confirmation timeouts and closed confirmation channels,
+//! where the file may well have reached the user.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+type DeliveryKey = (i64, [u8; 32]);
+
+/// When each (session, content hash) was last queued for delivery
+static DELIVERED: LazyLock<Mutex<HashMap<DeliveryKey, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn delivered() -> std::sync::MutexGuard<'static, HashMap<DeliveryKey, Instant>> {
+    DELIVERED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Claim on a delivery in progress; release it when delivery definitely failed
+pub(super) struct DeliveryClaim {
+    key: DeliveryKey,
+}
+
+impl DeliveryClaim {
+    /// Forget the delivery so a retry sends the file again
+    pub(super) fn release(self) {
+        delivered().remove(&self.key);
+    }
+}
+
+/// Claim the delivery of `content` for a session
+///
+/// Returns `None` when the same content was already queued for this session
+/// within the dedup window; the caller should report it as already delivered.
+pub(super) fn claim_delivery(session_id: i64, content: &[u8]) -> Option<DeliveryClaim> {
+    claim_delivery_within(
+        session_id,
+        content,
+        Duration::from_secs(crate::config::get_file_delivery_dedup_secs()),
+    )
+}
+
+fn claim_delivery_within(
+    session_id: i64,
+    content: &[u8],
+    window: Duration,
+) -> Option<DeliveryClaim> {
+    let key = (session_id, Sha256::digest(content).into());
+    if window.is_zero() {
+        return Some(DeliveryClaim { key });
+    }
+
+    let mut delivered = delivered();
+    delivered.retain(|_, queued_at| queued_at.elapsed() < window);
+    if delivered.contains_key(&key) {
+        return None;
+    }
+    delivered.insert(key, Instant::now());
+    Some(DeliveryClaim { key })
+}
+
+/// Tool output for a repeated delivery of the same file
+pub(super) fn already_delivered_message(file_name: &str) -> String {
+    format!(
+        "✅ File '{file_name}' was already sent to the user recently; not sending it again.\n\
+         If the user says they did not receive it, wait a few minutes before retrying."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_delivery_is_skipped_until_released() {
+        let window = Duration::from_secs(60);
+        let session_id = -7_000_001;
+
+        let claim = claim_delivery_within(session_id, b"report", window);
+        assert!(claim.is_some());
+        assert!(claim_delivery_within(session_id, b"report", window).is_none());
+        // Other content and other sessions are independent
+        assert!(claim_delivery_within(session_id, b"other", window).is_some());
+        assert!(claim_delivery_within(session_id - 1, b"report", window).is_some());
+
+        if let Some(claim) = claim {
+            claim.release();
+        }
+        assert!(claim_delivery_within(session_id, b"report", window).is_some());
+    }
+
+    #[test]
+    fn test_zero_window_disables_dedup() {
+        let session_id = -7_000_002;
+        assert!(claim_delivery_within(session_id, b"x", Duration::ZERO).is_some());
+        assert!(claim_delivery_within(session_id, b"x", Duration::ZERO).is_some());
+    }
+}
@@ -0,0 +1,243 @@
+//! Environment variables for `execute_command`
+//!
+//! Values come from the user (API keys, locale settings) through the model.
+//! `set_env` stores them per session; `execute_command` may add more for a
+//! single call. Names that change how the shell or the loader behaves are
+//! refused, as are values that contain one of the host's own secrets. Every
+//! value is registered for redaction under its session, since credentials do
+//! not always sit in variables named like one, so they are masked in logs and
+//! the audit log until the session ends.
+
+use crate::redaction::{contains_host_secret, forget_user_secrets, register_user_secret};
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+/// Environment variables as name → value, ordered for stable output
+pub(super) type EnvVars = BTreeMap<String, String>;
+
+/// Most variables a session may hold
+const MAX_ENV_VARS: usize = 32;
+/// Longest accepted value
+const MAX_ENV_VALUE_CHARS: usize = 4096;
+
+/// Names that would let a value hijack the shell, the dynamic loader or the
+/// process tree tagging of the sandbox
+const DENIED_ENV_NAMES: &[&str] = &[
+    "PATH",
+    "HOME",
+    "SHELL",
+    "ENV",
+    "BASH_ENV",
+    "IFS",
+    "PS4",
+    "PROMPT_COMMAND",
+    "SHELLOPTS",
+    "BASHOPTS",
+    "GLOBIGNORE",
+    "OXIDE_EXEC_ID",
+];
+/// Name prefixes refused for the same reason
+const DENIED_ENV_PREFIXES: &[&str] = &["LD_", "DYLD_", "BASH_FUNC_"];
+
+/// Variables set with `set_env`, keyed by session
+static SESSION_ENV: LazyLock<Mutex<HashMap<i64, EnvVars>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn session_env_table() -> std::sync::MutexGuard<'static, HashMap<i64, EnvVars>> {
+    SESSION_ENV.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Check a variable before it reaches the sandbox
+///
+/// # Errors
+///
+/// Returns an error naming the variable if it is not allowed.
+pub(super) fn validate_env_var(name: &str, value: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid_name = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        bail!("'{name}' is not a valid environment variable name");
+    }
+
+    let upper = name.to_ascii_uppercase();
+    if DENIED_ENV_NAMES.contains(&upper.as_str())
+        || DENIED_ENV_PREFIXES.iter().any(|p| upper.starts_with(p))
+    {
+        bail!("{name} cannot be set: it changes how commands are executed");
+    }
+    if value.contains('\0') {
+        bail!("{name} contains a NUL byte");
+    }
+    if value.chars().count() > MAX_ENV_VALUE_CHARS {
+        bail!("{name} is longer than {MAX_ENV_VALUE_CHARS} characters");
+    }
+    if contains_host_secret(value) {
+        bail!("{name} contains a credential of the bot itself and cannot be passed to the sandbox");
+    }
+    Ok(())
+}
+
+/// Validate all variables and register their values for redaction
+///
+/// # Errors
+///
+/// Returns an error for the first variable that is not allowed.
+pub(super) fn validate_env(session_id: i64, vars: &EnvVars) -> Result<()> {
+    for (name, value) in vars {
+        validate_env_var(name, value)?;
+    }
+    for value in vars.values() {
+        register_user_secret(session_id, value);
+    }
+    Ok(())
+}
+
+/// Variables stored for the session
+pub(super) fn session_env(session_id: i64) -> EnvVars {
+    session_env_table()
+        .get(&session_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Store validated variables for the session; empty values unset a variable
+///
+/// # Errors
+///
+/// Returns an error if the session would hold more than 32 variables.
+pub(super) fn update_session_env(session_id: i64, vars: EnvVars) -> Result<EnvVars> {
+    let mut table = session_env_table();
+    let mut env = table.get(&session_id).cloned().unwrap_or_default();
+    for (name, value) in vars {
+        if value.is_empty() {
+            env.remove(&name);
+        } else {
+            env.insert(name, value);
+        }
+    }
+    if env.len() > MAX_ENV_VARS {
+        bail!("A session can hold at most {MAX_ENV_VARS} environment variables");
+    }
+
+    if env.is_empty() {
+        table.remove(&session_id);
+    } else {
+        table.insert(session_id, env.clone());
+    }
+    Ok(env)
+}
+
+/// Forget the session's variables and stop masking their values, e.g. when
+/// the session is reset
+pub(crate) fn clear_session_env(session_id: i64) {
+    session_env_table().remove(&session_id);
+    forget_user_secrets(session_id);
+}
+
+/// Register the `env` values of an `execute_command` or `set_env` call for
+/// redaction before the call is logged
+pub(crate) fn register_env_arguments(session_id: i64, tool_name: &str, arguments: &str) {
+    #[derive(Deserialize)]
+    struct EnvArgs {
+        #[serde(default)]
+        env: HashMap<String, String>,
+    }
+
+    if !matches!(tool_name, "execute_command" | "set_env") {
+        return;
+    }
+    if let Ok(args) = serde_json::from_str::<EnvArgs>(arguments) {
+        for value in args.env.values() {
+            register_user_secret(session_id, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redaction::redact_secrets;
+
+    #[test]
+    fn test_dangerous_names_are_rejected() {
+        for name in [
+            "PATH",
+            "ld_preload",
+            "LD_LIBRARY_PATH",
+            "BASH_ENV",
+            "OXIDE_EXEC_ID",
+            "BASH_FUNC_ls%%",
+            "1ABC",
+            "A-B",
+            "A B",
+            "",
+        ] {
+            assert!(validate_env_var(name, "x").is_err(), "accepted {name:?}");
+        }
+        assert!(validate_env_var("OPENWEATHER_API_KEY", "abc").is_ok());
+        assert!(validate_env_var("LANG", "C.UTF-8").is_ok());
+        assert!(validate_env_var("_private", "").is_ok());
+    }
+
+    #[test]
+    fn test_host_secrets_cannot_be_passed() {
+        crate::redaction::register_secret("host-only-secret-value-123");
+        assert!(validate_env_var("TOKEN", "prefix host-only-secret-value-123").is_err());
+        assert!(validate_env_var("TOKEN", "user-provided-value-456").is_ok());
+        // The user's own keys look like secrets but are not the host's
+        assert!(validate_env_var("OPENAI_API_KEY", "sk-user0123456789abcdefghij").is_ok());
+        assert!(validate_env_var("TOKEN", "a\0b").is_err());
+    }
+
+    #[test]
+    fn test_accepted_values_are_redacted() {
+        let session_id = -8_000_004;
+        let vars = EnvVars::from([("API_KEY".to_string(), "user-key-9f8e7d6c".to_string())]);
+        assert!(validate_env(session_id, &vars).is_ok());
+        assert_eq!(redact_secrets("key=user-key-9f8e7d6c"), "key=[MASKED]");
+
+        // Values are masked whatever the variable is called
+        register_env_arguments(
+            session_id,
+            "execute_command",
+            r#"{"command": "env", "env": {"WEATHER": "other-key-1a2b3c4d"}}"#,
+        );
+        assert_eq!(redact_secrets("other-key-1a2b3c4d"), "[MASKED]");
+
+        // User values never block other sessions and are forgotten with the session
+        assert!(validate_env_var("OTHER_TOKEN", "other-key-1a2b3c4d").is_ok());
+        clear_session_env(session_id);
+        assert_eq!(redact_secrets("other-key-1a2b3c4d"), "other-key-1a2b3c4d");
+    }
+
+    #[test]
+    fn test_session_env_persists_and_unsets() {
+        let session_id = -8_000_005;
+        let set = EnvVars::from([
+            ("A".to_string(), "1".to_string()),
+            ("B".to_string(), "2".to_string()),
+        ]);
+        assert!(update_session_env(session_id, set).is_ok());
+
+        let unset = EnvVars::from([("A".to_string(), String::new())]);
+        assert!(update_session_env(session_id, unset).is_ok());
+        assert_eq!(
+            session_env(session_id),
+            EnvVars::from([("B".to_string(), "2".to_string())])
+        );
+
+        let too_many = (0..=MAX_ENV_VARS)
+            .map(|i| (format!("V{i}"), "x".to_string()))
+            .collect();
+        assert!(update_session_env(session_id, too_many).is_err());
+        assert_eq!(session_env(session_id).len(), 1);
+
+        clear_session_env(session_id);
+        assert!(session_env(session_id).is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! GoFile backend (default)
+//!
+//! Uploads straight from the sandbox with curl; `GOFILE_TOKEN` attaches the
+//! file to an account instead of a guest upload.
+
+use super::{FileHost, HostedFile};
+use crate::sandbox::shell_quote;
+use crate::sandbox::SandboxManager;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const GOFILE_UPLOAD_URL: &str = "https://upload.gofile.io/uploadfile";
+const GOFILE_DOWNLOAD_PAGE_PREFIX: &str = "https://gofile.io/d/";
+const GOFILE_EXPIRY_NOTE: &str =
+    "GoFile removes guest uploads after about 10 days without downloads";
+
+/// Uploads files to GoFile
+pub struct GoFileHost;
+
+#[async_trait]
+impl FileHost for GoFileHost {
+    fn name(&self) -> &'static str {
+        "GoFile"
+    }
+
+    async fn upload(
+        &self,
+        sandbox: &SandboxManager,
+        path: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<HostedFile> {
+        let token_opt = std::env::var("GOFILE_TOKEN").ok().filter(|t| !t.is_empty());
+        let token_part = token_opt.as_deref().map_or(String::new(), |token| {
+            format!(" -F {}", shell_quote(&format!("token={token}")))
+        });
+
+        let cmd = format!(
+            "curl -sS --fail-with-body --retry 3 --retry-all-errors --retry-delay 2 --retry-max-time 60 \
+             -F {file}{token_part} {url}",
+            file = shell_quote(&format!("file=@{path}")),
+            token_part = token_part,
+            url = shell_quote(GOFILE_UPLOAD_URL),
+        );
+
+        let result = match sandbox.exec_command(&cmd, cancellation_token).await {
+            Ok(r) => r,
+            Err(e) => bail!("GoFile upload error: {e}"),
+        };
+
+        if !result.success() {
+            bail!(
+                "GoFile upload error (code {}): {}",
+                result.exit_code,
+                result.combined_output()
+            );
+        }
+
+        let resp: GoFileUploadResponse = match serde_json::from_str(result.stdout.trim()) {
+            Ok(r) => r,
+            Err(e) => bail!(
+                "GoFile returned unexpected response (not JSON): {e}\n{}",
+                result.combined_output()
+            ),
+        };
+
+        let download_page = match resp.into_download_page() {
+            Ok(url) => url,
+            Err(msg) => bail!(
+                "GoFile returned error:\n{msg}\n{}",
+                result.combined_output()
+            ),
+        };
+
+        if !download_page.starts_with(GOFILE_DOWNLOAD_PAGE_PREFIX) {
+            bail!(
+                "GoFile returned unexpected response instead of link:\n{}",
+                result.combined_output()
+            );
+        }
+
+        Ok(HostedFile {
+            url: download_page,
+            expiry: GOFILE_EXPIRY_NOTE.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoFileUploadResponse {
+    status: String,
+    #[serde(default)]
+    data: Option<GoFileUploadData>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoFileUploadData {
+    #[serde(rename = "downloadPage")]
+    download_page: Option<String>,
+}
+
+impl GoFileUploadResponse {
+    fn into_download_page(self) -> std::result::Result<String, String> {
+        if self.status == "ok" {
+            let url = self
+                .data
+                .and_then(|d| d.download_page)
+                .filter(|u| !u.trim().is_empty());
+            return url.ok_or_else(|| "GoFile: missing downloadPage in response".to_string());
+        }
+
+        let msg = self
+            .error
+            .or(self.message)
+            .unwrap_or_else(|| "GoFile: unknown error".to_string());
+        Err(msg)
+    }
+}
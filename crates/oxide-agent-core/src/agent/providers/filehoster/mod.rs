@@ -0,0 +1,270 @@
+//! FileHoster provider - uploads large sandbox files to external hosting.
+//!
+//! Uploads go through a [`FileHost`] backend selected by `FILE_HOST_BACKEND`:
+//! GoFile (default) or the S3/R2 bucket from the `R2_*` settings.
+//! The chat delivery paths (`send_file_to_user`, yt-dlp auto-send) fall back
+//! to [`upload_large_file`] for files over `CHAT_DELIVERY_MAX_FILE_MB`.
+
+mod gofile;
+mod s3;
+
+pub use gofile::GoFileHost;
+pub use s3::S3FileHost;
+
+use crate::agent::provider::ToolProvider;
+use crate::config::AgentSettings;
+use crate::llm::ToolDefinition;
+use crate::sandbox::shell_quote;
+use crate::sandbox::{SandboxHandle, SandboxManager};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::{debug, error, info, warn};
+
+use super::path::resolve_file_path;
+
+const MAX_UPLOAD_SIZE_BYTES: u64 = 4 * 1024 * 1024 * 1024; // 4 GiB (safety limit)
+
+/// A file uploaded to a file host
+#[derive(Debug, Clone)]
+pub struct HostedFile {
+    /// Public download link
+    pub url: String,
+    /// How long the link stays valid, for the user
+    pub expiry: String,
+}
+
+/// External hosting for sandbox files too large for chat delivery
+#[async_trait]
+pub trait FileHost: Send + Sync {
+    /// Backend name shown in tool output
+    fn name(&self) -> &'static str;
+
+    /// Upload the file at `path` inside the sandbox and return its public link
+    async fn upload(
+        &self,
+        sandbox: &SandboxManager,
+        path: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<HostedFile>;
+}
+
+/// Backend built on first use; failures are not cached so config fixes apply
+static FILE_HOST: OnceCell<Arc<dyn FileHost>> = OnceCell::const_new();
+
+/// Get the file host selected by `FILE_HOST_BACKEND`
+///
+/// # Errors
+///
+/// Returns an error for an unknown backend or when the `s3` backend lacks its
+/// `R2_*` settings. There is no silent fallback to GoFile: files meant for a
+/// private bucket must not end up on a public host.
+pub async fn configured_file_host() -> Result<Arc<dyn FileHost>> {
+    FILE_HOST
+        .get_or_try_init(|| async {
+            let backend = crate::config::get_file_host_backend();
+            let host: Arc<dyn FileHost> = match backend.as_str() {
+                "gofile" => Arc::new(GoFileHost),
+                "s3" | "r2" => {
+                    let settings = AgentSettings::new()?;
+                    Arc::new(S3FileHost::from_settings(&settings).await?)
+                }
+                other => {
+                    anyhow::bail!("Unknown FILE_HOST_BACKEND '{other}' (expected 'gofile' or 's3')")
+                }
+            };
+            info!(backend = host.name(), "File host initialized");
+            Ok(host)
+        })
+        .await
+        .cloned()
+}
+
+/// Provider for file hosting tools (executed in sandbox)
+pub struct FileHosterProvider {
+    sandbox: SandboxHandle,
+}
+
+impl FileHosterProvider {
+    /// Create a new `FileHosterProvider` (sandbox is lazily initialized)
+    #[must_use]
+    pub fn new(user_id: i64) -> Self {
+        Self {
+            sandbox: SandboxHandle::new(user_id),
+        }
+    }
+
+    /// Work in a sandbox shared with other providers
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: SandboxHandle) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    async fn handle_upload_file(
+        &self,
+        sandbox: &SandboxManager,
+        arguments: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        let args: UploadFileArgs = serde_json::from_str(arguments)?;
+        info!(path = %args.path, "upload_file called");
+
+        let resolved_path = match resolve_file_path(sandbox, &args.path).await {
+            Ok(p) => p,
+            Err(e) => {
+                warn!(path = %args.path, error = %e, "Failed to resolve file path");
+                return Ok(format!("❌ {e}"));
+            }
+        };
+
+        let file_size = match sandbox
+            .file_size_bytes(&resolved_path, cancellation_token)
+            .await
+        {
+            Ok(size) => size,
+            Err(e) => {
+                error!(resolved_path = %resolved_path, error = %e, "Failed to check file size");
+                return Ok(format!("❌ File size check error: {e}"));
+            }
+        };
+
+        if file_size > MAX_UPLOAD_SIZE_BYTES {
+            return Ok("⛔ FATAL ERROR: File exceeds upload limit (4 GB). Upload impossible. Immediately inform the user that the task cannot be completed.".to_string());
+        }
+
+        Ok(
+            match upload_to_file_host(sandbox, &resolved_path, cancellation_token).await {
+                Ok((_, hosted)) => format!("{}\nExpiry: {}.", hosted.url, hosted.expiry),
+                Err(e) => e,
+            },
+        )
+    }
+}
+
+/// Deliver a file too large for chat as a file host link
+///
+/// Returns the tool output: the link with expiry info on success, otherwise
+/// an error that keeps the sandbox path so the agent can react.
+pub(super) async fn upload_large_file(
+    sandbox: &SandboxManager,
+    resolved_path: &str,
+    file_name: &str,
+    file_size: u64,
+    cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+) -> String {
+    let size_mb = file_size as f64 / 1024.0 / 1024.0;
+    let limit_mb = crate::config::get_chat_delivery_max_file_bytes() / 1024 / 1024;
+    if file_size > MAX_UPLOAD_SIZE_BYTES {
+        return format!(
+            "⛔ File '{file_name}' ({size_mb:.2} MB) is too large for both chat delivery and upload (4 GB). \
+             Inform the user that it cannot be delivered.\nPath in sandbox: {resolved_path}"
+        );
+    }
+
+    info!(resolved_path = %resolved_path, file_size, "File exceeds chat limit, uploading to file host");
+    match upload_to_file_host(sandbox, resolved_path, cancellation_token).await {
+        Ok((host, hosted)) => format!(
+            "📦 File '{file_name}' ({size_mb:.2} MB) exceeds the {limit_mb} MB chat limit, \
+             so it was uploaded to {host} instead of sent.\n\
+             Download link: {url}\n\
+             Expiry: {expiry}.\n\
+             Give this link to the user in your answer.",
+            url = hosted.url,
+            expiry = hosted.expiry,
+        ),
+        Err(e) => format!(
+            "❌ File '{file_name}' ({size_mb:.2} MB) exceeds the {limit_mb} MB chat limit \
+             and the upload failed:\n{e}\nPath in sandbox: {resolved_path}"
+        ),
+    }
+}
+
+/// Upload a sandbox file to the configured host and remove it from the sandbox on success
+///
+/// Returns the backend name and the hosted file, or a user-readable error message.
+async fn upload_to_file_host(
+    sandbox: &SandboxManager,
+    resolved_path: &str,
+    cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+) -> std::result::Result<(&'static str, HostedFile), String> {
+    let host = configured_file_host()
+        .await
+        .map_err(|e| format!("❌ File hosting is not available: {e}"))?;
+    let hosted = host
+        .upload(sandbox, resolved_path, cancellation_token)
+        .await
+        .map_err(|e| format!("❌ {e}"))?;
+
+    let rm_cmd = format!("rm -f {}", shell_quote(resolved_path));
+    match sandbox.exec_command(&rm_cmd, cancellation_token).await {
+        Ok(rm_res) if rm_res.success() => {}
+        Ok(rm_res) => warn!(
+            resolved_path = %resolved_path,
+            output = %rm_res.combined_output(),
+            "Failed to remove uploaded file from sandbox"
+        ),
+        Err(e) => {
+            warn!(resolved_path = %resolved_path, error = %e, "Failed to remove uploaded file from sandbox")
+        }
+    }
+
+    Ok((host.name(), hosted))
+}
+
+/// Arguments for `upload_file` tool
+#[derive(Debug, Deserialize)]
+struct UploadFileArgs {
+    path: String,
+}
+
+#[async_trait]
+impl ToolProvider for FileHosterProvider {
+    fn name(&self) -> &'static str {
+        "filehoster"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![ToolDefinition {
+            name: "upload_file".to_string(),
+            description: "Upload a file from the sandbox to external hosting. Use this for files too large for chat delivery (>50 MB). Returns a public link and its expiry on success.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the file in the sandbox (relative or absolute)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }]
+    }
+
+    fn can_handle(&self, tool_name: &str) -> bool {
+        matches!(tool_name, "upload_file")
+    }
+
+    async fn execute(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        _progress_tx: Option<&tokio::sync::mpsc::Sender<crate::agent::progress::AgentEvent>>,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        debug!(tool = tool_name, "Executing filehoster tool");
+
+        let sandbox = self.sandbox.get().await?;
+
+        match tool_name {
+            "upload_file" => {
+                self.handle_upload_file(&sandbox, arguments, cancellation_token)
+                    .await
+            }
+            _ => anyhow::bail!("Unknown filehoster tool: {tool_name}"),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! S3/R2 backend - uploads into the bucket configured by the `R2_*` settings
+//!
+//! The bot presigns a `PUT` URL and the sandbox uploads the file with curl,
+//! so large files never pass through the bot's memory. The link is
+//! `FILE_HOST_PUBLIC_URL/<key>` when the bucket is public, otherwise a
+//! presigned `GET` URL that expires after seven days (the S3 maximum).
+
+use super::{FileHost, HostedFile};
+use crate::config::AgentSettings;
+use crate::sandbox::shell_quote;
+use crate::sandbox::SandboxManager;
+use crate::storage::{build_r2_client, StorageError};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::Client;
+use std::time::Duration;
+
+/// Prefix of uploaded objects in the bucket
+const UPLOAD_KEY_PREFIX: &str = "uploads/";
+/// Lifetime of the presigned `PUT` URL handed to the sandbox
+const UPLOAD_URL_TTL: Duration = Duration::from_secs(60 * 60);
+/// Lifetime of presigned download links (the S3 maximum)
+const DOWNLOAD_URL_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Uploads files to an S3-compatible bucket (Cloudflare R2 by default)
+pub struct S3FileHost {
+    client: Client,
+    bucket: String,
+    public_base_url: Option<String>,
+}
+
+impl S3FileHost {
+    /// Create a host using the `R2_*` storage credentials
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::Config` if any of the `R2_*` settings is missing.
+    pub async fn from_settings(settings: &AgentSettings) -> Result<Self, StorageError> {
+        let (client, bucket) = build_r2_client(settings).await?;
+        Ok(Self {
+            client,
+            bucket,
+            public_base_url: crate::config::get_file_host_public_url(),
+        })
+    }
+
+    async fn download_link(&self, key: &str) -> Result<HostedFile> {
+        if let Some(base) = &self.public_base_url {
+            return Ok(HostedFile {
+                url: format!("{}/{key}", base.trim_end_matches('/')),
+                expiry: "the file stays in the bucket until it is deleted".to_string(),
+            });
+        }
+
+        let presigned = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(DOWNLOAD_URL_TTL)?)
+            .await?;
+        Ok(HostedFile {
+            url: presigned.uri().to_string(),
+            expiry: "the link expires in 7 days".to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl FileHost for S3FileHost {
+    fn name(&self) -> &'static str {
+        "S3"
+    }
+
+    async fn upload(
+        &self,
+        sandbox: &SandboxManager,
+        path: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<HostedFile> {
+        let file_name = std::path::Path::new(path)
+            .file_name()
+            .map_or_else(|| "file".to_string(), |n| n.to_string_lossy().to_string());
+        let key = object_key(&uuid::Uuid::new_v4().to_string(), &file_name);
+
+        let upload_url = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .presigned(PresigningConfig::expires_in(UPLOAD_URL_TTL)?)
+            .await?;
+
+        let cmd = format!(
+            "curl -sS --fail-with-body --retry 3 --retry-all-errors --retry-delay 2 --retry-max-time 60 \
+             -T {file} {url}",
+            file = shell_quote(path),
+            url = shell_quote(upload_url.uri()),
+        );
+        let result = match sandbox.exec_command(&cmd, cancellation_token).await {
+            Ok(r) => r,
+            Err(e) => bail!("S3 upload error: {e}"),
+        };
+        if !result.success() {
+            bail!(
+                "S3 upload error (code {}): {}",
+                result.exit_code,
+                result.combined_output()
+            );
+        }
+
+        self.download_link(&key).await
+    }
+}
+
+/// Unique object key that keeps a URL-safe version of the file name
+fn object_key(unique: &str, file_name: &str) -> String {
+    let safe_name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{UPLOAD_KEY_PREFIX}{unique}/{safe_name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_key_is_url_safe() {
+        assert_eq!(
+            object_key("abc", "my report (final).pdf"),
+            "uploads/abc/my_report__final_.pdf"
+        );
+        assert_eq!(object_key("abc", "видео.mp4"), "uploads/abc/_____.mp4");
+    }
+}
@@ -0,0 +1,131 @@
+//! Finish provider - lets the agent end the task on its own
+//!
+//! `finish_task` only validates its arguments here; the runner sees the
+//! successful call and stops the loop with the agent's message (see
+//! `AgentRunner::execute_tools`). A task reported as impossible ends with
+//! [`Outcome::GaveUp`](crate::agent::runner::Outcome::GaveUp) instead of
+//! looping until the iteration limit.
+
+use crate::agent::progress::AgentEvent;
+use crate::agent::provider::ToolProvider;
+use crate::agent::tool_error::{ToolError, ToolErrorKind};
+use crate::llm::ToolDefinition;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::debug;
+
+/// Name of the tool that ends the task
+pub const FINISH_TASK_TOOL: &str = "finish_task";
+
+/// Arguments for `finish_task`
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct FinishTaskArgs {
+    /// Final message for the user
+    pub message: String,
+    /// Whether the task was accomplished
+    pub success: bool,
+}
+
+/// Parse and validate `finish_task` arguments
+pub(crate) fn parse_finish_args(arguments: &str) -> Result<FinishTaskArgs> {
+    let mut args: FinishTaskArgs = serde_json::from_str(arguments)?;
+    args.message = args.message.trim().to_string();
+    if args.message.is_empty() {
+        return Err(ToolError::new(
+            ToolErrorKind::InvalidArguments,
+            "finish_task requires a non-empty message for the user",
+        )
+        .into());
+    }
+    Ok(args)
+}
+
+/// Provider for the `finish_task` tool
+#[derive(Debug, Default)]
+pub struct FinishTaskProvider;
+
+impl FinishTaskProvider {
+    /// Create a new `FinishTaskProvider`
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ToolProvider for FinishTaskProvider {
+    fn name(&self) -> &'static str {
+        "finish"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![ToolDefinition {
+            name: FINISH_TASK_TOOL.to_string(),
+            description: "End the task now with a final message for the user. Call it alone, \
+                as the last step. Use success=false when the task cannot be done (the video \
+                was deleted, the site is unreachable, access is denied) and explain why, \
+                instead of retrying the same thing or inventing an answer."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "message": {
+                        "type": "string",
+                        "description": "Final answer, or the reason the task cannot be done and what the user can try instead"
+                    },
+                    "success": {
+                        "type": "boolean",
+                        "description": "true if the task was accomplished, false if it is impossible"
+                    }
+                },
+                "required": ["message", "success"]
+            }),
+        }]
+    }
+
+    fn can_handle(&self, tool_name: &str) -> bool {
+        tool_name == FINISH_TASK_TOOL
+    }
+
+    async fn execute(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        _progress_tx: Option<&tokio::sync::mpsc::Sender<AgentEvent>>,
+        _cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        debug!(tool = tool_name, "Executing finish tool");
+
+        if tool_name != FINISH_TASK_TOOL {
+            anyhow::bail!("Unknown finish tool: {tool_name}");
+        }
+
+        let args = parse_finish_args(arguments)?;
+        Ok(if args.success {
+            "Task finished.".to_string()
+        } else {
+            "Task stopped as impossible.".to_string()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_finish_args() {
+        let Ok(args) =
+            parse_finish_args(r#"{"message": "  The video was deleted. ", "success": false}"#)
+        else {
+            panic!("valid arguments should parse");
+        };
+        assert_eq!(args.message, "The video was deleted.");
+        assert!(!args.success);
+
+        assert!(parse_finish_args(r#"{"message": " ", "success": true}"#).is_err());
+        assert!(parse_finish_args(r#"{"message": "Done"}"#).is_err());
+    }
+}
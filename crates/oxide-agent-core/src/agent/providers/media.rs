@@ -0,0 +1,327 @@
+//! Media provider - multimodal analysis of sandbox files.
+//!
+//! Lets the agent look at images, listen to audio and watch videos it has
+//! downloaded into the sandbox (e.g. with yt-dlp). Images go to the media
+//! model directly; audio is transcribed; videos are split in the sandbox into
+//! a contact sheet of frames plus an audio track.
+
+use crate::agent::provider::ToolProvider;
+use crate::llm::{LlmClient, ToolDefinition};
+use crate::sandbox::shell_quote;
+use crate::sandbox::{SandboxHandle, SandboxManager};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use super::path::resolve_file_path;
+
+/// Number of frames per side of the video contact sheet (3x3 = 9 frames)
+const CONTACT_SHEET_GRID: usize = 3;
+/// Width of a single frame in the contact sheet
+const CONTACT_SHEET_FRAME_WIDTH: usize = 480;
+
+const MEDIA_SYSTEM_PROMPT: &str = "You are a media analyzer for an AI agent. \
+    Answer the agent's question about the provided media precisely and include \
+    all details (text, objects, people, actions) relevant to it.";
+
+/// Kind of media file, detected from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MediaKind {
+    Image,
+    Audio(&'static str),
+    Video,
+}
+
+fn classify_media(path: &str) -> Option<MediaKind> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" | "jpg" | "jpeg" | "webp" | "gif" | "bmp" => Some(MediaKind::Image),
+        "mp3" => Some(MediaKind::Audio("audio/mpeg")),
+        "ogg" | "oga" | "opus" => Some(MediaKind::Audio("audio/ogg")),
+        "wav" => Some(MediaKind::Audio("audio/wav")),
+        "m4a" | "aac" => Some(MediaKind::Audio("audio/mp4")),
+        "flac" => Some(MediaKind::Audio("audio/flac")),
+        "mp4" | "webm" | "mkv" | "mov" | "avi" => Some(MediaKind::Video),
+        _ => None,
+    }
+}
+
+/// Shell command that renders a contact sheet of evenly spaced frames.
+fn contact_sheet_command(input: &str, output: &str) -> String {
+    let frames = CONTACT_SHEET_GRID * CONTACT_SHEET_GRID;
+    let input = shell_quote(input);
+    format!(
+        "fps=$(ffprobe -v error -show_entries format=duration -of csv=p=0 {input} \
+         | awk '{{d=$1; if (d < 1) d = 1; printf \"%.6f\", {frames}/d}}') && \
+         ffmpeg -y -v error -i {input} \
+         -vf \"fps=$fps,scale={CONTACT_SHEET_FRAME_WIDTH}:-2,tile={CONTACT_SHEET_GRID}x{CONTACT_SHEET_GRID}\" \
+         -frames:v 1 {output}",
+        output = shell_quote(output),
+    )
+}
+
+/// Shell command that extracts the audio track as compact mono MP3.
+fn audio_track_command(input: &str, output: &str) -> String {
+    format!(
+        "ffmpeg -y -v error -i {} -vn -ac 1 -b:a 64k {}",
+        shell_quote(input),
+        shell_quote(output)
+    )
+}
+
+/// Provider for the `analyze_media` tool (files are read from the sandbox)
+pub struct MediaProvider {
+    llm_client: Arc<LlmClient>,
+    sandbox: SandboxHandle,
+}
+
+impl MediaProvider {
+    /// Create a new `MediaProvider` (sandbox is lazily initialized)
+    #[must_use]
+    pub fn new(llm_client: Arc<LlmClient>, user_id: i64) -> Self {
+        Self {
+            llm_client,
+            sandbox: SandboxHandle::new(user_id),
+        }
+    }
+
+    /// Work in a sandbox shared with other providers
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: SandboxHandle) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    fn model_name(&self) -> &str {
+        self.llm_client
+            .media_model_name
+            .as_deref()
+            .unwrap_or(&self.llm_client.chat_model_name)
+    }
+
+    async fn analyze_image(&self, bytes: Vec<u8>, prompt: &str) -> Result<String> {
+        self.llm_client
+            .analyze_image(bytes, prompt, MEDIA_SYSTEM_PROMPT, self.model_name())
+            .await
+            .map_err(|e| anyhow::anyhow!("Image analysis failed: {e}"))
+    }
+
+    async fn transcribe(&self, bytes: Vec<u8>, mime_type: &str) -> Result<String> {
+        self.llm_client
+            .transcribe_audio(bytes, mime_type, None, self.model_name())
+            .await
+            .map_err(|e| anyhow::anyhow!("Transcription failed: {e}"))
+    }
+
+    async fn handle_analyze_media(
+        &self,
+        sandbox: &SandboxManager,
+        arguments: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        let args: AnalyzeMediaArgs = serde_json::from_str(arguments)?;
+        info!(path = %args.path, "analyze_media called");
+
+        let resolved_path = match resolve_file_path(sandbox, &args.path).await {
+            Ok(p) => p,
+            Err(e) => return Ok(format!("❌ {e}")),
+        };
+
+        let Some(kind) = classify_media(&resolved_path) else {
+            return Ok(format!(
+                "❌ Unsupported media type: {resolved_path}. Supported: images (png, jpg, webp, gif), \
+                 audio (mp3, ogg, opus, wav, m4a, flac) and video (mp4, webm, mkv, mov, avi)."
+            ));
+        };
+
+        match kind {
+            MediaKind::Image => {
+                let bytes = sandbox.download_file(&resolved_path).await?;
+                self.analyze_image(bytes, &args.prompt).await
+            }
+            MediaKind::Audio(mime_type) => {
+                let bytes = sandbox.download_file(&resolved_path).await?;
+                let transcript = self.transcribe(bytes, mime_type).await?;
+                Ok(format!("Audio transcript:\n{transcript}"))
+            }
+            MediaKind::Video => {
+                self.analyze_video(sandbox, &resolved_path, &args.prompt, cancellation_token)
+                    .await
+            }
+        }
+    }
+
+    async fn analyze_video(
+        &self,
+        sandbox: &SandboxManager,
+        path: &str,
+        prompt: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        // Unique names, so parallel calls in one sandbox do not overwrite each other
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let sheet_path = format!("/tmp/analyze_media_{id}_frames.jpg");
+        let audio_path = format!("/tmp/analyze_media_{id}_audio.mp3");
+
+        let result = self
+            .describe_video(
+                sandbox,
+                path,
+                prompt,
+                &sheet_path,
+                &audio_path,
+                cancellation_token,
+            )
+            .await;
+
+        let cleanup = format!("rm -f {sheet_path} {audio_path}");
+        if let Err(e) = sandbox.exec_command(&cleanup, None).await {
+            warn!(error = %e, "Failed to remove analyze_media temp files");
+        }
+        result
+    }
+
+    /// Contact sheet analysis plus audio transcript, using the given temp files
+    async fn describe_video(
+        &self,
+        sandbox: &SandboxManager,
+        path: &str,
+        prompt: &str,
+        sheet_path: &str,
+        audio_path: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        let sheet = sandbox
+            .exec_command(&contact_sheet_command(path, sheet_path), cancellation_token)
+            .await?;
+        if !sheet.success() {
+            return Ok(format!(
+                "❌ Failed to extract frames from video: {}",
+                sheet.combined_output()
+            ));
+        }
+
+        let frames = sandbox.download_file(sheet_path).await?;
+        let visual_prompt = format!(
+            "The image is a {grid}x{grid} grid of frames sampled evenly from a video, \
+             in chronological order left-to-right, top-to-bottom. {prompt}",
+            grid = CONTACT_SHEET_GRID
+        );
+        let visual = self.analyze_image(frames, &visual_prompt).await?;
+
+        // Videos without an audio stream make ffmpeg fail; that is not an error here
+        let audio = sandbox
+            .exec_command(&audio_track_command(path, audio_path), cancellation_token)
+            .await?;
+        let transcript = if audio.success() {
+            let bytes = sandbox.download_file(audio_path).await?;
+            match self.transcribe(bytes, "audio/mpeg").await {
+                Ok(text) => text,
+                Err(e) => {
+                    warn!(error = %e, "Video audio transcription failed");
+                    format!("(transcription failed: {e})")
+                }
+            }
+        } else {
+            "(no audio track)".to_string()
+        };
+
+        Ok(format!(
+            "Visual analysis:\n{visual}\n\nAudio transcript:\n{transcript}"
+        ))
+    }
+}
+
+/// Arguments for `analyze_media` tool
+#[derive(Debug, Deserialize)]
+struct AnalyzeMediaArgs {
+    path: String,
+    prompt: String,
+}
+
+#[async_trait]
+impl ToolProvider for MediaProvider {
+    fn name(&self) -> &'static str {
+        "media"
+    }
+
+    fn tools(&self) -> Vec<ToolDefinition> {
+        vec![ToolDefinition {
+            name: "analyze_media".to_string(),
+            description: "Analyze an image, audio or video file from the sandbox with a multimodal model. Images are answered against the prompt, audio is transcribed, videos return a frame-based visual analysis plus an audio transcript. Files are limited to 50 MB.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the media file in the sandbox (relative or absolute)"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "What to look for or answer about the media"
+                    }
+                },
+                "required": ["path", "prompt"]
+            }),
+        }]
+    }
+
+    fn can_handle(&self, tool_name: &str) -> bool {
+        matches!(tool_name, "analyze_media")
+    }
+
+    async fn execute(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        _progress_tx: Option<&tokio::sync::mpsc::Sender<crate::agent::progress::AgentEvent>>,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        debug!(tool = tool_name, "Executing media tool");
+
+        let sandbox = self.sandbox.get().await?;
+
+        match tool_name {
+            "analyze_media" => {
+                self.handle_analyze_media(&sandbox, arguments, cancellation_token)
+                    .await
+            }
+            _ => anyhow::bail!("Unknown media tool: {tool_name}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_media() {
+        assert_eq!(classify_media("/workspace/a.PNG"), Some(MediaKind::Image));
+        assert_eq!(
+            classify_media("voice.opus"),
+            Some(MediaKind::Audio("audio/ogg"))
+        );
+        assert_eq!(
+            classify_media("/workspace/downloads/clip.webm"),
+            Some(MediaKind::Video)
+        );
+        assert_eq!(classify_media("notes.txt"), None);
+        assert_eq!(classify_media("Makefile"), None);
+    }
+
+    #[test]
+    fn test_video_commands_escape_paths() {
+        let cmd = contact_sheet_command("/workspace/my clip.mp4", "/tmp/out.jpg");
+        assert!(cmd.contains("'/workspace/my clip.mp4'"));
+        assert!(cmd.contains("tile=3x3"));
+        assert!(cmd.contains("9/d"));
+
+        let cmd = audio_track_command("/workspace/my clip.mp4", "/tmp/out.mp3");
+        assert!(cmd.contains("-vn"));
+        assert!(cmd.contains("'/workspace/my clip.mp4'"));
+    }
+}
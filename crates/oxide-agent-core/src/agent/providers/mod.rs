@@ -2,28 +2,53 @@
 //!
 //! Contains implementations of `ToolProvider` for different tool sources.
 
+pub mod clarification;
+pub mod datetime;
 pub mod delegation;
 pub mod filehoster;
+pub mod finish;
+pub mod media;
 pub mod sandbox;
 pub mod todos;
 pub mod ytdlp;
 
+mod delivery;
+mod exec_env;
 mod path;
+mod workdir;
+
+#[cfg(any(feature = "tavily", feature = "brave"))]
+mod search_cache;
+#[cfg(any(feature = "tavily", feature = "brave"))]
+mod web_search;
 
 #[cfg(feature = "tavily")]
 pub mod tavily;
 
+#[cfg(feature = "brave")]
+pub mod brave;
+
 #[cfg(feature = "crawl4ai")]
 pub mod crawl4ai;
 
+pub use clarification::ClarificationProvider;
+pub use datetime::DateTimeProvider;
 pub use delegation::DelegationProvider;
 pub use filehoster::FileHosterProvider;
+pub use finish::FinishTaskProvider;
+pub use media::MediaProvider;
 pub use sandbox::SandboxProvider;
 pub use todos::{TodoItem, TodoList, TodoStatus, TodosProvider};
 pub use ytdlp::YtdlpProvider;
 
+pub(crate) use exec_env::{clear_session_env, register_env_arguments};
+pub(crate) use workdir::clear_working_dir;
+
 #[cfg(feature = "tavily")]
 pub use tavily::TavilyProvider;
 
+#[cfg(feature = "brave")]
+pub use brave::BraveSearchProvider;
+
 #[cfg(feature = "crawl4ai")]
 pub use crawl4ai::Crawl4aiProvider;
@@ -1,8 +1,8 @@
 //! Shared helpers for providers that work with sandbox file paths.
 
+use crate::sandbox::shell_quote;
 use crate::sandbox::SandboxManager;
 use anyhow::Result;
-use shell_escape::escape;
 use tracing::{info, warn};
 
 /// Resolve a relative path to an absolute path in the sandbox.
@@ -21,10 +21,7 @@ pub(super) async fn resolve_file_path(sandbox: &SandboxManager, path: &str) -> R
     }
 
     let workspace_path = format!("/workspace/{path}");
-    let check_cmd = format!(
-        "test -f {} && echo 'exists'",
-        escape(workspace_path.as_str().into())
-    );
+    let check_cmd = format!("test -f {} && echo 'exists'", shell_quote(&workspace_path));
     let check = sandbox.exec_command(&check_cmd, None).await?;
 
     if check.stdout.contains("exists") {
@@ -33,7 +30,7 @@ pub(super) async fn resolve_file_path(sandbox: &SandboxManager, path: &str) -> R
     }
 
     info!(path = %path, "File not found at /workspace/{path}, searching...");
-    let find_cmd = format!("find /workspace -name {} -type f", escape(path.into()));
+    let find_cmd = format!("find /workspace -name {} -type f", shell_quote(path));
     let result = sandbox.exec_command(&find_cmd, None).await?;
 
     let found_paths: Vec<&str> = result.stdout.lines().filter(|l| !l.is_empty()).collect();
@@ -1,32 +1,47 @@
 //! Sandbox Provider - executes tools in Docker sandbox
 //!
-//! Provides `execute_command`, `read_file`, `write_file`, `send_file_to_user` tools.
+//! Provides `execute_command`, `set_cwd`, `set_env`, `read_file`, `write_file`, `send_file_to_user`,
+//! `zip_and_send`, `list_files`, `search_files`, `sandbox_ps` and `sandbox_kill` tools.
 
 use crate::agent::progress::AgentEvent;
 use crate::agent::provider::ToolProvider;
 use crate::llm::ToolDefinition;
-use crate::sandbox::SandboxManager;
+use crate::sandbox::shell_quote;
+use crate::sandbox::{SandboxHandle, SandboxManager};
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::json;
-use shell_escape::escape;
-use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
+use super::delivery::{already_delivered_message, claim_delivery};
+use super::exec_env::{session_env, update_session_env, validate_env, EnvVars};
+use super::filehoster::upload_large_file;
 use super::path::resolve_file_path;
+use super::workdir::{resolve_working_dir, set_working_dir, working_dir};
 
-const CHAT_DELIVERY_MAX_FILE_SIZE_BYTES: u64 = 50 * 1024 * 1024;
 const CHAT_DELIVERY_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(120);
+/// Maximum number of matching lines returned by `search_files`
+const SEARCH_FILES_MAX_MATCHES: usize = 200;
+/// Maximum length of a single match line returned by `search_files`
+const SEARCH_FILES_MAX_LINE_CHARS: usize = 300;
+/// Lines returned by a ranged `read_file` when `line_count` is omitted
+const READ_FILE_DEFAULT_LINES: usize = 200;
+/// Upper bound for `line_count` in a ranged `read_file`
+const READ_FILE_MAX_LINES: usize = 2000;
+/// Directory where `zip_and_send` builds archives; relative paths start here too
+const ZIP_WORKDIR: &str = "/workspace";
+/// Archive name used by `zip_and_send` when the agent gives none
+const ZIP_DEFAULT_NAME: &str = "files.zip";
 
 /// Provider for Docker sandbox tools
 pub struct SandboxProvider {
-    sandbox: Arc<Mutex<Option<SandboxManager>>>,
+    sandbox: SandboxHandle,
     user_id: i64,
     progress_tx: Option<Sender<AgentEvent>>,
+    max_output_chars: usize,
 }
 
 struct FileDeliveryRequest {
@@ -40,12 +55,20 @@ impl SandboxProvider {
     #[must_use]
     pub fn new(user_id: i64) -> Self {
         Self {
-            sandbox: Arc::new(Mutex::new(None)),
+            sandbox: SandboxHandle::new(user_id),
             user_id,
             progress_tx: None,
+            max_output_chars: crate::config::get_sandbox_max_output_chars(),
         }
     }
 
+    /// Override the `execute_command` output cap (characters)
+    #[must_use]
+    pub const fn with_max_output_chars(mut self, max_chars: usize) -> Self {
+        self.max_output_chars = max_chars;
+        self
+    }
+
     /// Set the progress channel for sending events (like file transfers)
     #[must_use]
     pub fn with_progress_tx(mut self, tx: Sender<AgentEvent>) -> Self {
@@ -55,28 +78,14 @@ impl SandboxProvider {
 
     /// Set the sandbox manager (for when sandbox is created externally)
     pub async fn set_sandbox(&self, sandbox: SandboxManager) {
-        let mut guard = self.sandbox.lock().await;
-        *guard = Some(sandbox);
+        self.sandbox.set(sandbox).await;
     }
 
-    /// Get or create the sandbox
-    async fn ensure_sandbox(&self) -> Result<()> {
-        if self
-            .sandbox
-            .lock()
-            .await
-            .as_ref()
-            .is_some_and(SandboxManager::is_running)
-        {
-            return Ok(());
-        }
-
-        debug!(user_id = self.user_id, "Creating new sandbox for provider");
-        let mut sandbox = SandboxManager::new(self.user_id).await?;
-        sandbox.create_sandbox().await?;
-
-        *self.sandbox.lock().await = Some(sandbox);
-        Ok(())
+    /// Work in a sandbox shared with other providers
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: SandboxHandle) -> Self {
+        self.sandbox = sandbox;
+        self
     }
 
     async fn deliver_file_to_user(&self, request: FileDeliveryRequest) -> String {
@@ -103,6 +112,11 @@ impl SandboxProvider {
             );
         };
 
+        let Some(claim) = claim_delivery(self.user_id, &content) else {
+            info!(file_name = %file_name, "File already delivered recently, skipping");
+            return already_delivered_message(&file_name);
+        };
+
         let (confirm_tx, confirm_rx) = tokio::sync::oneshot::channel();
         if let Err(e) = tx
             .send(AgentEvent::FileToSendWithConfirmation {
@@ -113,6 +127,7 @@ impl SandboxProvider {
             })
             .await
         {
+            claim.release();
             warn!(file_name = %file_name, error = %e, "Failed to send FileToSendWithConfirmation event");
             return format!(
                 "⚠️ File '{file_name}' read ({size_mb:.2} MB), but failed to send: {e}\n\
@@ -126,6 +141,7 @@ impl SandboxProvider {
                 format!("✅ File '{file_name}' delivered to user")
             }
             Ok(Ok(Err(e))) => {
+                claim.release();
                 warn!(file_name = %file_name, error = %e, "File delivery failed");
                 format!(
                     "❌ Failed to send file '{file_name}' to the user: {e}\n\
@@ -150,15 +166,32 @@ impl SandboxProvider {
     }
 
     async fn handle_execute_command(
+        &self,
         sandbox: &SandboxManager,
         arguments: &str,
         cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     ) -> Result<String> {
         let args: ExecuteCommandArgs = serde_json::from_str(arguments)?;
+        let max_output_chars = self.max_output_chars;
+
+        let current_dir = working_dir(self.user_id, sandbox.container_id());
+        let dir = match args.cwd.as_deref().filter(|cwd| !cwd.trim().is_empty()) {
+            Some(cwd) => match resolve_working_dir(&current_dir, cwd) {
+                Ok(dir) => dir,
+                Err(e) => return Ok(format!("Invalid cwd: {e}")),
+            },
+            None => current_dir,
+        };
+
+        if let Err(e) = validate_env(self.user_id, &args.env) {
+            return Ok(format!("Invalid env: {e}"));
+        }
+        let mut env = session_env(self.user_id);
+        env.extend(args.env);
 
         // Pass cancellation_token to exec_command
         match sandbox
-            .exec_command(&args.command, cancellation_token)
+            .exec_command_in_dir(&args.command, &dir, &env, cancellation_token)
             .await
         {
             Ok(result) => {
@@ -166,13 +199,13 @@ impl SandboxProvider {
                     if result.stdout.is_empty() {
                         Ok("(command executed successfully, output is empty)".to_string())
                     } else {
-                        Ok(result.stdout)
+                        Ok(cap_command_output(&result.stdout, max_output_chars))
                     }
                 } else {
                     Ok(format!(
                         "Command failed (exit code {}): {}",
                         result.exit_code,
-                        result.combined_output()
+                        cap_command_output(&result.combined_output(), max_output_chars)
                     ))
                 }
             }
@@ -180,6 +213,52 @@ impl SandboxProvider {
         }
     }
 
+    /// Change the directory later `execute_command` calls start in
+    async fn handle_set_cwd(&self, sandbox: &SandboxManager, arguments: &str) -> Result<String> {
+        let args: SetCwdArgs = serde_json::from_str(arguments)?;
+        let dir = match resolve_working_dir(
+            &working_dir(self.user_id, sandbox.container_id()),
+            &args.path,
+        ) {
+            Ok(dir) => dir,
+            Err(e) => return Ok(format!("Error: {e}")),
+        };
+
+        let check = sandbox
+            .exec_command(&format!("test -d {}", shell_quote(&dir)), None)
+            .await?;
+        if !check.success() {
+            return Ok(format!("Error: directory {dir} does not exist"));
+        }
+
+        set_working_dir(self.user_id, sandbox.container_id(), dir.clone());
+        Ok(format!(
+            "Working directory set to {dir}. execute_command runs there unless it passes cwd."
+        ))
+    }
+
+    /// Store variables for every later `execute_command` of the session
+    fn handle_set_env(&self, arguments: &str) -> Result<String> {
+        let args: SetEnvArgs = serde_json::from_str(arguments)?;
+        if let Err(e) = validate_env(self.user_id, &args.env) {
+            return Ok(format!("Error: {e}"));
+        }
+
+        match update_session_env(self.user_id, args.env) {
+            Ok(env) if env.is_empty() => {
+                Ok("No environment variables are set for this session.".to_string())
+            }
+            Ok(env) => {
+                let names: Vec<&str> = env.keys().map(String::as_str).collect();
+                Ok(format!(
+                    "Environment variables for later commands: {}",
+                    names.join(", ")
+                ))
+            }
+            Err(e) => Ok(format!("Error: {e}")),
+        }
+    }
+
     async fn handle_write_file(sandbox: &SandboxManager, arguments: &str) -> Result<String> {
         let args: WriteFileArgs = serde_json::from_str(arguments)?;
         match sandbox
@@ -193,12 +272,45 @@ impl SandboxProvider {
 
     async fn handle_read_file(sandbox: &SandboxManager, arguments: &str) -> Result<String> {
         let args: ReadFileArgs = serde_json::from_str(arguments)?;
+        if args.start_line.is_some() || args.line_count.is_some() {
+            return Self::read_file_range(sandbox, &args).await;
+        }
+
         match sandbox.read_file(&args.path).await {
             Ok(content) => Ok(String::from_utf8_lossy(&content).to_string()),
             Err(e) => Ok(format!("Error reading file: {e}")),
         }
     }
 
+    /// Read a window of lines without transferring the whole file
+    async fn read_file_range(sandbox: &SandboxManager, args: &ReadFileArgs) -> Result<String> {
+        let start = args.start_line.unwrap_or(1).max(1);
+        let count = args
+            .line_count
+            .unwrap_or(READ_FILE_DEFAULT_LINES)
+            .clamp(1, READ_FILE_MAX_LINES);
+        let end = start.saturating_add(count - 1);
+        let path = shell_quote(&args.path);
+
+        let total = match sandbox
+            .exec_command(&format!("awk 'END {{ print NR }}' {path}"), None)
+            .await
+        {
+            Ok(result) if result.success() => result.stdout.trim().parse::<usize>().unwrap_or(0),
+            Ok(result) => return Ok(format!("Error reading file: {}", result.combined_output())),
+            Err(e) => return Ok(format!("Error reading file: {e}")),
+        };
+
+        let cmd = format!("sed -n '{start},{end}p;{end}q' {path}");
+        match sandbox.exec_command(&cmd, None).await {
+            Ok(result) if result.success() => {
+                Ok(format_line_range(&args.path, start, &result.stdout, total))
+            }
+            Ok(result) => Ok(format!("Error reading file: {}", result.combined_output())),
+            Err(e) => Ok(format!("Error reading file: {e}")),
+        }
+    }
+
     async fn handle_send_file(&self, sandbox: &SandboxManager, arguments: &str) -> Result<String> {
         let args: SendFileArgs = serde_json::from_str(arguments)?;
         info!(path = %args.path, "send_file_to_user called");
@@ -211,50 +323,118 @@ impl SandboxProvider {
             }
         };
 
-        let file_name = std::path::Path::new(&resolved_path)
+        Ok(self.send_sandbox_file(sandbox, &resolved_path).await)
+    }
+
+    /// Deliver a sandbox file through the confirmation flow, or as a file
+    /// host link when it exceeds the chat limit
+    async fn send_sandbox_file(&self, sandbox: &SandboxManager, resolved_path: &str) -> String {
+        let file_name = std::path::Path::new(resolved_path)
             .file_name()
             .map_or_else(|| "file".to_string(), |n| n.to_string_lossy().to_string());
 
-        let file_size = match sandbox.file_size_bytes(&resolved_path, None).await {
+        let file_size = match sandbox.file_size_bytes(resolved_path, None).await {
             Ok(size) => size,
             Err(e) => {
                 error!(resolved_path = %resolved_path, error = %e, "Failed to check file size");
-                return Ok(format!("❌ Error checking file size: {e}"));
+                return format!("❌ Error checking file size: {e}");
             }
         };
 
         if file_size == 0 {
-            return Ok(format!(
+            return format!(
                 "❌ ERROR: File '{file_name}' is empty (0 bytes) and cannot be sent.\n\
                  Path in sandbox: {resolved_path}"
-            ));
+            );
         }
 
-        if file_size > CHAT_DELIVERY_MAX_FILE_SIZE_BYTES {
-            return Ok(
-                "⚠️ ERROR: File too large for chat delivery (>50 MB). Please use the upload_file tool to upload it to the cloud."
-                    .to_string(),
-            );
+        if file_size > crate::config::get_chat_delivery_max_file_bytes() {
+            return upload_large_file(sandbox, resolved_path, &file_name, file_size, None).await;
         }
 
-        match sandbox.download_file(&resolved_path).await {
+        match sandbox.download_file(resolved_path).await {
             Ok(content) => {
-                let message = self
-                    .deliver_file_to_user(FileDeliveryRequest {
-                        file_name: file_name.clone(),
-                        content,
-                        sandbox_path: resolved_path.clone(),
-                    })
-                    .await;
-                Ok(message)
+                self.deliver_file_to_user(FileDeliveryRequest {
+                    file_name,
+                    content,
+                    sandbox_path: resolved_path.to_string(),
+                })
+                .await
             }
             Err(e) => {
-                error!(path = %args.path, resolved_path = %resolved_path, error = %e, "Failed to download file");
-                Ok(format!("❌ Error downloading file: {e}"))
+                error!(resolved_path = %resolved_path, error = %e, "Failed to download file");
+                format!("❌ Error downloading file: {e}")
             }
         }
     }
 
+    async fn handle_zip_and_send(
+        &self,
+        sandbox: &SandboxManager,
+        arguments: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<String> {
+        let args: ZipAndSendArgs = serde_json::from_str(arguments)?;
+        let archive = zip_archive_path(args.archive_name.as_deref());
+        let cmd = match build_zip_command(&archive, &args.paths) {
+            Ok(cmd) => cmd,
+            Err(e) => return Ok(format!("❌ {e}")),
+        };
+        info!(archive = %archive, paths = ?args.paths, "zip_and_send called");
+
+        let message = match sandbox.exec_command(&cmd, cancellation_token).await {
+            Ok(result) if result.success() => self.send_sandbox_file(sandbox, &archive).await,
+            Ok(result) => format!(
+                "❌ Failed to create archive (code {}): {}",
+                result.exit_code,
+                result.combined_output()
+            ),
+            Err(e) => format!("❌ Failed to create archive: {e}"),
+        };
+
+        // The archive is only a delivery vehicle; the source files stay
+        let rm_cmd = format!("rm -f {}", shell_quote(&archive));
+        if let Err(e) = sandbox.exec_command(&rm_cmd, None).await {
+            warn!(archive = %archive, error = %e, "Failed to remove archive from sandbox");
+        }
+        Ok(message)
+    }
+
+    async fn handle_sandbox_ps(sandbox: &SandboxManager) -> Result<String> {
+        // Fall back to `ps -ef` for busybox-based images without procps
+        let cmd = "ps aux 2>/dev/null || ps -ef";
+        match sandbox.exec_command(cmd, None).await {
+            Ok(result) if result.success() => Ok(format!(
+                "🧾 Sandbox processes:\n\n```\n{}\n```",
+                result.stdout.trim_end()
+            )),
+            Ok(result) => Ok(format!(
+                "❌ Error listing processes: {}",
+                result.combined_output()
+            )),
+            Err(e) => Ok(format!("❌ Error executing command: {e}")),
+        }
+    }
+
+    async fn handle_sandbox_kill(sandbox: &SandboxManager, arguments: &str) -> Result<String> {
+        let args: SandboxKillArgs = serde_json::from_str(arguments)?;
+        let cmd = match build_kill_command(args.pid, args.signal.as_deref()) {
+            Ok(cmd) => cmd,
+            Err(msg) => return Ok(format!("❌ {msg}")),
+        };
+
+        info!(pid = args.pid, command = %cmd, "sandbox_kill called");
+        match sandbox.exec_command(&cmd, None).await {
+            Ok(result) if result.success() => Ok(format!("✅ Signal sent to process {}", args.pid)),
+            Ok(result) => Ok(format!(
+                "❌ Failed to kill process {}: {}",
+                args.pid,
+                result.combined_output()
+            )),
+            Err(e) => Ok(format!("❌ Error executing command: {e}")),
+        }
+    }
+
     async fn handle_list_files(sandbox: &SandboxManager, arguments: &str) -> Result<String> {
         #[derive(Debug, Deserialize)]
         struct ListFilesArgs {
@@ -269,8 +449,8 @@ impl SandboxProvider {
         let args: ListFilesArgs = serde_json::from_str(arguments)?;
         let cmd = format!(
             "tree -L 3 -h --du {} 2>/dev/null || find {} -type f -o -type d | head -100",
-            escape(args.path.as_str().into()),
-            escape(args.path.as_str().into())
+            shell_quote(&args.path),
+            shell_quote(&args.path)
         );
 
         match sandbox.exec_command(&cmd, None).await {
@@ -294,6 +474,43 @@ impl SandboxProvider {
             Err(e) => Ok(format!("❌ Error executing command: {e}")),
         }
     }
+
+    async fn handle_search_files(sandbox: &SandboxManager, arguments: &str) -> Result<String> {
+        let args: SearchFilesArgs = serde_json::from_str(arguments)?;
+        if args.pattern.is_empty() {
+            return Ok("❌ Search pattern must not be empty".to_string());
+        }
+
+        let cmd = build_search_command(&args);
+        info!(pattern = %args.pattern, path = %args.path, "search_files called");
+
+        match sandbox.exec_command(&cmd, None).await {
+            // grep errors (e.g. a missing path) are piped into the output so the model sees them
+            Ok(result) => Ok(format_search_results(&args, &result.stdout)),
+            Err(e) => Ok(format!("❌ Error executing command: {e}")),
+        }
+    }
+}
+
+/// Keep the head and tail of oversized command output, with a notice in between
+///
+/// Errors and summaries usually sit at the end, so the tail is kept as well.
+fn cap_command_output(output: &str, max_chars: usize) -> String {
+    let total = output.chars().count();
+    if total <= max_chars {
+        return output.to_string();
+    }
+
+    let head_chars = max_chars / 2;
+    let tail_chars = max_chars - head_chars;
+    let head: String = output.chars().take(head_chars).collect();
+    let tail: String = output.chars().skip(total - tail_chars).collect();
+    let omitted = total - head_chars - tail_chars;
+    format!(
+        "{head}\n\n[... output truncated: {omitted} of {total} chars omitted. \
+         Narrow it down with grep, head/tail or sed -n, or redirect to a file \
+         and use search_files/read_file ...]\n\n{tail}"
+    )
 }
 
 #[cfg(test)]
@@ -373,6 +590,103 @@ mod tests {
         assert!(result.starts_with("⚠️"), "unexpected result: {result}");
     }
 
+    #[test]
+    fn zip_archive_path_stays_in_workspace() {
+        assert_eq!(zip_archive_path(None), "/workspace/files.zip");
+        assert_eq!(zip_archive_path(Some("report")), "/workspace/report.zip");
+        assert_eq!(
+            zip_archive_path(Some("../../etc/Results.ZIP")),
+            "/workspace/Results.ZIP"
+        );
+        assert_eq!(zip_archive_path(Some("  ")), "/workspace/files.zip");
+    }
+
+    #[test]
+    fn build_zip_command_quotes_paths() {
+        let paths = vec![
+            "out dir".to_string(),
+            "-notes.txt".to_string(),
+            " ".to_string(),
+        ];
+        assert_eq!(
+            build_zip_command("/workspace/files.zip", &paths),
+            Ok("cd /workspace && rm -f /workspace/files.zip && \
+                zip -r -q /workspace/files.zip 'out dir' ./-notes.txt 2>&1"
+                .to_string())
+        );
+        assert!(build_zip_command("/workspace/files.zip", &[]).is_err());
+    }
+
+    #[test]
+    fn build_search_command_quotes_arguments() {
+        let args = SearchFilesArgs {
+            pattern: "fn main(".to_string(),
+            path: "/workspace/my project".to_string(),
+            glob: Some("*.rs".to_string()),
+            ignore_case: true,
+        };
+        assert_eq!(
+            build_search_command(&args),
+            "grep -rnI --exclude-dir=.git --exclude-dir=node_modules -i --include='*.rs' \
+             -e 'fn main(' -- '/workspace/my project' 2>&1 | head -n 201"
+        );
+    }
+
+    #[test]
+    fn format_search_results_truncates_to_budget() {
+        let args = SearchFilesArgs {
+            pattern: "error".to_string(),
+            path: "/workspace".to_string(),
+            glob: None,
+            ignore_case: false,
+        };
+        assert_eq!(
+            format_search_results(&args, ""),
+            "No matches for 'error' in /workspace"
+        );
+
+        let long_line = format!("/workspace/a.log:1:{}", "x".repeat(400));
+        let output = std::iter::repeat_n(long_line.as_str(), SEARCH_FILES_MAX_MATCHES + 1)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let formatted = format_search_results(&args, &output);
+        assert!(formatted.starts_with("Found 200+ matches"));
+        assert!(formatted.contains("results truncated"));
+        assert!(formatted
+            .lines()
+            .all(|line| line.chars().count() <= SEARCH_FILES_MAX_LINE_CHARS + 3));
+    }
+
+    #[test]
+    fn format_line_range_reports_position() {
+        assert_eq!(
+            format_line_range("/workspace/app.log", 11, "k\nl\n", 30),
+            "Lines 11-12 of 30 in /workspace/app.log:\nk\nl\n\n(18 more lines; continue with start_line=13)"
+        );
+        assert_eq!(
+            format_line_range("a.txt", 1, "only\n", 1),
+            "Lines 1-1 of 1 in a.txt:\nonly\n\n(end of file)"
+        );
+        assert_eq!(
+            format_line_range("a.txt", 50, "", 10),
+            "No lines at 50 in a.txt: the file has 10 lines"
+        );
+    }
+
+    #[test]
+    fn build_kill_command_validates_pid_and_signal() {
+        assert_eq!(
+            build_kill_command(42, None).as_deref(),
+            Ok("kill -s TERM 42")
+        );
+        assert_eq!(
+            build_kill_command(42, Some("sigkill")).as_deref(),
+            Ok("kill -s KILL 42")
+        );
+        assert!(build_kill_command(1, None).is_err());
+        assert!(build_kill_command(42, Some("STOP; rm -rf /")).is_err());
+    }
+
     #[tokio::test]
     async fn deliver_file_rejects_empty_content() {
         let provider = SandboxProvider::new(1);
@@ -387,12 +701,56 @@ mod tests {
         assert!(result.starts_with("❌"), "unexpected result: {result}");
         assert!(result.contains("0 bytes"), "unexpected result: {result}");
     }
+
+    #[test]
+    fn cap_command_output_keeps_head_and_tail() {
+        let output = format!("HEAD{}TAIL", "x".repeat(1_000));
+        let capped = cap_command_output(&output, 100);
+
+        assert!(capped.starts_with(&output[..50]), "head lost: {capped}");
+        assert!(
+            capped.ends_with(&output[output.len() - 50..]),
+            "tail lost: {capped}"
+        );
+        assert!(capped.contains("output truncated: 908 of 1008 chars omitted"));
+        assert!(capped.contains("grep"));
+    }
+
+    #[test]
+    fn cap_command_output_leaves_short_output_alone() {
+        assert_eq!(cap_command_output("ok\n", 100), "ok\n");
+
+        let exact = "я".repeat(50);
+        assert_eq!(cap_command_output(&exact, 50), exact);
+
+        let capped = cap_command_output(&"я".repeat(51), 50);
+        assert!(capped.starts_with(&"я".repeat(25)));
+        assert!(capped.ends_with(&"я".repeat(25)));
+    }
 }
 
 /// Arguments for `execute_command` tool
 #[derive(Debug, Deserialize)]
 struct ExecuteCommandArgs {
     command: String,
+    /// Directory to run in for this call only
+    #[serde(default)]
+    cwd: Option<String>,
+    /// Variables for this call only, on top of the session's
+    #[serde(default)]
+    env: EnvVars,
+}
+
+/// Arguments for `set_env` tool
+#[derive(Debug, Deserialize)]
+struct SetEnvArgs {
+    env: EnvVars,
+}
+
+/// Arguments for `set_cwd` tool
+#[derive(Debug, Deserialize)]
+struct SetCwdArgs {
+    path: String,
 }
 
 /// Arguments for `write_file` tool
@@ -406,6 +764,35 @@ struct WriteFileArgs {
 #[derive(Debug, Deserialize)]
 struct ReadFileArgs {
     path: String,
+    /// First line to return (1-based); enables ranged reading
+    #[serde(default)]
+    start_line: Option<usize>,
+    /// Number of lines to return; enables ranged reading
+    #[serde(default)]
+    line_count: Option<usize>,
+}
+
+/// Format a ranged read with its position in the file
+fn format_line_range(path: &str, start: usize, lines: &str, total: usize) -> String {
+    let returned = lines.lines().count();
+    if returned == 0 {
+        return format!("No lines at {start} in {path}: the file has {total} lines");
+    }
+
+    let end = start + returned - 1;
+    let remaining = total.saturating_sub(end);
+    let footer = if remaining > 0 {
+        format!(
+            "{remaining} more lines; continue with start_line={}",
+            end + 1
+        )
+    } else {
+        "end of file".to_string()
+    };
+    format!(
+        "Lines {start}-{end} of {total} in {path}:\n{}\n\n({footer})",
+        lines.trim_end_matches('\n')
+    )
 }
 
 /// Arguments for `send_file_to_user` tool
@@ -414,6 +801,292 @@ struct SendFileArgs {
     path: String,
 }
 
+/// Arguments for `zip_and_send` tool
+#[derive(Debug, Deserialize)]
+struct ZipAndSendArgs {
+    paths: Vec<String>,
+    #[serde(default)]
+    archive_name: Option<String>,
+}
+
+/// Absolute path of the archive in [`ZIP_WORKDIR`], always ending in `.zip`
+///
+/// Only the file name part of `name` is used so the archive cannot land
+/// outside the workspace.
+fn zip_archive_path(name: Option<&str>) -> String {
+    let name = name
+        .and_then(|n| std::path::Path::new(n.trim()).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| ZIP_DEFAULT_NAME.to_string());
+    if name.to_ascii_lowercase().ends_with(".zip") {
+        format!("{ZIP_WORKDIR}/{name}")
+    } else {
+        format!("{ZIP_WORKDIR}/{name}.zip")
+    }
+}
+
+/// Build a `zip -r` over `paths` (files or directories) run from [`ZIP_WORKDIR`]
+///
+/// A stale archive with the same name is removed first so old entries do not
+/// leak into the new one.
+fn build_zip_command(archive: &str, paths: &[String]) -> std::result::Result<String, String> {
+    let paths: Vec<&str> = paths
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if paths.is_empty() {
+        return Err("No paths to archive: pass at least one file or directory".to_string());
+    }
+
+    let quoted: Vec<String> = paths
+        .iter()
+        .map(|p| {
+            // zip would read a leading dash as an option
+            if p.starts_with('-') {
+                shell_quote(&format!("./{p}"))
+            } else {
+                shell_quote(p)
+            }
+        })
+        .collect();
+    let archive = shell_quote(archive);
+    Ok(format!(
+        "cd {ZIP_WORKDIR} && rm -f {archive} && zip -r -q {archive} {} 2>&1",
+        quoted.join(" ")
+    ))
+}
+
+/// Arguments for `search_files` tool
+#[derive(Debug, Deserialize)]
+struct SearchFilesArgs {
+    pattern: String,
+    #[serde(default = "default_search_path")]
+    path: String,
+    #[serde(default)]
+    glob: Option<String>,
+    #[serde(default)]
+    ignore_case: bool,
+}
+
+fn default_search_path() -> String {
+    "/workspace".to_string()
+}
+
+/// Build a recursive `grep` over text files, capped one line past the budget
+/// so the formatter can tell whether results were cut.
+fn build_search_command(args: &SearchFilesArgs) -> String {
+    let mut cmd = "grep -rnI --exclude-dir=.git --exclude-dir=node_modules".to_string();
+    if args.ignore_case {
+        cmd.push_str(" -i");
+    }
+    if let Some(glob) = args.glob.as_deref().filter(|g| !g.is_empty()) {
+        cmd.push_str(&format!(" --include={}", shell_quote(glob)));
+    }
+    format!(
+        "{cmd} -e {} -- {} 2>&1 | head -n {}",
+        shell_quote(&args.pattern),
+        shell_quote(&args.path),
+        SEARCH_FILES_MAX_MATCHES + 1
+    )
+}
+
+/// Format `file:line:match` output, truncating long lines and the match list
+fn format_search_results(args: &SearchFilesArgs, output: &str) -> String {
+    let lines: Vec<&str> = output.lines().filter(|l| !l.is_empty()).collect();
+    if lines.is_empty() {
+        return format!("No matches for '{}' in {}", args.pattern, args.path);
+    }
+
+    let truncated = lines.len() > SEARCH_FILES_MAX_MATCHES;
+    let body = lines
+        .iter()
+        .take(SEARCH_FILES_MAX_MATCHES)
+        .map(|line| {
+            if line.chars().count() > SEARCH_FILES_MAX_LINE_CHARS {
+                let cut: String = line.chars().take(SEARCH_FILES_MAX_LINE_CHARS).collect();
+                format!("{cut}...")
+            } else {
+                (*line).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if truncated {
+        format!(
+            "Found {SEARCH_FILES_MAX_MATCHES}+ matches (format: file:line:match):\n{body}\n\n\
+             ⚠️ results truncated; narrow the search with `path` or `glob`"
+        )
+    } else {
+        format!(
+            "Found {} matches (format: file:line:match):\n{body}",
+            lines.len()
+        )
+    }
+}
+
+/// Arguments for `sandbox_kill` tool
+#[derive(Debug, Deserialize)]
+struct SandboxKillArgs {
+    pid: u32,
+    #[serde(default)]
+    signal: Option<String>,
+}
+
+/// Signals the agent is allowed to send via `sandbox_kill`
+const ALLOWED_KILL_SIGNALS: &[&str] = &["TERM", "KILL", "INT", "HUP"];
+
+/// Build a `kill` command for the sandbox, refusing PID 1 (the container's keep-alive process).
+fn build_kill_command(pid: u32, signal: Option<&str>) -> std::result::Result<String, String> {
+    if pid <= 1 {
+        return Err(format!(
+            "Refusing to kill PID {pid}: it keeps the sandbox container alive"
+        ));
+    }
+
+    let signal = signal
+        .map(|s| {
+            let upper = s.trim().to_ascii_uppercase();
+            upper.trim_start_matches("SIG").to_string()
+        })
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "TERM".to_string());
+
+    if !ALLOWED_KILL_SIGNALS.contains(&signal.as_str()) {
+        return Err(format!(
+            "Unsupported signal '{signal}' (allowed: {})",
+            ALLOWED_KILL_SIGNALS.join(", ")
+        ));
+    }
+
+    Ok(format!("kill -s {signal} {pid}"))
+}
+
+/// Tool definition for content search
+fn search_tool_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "search_files".to_string(),
+        description: "Search file contents in the sandbox (recursive grep). Returns matches as file:line:match, limited to 200 results. Prefer this over execute_command with grep.".to_string(),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "Regular expression to search for (grep basic syntax)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to search (defaults to /workspace)"
+                },
+                "glob": {
+                    "type": "string",
+                    "description": "Only search files whose name matches this glob, e.g. *.py"
+                },
+                "ignore_case": {
+                    "type": "boolean",
+                    "description": "Case-insensitive search (default: false)"
+                }
+            },
+            "required": ["pattern"]
+        }),
+    }
+}
+
+/// Tool definition for changing the `execute_command` working directory
+fn set_cwd_tool_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "set_cwd".to_string(),
+        description: "Set the directory execute_command runs in for the rest of the session, e.g. a cloned project. Must be inside /workspace.".to_string(),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory path, absolute or relative to the current directory"
+                }
+            },
+            "required": ["path"]
+        }),
+    }
+}
+
+/// Tool definition for session-wide `execute_command` environment variables
+fn set_env_tool_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "set_env".to_string(),
+        description: "Set environment variables for every later execute_command in this session, e.g. API keys or locale settings the user provided. An empty value unsets a variable. PATH, LD_* and other variables that change how commands run are refused.".to_string(),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "env": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Variables to set, as name → value"
+                }
+            },
+            "required": ["env"]
+        }),
+    }
+}
+
+/// Tool definition for archiving several files into one delivery
+fn zip_tool_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "zip_and_send".to_string(),
+        description: "Pack several files and/or directories from the sandbox into one ZIP archive and send it to the user. Prefer this over several send_file_to_user calls for multi-file results. The archive is removed from the sandbox afterwards; archives over the chat size limit are uploaded to file hosting and a download link is returned instead.".to_string(),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files or directories to include (relative to /workspace or absolute)"
+                },
+                "archive_name": {
+                    "type": "string",
+                    "description": "Name of the archive sent to the user (default: files.zip)"
+                }
+            },
+            "required": ["paths"]
+        }),
+    }
+}
+
+/// Tool definitions for process inspection and cleanup
+fn process_tool_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "sandbox_ps".to_string(),
+            description: "List processes running in the sandbox (ps aux). Use this to find stuck or leftover background processes after long-running commands.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        },
+        ToolDefinition {
+            name: "sandbox_kill".to_string(),
+            description: "Send a signal to a process in the sandbox by PID. Use after sandbox_ps to clean up stuck processes. Defaults to TERM; use KILL if the process ignores it.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "pid": {
+                        "type": "integer",
+                        "description": "Process ID from sandbox_ps"
+                    },
+                    "signal": {
+                        "type": "string",
+                        "enum": ALLOWED_KILL_SIGNALS,
+                        "description": "Signal to send (default: TERM)"
+                    }
+                },
+                "required": ["pid"]
+            }),
+        },
+    ]
+}
+
 #[async_trait]
 impl ToolProvider for SandboxProvider {
     fn name(&self) -> &'static str {
@@ -421,7 +1094,7 @@ impl ToolProvider for SandboxProvider {
     }
 
     fn tools(&self) -> Vec<ToolDefinition> {
-        vec![
+        let mut tools = vec![
             ToolDefinition {
                 name: "execute_command".to_string(),
                 description: "Execute a bash command in the isolated sandbox environment. Available commands include: python3, pip, ffmpeg, yt-dlp, curl, wget, date, cat, ls, grep, and other standard Unix tools.".to_string(),
@@ -431,6 +1104,15 @@ impl ToolProvider for SandboxProvider {
                         "command": {
                             "type": "string",
                             "description": "The bash command to execute"
+                        },
+                        "cwd": {
+                            "type": "string",
+                            "description": "Directory to run this command in (inside /workspace, relative to the current directory). Defaults to the directory set with set_cwd, or /workspace"
+                        },
+                        "env": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                            "description": "Environment variables for this command only, e.g. an API key the user gave you. Added to those set with set_env"
                         }
                     },
                     "required": ["command"]
@@ -456,13 +1138,21 @@ impl ToolProvider for SandboxProvider {
             },
             ToolDefinition {
                 name: "read_file".to_string(),
-                description: "Read content from a file in the sandbox.".to_string(),
+                description: "Read content from a file in the sandbox. For large files pass start_line/line_count to page through it; the result reports the total line count.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
                         "path": {
                             "type": "string",
                             "description": "Path to the file to read"
+                        },
+                        "start_line": {
+                            "type": "integer",
+                            "description": "First line to read (1-based). Omit both start_line and line_count to read the whole file"
+                        },
+                        "line_count": {
+                            "type": "integer",
+                            "description": "Number of lines to read (default 200, max 2000)"
                         }
                     },
                     "required": ["path"]
@@ -470,7 +1160,7 @@ impl ToolProvider for SandboxProvider {
             },
             ToolDefinition {
                 name: "send_file_to_user".to_string(),
-                description: "Send a file from the sandbox to the user via the chat transport. Use this when you need to deliver generated files, images, documents, or any output to the user. Supports both absolute paths (/workspace/file.txt) and relative paths (file.txt) - will automatically search in /workspace if not found.".to_string(),
+                description: "Send a file from the sandbox to the user via the chat transport. Use this when you need to deliver generated files, images, documents, or any output to the user. Supports both absolute paths (/workspace/file.txt) and relative paths (file.txt) - will automatically search in /workspace if not found. Files over the chat size limit are uploaded to file hosting and a download link is returned instead.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
@@ -495,13 +1185,29 @@ impl ToolProvider for SandboxProvider {
                     }
                 }),
             },
-        ]
+        ];
+        tools.insert(1, set_cwd_tool_definition());
+        tools.insert(2, set_env_tool_definition());
+        tools.push(zip_tool_definition());
+        tools.push(search_tool_definition());
+        tools.extend(process_tool_definitions());
+        tools
     }
 
     fn can_handle(&self, tool_name: &str) -> bool {
         matches!(
             tool_name,
-            "execute_command" | "read_file" | "write_file" | "send_file_to_user" | "list_files"
+            "execute_command"
+                | "set_cwd"
+                | "set_env"
+                | "read_file"
+                | "write_file"
+                | "send_file_to_user"
+                | "zip_and_send"
+                | "list_files"
+                | "search_files"
+                | "sandbox_ps"
+                | "sandbox_kill"
         )
     }
 
@@ -514,25 +1220,26 @@ impl ToolProvider for SandboxProvider {
     ) -> Result<String> {
         debug!(tool = tool_name, "Executing sandbox tool");
 
-        // Ensure sandbox is running
-        self.ensure_sandbox().await?;
-
-        let sandbox = {
-            let guard = self.sandbox.lock().await;
-            guard
-                .as_ref()
-                .cloned()
-                .ok_or_else(|| anyhow::anyhow!("Sandbox not initialized"))?
-        };
+        let sandbox = self.sandbox.get().await?;
 
         match tool_name {
             "execute_command" => {
-                Self::handle_execute_command(&sandbox, arguments, cancellation_token).await
+                self.handle_execute_command(&sandbox, arguments, cancellation_token)
+                    .await
             }
+            "set_cwd" => self.handle_set_cwd(&sandbox, arguments).await,
+            "set_env" => self.handle_set_env(arguments),
             "write_file" => Self::handle_write_file(&sandbox, arguments).await,
             "read_file" => Self::handle_read_file(&sandbox, arguments).await,
             "send_file_to_user" => self.handle_send_file(&sandbox, arguments).await,
+            "zip_and_send" => {
+                self.handle_zip_and_send(&sandbox, arguments, cancellation_token)
+                    .await
+            }
             "list_files" => Self::handle_list_files(&sandbox, arguments).await,
+            "search_files" => Self::handle_search_files(&sandbox, arguments).await,
+            "sandbox_ps" => Self::handle_sandbox_ps(&sandbox).await,
+            "sandbox_kill" => Self::handle_sandbox_kill(&sandbox, arguments).await,
             _ => anyhow::bail!("Unknown sandbox tool: {tool_name}"),
         }
     }
@@ -0,0 +1,68 @@
+//! Cache of formatted `web_search` results shared by the search backends
+
+use crate::config::{get_search_cache_ttl_secs, SEARCH_CACHE_MAX_ENTRIES};
+use moka::future::Cache;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Process-wide cache of formatted `web_search` results, keyed by normalized query.
+/// `None` when caching is disabled via `SEARCH_CACHE_TTL_SECS=0`.
+static SEARCH_CACHE: LazyLock<Option<Cache<String, String>>> = LazyLock::new(|| {
+    let ttl = get_search_cache_ttl_secs();
+    (ttl > 0).then(|| {
+        Cache::builder()
+            .max_capacity(SEARCH_CACHE_MAX_ENTRIES)
+            .time_to_live(Duration::from_secs(ttl))
+            .build()
+    })
+});
+
+/// Build a cache key that ignores case and whitespace differences.
+pub(super) fn search_cache_key(backend: &str, query: &str, max_results: u8, page: u8) -> String {
+    let normalized = query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    format!("{backend}:{max_results}:{page}:{normalized}")
+}
+
+/// Cached results for `key`, if caching is enabled and they have not expired
+pub(super) async fn cached_search(key: &str) -> Option<String> {
+    match SEARCH_CACHE.as_ref() {
+        Some(cache) => cache.get(key).await,
+        None => None,
+    }
+}
+
+/// Remember formatted results for `key`
+pub(super) async fn cache_search(key: String, output: String) {
+    if let Some(cache) = SEARCH_CACHE.as_ref() {
+        cache.insert(key, output).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::search_cache_key;
+
+    #[test]
+    fn test_search_cache_key_normalizes_query() {
+        assert_eq!(
+            search_cache_key("tavily", "  Rust   Async\tRuntime ", 5, 1),
+            search_cache_key("tavily", "rust async runtime", 5, 1)
+        );
+        assert_ne!(
+            search_cache_key("tavily", "rust async runtime", 5, 1),
+            search_cache_key("tavily", "rust async runtime", 10, 1)
+        );
+        assert_ne!(
+            search_cache_key("tavily", "rust async runtime", 5, 1),
+            search_cache_key("tavily", "rust async runtime", 5, 2)
+        );
+        assert_ne!(
+            search_cache_key("tavily", "rust async runtime", 5, 1),
+            search_cache_key("brave", "rust async runtime", 5, 1)
+        );
+    }
+}
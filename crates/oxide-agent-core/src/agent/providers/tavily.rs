@@ -2,6 +2,8 @@
 //!
 //! Provides `web_search` and `web_extract` tools using native Tavily Rust SDK.
 
+use super::search_cache::{cache_search, cached_search, search_cache_key};
+use super::web_search::{format_search_results, web_search_tool, SearchHit, WebSearchArgs};
 use crate::agent::provider::ToolProvider;
 use crate::llm::ToolDefinition;
 use anyhow::Result;
@@ -12,6 +14,9 @@ use std::time::Duration;
 use tavily::Tavily;
 use tracing::debug;
 
+/// Most results Tavily returns for one query
+const TAVILY_MAX_RESULTS: usize = 20;
+
 /// Provider for Tavily web search tools
 pub struct TavilyProvider {
     client: Tavily,
@@ -36,18 +41,50 @@ impl TavilyProvider {
             api_key: api_key.to_string(),
         })
     }
-}
 
-/// Arguments for `web_search` tool
-#[derive(Debug, Deserialize)]
-struct WebSearchArgs {
-    query: String,
-    #[serde(default = "default_max_results")]
-    max_results: u8,
-}
+    /// Tavily has no offset, so later pages fetch everything up to the end
+    /// of the page and drop the results already shown
+    async fn web_search(&self, args: &WebSearchArgs) -> Result<String> {
+        let max_results = args.max_results();
+        let cache_key = search_cache_key("tavily", &args.query, max_results, args.page());
+        if let Some(cached) = cached_search(&cache_key).await {
+            debug!(query = %args.query, "Tavily web search served from cache");
+            return Ok(format!("(cached)\n{cached}"));
+        }
 
-const fn default_max_results() -> u8 {
-    5
+        let wanted = args.offset() + usize::from(max_results);
+        if wanted > TAVILY_MAX_RESULTS {
+            return Ok(format!(
+                "Tavily returns at most {TAVILY_MAX_RESULTS} results per query, page {} is past the end. Refine the query instead.",
+                args.page()
+            ));
+        }
+        debug!(query = %args.query, max_results, page = args.page(), "Tavily web search");
+
+        let request = tavily::SearchRequest::new(&self.api_key, &args.query)
+            .max_results(i32::try_from(wanted).unwrap_or(i32::MAX))
+            .search_depth("basic");
+
+        match self.client.call(&request).await {
+            Ok(response) => {
+                let has_more = response.results.len() >= wanted && wanted < TAVILY_MAX_RESULTS;
+                let hits: Vec<SearchHit> = response
+                    .results
+                    .into_iter()
+                    .skip(args.offset())
+                    .map(|result| SearchHit {
+                        title: result.title,
+                        url: result.url,
+                        content: result.content,
+                    })
+                    .collect();
+                let output = format_search_results(args, &hits, has_more);
+                cache_search(cache_key, output.clone()).await;
+                Ok(output)
+            }
+            Err(e) => Ok(format!("Search error: {e}")),
+        }
+    }
 }
 
 /// Arguments for `web_extract` tool
@@ -64,24 +101,7 @@ impl ToolProvider for TavilyProvider {
 
     fn tools(&self) -> Vec<ToolDefinition> {
         vec![
-            ToolDefinition {
-                name: "web_search".to_string(),
-                description: "Search the web for current information. Use for news, facts, documentation, real-time data. Returns relevant search results with titles, URLs, and content snippets.".to_string(),
-                parameters: json!({
-                    "type": "object",
-                    "properties": {
-                        "query": {
-                            "type": "string",
-                            "description": "The search query"
-                        },
-                        "max_results": {
-                            "type": "integer",
-                            "description": "Maximum number of results (1-10, default: 5)"
-                        }
-                    },
-                    "required": ["query"]
-                }),
-            },
+            web_search_tool(),
             ToolDefinition {
                 name: "web_extract".to_string(),
                 description: "Extract and read content from web pages. Use to read articles, documentation, blog posts. Returns the full text content of the pages.".to_string(),
@@ -117,37 +137,7 @@ impl ToolProvider for TavilyProvider {
         match tool_name {
             "web_search" => {
                 let args: WebSearchArgs = serde_json::from_str(arguments)?;
-                let max_results = args.max_results.clamp(1, 10);
-
-                debug!(query = %args.query, max_results = max_results, "Tavily web search");
-
-                let request = tavily::SearchRequest::new(&self.api_key, &args.query)
-                    .max_results(i32::from(max_results))
-                    .search_depth("basic");
-
-                match self.client.call(&request).await {
-                    Ok(response) => {
-                        let mut output = format!("## Search results for: {}\n\n", args.query);
-
-                        if response.results.is_empty() {
-                            output.push_str("No results found for this query.\n");
-                        } else {
-                            for (i, result) in response.results.iter().enumerate() {
-                                let _ = write!(
-                                    output,
-                                    "### {}. {}\n**URL**: {}\n\n{}\n\n---\n\n",
-                                    i + 1,
-                                    crate::utils::clean_html(&result.title),
-                                    result.url,
-                                    crate::utils::clean_html(&result.content)
-                                );
-                            }
-                        }
-
-                        Ok(output)
-                    }
-                    Err(e) => Ok(format!("Search error: {e}")),
-                }
+                self.web_search(&args).await
             }
             "web_extract" => {
                 let args: WebExtractArgs = serde_json::from_str(arguments)?;
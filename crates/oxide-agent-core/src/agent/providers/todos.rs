@@ -2,6 +2,8 @@
 //!
 //! Provides `write_todos` tool for creating and managing task lists,
 //! enabling proactive agent behavior for complex multi-step requests.
+//! `update_todo` changes the status of a single item, so progress updates do
+//! not have to repeat the whole list.
 
 use crate::agent::provider::ToolProvider;
 use crate::llm::ToolDefinition;
@@ -142,6 +144,27 @@ impl TodoList {
         self.items = items;
         self.updated_at = Some(Utc::now());
     }
+
+    /// Set the status of the item with the given 1-based number
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such item.
+    pub fn set_status(&mut self, number: usize, status: TodoStatus) -> Result<&TodoItem> {
+        let total = self.items.len();
+        let Some(item) = number
+            .checked_sub(1)
+            .and_then(|index| self.items.get_mut(index))
+        else {
+            if total == 0 {
+                anyhow::bail!("The task list is empty, create it with write_todos first");
+            }
+            anyhow::bail!("There is no task {number}, the list has tasks 1-{total}");
+        };
+        item.status = status;
+        self.updated_at = Some(Utc::now());
+        Ok(item)
+    }
 }
 
 /// Arguments for `write_todos` tool
@@ -156,6 +179,14 @@ struct TodoItemArg {
     status: TodoStatus,
 }
 
+/// Arguments for `update_todo` tool
+#[derive(Debug, Deserialize)]
+struct UpdateTodoArgs {
+    /// 1-based number as shown in the task list
+    index: usize,
+    status: TodoStatus,
+}
+
 /// Provider for managing todo lists
 pub struct TodosProvider {
     /// Shared todo list state
@@ -172,6 +203,63 @@ impl TodosProvider {
     pub async fn get_todos(&self) -> TodoList {
         self.todos.lock().await.clone()
     }
+
+    async fn write_todos(&self, arguments: &str) -> Result<String> {
+        let args: WriteTodosArgs = serde_json::from_str(arguments)?;
+
+        // Convert to TodoItems with XML sanitization to prevent UI corruption
+        // LLM may include XML tags in task descriptions which would break formatting
+        let items: Vec<TodoItem> = args
+            .todos
+            .into_iter()
+            .map(|arg| TodoItem {
+                description: crate::agent::sanitize_xml_tags(&arg.description),
+                status: arg.status,
+            })
+            .collect();
+
+        let mut todos = self.todos.lock().await;
+        todos.update(items);
+        Ok(summarize(&todos))
+    }
+
+    async fn update_todo(&self, arguments: &str) -> Result<String> {
+        let args: UpdateTodoArgs = serde_json::from_str(arguments)?;
+
+        let mut todos = self.todos.lock().await;
+        let item = todos.set_status(args.index, args.status)?;
+        debug!(index = args.index, status = ?item.status, "Todo status updated");
+        Ok(summarize(&todos))
+    }
+}
+
+/// Log the list state and describe it for the model
+fn summarize(todos: &TodoList) -> String {
+    let completed = todos.completed_count();
+    let total = todos.items.len();
+    let current = todos.current_task().map(|t| t.description.as_str());
+
+    info!(
+        completed = completed,
+        total = total,
+        current = ?current,
+        "Todos updated"
+    );
+
+    current.map_or_else(
+        || {
+            if todos.is_complete() {
+                format!("✅ All tasks completed! ({completed}/{total})")
+            } else {
+                format!("✅ Task list updated ({completed}/{total} completed)")
+            }
+        },
+        |current_task| {
+            format!(
+                "✅ Task list updated ({completed}/{total} completed)\n🔄 Current task: {current_task}"
+            )
+        },
+    )
 }
 
 #[async_trait]
@@ -181,43 +269,66 @@ impl ToolProvider for TodosProvider {
     }
 
     fn tools(&self) -> Vec<ToolDefinition> {
-        vec![ToolDefinition {
-            name: "write_todos".to_string(),
-            description: "Create or update a list of tasks for the current request. \
-                ABSOLUTELY use it for complex requests that require multiple steps \
-                (research, comparison, analysis). Create a plan BEFORE starting work. \
-                DO NOT GIVE a final answer until all tasks are completed."
-                .to_string(),
-            parameters: json!({
-                "type": "object",
-                "properties": {
-                    "todos": {
-                        "type": "array",
-                        "description": "Full list of tasks (replaces previous list)",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "description": {
-                                    "type": "string",
-                                    "description": "Task description"
+        vec![
+            ToolDefinition {
+                name: "write_todos".to_string(),
+                description: "Create or update a list of tasks for the current request. \
+                    ABSOLUTELY use it for complex requests that require multiple steps \
+                    (research, comparison, analysis). Create a plan BEFORE starting work. \
+                    DO NOT GIVE a final answer until all tasks are completed."
+                    .to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "todos": {
+                            "type": "array",
+                            "description": "Full list of tasks (replaces previous list)",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "description": {
+                                        "type": "string",
+                                        "description": "Task description"
+                                    },
+                                    "status": {
+                                        "type": "string",
+                                        "enum": ["pending", "in_progress", "completed", "cancelled"],
+                                        "description": "Task status. Only ONE task can be in_progress."
+                                    }
                                 },
-                                "status": {
-                                    "type": "string",
-                                    "enum": ["pending", "in_progress", "completed", "cancelled"],
-                                    "description": "Task status. Only ONE task can be in_progress."
-                                }
-                            },
-                            "required": ["description", "status"]
+                                "required": ["description", "status"]
+                            }
+                        }
+                    },
+                    "required": ["todos"]
+                }),
+            },
+            ToolDefinition {
+                name: "update_todo".to_string(),
+                description: "Change the status of ONE task in the current list, e.g. mark it \
+                    in_progress or completed. Cheaper than rewriting the list with write_todos."
+                    .to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "index": {
+                            "type": "integer",
+                            "description": "Task number as shown in the task list, starting at 1"
+                        },
+                        "status": {
+                            "type": "string",
+                            "enum": ["pending", "in_progress", "completed", "cancelled"],
+                            "description": "New task status"
                         }
-                    }
-                },
-                "required": ["todos"]
-            }),
-        }]
+                    },
+                    "required": ["index", "status"]
+                }),
+            },
+        ]
     }
 
     fn can_handle(&self, tool_name: &str) -> bool {
-        tool_name == "write_todos"
+        matches!(tool_name, "write_todos" | "update_todo")
     }
 
     async fn execute(
@@ -229,58 +340,11 @@ impl ToolProvider for TodosProvider {
     ) -> Result<String> {
         debug!(tool = tool_name, "Executing todos tool");
 
-        if tool_name != "write_todos" {
-            anyhow::bail!("Unknown todos tool: {tool_name}");
+        match tool_name {
+            "write_todos" => self.write_todos(arguments).await,
+            "update_todo" => self.update_todo(arguments).await,
+            _ => anyhow::bail!("Unknown todos tool: {tool_name}"),
         }
-
-        let args: WriteTodosArgs = serde_json::from_str(arguments)?;
-
-        // Convert to TodoItems with XML sanitization to prevent UI corruption
-        // LLM may include XML tags in task descriptions which would break formatting
-        let items: Vec<TodoItem> = args
-            .todos
-            .into_iter()
-            .map(|arg| TodoItem {
-                description: crate::agent::sanitize_xml_tags(&arg.description),
-                status: arg.status,
-            })
-            .collect();
-
-        // Update the shared todo list and get state for response
-        let (completed, total, current, is_all_complete) = {
-            let mut todos = self.todos.lock().await;
-            todos.update(items);
-            let completed = todos.completed_count();
-            let total = todos.items.len();
-            let current = todos.current_task().map(|t| t.description.clone());
-            let is_all_complete = todos.is_complete();
-            drop(todos);
-            (completed, total, current, is_all_complete)
-        };
-
-        info!(
-            completed = completed,
-            total = total,
-            current = ?current,
-            "Todos updated"
-        );
-
-        let response = current.map_or_else(
-            || {
-                if is_all_complete {
-                    format!("✅ All tasks completed! ({completed}/{total})")
-                } else {
-                    format!("✅ Task list updated ({completed}/{total} completed)")
-                }
-            },
-            |current_task| {
-                format!(
-                    "✅ Task list updated ({completed}/{total} completed)\n🔄 Current task: {current_task}"
-                )
-            },
-        );
-
-        Ok(response)
     }
 }
 
@@ -396,4 +460,67 @@ mod tests {
         drop(list);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_update_todo_changes_one_item() -> Result<(), Box<dyn std::error::Error>> {
+        let todos = Arc::new(Mutex::new(TodoList::new()));
+        let provider = TodosProvider::new(todos.clone());
+        provider
+            .execute(
+                "write_todos",
+                r#"{"todos": [
+                    {"description": "Task 1", "status": "in_progress"},
+                    {"description": "Task 2", "status": "pending"}
+                ]}"#,
+                None,
+                None,
+            )
+            .await?;
+
+        let result = provider
+            .execute(
+                "update_todo",
+                r#"{"index": 1, "status": "completed"}"#,
+                None,
+                None,
+            )
+            .await?;
+        assert!(result.contains("1/2 completed"));
+
+        let result = provider
+            .execute(
+                "update_todo",
+                r#"{"index": 2, "status": "completed"}"#,
+                None,
+                None,
+            )
+            .await?;
+        assert!(result.contains("All tasks completed"));
+
+        let list = todos.lock().await;
+        assert_eq!(list.items[0].description, "Task 1");
+        assert!(list.is_complete());
+        drop(list);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_todo_rejects_unknown_index() {
+        let todos = Arc::new(Mutex::new(TodoList::new()));
+        let provider = TodosProvider::new(todos.clone());
+        let args = r#"{"index": 1, "status": "completed"}"#;
+        let Err(error) = provider.execute("update_todo", args, None, None).await else {
+            panic!("empty list should be rejected");
+        };
+        assert!(error.to_string().contains("write_todos"));
+
+        todos.lock().await.update(vec![TodoItem::new("Only task")]);
+        for index in [0, 2] {
+            let args = format!(r#"{{"index": {index}, "status": "completed"}}"#);
+            let Err(error) = provider.execute("update_todo", &args, None, None).await else {
+                panic!("index {index} should be rejected");
+            };
+            assert!(error.to_string().contains("tasks 1-1"));
+        }
+    }
 }
@@ -0,0 +1,178 @@
+//! `web_search` tool shared by the search backends
+//!
+//! Every backend exposes the same tool definition and output format, so the
+//! agent prompt does not depend on which one is configured. Results are paged:
+//! the agent passes `page` to continue a search instead of repeating it, and
+//! the output says whether another page is available.
+
+use crate::llm::ToolDefinition;
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt::Write;
+
+/// Highest page the agent may request
+pub(super) const MAX_SEARCH_PAGE: u8 = 10;
+
+/// Arguments for `web_search` tool
+#[derive(Debug, Deserialize)]
+pub(super) struct WebSearchArgs {
+    pub(super) query: String,
+    #[serde(default = "default_max_results")]
+    max_results: u8,
+    #[serde(default = "default_page")]
+    page: u8,
+}
+
+const fn default_max_results() -> u8 {
+    5
+}
+
+const fn default_page() -> u8 {
+    1
+}
+
+impl WebSearchArgs {
+    /// Results per page, 1-10
+    pub(super) fn max_results(&self) -> u8 {
+        self.max_results.clamp(1, 10)
+    }
+
+    /// Requested page, starting at 1
+    pub(super) fn page(&self) -> u8 {
+        self.page.clamp(1, MAX_SEARCH_PAGE)
+    }
+
+    /// Number of results on the pages before this one
+    pub(super) fn offset(&self) -> usize {
+        usize::from(self.page() - 1) * usize::from(self.max_results())
+    }
+}
+
+/// One search result, before formatting
+pub(super) struct SearchHit {
+    pub(super) title: String,
+    pub(super) url: String,
+    pub(super) content: String,
+}
+
+/// Definition of the `web_search` tool
+pub(super) fn web_search_tool() -> ToolDefinition {
+    ToolDefinition {
+        name: "web_search".to_string(),
+        description: "Search the web for current information. Use for news, facts, documentation, real-time data. Returns relevant search results with titles, URLs, and content snippets. If the output says more results are available, call again with the same query and the next `page` instead of repeating the search.".to_string(),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "The search query"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "description": "Maximum number of results per page (1-10, default: 5)"
+                },
+                "page": {
+                    "type": "integer",
+                    "description": format!("Page of results to return (1-{MAX_SEARCH_PAGE}, default: 1)")
+                }
+            },
+            "required": ["query"]
+        }),
+    }
+}
+
+/// Format one page of results; numbering continues across pages
+pub(super) fn format_search_results(
+    args: &WebSearchArgs,
+    hits: &[SearchHit],
+    has_more: bool,
+) -> String {
+    let page = args.page();
+    let mut output = format!("## Search results for: {}\n\n", args.query);
+    if page > 1 {
+        let _ = write!(output, "Page {page}\n\n");
+    }
+
+    if hits.is_empty() {
+        output.push_str("No results found for this query.\n");
+        return output;
+    }
+
+    for (i, hit) in hits.iter().enumerate() {
+        let _ = write!(
+            output,
+            "### {}. {}\n**URL**: {}\n\n{}\n\n---\n\n",
+            args.offset() + i + 1,
+            crate::utils::clean_html(&hit.title),
+            hit.url,
+            crate::utils::clean_html(&hit.content)
+        );
+    }
+
+    if has_more && page < MAX_SEARCH_PAGE {
+        let _ = writeln!(
+            output,
+            "More results are available: call web_search with the same query and page {}.",
+            page + 1
+        );
+    } else {
+        output.push_str("No more results for this query.\n");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(json: &str) -> WebSearchArgs {
+        match serde_json::from_str(json) {
+            Ok(args) => args,
+            Err(e) => panic!("invalid args: {e}"),
+        }
+    }
+
+    fn hit(n: usize) -> SearchHit {
+        SearchHit {
+            title: format!("Result {n}"),
+            url: format!("https://example.com/{n}"),
+            content: "snippet".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_page_defaults_and_bounds() {
+        let first = args(r#"{"query": "rust"}"#);
+        assert_eq!(
+            (first.page(), first.max_results(), first.offset()),
+            (1, 5, 0)
+        );
+
+        let third = args(r#"{"query": "rust", "max_results": 4, "page": 3}"#);
+        assert_eq!(third.offset(), 8);
+
+        let clamped = args(r#"{"query": "rust", "max_results": 0, "page": 200}"#);
+        assert_eq!(
+            (clamped.page(), clamped.max_results()),
+            (MAX_SEARCH_PAGE, 1)
+        );
+    }
+
+    #[test]
+    fn test_numbering_continues_and_next_page_is_announced() {
+        let second = args(r#"{"query": "rust", "max_results": 2, "page": 2}"#);
+        let output = format_search_results(&second, &[hit(3), hit(4)], true);
+        assert!(output.starts_with("## Search results for: rust\n\nPage 2\n\n### 3. Result 3"));
+        assert!(output.contains("### 4. Result 4"));
+        assert!(output.ends_with("call web_search with the same query and page 3.\n"));
+
+        let last = format_search_results(&second, &[hit(3)], false);
+        assert!(last.ends_with("No more results for this query.\n"));
+    }
+
+    #[test]
+    fn test_empty_page() {
+        let output = format_search_results(&args(r#"{"query": "zzz"}"#), &[], true);
+        assert!(output.ends_with("No results found for this query.\n"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Working directory of `execute_command`
+//!
+//! `set_cwd` stores a directory per session that later commands start in, so
+//! the agent does not have to `cd` into a cloned project every time. Paths are
+//! normalized lexically and must stay inside `/workspace`. The directory is
+//! tied to the container it was set in, so after the sandbox is destroyed or
+//! recreated commands start in `/workspace` again.
+
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+/// Root every working directory must stay in
+pub(super) const WORKSPACE_DIR: &str = "/workspace";
+
+/// Directory set in a specific container
+struct WorkingDir {
+    container_id: String,
+    dir: String,
+}
+
+/// Current directory per session; sessions without an entry use `/workspace`
+static WORKING_DIRS: LazyLock<Mutex<HashMap<i64, WorkingDir>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn working_dirs() -> std::sync::MutexGuard<'static, HashMap<i64, WorkingDir>> {
+    WORKING_DIRS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Current directory of the session's commands in container `container_id`
+///
+/// A directory set in another container, one since destroyed or recreated,
+/// is dropped in favor of `/workspace`.
+pub(super) fn working_dir(session_id: i64, container_id: Option<&str>) -> String {
+    let mut dirs = working_dirs();
+    match dirs.get(&session_id) {
+        Some(saved) if Some(saved.container_id.as_str()) == container_id => saved.dir.clone(),
+        Some(_) => {
+            dirs.remove(&session_id);
+            WORKSPACE_DIR.to_string()
+        }
+        None => WORKSPACE_DIR.to_string(),
+    }
+}
+
+/// Make `dir` (already resolved) the session's current directory in
+/// container `container_id`
+pub(super) fn set_working_dir(session_id: i64, container_id: Option<&str>, dir: String) {
+    match container_id {
+        Some(container_id) if dir != WORKSPACE_DIR => {
+            working_dirs().insert(
+                session_id,
+                WorkingDir {
+                    container_id: container_id.to_string(),
+                    dir,
+                },
+            );
+        }
+        _ => {
+            working_dirs().remove(&session_id);
+        }
+    }
+}
+
+/// Go back to `/workspace`, e.g. when the session is reset
+pub(crate) fn clear_working_dir(session_id: i64) {
+    working_dirs().remove(&session_id);
+}
+
+/// Resolve `requested` against `current` and check it stays in `/workspace`
+///
+/// Relative paths start at `current`; `.` and `..` are folded without
+/// touching the file system.
+///
+/// # Errors
+///
+/// Returns an error if the path leaves `/workspace`.
+pub(super) fn resolve_working_dir(current: &str, requested: &str) -> Result<String> {
+    let requested = requested.trim();
+    let joined = if requested.starts_with('/') {
+        requested.to_string()
+    } else {
+        format!("{current}/{requested}")
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    let resolved = format!("/{}", parts.join("/"));
+
+    let inside = resolved
+        .strip_prefix(WORKSPACE_DIR)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+    if !inside {
+        bail!("Working directory must be inside {WORKSPACE_DIR}, got '{resolved}'");
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_paths_resolve_against_current_dir() {
+        let resolve = |current, requested| resolve_working_dir(current, requested).ok();
+        assert_eq!(
+            resolve("/workspace", "repo"),
+            Some("/workspace/repo".to_string())
+        );
+        assert_eq!(
+            resolve("/workspace/repo", "./src/../tests/"),
+            Some("/workspace/repo/tests".to_string())
+        );
+        assert_eq!(
+            resolve("/workspace/repo", ".."),
+            Some("/workspace".to_string())
+        );
+        assert_eq!(
+            resolve("/workspace/repo", "/workspace//other"),
+            Some("/workspace/other".to_string())
+        );
+    }
+
+    #[test]
+    fn test_paths_outside_workspace_are_rejected() {
+        for requested in [
+            "/",
+            "/etc",
+            "..",
+            "../../etc",
+            "/workspace-other",
+            "/workspace/../root",
+        ] {
+            assert!(
+                resolve_working_dir("/workspace", requested).is_err(),
+                "accepted {requested}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_working_dir_persists_per_session() {
+        let session_id = -8_000_003;
+        let container = Some("c1");
+        assert_eq!(working_dir(session_id, container), WORKSPACE_DIR);
+
+        set_working_dir(session_id, container, "/workspace/repo".to_string());
+        assert_eq!(working_dir(session_id, container), "/workspace/repo");
+        assert_eq!(working_dir(-8_000_004, container), WORKSPACE_DIR);
+
+        clear_working_dir(session_id);
+        assert_eq!(working_dir(session_id, container), WORKSPACE_DIR);
+    }
+
+    #[test]
+    fn test_recreated_container_starts_in_workspace() {
+        let session_id = -8_000_005;
+        set_working_dir(session_id, Some("old"), "/workspace/repo".to_string());
+
+        assert_eq!(working_dir(session_id, Some("new")), WORKSPACE_DIR);
+        // The stale entry is gone for good
+        assert_eq!(working_dir(session_id, Some("old")), WORKSPACE_DIR);
+    }
+}
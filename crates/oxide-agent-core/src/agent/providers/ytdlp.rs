@@ -7,18 +7,22 @@
 
 use crate::agent::progress::AgentEvent;
 use crate::agent::provider::ToolProvider;
+use crate::config::ToolOutputLimits;
 use crate::llm::ToolDefinition;
-use crate::sandbox::SandboxManager;
+use crate::sandbox::{shell_quote, SandboxHandle, SandboxManager, ShellCommand};
+use crate::utils::truncate_with_notice;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::json;
 use std::fmt::Write;
-use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
 use tracing::{debug, info, warn};
 
+use super::delivery::{already_delivered_message, claim_delivery};
+use super::filehoster::upload_large_file;
+
 /// Patterns indicating fatal, unrecoverable yt-dlp errors
 /// that should stop execution immediately
 const FATAL_ERROR_PATTERNS: &[&str] = &[
@@ -74,20 +78,17 @@ fn is_retryable_ytdlp_error(error_msg: &str) -> bool {
         .any(|pattern| error_msg.contains(pattern))
 }
 
-/// Maximum character limit for transcript output (to avoid LLM context overflow)
-const MAX_TRANSCRIPT_LENGTH: usize = 50_000;
-
-/// Maximum character limit for metadata output
-const MAX_METADATA_LENGTH: usize = 25_000;
-
 /// Directory inside sandbox for downloaded media
 const DOWNLOADS_DIR: &str = "/workspace/downloads";
 
 /// Provider for yt-dlp video tools (executed in sandbox)
 pub struct YtdlpProvider {
-    sandbox: Arc<Mutex<Option<SandboxManager>>>,
+    sandbox: SandboxHandle,
+    /// Container that already has the downloads directory
+    prepared_container: Mutex<Option<String>>,
     user_id: i64,
     progress_tx: Option<Sender<AgentEvent>>,
+    output_limits: ToolOutputLimits,
 }
 
 impl YtdlpProvider {
@@ -95,12 +96,21 @@ impl YtdlpProvider {
     #[must_use]
     pub fn new(user_id: i64) -> Self {
         Self {
-            sandbox: Arc::new(Mutex::new(None)),
+            sandbox: SandboxHandle::new(user_id),
+            prepared_container: Mutex::new(None),
             user_id,
             progress_tx: None,
+            output_limits: ToolOutputLimits::default(),
         }
     }
 
+    /// Set the transcript and metadata size limits
+    #[must_use]
+    pub const fn with_output_limits(mut self, limits: ToolOutputLimits) -> Self {
+        self.output_limits = limits;
+        self
+    }
+
     /// Set the progress channel for sending events (like file transfers)
     #[must_use]
     pub fn with_progress_tx(mut self, tx: Sender<AgentEvent>) -> Self {
@@ -108,21 +118,25 @@ impl YtdlpProvider {
         self
     }
 
-    /// Ensure sandbox is running
+    /// Work in a sandbox shared with other providers
+    #[must_use]
+    pub fn with_sandbox(mut self, sandbox: SandboxHandle) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Ensure sandbox is running and has the downloads directory
     async fn ensure_sandbox(&self) -> Result<()> {
-        if self
-            .sandbox
-            .lock()
-            .await
-            .as_ref()
-            .is_some_and(SandboxManager::is_running)
-        {
+        let sandbox = self.sandbox.get().await?;
+        let mut prepared = self.prepared_container.lock().await;
+        if prepared.is_some() && prepared.as_deref() == sandbox.container_id() {
             return Ok(());
         }
 
-        debug!(user_id = self.user_id, "Creating sandbox for YtdlpProvider");
-        let mut sandbox = SandboxManager::new(self.user_id).await?;
-        sandbox.create_sandbox().await?;
+        debug!(
+            user_id = self.user_id,
+            "Preparing sandbox for YtdlpProvider"
+        );
 
         // Create downloads directory
         sandbox
@@ -145,17 +159,67 @@ impl YtdlpProvider {
             }
         });
 
-        *self.sandbox.lock().await = Some(sandbox);
+        *prepared = sandbox.container_id().map(str::to_string);
         Ok(())
     }
 
     /// Get sandbox reference
     async fn get_sandbox(&self) -> Result<SandboxManager> {
-        let guard = self.sandbox.lock().await;
-        guard
-            .as_ref()
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Sandbox not initialized"))
+        self.sandbox.get().await
+    }
+
+    /// Check the video length against `YTDLP_MAX_DURATION_SECS` before downloading
+    ///
+    /// With `chapter` set, the chapter's own span is checked. Returns a
+    /// refusal message for the model, also when the length cannot be fetched.
+    async fn check_duration(
+        &self,
+        url: &str,
+        start: Option<&str>,
+        end: Option<&str>,
+        chapter: Option<&str>,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Option<String> {
+        let max_secs = crate::config::get_ytdlp_max_duration_secs();
+        if max_secs == 0 {
+            return None;
+        }
+
+        let length = self
+            .exec_ytdlp(&duration_command(url), cancellation_token)
+            .await
+            .and_then(|output| {
+                serde_json::from_str::<VideoLength>(output.trim()).map_err(anyhow::Error::from)
+            });
+        let length = match length {
+            Ok(length) => length,
+            Err(e) => {
+                warn!(error = %e, url = %url, "Failed to fetch video duration");
+                return Some(format!(
+                    "❌ Could not check the video length, so the download was refused: {e}"
+                ));
+            }
+        };
+
+        let refusal = match chapter {
+            Some(title) => chapter_refusal(&length, title, max_secs),
+            None => None,
+        }
+        .or_else(|| {
+            duration_refusal(
+                length.duration,
+                length.is_live.unwrap_or(false),
+                start,
+                end,
+                max_secs,
+            )
+            // A chapter alone is checked by its span, not the full length
+            .filter(|_| chapter.is_none() || start.is_some() || end.is_some())
+        });
+        if refusal.is_some() {
+            info!(url = %url, duration = ?length.duration, chapter, max_secs, "Download refused: too long");
+        }
+        refusal
     }
 
     /// Send file to user with automatic cleanup after successful delivery
@@ -165,6 +229,15 @@ impl YtdlpProvider {
         file_path: &str,
         file_name: &str,
     ) -> Result<String> {
+        // Files over the chat limit go to the file host instead of a doomed upload
+        match sandbox.file_size_bytes(file_path, None).await {
+            Ok(size) if size > crate::config::get_chat_delivery_max_file_bytes() => {
+                return Ok(upload_large_file(sandbox, file_path, file_name, size, None).await);
+            }
+            Ok(_) => {}
+            Err(e) => warn!(error = %e, file_path = %file_path, "Failed to check file size"),
+        }
+
         // Download file from sandbox
         let content = match sandbox.download_file(file_path).await {
             Ok(c) => c,
@@ -179,6 +252,11 @@ impl YtdlpProvider {
         let size_mb = content.len() as f64 / 1024.0 / 1024.0;
 
         if let Some(ref tx) = self.progress_tx {
+            let Some(claim) = claim_delivery(self.user_id, &content) else {
+                info!(file_path = %file_path, "File already delivered recently, skipping");
+                return Ok(already_delivered_message(file_name));
+            };
+
             // Create oneshot channel for delivery confirmation
             let (confirm_tx, confirm_rx) = tokio::sync::oneshot::channel();
 
@@ -192,6 +270,7 @@ impl YtdlpProvider {
                 })
                 .await
             {
+                claim.release();
                 warn!(error = %e, "Failed to send FileToSendWithConfirmation event");
                 return Ok(format!(
                     "⚠️ File downloaded ({size_mb:.2} MB) but failed to queue for sending: {e}\n\
@@ -205,7 +284,7 @@ impl YtdlpProvider {
                     // Success! Delete file from sandbox
                     info!(file_path = %file_path, "File delivered successfully, cleaning up");
                     if let Err(e) = sandbox
-                        .exec_command(&format!("rm -f '{file_path}'"), None)
+                        .exec_command(&format!("rm -f {}", shell_quote(file_path)), None)
                         .await
                     {
                         warn!(error = %e, file_path = %file_path, "Failed to cleanup file after delivery");
@@ -216,6 +295,7 @@ impl YtdlpProvider {
                 }
                 Ok(Ok(Err(e))) => {
                     // Delivery failed after retries
+                    claim.release();
                     warn!(error = %e, file_path = %file_path, "File delivery failed after retries");
                     Ok(format!(
                         "⚠️ Failed to send file to user: {e}\n\
@@ -253,11 +333,11 @@ impl YtdlpProvider {
     /// Execute yt-dlp command and return output
     async fn exec_ytdlp(
         &self,
-        args: &str,
+        cmd: &ShellCommand,
         cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     ) -> Result<String> {
         let sandbox = self.get_sandbox().await?;
-        let cmd = format!("yt-dlp {args}");
+        let cmd = cmd.to_string();
         debug!(cmd = %cmd, "Executing yt-dlp command");
 
         let result = sandbox.exec_command(&cmd, cancellation_token).await?;
@@ -299,22 +379,10 @@ impl YtdlpProvider {
     ) -> Result<String> {
         let args: GetMetadataArgs = serde_json::from_str(arguments)?;
 
-        // Build field selection if specified
-        let fields_arg = if let Some(ref fields) = args.fields {
-            let field_list = fields.join(",");
-            format!("-O '%({field_list})j'")
-        } else {
-            // Default: dump full JSON metadata
-            "-j".to_string()
-        };
-
-        let ytdlp_args = format!(
-            "--no-download --no-warnings --ignore-errors {} '{}'",
-            fields_arg,
-            args.url.replace('\'', "'\\''")
-        );
-
-        let output = match self.exec_ytdlp(&ytdlp_args, cancellation_token).await {
+        let output = match self
+            .exec_ytdlp(&metadata_command(&args), cancellation_token)
+            .await
+        {
             Ok(out) => out,
             Err(e) => {
                 return Ok(format!(
@@ -326,18 +394,16 @@ impl YtdlpProvider {
             }
         };
 
-        // Truncate if too long
-        let truncated = if output.len() > MAX_METADATA_LENGTH {
-            format!(
-                "{}...\n\n(truncated, {} chars total)",
-                &output[..MAX_METADATA_LENGTH],
-                output.len()
-            )
-        } else {
-            output
-        };
+        // Chapters go first so truncating a large dump cannot cut them off
+        let chapters = serde_json::from_str::<serde_json::Value>(&output)
+            .ok()
+            .and_then(|metadata| format_chapters(&metadata))
+            .unwrap_or_default();
+        let truncated = truncate_with_notice(output, self.output_limits.ytdlp_metadata_chars);
 
-        Ok(format!("## Video Metadata\n\n```json\n{truncated}\n```"))
+        Ok(format!(
+            "{chapters}## Video Metadata\n\n```json\n{truncated}\n```"
+        ))
     }
 
     /// Handle ytdlp_download_transcript tool
@@ -348,18 +414,10 @@ impl YtdlpProvider {
     ) -> Result<String> {
         let args: TranscriptArgs = serde_json::from_str(arguments)?;
 
-        let lang = args.language.as_deref().unwrap_or("en");
-        let url_escaped = args.url.replace('\'', "'\\''");
-
-        // Download subtitles in VTT format, then convert to plain text
-        let ytdlp_args = format!(
-            "--skip-download --write-auto-sub --sub-lang '{lang}' \
-             --sub-format vtt --convert-subs srt \
-             -o '{DOWNLOADS_DIR}/transcript.%(ext)s' \
-             --no-warnings '{url_escaped}'"
-        );
-
-        if let Err(e) = self.exec_ytdlp(&ytdlp_args, cancellation_token).await {
+        if let Err(e) = self
+            .exec_ytdlp(&transcript_command(&args), cancellation_token)
+            .await
+        {
             return Ok(format!(
                 "❌ **Failed to download transcript**\n\n\
                  Reason: {e}\n\n\
@@ -385,8 +443,8 @@ impl YtdlpProvider {
 
         // Read and clean the transcript (remove timestamps and formatting)
         let clean_cmd = format!(
-            "cat '{}' | sed '/^[0-9]/d' | sed '/-->/d' | sed '/^$/d' | tr '\\n' ' '",
-            subtitle_path
+            "cat {} | sed '/^[0-9]/d' | sed '/-->/d' | sed '/^$/d' | tr '\\n' ' '",
+            shell_quote(subtitle_path)
         );
         let result = sandbox.exec_command(&clean_cmd, None).await?;
 
@@ -400,16 +458,7 @@ impl YtdlpProvider {
             return Ok("Transcript is empty or could not be extracted.".to_string());
         }
 
-        // Truncate if needed
-        let truncated = if transcript.len() > MAX_TRANSCRIPT_LENGTH {
-            format!(
-                "{}...\n\n(truncated, {} chars total)",
-                &transcript[..MAX_TRANSCRIPT_LENGTH],
-                transcript.len()
-            )
-        } else {
-            transcript
-        };
+        let truncated = truncate_with_notice(transcript, self.output_limits.ytdlp_transcript_chars);
 
         Ok(format!("## Transcript\n\n{truncated}"))
     }
@@ -422,14 +471,10 @@ impl YtdlpProvider {
     ) -> Result<String> {
         let args: SearchVideosArgs = serde_json::from_str(arguments)?;
 
-        let max_results = args.max_results.unwrap_or(5).min(20);
-        let query_escaped = args.query.replace('\'', "'\\''");
-
-        // Use ytsearch to search YouTube
-        let ytdlp_args =
-            format!("-j --flat-playlist --no-warnings 'ytsearch{max_results}:{query_escaped}'");
-
-        let output = match self.exec_ytdlp(&ytdlp_args, cancellation_token).await {
+        let output = match self
+            .exec_ytdlp(&search_command(&args), cancellation_token)
+            .await
+        {
             Ok(out) => out,
             Err(e) => {
                 return Ok(format!(
@@ -489,91 +534,140 @@ impl YtdlpProvider {
         cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     ) -> Result<String> {
         let args: DownloadVideoArgs = serde_json::from_str(arguments)?;
+        if let Some(refusal) = self
+            .check_duration(
+                &args.url,
+                args.start_time.as_deref(),
+                args.end_time.as_deref(),
+                args.chapter.as_deref().filter(|c| !c.trim().is_empty()),
+                cancellation_token,
+            )
+            .await
+        {
+            return Ok(refusal);
+        }
+        let mut height = args
+            .resolution
+            .as_deref()
+            .and_then(parse_resolution)
+            .or_else(|| parse_resolution(&crate::config::get_ytdlp_default_resolution()))
+            .unwrap_or(Some(720));
+        let container = args
+            .format
+            .as_deref()
+            .map(str::to_ascii_lowercase)
+            .filter(|f| crate::config::YTDLP_VIDEO_FORMATS.contains(&f.as_str()))
+            .unwrap_or_else(crate::config::get_ytdlp_default_format);
+        let limit = crate::config::get_chat_delivery_max_file_bytes();
 
-        let resolution = args.resolution.as_deref().unwrap_or("720");
-        let url_escaped = args.url.replace('\'', "'\\''");
+        let sandbox = self.get_sandbox().await?;
+        let mut downscaled = None;
+        let (video_path, size_bytes) = loop {
+            let cmd = video_command(&args, height, &container);
+            let (path, size) = match self
+                .download_video_file(&cmd, &container, cancellation_token)
+                .await?
+            {
+                Ok(file) => file,
+                Err(message) => return Ok(message),
+            };
 
-        // Format selection based on resolution
-        let format_arg = match resolution {
-            "480" | "480p" => "bestvideo[height<=480]+bestaudio/best[height<=480]",
-            "720" | "720p" => "bestvideo[height<=720]+bestaudio/best[height<=720]",
-            "1080" | "1080p" => "bestvideo[height<=1080]+bestaudio/best[height<=1080]",
-            "best" => "bestvideo+bestaudio/best",
-            _ => "bestvideo[height<=720]+bestaudio/best[height<=720]",
+            // Re-download smaller rather than fall back to a file host link
+            let lower = lower_resolution(height);
+            if !args.send_to_user || size <= limit || lower.is_none() {
+                break (path, size);
+            }
+            info!(path = %path, size, limit, ?lower, "Video exceeds upload limit, downscaling");
+            if let Err(e) = sandbox
+                .exec_command(&format!("rm -f {}", shell_quote(&path)), None)
+                .await
+            {
+                warn!(error = %e, path = %path, "Failed to remove oversized video");
+            }
+            let from = downscaled.map_or(height, |(from, _)| from);
+            height = lower.flatten();
+            downscaled = Some((from, height));
         };
+        let size_mb = size_bytes as f64 / 1024.0 / 1024.0;
 
-        let mut ytdlp_args = format!(
-            "-f '{format_arg}' --merge-output-format mp4 \
-             -o '{DOWNLOADS_DIR}/%(title).50s.%(ext)s' \
-             --no-warnings --progress '{url_escaped}'"
-        );
+        let note = downscaled.map_or_else(String::new, |(from, to)| {
+            format!(
+                "ℹ️ The {} video exceeded the {} MB upload limit, so it was downloaded at {} instead. Tell the user about the lower quality.\n\n",
+                resolution_label(from),
+                limit / 1024 / 1024,
+                resolution_label(to)
+            )
+        });
 
-        // Add time range if specified
-        if let Some(ref start) = args.start_time {
-            ytdlp_args.push_str(&format!(" --download-sections '*{start}-'"));
-        }
-        if let Some(ref end) = args.end_time {
-            let start = args.start_time.as_deref().unwrap_or("0");
-            ytdlp_args = ytdlp_args.replace(&format!("'*{start}-'"), &format!("'*{start}-{end}'"));
+        let filename = std::path::Path::new(&video_path)
+            .file_name()
+            .map_or(format!("video.{container}"), |n| {
+                n.to_string_lossy().to_string()
+            });
+
+        if args.send_to_user {
+            // Auto-send to user with confirmation for cleanup
+            let sent = self
+                .send_file_with_cleanup(&sandbox, &video_path, &filename)
+                .await?;
+            return Ok(format!("{note}{sent}"));
         }
 
-        let output = match self.exec_ytdlp(&ytdlp_args, cancellation_token).await {
+        Ok(format!(
+            "{note}Video downloaded successfully!\n\n\
+             - **File**: {filename}\n\
+             - **Path**: {video_path}\n\
+             - **Size**: {size_mb:.2} MB\n\n\
+             Use `send_file_to_user` tool with path `{video_path}` to send it to the user."
+        ))
+    }
+
+    /// Run a video download and find the resulting file and its size
+    ///
+    /// The inner error is a message for the model, e.g. when yt-dlp failed.
+    async fn download_video_file(
+        &self,
+        cmd: &ShellCommand,
+        container: &str,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<std::result::Result<(String, u64), String>> {
+        let output = match self.exec_ytdlp(cmd, cancellation_token).await {
             Ok(out) => out,
             Err(e) => {
-                return Ok(format!(
+                return Ok(Err(format!(
                     "❌ **Failed to download video**\n\n\
                      Reason: {e}\n\n\
                      The video may be unavailable, private, or blocked."
-                ));
+                )));
             }
         };
 
         if output.contains("yt-dlp error:") || output.contains("ERROR") {
-            return Ok(format!("Download failed: {output}"));
+            return Ok(Err(format!("Download failed: {output}")));
         }
 
         // Find the downloaded file
         let sandbox = self.get_sandbox().await?;
         let find_result = sandbox
             .exec_command(
-                &format!("ls -1t {DOWNLOADS_DIR}/*.mp4 2>/dev/null | head -1"),
+                &format!("ls -1t {DOWNLOADS_DIR}/*.{container} 2>/dev/null | head -1"),
                 None,
             )
             .await?;
 
-        let video_path = find_result.stdout.trim();
+        let video_path = find_result.stdout.trim().to_string();
         if video_path.is_empty() {
-            return Ok(
+            return Ok(Err(
                 "Video download completed but file not found. Try checking the sandbox files."
                     .to_string(),
-            );
+            ));
         }
 
-        // Get file size
         let size_result = sandbox
-            .exec_command(&format!("stat -c %s '{video_path}'"), None)
+            .exec_command(&format!("stat -c %s {}", shell_quote(&video_path)), None)
             .await?;
         let size_bytes: u64 = size_result.stdout.trim().parse().unwrap_or(0);
-        let size_mb = size_bytes as f64 / 1024.0 / 1024.0;
-
-        let filename = std::path::Path::new(video_path)
-            .file_name()
-            .map_or("video.mp4".to_string(), |n| n.to_string_lossy().to_string());
-
-        if args.send_to_user {
-            // Auto-send to user with confirmation for cleanup
-            return self
-                .send_file_with_cleanup(&sandbox, video_path, &filename)
-                .await;
-        }
-
-        Ok(format!(
-            "Video downloaded successfully!\n\n\
-             - **File**: {filename}\n\
-             - **Path**: {video_path}\n\
-             - **Size**: {size_mb:.2} MB\n\n\
-             Use `send_file_to_user` tool with path `{video_path}` to send it to the user."
-        ))
+        Ok(Ok((video_path, size_bytes)))
     }
 
     /// Handle ytdlp_download_audio tool
@@ -583,17 +677,23 @@ impl YtdlpProvider {
         cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     ) -> Result<String> {
         let args: DownloadAudioArgs = serde_json::from_str(arguments)?;
+        if let Some(refusal) = self
+            .check_duration(
+                &args.url,
+                args.start_time.as_deref(),
+                args.end_time.as_deref(),
+                None,
+                cancellation_token,
+            )
+            .await
+        {
+            return Ok(refusal);
+        }
 
-        let url_escaped = args.url.replace('\'', "'\\''");
-
-        // Extract best audio and convert to mp3
-        let ytdlp_args = format!(
-            "-x --audio-format mp3 --audio-quality 0 \
-             -o '{DOWNLOADS_DIR}/%(title).50s.%(ext)s' \
-             --no-warnings --progress '{url_escaped}'"
-        );
-
-        let output = match self.exec_ytdlp(&ytdlp_args, cancellation_token).await {
+        let output = match self
+            .exec_ytdlp(&audio_command(&args), cancellation_token)
+            .await
+        {
             Ok(out) => out,
             Err(e) => {
                 return Ok(format!(
@@ -627,7 +727,7 @@ impl YtdlpProvider {
 
         // Get file size
         let size_result = sandbox
-            .exec_command(&format!("stat -c %s '{audio_path}'"), None)
+            .exec_command(&format!("stat -c %s {}", shell_quote(audio_path)), None)
             .await?;
         let size_bytes: u64 = size_result.stdout.trim().parse().unwrap_or(0);
         let size_mb = size_bytes as f64 / 1024.0 / 1024.0;
@@ -683,25 +783,340 @@ struct DownloadVideoArgs {
     url: String,
     #[serde(default)]
     resolution: Option<String>,
+    /// Container format (`mp4`, `webm`, `mkv`)
+    #[serde(default)]
+    format: Option<String>,
+    /// Cut sponsor, self-promotion, intro and outro segments (SponsorBlock)
+    #[serde(default)]
+    remove_sponsors: bool,
+    /// Download only the chapter with this title
+    #[serde(default)]
+    chapter: Option<String>,
     #[serde(default)]
     start_time: Option<String>,
     #[serde(default)]
     end_time: Option<String>,
-    /// Automatically send to user after download (default: true)
-    #[serde(default = "default_true")]
+    /// Automatically send to user after download (default: `YTDLP_AUTO_SEND`)
+    #[serde(default = "default_send_to_user")]
     send_to_user: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct DownloadAudioArgs {
     url: String,
-    /// Automatically send to user after download (default: true)
-    #[serde(default = "default_true")]
+    #[serde(default)]
+    start_time: Option<String>,
+    #[serde(default)]
+    end_time: Option<String>,
+    /// Automatically send to user after download (default: `YTDLP_AUTO_SEND`)
+    #[serde(default = "default_send_to_user")]
     send_to_user: bool,
 }
 
-fn default_true() -> bool {
-    true
+fn default_send_to_user() -> bool {
+    crate::config::get_ytdlp_auto_send()
+}
+
+/// Description of `send_to_user` that states the configured default
+fn send_to_user_description() -> String {
+    if crate::config::get_ytdlp_auto_send() {
+        "Automatically send file to user after download (default: true). Set to false if you need to process the file first.".to_string()
+    } else {
+        "Automatically send file to user after download (default: false, the file stays in the sandbox). Set to true if the downloaded file is the final result.".to_string()
+    }
+}
+
+/// Delivery sentence of the download tool descriptions
+fn auto_send_summary() -> &'static str {
+    if crate::config::get_ytdlp_auto_send() {
+        "By default, automatically sends the file to the user and cleans up after successful delivery. Set send_to_user=false to keep the file in sandbox for further processing."
+    } else {
+        "By default, keeps the file in the sandbox for further processing; send it with send_file_to_user when done. Set send_to_user=true to send it right after download."
+    }
+}
+
+// ============================================================================
+// Command builders
+// ============================================================================
+
+/// Output template for downloaded media, named after the video title
+const MEDIA_OUTPUT_TEMPLATE: &str = "%(title).50s.%(ext)s";
+
+/// yt-dlp command; the URL or search term always follows `--`, so a value
+/// starting with a dash cannot smuggle in options such as `--exec`
+fn ytdlp_command(options: &[&str]) -> ShellCommand {
+    ShellCommand::new("yt-dlp").args(options)
+}
+
+fn metadata_command(args: &GetMetadataArgs) -> ShellCommand {
+    let cmd = ytdlp_command(&["--no-download", "--no-warnings", "--ignore-errors"]);
+    let cmd = match &args.fields {
+        Some(fields) => cmd.arg("-O").arg(format!("%({})j", fields.join(","))),
+        // Default: dump full JSON metadata
+        None => cmd.arg("-j"),
+    };
+    cmd.end_of_options().arg(&args.url)
+}
+
+/// Download subtitles in VTT format and convert them to SRT
+fn transcript_command(args: &TranscriptArgs) -> ShellCommand {
+    let lang = args.language.as_deref().unwrap_or("en");
+    ytdlp_command(&["--skip-download", "--write-auto-sub", "--sub-lang"])
+        .arg(lang)
+        .args(["--sub-format", "vtt", "--convert-subs", "srt", "-o"])
+        .arg(format!("{DOWNLOADS_DIR}/transcript.%(ext)s"))
+        .arg("--no-warnings")
+        .end_of_options()
+        .arg(&args.url)
+}
+
+fn search_command(args: &SearchVideosArgs) -> ShellCommand {
+    let max_results = args.max_results.unwrap_or(5).min(20);
+    ytdlp_command(&["-j", "--flat-playlist", "--no-warnings"])
+        .end_of_options()
+        .arg(format!("ytsearch{max_results}:{}", args.query))
+}
+
+/// SponsorBlock segments cut by `remove_sponsors`
+const SPONSORBLOCK_REMOVED_CATEGORIES: &str = "sponsor,selfpromo,intro,outro";
+
+/// Chapter list of a metadata dump, with times, for picking `chapter`
+fn format_chapters(metadata: &serde_json::Value) -> Option<String> {
+    let chapters = metadata.get("chapters")?.as_array()?;
+    if chapters.is_empty() {
+        return None;
+    }
+
+    let mut out = String::from("## Chapters\n\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        let title = chapter
+            .get("title")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("(untitled)");
+        let time = |key| {
+            chapter
+                .get(key)
+                .and_then(serde_json::Value::as_f64)
+                .map_or_else(|| "?".to_string(), format_timestamp)
+        };
+        let _ = writeln!(
+            out,
+            "{}. {title} ({}–{})",
+            i + 1,
+            time("start_time"),
+            time("end_time")
+        );
+    }
+    out.push_str(
+        "\nPass a chapter title as `chapter` to `ytdlp_download_video` to download only that part.\n\n",
+    );
+    Some(out)
+}
+
+/// Format seconds as `M:SS` or `H:MM:SS`
+fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (h, m, s) = (total / 3600, total / 60 % 60, total % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+/// Resolutions tried in turn when a video is too large to send
+const DOWNSCALE_HEIGHTS: &[u32] = &[1080, 720, 480, 360, 240];
+
+/// Parse a resolution such as `480`, `720p` or `best`
+///
+/// Returns the maximum video height, `Some(None)` for `best`, or `None` if
+/// the value is not a resolution.
+fn parse_resolution(value: &str) -> Option<Option<u32>> {
+    let value = value.trim().to_ascii_lowercase();
+    if value == "best" {
+        return Some(None);
+    }
+    value
+        .strip_suffix('p')
+        .unwrap_or(&value)
+        .parse::<u32>()
+        .ok()
+        .filter(|height| (144..=4320).contains(height))
+        .map(Some)
+}
+
+/// Next resolution down for an oversized video, if any is left
+fn lower_resolution(height: Option<u32>) -> Option<Option<u32>> {
+    DOWNSCALE_HEIGHTS
+        .iter()
+        .copied()
+        .find(|lower| height.is_none_or(|h| *lower < h))
+        .map(Some)
+}
+
+fn resolution_label(height: Option<u32>) -> String {
+    height.map_or_else(|| "best quality".to_string(), |h| format!("{h}p"))
+}
+
+fn video_command(args: &DownloadVideoArgs, height: Option<u32>, container: &str) -> ShellCommand {
+    let format = height.map_or_else(
+        || "bestvideo+bestaudio/best".to_string(),
+        |h| format!("bestvideo[height<={h}]+bestaudio/best[height<={h}]"),
+    );
+
+    let mut cmd = ytdlp_command(&["-f", &format, "--merge-output-format", container, "-o"])
+        .arg(format!("{DOWNLOADS_DIR}/{MEDIA_OUTPUT_TEMPLATE}"))
+        .args(["--no-warnings", "--progress"]);
+    cmd = with_time_range(cmd, args.start_time.as_deref(), args.end_time.as_deref());
+    if let Some(chapter) = args.chapter.as_deref().filter(|c| !c.trim().is_empty()) {
+        cmd = cmd.arg("--download-sections").arg(chapter_section(chapter));
+    }
+    if args.remove_sponsors {
+        cmd = cmd
+            .arg("--sponsorblock-remove")
+            .arg(SPONSORBLOCK_REMOVED_CATEGORIES);
+    }
+    cmd.end_of_options().arg(&args.url)
+}
+
+/// `--download-sections` value selecting the chapter titled `chapter`
+///
+/// Sections without `*` are regexes searched in chapter titles; anchoring it
+/// keeps "Intro" from also picking "Introduction to X".
+fn chapter_section(chapter: &str) -> String {
+    format!("(?i)^\\s*{}\\s*$", regex::escape(chapter.trim()))
+}
+
+/// Extract the best audio track and convert it to mp3
+fn audio_command(args: &DownloadAudioArgs) -> ShellCommand {
+    let cmd = ytdlp_command(&["-x", "--audio-format", "mp3", "--audio-quality", "0", "-o"])
+        .arg(format!("{DOWNLOADS_DIR}/{MEDIA_OUTPUT_TEMPLATE}"))
+        .args(["--no-warnings", "--progress"]);
+    with_time_range(cmd, args.start_time.as_deref(), args.end_time.as_deref())
+        .end_of_options()
+        .arg(&args.url)
+}
+
+/// Download only `start`..`end` when either is given
+fn with_time_range(cmd: ShellCommand, start: Option<&str>, end: Option<&str>) -> ShellCommand {
+    if start.is_none() && end.is_none() {
+        return cmd;
+    }
+    let start = start.unwrap_or("0");
+    let end = end.unwrap_or_default();
+    cmd.arg("--download-sections")
+        .arg(format!("*{start}-{end}"))
+}
+
+/// Duration, live status and chapters, printed as one JSON object
+fn duration_command(url: &str) -> ShellCommand {
+    ytdlp_command(&["--no-download", "--no-warnings", "--no-playlist", "-O"])
+        .arg("%(.{duration,is_live,chapters})j")
+        .end_of_options()
+        .arg(url)
+}
+
+/// Output of [`duration_command`]; fields yt-dlp does not know are `null`
+#[derive(Debug, Default, Deserialize)]
+struct VideoLength {
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    is_live: Option<bool>,
+    #[serde(default)]
+    chapters: Option<Vec<Chapter>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chapter {
+    #[serde(default)]
+    title: String,
+    start_time: f64,
+    end_time: f64,
+}
+
+/// The chapter titled `title`, ignoring case and surrounding whitespace
+fn find_chapter<'a>(chapters: &'a [Chapter], title: &str) -> Option<&'a Chapter> {
+    let title = title.trim().to_lowercase();
+    chapters
+        .iter()
+        .find(|chapter| chapter.title.trim().to_lowercase() == title)
+}
+
+/// Refusal message when the chapter titled `title` is missing or longer than `max_secs`
+fn chapter_refusal(length: &VideoLength, title: &str, max_secs: u64) -> Option<String> {
+    let chapters = length.chapters.as_deref().unwrap_or_default();
+    let Some(chapter) = find_chapter(chapters, title) else {
+        return Some(format!(
+            "❌ The video has no chapter titled '{}'. Check the chapter list from \
+             ytdlp_get_video_metadata and pass a title exactly as listed.",
+            title.trim()
+        ));
+    };
+    let span = chapter.end_time - chapter.start_time;
+    (span > max_secs as f64).then(|| {
+        format!(
+            "❌ The chapter '{}' is {} long, over the {} download limit. \
+             Ask the user which part they need and pass start_time/end_time instead.",
+            chapter.title,
+            format_timestamp(span),
+            format_timestamp(max_secs as f64)
+        )
+    })
+}
+
+/// Parse `SS`, `MM:SS` or `HH:MM:SS` (fractions allowed) into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    value.trim().split(':').try_fold(0.0, |total, part| {
+        part.parse::<f64>()
+            .ok()
+            .filter(|n| *n >= 0.0)
+            .map(|n| total * 60.0 + n)
+    })
+}
+
+/// Refusal message when the requested part of a video is longer than `max_secs`
+///
+/// A time range counts instead of the full duration, so a slice of a long
+/// video can still be downloaded. Live streams need an explicit end.
+fn duration_refusal(
+    duration: Option<f64>,
+    is_live: bool,
+    start: Option<&str>,
+    end: Option<&str>,
+    max_secs: u64,
+) -> Option<String> {
+    let max = max_secs as f64;
+    let limit = format_timestamp(max);
+    let hint = format!(
+        "Ask the user which part they need and pass start_time/end_time spanning at most {limit}."
+    );
+
+    if start.is_some() || end.is_some() {
+        let from = start.and_then(parse_timestamp).unwrap_or(0.0);
+        let to = end.and_then(parse_timestamp).or(duration);
+        return match to {
+            Some(to) if to - from <= max => None,
+            Some(to) => Some(format!(
+                "❌ The requested range is {} long, over the {limit} download limit. {hint}",
+                format_timestamp(to - from)
+            )),
+            None => Some(format!(
+                "❌ The end of this stream is unknown, so the range has no end. {hint}"
+            )),
+        };
+    }
+
+    if is_live {
+        return Some(format!("❌ This is a live stream with no end. {hint}"));
+    }
+    match duration {
+        Some(duration) if duration > max => Some(format!(
+            "❌ This video is {} long, over the {limit} download limit. {hint}",
+            format_timestamp(duration)
+        )),
+        _ => None,
+    }
 }
 
 // ============================================================================
@@ -776,7 +1191,10 @@ impl YtdlpProvider {
     fn get_download_video_tool() -> ToolDefinition {
         ToolDefinition {
             name: "ytdlp_download_video".to_string(),
-            description: "Download a video from YouTube or other platforms. By default, automatically sends the file to the user and cleans up after successful delivery. Set send_to_user=false to keep the file in sandbox for further processing.".to_string(),
+            description: format!(
+                "Download a video from YouTube or other platforms. {}",
+                auto_send_summary()
+            ),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -786,20 +1204,34 @@ impl YtdlpProvider {
                     },
                     "resolution": {
                         "type": "string",
-                        "description": "Video resolution: '480', '720', '1080', or 'best' (default: '720')"
+                        "description": "Video resolution: '480', '720', '1080', or 'best' (default set by the operator, usually '720'). Videos too large to send are re-downloaded at a lower resolution"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["mp4", "webm", "mkv"],
+                        "description": "Container format (default set by the operator, usually 'mp4')"
                     },
                     "start_time": {
                         "type": "string",
                         "description": "Optional start time for trimming (format: 'MM:SS' or 'HH:MM:SS')"
                     },
+                    "chapter": {
+                        "type": "string",
+                        "description": "Optional chapter title to download only that chapter (see the chapter list from ytdlp_get_video_metadata)"
+                    },
+                    "remove_sponsors": {
+                        "type": "boolean",
+                        "description": "Cut sponsor, self-promotion, intro and outro segments using SponsorBlock (default: false)",
+                        "default": false
+                    },
                     "end_time": {
                         "type": "string",
                         "description": "Optional end time for trimming (format: 'MM:SS' or 'HH:MM:SS')"
                     },
                     "send_to_user": {
                         "type": "boolean",
-                        "description": "Automatically send file to user after download (default: true). Set to false if you need to process the file first.",
-                        "default": true
+                        "description": send_to_user_description(),
+                        "default": crate::config::get_ytdlp_auto_send()
                     }
                 },
                 "required": ["url"]
@@ -810,7 +1242,10 @@ impl YtdlpProvider {
     fn get_download_audio_tool() -> ToolDefinition {
         ToolDefinition {
             name: "ytdlp_download_audio".to_string(),
-            description: "Extract and download audio from a video as MP3. By default, automatically sends the file to the user and cleans up after successful delivery. Set send_to_user=false to keep the file in sandbox for further processing.".to_string(),
+            description: format!(
+                "Extract and download audio from a video as MP3. {}",
+                auto_send_summary()
+            ),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -818,10 +1253,18 @@ impl YtdlpProvider {
                         "type": "string",
                         "description": "Video URL"
                     },
+                    "start_time": {
+                        "type": "string",
+                        "description": "Optional start time for trimming (format: 'MM:SS' or 'HH:MM:SS')"
+                    },
+                    "end_time": {
+                        "type": "string",
+                        "description": "Optional end time for trimming (format: 'MM:SS' or 'HH:MM:SS')"
+                    },
                     "send_to_user": {
                         "type": "boolean",
-                        "description": "Automatically send file to user after download (default: true). Set to false if you need to process the file first.",
-                        "default": true
+                        "description": send_to_user_description(),
+                        "default": crate::config::get_ytdlp_auto_send()
                     }
                 },
                 "required": ["url"]
@@ -901,3 +1344,164 @@ impl ToolProvider for YtdlpProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_are_quoted_after_end_of_options() {
+        let args: DownloadAudioArgs =
+            match serde_json::from_str(r#"{"url": "--exec='touch /tmp/x' $(id)"}"#) {
+                Ok(args) => args,
+                Err(e) => panic!("invalid args: {e}"),
+            };
+        assert!(audio_command(&args)
+            .to_string()
+            .ends_with(r"-- '--exec='\''touch /tmp/x'\'' $(id)'"));
+    }
+
+    #[test]
+    fn test_download_sections_cover_requested_range() {
+        let args: DownloadVideoArgs = match serde_json::from_str(
+            r#"{"url": "https://youtu.be/x", "end_time": "1:30", "resolution": "480"}"#,
+        ) {
+            Ok(args) => args,
+            Err(e) => panic!("invalid args: {e}"),
+        };
+        let cmd = video_command(&args, Some(480), "webm").to_string();
+        assert!(cmd.contains("--download-sections '*0-1:30'"), "{cmd}");
+        assert!(cmd.contains("-f 'bestvideo[height<=480]+bestaudio/best[height<=480]'"));
+        assert!(cmd.contains("--merge-output-format webm"));
+    }
+
+    #[test]
+    fn test_long_videos_need_a_short_enough_range() {
+        let max = 3 * 3600;
+        let ten_hours = Some(36_000.0);
+        assert_eq!(duration_refusal(Some(600.0), false, None, None, max), None);
+        assert_eq!(duration_refusal(None, false, None, None, max), None);
+
+        let Some(refusal) = duration_refusal(ten_hours, false, None, None, max) else {
+            panic!("10-hour video should be refused");
+        };
+        assert!(refusal.contains("10:00:00"), "{refusal}");
+        assert!(refusal.contains("start_time/end_time spanning at most 3:00:00"));
+
+        assert_eq!(
+            duration_refusal(ten_hours, false, Some("1:00:00"), Some("1:30:00"), max),
+            None
+        );
+        // Open end runs to the end of the video
+        assert!(duration_refusal(ten_hours, false, Some("2:00:00"), None, max).is_some());
+        assert_eq!(
+            duration_refusal(ten_hours, false, Some("9:00:00"), None, max),
+            None
+        );
+
+        assert!(duration_refusal(None, true, None, None, max).is_some());
+        assert!(duration_refusal(None, true, Some("0:10"), None, max).is_some());
+        assert_eq!(duration_refusal(None, true, None, Some("10:00"), max), None);
+    }
+
+    #[test]
+    fn test_chapters_are_checked_by_their_own_span() {
+        let max = 3600;
+        let length: VideoLength = match serde_json::from_str(
+            r#"{"duration": 36000.0, "is_live": false, "chapters": [
+                {"title": "Intro", "start_time": 0.0, "end_time": 600.0},
+                {"title": "Marathon", "start_time": 600.0, "end_time": 36000.0}
+            ]}"#,
+        ) {
+            Ok(length) => length,
+            Err(e) => panic!("invalid length: {e}"),
+        };
+        assert_eq!(chapter_refusal(&length, " intro ", max), None);
+        let Some(refusal) = chapter_refusal(&length, "Marathon", max) else {
+            panic!("a 9.8-hour chapter should be refused");
+        };
+        assert!(refusal.contains("9:50:00"), "{refusal}");
+        assert!(chapter_refusal(&length, "Outro", max).is_some_and(|r| r.contains("no chapter")));
+        assert!(chapter_refusal(&VideoLength::default(), "Intro", max).is_some());
+
+        let unknown: VideoLength = match serde_json::from_str(
+            r#"{"duration": null, "is_live": null, "chapters": null}"#,
+        ) {
+            Ok(length) => length,
+            Err(e) => panic!("invalid length: {e}"),
+        };
+        assert_eq!(unknown.duration, None);
+    }
+
+    #[test]
+    fn test_timestamps_parse_to_seconds() {
+        assert_eq!(parse_timestamp("90"), Some(90.0));
+        assert_eq!(parse_timestamp("1:30"), Some(90.0));
+        assert_eq!(parse_timestamp("01:02:03.5"), Some(3723.5));
+        assert_eq!(parse_timestamp("1:xx"), None);
+    }
+
+    #[test]
+    fn test_chapter_and_sponsor_options() {
+        let args: DownloadVideoArgs = match serde_json::from_str(
+            r#"{"url": "https://youtu.be/x", "chapter": "Part 2: Setup (v1.0)", "remove_sponsors": true}"#,
+        ) {
+            Ok(args) => args,
+            Err(e) => panic!("invalid args: {e}"),
+        };
+        let cmd = video_command(&args, Some(720), "mp4").to_string();
+        assert!(
+            cmd.contains(r"--download-sections '(?i)^\s*Part 2: Setup \(v1\.0\)\s*$'"),
+            "{cmd}"
+        );
+        assert!(cmd.contains("--sponsorblock-remove sponsor,selfpromo,intro,outro"));
+
+        // The section regex matches the whole title only
+        let section = match regex::Regex::new(&chapter_section("Intro")) {
+            Ok(section) => section,
+            Err(e) => panic!("invalid section regex: {e}"),
+        };
+        assert!(section.is_match(" intro "));
+        assert!(!section.is_match("Introduction to Rust"));
+
+        let args: DownloadVideoArgs = match serde_json::from_str(r#"{"url": "https://youtu.be/x"}"#)
+        {
+            Ok(args) => args,
+            Err(e) => panic!("invalid args: {e}"),
+        };
+        let cmd = video_command(&args, Some(720), "mp4").to_string();
+        assert!(!cmd.contains("--download-sections") && !cmd.contains("sponsorblock"));
+    }
+
+    #[test]
+    fn test_chapters_are_listed_with_times() {
+        let metadata = json!({
+            "title": "Talk",
+            "chapters": [
+                {"title": "Intro", "start_time": 0.0, "end_time": 83.0},
+                {"title": "Deep dive", "start_time": 83.0, "end_time": 3725.4}
+            ]
+        });
+        let Some(chapters) = format_chapters(&metadata) else {
+            panic!("chapters expected");
+        };
+        assert!(chapters.contains("1. Intro (0:00–1:23)"));
+        assert!(chapters.contains("2. Deep dive (1:23–1:02:05)"));
+        assert_eq!(format_chapters(&json!({"chapters": []})), None);
+        assert_eq!(format_chapters(&json!({"title": "No chapters"})), None);
+    }
+
+    #[test]
+    fn test_resolutions_parse_and_step_down() {
+        assert_eq!(parse_resolution("480"), Some(Some(480)));
+        assert_eq!(parse_resolution(" 720P "), Some(Some(720)));
+        assert_eq!(parse_resolution("best"), Some(None));
+        assert_eq!(parse_resolution("huge"), None);
+        assert_eq!(parse_resolution("5"), None);
+
+        assert_eq!(lower_resolution(None), Some(Some(1080)));
+        assert_eq!(lower_resolution(Some(720)), Some(Some(480)));
+        assert_eq!(lower_resolution(Some(600)), Some(Some(480)));
+        assert_eq!(lower_resolution(Some(240)), None);
+    }
+}
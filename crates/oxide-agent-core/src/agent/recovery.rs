@@ -5,6 +5,7 @@
 use crate::llm::{ToolCall, ToolCallFunction};
 use lazy_regex::regex;
 use serde_json::Value;
+use std::collections::hash_map::{Entry, HashMap};
 use tracing::warn;
 
 /// Sanitize leaked control XML tags from text.
@@ -190,6 +191,20 @@ pub fn extract_first_json(input: &str) -> Option<String> {
     None
 }
 
+/// Repair tool call arguments that are not valid JSON
+///
+/// Only trailing garbage after the first complete object is dropped.
+/// Truncated arguments are never closed: guessing the end of a cut-off
+/// command could run something the model did not ask for. Returns `None` if
+/// `arguments` is already valid or cannot be repaired.
+#[must_use]
+pub fn repair_json_arguments(arguments: &str) -> Option<String> {
+    if serde_json::from_str::<Value>(arguments).is_ok() {
+        return None;
+    }
+    extract_first_json(arguments)
+}
+
 /// Extract JSON content from markdown code fences.
 pub fn extract_fenced_json(input: &str) -> Option<String> {
     let fence = "```";
@@ -221,20 +236,89 @@ fn strip_fence_language(block: &str) -> String {
     block.trim().to_string()
 }
 
-/// Sanitize a vector of tool calls
-pub fn sanitize_tool_calls(tool_calls: Vec<ToolCall>) -> Vec<ToolCall> {
-    tool_calls
+/// Tool calls of one model response, with byte-identical repeats collapsed
+#[derive(Debug, Clone, Default)]
+pub struct SanitizedToolCalls {
+    /// All calls in response order; recorded in the assistant message
+    pub calls: Vec<ToolCall>,
+    /// Id of each repeated call mapped to the id of the first identical call
+    pub duplicates: HashMap<String, String>,
+}
+
+impl SanitizedToolCalls {
+    /// Calls to execute: the first of each identical group, in response order
+    #[must_use]
+    pub fn unique_calls(&self) -> Vec<ToolCall> {
+        self.calls
+            .iter()
+            .filter(|call| !self.duplicates.contains_key(&call.id))
+            .cloned()
+            .collect()
+    }
+
+    /// Ids of the repeats that share the result of the call `id`, in response order
+    #[must_use]
+    pub fn duplicate_ids_of(&self, id: &str) -> Vec<String> {
+        self.calls
+            .iter()
+            .filter(|call| {
+                self.duplicates
+                    .get(&call.id)
+                    .is_some_and(|first| first == id)
+            })
+            .map(|call| call.id.clone())
+            .collect()
+    }
+}
+
+impl From<Vec<ToolCall>> for SanitizedToolCalls {
+    fn from(calls: Vec<ToolCall>) -> Self {
+        Self {
+            calls,
+            duplicates: HashMap::new(),
+        }
+    }
+}
+
+/// Sanitize the tool calls of one response and collapse identical ones
+///
+/// Calls with the same name and byte-identical arguments run once; the repeats
+/// are listed in [`SanitizedToolCalls::duplicates`] so each of their ids can
+/// get the shared result and the message history stays valid.
+pub fn sanitize_tool_calls(tool_calls: Vec<ToolCall>) -> SanitizedToolCalls {
+    let mut first_ids: HashMap<(String, String), String> = HashMap::new();
+    let mut duplicates = HashMap::new();
+    let calls = tool_calls
         .into_iter()
         .map(|call| {
             let (name, arguments) =
                 sanitize_tool_call(&call.function.name, &call.function.arguments);
+            let is_recovered = call.is_recovered
+                || name != call.function.name
+                || arguments != call.function.arguments;
+            match first_ids.entry((name.clone(), arguments.clone())) {
+                Entry::Occupied(first) => {
+                    duplicates.insert(call.id.clone(), first.get().clone());
+                }
+                Entry::Vacant(slot) => {
+                    slot.insert(call.id.clone());
+                }
+            }
             ToolCall {
                 id: call.id,
                 function: ToolCallFunction { name, arguments },
-                is_recovered: call.is_recovered,
+                is_recovered,
             }
         })
-        .collect()
+        .collect();
+
+    if !duplicates.is_empty() {
+        warn!(
+            duplicates = duplicates.len(),
+            "Collapsed identical tool calls in one response"
+        );
+    }
+    SanitizedToolCalls { calls, duplicates }
 }
 
 /// Try to parse a malformed tool call from content text
@@ -498,6 +582,66 @@ pub fn sanitize_leaked_xml(iteration: usize, final_response: &mut String) -> boo
 mod tests {
     use super::*;
 
+    fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            function: ToolCallFunction {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+            is_recovered: false,
+        }
+    }
+
+    #[test]
+    fn test_sanitize_tool_calls_collapses_identical_calls() {
+        let sanitized = sanitize_tool_calls(vec![
+            call("a", "read_file", r#"{"path":"x"}"#),
+            call("b", "read_file", r#"{"path":"y"}"#),
+            call("c", "read_file", r#"{"path":"x"}"#),
+            call("d", "list_files", r#"{"path":"x"}"#),
+            call("e", "read_file", r#"{"path":"x"}"#),
+        ]);
+
+        // History keeps every call in response order
+        let ids: Vec<&str> = sanitized.calls.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+
+        let unique: Vec<String> = sanitized.unique_calls().into_iter().map(|c| c.id).collect();
+        assert_eq!(unique, ["a", "b", "d"]);
+    }
+
+    #[test]
+    fn test_sanitize_tool_calls_maps_duplicate_ids() {
+        let sanitized = sanitize_tool_calls(vec![
+            call("a", "read_file", r#"{"path":"x"}"#),
+            call("b", "read_file", r#"{"path": "x"}"#),
+            call("c", "read_file", r#"{"path":"x"}"#),
+        ]);
+
+        // Only byte-identical arguments count as duplicates
+        assert_eq!(sanitized.duplicates.len(), 1);
+        assert_eq!(sanitized.duplicates.get("c").map(String::as_str), Some("a"));
+        assert_eq!(sanitized.duplicate_ids_of("a"), ["c"]);
+        assert!(sanitized.duplicate_ids_of("b").is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_tool_calls_flags_corrected_calls() {
+        let sanitized = sanitize_tool_calls(vec![
+            call("a", "read_file", r#"{"path":"x"}"#),
+            call(
+                "b",
+                r#"todos [{"description": "x", "status": "pending"}]"#,
+                "{}",
+            ),
+        ]);
+
+        assert!(!sanitized.calls[0].is_recovered);
+        assert_eq!(sanitized.calls[1].function.name, "write_todos");
+        assert!(sanitized.calls[1].is_recovered);
+    }
+
     #[test]
     fn test_sanitize_tool_call_normal() {
         let (name, args) = sanitize_tool_call("write_todos", "{}");
@@ -562,6 +706,22 @@ mod tests {
         assert_eq!(array.len(), 3);
     }
 
+    #[test]
+    fn test_repair_json_arguments() {
+        assert_eq!(repair_json_arguments(r#"{"path": "a.txt"}"#), None);
+        assert_eq!(
+            repair_json_arguments(r#"{"path": "a.txt"}{"path": "b.txt"}"#).as_deref(),
+            Some(r#"{"path": "a.txt"}"#)
+        );
+        // Truncated arguments are left for the model to resend
+        assert_eq!(
+            repair_json_arguments(r#"{"command": "rm -rf /workspace/bui"#),
+            None
+        );
+        assert_eq!(repair_json_arguments(r#"{"todos": [{"title": "a"},"#), None);
+        assert_eq!(repair_json_arguments("not json"), None);
+    }
+
     #[test]
     fn test_sanitize_tool_call_invalid_json() {
         let malformed_name = "todos [invalid json}";
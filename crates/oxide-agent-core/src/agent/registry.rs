@@ -2,15 +2,20 @@
 //!
 //! Collects tools from all registered providers and routes tool calls appropriately.
 
+use super::compression::OutputCompressor;
 use super::provider::ToolProvider;
+use super::tool_error::{ToolError, ToolErrorKind};
 use crate::agent::progress::AgentEvent;
 use crate::llm::ToolDefinition;
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use std::collections::HashSet;
 use tracing::{debug, info, warn};
 
 /// Registry that manages multiple tool providers
 pub struct ToolRegistry {
     providers: Vec<Box<dyn ToolProvider>>,
+    allowlist: Option<HashSet<String>>,
+    compressor: Option<OutputCompressor>,
 }
 
 impl ToolRegistry {
@@ -19,9 +24,37 @@ impl ToolRegistry {
     pub const fn new() -> Self {
         Self {
             providers: Vec::new(),
+            allowlist: None,
+            compressor: None,
         }
     }
 
+    /// Restrict the registry to the given tool names (`None` allows every tool).
+    ///
+    /// Filtered-out tools are hidden from [`Self::all_tools`] and calling them
+    /// returns a "not permitted" error instead of running.
+    #[must_use]
+    pub fn with_allowlist(mut self, allowlist: Option<HashSet<String>>) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Fold repetitive successful results with [`Self::compress_output`]
+    /// (`None` keeps every result as is).
+    #[must_use]
+    pub fn with_output_compressor(mut self, compressor: Option<OutputCompressor>) -> Self {
+        self.compressor = compressor;
+        self
+    }
+
+    /// Whether the allowlist permits the tool
+    #[must_use]
+    pub fn is_permitted(&self, tool_name: &str) -> bool {
+        self.allowlist
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(tool_name))
+    }
+
     /// Register a new tool provider
     pub fn register(&mut self, provider: Box<dyn ToolProvider>) {
         info!(provider = provider.name(), "Registered tool provider");
@@ -31,7 +64,11 @@ impl ToolRegistry {
     /// Get all tools from all registered providers
     #[must_use]
     pub fn all_tools(&self) -> Vec<ToolDefinition> {
-        self.providers.iter().flat_map(|p| p.tools()).collect()
+        self.providers
+            .iter()
+            .flat_map(|p| p.tools())
+            .filter(|tool| self.is_permitted(&tool.name))
+            .collect()
     }
 
     /// Find a provider and execute the tool
@@ -48,6 +85,18 @@ impl ToolRegistry {
     ) -> Result<String> {
         debug!(tool = tool_name, "Looking for provider to handle tool");
 
+        if !self.is_permitted(tool_name) && self.can_handle(tool_name) {
+            warn!(tool = tool_name, "Tool blocked by allowlist");
+            return Err(ToolError::new(
+                ToolErrorKind::PermissionDenied,
+                format!(
+                    "Tool not permitted: '{tool_name}' is not enabled for this user. \
+                     Continue with the available tools or explain the limitation."
+                ),
+            )
+            .into());
+        }
+
         for provider in &self.providers {
             if provider.can_handle(tool_name) {
                 debug!(
@@ -55,14 +104,29 @@ impl ToolRegistry {
                     provider = provider.name(),
                     "Found provider for tool"
                 );
-                return provider
+                let start = std::time::Instant::now();
+                let result = provider
                     .execute(tool_name, arguments, progress_tx, cancellation_token)
                     .await;
+                crate::metrics::record_tool_execution(tool_name, start.elapsed(), result.is_ok());
+                return result;
             }
         }
 
         warn!(tool = tool_name, "No provider found for tool");
-        Err(anyhow!("Unknown tool: {tool_name}"))
+        Err(ToolError::new(
+            ToolErrorKind::Unavailable,
+            format!("Unknown tool: {tool_name}"),
+        )
+        .into())
+    }
+
+    /// `output` of a successful `tool_name` run, folded if a compressor is set
+    pub async fn compress_output(&self, tool_name: &str, output: String) -> String {
+        match &self.compressor {
+            Some(compressor) => compressor.compress(tool_name, output).await,
+            None => output,
+        }
     }
 
     /// Check if any provider can handle the tool
@@ -83,3 +147,37 @@ impl Default for ToolRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{TodoList, TodosProvider};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    fn registry(allowlist: Option<HashSet<String>>) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(TodosProvider::new(Arc::new(Mutex::new(
+            TodoList::new(),
+        )))));
+        registry.with_allowlist(allowlist)
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_hides_and_denies_tools() {
+        assert!(registry(None)
+            .all_tools()
+            .iter()
+            .any(|tool| tool.name == "write_todos"));
+
+        let restricted = registry(Some(HashSet::from(["web_search".to_string()])));
+        assert!(restricted.all_tools().is_empty());
+
+        let error = match restricted.execute("write_todos", "{}", None, None).await {
+            Err(error) => ToolError::classify(&error),
+            Ok(output) => panic!("expected permission error, got {output}"),
+        };
+        assert_eq!(error.kind, ToolErrorKind::PermissionDenied);
+        assert!(error.message.contains("not permitted"));
+    }
+}
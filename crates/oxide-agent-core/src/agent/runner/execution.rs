@@ -1,20 +1,58 @@
 //! Core execution loop for the agent runner.
 
-use super::types::{AgentRunnerContext, FinalResponseInput, RunState, StructuredOutputFailure};
+use super::types::{
+    AgentRunResult, AgentRunnerContext, FinalResponseInput, Outcome, RunState, RunStats,
+    StructuredOutputFailure,
+};
 use super::AgentRunner;
+use crate::agent::memory::AgentMessage;
+use crate::agent::messages::AgentLanguage;
 use crate::agent::progress::AgentEvent;
-use crate::agent::recovery::sanitize_tool_calls;
+use crate::agent::recovery::{sanitize_tool_calls, sanitize_xml_tags};
 use crate::agent::structured_output::parse_structured_output;
-use crate::llm::ChatResponse;
+use crate::llm::{ChatResponse, Message};
 use anyhow::{anyhow, Result};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 impl AgentRunner {
     /// Execute the agent loop until completion or error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run fails or stops without an answer
+    /// (see [`AgentRunResult::into_response`]).
     pub async fn run(&mut self, ctx: &mut AgentRunnerContext<'_>) -> Result<String> {
+        self.run_detailed(ctx)
+            .await
+            .and_then(AgentRunResult::into_response)
+    }
+
+    /// Execute the agent loop and report how it ended.
+    ///
+    /// Cancellation, loops, timeouts and limits are reported through
+    /// [`AgentRunResult::outcome`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an LLM call or a hook fails.
+    pub async fn run_detailed(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+    ) -> Result<AgentRunResult> {
+        self.stats = RunStats::default();
+        if !ctx.config.is_sub_agent {
+            crate::agent::debug::start_run(ctx.user_id);
+        }
         self.reset_loop_detector(ctx).await;
         self.apply_before_agent_hooks(ctx)?;
-        self.run_loop(ctx).await
+        let result = self.run_loop(ctx).await;
+
+        let stats = std::mem::take(&mut self.stats);
+        match result {
+            Ok(response) => Ok(stats.into_result(response, true)),
+            Err(e) if stats.outcome.is_some() => Ok(stats.into_result(e.to_string(), false)),
+            Err(e) => Err(e),
+        }
     }
 
     async fn run_loop(&mut self, ctx: &mut AgentRunnerContext<'_>) -> Result<String> {
@@ -22,18 +60,38 @@ impl AgentRunner {
 
         for iteration in 0..ctx.config.max_iterations {
             state.iteration = iteration;
+            self.stats.iterations = iteration + 1;
+            if !ctx.config.is_sub_agent {
+                crate::agent::debug::record_iteration(ctx.user_id, iteration + 1);
+            }
+
+            if iteration > 0 && !ctx.config.iteration_delay.is_zero() {
+                // Rate limiting for strict RPM quotas; cancellation cuts it short
+                tokio::select! {
+                    () = tokio::time::sleep(ctx.config.iteration_delay) => {}
+                    () = ctx.agent.cancellation_token().cancelled() => {}
+                }
+            }
 
             if ctx.agent.cancellation_token().is_cancelled() {
                 return Err(self.cancelled_error(ctx).await);
             }
 
+            if !ctx.config.is_sub_agent {
+                inject_user_instructions(ctx).await;
+            }
+
             if ctx.agent.elapsed_secs() >= ctx.config.timeout_secs {
                 if let Some(res) = self.apply_timeout_hook(ctx, &state)? {
+                    self.stats.stop(Outcome::Timeout);
                     return Ok(res);
                 }
             }
 
-            self.apply_before_iteration_hooks(ctx, &state)?;
+            if let Some(res) = self.apply_before_iteration_hooks(ctx, &state)? {
+                self.stats.stop(Outcome::BudgetExhausted);
+                return Ok(res);
+            }
 
             debug!(task_id = %ctx.task_id, iteration = iteration, "Agent loop iteration");
 
@@ -68,9 +126,10 @@ impl AgentRunner {
             }
         }
 
+        self.stats.stop(Outcome::IterationLimit);
         Err(anyhow!(
-            "Agent exceeded iteration limit ({}).",
-            ctx.config.max_iterations
+            "{}",
+            AgentLanguage::current().iteration_limit(ctx.config.max_iterations)
         ))
     }
 
@@ -185,7 +244,7 @@ impl AgentRunner {
         }
 
         self.record_assistant_tool_call(ctx, &raw_json, &tool_calls);
-        if let Some(res) = self.execute_tools(ctx, state, tool_calls).await? {
+        if let Some(res) = self.execute_tools(ctx, state, tool_calls.into()).await? {
             return Ok(Some(res));
         }
         Ok(None)
@@ -198,15 +257,16 @@ impl AgentRunner {
         ctx: &mut AgentRunnerContext<'_>,
         state: &mut RunState,
     ) -> Result<Option<String>> {
-        let tool_calls = sanitize_tool_calls(std::mem::take(&mut response.tool_calls));
+        let sanitized = sanitize_tool_calls(std::mem::take(&mut response.tool_calls));
+        let unique_calls = sanitized.unique_calls();
 
         self.spawn_narrative_task(
             response.reasoning_content.as_deref(),
-            &tool_calls,
+            &unique_calls,
             ctx.progress_tx,
         );
 
-        if self.tool_loop_detected(&tool_calls).await {
+        if self.tool_loop_detected(&unique_calls).await {
             return Err(self
                 .loop_detected_error(
                     ctx,
@@ -216,8 +276,8 @@ impl AgentRunner {
                 .await);
         }
 
-        self.record_assistant_tool_call(ctx, raw_json, &tool_calls);
-        if let Some(res) = self.execute_tools(ctx, state, tool_calls).await? {
+        self.record_assistant_tool_call(ctx, raw_json, &sanitized.calls);
+        if let Some(res) = self.execute_tools(ctx, state, sanitized).await? {
             return Ok(Some(res));
         }
         Ok(None)
@@ -277,9 +337,10 @@ impl AgentRunner {
         ctx: &mut AgentRunnerContext<'_>,
     ) {
         if let Some(u) = &response.usage {
-            ctx.agent
-                .memory_mut()
-                .sync_token_count(u.total_tokens as usize);
+            self.stats.add_usage(u);
+            let memory = ctx.agent.memory_mut();
+            memory.sync_token_count(u.total_tokens as usize);
+            memory.record_token_usage(u.total_tokens as usize);
         }
 
         if let Some(ref reasoning) = response.reasoning_content {
@@ -288,6 +349,13 @@ impl AgentRunner {
             if let Some(tx) = ctx.progress_tx {
                 let summary = crate::agent::thoughts::extract_reasoning_summary(reasoning, 100);
                 let _ = tx.send(AgentEvent::Reasoning { summary }).await;
+
+                if crate::config::is_reasoning_visible(ctx.user_id) {
+                    let content = sanitize_xml_tags(reasoning.trim());
+                    if !content.is_empty() {
+                        let _ = tx.send(AgentEvent::ReasoningDetails { content }).await;
+                    }
+                }
             }
         }
 
@@ -332,6 +400,7 @@ impl AgentRunner {
         &mut self,
         ctx: &mut AgentRunnerContext<'_>,
     ) -> anyhow::Error {
+        self.stats.stop(Outcome::Cancelled);
         ctx.agent.memory_mut().todos.clear();
         let mut todos = ctx.todos_arc.lock().await;
         todos.clear();
@@ -345,8 +414,26 @@ impl AgentRunner {
             let _ = tx.send(AgentEvent::Cancelled).await;
         }
 
-        anyhow!("Task cancelled by user")
+        anyhow!("{}", AgentLanguage::current().cancelled())
     }
 
     // Response helpers live in responses.rs
 }
+
+/// Add instructions the user sent since the last iteration to the conversation
+async fn inject_user_instructions(ctx: &mut AgentRunnerContext<'_>) {
+    for instruction in crate::agent::instructions::take_instructions(ctx.user_id) {
+        info!(task_id = %ctx.task_id, "Injecting mid-task instruction from the user");
+        let content = AgentLanguage::current().mid_task_instruction(&instruction);
+        ctx.messages.push(Message::user(&content));
+        ctx.agent
+            .memory_mut()
+            .add_message(AgentMessage::user(content));
+
+        if let Some(tx) = ctx.progress_tx {
+            let _ = tx
+                .send(AgentEvent::InstructionInjected { instruction })
+                .await;
+        }
+    }
+}
@@ -43,11 +43,13 @@ impl AgentRunner {
     }
 
     /// Apply hooks before a loop iteration begins.
+    ///
+    /// Returns a report when a hook decides to finish the run.
     pub(super) fn apply_before_iteration_hooks(
         &mut self,
         ctx: &mut AgentRunnerContext<'_>,
         state: &RunState,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<String>> {
         let hook_context = HookContext::new(
             &ctx.agent.memory().todos,
             ctx.agent.memory(),
@@ -68,7 +70,7 @@ impl AgentRunner {
             &hook_context,
         );
 
-        self.apply_hook_result(result, ctx).map(|_| ())
+        self.apply_hook_result(result, ctx)
     }
 
     /// Apply hooks before executing a tool call.
@@ -213,7 +215,12 @@ impl AgentRunner {
                 Ok(None)
             }
             HookResult::Block { reason } => Err(anyhow::anyhow!(reason)),
-            HookResult::ForceIteration { .. } => Ok(None),
+            HookResult::ForceIteration { context, .. } => {
+                if let Some(context) = context {
+                    self.inject_system_context(ctx, context);
+                }
+                Ok(None)
+            }
             HookResult::Finish(report) => Ok(Some(report)),
         }
     }
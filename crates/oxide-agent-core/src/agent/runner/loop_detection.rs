@@ -1,8 +1,9 @@
 //! Loop detection helpers for the agent runner.
 
-use super::types::{AgentRunnerContext, RunState};
+use super::types::{AgentRunnerContext, Outcome, RunState};
 use super::AgentRunner;
 use crate::agent::loop_detection::LoopType;
+use crate::agent::messages::AgentLanguage;
 use crate::agent::progress::AgentEvent;
 use tracing::{debug, warn};
 
@@ -15,6 +16,9 @@ impl AgentRunner {
             detector.disable_for_session();
             self.loop_detection_disabled_next_run = false;
         }
+        if let Some(overrides) = self.content_loop_override_next_run.take() {
+            detector.override_content_detection(&overrides);
+        }
     }
 
     /// Build and emit a loop-detected error.
@@ -22,7 +26,7 @@ impl AgentRunner {
     /// Cancels the agent's cancellation token to ensure the run loop terminates
     /// before the UI notification reaches the user (fixes race condition with reset).
     pub(super) async fn loop_detected_error(
-        &self,
+        &mut self,
         ctx: &mut AgentRunnerContext<'_>,
         state: &RunState,
         loop_type: LoopType,
@@ -39,6 +43,8 @@ impl AgentRunner {
             "Loop detected in agent execution"
         );
 
+        self.stats.stop(Outcome::LoopDetected);
+
         // Cancel the token BEFORE sending the UI event.
         // This ensures the run loop terminates before user can press "Reset".
         ctx.agent.cancellation_token().cancel();
@@ -52,7 +58,10 @@ impl AgentRunner {
                 .await;
         }
 
-        anyhow::anyhow!("Loop detected: {:?}", loop_type)
+        anyhow::anyhow!(
+            "{}",
+            AgentLanguage::current().loop_detected(&format!("{loop_type:?}"))
+        )
     }
 
     /// Check for LLM-based loop detection signals.
@@ -86,6 +95,21 @@ impl AgentRunner {
         }
     }
 
+    /// Check whether a tool keeps failing with the same fatal error.
+    pub(super) async fn fatal_error_loop_detected(
+        &self,
+        tool_result: &crate::agent::tool_bridge::ToolExecutionResult,
+    ) -> bool {
+        let mut detector = self.loop_detector.lock().await;
+        match detector.check_tool_result(&tool_result.tool_name, tool_result.error.as_ref()) {
+            Ok(detected) => detected,
+            Err(err) => {
+                warn!(error = %err, "Fatal error loop check failed, continuing");
+                false
+            }
+        }
+    }
+
     /// Check for repeated tool call loops.
     pub(super) async fn tool_loop_detected(&self, tool_calls: &[crate::llm::ToolCall]) -> bool {
         let mut detector = self.loop_detector.lock().await;
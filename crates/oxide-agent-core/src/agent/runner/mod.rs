@@ -10,14 +10,16 @@ mod tools;
 mod types;
 
 use crate::agent::hooks::HookRegistry;
-use crate::agent::loop_detection::{LoopDetectionConfig, LoopDetectionService};
+use crate::agent::loop_detection::{
+    ContentLoopOverride, LoopDetectionConfig, LoopDetectionService,
+};
 use crate::agent::memory::AgentMessage;
 use crate::agent::narrator::Narrator;
 use crate::llm::{LlmClient, Message};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-pub use types::{AgentRunnerConfig, AgentRunnerContext};
+pub use types::{AgentRunResult, AgentRunnerConfig, AgentRunnerContext, Outcome};
 
 /// Agent runner that executes the core loop.
 pub struct AgentRunner {
@@ -25,7 +27,9 @@ pub struct AgentRunner {
     hook_registry: HookRegistry,
     loop_detector: Arc<Mutex<LoopDetectionService>>,
     loop_detection_disabled_next_run: bool,
+    content_loop_override_next_run: Option<ContentLoopOverride>,
     narrator: Arc<Narrator>,
+    stats: types::RunStats,
 }
 
 impl AgentRunner {
@@ -45,7 +49,9 @@ impl AgentRunner {
             hook_registry: HookRegistry::new(),
             loop_detector,
             loop_detection_disabled_next_run: false,
+            content_loop_override_next_run: None,
             narrator,
+            stats: types::RunStats::default(),
         }
     }
 
@@ -65,14 +71,40 @@ impl AgentRunner {
         self.loop_detection_disabled_next_run = true;
     }
 
+    /// Use different content loop settings for the next execution attempt.
+    pub fn override_content_loop_next_run(&mut self, overrides: ContentLoopOverride) {
+        self.content_loop_override_next_run = Some(overrides);
+    }
+
+    /// Whether loop detection is off for the current or the next run.
+    #[must_use]
+    pub fn loop_detection_disabled(&self) -> bool {
+        self.loop_detection_disabled_next_run
+            || self
+                .loop_detector
+                .try_lock()
+                .is_ok_and(|detector| !detector.is_enabled())
+    }
+
     /// Reset internal loop detector state.
     pub fn reset(&mut self) {
         self.loop_detection_disabled_next_run = false;
+        self.content_loop_override_next_run = None;
         if let Ok(mut detector) = self.loop_detector.try_lock() {
             detector.reset(String::new());
         }
     }
 
+    /// Result for a run aborted from outside the runner (e.g. by a hard timeout).
+    ///
+    /// Uses the counters collected before the run was aborted.
+    #[must_use]
+    pub fn interrupted_run_result(&mut self, outcome: Outcome, reason: String) -> AgentRunResult {
+        let mut stats = std::mem::take(&mut self.stats);
+        stats.stop(outcome);
+        stats.into_result(reason, false)
+    }
+
     /// Convert `AgentMessage` history to LLM Message format.
     #[must_use]
     pub fn convert_memory_to_messages(messages: &[AgentMessage]) -> Vec<Message> {
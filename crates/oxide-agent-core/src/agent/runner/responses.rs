@@ -1,7 +1,10 @@
 //! Response handling for the agent runner.
 
-use super::types::{AgentRunnerContext, FinalResponseInput, RunState, StructuredOutputFailure};
+use super::types::{
+    AgentRunnerContext, FinalResponseInput, Outcome, RunState, StructuredOutputFailure,
+};
 use super::AgentRunner;
+use crate::agent::messages::AgentLanguage;
 use crate::agent::progress::AgentEvent;
 use crate::agent::tool_bridge::sync_todos_from_arc;
 use tracing::warn;
@@ -32,7 +35,7 @@ impl AgentRunner {
             if let Some(tx) = ctx.progress_tx {
                 let _ = tx
                     .send(AgentEvent::Continuation {
-                        reason: "Too many JSON errors, falling back to raw response".to_string(),
+                        reason: AgentLanguage::current().json_fallback().to_string(),
                         count: state.continuation_count,
                     })
                     .await;
@@ -51,18 +54,15 @@ impl AgentRunner {
         if let Some(tx) = ctx.progress_tx {
             let _ = tx
                 .send(AgentEvent::Continuation {
-                    reason: "Invalid JSON response, retrying...".to_string(),
+                    reason: AgentLanguage::current().invalid_json_retry().to_string(),
                     count: state.continuation_count,
                 })
                 .await;
         }
 
         let response_preview = crate::utils::truncate_str(&failure.raw_json, 400);
-        let system_message = format!(
-            "[SYSTEM: Your previous response does not follow the strict JSON schema.\nError: {}\nResponse: {}\nReturn ONLY valid JSON according to the schema without markdown, XML, or text outside JSON.]",
-            failure.error.message(),
-            response_preview
-        );
+        let system_message = AgentLanguage::current()
+            .structured_output_nudge(failure.error.message(), &response_preview);
         ctx.messages
             .push(crate::llm::Message::system(&system_message));
 
@@ -121,10 +121,22 @@ impl AgentRunner {
                 "[SYSTEM: {reason}]\n\n{}",
                 context.unwrap_or_default()
             )));
+            state.partial_answers.push(final_response);
             return Ok(None);
         }
 
-        self.save_final_response(ctx, &input.raw_json, input.reasoning);
+        let mut raw_json = input.raw_json;
+        let final_response = if gave_up_on_continuations(ctx, state) {
+            let best = self
+                .finish_at_continuation_limit(ctx, state, final_response)
+                .await;
+            raw_json.clone_from(&best);
+            best
+        } else {
+            final_response
+        };
+
+        self.save_final_response(ctx, &raw_json, input.reasoning);
 
         if let Some(tx) = ctx.progress_tx {
             if !ctx.config.is_sub_agent {
@@ -133,4 +145,37 @@ impl AgentRunner {
         }
         Ok(Some(final_response))
     }
+
+    /// Stop sending the agent back to its todos and pick the best answer so far.
+    async fn finish_at_continuation_limit(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+        state: &RunState,
+        latest: String,
+    ) -> String {
+        warn!(
+            continuations = state.continuation_count,
+            partial_answers = state.partial_answers.len(),
+            "Continuation limit reached with open todos, finishing with the best partial answer"
+        );
+        self.stats.stop(Outcome::ContinuationLimit);
+
+        if let Some(tx) = ctx.progress_tx {
+            let _ = tx
+                .send(AgentEvent::ContinuationLimitReached {
+                    limit: ctx.config.continuation_limit,
+                })
+                .await;
+        }
+
+        state.most_complete_answer(latest)
+    }
+}
+
+/// Whether the run is finishing only because no continuations are left.
+fn gave_up_on_continuations(ctx: &AgentRunnerContext<'_>, state: &RunState) -> bool {
+    let todos = &ctx.agent.memory().todos;
+    state.continuation_count >= ctx.config.continuation_limit
+        && !todos.items.is_empty()
+        && !todos.is_complete()
 }
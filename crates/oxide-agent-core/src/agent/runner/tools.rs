@@ -1,16 +1,39 @@
 //! Tool execution helpers for the agent runner.
 
 use super::hooks::ToolHookDecision;
-use super::types::{AgentRunnerContext, RunState};
+use super::types::{AgentRunnerContext, FinalResponseInput, Outcome, RunState};
 use super::AgentRunner;
+use crate::agent::loop_detection::LoopType;
 use crate::agent::memory::AgentMessage;
+use crate::agent::messages::AgentLanguage;
 use crate::agent::progress::AgentEvent;
-use crate::agent::recovery::sanitize_xml_tags;
-use crate::agent::tool_bridge::{execute_single_tool_call, ToolExecutionContext};
+use crate::agent::providers::clarification::ASK_USER_TOOL;
+use crate::agent::providers::finish::{parse_finish_args, FinishTaskArgs, FINISH_TASK_TOOL};
+use crate::agent::recovery::{sanitize_xml_tags, SanitizedToolCalls};
+use crate::agent::tool_bridge::{
+    execute_single_tool_call, record_tool_run, run_tool_calls, ToolExecutionContext,
+    ToolExecutionResult, ToolRunContext,
+};
+use crate::agent::tool_error::ToolErrorKind;
+use crate::config::get_agent_clarification_timeout_secs;
 use crate::llm::{Message, ToolCall, ToolCallFunction};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Tools that end the turn or change what later calls see; they never run
+/// in a concurrent batch
+const SERIAL_TOOLS: &[&str] = &[FINISH_TASK_TOOL, ASK_USER_TOOL, "set_cwd", "set_env"];
+
+/// What to do with a tool call after the before-tool hooks
+enum ToolCallStep {
+    /// Execute the call
+    Run,
+    /// The call was blocked and its result already recorded
+    Skip,
+    /// Stop the run with this progress report
+    Stop(String),
+}
+
 impl AgentRunner {
     /// Build a tool call payload from validated structured output.
     pub(super) fn build_tool_call(
@@ -23,7 +46,7 @@ impl AgentRunner {
                 name: tool_call.name,
                 arguments: tool_call.arguments_json,
             },
-            is_recovered: false,
+            is_recovered: tool_call.is_recovered,
         }
     }
 
@@ -47,52 +70,310 @@ impl AgentRunner {
             ));
     }
 
-    /// Execute all tool calls in sequence.
+    /// Execute the tool calls of one response.
+    ///
+    /// Repeated identical calls run once; their ids get the same result.
+    /// With `max_parallel_tools` above 1, approved calls run concurrently
+    /// up to that limit and their results are recorded in call order;
+    /// `finish_task`, `ask_user`, `set_cwd` and `set_env` still run alone.
     pub(super) async fn execute_tools(
         &mut self,
         ctx: &mut AgentRunnerContext<'_>,
-        state: &RunState,
-        tool_calls: Vec<ToolCall>,
+        state: &mut RunState,
+        tool_calls: SanitizedToolCalls,
     ) -> anyhow::Result<Option<String>> {
-        for tool_call in &tool_calls {
-            self.load_skill_context_for_tool(ctx, &tool_call.function.name)
-                .await?;
-            match self.apply_before_tool_hooks(ctx, state, tool_call)? {
-                ToolHookDecision::Continue => {}
-                ToolHookDecision::Blocked { reason } => {
-                    self.record_blocked_tool_result(ctx, tool_call, &reason)
-                        .await;
-                    continue;
-                }
-                ToolHookDecision::Finish { report } => {
-                    return Ok(Some(report));
-                }
+        if ctx.config.max_parallel_tools > 1 {
+            return self
+                .execute_tools_concurrently(ctx, state, &tool_calls)
+                .await;
+        }
+
+        for tool_call in &tool_calls.unique_calls() {
+            match self
+                .prepare_tool_call(ctx, state, &tool_calls, tool_call)
+                .await?
+            {
+                ToolCallStep::Run => {}
+                ToolCallStep::Skip => continue,
+                ToolCallStep::Stop(report) => return Ok(Some(report)),
             }
-            let cancellation_token = ctx.agent.cancellation_token().clone();
-            let memory = ctx.agent.memory_mut();
-            let mut tool_ctx = ToolExecutionContext {
-                registry: ctx.registry,
-                progress_tx: ctx.progress_tx,
-                todos_arc: ctx.todos_arc,
-                messages: ctx.messages,
-                memory,
-                cancellation_token,
+            let tool_result = {
+                let mut tool_ctx = Self::tool_context(ctx);
+                execute_single_tool_call(tool_call.clone(), &mut tool_ctx).await?
             };
-            let tool_result = execute_single_tool_call(tool_call.clone(), &mut tool_ctx).await?;
-            self.apply_after_tool_hooks(ctx, state, &tool_result);
+            if let Some(done) = self
+                .after_tool_call(ctx, state, &tool_calls, tool_call, &tool_result)
+                .await
+            {
+                return done;
+            }
         }
         Ok(None)
     }
 
-    async fn record_blocked_tool_result(
+    /// Run approved calls as the shared tool permits allow
+    ///
+    /// Calls of [`SERIAL_TOOLS`] run alone, in call order between the
+    /// concurrent batches around them, so e.g. commands after a `set_cwd`
+    /// see the new directory. Calls within one batch cannot depend on each
+    /// other: they all start from the session state before the batch.
+    async fn execute_tools_concurrently(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+        state: &mut RunState,
+        tool_calls: &SanitizedToolCalls,
+    ) -> anyhow::Result<Option<String>> {
+        let mut approved = Vec::new();
+        for tool_call in tool_calls.unique_calls() {
+            match self
+                .prepare_tool_call(ctx, state, tool_calls, &tool_call)
+                .await?
+            {
+                ToolCallStep::Run => approved.push(tool_call),
+                ToolCallStep::Skip => {}
+                ToolCallStep::Stop(report) => return Ok(Some(report)),
+            }
+        }
+
+        let mut batch = Vec::new();
+        for tool_call in approved {
+            if !SERIAL_TOOLS.contains(&tool_call.function.name.as_str()) {
+                batch.push(tool_call);
+                continue;
+            }
+            let batch = std::mem::take(&mut batch);
+            if let Some(done) = self.run_tool_batch(ctx, state, tool_calls, &batch).await {
+                return done;
+            }
+            let tool_result = {
+                let mut tool_ctx = Self::tool_context(ctx);
+                execute_single_tool_call(tool_call.clone(), &mut tool_ctx).await?
+            };
+            if let Some(done) = self
+                .after_tool_call(ctx, state, tool_calls, &tool_call, &tool_result)
+                .await
+            {
+                return done;
+            }
+        }
+        self.run_tool_batch(ctx, state, tool_calls, &batch)
+            .await
+            .unwrap_or(Ok(None))
+    }
+
+    /// Run `batch` concurrently and handle the results in call order; `Some`
+    /// ends the tool loop with that result
+    async fn run_tool_batch(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+        state: &mut RunState,
+        tool_calls: &SanitizedToolCalls,
+        batch: &[ToolCall],
+    ) -> Option<anyhow::Result<Option<String>>> {
+        if batch.is_empty() {
+            return None;
+        }
+        let cancellation_token = ctx.agent.cancellation_token().clone();
+        let run_ctx = ToolRunContext {
+            registry: ctx.registry,
+            progress_tx: ctx.progress_tx,
+            cancellation_token: &cancellation_token,
+            user_id: ctx.user_id,
+            task_id: ctx.task_id,
+        };
+        let runs = run_tool_calls(batch, &run_ctx, &ctx.config.tool_permits).await;
+
+        for (tool_call, run) in batch.iter().zip(runs) {
+            let run = match run {
+                Ok(run) => run,
+                Err(e) => return Some(Err(e)),
+            };
+            let tool_result = {
+                let mut tool_ctx = Self::tool_context(ctx);
+                record_tool_run(tool_call, run, &mut tool_ctx).await
+            };
+            if let Some(done) = self
+                .after_tool_call(ctx, state, tool_calls, tool_call, &tool_result)
+                .await
+            {
+                return Some(done);
+            }
+        }
+        None
+    }
+
+    /// Count the call, load its skill and apply the before-tool hooks
+    async fn prepare_tool_call(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+        state: &mut RunState,
+        tool_calls: &SanitizedToolCalls,
+        tool_call: &ToolCall,
+    ) -> anyhow::Result<ToolCallStep> {
+        record_tool_call_metric(&ctx.config.model_name, tool_call);
+        if !has_valid_arguments(tool_call) {
+            // Most likely a stream cut off mid-call; never run a guessed command
+            warn!(
+                tool = %tool_call.function.name,
+                "Rejecting tool call with truncated arguments"
+            );
+            let output = self
+                .record_rejected_tool_result(ctx, tool_call, TRUNCATED_ARGUMENTS_ERROR.to_string())
+                .await;
+            let duplicate_ids = tool_calls.duplicate_ids_of(&tool_call.id);
+            Self::record_duplicate_results(ctx, &duplicate_ids, tool_call, &output);
+            return Ok(ToolCallStep::Skip);
+        }
+        self.load_skill_context_for_tool(ctx, &tool_call.function.name)
+            .await?;
+        match self.apply_before_tool_hooks(ctx, state, tool_call)? {
+            ToolHookDecision::Continue => {}
+            ToolHookDecision::Blocked { reason } => {
+                let output = self
+                    .record_rejected_tool_result(
+                        ctx,
+                        tool_call,
+                        format!("⛔ Tool call blocked by policy.\n{reason}"),
+                    )
+                    .await;
+                let duplicate_ids = tool_calls.duplicate_ids_of(&tool_call.id);
+                Self::record_duplicate_results(ctx, &duplicate_ids, tool_call, &output);
+                return Ok(ToolCallStep::Skip);
+            }
+            ToolHookDecision::Finish { report } => {
+                self.stats.stop(Outcome::BudgetExhausted);
+                return Ok(ToolCallStep::Stop(report));
+            }
+        }
+        self.stats.tool_calls.push(tool_call.function.name.clone());
+        if !ctx.config.is_sub_agent {
+            crate::agent::debug::record_tool_call(ctx.user_id, &tool_call.function.name);
+        }
+        Ok(ToolCallStep::Run)
+    }
+
+    /// Handle a recorded tool result; `Some` ends the tool loop with that result
+    async fn after_tool_call(
         &mut self,
         ctx: &mut AgentRunnerContext<'_>,
+        state: &mut RunState,
+        tool_calls: &SanitizedToolCalls,
         tool_call: &ToolCall,
-        reason: &str,
+        tool_result: &ToolExecutionResult,
+    ) -> Option<anyhow::Result<Option<String>>> {
+        let duplicate_ids = tool_calls.duplicate_ids_of(&tool_call.id);
+        Self::record_duplicate_results(ctx, &duplicate_ids, tool_call, &tool_result.output);
+        if let Some(finish) = Self::finish_request(tool_call, tool_result) {
+            return Some(self.finish_task(ctx, state, finish).await);
+        }
+        if Self::is_unanswered_question(tool_result) {
+            // Nobody is there to answer; stop instead of letting the model guess
+            return Some(Err(anyhow::anyhow!(
+                "{}",
+                AgentLanguage::current()
+                    .clarification_timeout(get_agent_clarification_timeout_secs())
+            )));
+        }
+        self.apply_after_tool_hooks(ctx, state, tool_result);
+        if self.fatal_error_loop_detected(tool_result).await {
+            return Some(Err(self
+                .loop_detected_error(ctx, state, LoopType::FatalErrorLoop)
+                .await));
+        }
+        None
+    }
+
+    /// Context that records tool results into the runner's conversation
+    fn tool_context<'c>(ctx: &'c mut AgentRunnerContext<'_>) -> ToolExecutionContext<'c> {
+        let cancellation_token = ctx.agent.cancellation_token().clone();
+        ToolExecutionContext {
+            registry: ctx.registry,
+            progress_tx: ctx.progress_tx,
+            todos_arc: ctx.todos_arc,
+            messages: ctx.messages,
+            memory: ctx.agent.memory_mut(),
+            cancellation_token,
+            user_id: ctx.user_id,
+            task_id: ctx.task_id,
+        }
+    }
+
+    /// Give repeats of `tool_call` in the same response its result
+    fn record_duplicate_results(
+        ctx: &mut AgentRunnerContext<'_>,
+        duplicate_ids: &[String],
+        tool_call: &ToolCall,
+        output: &str,
     ) {
+        let tool_name = &tool_call.function.name;
+        for id in duplicate_ids {
+            ctx.messages.push(Message::tool(id, tool_name, output));
+            ctx.agent
+                .memory_mut()
+                .add_message(AgentMessage::tool(id, tool_name, output));
+        }
+    }
+
+    /// Arguments of a successful `finish_task` call
+    fn finish_request(
+        tool_call: &ToolCall,
+        tool_result: &ToolExecutionResult,
+    ) -> Option<FinishTaskArgs> {
+        if tool_result.tool_name != FINISH_TASK_TOOL || tool_result.error.is_some() {
+            return None;
+        }
+        parse_finish_args(&tool_call.function.arguments).ok()
+    }
+
+    /// End the run with the message passed to `finish_task`
+    ///
+    /// A successful finish is handled like a final answer, so open todos can
+    /// still send the agent back to work. Giving up ends the run right away.
+    async fn finish_task(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+        state: &mut RunState,
+        finish: FinishTaskArgs,
+    ) -> anyhow::Result<Option<String>> {
+        if finish.success {
+            let input = FinalResponseInput {
+                final_answer: finish.message.clone(),
+                raw_json: finish.message,
+                reasoning: None,
+            };
+            return self.handle_final_response(ctx, state, input).await;
+        }
+
+        info!(task_id = %ctx.task_id, "Agent stopped the task as impossible");
+        self.stats.stop(Outcome::GaveUp);
+        ctx.agent
+            .memory_mut()
+            .add_message(AgentMessage::assistant(finish.message.clone()));
+        if let Some(tx) = ctx.progress_tx {
+            if !ctx.config.is_sub_agent {
+                let _ = tx.send(AgentEvent::Finished).await;
+            }
+        }
+        Ok(Some(finish.message))
+    }
+
+    fn is_unanswered_question(tool_result: &ToolExecutionResult) -> bool {
+        tool_result.tool_name == ASK_USER_TOOL
+            && tool_result
+                .error
+                .as_ref()
+                .is_some_and(|error| error.kind == ToolErrorKind::Timeout)
+    }
+
+    /// Record `output` as the result of a call that was not executed
+    async fn record_rejected_tool_result(
+        &mut self,
+        ctx: &mut AgentRunnerContext<'_>,
+        tool_call: &ToolCall,
+        output: String,
+    ) -> String {
         let tool_name = &tool_call.function.name;
         let tool_args = &tool_call.function.arguments;
-        let output = format!("⛔ Tool call blocked by policy.\n{reason}");
 
         if let Some(tx) = ctx.progress_tx {
             let sanitized_name = sanitize_xml_tags(tool_name);
@@ -114,6 +395,7 @@ impl AgentRunner {
                 .send(AgentEvent::ToolResult {
                     name: sanitized_name,
                     output: output.clone(),
+                    duration_ms: 0,
                 })
                 .await;
         }
@@ -123,6 +405,7 @@ impl AgentRunner {
         ctx.agent
             .memory_mut()
             .add_message(AgentMessage::tool(&tool_call.id, tool_name, &output));
+        output
     }
 
     fn extract_command_preview(arguments: &str) -> Option<String> {
@@ -182,3 +465,25 @@ impl AgentRunner {
         Ok(())
     }
 }
+
+/// Tool result for a call whose arguments are not complete JSON
+const TRUNCATED_ARGUMENTS_ERROR: &str = "⚠️ Tool call not executed: its arguments were truncated \
+     or are not valid JSON. Resend the complete call.";
+
+/// Whether the call's arguments parse; providers send empty ones for no-arg tools
+fn has_valid_arguments(tool_call: &ToolCall) -> bool {
+    let arguments = tool_call.function.arguments.trim();
+    arguments.is_empty() || serde_json::from_str::<serde_json::Value>(arguments).is_ok()
+}
+
+/// Count the call per model, flagging calls recovered from malformed output
+fn record_tool_call_metric(model: &str, tool_call: &ToolCall) {
+    if tool_call.is_recovered {
+        warn!(
+            model = %model,
+            tool = %tool_call.function.name,
+            "METRIC: Executing tool call recovered from malformed model output"
+        );
+    }
+    crate::metrics::record_tool_call(model, tool_call.is_recovered);
+}
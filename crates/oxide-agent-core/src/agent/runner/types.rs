@@ -5,10 +5,14 @@ use crate::agent::progress::AgentEvent;
 use crate::agent::providers::TodoList;
 use crate::agent::registry::ToolRegistry;
 use crate::agent::skills::SkillRegistry;
-use crate::config::{get_agent_model, AGENT_CONTINUATION_LIMIT, AGENT_MAX_ITERATIONS};
-use crate::llm::{Message, ToolDefinition};
+use crate::config::{
+    get_agent_continuation_limit, get_agent_iteration_delay_ms, get_agent_model,
+    get_max_parallel_tools, AGENT_MAX_ITERATIONS,
+};
+use crate::llm::{Message, TokenUsage, ToolDefinition};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
 
 /// Configuration for the agent runner.
 #[derive(Debug, Clone)]
@@ -23,6 +27,13 @@ pub struct AgentRunnerConfig {
     pub is_sub_agent: bool,
     /// Soft timeout in seconds.
     pub timeout_secs: u64,
+    /// Pause before every iteration after the first.
+    pub iteration_delay: Duration,
+    /// Tool calls of one response that may run concurrently (1 = sequential).
+    pub max_parallel_tools: usize,
+    /// Permits for concurrent tool runs, shared with sub-agents so the
+    /// session as a whole stays within `max_parallel_tools`.
+    pub tool_permits: Arc<Semaphore>,
 }
 
 impl AgentRunnerConfig {
@@ -34,15 +45,42 @@ impl AgentRunnerConfig {
         continuation_limit: usize,
         timeout_secs: u64,
     ) -> Self {
+        let max_parallel_tools = get_max_parallel_tools().max(1);
         Self {
             model_name,
             max_iterations,
             continuation_limit,
             is_sub_agent: false,
             timeout_secs,
+            iteration_delay: Duration::from_millis(get_agent_iteration_delay_ms()),
+            max_parallel_tools,
+            tool_permits: Arc::new(Semaphore::new(max_parallel_tools)),
         }
     }
 
+    /// Override the pause between iterations (`AGENT_ITERATION_DELAY_MS`).
+    #[must_use]
+    pub fn with_iteration_delay(mut self, iteration_delay: Duration) -> Self {
+        self.iteration_delay = iteration_delay;
+        self
+    }
+
+    /// Override how many tool calls may run at once (`MAX_PARALLEL_TOOLS`).
+    #[must_use]
+    pub fn with_max_parallel_tools(mut self, max_parallel_tools: usize) -> Self {
+        self.max_parallel_tools = max_parallel_tools.max(1);
+        self.tool_permits = Arc::new(Semaphore::new(self.max_parallel_tools));
+        self
+    }
+
+    /// Draw tool run permits from a pool shared with other runners, e.g. the
+    /// parent agent of a sub-agent.
+    #[must_use]
+    pub fn with_tool_permits(mut self, tool_permits: Arc<Semaphore>) -> Self {
+        self.tool_permits = tool_permits;
+        self
+    }
+
     /// Set whether this runner is for a sub-agent.
     #[must_use]
     pub fn with_sub_agent(mut self, is_sub_agent: bool) -> Self {
@@ -56,7 +94,7 @@ impl Default for AgentRunnerConfig {
         Self::new(
             get_agent_model(),
             AGENT_MAX_ITERATIONS,
-            AGENT_CONTINUATION_LIMIT,
+            get_agent_continuation_limit(),
             crate::config::AGENT_TIMEOUT_SECS,
         )
     }
@@ -78,6 +116,8 @@ pub struct AgentRunnerContext<'a> {
     pub todos_arc: &'a Arc<Mutex<TodoList>>,
     /// Task ID for loop detection correlation.
     pub task_id: &'a str,
+    /// Session owner, recorded in the tool audit log.
+    pub user_id: i64,
     /// Messages for the current LLM conversation.
     pub messages: &'a mut Vec<Message>,
     /// Agent context abstraction (memory + cancellation).
@@ -88,6 +128,109 @@ pub struct AgentRunnerContext<'a> {
     pub config: AgentRunnerConfig,
 }
 
+/// How an agent run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The agent produced its final answer.
+    Completed,
+    /// The run hit the time limit (with a progress report if one was produced).
+    Timeout,
+    /// Loop detection stopped the run.
+    LoopDetected,
+    /// The user cancelled the run.
+    Cancelled,
+    /// The run used all iterations without a final answer.
+    IterationLimit,
+    /// The token or tool-call budget stopped the run with a progress report.
+    BudgetExhausted,
+    /// Todos were still open after the last allowed continuation; the run
+    /// ended with the most complete partial answer.
+    ContinuationLimit,
+    /// The agent called `finish_task` with `success: false`; the response
+    /// explains why the task cannot be done.
+    GaveUp,
+}
+
+/// Structured result of an agent run.
+#[derive(Debug, Clone)]
+pub struct AgentRunResult {
+    /// Final answer, progress report or the reason the run stopped.
+    pub response: String,
+    /// LLM iterations used.
+    pub iterations: usize,
+    /// Names of executed tools, in call order.
+    pub tool_calls: Vec<String>,
+    /// Tokens used by the run's LLM calls.
+    pub usage: TokenUsage,
+    /// How the run ended.
+    pub outcome: Outcome,
+    answered: bool,
+}
+
+impl AgentRunResult {
+    /// Whether `response` is an answer or report for the user rather than an error.
+    #[must_use]
+    pub const fn is_answer(&self) -> bool {
+        self.answered
+    }
+
+    /// Convert to the plain string result: runs without an answer become errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns the stop reason for cancelled, looping, iteration-limited and
+    /// hard-timed-out runs.
+    pub fn into_response(self) -> anyhow::Result<String> {
+        if self.answered {
+            Ok(self.response)
+        } else {
+            Err(anyhow::anyhow!("{}", self.response))
+        }
+    }
+}
+
+/// Counters collected over one run; they survive the run being aborted.
+#[derive(Debug, Default)]
+pub(super) struct RunStats {
+    /// LLM iterations started.
+    pub iterations: usize,
+    /// Names of executed tools.
+    pub tool_calls: Vec<String>,
+    /// Accumulated token usage.
+    pub usage: TokenUsage,
+    /// Terminal outcome other than completion, once known.
+    pub outcome: Option<Outcome>,
+}
+
+impl RunStats {
+    /// Record the reason the run stopped; the first reason wins.
+    pub(super) fn stop(&mut self, outcome: Outcome) {
+        self.outcome.get_or_insert(outcome);
+    }
+
+    /// Add the usage of one LLM call.
+    pub(super) fn add_usage(&mut self, usage: &TokenUsage) {
+        self.usage.prompt_tokens = self.usage.prompt_tokens.saturating_add(usage.prompt_tokens);
+        self.usage.completion_tokens = self
+            .usage
+            .completion_tokens
+            .saturating_add(usage.completion_tokens);
+        self.usage.total_tokens = self.usage.total_tokens.saturating_add(usage.total_tokens);
+    }
+
+    /// Build the run result.
+    pub(super) fn into_result(self, response: String, answered: bool) -> AgentRunResult {
+        AgentRunResult {
+            response,
+            iterations: self.iterations,
+            tool_calls: self.tool_calls,
+            usage: self.usage,
+            outcome: self.outcome.unwrap_or(Outcome::Completed),
+            answered,
+        }
+    }
+}
+
 /// Internal run state for the current loop execution.
 pub(super) struct RunState {
     /// Current iteration index.
@@ -96,6 +239,8 @@ pub(super) struct RunState {
     pub continuation_count: usize,
     /// Number of consecutive structured output failures.
     pub structured_output_failures: usize,
+    /// Answers rejected by a forced continuation, oldest first.
+    pub partial_answers: Vec<String>,
 }
 
 impl RunState {
@@ -105,8 +250,22 @@ impl RunState {
             iteration: 0,
             continuation_count: 0,
             structured_output_failures: 0,
+            partial_answers: Vec::new(),
         }
     }
+
+    /// The most complete of the partial answers and `latest`.
+    ///
+    /// Answers are compared by trimmed length; `latest` wins ties.
+    pub(super) fn most_complete_answer(&self, latest: String) -> String {
+        let len = |answer: &str| answer.trim().chars().count();
+        self.partial_answers
+            .iter()
+            .max_by_key(|answer| len(answer))
+            .filter(|best| len(best) > len(&latest))
+            .cloned()
+            .unwrap_or(latest)
+    }
 }
 
 /// Structured output parsing failure payload.
@@ -126,3 +285,27 @@ pub(super) struct FinalResponseInput {
     /// Optional reasoning content from the model.
     pub reasoning: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_complete_answer_prefers_longest() {
+        let mut state = RunState::new();
+        assert_eq!(state.most_complete_answer("done".into()), "done");
+
+        state.partial_answers = vec![
+            "Step 1 and step 2 are done, step 3 is next.".into(),
+            "Working on it".into(),
+        ];
+        assert_eq!(
+            state.most_complete_answer("Still working".into()),
+            "Step 1 and step 2 are done, step 3 is next."
+        );
+        assert_eq!(
+            state.most_complete_answer("All three steps are done, see the report below.".into()),
+            "All three steps are done, see the report below."
+        );
+    }
+}
@@ -11,7 +11,7 @@ use crate::sandbox::SandboxManager;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
@@ -46,6 +46,8 @@ pub struct AgentSession {
     sandbox: Option<SandboxManager>,
     /// When the current task started
     started_at: Option<Instant>,
+    /// When the previous task finished (used for follow-up detection)
+    finished_at: Option<Instant>,
     /// Unique ID for the current task execution (for log correlation)
     pub current_task_id: Option<String>,
     /// Current status
@@ -56,6 +58,9 @@ pub struct AgentSession {
     pub cancellation_token: CancellationToken,
     /// Last task text for retry actions.
     pub last_task: Option<String>,
+    /// User who started the current task, for per-user permissions.
+    /// Differs from the session ID when a group shares the session.
+    pub requester_id: Option<i64>,
     /// Loaded skills for the current system prompt or dynamic context.
     loaded_skills: HashSet<String>,
     /// Token count for loaded skills.
@@ -71,15 +76,25 @@ impl AgentSession {
             memory: AgentMemory::new(AGENT_MAX_TOKENS),
             sandbox: None,
             started_at: None,
+            finished_at: None,
             current_task_id: None,
             status: AgentStatus::Idle,
             cancellation_token: CancellationToken::new(),
             last_task: None,
+            requester_id: None,
             loaded_skills: HashSet::new(),
             skill_token_count: 0,
         }
     }
 
+    /// User whose permissions apply to the current task; the session
+    /// owner unless the transport set [`Self::requester_id`]
+    #[must_use]
+    pub fn requester_id(&self) -> i64 {
+        self.requester_id
+            .unwrap_or_else(|| self.session_id.as_i64())
+    }
+
     /// Renew the cancellation token before a new task
     /// CRITICAL: Prevents old cancellation signals from affecting new tasks
     pub fn renew_cancellation_token(&mut self) {
@@ -88,6 +103,7 @@ impl AgentSession {
 
     /// Start a new task, resetting the timer and generating a task ID
     pub fn start_task(&mut self) {
+        crate::agent::providers::clarification::reset_time_waited(self.session_id.as_i64());
         self.started_at = Some(Instant::now());
         self.current_task_id = Some(uuid::Uuid::new_v4().to_string());
         self.status = AgentStatus::Processing {
@@ -99,14 +115,18 @@ impl AgentSession {
     /// Check if the session has exceeded the timeout limit
     #[must_use]
     pub fn is_timed_out(&self) -> bool {
-        self.started_at
-            .is_some_and(|start| start.elapsed().as_secs() > AGENT_TIMEOUT_SECS)
+        self.elapsed_secs() > AGENT_TIMEOUT_SECS
     }
 
-    /// Get elapsed time in seconds since task start
+    /// Get elapsed time in seconds since task start, excluding time spent
+    /// waiting for the user to answer `ask_user`
     #[must_use]
     pub fn elapsed_secs(&self) -> u64 {
-        self.started_at.map_or(0, |start| start.elapsed().as_secs())
+        self.started_at.map_or(0, |start| {
+            let waited =
+                crate::agent::providers::clarification::time_waited(self.session_id.as_i64());
+            start.elapsed().saturating_sub(waited).as_secs()
+        })
     }
 
     /// Update the progress status
@@ -120,19 +140,33 @@ impl AgentSession {
     /// Mark the task as completed
     pub fn complete(&mut self) {
         self.status = AgentStatus::Completed;
-        self.started_at = None;
+        self.finish();
     }
 
     /// Mark the task as timed out
     pub fn timeout(&mut self) {
         self.status = AgentStatus::TimedOut;
-        self.started_at = None;
+        self.finish();
     }
 
     /// Mark the task as failed with an error
     pub fn fail(&mut self, error: String) {
         self.status = AgentStatus::Error(error);
+        self.finish();
+    }
+
+    fn finish(&mut self) {
         self.started_at = None;
+        self.finished_at = Some(Instant::now());
+    }
+
+    /// Check whether a new task arrives within `window` of the previous one finishing.
+    #[must_use]
+    pub fn is_follow_up(&self, window: Duration) -> bool {
+        self.last_task.is_some()
+            && self
+                .finished_at
+                .is_some_and(|finished| finished.elapsed() <= window)
     }
 
     /// Reset the session (clear memory, todos, reset status)
@@ -141,8 +175,10 @@ impl AgentSession {
         self.memory.clear();
         self.status = AgentStatus::Idle;
         self.started_at = None;
+        self.finished_at = None;
         self.current_task_id = None;
         self.last_task = None;
+        self.requester_id = None;
         self.loaded_skills.clear();
         self.skill_token_count = 0;
 
@@ -177,6 +213,14 @@ impl AgentSession {
         self.loaded_skills.contains(name)
     }
 
+    /// Names of the loaded skills, sorted.
+    #[must_use]
+    pub fn loaded_skills(&self) -> Vec<String> {
+        let mut skills: Vec<String> = self.loaded_skills.iter().cloned().collect();
+        skills.sort();
+        skills
+    }
+
     /// Get total tokens used by loaded skills.
     #[must_use]
     pub const fn skill_token_count(&self) -> usize {
@@ -234,16 +278,46 @@ impl AgentSession {
         self.sandbox.as_mut().filter(|s| s.is_running())
     }
 
-    /// Destroy sandbox if running
+    /// Destroy the session's sandbox container and free its slot
+    ///
+    /// Tool providers start the container through their own managers, so it
+    /// is removed by name rather than through `self.sandbox`.
     ///
     /// # Errors
     ///
     /// Returns an error if sandbox destruction fails.
     pub async fn destroy_sandbox(&mut self) -> Result<()> {
-        if let Some(mut sandbox) = self.sandbox.take() {
-            sandbox.destroy().await?;
-            info!(session_id = %self.session_id, "Sandbox destroyed");
-        }
-        Ok(())
+        self.sandbox = None;
+        SandboxManager::destroy_for_user(self.session_id.as_i64()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requester_defaults_to_session_owner() {
+        let mut session = AgentSession::new(SessionId::from(-100_200));
+        assert_eq!(session.requester_id(), -100_200);
+
+        session.requester_id = Some(42);
+        assert_eq!(session.requester_id(), 42);
+        session.reset();
+        assert_eq!(session.requester_id(), -100_200);
+    }
+
+    #[test]
+    fn test_follow_up_window() {
+        let mut session = AgentSession::new(SessionId::from(1));
+        assert!(!session.is_follow_up(Duration::from_secs(600)));
+
+        session.remember_task("download the report");
+        session.start_task();
+        session.complete();
+        assert!(session.is_follow_up(Duration::from_secs(600)));
+
+        session.reset();
+        assert!(!session.is_follow_up(Duration::from_secs(600)));
     }
 }
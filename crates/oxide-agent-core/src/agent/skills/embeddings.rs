@@ -7,17 +7,122 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct EmbeddingCacheEntry {
+    /// Model that produced the vector; missing in entries written before versioning.
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    dimension: Option<usize>,
     embedding: Vec<f32>,
 }
 
+/// On-disk embedding cache scoped to a single embedding model.
+///
+/// File names carry the model id and every entry records the model and
+/// dimension it was generated with, so vectors from another model are
+/// treated as a miss and regenerated instead of being reused.
+struct EmbeddingDiskCache {
+    dir: PathBuf,
+    model: String,
+}
+
+impl EmbeddingDiskCache {
+    fn new(dir: PathBuf, model: &str) -> Self {
+        Self {
+            dir,
+            model: model.to_string(),
+        }
+    }
+
+    fn path(&self, skill_name: &str) -> PathBuf {
+        let model_key: String = self
+            .model
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{skill_name}.{model_key}.json"))
+    }
+
+    fn read(&self, skill_name: &str) -> Option<EmbeddingCacheEntry> {
+        let path = self.path(skill_name);
+        let data = std::fs::read(&path).ok()?;
+
+        match serde_json::from_slice(&data) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                warn!(path = %path.display(), error = %err, "Unreadable embedding cache entry, regenerating");
+                None
+            }
+        }
+    }
+
+    /// Load a cached vector; stale or unreadable entries count as a miss.
+    fn load(&self, skill_name: &str, dimension: usize) -> Option<Vec<f32>> {
+        let entry = self.read(skill_name)?;
+
+        if entry.model.as_deref() != Some(self.model.as_str())
+            || entry.dimension != Some(entry.embedding.len())
+            || entry.embedding.len() != dimension
+        {
+            warn!(
+                skill = %skill_name,
+                cached_model = ?entry.model,
+                current_model = %self.model,
+                cached_dim = entry.embedding.len(),
+                current_dim = dimension,
+                "Cached embedding does not match current model, regenerating"
+            );
+            return None;
+        }
+
+        Some(entry.embedding)
+    }
+
+    /// Dimension of a vector cached by this model that differs from `dimension`.
+    ///
+    /// Vectors of other models are simply regenerated, but a different size
+    /// from the same model means the configured dimension is wrong.
+    fn conflicting_dimension(&self, skill_name: &str, dimension: usize) -> Option<usize> {
+        let entry = self.read(skill_name)?;
+        let cached = entry.embedding.len();
+        (entry.model.as_deref() == Some(self.model.as_str())
+            && entry.dimension == Some(cached)
+            && cached != dimension)
+            .then_some(cached)
+    }
+
+    fn save(&self, skill_name: &str, embedding: &[f32]) -> SkillResult<()> {
+        std::fs::create_dir_all(&self.dir).map_err(|err| {
+            SkillError::EmbeddingCache(format!("failed to create {}: {err}", self.dir.display()))
+        })?;
+
+        let path = self.path(skill_name);
+        let entry = EmbeddingCacheEntry {
+            model: Some(self.model.clone()),
+            dimension: Some(embedding.len()),
+            embedding: embedding.to_vec(),
+        };
+        let encoded = serde_json::to_vec(&entry)
+            .map_err(|err| SkillError::EmbeddingCache(err.to_string()))?;
+        std::fs::write(&path, encoded).map_err(|err| {
+            SkillError::EmbeddingCache(format!("failed to write {}: {err}", path.display()))
+        })
+    }
+}
+
 /// Embedding service for skill descriptions.
 pub struct EmbeddingService {
     llm_client: Arc<LlmClient>,
-    cache_dir: PathBuf,
+    disk_cache: EmbeddingDiskCache,
     dimension: Arc<Mutex<Option<usize>>>,
     in_memory: HashMap<String, Vec<f32>>,
 }
@@ -30,20 +135,39 @@ impl EmbeddingService {
             warn!("Embeddings disabled, using keyword matching only");
         }
 
+        let model = llm_client
+            .embedding_model_id()
+            .unwrap_or("none")
+            .to_string();
+
+        if let Some(dimension) = config.embedding_dimension {
+            info!(
+                dimension,
+                "Using embedding dimension from EMBEDDING_DIMENSION"
+            );
+        }
+
         Self {
+            disk_cache: EmbeddingDiskCache::new(config.embedding_cache_dir.clone(), &model),
             llm_client,
-            cache_dir: config.embedding_cache_dir.clone(),
-            dimension: Arc::new(Mutex::new(None)),
+            dimension: Arc::new(Mutex::new(config.embedding_dimension)),
             in_memory: HashMap::new(),
         }
     }
 
+    /// Whether an embedding provider is configured.
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        self.llm_client.is_embedding_available()
+    }
+
     /// Clear in-memory embeddings cache.
     pub fn clear_cache(&mut self) {
         self.in_memory.clear();
     }
 
-    /// Get or determine embedding dimension.
+    /// Embedding dimension: the `EMBEDDING_DIMENSION` override, or probed
+    /// from the model on first use and cached.
     async fn get_dimension(&self) -> SkillResult<usize> {
         {
             let dim = self
@@ -59,7 +183,12 @@ impl EmbeddingService {
             .llm_client
             .probe_embedding_dimension()
             .await
-            .unwrap_or(1536);
+            .ok_or_else(|| {
+                SkillError::EmbeddingUnavailable(format!(
+                    "could not probe the dimension of embedding model {}; set EMBEDDING_DIMENSION",
+                    self.disk_cache.model
+                ))
+            })?;
         info!(dimension = detected, "Auto-detected embedding dimension");
 
         let mut dim = self
@@ -117,7 +246,7 @@ impl EmbeddingService {
             return Ok(Some(embedding));
         }
 
-        if let Some(embedding) = self.load_cached_embedding(&meta.name)? {
+        if let Some(embedding) = self.load_cached_embedding(&meta.name).await? {
             meta.embedding = Some(embedding.clone());
             return Ok(Some(embedding));
         }
@@ -151,28 +280,10 @@ impl EmbeddingService {
             _ => SkillError::EmbeddingRequest(err.to_string()),
         })?;
 
-        let expected_dim = self.get_dimension().await?;
-        if result.len() != expected_dim {
-            let mut dim = self
-                .dimension
-                .lock()
-                .map_err(|e| SkillError::EmbeddingRequest(format!("Mutex poisoned: {}", e)))?;
-            *dim = Some(result.len());
-            info!(
-                old_dimension = expected_dim,
-                new_dimension = result.len(),
-                "Updated embedding dimension based on API response"
-            );
-        }
-
+        self.ensure_dimension_match(&result).await?;
         Ok(result)
     }
 
-    fn cache_path(&self, skill_name: &str) -> PathBuf {
-        let file_name = format!("{skill_name}.json");
-        self.cache_dir.join(file_name)
-    }
-
     async fn ensure_dimension_match(&self, embedding: &[f32]) -> SkillResult<()> {
         let expected_dim = self.get_dimension().await?;
         if embedding.len() != expected_dim {
@@ -184,44 +295,34 @@ impl EmbeddingService {
         Ok(())
     }
 
-    fn load_cached_embedding(&mut self, skill_name: &str) -> SkillResult<Option<Vec<f32>>> {
+    async fn load_cached_embedding(&mut self, skill_name: &str) -> SkillResult<Option<Vec<f32>>> {
         if let Some(embedding) = self.in_memory.get(skill_name) {
             return Ok(Some(embedding.clone()));
         }
 
-        let path = self.cache_path(skill_name);
-        if !path.exists() {
-            return Ok(None);
-        }
-
-        let data = std::fs::read(&path).map_err(|err| {
-            SkillError::EmbeddingCache(format!("failed to read {}: {err}", path.display()))
-        })?;
-
-        let entry: EmbeddingCacheEntry = serde_json::from_slice(&data).map_err(|err| {
-            SkillError::EmbeddingCache(format!("failed to parse {}: {err}", path.display()))
-        })?;
-
-        let current_dim = self
-            .dimension
-            .lock()
-            .map_err(|e| SkillError::EmbeddingRequest(format!("Mutex poisoned: {}", e)))?;
-        if let Some(d) = *current_dim {
-            if entry.embedding.len() != d {
-                warn!(
+        let dimension = self.get_dimension().await?;
+        let Some(embedding) = self.disk_cache.load(skill_name, dimension) else {
+            if let Some(cached) = self.disk_cache.conflicting_dimension(skill_name, dimension) {
+                error!(
                     skill = %skill_name,
-                    cached_dim = entry.embedding.len(),
-                    current_dim = d,
-                    "Cached embedding dimension mismatch, ignoring cache"
+                    model = %self.disk_cache.model,
+                    cached_dim = cached,
+                    current_dim = dimension,
+                    cache_dir = %self.disk_cache.dir.display(),
+                    "Cached embeddings of this model have a different dimension"
                 );
-                return Ok(None);
+                return Err(SkillError::EmbeddingDimensionMismatch {
+                    expected: dimension,
+                    actual: cached,
+                });
             }
-        }
+            return Ok(None);
+        };
 
         self.in_memory
-            .insert(skill_name.to_string(), entry.embedding.clone());
+            .insert(skill_name.to_string(), embedding.clone());
 
-        Ok(Some(entry.embedding))
+        Ok(Some(embedding))
     }
 
     async fn save_cached_embedding(
@@ -230,22 +331,7 @@ impl EmbeddingService {
         embedding: &[f32],
     ) -> SkillResult<()> {
         self.ensure_dimension_match(embedding).await?;
-        std::fs::create_dir_all(&self.cache_dir).map_err(|err| {
-            SkillError::EmbeddingCache(format!(
-                "failed to create {}: {err}",
-                self.cache_dir.display()
-            ))
-        })?;
-
-        let path = self.cache_path(skill_name);
-        let entry = EmbeddingCacheEntry {
-            embedding: embedding.to_vec(),
-        };
-        let encoded = serde_json::to_vec(&entry)
-            .map_err(|err| SkillError::EmbeddingCache(err.to_string()))?;
-        std::fs::write(&path, encoded).map_err(|err| {
-            SkillError::EmbeddingCache(format!("failed to write {}: {err}", path.display()))
-        })?;
+        self.disk_cache.save(skill_name, embedding)?;
 
         self.in_memory
             .insert(skill_name.to_string(), embedding.to_vec());
@@ -276,3 +362,50 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
 
     Some(dot / (norm_a.sqrt() * norm_b.sqrt()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_is_rebuilt_after_model_change() -> SkillResult<()> {
+        let dir = std::env::temp_dir().join(format!("oxide-embeddings-{}", std::process::id()));
+        let old_model = EmbeddingDiskCache::new(dir.clone(), "mistral-embed");
+        let new_model = EmbeddingDiskCache::new(dir.clone(), "text-embedding-3-small");
+
+        old_model.save("core", &[0.1, 0.2, 0.3])?;
+        assert_eq!(old_model.load("core", 3), Some(vec![0.1, 0.2, 0.3]));
+
+        // A different model misses instead of returning a foreign vector
+        assert_eq!(new_model.load("core", 4), None);
+        new_model.save("core", &[0.4, 0.3, 0.2, 0.1])?;
+        assert_eq!(new_model.load("core", 4), Some(vec![0.4, 0.3, 0.2, 0.1]));
+
+        // Dimension changes and pre-versioning entries are misses as well
+        assert_eq!(old_model.load("core", 4), None);
+        std::fs::write(old_model.path("legacy"), br#"{"embedding":[0.1,0.2,0.3]}"#)
+            .map_err(|err| SkillError::EmbeddingCache(err.to_string()))?;
+        assert_eq!(old_model.load("legacy", 3), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_model_with_other_dimension_conflicts() -> SkillResult<()> {
+        let dir = std::env::temp_dir().join(format!("oxide-embeddings-dim-{}", std::process::id()));
+        let cache = EmbeddingDiskCache::new(dir.clone(), "mistral-embed");
+        let other_model = EmbeddingDiskCache::new(dir.clone(), "text-embedding-3-small");
+
+        cache.save("core", &[0.1, 0.2, 0.3])?;
+        assert_eq!(cache.conflicting_dimension("core", 3), None);
+        assert_eq!(cache.conflicting_dimension("core", 1024), Some(3));
+
+        // Vectors of another model are regenerated, not reported
+        assert_eq!(other_model.conflicting_dimension("core", 1024), None);
+        assert_eq!(cache.conflicting_dimension("missing", 1024), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -1,9 +1,8 @@
 //! Skill loader for markdown files with YAML frontmatter.
 
-use crate::agent::skills::types::{
-    count_tokens, ActivationMode, LazyContent, Skill, SkillMetadata, SkillWeight,
-};
+use crate::agent::skills::types::{ActivationMode, LazyContent, Skill, SkillMetadata, SkillWeight};
 use crate::agent::skills::{SkillError, SkillResult};
+use crate::agent::tokenizer::{default_tokenizer, Tokenizer};
 use chrono::Utc;
 use serde::Deserialize;
 use std::collections::{hash_map::Entry, HashMap};
@@ -14,60 +13,33 @@ use tracing::warn;
 #[derive(Debug, Clone)]
 pub struct SkillLoader {
     skills_dir: PathBuf,
+    tokenizer: &'static dyn Tokenizer,
 }
 
 impl SkillLoader {
     /// Create a new loader rooted at the skills directory.
     #[must_use]
     pub fn new(skills_dir: PathBuf) -> Self {
-        Self { skills_dir }
+        Self {
+            skills_dir,
+            tokenizer: default_tokenizer(),
+        }
     }
 
-    /// Load metadata from all markdown files in the skills directory.
-    pub fn load_all_metadata(&self) -> SkillResult<Vec<SkillMetadata>> {
-        let entries =
-            std::fs::read_dir(&self.skills_dir).map_err(|source| SkillError::ReadSkillFile {
-                path: self.skills_dir.clone(),
-                source,
-            })?;
-
-        let mut paths = Vec::new();
-
-        for entry in entries {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(err) => {
-                    warn!(error = %err, "Failed to read skill directory entry");
-                    continue;
-                }
-            };
-
-            let path = entry.path();
-            let file_name = path.file_name().and_then(|name| name.to_str());
-            if file_name.map(|name| name.starts_with('.')).unwrap_or(false) {
-                continue;
-            }
-
-            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
-                continue;
-            }
-
-            let file_type = match entry.file_type() {
-                Ok(file_type) => file_type,
-                Err(err) => {
-                    warn!(path = ?path, error = %err, "Failed to read skill file type");
-                    continue;
-                }
-            };
-
-            if !(file_type.is_file() || file_type.is_symlink()) {
-                continue;
-            }
+    /// Count skill tokens with `tokenizer` for skills loaded from now on.
+    pub fn set_tokenizer(&mut self, tokenizer: &'static dyn Tokenizer) {
+        self.tokenizer = tokenizer;
+    }
 
-            paths.push(path);
-        }
+    /// Tokenizer used for skill token counts.
+    #[must_use]
+    pub fn tokenizer(&self) -> &'static dyn Tokenizer {
+        self.tokenizer
+    }
 
-        paths.sort();
+    /// Load metadata from all markdown files in the skills directory.
+    pub fn load_all_metadata(&self) -> SkillResult<Vec<SkillMetadata>> {
+        let paths = self.skill_paths()?;
         let mut metadata = HashMap::new();
 
         for path in paths {
@@ -119,6 +91,61 @@ impl SkillLoader {
         Ok(metadata)
     }
 
+    /// Parse every skill file and report problems instead of skipping them.
+    ///
+    /// Checks frontmatter, names, duplicate names and referenced files.
+    /// Fails only if the skills directory itself cannot be read.
+    pub fn validate_all(&self) -> SkillResult<SkillValidationReport> {
+        let paths = self.skill_paths()?;
+        let mut report = SkillValidationReport {
+            checked: paths.len(),
+            errors: Vec::new(),
+        };
+        let mut seen: HashMap<String, PathBuf> = HashMap::new();
+
+        for path in paths {
+            let metadata = match self.parse_file(&path) {
+                Ok((metadata, _)) => metadata,
+                Err(err) => {
+                    report.errors.push((path, err.to_string()));
+                    continue;
+                }
+            };
+
+            for reference in &metadata.references {
+                let resolved = if reference.is_absolute() {
+                    reference.clone()
+                } else {
+                    self.skills_dir.join(reference)
+                };
+                if !resolved.is_file() {
+                    report.errors.push((
+                        path.clone(),
+                        format!("Referenced file not found: {}", resolved.display()),
+                    ));
+                }
+            }
+
+            match seen.entry(metadata.name.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(path);
+                }
+                Entry::Occupied(entry) => {
+                    report.errors.push((
+                        path,
+                        format!(
+                            "Duplicate skill name '{}' (also in {})",
+                            metadata.name,
+                            entry.get().display()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Load a full skill definition by name (stem without extension).
     pub fn load_skill_content(&self, name: &str) -> SkillResult<Skill> {
         let path = self.skills_dir.join(format!("{name}.md"));
@@ -143,10 +170,58 @@ impl SkillLoader {
             content: content.clone(),
             supporting_files,
             loaded_at: Utc::now(),
-            token_count: count_tokens(&content),
+            token_count: self.tokenizer.count_tokens(&content),
         })
     }
 
+    /// Markdown files in the skills directory, sorted by path.
+    fn skill_paths(&self) -> SkillResult<Vec<PathBuf>> {
+        let entries =
+            std::fs::read_dir(&self.skills_dir).map_err(|source| SkillError::ReadSkillFile {
+                path: self.skills_dir.clone(),
+                source,
+            })?;
+
+        let mut paths = Vec::new();
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    warn!(error = %err, "Failed to read skill directory entry");
+                    continue;
+                }
+            };
+
+            let path = entry.path();
+            let file_name = path.file_name().and_then(|name| name.to_str());
+            if file_name.map(|name| name.starts_with('.')).unwrap_or(false) {
+                continue;
+            }
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(err) => {
+                    warn!(path = ?path, error = %err, "Failed to read skill file type");
+                    continue;
+                }
+            };
+
+            if !(file_type.is_file() || file_type.is_symlink()) {
+                continue;
+            }
+
+            paths.push(path);
+        }
+
+        paths.sort();
+        Ok(paths)
+    }
+
     fn parse_file(&self, path: &Path) -> SkillResult<(SkillMetadata, String)> {
         let raw = std::fs::read_to_string(path).map_err(|source| SkillError::ReadSkillFile {
             path: path.to_path_buf(),
@@ -165,6 +240,23 @@ impl SkillLoader {
     }
 }
 
+/// Result of [`SkillLoader::validate_all`].
+#[derive(Debug, Clone, Default)]
+pub struct SkillValidationReport {
+    /// Number of skill files inspected.
+    pub checked: usize,
+    /// Problems found, as (file, description) pairs.
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl SkillValidationReport {
+    /// Whether every skill file is valid.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
 struct SkillFrontmatter {
     name: Option<String>,
@@ -243,3 +335,44 @@ fn split_frontmatter(path: &Path, content: &str) -> SkillResult<(SkillFrontmatte
 
     Ok((frontmatter, body.trim().to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_all_reports_broken_skills() -> std::io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("oxide-skills-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(
+            dir.join("good.md"),
+            "---\nname: good\ndescription: ok\n---\nBody",
+        )?;
+        std::fs::write(dir.join("no-frontmatter.md"), "Just text")?;
+        std::fs::write(dir.join("bad-yaml.md"), "---\ntriggers: [unclosed\n---\n")?;
+        std::fs::write(
+            dir.join("dup.md"),
+            "---\nname: good\nreferences: [missing.txt]\n---\n",
+        )?;
+
+        let report = SkillLoader::new(dir.clone()).validate_all();
+        std::fs::remove_dir_all(&dir)?;
+        let report = match report {
+            Ok(report) => report,
+            Err(err) => panic!("validation failed: {err}"),
+        };
+
+        assert_eq!(report.checked, 4);
+        assert!(!report.is_ok());
+        let messages: Vec<&str> = report.errors.iter().map(|(_, m)| m.as_str()).collect();
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("Missing or invalid frontmatter")));
+        assert!(messages.iter().any(|m| m.contains("Invalid YAML")));
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("Duplicate skill name 'good'")));
+        assert!(messages.iter().any(|m| m.contains("missing.txt")));
+        Ok(())
+    }
+}
@@ -1,8 +1,38 @@
 //! Skill matching logic (keyword + semantic).
 
-use crate::agent::skills::types::{SkillMetadata, SkillWeight};
+use crate::agent::skills::types::{ActivationMode, SkillMetadata, SkillWeight};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Minimum keyword score for a skill to qualify without a trigger match.
+const KEYWORD_SCORE_THRESHOLD: f32 = 0.3;
+
+/// Words too common to say anything about a skill.
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "this", "that", "from", "into", "via", "are", "was", "you",
+    "your", "can", "please", "what", "how", "about", "some", "all", "any", "use", "using",
+];
+
+/// Scoring used to rank skills for a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrategy {
+    /// Embedding similarity between request and skill description.
+    #[default]
+    Semantic,
+    /// Term overlap, used when embeddings are unavailable.
+    Keyword,
+}
+
+impl MatchStrategy {
+    /// Short name for logs.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Semantic => "semantic",
+            Self::Keyword => "keyword",
+        }
+    }
+}
 
 /// Input for skill selection.
 pub struct SkillMatcherInput<'a> {
@@ -12,6 +42,8 @@ pub struct SkillMatcherInput<'a> {
     pub metadata: &'a [SkillMetadata],
     /// Precomputed semantic scores per skill.
     pub semantic_scores: Option<&'a HashMap<String, f32>>,
+    /// Precomputed keyword scores per skill (fallback without embeddings).
+    pub keyword_scores: Option<&'a HashMap<String, f32>>,
     /// Whether embeddings are available for matching.
     pub embeddings_available: bool,
 }
@@ -23,10 +55,14 @@ pub struct SkillMatch {
     pub name: String,
     /// Skill weight.
     pub weight: SkillWeight,
+    /// Skill activation mode.
+    pub activation: ActivationMode,
     /// Whether a keyword trigger matched.
     pub trigger_match: bool,
     /// Semantic similarity score, if available.
     pub semantic_score: Option<f32>,
+    /// Keyword overlap score, if keyword matching was used.
+    pub keyword_score: Option<f32>,
     /// Combined score used for ranking.
     pub combined_score: f32,
 }
@@ -55,10 +91,14 @@ impl SkillMatcher {
         let mut matches = Vec::new();
 
         for meta in input.metadata {
-            let trigger_match = meta
-                .triggers
-                .iter()
-                .any(|trigger| user_message.contains(&trigger.to_lowercase()));
+            let trigger_match = match meta.activation {
+                // Manual skills react to their own name only, never to triggers
+                ActivationMode::Manual => user_message.contains(&meta.name.to_lowercase()),
+                ActivationMode::Hybrid | ActivationMode::Always => meta
+                    .triggers
+                    .iter()
+                    .any(|trigger| user_message.contains(&trigger.to_lowercase())),
+            };
 
             let semantic_score = input
                 .semantic_scores
@@ -66,25 +106,37 @@ impl SkillMatcher {
             let semantic_pass = semantic_score
                 .map(|score| score >= self.semantic_threshold)
                 .unwrap_or(false);
+            let keyword_score = input
+                .keyword_scores
+                .and_then(|scores| scores.get(&meta.name).copied());
+            let keyword_pass = keyword_score.is_some_and(|score| score >= KEYWORD_SCORE_THRESHOLD);
+            let score_pass = semantic_pass || keyword_pass;
 
-            let qualifies = match meta.weight {
-                SkillWeight::Always => true,
-                SkillWeight::High => trigger_match || semantic_pass || !input.embeddings_available,
-                SkillWeight::Medium | SkillWeight::OnDemand => trigger_match || semantic_pass,
+            let qualifies = match (meta.activation, meta.weight) {
+                (ActivationMode::Always, _) | (ActivationMode::Hybrid, SkillWeight::Always) => true,
+                (ActivationMode::Manual, _) => trigger_match,
+                (ActivationMode::Hybrid, SkillWeight::High) => {
+                    trigger_match || score_pass || !input.embeddings_available
+                }
+                (ActivationMode::Hybrid, SkillWeight::Medium | SkillWeight::OnDemand) => {
+                    trigger_match || score_pass
+                }
             };
 
             if !qualifies {
                 continue;
             }
 
-            let combined_score =
-                0.7 * semantic_score.unwrap_or(0.0) + if trigger_match { 0.3 } else { 0.0 };
+            let combined_score = 0.7 * semantic_score.or(keyword_score).unwrap_or(0.0)
+                + if trigger_match { 0.3 } else { 0.0 };
 
             matches.push(SkillMatch {
                 name: meta.name.clone(),
                 weight: meta.weight,
+                activation: meta.activation,
                 trigger_match,
                 semantic_score,
+                keyword_score,
                 combined_score,
             });
         }
@@ -107,7 +159,8 @@ impl SkillMatcher {
         let mut candidates = Vec::new();
 
         for skill in deduped {
-            if skill.weight == SkillWeight::Always {
+            // Always-on skills skip the selection limit; weight decides the token budget
+            if skill.weight == SkillWeight::Always || skill.activation == ActivationMode::Always {
                 always.push(skill);
             } else {
                 candidates.push(skill);
@@ -132,6 +185,77 @@ impl SkillMatcher {
     }
 }
 
+/// Score skills by term overlap with the request, BM25-style.
+///
+/// Each request term found in a skill's name, description, triggers or tools
+/// contributes its inverse document frequency; the sum is normalized by the
+/// weight of all request terms that any skill knows about, giving 0.0..=1.0.
+#[must_use]
+pub fn keyword_scores(user_message: &str, metadata: &[SkillMetadata]) -> HashMap<String, f32> {
+    let documents: Vec<(&str, HashSet<String>)> = metadata
+        .iter()
+        .map(|meta| {
+            let mut text = format!("{} {}", meta.name, meta.description);
+            for term in meta.triggers.iter().chain(&meta.allowed_tools) {
+                text.push(' ');
+                text.push_str(term);
+            }
+            (meta.name.as_str(), tokenize(&text))
+        })
+        .collect();
+
+    let total = documents.len() as f32;
+    let idf = |term: &str| {
+        let df = documents
+            .iter()
+            .filter(|(_, terms)| terms.contains(term))
+            .count() as f32;
+        (1.0 + (total - df + 0.5) / (df + 0.5)).ln()
+    };
+
+    let query: Vec<(String, f32)> = tokenize(user_message)
+        .into_iter()
+        .filter(|term| documents.iter().any(|(_, terms)| terms.contains(term)))
+        .map(|term| {
+            let weight = idf(&term);
+            (term, weight)
+        })
+        .collect();
+    let query_weight: f32 = query.iter().map(|(_, weight)| weight).sum();
+
+    documents
+        .iter()
+        .map(|(name, terms)| {
+            let matched: f32 = query
+                .iter()
+                .filter(|(term, _)| terms.contains(term))
+                .map(|(_, weight)| weight)
+                .sum();
+            let score = if query_weight > 0.0 {
+                matched / query_weight
+            } else {
+                0.0
+            };
+            ((*name).to_string(), score)
+        })
+        .collect()
+}
+
+fn tokenize(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3 && !STOPWORDS.contains(word))
+        .map(|word| {
+            // Crude plural folding so "videos" matches "video"
+            if word.chars().count() > 4 && word.ends_with('s') && !word.ends_with("ss") {
+                word[..word.len() - 1].to_string()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect()
+}
+
 fn is_better_match(candidate: &SkillMatch, current: &SkillMatch) -> bool {
     let weight_cmp = candidate.weight.priority().cmp(&current.weight.priority());
     if weight_cmp != Ordering::Equal {
@@ -154,3 +278,131 @@ fn is_better_match(candidate: &SkillMatch, current: &SkillMatch) -> bool {
         .total_cmp(&current.semantic_score.unwrap_or(0.0))
         == Ordering::Greater
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skill(name: &str, description: &str, triggers: &[&str]) -> SkillMetadata {
+        SkillMetadata {
+            name: name.to_string(),
+            description: description.to_string(),
+            triggers: triggers.iter().map(ToString::to_string).collect(),
+            allowed_tools: Vec::new(),
+            weight: SkillWeight::Medium,
+            references: Vec::new(),
+            activation: ActivationMode::default(),
+            embedding: None,
+        }
+    }
+
+    #[test]
+    fn test_keyword_fallback_selects_by_description_overlap() {
+        let metadata = vec![
+            skill(
+                "ffmpeg-conversion",
+                "Convert audio and video formats with ffmpeg",
+                &[],
+            ),
+            skill(
+                "web-search",
+                "Search and extract information from the internet",
+                &[],
+            ),
+            skill("html-report", "Build HTML reports with charts", &[]),
+        ];
+        let message = "Please convert my recording to another audio format";
+        let scores = keyword_scores(message, &metadata);
+
+        let matches = SkillMatcher::new(0.6, 3).select_skills(SkillMatcherInput {
+            user_message: message,
+            metadata: &metadata,
+            semantic_scores: None,
+            keyword_scores: Some(&scores),
+            embeddings_available: false,
+        });
+
+        let names: Vec<&str> = matches.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["ffmpeg-conversion"]);
+        assert!(matches[0].keyword_score.is_some_and(|score| score > 0.9));
+    }
+
+    fn select(metadata: &[SkillMetadata], message: &str, scores: &[(&str, f32)]) -> Vec<String> {
+        let scores: HashMap<String, f32> = scores
+            .iter()
+            .map(|(name, score)| ((*name).to_string(), *score))
+            .collect();
+        SkillMatcher::new(0.6, 1)
+            .select_skills(SkillMatcherInput {
+                user_message: message,
+                metadata,
+                semantic_scores: Some(&scores),
+                keyword_scores: None,
+                embeddings_available: true,
+            })
+            .into_iter()
+            .map(|m| m.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_always_activation_ignores_similarity_and_limit() {
+        let mut safety = skill("safety", "Safety rules", &[]);
+        safety.activation = ActivationMode::Always;
+        let metadata = vec![safety, skill("web-search", "Search the web", &["search"])];
+
+        assert_eq!(
+            select(&metadata, "search for news", &[("safety", 0.0)]),
+            vec!["safety", "web-search"]
+        );
+        assert_eq!(select(&metadata, "hello", &[]), vec!["safety"]);
+    }
+
+    #[test]
+    fn test_manual_activation_requires_explicit_request() {
+        let mut report = skill("html-report", "Build HTML reports", &["report"]);
+        report.activation = ActivationMode::Manual;
+        let metadata = vec![report];
+
+        assert!(select(&metadata, "make a report", &[("html-report", 0.95)]).is_empty());
+        assert_eq!(
+            select(&metadata, "use the html-report skill", &[]),
+            vec!["html-report"]
+        );
+    }
+
+    #[test]
+    fn test_hybrid_activation_uses_semantic_threshold() {
+        let metadata = vec![skill("web-search", "Search the web", &[])];
+
+        assert_eq!(
+            select(&metadata, "latest news", &[("web-search", 0.7)]),
+            vec!["web-search"]
+        );
+        assert!(select(&metadata, "latest news", &[("web-search", 0.5)]).is_empty());
+    }
+
+    #[test]
+    fn test_activation_mode_accepts_aliases() -> Result<(), serde_yaml::Error> {
+        let modes: Vec<ActivationMode> =
+            serde_yaml::from_str("[auto, hybrid, always, manual, tool_only]")?;
+        assert_eq!(
+            modes,
+            vec![
+                ActivationMode::Hybrid,
+                ActivationMode::Hybrid,
+                ActivationMode::Always,
+                ActivationMode::Manual,
+                ActivationMode::Manual,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyword_scores_ignore_unknown_terms() {
+        let metadata = vec![skill("web-search", "Search the internet", &[])];
+        let scores = keyword_scores("zzzz qqqq", &metadata);
+        assert_eq!(scores.get("web-search").copied(), Some(0.0));
+    }
+}
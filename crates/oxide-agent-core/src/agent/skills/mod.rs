@@ -12,8 +12,8 @@ pub mod types;
 
 pub use cache::SkillCache;
 pub use embeddings::EmbeddingService;
-pub use loader::SkillLoader;
-pub use matcher::{SkillMatch, SkillMatcher, SkillMatcherInput};
+pub use loader::{SkillLoader, SkillValidationReport};
+pub use matcher::{MatchStrategy, SkillMatch, SkillMatcher, SkillMatcherInput};
 pub use registry::{SkillPrompt, SkillRegistry};
 pub use types::{ActivationMode, LazyContent, Skill, SkillContext, SkillMetadata, SkillWeight};
 
@@ -65,7 +65,10 @@ pub enum SkillError {
     #[error("Embedding cache error: {0}")]
     EmbeddingCache(String),
     /// Embedding dimension mismatch.
-    #[error("Embedding dimension mismatch: expected {expected}, got {actual}")]
+    #[error(
+        "Embedding dimension mismatch: expected {expected}, got {actual}. \
+         Check EMBEDDING_DIMENSION and EMBEDDING_MODEL_ID, or clear the embedding cache"
+    )]
     EmbeddingDimensionMismatch {
         /// Expected embedding size.
         expected: usize,
@@ -90,6 +93,8 @@ pub struct SkillConfig {
     pub skills_dir: PathBuf,
     /// Directory used for embedding cache files.
     pub embedding_cache_dir: PathBuf,
+    /// Embedding dimension override; probed from the model when `None`.
+    pub embedding_dimension: Option<usize>,
     /// Maximum token budget for selected skills.
     pub token_budget: usize,
     /// Similarity threshold for semantic matching.
@@ -115,6 +120,7 @@ impl SkillConfig {
         Self {
             skills_dir: PathBuf::from(crate::config::get_skills_dir()),
             embedding_cache_dir: PathBuf::from(crate::config::get_embedding_cache_dir()),
+            embedding_dimension: crate::config::get_embedding_dimension(),
             token_budget,
             semantic_threshold: crate::config::get_skill_semantic_threshold(),
             max_selected,
@@ -2,9 +2,12 @@
 
 use crate::agent::skills::embeddings::EmbeddingService;
 use crate::agent::skills::loader::SkillLoader;
-use crate::agent::skills::matcher::{SkillMatch, SkillMatcher, SkillMatcherInput};
+use crate::agent::skills::matcher::{
+    keyword_scores, MatchStrategy, SkillMatch, SkillMatcher, SkillMatcherInput,
+};
 use crate::agent::skills::types::{Skill, SkillContext, SkillWeight};
 use crate::agent::skills::{SkillCache, SkillConfig, SkillError, SkillResult};
+use crate::agent::tokenizer::Tokenizer;
 use crate::llm::LlmClient;
 use std::path::Path;
 use std::sync::Arc;
@@ -19,7 +22,9 @@ const GENERIC_TOOLS: &[&str] = &[
     "read_file",
     "write_file",
     "send_file_to_user",
+    "zip_and_send",
     "list_files",
+    "search_files",
 ];
 
 /// Resulting prompt composed from selected skills.
@@ -33,6 +38,8 @@ pub struct SkillPrompt {
     pub token_count: usize,
     /// Skills skipped due to token budget.
     pub skipped: Vec<String>,
+    /// Scoring used to select the skills.
+    pub matcher: MatchStrategy,
 }
 
 /// Central registry for skill metadata and content.
@@ -111,15 +118,27 @@ impl SkillRegistry {
     pub async fn build_prompt(&mut self, user_message: &str) -> SkillResult<SkillPrompt> {
         self.refresh_if_stale()?;
 
-        let semantic_scores = self
-            .embeddings
-            .semantic_scores(user_message, &mut self.metadata)
-            .await?;
+        let semantic_scores = if self.embeddings.is_available() {
+            self.embeddings
+                .semantic_scores(user_message, &mut self.metadata)
+                .await?
+        } else {
+            None
+        };
+        let keyword_scores = semantic_scores
+            .is_none()
+            .then(|| keyword_scores(user_message, &self.metadata));
+        let matcher = if semantic_scores.is_some() {
+            MatchStrategy::Semantic
+        } else {
+            MatchStrategy::Keyword
+        };
 
         let matches = self.matcher.select_skills(SkillMatcherInput {
             user_message,
             metadata: &self.metadata,
             semantic_scores: semantic_scores.as_ref(),
+            keyword_scores: keyword_scores.as_ref(),
             embeddings_available: semantic_scores.is_some(),
         });
 
@@ -161,6 +180,7 @@ impl SkillRegistry {
             skills: contexts,
             token_count: total_tokens,
             skipped,
+            matcher,
         })
     }
 
@@ -197,6 +217,18 @@ impl SkillRegistry {
         Ok(Some(skill))
     }
 
+    /// Budget skills with the tokenizer of the agent model.
+    ///
+    /// Cached skills are dropped when the tokenizer changes so their token
+    /// counts are recomputed.
+    pub fn set_tokenizer(&mut self, tokenizer: &'static dyn Tokenizer) {
+        if tokenizer.name() == self.loader.tokenizer().name() {
+            return;
+        }
+        self.loader.set_tokenizer(tokenizer);
+        self.cache.clear();
+    }
+
     #[must_use]
     /// Get the configured skills directory.
     pub fn skills_dir(&self) -> &Path {
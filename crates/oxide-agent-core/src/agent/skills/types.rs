@@ -5,7 +5,6 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use tiktoken_rs::cl100k_base;
 
 /// Skill load priority and selection weight.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -41,9 +40,13 @@ impl SkillWeight {
 pub enum ActivationMode {
     /// Hybrid matching (keywords + semantic).
     #[default]
+    #[serde(alias = "auto")]
     Hybrid,
-    /// Tool-only activation.
-    ToolOnly,
+    /// Included for every request, subject to the token budget.
+    Always,
+    /// Loaded only when one of its tools is used or the request names the skill.
+    #[serde(alias = "tool_only")]
+    Manual,
 }
 
 /// Metadata parsed from frontmatter.
@@ -141,10 +144,8 @@ pub struct SkillContext {
     pub token_count: usize,
 }
 
-/// Count tokens in a string using cl100k tokenizer (GPT-4/Claude compatible).
+/// Count tokens in a string with the default tokenizer (cl100k, GPT-4/Claude compatible).
 #[must_use]
 pub fn count_tokens(text: &str) -> usize {
-    cl100k_base().map_or(text.len() / 4, |bpe| {
-        bpe.encode_with_special_tokens(text).len()
-    })
+    crate::agent::tokenizer::default_tokenizer().count_tokens(text)
 }
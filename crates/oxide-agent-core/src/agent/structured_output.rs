@@ -68,6 +68,17 @@ pub struct ValidatedToolCall {
     pub name: String,
     /// Serialized JSON arguments for the tool.
     pub arguments_json: String,
+    /// Whether the response only parsed after control-character stripping or fallback parsing.
+    pub is_recovered: bool,
+}
+
+impl ValidatedStructuredOutput {
+    fn recovered(mut self) -> Self {
+        if let Some(tool_call) = self.tool_call.as_mut() {
+            tool_call.is_recovered = true;
+        }
+        self
+    }
 }
 
 /// Parse and validate a structured JSON response against the agent schema.
@@ -90,7 +101,7 @@ pub fn parse_structured_output(
         match try_parse_structured_output(&sanitized, tools) {
             Ok(parsed) => {
                 warn!("Structured output required control character stripping. This should be rare in JSON mode.");
-                return Ok(parsed);
+                return Ok(parsed.recovered());
             }
             Err(err) => last_error = err.message().to_string(),
         }
@@ -103,7 +114,7 @@ pub fn parse_structured_output(
                     raw_content = %trimmed,
                     "Structured output required fallback parsing. This should be rare in JSON mode."
                 );
-                return Ok(parsed);
+                return Ok(parsed.recovered());
             }
             Err(err) => last_error = err.message().to_string(),
         }
@@ -226,6 +237,7 @@ fn validate_structured_output(
         validated_tool_call = Some(ValidatedToolCall {
             name,
             arguments_json,
+            is_recovered: false,
         });
     }
 
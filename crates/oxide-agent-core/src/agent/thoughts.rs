@@ -10,7 +10,12 @@ const THOUGHT_TEMPLATES: &[(&str, &str)] = &[
     ("read_file", "Reading file {path}"),
     ("write_file", "Writing changes to {path}"),
     ("execute_command", "Executing command"),
+    ("set_cwd", "Changing directory to {path}"),
+    ("set_env", "Setting environment variables"),
     ("list_files", "Viewing directory contents {directory}"),
+    ("search_files", "Searching files for {pattern}"),
+    ("sandbox_ps", "Inspecting sandbox processes"),
+    ("sandbox_kill", "Stopping a stuck process"),
     ("tavily_search", "Searching for information: {query}"),
     ("tavily_extract", "Extracting content from {url}"),
     ("tavily_crawl", "Analyzing website structure {url}"),
@@ -19,6 +24,10 @@ const THOUGHT_TEMPLATES: &[(&str, &str)] = &[
     ("ytdlp_info", "Getting video information {url}"),
     ("upload_to_gofile", "Uploading file to filehosting"),
     ("write_todos", "Updating todo list"),
+    ("update_todo", "Updating todo status"),
+    ("ask_user", "Waiting for your answer: {question}"),
+    ("get_datetime", "Checking the current time"),
+    ("finish_task", "Wrapping up the task"),
     ("complete_todo", "Marking todo as completed"),
 ];
 
@@ -0,0 +1,181 @@
+//! Token counting per model family
+//!
+//! Memory compaction and the skill budget compare token counts against fixed
+//! thresholds, so the count should match the tokenizer of the model in use.
+//! [`tokenizer_for_model`] picks a tiktoken encoding by model family (with the
+//! `tiktoken` feature) and falls back to a characters-per-token heuristic for
+//! models it does not recognize.
+
+use std::fmt;
+
+/// Counts tokens the way a model family does
+pub trait Tokenizer: Send + Sync {
+    /// Short name for logs (`cl100k`, `o200k`, `heuristic`)
+    fn name(&self) -> &'static str;
+
+    /// Number of tokens in `text`
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+impl fmt::Debug for dyn Tokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Average characters per token assumed by [`HeuristicTokenizer`]
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimates one token per four characters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> &'static str {
+        "heuristic"
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(CHARS_PER_TOKEN)
+    }
+}
+
+/// BPE encodings known to [`tokenizer_for_model`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// GPT-4 / GPT-3.5 encoding, also a close match for Claude, GLM and Mistral models
+    Cl100k,
+    /// GPT-4o, GPT-4.1, GPT-5 and the o-series
+    O200k,
+}
+
+impl Encoding {
+    /// Encoding used by `model_id`, or `None` for unknown families
+    ///
+    /// Provider prefixes such as `openai/` are ignored.
+    #[must_use]
+    pub fn for_model(model_id: &str) -> Option<Self> {
+        let model = model_id.rsplit('/').next().unwrap_or(model_id);
+        let model = model.trim().to_ascii_lowercase();
+        let starts = |prefixes: &[&str]| prefixes.iter().any(|p| model.starts_with(p));
+
+        if starts(&[
+            "gpt-4o",
+            "gpt-4.1",
+            "gpt-5",
+            "gpt-oss",
+            "o1",
+            "o3",
+            "o4",
+            "chatgpt-4o",
+        ]) {
+            Some(Self::O200k)
+        } else if starts(&[
+            "gpt-4",
+            "gpt-3.5",
+            "claude",
+            "glm",
+            "mistral",
+            "devstral",
+            "codestral",
+            "magistral",
+            "ministral",
+            "labs-devstral",
+        ]) {
+            Some(Self::Cl100k)
+        } else {
+            None
+        }
+    }
+}
+
+/// Counts tokens with a tiktoken BPE encoding
+#[cfg(feature = "tiktoken")]
+#[derive(Debug, Clone, Copy)]
+pub struct TiktokenTokenizer {
+    encoding: Encoding,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenTokenizer {
+    /// Tokenizer for `encoding`; the BPE tables load on first use
+    #[must_use]
+    pub const fn new(encoding: Encoding) -> Self {
+        Self { encoding }
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl Tokenizer for TiktokenTokenizer {
+    fn name(&self) -> &'static str {
+        match self.encoding {
+            Encoding::Cl100k => "cl100k",
+            Encoding::O200k => "o200k",
+        }
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        let bpe = match self.encoding {
+            Encoding::Cl100k => tiktoken_rs::cl100k_base_singleton(),
+            Encoding::O200k => tiktoken_rs::o200k_base_singleton(),
+        };
+        bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+static HEURISTIC: HeuristicTokenizer = HeuristicTokenizer;
+#[cfg(feature = "tiktoken")]
+static CL100K: TiktokenTokenizer = TiktokenTokenizer::new(Encoding::Cl100k);
+#[cfg(feature = "tiktoken")]
+static O200K: TiktokenTokenizer = TiktokenTokenizer::new(Encoding::O200k);
+
+/// Tokenizer for `model_id`, or the heuristic when its family is unknown
+#[must_use]
+pub fn tokenizer_for_model(model_id: &str) -> &'static dyn Tokenizer {
+    Encoding::for_model(model_id).map_or(&HEURISTIC, tokenizer_for_encoding)
+}
+
+/// Tokenizer used before the model is known (cl100k, or the heuristic without `tiktoken`)
+#[must_use]
+pub fn default_tokenizer() -> &'static dyn Tokenizer {
+    tokenizer_for_encoding(Encoding::Cl100k)
+}
+
+#[cfg(feature = "tiktoken")]
+fn tokenizer_for_encoding(encoding: Encoding) -> &'static dyn Tokenizer {
+    match encoding {
+        Encoding::Cl100k => &CL100K,
+        Encoding::O200k => &O200K,
+    }
+}
+
+#[cfg(not(feature = "tiktoken"))]
+fn tokenizer_for_encoding(_encoding: Encoding) -> &'static dyn Tokenizer {
+    &HEURISTIC
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_is_chosen_by_model_family() {
+        assert_eq!(Encoding::for_model("gpt-4o-mini"), Some(Encoding::O200k));
+        assert_eq!(Encoding::for_model("openai/o3-mini"), Some(Encoding::O200k));
+        assert_eq!(Encoding::for_model("gpt-4-turbo"), Some(Encoding::Cl100k));
+        assert_eq!(Encoding::for_model("GLM-4.7"), Some(Encoding::Cl100k));
+        assert_eq!(
+            Encoding::for_model("mistralai/devstral-2512"),
+            Some(Encoding::Cl100k)
+        );
+        assert_eq!(Encoding::for_model("some-local-model"), None);
+    }
+
+    #[test]
+    fn test_unknown_model_uses_heuristic() {
+        let tokenizer = tokenizer_for_model("some-local-model");
+        assert_eq!(tokenizer.name(), "heuristic");
+        assert_eq!(tokenizer.count_tokens("abcdefghi"), 3);
+        assert_eq!(tokenizer.count_tokens(""), 0);
+    }
+}
@@ -1,21 +1,36 @@
 //! Tool bridge module
 //!
 //! Handles tool execution with timeout, cancellation support, and progress events.
+//! Running a tool ([`run_tool_call`]) only needs shared references, so several
+//! calls can run at once; their results are then added to the conversation in
+//! order with [`record_tool_run`].
 
+use super::audit::{self, AuditRecord};
 use super::memory::AgentMemory;
 use super::memory::AgentMessage;
+use super::messages::AgentLanguage;
 use super::progress::AgentEvent;
+use super::providers::clarification::ASK_USER_TOOL;
 use super::providers::TodoList;
 use super::recovery::sanitize_xml_tags;
 use super::registry::ToolRegistry;
-use crate::config::AGENT_TOOL_TIMEOUT_SECS;
+use super::tool_error::{ToolError, ToolErrorKind};
+use crate::config::{get_agent_clarification_timeout_secs, AGENT_TOOL_TIMEOUT_SECS};
 use crate::llm::{Message, ToolCall};
 use anyhow::Result;
+use futures_util::future::join_all;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 use tokio::time::{timeout, Duration};
 use tracing::{info, warn};
 
+/// Tools whose results carry user answers or environment setup and are
+/// pinned in memory, so compaction never drops them
+const PINNED_TOOLS: &[&str] = &[ASK_USER_TOOL, "set_env"];
+
+/// Tool that runs a sub-agent and only waits for its tool calls
+const DELEGATION_TOOL: &str = "delegate_to_sub_agent";
+
 /// Context for tool execution
 pub struct ToolExecutionContext<'a> {
     /// Tool registry for executing tools
@@ -30,6 +45,48 @@ pub struct ToolExecutionContext<'a> {
     pub memory: &'a mut AgentMemory,
     /// Cancellation token for the current task
     pub cancellation_token: tokio_util::sync::CancellationToken,
+    /// Session owner, for the audit log
+    pub user_id: i64,
+    /// Current task ID, for the audit log
+    pub task_id: &'a str,
+}
+
+impl ToolExecutionContext<'_> {
+    /// The shared part of the context needed to run a tool
+    #[must_use]
+    pub fn run_context(&self) -> ToolRunContext<'_> {
+        ToolRunContext {
+            registry: self.registry,
+            progress_tx: self.progress_tx,
+            cancellation_token: &self.cancellation_token,
+            user_id: self.user_id,
+            task_id: self.task_id,
+        }
+    }
+}
+
+/// Context for running a tool, shared by calls that run concurrently
+pub struct ToolRunContext<'a> {
+    /// Tool registry for executing tools
+    pub registry: &'a ToolRegistry,
+    /// Channel for sending progress events
+    pub progress_tx: Option<&'a tokio::sync::mpsc::Sender<AgentEvent>>,
+    /// Cancellation token for the current task
+    pub cancellation_token: &'a tokio_util::sync::CancellationToken,
+    /// Session owner, for the audit log
+    pub user_id: i64,
+    /// Current task ID, for the audit log
+    pub task_id: &'a str,
+}
+
+/// Output of a finished tool run, not yet added to the conversation
+pub struct ToolRunOutput {
+    /// Output produced by the tool (or the error text).
+    pub output: String,
+    /// Structured error when the tool failed.
+    pub error: Option<ToolError>,
+    /// Execution time in milliseconds.
+    pub duration_ms: u64,
 }
 
 /// Result of executing a tool call.
@@ -38,6 +95,8 @@ pub struct ToolExecutionResult {
     pub tool_name: String,
     /// Output produced by the tool.
     pub output: String,
+    /// Structured error when the tool failed.
+    pub error: Option<ToolError>,
 }
 
 /// Execute a list of tool calls
@@ -57,43 +116,51 @@ pub async fn execute_single_tool_call(
     tool_call: ToolCall,
     ctx: &mut ToolExecutionContext<'_>,
 ) -> Result<ToolExecutionResult> {
+    let run = run_tool_call(&tool_call, &ctx.run_context()).await?;
+    Ok(record_tool_run(&tool_call, run, ctx).await)
+}
+
+/// Announce and run a tool call with timeout and cancellation support
+///
+/// The run is written to the audit log before its output is folded; the
+/// conversation is left untouched.
+///
+/// # Errors
+///
+/// Returns an error if the task is cancelled before or during the run.
+pub async fn run_tool_call(
+    tool_call: &ToolCall,
+    ctx: &ToolRunContext<'_>,
+) -> Result<ToolRunOutput> {
     // Check for cancellation before execution
     if ctx.cancellation_token.is_cancelled() {
-        return Err(anyhow::anyhow!("Task cancelled by user"));
+        return Err(anyhow::anyhow!("{}", AgentLanguage::current().cancelled()));
     }
 
-    let ToolCall { id, function, .. } = tool_call;
-    let name = function.name;
-    let args = function.arguments;
+    let name = &tool_call.function.name;
+    let args = &tool_call.function.arguments;
+    // Mask user-provided env values before the arguments reach any log
+    super::providers::register_env_arguments(ctx.user_id, name, args);
 
     info!(
         tool_name = %name,
-        tool_args = %crate::utils::truncate_str(&args, 200),
+        tool_args = %crate::utils::truncate_str(args, 200),
         "Executing tool call"
     );
 
     if let Some(tx) = ctx.progress_tx {
-        // Extract command preview for execute_command tool
-        let command_preview = if name == "execute_command" {
-            extract_command_preview(&args)
-        } else {
-            None
-        };
-
-        // Sanitize XML tags from tool name and input to prevent UI corruption
-        // This protects against malformed LLM responses that leak XML syntax
-        let _ = tx
-            .send(AgentEvent::ToolCall {
-                name: sanitize_xml_tags(&name),
-                input: sanitize_xml_tags(&args),
-                command_preview,
-            })
-            .await;
+        send_tool_call_event(tx, name, args).await;
     }
 
-    // Execute tool with timeout and cancellation support
-    let tool_timeout = Duration::from_secs(AGENT_TOOL_TIMEOUT_SECS);
-    let result = {
+    // Execute tool with timeout and cancellation support; `ask_user` enforces its own wait limit
+    let tool_timeout_secs = if name == ASK_USER_TOOL {
+        get_agent_clarification_timeout_secs().saturating_add(AGENT_TOOL_TIMEOUT_SECS)
+    } else {
+        AGENT_TOOL_TIMEOUT_SECS
+    };
+    let tool_timeout = Duration::from_secs(tool_timeout_secs);
+    let started_at = std::time::Instant::now();
+    let (output, error) = {
         use tokio::select;
         select! {
             biased;
@@ -104,30 +171,92 @@ pub async fn execute_single_tool_call(
                     // Give UI time to show cancelling status (2 sec cleanup timeout)
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                 }
-                return Err(anyhow::anyhow!("Task cancelled by user"));
+                return Err(anyhow::anyhow!("{}", AgentLanguage::current().cancelled()));
             },
-            res = timeout(tool_timeout, ctx.registry.execute(&name, &args, ctx.progress_tx, Some(&ctx.cancellation_token))) => {
+            res = timeout(tool_timeout, ctx.registry.execute(name, args, ctx.progress_tx, Some(ctx.cancellation_token))) => {
                 match res {
-                    Ok(Ok(r)) => r,
-                    Ok(Err(e)) => format!("Tool execution error: {e}"),
+                    Ok(Ok(r)) => (r, None),
+                    Ok(Err(e)) => {
+                        let error = ToolError::classify(&e);
+                        (error.to_tool_output(), Some(error))
+                    }
                     Err(_) => {
                         warn!(
                             tool_name = %name,
-                            timeout_secs = AGENT_TOOL_TIMEOUT_SECS,
+                            timeout_secs = tool_timeout_secs,
                             "Tool execution timed out"
                         );
-                        format!(
-                            "Tool '{name}' timed out ({} seconds)",
-                            AGENT_TOOL_TIMEOUT_SECS
-                        )
+                        let error = ToolError::new(
+                            ToolErrorKind::Timeout,
+                            format!("Tool '{name}' timed out ({tool_timeout_secs} seconds)"),
+                        );
+                        (error.to_tool_output(), Some(error))
                     }
                 }
             },
         }
     };
+    let duration_ms = u64::try_from(started_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+    audit::record(&AuditRecord::new(
+        ctx.user_id,
+        ctx.task_id,
+        name,
+        args,
+        &output,
+        error.as_ref(),
+        duration_ms,
+    ));
+    // Fold only after auditing, so the audit log sees the full output
+    let output = if error.is_none() {
+        ctx.registry.compress_output(name, output).await
+    } else {
+        output
+    };
+
+    Ok(ToolRunOutput {
+        output,
+        error,
+        duration_ms,
+    })
+}
 
-    // Sync todos if write_todos was called
-    if name == "write_todos" {
+/// Run tool calls concurrently, each holding one of `permits` while it runs
+///
+/// Results come back in call order, whatever order the runs finish in.
+/// Delegation takes no permit: the sub-agent's own tool calls draw from the
+/// same pool, and would otherwise wait for the permit their parent holds.
+pub async fn run_tool_calls(
+    tool_calls: &[ToolCall],
+    ctx: &ToolRunContext<'_>,
+    permits: &Semaphore,
+) -> Vec<Result<ToolRunOutput>> {
+    join_all(tool_calls.iter().map(|tool_call| async {
+        let _permit = if tool_call.function.name == DELEGATION_TOOL {
+            None
+        } else {
+            permits.acquire().await.ok()
+        };
+        run_tool_call(tool_call, ctx).await
+    }))
+    .await
+}
+
+/// Add a finished tool run to the conversation and report its result
+pub async fn record_tool_run(
+    tool_call: &ToolCall,
+    run: ToolRunOutput,
+    ctx: &mut ToolExecutionContext<'_>,
+) -> ToolExecutionResult {
+    let id = &tool_call.id;
+    let name = &tool_call.function.name;
+    let ToolRunOutput {
+        output: result,
+        error,
+        duration_ms,
+    } = run;
+
+    // Sync todos if the list was changed
+    if matches!(name.as_str(), "write_todos" | "update_todo") {
         sync_todos_from_arc(ctx.memory, ctx.todos_arc).await;
         if let Some(tx) = ctx.progress_tx {
             let _ = tx
@@ -144,19 +273,45 @@ pub async fn execute_single_tool_call(
             .send(AgentEvent::ToolResult {
                 name: name.clone(),
                 output: result.clone(),
+                duration_ms,
             })
             .await;
     }
 
     // Add result to messages
-    ctx.messages.push(Message::tool(&id, &name, &result));
-    let tool_msg = AgentMessage::tool(&id, &name, &result);
-    ctx.memory.add_message(tool_msg);
+    ctx.messages.push(Message::tool(id, name, &result));
+    let tool_msg = AgentMessage::tool(id, name, &result);
+    if PINNED_TOOLS.contains(&name.as_str()) && error.is_none() {
+        ctx.memory.add_pinned_message(tool_msg);
+    } else {
+        ctx.memory.add_message(tool_msg);
+    }
 
-    Ok(ToolExecutionResult {
-        tool_name: name,
+    ToolExecutionResult {
+        tool_name: name.clone(),
         output: result,
-    })
+        error,
+    }
+}
+
+/// Announce a tool call to the progress channel
+async fn send_tool_call_event(tx: &tokio::sync::mpsc::Sender<AgentEvent>, name: &str, args: &str) {
+    // Extract command preview for execute_command tool
+    let command_preview = if name == "execute_command" {
+        extract_command_preview(args)
+    } else {
+        None
+    };
+
+    // Sanitize XML tags from tool name and input to prevent UI corruption
+    // This protects against malformed LLM responses that leak XML syntax
+    let _ = tx
+        .send(AgentEvent::ToolCall {
+            name: sanitize_xml_tags(name),
+            input: sanitize_xml_tags(args),
+            command_preview,
+        })
+        .await;
 }
 
 /// Synchronize todos from the shared Arc to the session memory
@@ -171,3 +326,110 @@ fn extract_command_preview(args: &str) -> Option<String> {
         .ok()
         .and_then(|v| v.get("command").and_then(|c| c.as_str()).map(String::from))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::provider::ToolProvider;
+    use crate::llm::{ToolCallFunction, ToolDefinition};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sleeps on every call and tracks how many calls overlap
+    #[derive(Default)]
+    struct ProbeProvider {
+        running: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl ToolProvider for ProbeProvider {
+        fn name(&self) -> &'static str {
+            "probe"
+        }
+
+        fn tools(&self) -> Vec<ToolDefinition> {
+            vec![ToolDefinition {
+                name: "probe".to_string(),
+                description: "Test tool".to_string(),
+                parameters: serde_json::json!({"type": "object"}),
+            }]
+        }
+
+        fn can_handle(&self, tool_name: &str) -> bool {
+            tool_name == "probe"
+        }
+
+        async fn execute(
+            &self,
+            _tool_name: &str,
+            arguments: &str,
+            _progress_tx: Option<&tokio::sync::mpsc::Sender<AgentEvent>>,
+            _cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+        ) -> Result<String> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(arguments.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_calls_bounds_concurrency_and_keeps_order() {
+        let provider = ProbeProvider::default();
+        let peak = Arc::clone(&provider.peak);
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(provider));
+        let cancellation_token = tokio_util::sync::CancellationToken::new();
+        let ctx = ToolRunContext {
+            registry: &registry,
+            progress_tx: None,
+            cancellation_token: &cancellation_token,
+            user_id: 0,
+            task_id: "task-1",
+        };
+        let calls: Vec<ToolCall> = (0..5)
+            .map(|i| ToolCall {
+                id: format!("call_{i}"),
+                function: ToolCallFunction {
+                    name: "probe".to_string(),
+                    arguments: format!("{{\"n\": {i}}}"),
+                },
+                is_recovered: false,
+            })
+            .collect();
+
+        let outputs: Vec<String> = run_tool_calls(&calls, &ctx, &Semaphore::new(2))
+            .await
+            .into_iter()
+            .map(|run| match run {
+                Ok(run) => run.output,
+                Err(e) => panic!("probe should not fail: {e}"),
+            })
+            .collect();
+
+        assert_eq!(outputs[0], r#"{"n": 0}"#);
+        assert_eq!(outputs[4], r#"{"n": 4}"#);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+        // Delegation runs while its caller's permits are all taken
+        let permits = Semaphore::new(1);
+        let Ok(_held) = permits.acquire().await else {
+            panic!("semaphore closed");
+        };
+        let delegate = ToolCall {
+            id: "call_d".to_string(),
+            function: ToolCallFunction {
+                name: DELEGATION_TOOL.to_string(),
+                arguments: "{}".to_string(),
+            },
+            is_recovered: false,
+        };
+        let runs = tokio::time::timeout(
+            Duration::from_secs(5),
+            run_tool_calls(std::slice::from_ref(&delegate), &ctx, &permits),
+        )
+        .await;
+        assert!(runs.is_ok_and(|runs| runs.len() == 1));
+    }
+}
@@ -0,0 +1,202 @@
+//! Structured tool errors
+//!
+//! Providers return `anyhow::Error`; the tool bridge classifies it into a
+//! [`ToolError`] so the model always sees the same machine-readable shape and
+//! can tell recoverable failures from fatal ones. Providers that know the
+//! category can return a `ToolError` directly (it converts into `anyhow::Error`).
+
+use serde_json::json;
+use std::fmt;
+use thiserror::Error;
+
+/// Category of a tool failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolErrorKind {
+    /// Arguments were missing or malformed
+    InvalidArguments,
+    /// File, URL or other resource does not exist
+    NotFound,
+    /// Operation is not permitted
+    PermissionDenied,
+    /// Tool did not finish in time
+    Timeout,
+    /// Network or upstream service failure
+    Network,
+    /// Tool is unknown or not available in this session
+    Unavailable,
+    /// Any other failure
+    Internal,
+}
+
+impl ToolErrorKind {
+    /// Stable snake_case identifier shown to the model
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::InvalidArguments => "invalid_arguments",
+            Self::NotFound => "not_found",
+            Self::PermissionDenied => "permission_denied",
+            Self::Timeout => "timeout",
+            Self::Network => "network",
+            Self::Unavailable => "unavailable",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// Whether retrying (possibly with different arguments) can succeed
+    #[must_use]
+    pub const fn is_recoverable(self) -> bool {
+        !matches!(self, Self::PermissionDenied | Self::Unavailable)
+    }
+
+    fn from_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+
+        if has(&["unknown tool", "not available", "not enabled"]) {
+            Self::Unavailable
+        } else if has(&[
+            "permission denied",
+            "not permitted",
+            "forbidden",
+            "access denied",
+        ]) {
+            Self::PermissionDenied
+        } else if has(&["not found", "no such file", "does not exist", "404"]) {
+            Self::NotFound
+        } else if has(&["timed out", "timeout"]) {
+            Self::Timeout
+        } else if has(&["connection", "network", "dns", "502", "503", "504"]) {
+            Self::Network
+        } else if has(&[
+            "invalid",
+            "missing field",
+            "requires",
+            "expected",
+            "unknown field",
+        ]) {
+            Self::InvalidArguments
+        } else {
+            Self::Internal
+        }
+    }
+}
+
+impl fmt::Display for ToolErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A categorized tool failure
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message}")]
+pub struct ToolError {
+    /// Failure category
+    pub kind: ToolErrorKind,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl ToolError {
+    /// Create a tool error with an explicit category
+    #[must_use]
+    pub fn new(kind: ToolErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Classify an arbitrary provider error
+    #[must_use]
+    pub fn classify(error: &anyhow::Error) -> Self {
+        if let Some(tool_error) = error.downcast_ref::<Self>() {
+            return tool_error.clone();
+        }
+
+        let message = error.to_string();
+        let kind = if error.downcast_ref::<serde_json::Error>().is_some() {
+            ToolErrorKind::InvalidArguments
+        } else if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+            match io_error.kind() {
+                std::io::ErrorKind::NotFound => ToolErrorKind::NotFound,
+                std::io::ErrorKind::PermissionDenied => ToolErrorKind::PermissionDenied,
+                std::io::ErrorKind::TimedOut => ToolErrorKind::Timeout,
+                _ => ToolErrorKind::from_message(&message),
+            }
+        } else if let Some(http_error) = error.downcast_ref::<reqwest::Error>() {
+            if http_error.is_timeout() {
+                ToolErrorKind::Timeout
+            } else {
+                ToolErrorKind::Network
+            }
+        } else {
+            ToolErrorKind::from_message(&message)
+        };
+
+        Self { kind, message }
+    }
+
+    /// Whether retrying (possibly with different arguments) can succeed
+    #[must_use]
+    pub const fn is_recoverable(&self) -> bool {
+        self.kind.is_recoverable()
+    }
+
+    /// Render the error as the tool result passed back to the model
+    #[must_use]
+    pub fn to_tool_output(&self) -> String {
+        json!({
+            "error": {
+                "kind": self.kind.as_str(),
+                "recoverable": self.is_recoverable(),
+                "message": self.message,
+            }
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ToolError, ToolErrorKind};
+
+    #[test]
+    fn classifies_common_errors() {
+        let json_error =
+            serde_json::from_str::<serde_json::Value>("{").map_err(anyhow::Error::from);
+        if let Err(e) = json_error {
+            assert_eq!(
+                ToolError::classify(&e).kind,
+                ToolErrorKind::InvalidArguments
+            );
+        }
+
+        let io_error = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(ToolError::classify(&io_error).kind, ToolErrorKind::NotFound);
+
+        let message = anyhow::anyhow!("cat: /etc/shadow: Permission denied");
+        let classified = ToolError::classify(&message);
+        assert_eq!(classified.kind, ToolErrorKind::PermissionDenied);
+        assert!(!classified.is_recoverable());
+
+        let explicit = anyhow::Error::from(ToolError::new(ToolErrorKind::Unavailable, "nope"));
+        assert_eq!(
+            ToolError::classify(&explicit).kind,
+            ToolErrorKind::Unavailable
+        );
+    }
+
+    #[test]
+    fn renders_structured_output() {
+        let output = ToolError::new(ToolErrorKind::Timeout, "took too long").to_tool_output();
+        let value: serde_json::Value = match serde_json::from_str(&output) {
+            Ok(value) => value,
+            Err(e) => panic!("tool output is not JSON: {e}"),
+        };
+        assert_eq!(value["error"]["kind"], "timeout");
+        assert_eq!(value["error"]["recoverable"], true);
+        assert_eq!(value["error"]["message"], "took too long");
+    }
+}
@@ -4,6 +4,7 @@
 //!
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 // LLM provider defaults
 /// Default temperature used for Groq chat completions.
@@ -46,6 +47,9 @@ pub const OPENROUTER_AUDIO_TRANSCRIBE_PROMPT: &str = concat!(
     "simply write '(no speech)'."
 );
 
+/// LLM provider names accepted in `*_MODEL_PROVIDER` settings.
+pub const KNOWN_LLM_PROVIDERS: &[&str] = &["groq", "mistral", "zai", "gemini", "openrouter"];
+
 /// Agent settings loaded from environment variables.
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct AgentSettings {
@@ -81,12 +85,16 @@ pub struct AgentSettings {
     /// R2 Storage bucket name
     pub r2_bucket_name: Option<String>,
 
-    /// Site URL for `OpenRouter` identification
-    #[serde(default = "default_openrouter_site_url")]
+    /// Site URL for `OpenRouter` identification (overrides `LLM_REFERER`)
+    #[serde(default)]
     pub openrouter_site_url: String,
-    /// Site name for `OpenRouter` identification
-    #[serde(default = "default_openrouter_site_name")]
+    /// Site name for `OpenRouter` identification (overrides `LLM_TITLE`)
+    #[serde(default)]
     pub openrouter_site_name: String,
+    /// Comma-separated `OpenRouter` upstream provider order (e.g. "anthropic,openai")
+    pub openrouter_provider_order: Option<String>,
+    /// Whether `OpenRouter` may fall back to providers outside the configured order
+    pub openrouter_allow_fallbacks: Option<bool>,
 
     /// Default system message
     pub system_message: Option<String>,
@@ -100,6 +108,10 @@ pub struct AgentSettings {
     pub chat_model_provider: Option<String>,
     /// Chat model max tokens override
     pub chat_model_max_tokens: Option<u32>,
+    /// Chat model stop sequences (JSON array or comma-separated)
+    pub chat_model_stop: Option<String>,
+    /// Chat model context window override, in tokens
+    pub chat_model_context_window: Option<u32>,
 
     /// Agent model ID override
     pub agent_model_id: Option<String>,
@@ -107,6 +119,10 @@ pub struct AgentSettings {
     pub agent_model_provider: Option<String>,
     /// Agent model max tokens override
     pub agent_model_max_tokens: Option<u32>,
+    /// Agent model stop sequences (JSON array or comma-separated)
+    pub agent_model_stop: Option<String>,
+    /// Agent model context window override, in tokens
+    pub agent_model_context_window: Option<u32>,
 
     /// Sub-agent model ID override
     pub sub_agent_model_id: Option<String>,
@@ -114,6 +130,10 @@ pub struct AgentSettings {
     pub sub_agent_model_provider: Option<String>,
     /// Sub-agent model max tokens override
     pub sub_agent_max_tokens: Option<u32>,
+    /// Sub-agent model stop sequences (JSON array or comma-separated)
+    pub sub_agent_model_stop: Option<String>,
+    /// Sub-agent model context window override, in tokens
+    pub sub_agent_model_context_window: Option<u32>,
 
     /// Media model ID override (for voice/images)
     pub media_model_id: Option<String>,
@@ -134,20 +154,19 @@ pub struct AgentSettings {
     pub agent_timeout_secs: Option<u64>,
     /// Sub-agent timeout in seconds
     pub sub_agent_timeout_secs: Option<u64>,
-}
 
-const fn default_openrouter_site_url() -> String {
-    String::new()
+    /// Sampling seed sent to providers that support one (`LLM_SEED`)
+    ///
+    /// Meant for tests and debugging. Reproducibility is best-effort: providers
+    /// may ignore the seed or change backends, and tool calling or long
+    /// generations can still diverge.
+    pub llm_seed: Option<u64>,
 }
 
 fn default_zai_api_base() -> String {
     "https://api.z.ai/api/coding/paas/v4/chat/completions".to_string()
 }
 
-fn default_openrouter_site_name() -> String {
-    "Oxide Agent Bot".to_string()
-}
-
 /// Build the base configuration loader.
 ///
 /// # Errors
@@ -223,7 +242,9 @@ impl AgentSettings {
         }
 
         if settings.search_provider.is_none() {
-            if let Ok(val) = std::env::var("SEARCH_PROVIDER") {
+            if let Ok(val) =
+                std::env::var("SEARCH_BACKEND").or_else(|_| std::env::var("SEARCH_PROVIDER"))
+            {
                 if !val.is_empty() {
                     settings.search_provider = Some(val);
                 }
@@ -293,6 +314,135 @@ impl AgentSettings {
         Ok(settings)
     }
 
+    /// `OpenRouter` upstream provider order parsed from `OPENROUTER_PROVIDER_ORDER`.
+    #[must_use]
+    pub fn openrouter_provider_order(&self) -> Vec<String> {
+        self.openrouter_provider_order
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Cross-check model/provider configuration for consistency.
+    ///
+    /// Every configured model must reference a known provider name that also
+    /// has credentials. All problems are collected and reported at once so a
+    /// misconfigured deployment fails fast at startup instead of on first use.
+    /// Agent models whose max tokens fill their context window only get a
+    /// warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` listing every detected problem.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.warn_on_output_reservation();
+
+        let slots = [
+            ("CHAT_MODEL", &self.chat_model_id, &self.chat_model_provider),
+            (
+                "AGENT_MODEL",
+                &self.agent_model_id,
+                &self.agent_model_provider,
+            ),
+            (
+                "SUB_AGENT_MODEL",
+                &self.sub_agent_model_id,
+                &self.sub_agent_model_provider,
+            ),
+            (
+                "NARRATOR_MODEL",
+                &self.narrator_model_id,
+                &self.narrator_model_provider,
+            ),
+            (
+                "MEDIA_MODEL",
+                &self.media_model_id,
+                &self.media_model_provider,
+            ),
+        ];
+
+        let mut problems = Vec::new();
+        for (prefix, id, provider) in slots {
+            match (id, provider) {
+                (Some(_), None) => {
+                    problems.push(format!("{prefix}_ID is set but {prefix}_PROVIDER is not"));
+                }
+                (None, Some(_)) => {
+                    problems.push(format!("{prefix}_PROVIDER is set but {prefix}_ID is not"));
+                }
+                (Some(_), Some(provider)) => {
+                    if let Some(problem) = self.check_provider(provider) {
+                        problems.push(format!("{prefix}_PROVIDER: {problem}"));
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConfigError::Message(format!(
+            "Invalid model configuration:\n  - {}",
+            problems.join("\n  - ")
+        )))
+    }
+
+    /// Warn about agent models whose max tokens leave no room for input;
+    /// memory then reserves only a quarter of the window for the reply
+    fn warn_on_output_reservation(&self) {
+        let specs = [
+            ("AGENT_MODEL_MAX_TOKENS", self.agent_model_spec()),
+            ("SUB_AGENT_MAX_TOKENS", self.sub_agent_model_spec()),
+        ];
+        for (var, spec) in specs {
+            let Some((_, info)) = spec else {
+                continue;
+            };
+            if info.max_tokens >= info.context_window {
+                tracing::warn!(
+                    max_tokens = info.max_tokens,
+                    context_window = info.context_window,
+                    "{var} is not below the model's context window; \
+                     agent memory reserves only a quarter of the window for replies"
+                );
+            }
+        }
+    }
+
+    fn check_provider(&self, provider: &str) -> Option<String> {
+        if !KNOWN_LLM_PROVIDERS.contains(&provider) {
+            return Some(format!(
+                "unknown provider '{provider}' (expected one of: {})",
+                KNOWN_LLM_PROVIDERS.join(", ")
+            ));
+        }
+        if self.provider_api_key(provider).is_none() {
+            return Some(format!(
+                "provider '{provider}' has no API key ({}_API_KEY)",
+                provider.to_uppercase()
+            ));
+        }
+        None
+    }
+
+    fn provider_api_key(&self, provider: &str) -> Option<&str> {
+        let key = match provider {
+            "groq" => self.groq_api_key.as_deref(),
+            "mistral" => self.mistral_api_key.as_deref(),
+            "zai" => self.zai_api_key.as_deref(),
+            "gemini" => self.gemini_api_key.as_deref(),
+            "openrouter" => self.openrouter_api_key.as_deref(),
+            _ => None,
+        };
+        key.filter(|k| !k.trim().is_empty())
+    }
+
     fn upsert_model(models: &mut Vec<(String, ModelInfo)>, name: String, info: ModelInfo) {
         if let Some(pos) = models.iter().position(|(n, _)| n == &name) {
             models[pos] = (name, info);
@@ -313,6 +463,10 @@ impl AgentSettings {
                 id: id.clone(),
                 max_tokens,
                 provider: provider.clone(),
+                stop: parse_stop_sequences(self.chat_model_stop.as_deref()),
+                context_window: self
+                    .chat_model_context_window
+                    .unwrap_or_else(|| default_context_window(provider)),
             },
         ))
     }
@@ -328,6 +482,10 @@ impl AgentSettings {
                 id: id.clone(),
                 max_tokens,
                 provider: provider.clone(),
+                stop: parse_stop_sequences(self.agent_model_stop.as_deref()),
+                context_window: self
+                    .agent_model_context_window
+                    .unwrap_or_else(|| default_context_window(provider)),
             },
         ))
     }
@@ -343,6 +501,10 @@ impl AgentSettings {
                 id: id.clone(),
                 max_tokens,
                 provider: provider.clone(),
+                stop: parse_stop_sequences(self.sub_agent_model_stop.as_deref()),
+                context_window: self
+                    .sub_agent_model_context_window
+                    .unwrap_or_else(|| default_context_window(provider)),
             },
         ))
     }
@@ -357,6 +519,8 @@ impl AgentSettings {
                 id: id.clone(),
                 max_tokens: NARRATOR_MAX_TOKENS,
                 provider: provider.clone(),
+                stop: Vec::new(),
+                context_window: default_context_window(provider),
             },
         ))
     }
@@ -371,6 +535,8 @@ impl AgentSettings {
                 id: id.clone(),
                 max_tokens: self.chat_model_max_tokens.unwrap_or(64000),
                 provider: provider.clone(),
+                stop: Vec::new(),
+                context_window: default_context_window(provider),
             },
         ))
     }
@@ -461,6 +627,21 @@ impl AgentSettings {
         (String::new(), String::new(), 0)
     }
 
+    /// Returns the context window of the configured agent model, in tokens
+    pub fn get_agent_context_window(&self) -> u32 {
+        self.agent_model_spec()
+            .or_else(|| self.chat_model_spec())
+            .map_or(DEFAULT_CONTEXT_WINDOW, |(_, info)| info.context_window)
+    }
+
+    /// Returns the context window of the configured sub-agent model, in tokens
+    pub fn get_sub_agent_context_window(&self) -> u32 {
+        self.sub_agent_model_spec()
+            .or_else(|| self.agent_model_spec())
+            .or_else(|| self.chat_model_spec())
+            .map_or(DEFAULT_CONTEXT_WINDOW, |(_, info)| info.context_window)
+    }
+
     /// Returns the configured media model (id, provider)
     pub fn get_media_model(&self) -> (String, String) {
         if let (Some(id), Some(provider)) = (&self.media_model_id, &self.media_model_provider) {
@@ -559,10 +740,177 @@ mod tests {
         env::remove_var("ZAI_API_KEY");
         Ok(())
     }
+
+    #[test]
+    fn test_validate_accepts_consistent_config() {
+        let settings = AgentSettings {
+            zai_api_key: Some("key".to_string()),
+            chat_model_id: Some("glm-4.7".to_string()),
+            chat_model_provider: Some("zai".to_string()),
+            ..AgentSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        let settings = AgentSettings {
+            zai_api_key: Some("key".to_string()),
+            chat_model_id: Some("test-model".to_string()),
+            chat_model_provider: Some("openrotuer".to_string()),
+            agent_model_id: Some("agent-model".to_string()),
+            agent_model_provider: Some("gemini".to_string()),
+            narrator_model_id: Some("narrator".to_string()),
+            ..AgentSettings::default()
+        };
+
+        let message = match settings.validate() {
+            Err(ConfigError::Message(message)) => message,
+            other => panic!("expected validation error, got {other:?}"),
+        };
+        assert!(message.contains("unknown provider 'openrotuer'"));
+        assert!(message.contains("GEMINI_API_KEY"));
+        assert!(message.contains("NARRATOR_MODEL_ID is set but NARRATOR_MODEL_PROVIDER is not"));
+    }
+
+    #[test]
+    fn test_parse_tool_allowlist() {
+        let map = r#"{"42": ["web_search"], "default": ["write_todos", "read_file"]}"#;
+
+        let per_user = parse_tool_allowlist(42, Some("execute_command, read_file"), Some(map));
+        assert_eq!(
+            per_user,
+            Some(HashSet::from([
+                "execute_command".to_string(),
+                "read_file".to_string()
+            ]))
+        );
+
+        let from_map = parse_tool_allowlist(42, None, Some(map));
+        assert_eq!(from_map, Some(HashSet::from(["web_search".to_string()])));
+
+        let fallback = parse_tool_allowlist(7, None, Some(map));
+        assert_eq!(fallback.map(|tools| tools.len()), Some(2));
+
+        assert_eq!(parse_tool_allowlist(7, None, None), None);
+        assert_eq!(
+            parse_tool_allowlist(7, None, Some("not json")),
+            Some(HashSet::new())
+        );
+        assert_eq!(parse_tool_allowlist(7, None, Some(r#"{"42": []}"#)), None);
+    }
+
+    #[test]
+    fn test_tool_output_limits_scale_with_max_tokens() {
+        let base = ToolOutputLimits::scaled(TOOL_OUTPUT_REFERENCE_MAX_TOKENS);
+        assert_eq!(base.ytdlp_transcript_chars, YTDLP_MAX_TRANSCRIPT_CHARS);
+        assert_eq!(ToolOutputLimits::scaled(0), base);
+
+        let small = ToolOutputLimits::scaled(32_000);
+        assert_eq!(small.ytdlp_transcript_chars, YTDLP_MAX_TRANSCRIPT_CHARS / 4);
+
+        let tiny = ToolOutputLimits::scaled(1_000);
+        assert_eq!(tiny.crawl4ai_output_chars, CRAWL4AI_MAX_OUTPUT_CHARS / 8);
+
+        let huge = ToolOutputLimits::scaled(2_000_000);
+        assert_eq!(huge.ytdlp_metadata_chars, YTDLP_MAX_METADATA_CHARS * 4);
+    }
+
+    #[test]
+    fn test_context_window_defaults_per_provider() {
+        let settings = AgentSettings {
+            agent_model_id: Some("glm-4.7".to_string()),
+            agent_model_provider: Some("zai".to_string()),
+            sub_agent_model_id: Some("gemini-2.5-flash".to_string()),
+            sub_agent_model_provider: Some("gemini".to_string()),
+            sub_agent_model_context_window: Some(32_000),
+            ..AgentSettings::default()
+        };
+        assert_eq!(settings.get_agent_context_window(), 200_000);
+        assert_eq!(settings.get_sub_agent_context_window(), 32_000);
+        assert_eq!(
+            AgentSettings::default().get_agent_context_window(),
+            DEFAULT_CONTEXT_WINDOW
+        );
+        assert_eq!(default_context_window("mistral"), DEFAULT_CONTEXT_WINDOW);
+
+        let parsed: Result<ModelInfo, _> =
+            serde_json::from_str(r#"{"id": "glm-4.7", "max_tokens": 8000, "provider": "zai"}"#);
+        assert!(matches!(parsed, Ok(info) if info.context_window == 200_000));
+    }
+
+    #[test]
+    fn test_parse_show_reasoning() {
+        assert!(!parse_show_reasoning(None, 42));
+        assert!(!parse_show_reasoning(Some("false"), 42));
+        assert!(parse_show_reasoning(Some("TRUE"), 42));
+        assert!(parse_show_reasoning(Some("7, 42"), 42));
+        assert!(!parse_show_reasoning(Some("7,420"), 42));
+    }
+
+    #[test]
+    fn test_parse_stop_sequences() {
+        assert!(parse_stop_sequences(None).is_empty());
+        assert!(parse_stop_sequences(Some("  ")).is_empty());
+        assert_eq!(
+            parse_stop_sequences(Some("</answer>, END ,,")),
+            vec!["</answer>".to_string(), "END".to_string()]
+        );
+        assert_eq!(
+            parse_stop_sequences(Some(r#"["a, b", "\n\nUser:", ""]"#)),
+            vec!["a, b".to_string(), "\n\nUser:".to_string()]
+        );
+        assert!(parse_stop_sequences(Some("[not json")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_extra_headers() {
+        assert_eq!(
+            parse_extra_headers(r#"{"X-Proxy-Token": "abc", "X-Team": "ml"}"#).ok(),
+            Some(vec![
+                ("X-Proxy-Token".to_string(), "abc".to_string()),
+                ("X-Team".to_string(), "ml".to_string()),
+            ])
+        );
+        assert_eq!(parse_extra_headers("  ").ok(), Some(Vec::new()));
+        assert!(parse_extra_headers("X-Team: ml").is_err());
+        assert!(parse_extra_headers(r#"{"X-Retries": 3}"#).is_err());
+    }
+
+    #[test]
+    fn test_upload_type_matches() {
+        assert!(upload_type_matches(None, Some("a.exe"), None));
+        assert!(upload_type_matches(Some(" , "), Some("a.exe"), None));
+
+        let allowlist = Some("application/pdf, image/*, .CSV");
+        assert!(upload_type_matches(
+            allowlist,
+            Some("r.pdf"),
+            Some("application/pdf")
+        ));
+        assert!(upload_type_matches(
+            allowlist,
+            Some("p.png"),
+            Some("image/png")
+        ));
+        assert!(upload_type_matches(allowlist, Some("Data.csv"), None));
+        assert!(!upload_type_matches(
+            allowlist,
+            Some("a.exe"),
+            Some("application/x-msdownload")
+        ));
+        assert!(!upload_type_matches(allowlist, None, None));
+        assert!(!upload_type_matches(
+            allowlist,
+            Some("imagepng"),
+            Some("imagepng")
+        ));
+    }
 }
 
 /// Information about a supported LLM model
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "ModelInfoFields")]
 pub struct ModelInfo {
     /// Internal model identifier
     pub id: String,
@@ -570,6 +918,73 @@ pub struct ModelInfo {
     pub max_tokens: u32,
     /// Provider name
     pub provider: String,
+    /// Stop sequences sent with every request to this model (empty = none)
+    #[serde(default)]
+    pub stop: Vec<String>,
+    /// Context window (input and output) in tokens
+    pub context_window: u32,
+}
+
+/// Serialized form of [`ModelInfo`]; a missing context window falls back to
+/// the provider's default
+#[derive(Deserialize)]
+struct ModelInfoFields {
+    id: String,
+    max_tokens: u32,
+    provider: String,
+    #[serde(default)]
+    stop: Vec<String>,
+    #[serde(default)]
+    context_window: Option<u32>,
+}
+
+impl From<ModelInfoFields> for ModelInfo {
+    fn from(fields: ModelInfoFields) -> Self {
+        Self {
+            context_window: fields
+                .context_window
+                .unwrap_or_else(|| default_context_window(&fields.provider)),
+            id: fields.id,
+            max_tokens: fields.max_tokens,
+            provider: fields.provider,
+            stop: fields.stop,
+        }
+    }
+}
+
+/// Context window assumed for providers without a known default
+pub const DEFAULT_CONTEXT_WINDOW: u32 = 128_000;
+
+/// Typical context window of a provider's models, used when
+/// `*_MODEL_CONTEXT_WINDOW` is not set.
+#[must_use]
+pub fn default_context_window(provider: &str) -> u32 {
+    match provider {
+        "gemini" => 1_048_576,
+        "zai" => 200_000,
+        "groq" => 131_072,
+        _ => DEFAULT_CONTEXT_WINDOW,
+    }
+}
+
+/// Parse stop sequences from a `*_MODEL_STOP` setting.
+///
+/// Accepts a JSON array (`["</answer>", "\n\nUser:"]`, needed for sequences
+/// with commas or newlines) or a comma-separated list. Blank entries are dropped.
+#[must_use]
+pub fn parse_stop_sequences(raw: Option<&str>) -> Vec<String> {
+    let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Vec::new();
+    };
+    let sequences = if raw.starts_with('[') {
+        serde_json::from_str::<Vec<String>>(raw).unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Invalid stop sequence list, ignoring it");
+            Vec::new()
+        })
+    } else {
+        raw.split(',').map(str::trim).map(String::from).collect()
+    };
+    sequences.into_iter().filter(|s| !s.is_empty()).collect()
 }
 
 /// Get the agent model name from environment.
@@ -597,12 +1012,305 @@ pub const AGENT_TOOL_TIMEOUT_SECS: u64 = 300; // 5 minutes
 pub const AGENT_MAX_TOKENS: usize = 200_000;
 /// Sub-agent memory token limit (lighter context)
 pub const SUB_AGENT_MAX_TOKENS: usize = 64_000;
-/// Threshold to trigger memory compaction
-pub const AGENT_COMPACT_THRESHOLD: usize = 180_000; // 90% of max, triggers auto-compact
-/// Max forced continuations when todos incomplete
-pub const AGENT_CONTINUATION_LIMIT: usize = 10; // Max forced continuations when todos incomplete
+/// Share of the memory token budget (in percent) that triggers auto-compaction
+pub const AGENT_COMPACT_PERCENT: usize = 90;
+/// Share of the memory token budget (in percent) that pinned messages may keep
+/// through compaction; the oldest pins beyond it are summarized
+pub const AGENT_PINNED_PERCENT: usize = 30;
+/// Default for forced continuations when todos are incomplete
+pub const AGENT_CONTINUATION_LIMIT: usize = 10;
 /// Default limit for search tool calls per agent session
 pub const AGENT_SEARCH_LIMIT: usize = 10;
+/// Consecutive drip-feed iterations before the agent is nudged to batch tool
+/// calls; off by default, since many tasks legitimately need one call per step
+pub const WORKLOAD_DRIP_FEED_ITERATIONS: usize = 0;
+/// Tool calls per iteration at or below which an iteration counts as drip-feed
+pub const WORKLOAD_DRIP_FEED_MAX_CALLS: usize = 1;
+/// Default window (seconds) in which a new message is treated as a follow-up to the previous task
+pub const AGENT_FOLLOWUP_WINDOW_SECS: u64 = 600;
+
+/// Get the follow-up window in seconds (0 disables follow-up detection)
+///
+/// Environment variable: `AGENT_FOLLOWUP_WINDOW_SECS`
+#[must_use]
+pub fn get_agent_followup_window_secs() -> u64 {
+    std::env::var("AGENT_FOLLOWUP_WINDOW_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(AGENT_FOLLOWUP_WINDOW_SECS)
+}
+
+/// Get the maximum number of forced continuations before the agent finishes
+/// with its best partial answer
+///
+/// Environment variable: `AGENT_CONTINUATION_LIMIT`
+#[must_use]
+pub fn get_agent_continuation_limit() -> usize {
+    std::env::var("AGENT_CONTINUATION_LIMIT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(AGENT_CONTINUATION_LIMIT)
+}
+
+/// Get the pause between agent iterations in milliseconds (0 = no pause)
+///
+/// Spreads LLM requests out for providers with strict requests-per-minute
+/// limits, instead of hitting 429 and retrying.
+///
+/// Environment variable: `AGENT_ITERATION_DELAY_MS`
+#[must_use]
+pub fn get_agent_iteration_delay_ms() -> u64 {
+    std::env::var("AGENT_ITERATION_DELAY_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Get how many tool calls from one model response may run at once
+///
+/// The default of 1 runs them one after another. Higher values bound how
+/// many requests a single session has in flight; 0 is treated as 1.
+/// `finish_task`, `ask_user`, `set_cwd` and `set_env` always run alone, and
+/// calls running together do not see each other's effects.
+///
+/// Environment variable: `MAX_PARALLEL_TOOLS`
+#[must_use]
+pub fn get_max_parallel_tools() -> usize {
+    std::env::var("MAX_PARALLEL_TOOLS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Default time (seconds) the `ask_user` tool waits for the user's answer
+pub const AGENT_CLARIFICATION_TIMEOUT_SECS: u64 = 300;
+
+/// Get how long `ask_user` waits for an answer before the task fails
+///
+/// Environment variable: `AGENT_CLARIFICATION_TIMEOUT_SECS`
+#[must_use]
+pub fn get_agent_clarification_timeout_secs() -> u64 {
+    std::env::var("AGENT_CLARIFICATION_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(AGENT_CLARIFICATION_TIMEOUT_SECS)
+}
+
+/// Default size limit (MB) for sending a file into the chat
+pub const CHAT_DELIVERY_MAX_FILE_MB: u64 = 50;
+
+/// Get the largest file (bytes) sent into the chat; larger files are uploaded to a file host
+///
+/// Environment variable: `CHAT_DELIVERY_MAX_FILE_MB`
+#[must_use]
+pub fn get_chat_delivery_max_file_bytes() -> u64 {
+    std::env::var("CHAT_DELIVERY_MAX_FILE_MB")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|mb| *mb > 0)
+        .unwrap_or(CHAT_DELIVERY_MAX_FILE_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+/// Default `ytdlp_download_video` resolution
+pub const YTDLP_DEFAULT_RESOLUTION: &str = "720";
+/// Default container of videos downloaded with yt-dlp
+pub const YTDLP_DEFAULT_FORMAT: &str = "mp4";
+/// Containers yt-dlp may merge downloaded videos into
+pub const YTDLP_VIDEO_FORMATS: &[&str] = &["mp4", "webm", "mkv"];
+
+/// Default longest video (seconds) yt-dlp may download without a time range (3 hours)
+pub const YTDLP_MAX_DURATION_SECS: u64 = 3 * 3600;
+
+/// Get the longest video or range yt-dlp may download; zero disables the check.
+///
+/// Environment variable: `YTDLP_MAX_DURATION_SECS`
+#[must_use]
+pub fn get_ytdlp_max_duration_secs() -> u64 {
+    std::env::var("YTDLP_MAX_DURATION_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(YTDLP_MAX_DURATION_SECS)
+}
+
+/// Get the video resolution used when the agent asks for none (`480`, `720p`, `best`, ...)
+///
+/// Environment variable: `YTDLP_DEFAULT_RESOLUTION`
+#[must_use]
+pub fn get_ytdlp_default_resolution() -> String {
+    std::env::var("YTDLP_DEFAULT_RESOLUTION")
+        .ok()
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| YTDLP_DEFAULT_RESOLUTION.to_string())
+}
+
+/// Get the video container used when the agent asks for none, one of [`YTDLP_VIDEO_FORMATS`]
+///
+/// Environment variable: `YTDLP_DEFAULT_FORMAT`
+#[must_use]
+pub fn get_ytdlp_default_format() -> String {
+    std::env::var("YTDLP_DEFAULT_FORMAT")
+        .ok()
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| YTDLP_VIDEO_FORMATS.contains(&s.as_str()))
+        .unwrap_or_else(|| YTDLP_DEFAULT_FORMAT.to_string())
+}
+
+/// Whether yt-dlp downloads are sent to the user when the agent does not say
+/// (enabled by default; the agent can still pass `send_to_user`)
+///
+/// Environment variable: `YTDLP_AUTO_SEND`
+#[must_use]
+pub fn get_ytdlp_auto_send() -> bool {
+    std::env::var("YTDLP_AUTO_SEND")
+        .map(|v| !matches!(v.trim(), "false" | "0"))
+        .unwrap_or(true)
+}
+
+/// Default window (seconds) in which a repeated delivery of the same file is skipped
+pub const FILE_DELIVERY_DEDUP_SECS: u64 = 600;
+
+/// Get how long a delivered file is remembered to avoid sending it twice (0 = off)
+///
+/// Environment variable: `FILE_DELIVERY_DEDUP_SECS`
+#[must_use]
+pub fn get_file_delivery_dedup_secs() -> u64 {
+    std::env::var("FILE_DELIVERY_DEDUP_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(FILE_DELIVERY_DEDUP_SECS)
+}
+
+/// Default backend for `upload_file` and oversized chat deliveries
+pub const FILE_HOST_BACKEND: &str = "gofile";
+
+/// Get the file hosting backend: `gofile` or `s3` (the `R2_*` bucket)
+///
+/// Environment variable: `FILE_HOST_BACKEND`
+#[must_use]
+pub fn get_file_host_backend() -> String {
+    std::env::var("FILE_HOST_BACKEND")
+        .ok()
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| FILE_HOST_BACKEND.to_string())
+}
+
+/// Get the public base URL of the file host bucket (e.g. an R2 custom domain)
+///
+/// When unset, the `s3` backend returns presigned links that expire in 7 days.
+///
+/// Environment variable: `FILE_HOST_PUBLIC_URL`
+#[must_use]
+pub fn get_file_host_public_url() -> Option<String> {
+    std::env::var("FILE_HOST_PUBLIC_URL")
+        .ok()
+        .map(|s| s.trim().trim_end_matches('/').to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Default cap (MB) on files users send in chat mode (voice messages, photos)
+pub const MAX_UPLOAD_MB: u64 = 10;
+
+/// Get the largest incoming file (bytes) accepted in chat mode
+///
+/// Environment variable: `MAX_UPLOAD_MB`
+#[must_use]
+pub fn get_max_upload_bytes() -> u64 {
+    upload_limit_bytes("MAX_UPLOAD_MB", MAX_UPLOAD_MB)
+}
+
+/// Default cap (MB) on files users send in agent mode; they are copied into the sandbox
+pub const AGENT_MAX_UPLOAD_MB: u64 = 20;
+
+/// Get the largest incoming file (bytes) accepted in agent mode
+///
+/// Environment variable: `AGENT_MAX_UPLOAD_MB`
+#[must_use]
+pub fn get_agent_max_upload_bytes() -> u64 {
+    upload_limit_bytes("AGENT_MAX_UPLOAD_MB", AGENT_MAX_UPLOAD_MB)
+}
+
+fn upload_limit_bytes(var: &str, default_mb: u64) -> u64 {
+    std::env::var(var)
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .filter(|mb| *mb > 0)
+        .unwrap_or(default_mb)
+        .saturating_mul(1024 * 1024)
+}
+
+/// Whether an incoming document passes the `UPLOAD_ALLOWED_TYPES` allowlist
+///
+/// The allowlist is comma-separated: MIME types (`application/pdf`), MIME
+/// wildcards (`image/*`) and file extensions (`.csv`). Unset allows everything.
+///
+/// Environment variable: `UPLOAD_ALLOWED_TYPES`
+#[must_use]
+pub fn is_upload_type_allowed(file_name: Option<&str>, mime_type: Option<&str>) -> bool {
+    upload_type_matches(
+        std::env::var("UPLOAD_ALLOWED_TYPES").ok().as_deref(),
+        file_name,
+        mime_type,
+    )
+}
+
+fn upload_type_matches(
+    allowlist: Option<&str>,
+    file_name: Option<&str>,
+    mime_type: Option<&str>,
+) -> bool {
+    let entries: Vec<String> = allowlist
+        .unwrap_or_default()
+        .split(',')
+        .map(|entry| entry.trim().to_ascii_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    if entries.is_empty() {
+        return true;
+    }
+
+    let file_name = file_name.map(str::to_ascii_lowercase);
+    let mime_type = mime_type.map(str::to_ascii_lowercase);
+    entries.iter().any(|entry| {
+        if entry.starts_with('.') {
+            file_name
+                .as_deref()
+                .is_some_and(|name| name.ends_with(entry.as_str()))
+        } else if let Some(prefix) = entry.strip_suffix("/*") {
+            mime_type
+                .as_deref()
+                .and_then(|mime| mime.split_once('/'))
+                .is_some_and(|(kind, _)| kind == prefix)
+        } else {
+            mime_type.as_deref() == Some(entry.as_str())
+        }
+    })
+}
+
+/// Whether the model's raw reasoning is shown to the user (a debugging aid, off by default)
+///
+/// `true` enables it for everyone; otherwise a comma-separated list of user IDs.
+///
+/// Environment variable: `SHOW_REASONING`
+#[must_use]
+pub fn is_reasoning_visible(user_id: i64) -> bool {
+    parse_show_reasoning(std::env::var("SHOW_REASONING").ok().as_deref(), user_id)
+}
+
+fn parse_show_reasoning(raw: Option<&str>, user_id: i64) -> bool {
+    let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return false;
+    };
+    if matches!(raw.to_ascii_lowercase().as_str(), "true" | "1" | "all") {
+        return true;
+    }
+    raw.split(',')
+        .any(|id| id.trim().parse::<i64>().ok() == Some(user_id))
+}
 
 // Narrator system configuration
 /// Maximum tokens for narrator response (concise output)
@@ -613,6 +1321,8 @@ pub const NARRATOR_MAX_TOKENS: u32 = 256;
 pub const SKILLS_DIR: &str = "skills";
 /// Maximum tokens allocated to selected skills
 pub const SKILL_TOKEN_BUDGET: usize = 4096;
+/// Default share of the agent model's `max_tokens` the system prompt may take
+pub const SYSTEM_PROMPT_BUDGET_FRACTION: f64 = 0.25;
 /// Minimum semantic similarity score to consider a skill relevant
 pub const SKILL_EMBEDDING_THRESHOLD: f32 = 0.6;
 /// Maximum number of non-core skills to select
@@ -637,6 +1347,20 @@ pub fn get_skill_token_budget() -> usize {
         .unwrap_or(SKILL_TOKEN_BUDGET)
 }
 
+/// Get the share of the agent model's `max_tokens` the system prompt may take
+///
+/// Values outside `(0, 1]` fall back to the default.
+///
+/// Environment variable: `SYSTEM_PROMPT_BUDGET_FRACTION`
+#[must_use]
+pub fn get_system_prompt_budget_fraction() -> f64 {
+    std::env::var("SYSTEM_PROMPT_BUDGET_FRACTION")
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .filter(|f| *f > 0.0 && *f <= 1.0)
+        .unwrap_or(SYSTEM_PROMPT_BUDGET_FRACTION)
+}
+
 /// Get semantic threshold from env or default.
 #[must_use]
 pub fn get_skill_semantic_threshold() -> f32 {
@@ -680,6 +1404,18 @@ pub fn get_embedding_model_id() -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
+/// Get the embedding dimension override from env.
+///
+/// Unset or `0` means the dimension is probed from the embedding model.
+/// Environment variable: `EMBEDDING_DIMENSION`
+#[must_use]
+pub fn get_embedding_dimension() -> Option<usize> {
+    std::env::var("EMBEDDING_DIMENSION")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&dim| dim > 0)
+}
+
 /// Get embedding cache directory from env or default.
 /// Appends provider/model subdirectory for cache isolation.
 #[must_use]
@@ -702,6 +1438,226 @@ pub fn get_agent_search_limit() -> usize {
         .unwrap_or(AGENT_SEARCH_LIMIT)
 }
 
+/// Get the number of consecutive drip-feed iterations that trigger batching guidance.
+///
+/// Zero (the default) disables the check.
+///
+/// Environment variable: `WORKLOAD_DRIP_FEED_ITERATIONS`
+#[must_use]
+pub fn get_workload_drip_feed_iterations() -> usize {
+    std::env::var("WORKLOAD_DRIP_FEED_ITERATIONS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(WORKLOAD_DRIP_FEED_ITERATIONS)
+}
+
+/// Get the maximum tool calls per iteration that still count as drip-feed.
+///
+/// Environment variable: `WORKLOAD_DRIP_FEED_MAX_CALLS`
+#[must_use]
+pub fn get_workload_drip_feed_max_calls() -> usize {
+    std::env::var("WORKLOAD_DRIP_FEED_MAX_CALLS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(WORKLOAD_DRIP_FEED_MAX_CALLS)
+}
+
+/// Get the cumulative token budget for a single agent task.
+///
+/// Sums `total_tokens` reported by every LLM call of the task. Unset or
+/// zero disables the budget.
+///
+/// Environment variable: `AGENT_TOKEN_BUDGET`
+#[must_use]
+pub fn get_agent_token_budget() -> Option<usize> {
+    std::env::var("AGENT_TOKEN_BUDGET")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|budget| *budget > 0)
+}
+
+/// Get the maximum number of tool calls for a single agent task.
+///
+/// Unset or zero disables the limit.
+///
+/// Environment variable: `AGENT_MAX_TOOL_CALLS`
+#[must_use]
+pub fn get_agent_max_tool_calls() -> Option<usize> {
+    std::env::var("AGENT_MAX_TOOL_CALLS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|limit| *limit > 0)
+}
+
+/// Get the tools a user's agent is allowed to call; `None` allows every tool.
+///
+/// `TOOL_ALLOWLIST_<user_id>` (comma-separated tool names) takes precedence over
+/// the JSON map in `TOOL_ALLOWLIST`, e.g. `{"123": ["web_search"], "default": ["write_todos"]}`.
+/// The `"default"` entry applies to users without their own entry. Invalid
+/// JSON fails closed: the user gets no tools until the setting is fixed.
+///
+/// Environment variables: `TOOL_ALLOWLIST_<user_id>`, `TOOL_ALLOWLIST`
+#[must_use]
+pub fn get_tool_allowlist(user_id: i64) -> Option<HashSet<String>> {
+    let per_user = std::env::var(format!("TOOL_ALLOWLIST_{user_id}")).ok();
+    let map = std::env::var("TOOL_ALLOWLIST").ok();
+    parse_tool_allowlist(user_id, per_user.as_deref(), map.as_deref())
+}
+
+fn parse_tool_allowlist(
+    user_id: i64,
+    per_user: Option<&str>,
+    map: Option<&str>,
+) -> Option<HashSet<String>> {
+    if let Some(list) = per_user.filter(|list| !list.trim().is_empty()) {
+        return Some(
+            list.split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        );
+    }
+
+    let map = map.filter(|map| !map.trim().is_empty())?;
+    let mut map: HashMap<String, HashSet<String>> = match serde_json::from_str(map) {
+        Ok(map) => map,
+        Err(e) => {
+            tracing::error!(error = %e, "Invalid TOOL_ALLOWLIST JSON, denying all tools");
+            return Some(HashSet::new());
+        }
+    };
+    map.remove(&user_id.to_string())
+        .or_else(|| map.remove("default"))
+}
+
+/// Default language for system-level agent messages
+pub const AGENT_LANGUAGE: &str = "en";
+
+/// Get the language of system-level agent messages (`en` or `ru`)
+///
+/// Environment variable: `AGENT_LANGUAGE`
+#[must_use]
+pub fn get_agent_language() -> String {
+    std::env::var("AGENT_LANGUAGE")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| AGENT_LANGUAGE.to_string())
+}
+
+/// Get the timezone the agent sees dates in; `None` means server local time
+///
+/// Takes an IANA name such as `Europe/Moscow`. Invalid names are logged and
+/// ignored.
+///
+/// Environment variable: `AGENT_TIMEZONE`
+#[must_use]
+pub fn get_agent_timezone() -> Option<chrono_tz::Tz> {
+    let name = std::env::var("AGENT_TIMEZONE").ok()?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    name.parse()
+        .map_err(|_| tracing::warn!(timezone = name, "Invalid AGENT_TIMEZONE, using server time"))
+        .ok()
+}
+
+/// Agent model `max_tokens` the default tool output limits are sized for
+pub const TOOL_OUTPUT_REFERENCE_MAX_TOKENS: u32 = 128_000;
+/// Default character limit for `ytdlp_download_transcript` output
+pub const YTDLP_MAX_TRANSCRIPT_CHARS: usize = 50_000;
+/// Default character limit for `ytdlp_get_video_metadata` output
+pub const YTDLP_MAX_METADATA_CHARS: usize = 25_000;
+/// Default character limit for Crawl4AI tool output
+pub const CRAWL4AI_MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// Character limits applied to large tool outputs before they reach the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolOutputLimits {
+    /// `ytdlp_download_transcript` limit
+    pub ytdlp_transcript_chars: usize,
+    /// `ytdlp_get_video_metadata` limit
+    pub ytdlp_metadata_chars: usize,
+    /// Crawl4AI (`deep_crawl`, `web_markdown`, `web_pdf`) limit
+    pub crawl4ai_output_chars: usize,
+}
+
+impl Default for ToolOutputLimits {
+    fn default() -> Self {
+        Self::scaled(TOOL_OUTPUT_REFERENCE_MAX_TOKENS)
+    }
+}
+
+impl ToolOutputLimits {
+    /// Limits for a model with the given `max_tokens`.
+    ///
+    /// Defaults scale with `max_tokens` relative to a 128k model (between 1/8
+    /// and 4x of the base values); explicit environment values always win.
+    ///
+    /// Environment variables: `YTDLP_MAX_TRANSCRIPT_CHARS`,
+    /// `YTDLP_MAX_METADATA_CHARS`, `CRAWL4AI_MAX_OUTPUT_CHARS`
+    #[must_use]
+    pub fn for_max_tokens(max_tokens: u32) -> Self {
+        let defaults = Self::scaled(max_tokens);
+        let env_or = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|limit| *limit > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            ytdlp_transcript_chars: env_or(
+                "YTDLP_MAX_TRANSCRIPT_CHARS",
+                defaults.ytdlp_transcript_chars,
+            ),
+            ytdlp_metadata_chars: env_or("YTDLP_MAX_METADATA_CHARS", defaults.ytdlp_metadata_chars),
+            crawl4ai_output_chars: env_or(
+                "CRAWL4AI_MAX_OUTPUT_CHARS",
+                defaults.crawl4ai_output_chars,
+            ),
+        }
+    }
+
+    fn scaled(max_tokens: u32) -> Self {
+        // Unknown models (max_tokens == 0) keep the base limits
+        let max_tokens = if max_tokens == 0 {
+            TOOL_OUTPUT_REFERENCE_MAX_TOKENS
+        } else {
+            max_tokens
+        };
+        let scale = |base: usize| {
+            let scaled =
+                base as u64 * u64::from(max_tokens) / u64::from(TOOL_OUTPUT_REFERENCE_MAX_TOKENS);
+            usize::try_from(scaled)
+                .unwrap_or(usize::MAX)
+                .clamp(base / 8, base * 4)
+        };
+        Self {
+            ytdlp_transcript_chars: scale(YTDLP_MAX_TRANSCRIPT_CHARS),
+            ytdlp_metadata_chars: scale(YTDLP_MAX_METADATA_CHARS),
+            crawl4ai_output_chars: scale(CRAWL4AI_MAX_OUTPUT_CHARS),
+        }
+    }
+}
+
+/// Default TTL for cached `web_search` results (seconds)
+pub const SEARCH_CACHE_TTL_SECS: u64 = 600;
+/// Maximum number of cached `web_search` results per process
+pub const SEARCH_CACHE_MAX_ENTRIES: u64 = 500;
+
+/// Get the TTL for cached `web_search` results (0 disables the cache)
+///
+/// Environment variable: `SEARCH_CACHE_TTL_SECS`
+#[must_use]
+pub fn get_search_cache_ttl_secs() -> u64 {
+    std::env::var("SEARCH_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SEARCH_CACHE_TTL_SECS)
+}
+
 // Sandbox configuration
 /// Docker image for the sandbox
 pub const SANDBOX_IMAGE: &str = "agent-sandbox:latest";
@@ -713,6 +1669,114 @@ pub const SANDBOX_CPU_PERIOD: i64 = 100_000;
 pub const SANDBOX_CPU_QUOTA: i64 = 200_000; // 2 CPUs (200% of period)
 /// Timeout for individual command execution in sandbox
 pub const SANDBOX_EXEC_TIMEOUT_SECS: u64 = 60; // 1 minute per command
+/// Tools the agent prompt assumes are available in the sandbox image
+pub const SANDBOX_EXPECTED_TOOLS: &[&str] = &["python3", "ffmpeg", "yt-dlp", "curl"];
+
+/// Get the sandbox Docker image.
+/// Environment variable: `SANDBOX_IMAGE`
+/// Custom images should provide at least [`SANDBOX_EXPECTED_TOOLS`].
+#[must_use]
+pub fn get_sandbox_image() -> String {
+    std::env::var("SANDBOX_IMAGE")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| SANDBOX_IMAGE.to_string())
+}
+
+/// Default time (seconds) a sandbox container may go unused before it is reaped on startup (1 day)
+pub const SANDBOX_STALE_AFTER_SECS: u64 = 86_400;
+
+/// Get how long a sandbox container may go unused (since it was last
+/// started or stopped) before it is removed on startup.
+///
+/// Zero disables the startup cleanup.
+///
+/// Environment variable: `SANDBOX_STALE_AFTER_SECS`
+#[must_use]
+pub fn get_sandbox_stale_after_secs() -> u64 {
+    std::env::var("SANDBOX_STALE_AFTER_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SANDBOX_STALE_AFTER_SECS)
+}
+
+/// Default cap (characters) on `execute_command` output passed back to the model
+pub const SANDBOX_MAX_OUTPUT_CHARS: usize = 30_000;
+
+/// Get the cap on `execute_command` output; longer output keeps its head and tail.
+///
+/// Environment variable: `SANDBOX_MAX_OUTPUT_CHARS`
+#[must_use]
+pub fn get_sandbox_max_output_chars() -> usize {
+    std::env::var("SANDBOX_MAX_OUTPUT_CHARS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|chars| *chars > 0)
+        .unwrap_or(SANDBOX_MAX_OUTPUT_CHARS)
+}
+
+/// Default number of sandbox containers allowed to run at once (0 = unlimited)
+pub const SANDBOX_MAX_CONTAINERS: usize = 0;
+/// Default time (seconds) a task waits for a free sandbox slot
+pub const SANDBOX_SLOT_WAIT_SECS: u64 = 30;
+
+/// Get the host-wide limit on live sandbox containers; zero means unlimited.
+///
+/// Environment variable: `SANDBOX_MAX_CONTAINERS`
+#[must_use]
+pub fn get_sandbox_max_containers() -> usize {
+    std::env::var("SANDBOX_MAX_CONTAINERS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SANDBOX_MAX_CONTAINERS)
+}
+
+/// Get how long a task waits for a sandbox slot before failing; zero fails fast.
+///
+/// Environment variable: `SANDBOX_SLOT_WAIT_SECS`
+#[must_use]
+pub fn get_sandbox_slot_wait_secs() -> u64 {
+    std::env::var("SANDBOX_SLOT_WAIT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SANDBOX_SLOT_WAIT_SECS)
+}
+
+/// Default time (seconds) a sandbox container may sit unused before it is
+/// stopped and its slot freed (30 minutes)
+pub const SANDBOX_IDLE_TIMEOUT_SECS: u64 = 1_800;
+/// How often (seconds) to look for idle sandbox containers
+pub const SANDBOX_IDLE_CHECK_SECS: u64 = 60;
+
+/// Get how long a sandbox container may sit unused before it is stopped.
+///
+/// The container is kept and restarts on next use; zero disables the reaper.
+///
+/// Environment variable: `SANDBOX_IDLE_TIMEOUT_SECS`
+#[must_use]
+pub fn get_sandbox_idle_timeout_secs() -> u64 {
+    std::env::var("SANDBOX_IDLE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SANDBOX_IDLE_TIMEOUT_SECS)
+}
+
+/// Default number of retries for a sandbox container that fails to be created
+pub const SANDBOX_CREATE_RETRIES: usize = 2;
+/// Delay (milliseconds) before the first sandbox creation retry; doubles each time
+pub const SANDBOX_CREATE_BACKOFF_MS: u64 = 500;
+
+/// Get how many times container creation is retried after a transient Docker
+/// failure (daemon busy or unreachable); zero disables retries.
+///
+/// Environment variable: `SANDBOX_CREATE_RETRIES`
+#[must_use]
+pub fn get_sandbox_create_retries() -> usize {
+    std::env::var("SANDBOX_CREATE_RETRIES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SANDBOX_CREATE_RETRIES)
+}
 
 /// Transport API retry configuration for file operations.
 pub const TRANSPORT_API_MAX_RETRIES: usize = 3;
@@ -744,18 +1808,98 @@ pub fn get_crawl4ai_timeout() -> u64 {
         .unwrap_or(CRAWL4AI_DEFAULT_TIMEOUT_SECS)
 }
 
+/// Default number of retries for transient Crawl4AI failures (5xx, connection errors)
+pub const CRAWL4AI_DEFAULT_MAX_RETRIES: usize = 2;
+
+/// Get the number of retries for transient Crawl4AI failures
+///
+/// Environment variable: `CRAWL4AI_MAX_RETRIES`
+#[must_use]
+pub fn get_crawl4ai_max_retries() -> usize {
+    std::env::var("CRAWL4AI_MAX_RETRIES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(CRAWL4AI_DEFAULT_MAX_RETRIES)
+}
+
+/// Default maximum PDF size (bytes) returned inline as base64 by `web_pdf`
+///
+/// Base64 grows the PDF by a third and the result lands in the prompt, so only
+/// tiny documents stay inline.
+pub const CRAWL4AI_PDF_MAX_BYTES: usize = 8 * 1024;
+
+/// Get the maximum PDF size returned inline by `web_pdf`.
+/// Larger PDFs are saved into the sandbox instead.
+///
+/// Environment variable: `CRAWL4AI_PDF_MAX_BYTES`
+#[must_use]
+pub fn get_crawl4ai_pdf_max_bytes() -> usize {
+    std::env::var("CRAWL4AI_PDF_MAX_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(CRAWL4AI_PDF_MAX_BYTES)
+}
+
+/// Whether `deep_crawl` should honor robots.txt (disabled by default)
+///
+/// Environment variable: `CRAWL4AI_RESPECT_ROBOTS`
+#[must_use]
+pub fn get_crawl4ai_respect_robots() -> bool {
+    std::env::var("CRAWL4AI_RESPECT_ROBOTS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Get the per-domain delay between crawled pages in milliseconds (0 = no delay)
+///
+/// Environment variable: `CRAWL4AI_CRAWL_DELAY_MS`
+#[must_use]
+pub fn get_crawl4ai_crawl_delay_ms() -> u64 {
+    std::env::var("CRAWL4AI_CRAWL_DELAY_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Default size from which tool results are folded (chars)
+pub const COMPRESS_TOOL_OUTPUT_MIN_CHARS: usize = 4000;
+
+/// Whether repetitive tool output is folded before it enters the conversation
+///
+/// Environment variable: `COMPRESS_TOOL_OUTPUT`
+#[must_use]
+pub fn get_compress_tool_output() -> bool {
+    std::env::var("COMPRESS_TOOL_OUTPUT")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Get the size from which tool results are folded
+///
+/// Environment variable: `COMPRESS_TOOL_OUTPUT_MIN_CHARS`
+#[must_use]
+pub fn get_compress_tool_output_min_chars() -> usize {
+    std::env::var("COMPRESS_TOOL_OUTPUT_MIN_CHARS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(COMPRESS_TOOL_OUTPUT_MIN_CHARS)
+}
+
 /// Default web search provider
 pub const DEFAULT_SEARCH_PROVIDER: &str = "tavily";
 
 /// Get web search provider from env or default
 ///
-/// Environment variable: `SEARCH_PROVIDER`
-/// Valid values: "tavily" or "crawl4ai"
+/// Environment variable: `SEARCH_BACKEND` (`SEARCH_PROVIDER` is still read
+/// when it is unset)
+/// Valid values: "tavily", "brave" or "crawl4ai"
 #[must_use]
 pub fn get_search_provider() -> String {
-    std::env::var("SEARCH_PROVIDER")
+    std::env::var("SEARCH_BACKEND")
+        .or_else(|_| std::env::var("SEARCH_PROVIDER"))
         .ok()
-        .filter(|s| matches!(s.as_str(), "tavily" | "crawl4ai"))
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| matches!(s.as_str(), "tavily" | "brave" | "crawl4ai"))
         .unwrap_or_else(|| DEFAULT_SEARCH_PROVIDER.to_string())
 }
 
@@ -774,3 +1918,176 @@ pub fn get_llm_http_timeout_secs() -> u64 {
         .and_then(|s| s.parse().ok())
         .unwrap_or(LLM_HTTP_TIMEOUT_SECS)
 }
+
+/// Default timeout for agent/tool-calling LLM requests (seconds)
+/// Agent turns carry large contexts, so they get more time than plain chat
+pub const LLM_REQUEST_TIMEOUT_SECS: u64 = 600;
+
+/// Default timeout for establishing a connection to an LLM API (seconds)
+pub const LLM_CONNECT_TIMEOUT_SECS: u64 = 30;
+
+/// Get the agent/tool-calling LLM request timeout from env or default
+///
+/// For streaming providers this bounds the wait for the first response and
+/// the gap between chunks rather than the whole generation.
+///
+/// Environment variable: `LLM_REQUEST_TIMEOUT_SECS`
+#[must_use]
+pub fn get_llm_request_timeout_secs() -> u64 {
+    std::env::var("LLM_REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(LLM_REQUEST_TIMEOUT_SECS)
+}
+
+/// Get the LLM connect timeout from env or default
+///
+/// Environment variable: `LLM_CONNECT_TIMEOUT_SECS`
+#[must_use]
+pub fn get_llm_connect_timeout_secs() -> u64 {
+    std::env::var("LLM_CONNECT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(LLM_CONNECT_TIMEOUT_SECS)
+}
+
+/// Default `HTTP-Referer` sent to LLM providers
+pub const LLM_REFERER: &str = "https://github.com/0FL01/Another-Chat-with-LLM";
+/// Default `X-Title` sent to LLM providers
+pub const LLM_TITLE: &str = "Oxide Agent Bot";
+
+/// Get the `User-Agent` sent to LLM providers
+///
+/// Defaults to `oxide-agent/<version>`.
+///
+/// Environment variable: `LLM_USER_AGENT`
+#[must_use]
+pub fn get_llm_user_agent() -> String {
+    non_empty_env("LLM_USER_AGENT")
+        .unwrap_or_else(|| format!("oxide-agent/{}", env!("CARGO_PKG_VERSION")))
+}
+
+/// Get the `HTTP-Referer` sent to LLM providers (app attribution)
+///
+/// Environment variable: `LLM_REFERER`
+#[must_use]
+pub fn get_llm_referer() -> String {
+    non_empty_env("LLM_REFERER").unwrap_or_else(|| LLM_REFERER.to_string())
+}
+
+/// Get the `X-Title` sent to LLM providers (app attribution)
+///
+/// Environment variable: `LLM_TITLE`
+#[must_use]
+pub fn get_llm_title() -> String {
+    non_empty_env("LLM_TITLE").unwrap_or_else(|| LLM_TITLE.to_string())
+}
+
+fn non_empty_env(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Get extra HTTP headers sent with every request to `provider`
+///
+/// Meant for proxies in front of a provider that need their own auth headers.
+/// The value is a JSON object of header names to values; invalid JSON is
+/// ignored with a warning. Values of secret-looking headers are registered
+/// for log redaction.
+///
+/// Environment variable: `<PROVIDER>_EXTRA_HEADERS`, e.g. `OPENROUTER_EXTRA_HEADERS`
+#[must_use]
+pub fn get_provider_extra_headers(provider: &str) -> Vec<(String, String)> {
+    let var = format!("{}_EXTRA_HEADERS", provider.to_ascii_uppercase());
+    let Ok(raw) = std::env::var(&var) else {
+        return Vec::new();
+    };
+    match parse_extra_headers(&raw) {
+        Ok(headers) => {
+            for (name, value) in &headers {
+                if crate::redaction::is_secret_header(name) {
+                    crate::redaction::register_secret(value);
+                }
+            }
+            headers
+        }
+        Err(e) => {
+            tracing::warn!(var = %var, error = %e, "Invalid extra headers JSON, ignoring");
+            Vec::new()
+        }
+    }
+}
+
+fn parse_extra_headers(raw: &str) -> Result<Vec<(String, String)>, serde_json::Error> {
+    if raw.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let headers: std::collections::BTreeMap<String, String> = serde_json::from_str(raw)?;
+    Ok(headers.into_iter().collect())
+}
+
+/// Default number of consecutive provider failures that open its circuit breaker
+pub const LLM_CIRCUIT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time an open circuit fast-fails before probing the provider again (seconds)
+pub const LLM_CIRCUIT_COOLDOWN_SECS: u64 = 60;
+
+/// Get the number of consecutive failures that open a provider's circuit
+///
+/// Zero disables the circuit breaker.
+///
+/// Environment variable: `LLM_CIRCUIT_FAILURE_THRESHOLD`
+#[must_use]
+pub fn get_llm_circuit_failure_threshold() -> u32 {
+    std::env::var("LLM_CIRCUIT_FAILURE_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(LLM_CIRCUIT_FAILURE_THRESHOLD)
+}
+
+/// Get the circuit breaker cooldown from env or default
+///
+/// Environment variable: `LLM_CIRCUIT_COOLDOWN_SECS`
+#[must_use]
+pub fn get_llm_circuit_cooldown_secs() -> u64 {
+    std::env::var("LLM_CIRCUIT_COOLDOWN_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(LLM_CIRCUIT_COOLDOWN_SECS)
+}
+
+/// Default ffmpeg binary used to convert voice messages before transcription
+pub const DEFAULT_FFMPEG_PATH: &str = "ffmpeg";
+
+/// Get the ffmpeg binary used for audio conversion
+///
+/// Environment variable: `FFMPEG_PATH`
+#[must_use]
+pub fn get_ffmpeg_path() -> String {
+    std::env::var("FFMPEG_PATH")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_FFMPEG_PATH.to_string())
+}
+
+/// Get the Prometheus metrics listen address.
+/// Environment variable: `METRICS_ADDR` (e.g. `0.0.0.0:9090`)
+/// Metrics are disabled when unset or when built without the `metrics` feature.
+#[must_use]
+pub fn get_metrics_addr() -> Option<String> {
+    std::env::var("METRICS_ADDR")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// Get the path of the JSONL tool and feedback audit log; auditing is off when unset.
+///
+/// Environment variable: `AUDIT_LOG_PATH`
+#[must_use]
+pub fn get_audit_log_path() -> Option<String> {
+    std::env::var("AUDIT_LOG_PATH")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
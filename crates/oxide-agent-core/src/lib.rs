@@ -9,6 +9,10 @@ pub mod agent;
 pub mod config;
 /// LLM providers and client.
 pub mod llm;
+/// Prometheus metrics (no-op without the `metrics` feature).
+pub mod metrics;
+/// Secret redaction for logs and audit records.
+pub mod redaction;
 /// Docker sandboxing for code execution.
 pub mod sandbox;
 /// Storage layer (R2/S3).
@@ -0,0 +1,206 @@
+//! Audio format detection and conversion for transcription
+//!
+//! Telegram voice messages are OGG/Opus, while some providers only accept a
+//! few container formats. Audio is converted with ffmpeg when the provider
+//! cannot take the original encoding; if ffmpeg is missing or fails, the
+//! original bytes are passed through with a warning.
+
+use crate::config::get_ffmpeg_path;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+/// Audio bytes together with the format they are actually encoded in
+pub struct PreparedAudio {
+    /// Encoded audio
+    pub bytes: Vec<u8>,
+    /// Short format name (`wav`, `mp3`, `ogg`, ...)
+    pub format: &'static str,
+}
+
+impl PreparedAudio {
+    /// MIME type matching [`Self::format`]
+    #[must_use]
+    pub fn mime_type(&self) -> &'static str {
+        mime_for_format(self.format)
+    }
+}
+
+/// Append a language hint to a transcription prompt (`None` keeps autodetect).
+#[must_use]
+pub fn transcription_prompt(base: &str, language: Option<&str>) -> String {
+    match language.map(str::trim).filter(|l| !l.is_empty()) {
+        Some(language) => format!(
+            "{base} The speech is in the following language: {language}. \
+            Transcribe it in that language without translating."
+        ),
+        None => base.to_string(),
+    }
+}
+
+/// Detect the audio container from magic bytes, falling back to the MIME type.
+#[must_use]
+pub fn detect_audio_format(bytes: &[u8], mime_type: &str) -> &'static str {
+    if bytes.starts_with(b"OggS") {
+        "ogg"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+        "wav"
+    } else if bytes.starts_with(b"ID3") || bytes.starts_with(&[0xFF, 0xFB]) {
+        "mp3"
+    } else if bytes.starts_with(b"fLaC") {
+        "flac"
+    } else if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+        "m4a"
+    } else if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        "webm"
+    } else {
+        format_for_mime(mime_type)
+    }
+}
+
+fn format_for_mime(mime_type: &str) -> &'static str {
+    let mime = mime_type.split(';').next().unwrap_or_default().trim();
+    match mime.to_ascii_lowercase().as_str() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/flac" | "audio/x-flac" => "flac",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" | "audio/aac" => "m4a",
+        "audio/webm" => "webm",
+        _ => "ogg",
+    }
+}
+
+fn mime_for_format(format: &str) -> &'static str {
+    match format {
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "m4a" => "audio/mp4",
+        "webm" => "audio/webm",
+        _ => "audio/ogg",
+    }
+}
+
+/// Make sure `bytes` are in one of the `accepted` formats, converting to
+/// `target` with ffmpeg when they are not.
+///
+/// Conversion failures are logged and the original audio is returned as-is.
+pub async fn prepare_audio(
+    bytes: Vec<u8>,
+    mime_type: &str,
+    accepted: &[&str],
+    target: &'static str,
+) -> PreparedAudio {
+    let format = detect_audio_format(&bytes, mime_type);
+    if accepted.contains(&format) {
+        return PreparedAudio { bytes, format };
+    }
+
+    match convert_audio(&bytes, target).await {
+        Ok(converted) => {
+            debug!(
+                from = format,
+                to = target,
+                size = converted.len(),
+                "Converted audio for transcription"
+            );
+            PreparedAudio {
+                bytes: converted,
+                format: target,
+            }
+        }
+        Err(e) => {
+            warn!(
+                from = format,
+                to = target,
+                error = %e,
+                "Audio conversion unavailable, sending original encoding"
+            );
+            PreparedAudio { bytes, format }
+        }
+    }
+}
+
+/// Convert audio to `target` format by piping it through ffmpeg.
+///
+/// # Errors
+///
+/// Returns an error if ffmpeg cannot be started or exits unsuccessfully.
+pub async fn convert_audio(bytes: &[u8], target: &str) -> std::io::Result<Vec<u8>> {
+    let mut child = tokio::process::Command::new(get_ffmpeg_path())
+        .args(["-hide_banner", "-loglevel", "error", "-i", "pipe:0"])
+        .args(["-f", target, "pipe:1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| std::io::Error::other("ffmpeg stdin unavailable"))?;
+    let input = bytes.to_vec();
+    // Feed stdin concurrently so a full stdout pipe cannot deadlock ffmpeg
+    let writer = tokio::spawn(async move {
+        let result = stdin.write_all(&input).await;
+        drop(stdin);
+        result
+    });
+
+    let output = child.wait_with_output().await?;
+    match writer.await.map_err(std::io::Error::other)? {
+        // ffmpeg may close stdin early once it has read enough
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+        _ => {}
+    }
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(std::io::Error::other(format!(
+            "ffmpeg exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_audio_format, prepare_audio, transcription_prompt};
+
+    #[test]
+    fn detects_format_from_magic_bytes() {
+        assert_eq!(detect_audio_format(b"OggS\0\x02", "audio/wav"), "ogg");
+        assert_eq!(
+            detect_audio_format(b"RIFF\0\0\0\0WAVEfmt ", "audio/ogg"),
+            "wav"
+        );
+        assert_eq!(
+            detect_audio_format(b"ID3\x04", "application/octet-stream"),
+            "mp3"
+        );
+        assert_eq!(detect_audio_format(b"", "audio/mpeg; codecs=mp3"), "mp3");
+        assert_eq!(detect_audio_format(b"", "application/octet-stream"), "ogg");
+    }
+
+    #[test]
+    fn language_hint_is_optional() {
+        assert_eq!(transcription_prompt("Transcribe.", None), "Transcribe.");
+        assert_eq!(
+            transcription_prompt("Transcribe.", Some("  ")),
+            "Transcribe."
+        );
+        assert!(transcription_prompt("Transcribe.", Some("ru")).contains("language: ru."));
+    }
+
+    #[tokio::test]
+    async fn accepted_format_is_passed_through() {
+        let wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        let prepared = prepare_audio(wav.clone(), "audio/wav", &["wav", "mp3"], "wav").await;
+        assert_eq!(prepared.format, "wav");
+        assert_eq!(prepared.mime_type(), "audio/wav");
+        assert_eq!(prepared.bytes, wav);
+    }
+}
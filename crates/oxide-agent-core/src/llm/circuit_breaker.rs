@@ -0,0 +1,281 @@
+//! Per-provider circuit breaker
+//!
+//! After a number of consecutive outage errors the provider's circuit opens
+//! and requests fail fast for a cooldown. Once the cooldown has passed a
+//! single probe request is let through (half-open): any answer from the
+//! provider closes the circuit, an outage error opens it for another cooldown.
+
+use super::http_utils::http_status;
+use super::LlmError;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open probe was let through, while it is in flight
+    probe_started: Option<Instant>,
+}
+
+/// Tracks provider health and fast-fails providers that are down
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    states: Mutex<HashMap<String, CircuitState>>,
+}
+
+impl CircuitBreaker {
+    /// Create a circuit breaker. A `failure_threshold` of zero disables it.
+    #[must_use]
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a circuit breaker configured from the environment
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::new(
+            crate::config::get_llm_circuit_failure_threshold(),
+            Duration::from_secs(crate::config::get_llm_circuit_cooldown_secs()),
+        )
+    }
+
+    /// Whether an error indicates the provider itself is unavailable.
+    ///
+    /// Only connection failures and 500/502/503/504 responses count; rate
+    /// limits and request errors (bad input, auth) do not.
+    #[must_use]
+    pub fn is_outage_error(error: &LlmError) -> bool {
+        match error {
+            LlmError::NetworkError(_) => true,
+            LlmError::ApiError(msg) => {
+                matches!(http_status(msg), Some(500 | 502 | 503 | 504))
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether a request to `provider` may be sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LlmError::CircuitOpen` while the provider's circuit is open.
+    pub fn check(&self, provider: &str) -> Result<(), LlmError> {
+        self.check_at(provider, Instant::now())
+    }
+
+    /// Record the outcome of a request to `provider`
+    pub fn record<T>(&self, provider: &str, result: &Result<T, LlmError>) {
+        match result {
+            Ok(_) => self.record_success(provider),
+            Err(e) if Self::is_outage_error(e) => self.record_failure_at(provider, Instant::now()),
+            // The provider answered, so a half-open probe proves it is back
+            Err(_) => self.record_answer(provider),
+        }
+    }
+
+    fn check_at(&self, provider: &str, now: Instant) -> Result<(), LlmError> {
+        if self.failure_threshold == 0 {
+            return Ok(());
+        }
+
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(state) = states.get_mut(provider) else {
+            return Ok(());
+        };
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+
+        // A probe is in flight: fail fast until it reports back, or until a
+        // cooldown has passed in case it never does
+        let since = state.probe_started.unwrap_or(opened_at);
+        let elapsed = now.saturating_duration_since(since);
+        if elapsed >= self.cooldown {
+            // Half-open: let this request probe, keep fast-failing the rest
+            info!(provider, "Circuit half-open, probing provider");
+            state.probe_started = Some(now);
+            return Ok(());
+        }
+
+        Err(LlmError::CircuitOpen {
+            provider: provider.to_string(),
+            retry_in_secs: (self.cooldown - elapsed).as_secs().max(1),
+        })
+    }
+
+    fn record_success(&self, provider: &str) {
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(state) = states.remove(provider) {
+            if state.opened_at.is_some() {
+                info!(provider, "Circuit closed, provider recovered");
+            }
+        }
+    }
+
+    /// A non-outage error: closes the circuit if it came from the probe
+    fn record_answer(&self, provider: &str) {
+        let probing = self
+            .states
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(provider)
+            .is_some_and(|state| state.probe_started.is_some());
+        if probing {
+            self.record_success(provider);
+        }
+    }
+
+    fn record_failure_at(&self, provider: &str, now: Instant) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut states = self.states.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = states.entry(provider.to_string()).or_default();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+
+        if state.consecutive_failures >= self.failure_threshold {
+            if state.opened_at.is_none() {
+                warn!(
+                    provider,
+                    failures = state.consecutive_failures,
+                    cooldown_secs = self.cooldown.as_secs(),
+                    "Circuit opened, provider fast-fails until cooldown"
+                );
+            }
+            state.opened_at = Some(now);
+            state.probe_started = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outage() -> Result<(), LlmError> {
+        Err(LlmError::ApiError(
+            "Zai API error: 503 Service Unavailable - down".to_string(),
+        ))
+    }
+
+    #[test]
+    fn opens_after_threshold_and_half_opens_after_cooldown() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        let start = Instant::now();
+
+        for _ in 0..2 {
+            breaker.record_failure_at("zai", start);
+        }
+        assert!(breaker.check_at("zai", start).is_ok());
+
+        breaker.record_failure_at("zai", start);
+        match breaker.check_at("zai", start + Duration::from_secs(10)) {
+            Err(LlmError::CircuitOpen {
+                provider,
+                retry_in_secs,
+            }) => {
+                assert_eq!(provider, "zai");
+                assert_eq!(retry_in_secs, 50);
+            }
+            other => panic!("expected open circuit, got {other:?}"),
+        }
+        assert!(breaker.check_at("mistral", start).is_ok());
+
+        // One probe passes after the cooldown, concurrent requests still fail fast
+        let probe_time = start + Duration::from_secs(61);
+        assert!(breaker.check_at("zai", probe_time).is_ok());
+        assert!(breaker.check_at("zai", probe_time).is_err());
+
+        breaker.record("zai", &Ok::<(), LlmError>(()));
+        assert!(breaker.check_at("zai", probe_time).is_ok());
+    }
+
+    #[test]
+    fn failed_probe_reopens_circuit() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let start = Instant::now();
+
+        breaker.record_failure_at("groq", start);
+        let probe_time = start + Duration::from_secs(30);
+        assert!(breaker.check_at("groq", probe_time).is_ok());
+
+        breaker.record_failure_at("groq", probe_time);
+        assert!(breaker
+            .check_at("groq", probe_time + Duration::from_secs(29))
+            .is_err());
+    }
+
+    #[test]
+    fn probe_answered_with_request_error_closes_circuit() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let start = Instant::now();
+
+        breaker.record_failure_at("groq", start);
+        let probe_time = start + Duration::from_secs(30);
+        assert!(breaker.check_at("groq", probe_time).is_ok());
+        assert!(breaker.check_at("groq", probe_time).is_err());
+
+        breaker.record(
+            "groq",
+            &Err::<(), _>(LlmError::ApiError(
+                "Groq API error: 400 Bad Request - bad input".to_string(),
+            )),
+        );
+        assert!(breaker.check_at("groq", probe_time).is_ok());
+    }
+
+    #[test]
+    fn lost_probe_is_retried_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let start = Instant::now();
+
+        breaker.record_failure_at("zai", start);
+        let probe_time = start + Duration::from_secs(30);
+        assert!(breaker.check_at("zai", probe_time).is_ok());
+        assert!(breaker
+            .check_at("zai", probe_time + Duration::from_secs(29))
+            .is_err());
+        assert!(breaker
+            .check_at("zai", probe_time + Duration::from_secs(30))
+            .is_ok());
+    }
+
+    #[test]
+    fn outages_are_detected_by_status() {
+        assert!(CircuitBreaker::is_outage_error(&LlmError::ApiError(
+            "Mistral API error: 502 Bad Gateway - upstream".to_string()
+        )));
+        assert!(!CircuitBreaker::is_outage_error(&LlmError::ApiError(
+            "Mistral API error: 400 Bad Request - max_tokens=5000".to_string()
+        )));
+    }
+
+    #[test]
+    fn ignores_non_outage_errors_and_can_be_disabled() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        breaker.record(
+            "openrouter",
+            &Err::<(), _>(LlmError::RateLimit {
+                wait_secs: Some(5),
+                message: "slow down".to_string(),
+            }),
+        );
+        assert!(breaker.check("openrouter").is_ok());
+
+        let disabled = CircuitBreaker::new(0, Duration::from_secs(30));
+        for _ in 0..10 {
+            disabled.record("openrouter", &outage());
+        }
+        assert!(disabled.check("openrouter").is_ok());
+    }
+}
@@ -67,3 +67,35 @@ pub fn extract_openai_response(
         .and_then(|c| c.message.content.clone())
         .ok_or_else(|| LlmError::ApiError("Empty response".to_string()))
 }
+
+/// Parse a JSON-mode reply, tolerating a surrounding markdown code fence
+///
+/// # Errors
+///
+/// Returns the parse error when the (unfenced) text is not valid JSON.
+pub fn parse_json_output(text: &str) -> Result<serde_json::Value, serde_json::Error> {
+    let trimmed = text.trim();
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map_or(trimmed, |inner| {
+            inner
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .trim()
+        });
+    serde_json::from_str(unfenced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_output() {
+        assert!(parse_json_output(r#" {"a": 1} "#).is_ok());
+        assert!(parse_json_output("```json\n{\"a\": [1, 2]}\n```").is_ok());
+        assert!(parse_json_output("```\n[]\n```").is_ok());
+        assert!(parse_json_output("Sure! {\"a\": 1}").is_err());
+        assert!(parse_json_output("{\"a\": 1").is_err());
+    }
+}
@@ -48,14 +48,10 @@ impl EmbeddingProvider {
             .json(&body)
             .send()
             .await
-            .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+            .map_err(|e| http_utils::map_send_error(&e))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(LlmError::ApiError(format!(
-                "Embedding API error: {status} - {error_text}"
-            )));
+            return Err(http_utils::error_from_response(response, "Embedding API error").await);
         }
 
         let parsed: EmbeddingResponse = response
@@ -3,11 +3,16 @@
 //! Provides common HTTP request/response handling to eliminate
 //! code duplication across provider implementations.
 
-use crate::config::get_llm_http_timeout_secs;
+use crate::config::{
+    get_llm_connect_timeout_secs, get_llm_http_timeout_secs, get_llm_referer,
+    get_llm_request_timeout_secs, get_llm_title, get_llm_user_agent, get_provider_extra_headers,
+};
 use crate::llm::LlmError;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Client as HttpClient;
 use serde_json::Value;
 use std::time::Duration;
+use tracing::warn;
 
 /// Creates an HTTP client configured with the standard LLM timeout.
 ///
@@ -15,13 +20,105 @@ use std::time::Duration;
 /// This keeps long-running responses alive while preventing infinite hangs.
 #[must_use]
 pub fn create_http_client() -> HttpClient {
-    let timeout = Duration::from_secs(get_llm_http_timeout_secs());
-    HttpClient::builder()
-        .timeout(timeout)
+    build_http_client(get_llm_http_timeout_secs())
+}
+
+/// Same as [`create_http_client`], also sending `<PROVIDER>_EXTRA_HEADERS`.
+#[must_use]
+pub fn create_provider_http_client(provider: &str) -> HttpClient {
+    build_provider_http_client(get_llm_http_timeout_secs(), provider)
+}
+
+/// Creates an HTTP client for agent/tool-calling requests to `provider`.
+///
+/// Uses `LLM_REQUEST_TIMEOUT_SECS`, which defaults to a longer limit than chat
+/// because agent turns carry large contexts. Sends `<PROVIDER>_EXTRA_HEADERS`.
+#[must_use]
+pub fn create_provider_agent_http_client(provider: &str) -> HttpClient {
+    build_provider_http_client(get_llm_request_timeout_secs(), provider)
+}
+
+fn build_http_client(timeout_secs: u64) -> HttpClient {
+    http_client_builder(timeout_secs)
         .build()
         .unwrap_or_else(|_| HttpClient::new())
 }
 
+fn build_provider_http_client(timeout_secs: u64, provider: &str) -> HttpClient {
+    http_client_builder(timeout_secs)
+        .default_headers(provider_extra_headers(provider))
+        .build()
+        .unwrap_or_else(|_| HttpClient::new())
+}
+
+fn http_client_builder(timeout_secs: u64) -> reqwest::ClientBuilder {
+    identified_client_builder()
+        .connect_timeout(Duration::from_secs(get_llm_connect_timeout_secs()))
+        .timeout(Duration::from_secs(timeout_secs))
+}
+
+/// Client builder that identifies the bot to providers
+///
+/// Sets `User-Agent` (`LLM_USER_AGENT`) and the `HTTP-Referer` / `X-Title`
+/// attribution headers (`LLM_REFERER`, `LLM_TITLE`). Headers set on a request
+/// or through `<PROVIDER>_EXTRA_HEADERS` take precedence.
+pub fn identified_client_builder() -> reqwest::ClientBuilder {
+    let mut headers = HeaderMap::new();
+    for (name, value) in [
+        ("HTTP-Referer", get_llm_referer()),
+        ("X-Title", get_llm_title()),
+    ] {
+        match HeaderValue::from_str(&value) {
+            Ok(value) => {
+                headers.insert(name, value);
+            }
+            Err(_) => warn!(
+                header = name,
+                "Invalid attribution header value, skipping it"
+            ),
+        }
+    }
+    HttpClient::builder()
+        .user_agent(get_llm_user_agent())
+        .default_headers(headers)
+}
+
+/// Configured extra headers of `provider`; invalid names or values are skipped
+/// with a warning.
+#[must_use]
+pub fn provider_extra_headers(provider: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in get_provider_extra_headers(provider) {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            (Ok(name), Ok(mut value)) => {
+                value.set_sensitive(crate::redaction::is_secret_header(name.as_str()));
+                headers.insert(name, value);
+            }
+            _ => warn!(provider, header = %name, "Invalid extra header, skipping it"),
+        }
+    }
+    headers
+}
+
+/// Converts a `reqwest` send error into an `LlmError`, spelling out timeouts.
+#[must_use]
+pub fn map_send_error(error: &reqwest::Error) -> LlmError {
+    if error.is_connect() && error.is_timeout() {
+        LlmError::NetworkError(format!(
+            "Connection timed out (LLM_CONNECT_TIMEOUT_SECS): {error}"
+        ))
+    } else if error.is_timeout() {
+        LlmError::NetworkError(format!(
+            "Request timed out waiting for the model (LLM_REQUEST_TIMEOUT_SECS / LLM_HTTP_TIMEOUT_SECS): {error}"
+        ))
+    } else {
+        LlmError::NetworkError(error.to_string())
+    }
+}
+
 /// Sends an HTTP POST request with JSON body and returns parsed JSON response.
 ///
 /// This function handles:
@@ -60,51 +157,85 @@ pub async fn send_json_request(
         request = request.header(*key, *value);
     }
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+    let response = request.send().await.map_err(|e| map_send_error(&e))?;
 
     if !response.status().is_success() {
-        let status = response.status();
-
-        // Handle 429 Too Many Requests specifically
-        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            let wait_secs = parse_retry_after(response.headers());
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(LlmError::RateLimit {
-                wait_secs,
-                message: error_text,
-            });
-        }
+        return Err(error_from_response(response, "API error").await);
+    }
 
+    response
+        .json()
+        .await
+        .map_err(|e| LlmError::JsonError(e.to_string()))
+}
+
+/// Converts a non-success HTTP response into an `LlmError`.
+///
+/// 429 responses become `LlmError::RateLimit` with the server-provided wait
+/// time (from `Retry-After` or, failing that, a hint in the error body), so
+/// the retry loop can back off exactly as long as requested.
+/// Other failures become `LlmError::ApiError` prefixed with `label`.
+pub async fn error_from_response(response: reqwest::Response, label: &str) -> LlmError {
+    let status = response.status();
+
+    // Handle 429 Too Many Requests specifically
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let header_wait = parse_retry_after(response.headers());
         let error_text = response.text().await.unwrap_or_default();
+        return LlmError::RateLimit {
+            wait_secs: header_wait.or_else(|| parse_retry_hint(&error_text)),
+            message: error_text,
+        };
+    }
+
+    let error_text = response.text().await.unwrap_or_default();
 
-        // Detect HTML error pages from Nginx/proxies
-        let is_html = error_text.trim_start().starts_with("<!DOCTYPE")
-            || error_text.trim_start().starts_with("<html")
-            || error_text.trim_start().starts_with("<HTML");
+    // Detect HTML error pages from Nginx/proxies
+    let is_html = error_text.trim_start().starts_with("<!DOCTYPE")
+        || error_text.trim_start().starts_with("<html")
+        || error_text.trim_start().starts_with("<HTML");
 
-        let clean_message = if is_html {
-            // Don't include raw HTML in error message
-            format!("API error: {status} (Server returned HTML error page)")
+    let clean_message = if is_html {
+        // Don't include raw HTML in error message
+        format!("{label}: {status} (Server returned HTML error page)")
+    } else {
+        // Truncate very long error messages
+        let truncated = if error_text.chars().count() > 500 {
+            format!(
+                "{}... (truncated)",
+                crate::utils::truncate_str(&error_text, 500)
+            )
         } else {
-            // Truncate very long error messages
-            let truncated = if error_text.len() > 500 {
-                format!("{}... (truncated)", &error_text[..500])
-            } else {
-                error_text
-            };
-            format!("API error: {status} - {truncated}")
+            error_text
         };
+        format!("{label}: {status} - {truncated}")
+    };
 
-        return Err(LlmError::ApiError(clean_message));
+    LlmError::ApiError(clean_message)
+}
+
+/// HTTP status of an `LlmError::ApiError` message, if it carries one.
+///
+/// Understands the `{label}: {status} ...` form built by
+/// [`error_from_response`], a bare `{status} {reason}` and the ZAI SDK's
+/// `HTTP error [{status}]: ...`. Only the prefix is inspected, so numbers in
+/// the error body never count.
+#[must_use]
+pub fn http_status(message: &str) -> Option<u16> {
+    fn parse_code(code: &str) -> Option<u16> {
+        if code.len() != 3 {
+            return None;
+        }
+        code.parse().ok()
     }
 
-    response
-        .json()
-        .await
-        .map_err(|e| LlmError::JsonError(e.to_string()))
+    if let Some(rest) = message.strip_prefix("HTTP error [") {
+        return parse_code(rest.split_once(']')?.0);
+    }
+    parse_code(message.split(' ').next()?).or_else(|| {
+        let (_, rest) = message.split_once(": ")?;
+        parse_code(rest.split(' ').next()?)
+    })
 }
 
 /// Extracts text content from a JSON response by navigating a path.
@@ -145,17 +276,196 @@ pub fn extract_text_content(response: &Value, path: &[&str]) -> Result<String, L
         .ok_or_else(|| LlmError::ApiError(format!("Expected string at path, got: {current:?}")))
 }
 
+/// Fetches an OpenAI-style model list (`GET {base}/models`) and returns its ids.
+///
+/// # Errors
+///
+/// Returns `LlmError::NetworkError` on connectivity issues, `LlmError::ApiError`
+/// on non-success status codes or an unexpected body, or `LlmError::JsonError`
+/// if the body is not JSON.
+pub async fn fetch_model_ids(
+    client: &HttpClient,
+    url: &str,
+    api_key: &str,
+    label: &str,
+) -> Result<Vec<String>, LlmError> {
+    let response = client
+        .get(url)
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .map_err(|e| map_send_error(&e))?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response, label).await);
+    }
+
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| LlmError::JsonError(e.to_string()))?;
+    parse_model_ids(&body)
+}
+
+/// Check that `url` answers at all, for providers without a models endpoint.
+///
+/// Any HTTP status counts, so the request needs no credentials and is never
+/// billed.
+///
+/// # Errors
+///
+/// Returns `LlmError::NetworkError` if the host cannot be reached.
+pub async fn check_reachable(client: &HttpClient, url: &str) -> Result<(), LlmError> {
+    client
+        .head(url)
+        .send()
+        .await
+        .map_err(|e| map_send_error(&e))?;
+    Ok(())
+}
+
+/// Sorted model ids from an OpenAI-style `{"data": [{"id": ...}]}` list.
+///
+/// Entries without an id are skipped, so extra provider fields never break
+/// the listing.
+///
+/// # Errors
+///
+/// Returns `LlmError::ApiError` if the body has no `data` array.
+pub fn parse_model_ids(response: &Value) -> Result<Vec<String>, LlmError> {
+    let data = response
+        .get("data")
+        .and_then(Value::as_array)
+        .ok_or_else(|| LlmError::ApiError("Model list has no data array".to_string()))?;
+    let mut ids: Vec<String> = data
+        .iter()
+        .filter_map(|model| model.get("id").and_then(Value::as_str))
+        .map(ToString::to_string)
+        .collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
 /// Helper to parse Retry-After header
 /// Returns number of seconds to wait if present and valid
 pub fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<u64> {
-    if let Some(header_val) = headers.get(reqwest::header::RETRY_AFTER) {
-        if let Ok(val_str) = header_val.to_str() {
-            // Try parsing as seconds
-            if let Ok(secs) = val_str.parse::<u64>() {
-                return Some(secs);
-            }
-            // Could implement HTTP Date parsing here if needed, but seconds is most common for APIs
-        }
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after_value(value, chrono::Utc::now())
+}
+
+/// Parse a `Retry-After` value given either as seconds or as an HTTP date.
+fn parse_retry_after_value(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    if let Some(secs) = parse_fractional_secs(value) {
+        return Some(secs);
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&chrono::Utc) - now;
+    Some(u64::try_from(delta.num_seconds()).unwrap_or(0))
+}
+
+/// Extract a wait hint from a rate-limit error body.
+///
+/// Recognizes Gemini's `"retryDelay": "30s"` and OpenAI/Groq-style
+/// "Please try again in 7.66s" / "try again in 1m30s" messages.
+#[must_use]
+pub fn parse_retry_hint(body: &str) -> Option<u64> {
+    if let Some((_, secs)) = lazy_regex::regex_captures!(r#""retryDelay"\s*:\s*"([0-9.]+)s""#, body)
+    {
+        return parse_fractional_secs(secs);
+    }
+
+    let (_, minutes, secs) =
+        lazy_regex::regex_captures!(r"(?i)try again in (?:([0-9]+)m)?([0-9.]+)s", body)?;
+    let minutes: u64 = minutes.parse().unwrap_or(0);
+    Some(minutes * 60 + parse_fractional_secs(secs)?)
+}
+
+/// Parse a non-negative decimal number of seconds, rounding up.
+fn parse_fractional_secs(value: &str) -> Option<u64> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let whole: u64 = whole.parse().ok()?;
+    let has_fraction = fraction.chars().any(|c| c != '0');
+    if !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(whole + u64::from(has_fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{http_status, parse_model_ids, parse_retry_after_value, parse_retry_hint};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_http_status_reads_prefix_only() {
+        assert_eq!(
+            http_status("Mistral API error: 503 Service Unavailable - busy"),
+            Some(503)
+        );
+        assert_eq!(http_status("HTTP error [502]: Bad Gateway"), Some(502));
+        assert_eq!(http_status("500 Internal Server Error"), Some(500));
+        assert_eq!(
+            http_status("Gemini API error: 400 Bad Request - max_tokens=5000 exceeds 500"),
+            Some(400)
+        );
+        assert_eq!(http_status("upstream said 500"), None);
+        assert_eq!(http_status("Empty response"), None);
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        let now = match chrono::Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 0) {
+            chrono::LocalResult::Single(now) => now,
+            _ => panic!("invalid test date"),
+        };
+        assert_eq!(parse_retry_after_value("120", now), Some(120));
+        assert_eq!(parse_retry_after_value("1.5", now), Some(2));
+        assert_eq!(
+            parse_retry_after_value("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(60)
+        );
+        assert_eq!(
+            parse_retry_after_value("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(0)
+        );
+        assert_eq!(parse_retry_after_value("soon", now), None);
+    }
+
+    #[test]
+    fn parses_retry_hints_from_bodies() {
+        assert_eq!(
+            parse_retry_hint(r#"{"error":{"details":[{"retryDelay": "30s"}]}}"#),
+            Some(30)
+        );
+        assert_eq!(
+            parse_retry_hint("Rate limit reached. Please try again in 7.66s."),
+            Some(8)
+        );
+        assert_eq!(parse_retry_hint("Please try again in 1m30s"), Some(90));
+        assert_eq!(parse_retry_hint("Too many requests"), None);
+    }
+
+    #[test]
+    fn parses_model_ids_from_lists() {
+        let body = serde_json::json!({
+            "object": "list",
+            "data": [
+                {"id": "llama-3.3-70b-versatile", "owned_by": "Meta"},
+                {"id": "deepseek/deepseek-chat", "context_length": 163_840},
+                {"object": "model"},
+                {"id": "deepseek/deepseek-chat"}
+            ]
+        });
+        let Ok(ids) = parse_model_ids(&body) else {
+            panic!("model list should parse");
+        };
+        assert_eq!(ids, ["deepseek/deepseek-chat", "llama-3.3-70b-versatile"]);
+
+        assert!(parse_model_ids(&serde_json::json!({"error": "unauthorized"})).is_err());
     }
-    None
 }
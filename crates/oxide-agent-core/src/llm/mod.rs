@@ -2,6 +2,8 @@
 //!
 //! Provides a unified interface to various LLM providers (Groq, Mistral, Gemini, OpenRouter).
 
+pub mod audio;
+mod circuit_breaker;
 mod common;
 pub mod embeddings;
 mod http_utils;
@@ -39,6 +41,14 @@ pub enum LlmError {
         /// Error message from the server
         message: String,
     },
+    /// Provider temporarily shed by the circuit breaker after repeated outages
+    #[error("Provider {provider} unavailable (circuit open, retry in {retry_in_secs}s)")]
+    CircuitOpen {
+        /// Provider whose circuit is open
+        provider: String,
+        /// Seconds until the circuit lets a probe request through
+        retry_in_secs: u64,
+    },
     /// Any other unexpected error
     #[error("Unknown error: {0}")]
     Unknown(String),
@@ -184,6 +194,22 @@ pub struct ChatResponse {
 }
 
 /// Interface for all LLM providers
+///
+/// `stop` carries the model's configured stop sequences. Providers send them
+/// where the API supports it and ignore them otherwise: OpenAI-compatible APIs
+/// (Groq, Mistral, OpenRouter) get `stop` with at most four sequences, Gemini
+/// gets `stopSequences`, and ZAI only honors the first sequence.
+///
+/// The `LLM_SEED` setting is handed to each provider by [`LlmClient::new`] and
+/// sent where the API has a seed: `seed` on Groq and OpenRouter, `random_seed`
+/// on Mistral, `generationConfig.seed` on Gemini. The ZAI SDK has no seed
+/// field. Reproducibility stays best-effort and provider-dependent, especially
+/// with streaming and tool calling.
+///
+/// `json_mode` asks for a single JSON object: `response_format: json_object`
+/// on OpenAI-compatible APIs and `responseMimeType: application/json` on
+/// Gemini. Providers without a JSON mode ignore it; callers must still
+/// validate the output.
 #[cfg_attr(test, mockall::automock)]
 #[async_trait::async_trait]
 #[allow(clippy::too_many_arguments)]
@@ -196,13 +222,18 @@ pub trait LlmProvider: Send + Sync {
         user_message: &str,
         model_id: &str,
         max_tokens: u32,
+        json_mode: bool,
+        stop: &[String],
     ) -> Result<String, LlmError>;
 
     /// Transcribe audio content
+    ///
+    /// `language` is an optional hint (e.g. `ru` or `Russian`); `None` means autodetect.
     async fn transcribe_audio(
         &self,
         audio_bytes: Vec<u8>,
         mime_type: &str,
+        language: Option<String>,
         model_id: &str,
     ) -> Result<String, LlmError>;
 
@@ -227,13 +258,37 @@ pub trait LlmProvider: Send + Sync {
         _model_id: &str,
         _max_tokens: u32,
         _json_mode: bool,
+        _stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         Err(LlmError::Unknown(
             "Tool calling not supported by this provider".to_string(),
         ))
     }
+
+    /// List the model ids the provider currently serves (optional)
+    ///
+    /// Default implementation returns an empty list. Providers with a models
+    /// endpoint (Groq, Mistral, Gemini, `OpenRouter`) override this method.
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        Ok(Vec::new())
+    }
+
+    /// Check that the API answers without a billed request (optional)
+    ///
+    /// Used for providers without a models endpoint. Default implementation
+    /// returns `Ok(false)`: the provider cannot be checked.
+    async fn check_reachable(&self) -> Result<bool, LlmError> {
+        Ok(false)
+    }
 }
 
+/// Upper bound for one provider's reachability check in [`LlmClient::ping_providers`]
+const PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Follow-up sent when a JSON-mode reply does not parse
+const JSON_REPAIR_PROMPT: &str = "Your previous reply is not valid JSON. \
+Return only the corrected JSON value, without markdown or any text around it.";
+
 /// Unified client for interacting with multiple LLM providers
 pub struct LlmClient {
     groq: Option<providers::GroqProvider>,
@@ -243,6 +298,7 @@ pub struct LlmClient {
     openrouter: Option<providers::OpenRouterProvider>,
     embedding: Option<(embeddings::EmbeddingProvider, String)>,
     custom_providers: HashMap<String, Arc<dyn LlmProvider>>,
+    circuit_breaker: circuit_breaker::CircuitBreaker,
     /// Available models configured from settings
     pub models: Vec<(String, crate::config::ModelInfo)>,
     /// Narrator model ID
@@ -294,11 +350,11 @@ impl LlmClient {
             groq: settings
                 .groq_api_key
                 .as_ref()
-                .map(|k| providers::GroqProvider::new(k.clone())),
+                .map(|k| providers::GroqProvider::new(k.clone()).with_seed(settings.llm_seed)),
             mistral: settings
                 .mistral_api_key
                 .as_ref()
-                .map(|k| providers::MistralProvider::new(k.clone())),
+                .map(|k| providers::MistralProvider::new(k.clone()).with_seed(settings.llm_seed)),
             zai: settings
                 .zai_api_key
                 .as_ref()
@@ -306,13 +362,18 @@ impl LlmClient {
             gemini: settings
                 .gemini_api_key
                 .as_ref()
-                .map(|k| providers::GeminiProvider::new(k.clone())),
+                .map(|k| providers::GeminiProvider::new(k.clone()).with_seed(settings.llm_seed)),
             openrouter: settings.openrouter_api_key.as_ref().map(|k| {
                 providers::OpenRouterProvider::new(
                     k.clone(),
                     settings.openrouter_site_url.clone(),
                     settings.openrouter_site_name.clone(),
                 )
+                .with_routing(
+                    settings.openrouter_provider_order(),
+                    settings.openrouter_allow_fallbacks,
+                )
+                .with_seed(settings.llm_seed)
             }),
             embedding: Self::create_embedding_provider(settings),
             models: settings.get_available_models(),
@@ -323,6 +384,7 @@ impl LlmClient {
             media_model_id,
             media_model_provider,
             custom_providers: HashMap::new(),
+            circuit_breaker: circuit_breaker::CircuitBreaker::from_env(),
         }
     }
 
@@ -343,6 +405,12 @@ impl LlmClient {
         self.embedding.is_some()
     }
 
+    /// Model used for embeddings, if an embedding provider is configured.
+    #[must_use]
+    pub fn embedding_model_id(&self) -> Option<&str> {
+        self.embedding.as_ref().map(|(_, model)| model.as_str())
+    }
+
     /// Returns true if requested provider is configured.
     #[must_use]
     pub fn is_provider_available(&self, name: &str) -> bool {
@@ -364,6 +432,67 @@ impl LlmClient {
         false
     }
 
+    /// Names of the configured providers, built-in ones first
+    #[must_use]
+    pub fn configured_providers(&self) -> Vec<String> {
+        let mut names: Vec<String> = ["groq", "mistral", "zai", "gemini", "openrouter"]
+            .into_iter()
+            .filter(|name| self.is_provider_available(name))
+            .map(ToString::to_string)
+            .collect();
+        let mut custom: Vec<String> = self
+            .custom_providers
+            .keys()
+            .filter(|name| !names.contains(name))
+            .cloned()
+            .collect();
+        custom.sort();
+        names.extend(custom);
+        names
+    }
+
+    /// Name of the first configured provider that answers a cheap request
+    ///
+    /// A reachability check for readiness probes that never bills: providers
+    /// are asked for their model list, and those without a models endpoint
+    /// for [`LlmProvider::check_reachable`]. Providers that cannot be checked
+    /// either way are skipped. Each check is bounded by [`PING_TIMEOUT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `LlmError::MissingConfig` if no provider can be checked, or the
+    /// last provider error if none is reachable.
+    pub async fn ping_providers(&self) -> Result<String, LlmError> {
+        let mut last_error = LlmError::MissingConfig("no LLM provider configured".to_string());
+        for name in self.configured_providers() {
+            let result = match tokio::time::timeout(PING_TIMEOUT, self.ping_provider(&name)).await {
+                Ok(result) => result,
+                Err(_) => Err(LlmError::NetworkError(format!(
+                    "no answer within {}s",
+                    PING_TIMEOUT.as_secs()
+                ))),
+            };
+            match result {
+                Ok(true) => return Ok(name),
+                Ok(false) => {}
+                Err(e) => {
+                    warn!(provider = %name, error = %e, "LLM provider is unreachable");
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sends one unbilled request to a provider; `Ok(false)` if it cannot be checked.
+    async fn ping_provider(&self, provider_name: &str) -> Result<bool, LlmError> {
+        let provider = self.get_provider(provider_name)?;
+        if !provider.list_models().await?.is_empty() {
+            return Ok(true);
+        }
+        provider.check_reachable().await
+    }
+
     /// Returns the provider for the given name
     ///
     /// # Errors
@@ -384,6 +513,20 @@ impl LlmClient {
         .ok_or_else(|| LlmError::MissingConfig(provider_name.to_string()))
     }
 
+    /// Model ids served by a configured provider, fetched from its API
+    ///
+    /// Useful for checking `*_MODEL_ID` settings against what the provider
+    /// actually offers. An empty list means the provider has no models
+    /// endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LlmError::MissingConfig` if the provider is not configured, or
+    /// any error from the provider's models endpoint.
+    pub async fn list_remote_models(&self, provider_name: &str) -> Result<Vec<String>, LlmError> {
+        self.get_provider(provider_name)?.list_models().await
+    }
+
     /// Perform a chat completion request
     ///
     /// # Errors
@@ -396,6 +539,59 @@ impl LlmClient {
         history: &[Message],
         user_message: &str,
         model_name: &str,
+    ) -> Result<String, LlmError> {
+        self.complete(system_prompt, history, user_message, model_name, false)
+            .await
+    }
+
+    /// Perform a chat completion that must return a JSON value
+    ///
+    /// Requests the provider's JSON mode, then parses the reply (tolerating a
+    /// markdown code fence). If it is not valid JSON, the model is asked once
+    /// to repair its answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LlmError::JsonError` if the repaired reply still is not valid JSON,
+    /// or any error from [`Self::chat_completion`].
+    #[instrument(skip(self, system_prompt, history))]
+    pub async fn chat_completion_json(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_message: &str,
+        model_name: &str,
+    ) -> Result<serde_json::Value, LlmError> {
+        let response = self
+            .complete(system_prompt, history, user_message, model_name, true)
+            .await?;
+        let error = match common::parse_json_output(&response) {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+
+        warn!(model = model_name, error = %error, "LLM returned invalid JSON, asking for a repair");
+        let mut repair_history = history.to_vec();
+        if !user_message.is_empty() {
+            repair_history.push(Message::user(user_message));
+        }
+        repair_history.push(Message::assistant(&response));
+        let nudge = format!("{JSON_REPAIR_PROMPT}\nParse error: {error}");
+        let repaired = self
+            .complete(system_prompt, &repair_history, &nudge, model_name, true)
+            .await?;
+        common::parse_json_output(&repaired).map_err(|e| {
+            LlmError::JsonError(format!("Model returned invalid JSON after repair: {e}"))
+        })
+    }
+
+    async fn complete(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_message: &str,
+        model_name: &str,
+        json_mode: bool,
     ) -> Result<String, LlmError> {
         let model_info = self.get_model_info(model_name)?;
 
@@ -404,6 +600,7 @@ impl LlmClient {
         debug!(
             model = model_name,
             provider = model_info.provider,
+            json_mode,
             "Sending request to LLM"
         );
         trace!(
@@ -413,6 +610,7 @@ impl LlmClient {
             "Full LLM Request"
         );
 
+        self.circuit_breaker.check(&model_info.provider)?;
         let start = std::time::Instant::now();
         let result = provider
             .chat_completion(
@@ -421,9 +619,12 @@ impl LlmClient {
                 user_message,
                 &model_info.id,
                 model_info.max_tokens,
+                json_mode,
+                &model_info.stop,
             )
             .await;
         let duration = start.elapsed();
+        self.circuit_breaker.record(&model_info.provider, &result);
 
         if let Ok(resp) = &result {
             debug!(
@@ -448,11 +649,13 @@ impl LlmClient {
     ///
     /// This method includes retry logic with exponential backoff for transient errors
     /// (5xx status codes and network errors). Up to 5 attempts will be made with
-    /// increasing delays: 1s, 2s, 4s, 8s, 16s.
+    /// increasing delays: 1s, 2s, 4s, 8s, 16s. Outage errors feed the provider's
+    /// circuit breaker; once it opens, requests fail fast until the cooldown ends.
     ///
     /// # Errors
     ///
     /// Returns `LlmError::Unknown` if the model is not found, if tool calling is not supported for the provider,
+    /// `LlmError::CircuitOpen` if the provider's circuit is open,
     /// or any error from the provider after all retry attempts are exhausted.
     #[instrument(skip(self, system_prompt, messages, tools))]
     pub async fn chat_with_tools(
@@ -481,6 +684,8 @@ impl LlmClient {
         );
 
         for attempt in 1..=MAX_RETRIES {
+            // An open circuit fails fast; callers decide whether to wait it out
+            self.circuit_breaker.check(&model_info.provider)?;
             let start = std::time::Instant::now();
             let result = provider
                 .chat_with_tools(
@@ -490,9 +695,12 @@ impl LlmClient {
                     &model_info.id,
                     model_info.max_tokens,
                     json_mode,
+                    &model_info.stop,
                 )
                 .await;
             let duration = start.elapsed();
+            crate::metrics::record_llm_request(&model_info.provider, duration, result.is_ok());
+            self.circuit_breaker.record(&model_info.provider, &result);
 
             match result {
                 Ok(resp) => {
@@ -535,6 +743,7 @@ impl LlmClient {
                                 error_type = ?e,
                                 "Retrying LLM request"
                             );
+                            crate::metrics::record_llm_retry(&model_info.provider);
                             tokio::time::sleep(backoff).await;
                             continue;
                         }
@@ -553,6 +762,9 @@ impl LlmClient {
 
     /// Calculates the delay before the next retry attempt based on the error type.
     /// Returns `None` if the error is not retryable.
+    ///
+    /// A server-provided wait time is used as is; computed backoffs get jitter
+    /// so that concurrent requests failing together do not retry in lockstep.
     fn get_retry_delay(error: &LlmError, attempt: usize) -> Option<std::time::Duration> {
         const INITIAL_BACKOFF_MS: u64 = 1000;
 
@@ -565,36 +777,49 @@ impl LlmClient {
                 // Otherwise use a more aggressive backoff for rate limits: 10s, 20s, 40s...
                 // attempt starts at 1
                 let backoff_secs = 10u64 * 2u64.pow((attempt - 1) as u32);
-                Some(std::time::Duration::from_secs(backoff_secs))
+                Some(Self::with_jitter(std::time::Duration::from_secs(
+                    backoff_secs,
+                )))
             }
             LlmError::ApiError(msg) => {
                 let msg_lower = msg.to_lowercase();
-                if msg_lower.contains("429") {
+                let status = http_utils::http_status(msg);
+                if status == Some(429) {
                     // Treat as rate limit without explicit wait time
                     let backoff_secs = 10u64 * 2u64.pow((attempt - 1) as u32);
-                    return Some(std::time::Duration::from_secs(backoff_secs));
+                    return Some(Self::with_jitter(std::time::Duration::from_secs(
+                        backoff_secs,
+                    )));
                 }
 
-                if msg_lower.contains("500")
-                    || msg_lower.contains("502")
-                    || msg_lower.contains("503")
-                    || msg_lower.contains("504")
+                if matches!(status, Some(500 | 502 | 503 | 504))
                     || msg_lower.contains("timeout")
                     || msg_lower.contains("overloaded")
                 {
                     let backoff_ms = INITIAL_BACKOFF_MS * 2u64.pow((attempt - 1) as u32);
-                    return Some(std::time::Duration::from_millis(backoff_ms));
+                    return Some(Self::with_jitter(std::time::Duration::from_millis(
+                        backoff_ms,
+                    )));
                 }
                 None
             }
             LlmError::NetworkError(_) => {
                 let backoff_ms = INITIAL_BACKOFF_MS * 2u64.pow((attempt - 1) as u32);
-                Some(std::time::Duration::from_millis(backoff_ms))
+                Some(Self::with_jitter(std::time::Duration::from_millis(
+                    backoff_ms,
+                )))
             }
             _ => None,
         }
     }
 
+    /// "Equal jitter": keep half of the backoff and randomize the other half,
+    /// so the delay stays within `[backoff / 2, backoff)` and never collapses to zero.
+    fn with_jitter(backoff: std::time::Duration) -> std::time::Duration {
+        let half = backoff / 2;
+        half + tokio_retry::strategy::jitter(half)
+    }
+
     /// Generate an embedding vector using configured provider.
     ///
     /// # Errors
@@ -625,13 +850,22 @@ impl LlmClient {
         &self,
         audio_bytes: Vec<u8>,
         mime_type: &str,
+        language: Option<&str>,
         model_name: &str,
     ) -> Result<String, LlmError> {
         let model_info = self.get_model_info(model_name)?;
         let provider = self.get_provider(&model_info.provider)?;
-        provider
-            .transcribe_audio(audio_bytes, mime_type, &model_info.id)
-            .await
+        self.circuit_breaker.check(&model_info.provider)?;
+        let result = provider
+            .transcribe_audio(
+                audio_bytes,
+                mime_type,
+                language.map(str::to_string),
+                &model_info.id,
+            )
+            .await;
+        self.circuit_breaker.record(&model_info.provider, &result);
+        result
     }
 
     /// Transcribe audio with automatic fallback for text-only providers
@@ -645,11 +879,17 @@ impl LlmClient {
         provider_name: &str,
         audio_bytes: Vec<u8>,
         mime_type: &str,
+        language: Option<&str>,
         model_id: &str,
     ) -> Result<String, LlmError> {
         let provider = self.get_provider(provider_name)?;
         match provider
-            .transcribe_audio(audio_bytes.clone(), mime_type, model_id)
+            .transcribe_audio(
+                audio_bytes.clone(),
+                mime_type,
+                language.map(str::to_string),
+                model_id,
+            )
             .await
         {
             Ok(text) => Ok(text),
@@ -666,7 +906,12 @@ impl LlmClient {
                 info!("ZAI does not support audio, falling back to media model {media_model_id}");
                 let provider = self.get_provider(media_provider)?;
                 provider
-                    .transcribe_audio(audio_bytes, mime_type, media_model_id)
+                    .transcribe_audio(
+                        audio_bytes,
+                        mime_type,
+                        language.map(str::to_string),
+                        media_model_id,
+                    )
                     .await
             }
             Err(e) => Err(e),
@@ -687,9 +932,12 @@ impl LlmClient {
     ) -> Result<String, LlmError> {
         let model_info = self.get_model_info(model_name)?;
         let provider = self.get_provider(&model_info.provider)?;
-        provider
+        self.circuit_breaker.check(&model_info.provider)?;
+        let result = provider
             .analyze_image(image_bytes, text_prompt, system_prompt, &model_info.id)
-            .await
+            .await;
+        self.circuit_breaker.record(&model_info.provider, &result);
+        result
     }
 
     /// Returns the model info for the given name
@@ -705,3 +953,139 @@ impl LlmClient {
             .ok_or_else(|| LlmError::Unknown(format!("Model {model_name} not found")))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ping_providers_skips_unreachable_ones() {
+        let mut llm = LlmClient::new(&crate::config::AgentSettings::default());
+        assert!(matches!(
+            llm.ping_providers().await,
+            Err(LlmError::MissingConfig(_))
+        ));
+
+        let mut down = MockLlmProvider::new();
+        down.expect_list_models()
+            .returning(|| Err(LlmError::NetworkError("connection refused".to_string())));
+        let mut up = MockLlmProvider::new();
+        up.expect_list_models()
+            .returning(|| Ok(vec!["test-model".to_string()]));
+        llm.register_provider("a-down".to_string(), Arc::new(down));
+        llm.register_provider("b-up".to_string(), Arc::new(up));
+
+        assert_eq!(llm.configured_providers(), ["a-down", "b-up"]);
+        assert!(matches!(llm.ping_providers().await, Ok(name) if name == "b-up"));
+    }
+
+    #[tokio::test]
+    async fn test_ping_providers_without_models_endpoint() {
+        let mut llm = LlmClient::new(&crate::config::AgentSettings::default());
+
+        let mut unchecked = MockLlmProvider::new();
+        unchecked.expect_list_models().returning(|| Ok(Vec::new()));
+        unchecked.expect_check_reachable().returning(|| Ok(false));
+        unchecked.expect_chat_completion().never();
+        llm.register_provider("a-unchecked".to_string(), Arc::new(unchecked));
+        assert!(matches!(
+            llm.ping_providers().await,
+            Err(LlmError::MissingConfig(_))
+        ));
+
+        // Reachability is checked without a billed completion
+        let mut reachable = MockLlmProvider::new();
+        reachable.expect_list_models().returning(|| Ok(Vec::new()));
+        reachable
+            .expect_check_reachable()
+            .times(1)
+            .returning(|| Ok(true));
+        reachable.expect_chat_completion().never();
+        llm.register_provider("b-reachable".to_string(), Arc::new(reachable));
+        assert!(matches!(llm.ping_providers().await, Ok(name) if name == "b-reachable"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ping_providers_times_out_hung_provider() {
+        struct Hung;
+
+        #[async_trait::async_trait]
+        impl LlmProvider for Hung {
+            async fn chat_completion(
+                &self,
+                _system_prompt: &str,
+                _history: &[Message],
+                _user_message: &str,
+                _model_id: &str,
+                _max_tokens: u32,
+                _json_mode: bool,
+                _stop: &[String],
+            ) -> Result<String, LlmError> {
+                Ok(String::new())
+            }
+
+            async fn transcribe_audio(
+                &self,
+                _audio_bytes: Vec<u8>,
+                _mime_type: &str,
+                _language: Option<String>,
+                _model_id: &str,
+            ) -> Result<String, LlmError> {
+                Ok(String::new())
+            }
+
+            async fn analyze_image(
+                &self,
+                _image_bytes: Vec<u8>,
+                _text_prompt: &str,
+                _system_prompt: &str,
+                _model_id: &str,
+            ) -> Result<String, LlmError> {
+                Ok(String::new())
+            }
+
+            async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+                std::future::pending().await
+            }
+        }
+
+        let mut llm = LlmClient::new(&crate::config::AgentSettings::default());
+        llm.register_provider("hung".to_string(), Arc::new(Hung));
+        assert!(matches!(
+            llm.ping_providers().await,
+            Err(LlmError::NetworkError(_))
+        ));
+    }
+
+    #[test]
+    fn test_retry_delay_is_jittered() {
+        let error = LlmError::NetworkError("connection reset".to_string());
+        let delays: Vec<_> = (0..8)
+            .filter_map(|_| LlmClient::get_retry_delay(&error, 2))
+            .collect();
+        assert_eq!(delays.len(), 8);
+        assert!(delays
+            .iter()
+            .all(|d| d.as_millis() >= 1000 && d.as_millis() < 2000));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+
+        let server_wait = LlmError::RateLimit {
+            wait_secs: Some(5),
+            message: String::new(),
+        };
+        assert_eq!(
+            LlmClient::get_retry_delay(&server_wait, 1),
+            Some(std::time::Duration::from_secs(6))
+        );
+
+        let circuit_open = LlmError::CircuitOpen {
+            provider: "zai".to_string(),
+            retry_in_secs: 12,
+        };
+        assert_eq!(LlmClient::get_retry_delay(&circuit_open, 1), None);
+
+        let body_mentions_500 =
+            LlmError::ApiError("Mistral API error: 400 Bad Request - max_tokens=5000".to_string());
+        assert_eq!(LlmClient::get_retry_delay(&body_mentions_500, 1), None);
+    }
+}
@@ -1,16 +1,68 @@
 //! OpenAI-compatible provider utilities
 //!
-//! Shared implementation for providers using the async-openai client
-//! (Groq, Mistral, Zai).
+//! Shared implementation for providers using the async-openai client (Groq),
+//! plus request-body helpers for the JSON-based ones (Mistral, OpenRouter).
 
 use super::common::{build_openai_messages, extract_openai_response};
+use super::http_utils::{
+    identified_client_builder, map_send_error, parse_retry_hint, provider_extra_headers,
+};
 use super::{LlmError, Message};
-use async_openai::{config::OpenAIConfig, types::chat::CreateChatCompletionRequestArgs, Client};
+use async_openai::error::OpenAIError;
+use async_openai::types::chat::{
+    CreateChatCompletionRequest, CreateChatCompletionRequestArgs, ResponseFormat, StopConfiguration,
+};
+use async_openai::{config::OpenAIConfig, Client};
+
+/// async-openai client for `config` that identifies the bot and sends
+/// `<PROVIDER>_EXTRA_HEADERS`
+#[must_use]
+pub fn create_client(config: OpenAIConfig, provider: &str) -> Client<OpenAIConfig> {
+    let client = Client::with_config(config);
+    match identified_client_builder()
+        .default_headers(provider_extra_headers(provider))
+        .build()
+    {
+        Ok(http_client) => client.with_http_client(http_client),
+        Err(e) => {
+            tracing::warn!(provider, error = %e, "Failed to build HTTP client, using the default one");
+            client
+        }
+    }
+}
+
+/// Maximum number of stop sequences OpenAI-compatible APIs accept
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Stop sequences trimmed to the API limit, or `None` when there are none
+#[must_use]
+pub fn stop_sequences(stop: &[String]) -> Option<Vec<String>> {
+    if stop.is_empty() {
+        return None;
+    }
+    Some(stop.iter().take(MAX_STOP_SEQUENCES).cloned().collect())
+}
+
+/// Add `stop` to a JSON request body when the model has stop sequences
+pub fn apply_stop(body: &mut serde_json::Value, stop: &[String]) {
+    if let Some(stop) = stop_sequences(stop) {
+        body["stop"] = serde_json::json!(stop);
+    }
+}
+
+/// Add `seed` to a JSON request body under `key` (`seed` for
+/// OpenAI-compatible APIs, `random_seed` for Mistral)
+pub fn apply_seed(body: &mut serde_json::Value, key: &str, seed: Option<u64>) {
+    if let Some(seed) = seed {
+        body[key] = serde_json::json!(seed);
+    }
+}
 
 /// Perform a chat completion using an OpenAI-compatible API
 ///
-/// This is a shared implementation for Groq, Mistral, and Zai providers
-/// which all use the same async-openai client with different base URLs.
+/// Used by the Groq provider through the async-openai client.
+/// `seed` is sent as OpenAI's `seed` field; pass `None` for APIs that reject it.
+#[allow(clippy::too_many_arguments)]
 pub async fn chat_completion(
     client: &Client<OpenAIConfig>,
     system_prompt: &str,
@@ -19,22 +71,123 @@ pub async fn chat_completion(
     model_id: &str,
     max_tokens: u32,
     temperature: f32,
+    json_mode: bool,
+    stop: &[String],
+    seed: Option<u64>,
 ) -> Result<String, LlmError> {
-    let messages = build_openai_messages(system_prompt, history, user_message)?;
-
-    let request = CreateChatCompletionRequestArgs::default()
-        .model(model_id)
-        .messages(messages)
-        .max_tokens(max_tokens)
-        .temperature(temperature)
-        .build()
-        .map_err(|e| LlmError::Unknown(e.to_string()))?;
+    let request = build_request(
+        system_prompt,
+        history,
+        user_message,
+        model_id,
+        max_tokens,
+        temperature,
+        json_mode,
+        stop,
+        seed,
+    )?;
 
     let response = client
         .chat()
         .create(request)
         .await
-        .map_err(|e| LlmError::ApiError(e.to_string()))?;
+        .map_err(map_openai_error)?;
 
     extract_openai_response(&response)
 }
+
+/// Build the request sent by [`chat_completion`]
+#[allow(clippy::too_many_arguments)]
+fn build_request(
+    system_prompt: &str,
+    history: &[Message],
+    user_message: &str,
+    model_id: &str,
+    max_tokens: u32,
+    temperature: f32,
+    json_mode: bool,
+    stop: &[String],
+    seed: Option<u64>,
+) -> Result<CreateChatCompletionRequest, LlmError> {
+    let messages = build_openai_messages(system_prompt, history, user_message)?;
+
+    let mut args = CreateChatCompletionRequestArgs::default();
+    args.model(model_id)
+        .messages(messages)
+        .max_tokens(max_tokens)
+        .temperature(temperature);
+    if json_mode {
+        args.response_format(ResponseFormat::JsonObject);
+    }
+    if let Some(stop) = stop_sequences(stop) {
+        args.stop(StopConfiguration::StringArray(stop));
+    }
+    if let Some(seed) = seed.and_then(|seed| i64::try_from(seed).ok()) {
+        args.seed(seed);
+    }
+    args.build().map_err(|e| LlmError::Unknown(e.to_string()))
+}
+
+/// Map an async-openai error, turning rate limits into `LlmError::RateLimit`
+///
+/// The client does not expose response headers, so the wait time comes from
+/// the error message (e.g. Groq's "Please try again in 7.66s").
+fn map_openai_error(error: OpenAIError) -> LlmError {
+    match error {
+        OpenAIError::ApiError(api_error) => {
+            let is_rate_limit = [api_error.code.as_deref(), api_error.r#type.as_deref()]
+                .into_iter()
+                .flatten()
+                .any(|value| value.contains("rate_limit"))
+                || api_error.message.to_lowercase().contains("rate limit");
+            if is_rate_limit {
+                LlmError::RateLimit {
+                    wait_secs: parse_retry_hint(&api_error.message),
+                    message: api_error.message,
+                }
+            } else {
+                LlmError::ApiError(api_error.to_string())
+            }
+        }
+        OpenAIError::Reqwest(e) => map_send_error(&e),
+        other => LlmError::ApiError(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_seed, build_request};
+    use serde_json::json;
+
+    #[test]
+    fn test_build_request_sends_seed() {
+        let Ok(request) = build_request("sys", &[], "hi", "model", 16, 0.5, false, &[], Some(42))
+        else {
+            panic!("request should build");
+        };
+        let Ok(body) = serde_json::to_value(&request) else {
+            panic!("request should serialize");
+        };
+        assert_eq!(body["seed"], json!(42));
+
+        let Ok(request) = build_request("sys", &[], "hi", "model", 16, 0.5, false, &[], None)
+        else {
+            panic!("request should build");
+        };
+        let Ok(body) = serde_json::to_value(&request) else {
+            panic!("request should serialize");
+        };
+        assert!(body.get("seed").is_none());
+    }
+
+    #[test]
+    fn test_apply_seed_uses_given_key() {
+        let mut body = json!({"model": "m"});
+        apply_seed(&mut body, "random_seed", Some(7));
+        assert_eq!(body["random_seed"], json!(7));
+
+        let mut body = json!({"model": "m"});
+        apply_seed(&mut body, "seed", None);
+        assert!(body.get("seed").is_none());
+    }
+}
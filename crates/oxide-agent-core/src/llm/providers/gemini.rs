@@ -2,17 +2,21 @@ use crate::config::{
     GEMINI_AUDIO_TRANSCRIBE_PROMPT, GEMINI_AUDIO_TRANSCRIBE_TEMPERATURE, GEMINI_CHAT_TEMPERATURE,
     GEMINI_IMAGE_TEMPERATURE,
 };
-use crate::llm::http_utils::{extract_text_content, send_json_request};
+use crate::llm::audio::{prepare_audio, transcription_prompt};
+use crate::llm::http_utils::{
+    error_from_response, extract_text_content, map_send_error, send_json_request,
+};
 use crate::llm::{LlmError, LlmProvider, Message};
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use reqwest::Client as HttpClient;
-use serde_json::json;
+use serde_json::{json, Value};
 
 /// LLM provider implementation for Google Gemini
 pub struct GeminiProvider {
     http_client: HttpClient,
     api_key: String,
+    seed: Option<u64>,
 }
 
 impl GeminiProvider {
@@ -20,27 +24,29 @@ impl GeminiProvider {
     #[must_use]
     pub fn new(api_key: String) -> Self {
         Self {
-            http_client: crate::llm::http_utils::create_http_client(),
+            http_client: crate::llm::http_utils::create_provider_http_client("gemini"),
             api_key,
+            seed: None,
         }
     }
-}
 
-#[async_trait]
-impl LlmProvider for GeminiProvider {
-    async fn chat_completion(
+    /// Send `seed` as `generationConfig.seed` with chat completions
+    #[must_use]
+    pub const fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Request body for a `generateContent` chat completion
+    fn chat_body(
         &self,
         system_prompt: &str,
         history: &[Message],
         user_message: &str,
-        model_id: &str,
         max_tokens: u32,
-    ) -> Result<String, LlmError> {
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{model_id}:generateContent?key={}",
-            self.api_key
-        );
-
+        json_mode: bool,
+        stop: &[String],
+    ) -> Value {
         let mut contents = Vec::new();
         for msg in history {
             if msg.role != "system" {
@@ -56,7 +62,7 @@ impl LlmProvider for GeminiProvider {
             "parts": [{"text": user_message}]
         }));
 
-        let body = json!({
+        let mut body = json!({
             "contents": contents,
             "system_instruction": {
                 "parts": [{"text": system_prompt}]
@@ -72,6 +78,44 @@ impl LlmProvider for GeminiProvider {
                 {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_NONE"}
             ]
         });
+        if json_mode {
+            body["generationConfig"]["responseMimeType"] = json!("application/json");
+        }
+        if !stop.is_empty() {
+            body["generationConfig"]["stopSequences"] = json!(stop);
+        }
+        if let Some(seed) = self.seed {
+            body["generationConfig"]["seed"] = json!(seed);
+        }
+        body
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    async fn chat_completion(
+        &self,
+        system_prompt: &str,
+        history: &[Message],
+        user_message: &str,
+        model_id: &str,
+        max_tokens: u32,
+        json_mode: bool,
+        stop: &[String],
+    ) -> Result<String, LlmError> {
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{model_id}:generateContent?key={}",
+            self.api_key
+        );
+
+        let body = self.chat_body(
+            system_prompt,
+            history,
+            user_message,
+            max_tokens,
+            json_mode,
+            stop,
+        );
 
         let res_json = send_json_request(&self.http_client, &url, &body, None, &[]).await?;
         extract_text_content(
@@ -84,6 +128,7 @@ impl LlmProvider for GeminiProvider {
         &self,
         audio_bytes: Vec<u8>,
         mime_type: &str,
+        language: Option<String>,
         model_id: &str,
     ) -> Result<String, LlmError> {
         let url = format!(
@@ -91,14 +136,22 @@ impl LlmProvider for GeminiProvider {
             self.api_key
         );
 
+        let audio = prepare_audio(
+            audio_bytes,
+            mime_type,
+            &["wav", "mp3", "ogg", "flac", "m4a"],
+            "ogg",
+        )
+        .await;
+
         let body = json!({
             "contents": [{
                 "parts": [
-                    {"text": GEMINI_AUDIO_TRANSCRIBE_PROMPT},
+                    {"text": transcription_prompt(GEMINI_AUDIO_TRANSCRIBE_PROMPT, language.as_deref())},
                     {
                         "inline_data": {
-                            "mime_type": mime_type,
-                            "data": BASE64.encode(&audio_bytes)
+                            "mime_type": audio.mime_type(),
+                            "data": BASE64.encode(&audio.bytes)
                         }
                     }
                 ]
@@ -154,4 +207,73 @@ impl LlmProvider for GeminiProvider {
             &["candidates", "0", "content", "parts", "0", "text"],
         )
     }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000&key={}",
+            self.api_key
+        );
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| map_send_error(&e))?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response, "Gemini API error").await);
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| LlmError::JsonError(e.to_string()))?;
+        parse_gemini_model_ids(&body)
+    }
+}
+
+/// Sorted model ids from a Gemini `{"models": [{"name": "models/..."}]}` list.
+fn parse_gemini_model_ids(response: &Value) -> Result<Vec<String>, LlmError> {
+    let models = response
+        .get("models")
+        .and_then(Value::as_array)
+        .ok_or_else(|| LlmError::ApiError("Model list has no models array".to_string()))?;
+    let mut ids: Vec<String> = models
+        .iter()
+        .filter_map(|model| model.get("name").and_then(Value::as_str))
+        .map(|name| name.strip_prefix("models/").unwrap_or(name).to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_body_sends_seed() {
+        let provider = GeminiProvider::new("key".to_string()).with_seed(Some(42));
+        let body = provider.chat_body("sys", &[], "hi", 16, false, &[]);
+        assert_eq!(body["generationConfig"]["seed"], json!(42));
+
+        let provider = GeminiProvider::new("key".to_string());
+        let body = provider.chat_body("sys", &[], "hi", 16, false, &[]);
+        assert!(body["generationConfig"].get("seed").is_none());
+    }
+
+    #[test]
+    fn test_parse_gemini_model_ids_strips_prefix() {
+        let body = json!({
+            "models": [
+                {"name": "models/gemini-2.5-flash"},
+                {"name": "models/gemini-2.5-pro"},
+                {"displayName": "no name"}
+            ]
+        });
+        assert!(matches!(
+            parse_gemini_model_ids(&body),
+            Ok(ids) if ids == ["gemini-2.5-flash", "gemini-2.5-pro"]
+        ));
+        assert!(parse_gemini_model_ids(&json!({"error": {}})).is_err());
+    }
 }
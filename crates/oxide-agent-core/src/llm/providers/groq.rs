@@ -1,11 +1,17 @@
 use crate::config::GROQ_CHAT_TEMPERATURE;
-use crate::llm::{openai_compat, LlmError, LlmProvider, Message};
+use crate::llm::{http_utils, openai_compat, LlmError, LlmProvider, Message};
 use async_openai::{config::OpenAIConfig, Client};
 use async_trait::async_trait;
+use reqwest::Client as HttpClient;
+
+const GROQ_API_BASE: &str = "https://api.groq.com/openai/v1";
 
 /// LLM provider implementation for Groq
 pub struct GroqProvider {
     client: Client<OpenAIConfig>,
+    http_client: HttpClient,
+    api_key: String,
+    seed: Option<u64>,
 }
 
 impl GroqProvider {
@@ -13,12 +19,22 @@ impl GroqProvider {
     #[must_use]
     pub fn new(api_key: String) -> Self {
         let config = OpenAIConfig::new()
-            .with_api_key(api_key)
-            .with_api_base("https://api.groq.com/openai/v1");
+            .with_api_key(api_key.clone())
+            .with_api_base(GROQ_API_BASE);
         Self {
-            client: Client::with_config(config),
+            client: openai_compat::create_client(config, "groq"),
+            http_client: http_utils::create_provider_http_client("groq"),
+            api_key,
+            seed: None,
         }
     }
+
+    /// Send `seed` with chat completions
+    #[must_use]
+    pub const fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
 }
 
 #[async_trait]
@@ -30,6 +46,8 @@ impl LlmProvider for GroqProvider {
         user_message: &str,
         model_id: &str,
         max_tokens: u32,
+        json_mode: bool,
+        stop: &[String],
     ) -> Result<String, LlmError> {
         openai_compat::chat_completion(
             &self.client,
@@ -39,6 +57,9 @@ impl LlmProvider for GroqProvider {
             model_id,
             max_tokens,
             GROQ_CHAT_TEMPERATURE,
+            json_mode,
+            stop,
+            self.seed,
         )
         .await
     }
@@ -47,6 +68,7 @@ impl LlmProvider for GroqProvider {
         &self,
         _audio_bytes: Vec<u8>,
         _mime_type: &str,
+        _language: Option<String>,
         _model_id: &str,
     ) -> Result<String, LlmError> {
         Err(LlmError::Unknown("Not implemented for Groq".to_string()))
@@ -61,4 +83,14 @@ impl LlmProvider for GroqProvider {
     ) -> Result<String, LlmError> {
         Err(LlmError::Unknown("Not implemented for Groq".to_string()))
     }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        http_utils::fetch_model_ids(
+            &self.http_client,
+            &format!("{GROQ_API_BASE}/models"),
+            &self.api_key,
+            "Groq API error",
+        )
+        .await
+    }
 }
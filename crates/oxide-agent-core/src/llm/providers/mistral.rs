@@ -3,7 +3,6 @@ use crate::llm::{
     http_utils, openai_compat, ChatResponse, LlmError, LlmProvider, Message, TokenUsage,
     ToolDefinition,
 };
-use async_openai::{config::OpenAIConfig, Client};
 use async_trait::async_trait;
 use reqwest::Client as HttpClient;
 use serde_json::json;
@@ -35,25 +34,73 @@ struct LenientResponse {
     usage: Option<MistralUsage>,
 }
 
+const CHAT_COMPLETIONS_URL: &str = "https://api.mistral.ai/v1/chat/completions";
+
 /// LLM provider implementation for Mistral AI
 pub struct MistralProvider {
-    client: Client<OpenAIConfig>,
     http_client: HttpClient,
     api_key: String,
+    seed: Option<u64>,
 }
 
 impl MistralProvider {
     /// Create a new Mistral provider instance
     #[must_use]
     pub fn new(api_key: String) -> Self {
-        let config = OpenAIConfig::new()
-            .with_api_key(api_key.clone())
-            .with_api_base("https://api.mistral.ai/v1");
         Self {
-            client: Client::with_config(config),
-            http_client: http_utils::create_http_client(),
+            http_client: http_utils::create_provider_agent_http_client("mistral"),
             api_key,
+            seed: None,
+        }
+    }
+
+    /// Send `seed` as `random_seed` with chat and tool-calling requests
+    #[must_use]
+    pub const fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Chat completions request body with stop sequences and the seed
+    fn request_body(
+        &self,
+        model_id: &str,
+        messages: Vec<serde_json::Value>,
+        max_tokens: u32,
+        temperature: f32,
+        stop: &[String],
+    ) -> serde_json::Value {
+        let mut body = json!({
+            "model": model_id,
+            "messages": messages,
+            "max_tokens": max_tokens,
+            "temperature": temperature
+        });
+        openai_compat::apply_stop(&mut body, stop);
+        // Mistral calls it `random_seed` and rejects unknown fields
+        openai_compat::apply_seed(&mut body, "random_seed", self.seed);
+        body
+    }
+
+    /// POST `body` to the chat completions endpoint
+    async fn send(&self, body: &serde_json::Value) -> Result<LenientResponse, LlmError> {
+        let response = self
+            .http_client
+            .post(CHAT_COMPLETIONS_URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| http_utils::map_send_error(&e))?;
+
+        if !response.status().is_success() {
+            return Err(http_utils::error_from_response(response, "Mistral API error").await);
         }
+
+        response
+            .json()
+            .await
+            .map_err(|e| LlmError::JsonError(e.to_string()))
     }
 
     fn prepare_structured_messages(
@@ -120,23 +167,40 @@ impl LlmProvider for MistralProvider {
         user_message: &str,
         model_id: &str,
         max_tokens: u32,
+        json_mode: bool,
+        stop: &[String],
     ) -> Result<String, LlmError> {
-        openai_compat::chat_completion(
-            &self.client,
-            system_prompt,
-            history,
-            user_message,
+        let mut messages = Self::prepare_structured_messages(system_prompt, history);
+        messages.push(json!({
+            "role": "user",
+            "content": user_message
+        }));
+
+        let mut body = self.request_body(
             model_id,
+            messages,
             max_tokens,
             MISTRAL_CHAT_TEMPERATURE,
-        )
-        .await
+            stop,
+        );
+        if json_mode {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+
+        let res_json = self.send(&body).await?;
+        res_json
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| LlmError::ApiError("Empty response".to_string()))
     }
 
     async fn transcribe_audio(
         &self,
         _audio_bytes: Vec<u8>,
         _mime_type: &str,
+        _language: Option<String>,
         _model_id: &str,
     ) -> Result<String, LlmError> {
         Err(LlmError::Unknown("Not implemented for Mistral".to_string()))
@@ -166,40 +230,20 @@ impl LlmProvider for MistralProvider {
         model_id: &str,
         max_tokens: u32,
         _json_mode: bool,
+        stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
-        let url = "https://api.mistral.ai/v1/chat/completions";
-
         let messages = Self::prepare_structured_messages(system_prompt, history);
 
-        let body = json!({
-            "model": model_id,
-            "messages": messages,
-            "response_format": { "type": "json_object" },
-            "max_tokens": max_tokens,
-            "temperature": MISTRAL_TOOL_TEMPERATURE
-        });
-
-        let response = self
-            .http_client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| LlmError::NetworkError(e.to_string()))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(LlmError::ApiError(format!(
-                "Mistral API error: {status} - {error_text}"
-            )));
-        }
+        let mut body = self.request_body(
+            model_id,
+            messages,
+            max_tokens,
+            MISTRAL_TOOL_TEMPERATURE,
+            stop,
+        );
+        body["response_format"] = json!({ "type": "json_object" });
 
-        let res_json: LenientResponse = response
-            .json()
-            .await
-            .map_err(|e| LlmError::JsonError(e.to_string()))?;
+        let res_json = self.send(&body).await?;
 
         let choice = res_json
             .choices
@@ -226,4 +270,32 @@ impl LlmProvider for MistralProvider {
             usage,
         })
     }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        http_utils::fetch_model_ids(
+            &self.http_client,
+            "https://api.mistral.ai/v1/models",
+            &self.api_key,
+            "Mistral API error",
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MistralProvider;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_body_sends_random_seed() {
+        let provider = MistralProvider::new("key".to_string()).with_seed(Some(42));
+        let body = provider.request_body("model", vec![], 16, 0.5, &[]);
+        assert_eq!(body["random_seed"], json!(42));
+        assert!(body.get("seed").is_none());
+
+        let provider = MistralProvider::new("key".to_string());
+        let body = provider.request_body("model", vec![], 16, 0.5, &[]);
+        assert!(body.get("random_seed").is_none());
+    }
 }
@@ -4,21 +4,30 @@ use crate::config::{
     OPENROUTER_AUDIO_TRANSCRIBE_PROMPT, OPENROUTER_AUDIO_TRANSCRIBE_TEMPERATURE,
     OPENROUTER_CHAT_TEMPERATURE, OPENROUTER_IMAGE_TEMPERATURE,
 };
+use crate::llm::audio::{prepare_audio, transcription_prompt};
 use crate::llm::http_utils::{extract_text_content, send_json_request};
-use crate::llm::{ChatResponse, LlmError, LlmProvider, Message, TokenUsage, ToolDefinition};
+use crate::llm::openai_compat::{apply_seed, apply_stop};
+use crate::llm::{ChatResponse, LlmError, LlmProvider, Message, ToolDefinition};
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use reqwest::Client as HttpClient;
 use serde_json::json;
 
-use helpers::{prepare_structured_messages, prepare_tools_json};
+use helpers::{
+    parse_tool_response, prepare_structured_messages, prepare_tools_json, provider_preferences,
+};
 
 /// LLM provider implementation for `OpenRouter`
 pub struct OpenRouterProvider {
     http_client: HttpClient,
+    /// Client with the longer agent timeout, used for tool-calling requests
+    agent_http_client: HttpClient,
     api_key: String,
     site_url: String,
     site_name: String,
+    /// Upstream provider routing preferences (`provider` request field)
+    routing: Option<serde_json::Value>,
+    seed: Option<u64>,
 }
 
 impl OpenRouterProvider {
@@ -26,11 +35,49 @@ impl OpenRouterProvider {
     #[must_use]
     pub fn new(api_key: String, site_url: String, site_name: String) -> Self {
         Self {
-            http_client: crate::llm::http_utils::create_http_client(),
+            http_client: crate::llm::http_utils::create_provider_http_client("openrouter"),
+            agent_http_client: crate::llm::http_utils::create_provider_agent_http_client(
+                "openrouter",
+            ),
             api_key,
             site_url,
             site_name,
+            routing: None,
+            seed: None,
+        }
+    }
+
+    /// Pin requests to specific upstream providers.
+    ///
+    /// Nothing is sent unless `order` is non-empty or `allow_fallbacks` is set,
+    /// so `OpenRouter`'s default routing stays in effect otherwise.
+    #[must_use]
+    pub fn with_routing(mut self, order: Vec<String>, allow_fallbacks: Option<bool>) -> Self {
+        self.routing = provider_preferences(&order, allow_fallbacks);
+        self
+    }
+
+    /// Send `seed` with chat and tool-calling requests
+    #[must_use]
+    pub const fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn apply_routing(&self, body: &mut serde_json::Value) {
+        if let Some(routing) = &self.routing {
+            body["provider"] = routing.clone();
+        }
+    }
+
+    /// JSON mode, stop sequences, seed and routing shared by chat requests
+    fn apply_chat_options(&self, body: &mut serde_json::Value, json_mode: bool, stop: &[String]) {
+        if json_mode {
+            body["response_format"] = json!({"type": "json_object"});
         }
+        apply_stop(body, stop);
+        apply_seed(body, "seed", self.seed);
+        self.apply_routing(body);
     }
 }
 
@@ -43,6 +90,8 @@ impl LlmProvider for OpenRouterProvider {
         user_message: &str,
         model_id: &str,
         max_tokens: u32,
+        json_mode: bool,
+        stop: &[String],
     ) -> Result<String, LlmError> {
         let url = "https://openrouter.ai/api/v1/chat/completions";
 
@@ -52,12 +101,13 @@ impl LlmProvider for OpenRouterProvider {
         }
         messages.push(json!({"role": "user", "content": user_message}));
 
-        let body = json!({
+        let mut body = json!({
             "model": model_id,
             "messages": messages,
             "max_tokens": max_tokens,
             "temperature": OPENROUTER_CHAT_TEMPERATURE
         });
+        self.apply_chat_options(&mut body, json_mode, stop);
 
         let mut request = self
             .http_client
@@ -76,14 +126,14 @@ impl LlmProvider for OpenRouterProvider {
             .json(&body)
             .send()
             .await
-            .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+            .map_err(|e| crate::llm::http_utils::map_send_error(&e))?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(LlmError::ApiError(format!(
-                "OpenRouter API error: {status} - {error_text}"
-            )));
+            return Err(crate::llm::http_utils::error_from_response(
+                response,
+                "OpenRouter API error",
+            )
+            .await);
         }
 
         let res_json: serde_json::Value = response
@@ -100,24 +150,30 @@ impl LlmProvider for OpenRouterProvider {
     async fn transcribe_audio(
         &self,
         audio_bytes: Vec<u8>,
-        _mime_type: &str,
+        mime_type: &str,
+        language: Option<String>,
         model_id: &str,
     ) -> Result<String, LlmError> {
         let url = "https://openrouter.ai/api/v1/chat/completions";
-        let audio_base64 = BASE64.encode(&audio_bytes);
+        // `input_audio` only accepts wav and mp3
+        let audio = prepare_audio(audio_bytes, mime_type, &["wav", "mp3"], "wav").await;
+        let audio_base64 = BASE64.encode(&audio.bytes);
 
-        let body = json!({
+        let mut body = json!({
             "model": model_id,
             "messages": [
                 {
                     "role": "user",
                     "content": [
-                        {"type": "text", "text": OPENROUTER_AUDIO_TRANSCRIBE_PROMPT},
+                        {
+                            "type": "text",
+                            "text": transcription_prompt(OPENROUTER_AUDIO_TRANSCRIBE_PROMPT, language.as_deref())
+                        },
                         {
                             "type": "input_audio",
                             "input_audio": {
                                 "data": audio_base64,
-                                "format": "wav"
+                                "format": audio.format
                             }
                         }
                     ]
@@ -126,6 +182,7 @@ impl LlmProvider for OpenRouterProvider {
             "max_tokens": 8000,
             "temperature": OPENROUTER_AUDIO_TRANSCRIBE_TEMPERATURE
         });
+        self.apply_routing(&mut body);
 
         let auth = format!("Bearer {}", self.api_key);
         let res_json = send_json_request(&self.http_client, url, &body, Some(&auth), &[]).await?;
@@ -143,7 +200,7 @@ impl LlmProvider for OpenRouterProvider {
         let image_base64 = BASE64.encode(&image_bytes);
         let data_url = format!("data:image/jpeg;base64,{image_base64}");
 
-        let body = json!({
+        let mut body = json!({
             "model": model_id,
             "messages": [
                 {"role": "system", "content": system_prompt},
@@ -161,6 +218,7 @@ impl LlmProvider for OpenRouterProvider {
             "max_tokens": 4000,
             "temperature": OPENROUTER_IMAGE_TEMPERATURE
         });
+        self.apply_routing(&mut body);
 
         let auth = format!("Bearer {}", self.api_key);
         let res_json = send_json_request(&self.http_client, url, &body, Some(&auth), &[]).await?;
@@ -174,7 +232,8 @@ impl LlmProvider for OpenRouterProvider {
         tools: &[ToolDefinition],
         model_id: &str,
         max_tokens: u32,
-        _json_mode: bool,
+        json_mode: bool,
+        stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         let url = "https://openrouter.ai/api/v1/chat/completions";
 
@@ -191,6 +250,7 @@ impl LlmProvider for OpenRouterProvider {
         if !openai_tools.is_empty() {
             body["tools"] = json!(openai_tools);
         }
+        self.apply_chat_options(&mut body, json_mode, stop);
 
         let mut extra_headers = Vec::new();
         if !self.site_url.is_empty() {
@@ -201,58 +261,48 @@ impl LlmProvider for OpenRouterProvider {
         }
 
         let auth = format!("Bearer {}", self.api_key);
-        let res_json =
-            send_json_request(&self.http_client, url, &body, Some(&auth), &extra_headers).await?;
-
-        let content = res_json
-            .get("choices")
-            .and_then(|choices| choices.get(0))
-            .and_then(|choice| choice.get("message"))
-            .and_then(|message| message.get("content"))
-            .and_then(|value| value.as_str())
-            .map(ToString::to_string);
-
-        let tool_calls_value = res_json
-            .get("choices")
-            .and_then(|choices| choices.get(0))
-            .and_then(|choice| choice.get("message"))
-            .and_then(|message| message.get("tool_calls"));
-
-        let tool_calls = match tool_calls_value {
-            Some(value) if value.is_null() => Vec::new(),
-            Some(value) if value.is_array() => serde_json::from_value(value.clone())
-                .map_err(|e| LlmError::JsonError(e.to_string()))?,
-            Some(_) => {
-                return Err(LlmError::JsonError(
-                    "Invalid tool_calls format from OpenRouter".to_string(),
-                ))
-            }
-            None => Vec::new(),
-        };
-
-        if content.is_none() && tool_calls.is_empty() {
-            return Err(LlmError::ApiError("Empty response".to_string()));
-        }
+        let res_json = send_json_request(
+            &self.agent_http_client,
+            url,
+            &body,
+            Some(&auth),
+            &extra_headers,
+        )
+        .await?;
+
+        parse_tool_response(res_json)
+    }
 
-        let finish_reason = res_json["choices"][0]["finish_reason"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-
-        let usage = res_json.get("usage").and_then(|u| {
-            Some(TokenUsage {
-                prompt_tokens: u.get("prompt_tokens")?.as_u64()? as u32,
-                completion_tokens: u.get("completion_tokens")?.as_u64()? as u32,
-                total_tokens: u.get("total_tokens")?.as_u64()? as u32,
-            })
-        });
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        crate::llm::http_utils::fetch_model_ids(
+            &self.http_client,
+            "https://openrouter.ai/api/v1/models",
+            &self.api_key,
+            "OpenRouter API error",
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpenRouterProvider;
+    use serde_json::json;
+
+    fn provider() -> OpenRouterProvider {
+        OpenRouterProvider::new("key".to_string(), String::new(), String::new())
+    }
 
-        Ok(ChatResponse {
-            content,
-            tool_calls,
-            finish_reason,
-            reasoning_content: None,
-            usage,
-        })
+    #[test]
+    fn test_chat_options_send_seed() {
+        let mut body = json!({"model": "m"});
+        provider()
+            .with_seed(Some(42))
+            .apply_chat_options(&mut body, false, &[]);
+        assert_eq!(body["seed"], json!(42));
+
+        let mut body = json!({"model": "m"});
+        provider().apply_chat_options(&mut body, false, &[]);
+        assert!(body.get("seed").is_none());
     }
 }
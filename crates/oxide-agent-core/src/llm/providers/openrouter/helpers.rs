@@ -1,6 +1,55 @@
-use crate::llm::{Message, ToolDefinition};
+use crate::llm::{
+    ChatResponse, LlmError, Message, TokenUsage, ToolCall, ToolCallFunction, ToolDefinition,
+};
+use serde::Deserialize;
 use serde_json::json;
 
+// Some upstreams routed through OpenRouter omit `type` (and occasionally `id`)
+// on tool calls or send `arguments` as an object, so parse leniently.
+#[derive(Deserialize, Debug)]
+struct LenientFunction {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct LenientToolCall {
+    #[serde(default)]
+    id: Option<String>,
+    function: LenientFunction,
+}
+
+#[derive(Deserialize, Debug)]
+struct LenientMessage {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<LenientToolCall>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LenientChoice {
+    message: LenientMessage,
+    finish_reason: Option<String>,
+}
+
+/// Token usage; some upstream providers omit fields, which then count as 0
+#[derive(Deserialize, Debug)]
+struct OpenRouterUsage {
+    #[serde(rename = "prompt_tokens", default)]
+    prompt: u32,
+    #[serde(rename = "completion_tokens", default)]
+    completion: u32,
+    #[serde(rename = "total_tokens", default)]
+    total: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LenientResponse {
+    choices: Vec<LenientChoice>,
+    usage: Option<OpenRouterUsage>,
+}
+
 pub(super) fn prepare_structured_messages(
     system_prompt: &str,
     history: &[Message],
@@ -79,3 +128,152 @@ pub(super) fn prepare_tools_json(tools: &[ToolDefinition]) -> Vec<serde_json::Va
         })
         .collect()
 }
+
+/// Parse a chat completion with optional tool calls into a [`ChatResponse`].
+pub(super) fn parse_tool_response(res_json: serde_json::Value) -> Result<ChatResponse, LlmError> {
+    let response: LenientResponse =
+        serde_json::from_value(res_json).map_err(|e| LlmError::JsonError(e.to_string()))?;
+
+    let choice = response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| LlmError::ApiError("Empty response".to_string()))?;
+
+    let tool_calls: Vec<ToolCall> = choice
+        .message
+        .tool_calls
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, call)| ToolCall {
+            id: call.id.unwrap_or_else(|| format!("call_{index}")),
+            function: ToolCallFunction {
+                name: call.function.name,
+                arguments: match call.function.arguments {
+                    serde_json::Value::String(arguments) => arguments,
+                    serde_json::Value::Null => "{}".to_string(),
+                    other => other.to_string(),
+                },
+            },
+            is_recovered: false,
+        })
+        .collect();
+
+    let content = choice.message.content;
+    if content.is_none() && tool_calls.is_empty() {
+        return Err(LlmError::ApiError("Empty response".to_string()));
+    }
+
+    Ok(ChatResponse {
+        content,
+        tool_calls,
+        finish_reason: choice
+            .finish_reason
+            .unwrap_or_else(|| "unknown".to_string()),
+        reasoning_content: None,
+        usage: response.usage.map(|u| TokenUsage {
+            prompt_tokens: u.prompt,
+            completion_tokens: u.completion,
+            total_tokens: u.total.unwrap_or(u.prompt + u.completion),
+        }),
+    })
+}
+
+/// Build the `provider` routing object, or `None` when nothing is configured.
+pub(super) fn provider_preferences(
+    order: &[String],
+    allow_fallbacks: Option<bool>,
+) -> Option<serde_json::Value> {
+    let mut preferences = serde_json::Map::new();
+    if !order.is_empty() {
+        preferences.insert("order".to_string(), json!(order));
+    }
+    if let Some(allow) = allow_fallbacks {
+        preferences.insert("allow_fallbacks".to_string(), json!(allow));
+    }
+    (!preferences.is_empty()).then_some(serde_json::Value::Object(preferences))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_tool_response, provider_preferences};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_tool_response_without_type() {
+        let response = parse_tool_response(json!({
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [
+                        {"id": "call_a", "function": {"name": "search", "arguments": "{\"q\":\"rust\"}"}},
+                        {"function": {"name": "read", "arguments": {"path": "a.txt"}}}
+                    ]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        }));
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => panic!("failed to parse response: {e}"),
+        };
+
+        assert_eq!(response.tool_calls.len(), 2);
+        assert_eq!(response.tool_calls[0].id, "call_a");
+        assert_eq!(response.tool_calls[0].function.arguments, r#"{"q":"rust"}"#);
+        assert_eq!(response.tool_calls[1].id, "call_1");
+        assert_eq!(
+            response.tool_calls[1].function.arguments,
+            r#"{"path":"a.txt"}"#
+        );
+        assert_eq!(response.finish_reason, "tool_calls");
+        assert_eq!(response.usage.map(|u| u.total_tokens), Some(15));
+    }
+
+    #[test]
+    fn test_parse_tool_response_with_partial_usage() {
+        let response = parse_tool_response(json!({
+            "choices": [{
+                "message": {"content": "done"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5}
+        }));
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => panic!("failed to parse response: {e}"),
+        };
+
+        assert_eq!(response.content.as_deref(), Some("done"));
+        let Some(usage) = response.usage else {
+            panic!("usage missing");
+        };
+        assert_eq!(usage.prompt_tokens, 10);
+        assert_eq!(usage.completion_tokens, 5);
+        assert_eq!(usage.total_tokens, 15);
+
+        let response = parse_tool_response(json!({
+            "choices": [{"message": {"content": "done"}}],
+            "usage": {"total_tokens": 7}
+        }));
+        assert!(matches!(response, Ok(r) if r.usage.as_ref().is_some_and(|u| u.total_tokens == 7)));
+    }
+
+    #[test]
+    fn test_provider_preferences() {
+        assert!(provider_preferences(&[], None).is_none());
+        assert_eq!(
+            provider_preferences(
+                &["anthropic".to_string(), "openai".to_string()],
+                Some(false)
+            ),
+            Some(json!({"order": ["anthropic", "openai"], "allow_fallbacks": false}))
+        );
+        assert_eq!(
+            provider_preferences(&[], Some(true)),
+            Some(json!({"allow_fallbacks": true}))
+        );
+    }
+}
@@ -2,7 +2,7 @@ mod sdk;
 
 use crate::llm::{ChatResponse, LlmError, LlmProvider, Message, ToolDefinition};
 use async_trait::async_trait;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// LLM provider implementation for Zai (Zhipu AI)
 pub struct ZaiProvider {
@@ -12,8 +12,15 @@ pub struct ZaiProvider {
 
 impl ZaiProvider {
     /// Create a new Zai provider instance
+    ///
+    /// `ZAI_EXTRA_HEADERS` and the `LLM_USER_AGENT` / `LLM_REFERER` /
+    /// `LLM_TITLE` identification are not applied: the Z.AI SDK builds its
+    /// own requests and has no way to add headers.
     #[must_use]
     pub fn new(api_key: String, api_base: String) -> Self {
+        if !crate::config::get_provider_extra_headers("zai").is_empty() {
+            warn!("ZAI_EXTRA_HEADERS is set, but the Z.AI SDK cannot send custom headers; ignoring it");
+        }
         Self { api_key, api_base }
     }
 }
@@ -27,20 +34,30 @@ impl LlmProvider for ZaiProvider {
         user_message: &str,
         model_id: &str,
         max_tokens: u32,
+        _json_mode: bool,
+        stop: &[String],
     ) -> Result<String, LlmError> {
         debug!(
             "ZAI: Starting chat completion request (model: {model_id}, max_tokens: {max_tokens}, history_size: {})",
             history.len()
         );
 
-        self.chat_completion_sdk(system_prompt, history, user_message, model_id, max_tokens)
-            .await
+        self.chat_completion_sdk(
+            system_prompt,
+            history,
+            user_message,
+            model_id,
+            max_tokens,
+            stop,
+        )
+        .await
     }
 
     async fn transcribe_audio(
         &self,
         _audio_bytes: Vec<u8>,
         _mime_type: &str,
+        _language: Option<String>,
         _model_id: &str,
     ) -> Result<String, LlmError> {
         Err(LlmError::Unknown("ZAI_FALLBACK_TO_GEMINI".to_string()))
@@ -72,6 +89,7 @@ impl LlmProvider for ZaiProvider {
         model_id: &str,
         max_tokens: u32,
         json_mode: bool,
+        stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         debug!(
             "ZAI: *** CHAT_WITH_TOOLS ENTRY *** model={model_id} tools_count={} history_size={} json_mode={}",
@@ -86,7 +104,14 @@ impl LlmProvider for ZaiProvider {
             history.len()
         );
 
-        self.chat_with_tools_sdk(system_prompt, history, tools, model_id, max_tokens)
+        self.chat_with_tools_sdk(system_prompt, history, tools, model_id, max_tokens, stop)
             .await
     }
+
+    /// Z.AI has no models endpoint; checks that its API host answers instead.
+    async fn check_reachable(&self) -> Result<bool, LlmError> {
+        let client = crate::llm::http_utils::create_http_client();
+        crate::llm::http_utils::check_reachable(&client, &self.api_base).await?;
+        Ok(true)
+    }
 }
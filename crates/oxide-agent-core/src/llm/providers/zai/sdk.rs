@@ -35,15 +35,30 @@ impl ZaiProvider {
         user_message: &str,
         model_id: &str,
         max_tokens: u32,
+        stop: &[String],
     ) -> Result<String, LlmError> {
         let response = match select_model(model_id)? {
             ZaiModel::Main(model) => {
-                self.text_chat_completion(model, system_prompt, history, user_message, max_tokens)
-                    .await?
+                self.text_chat_completion(
+                    model,
+                    system_prompt,
+                    history,
+                    user_message,
+                    max_tokens,
+                    stop,
+                )
+                .await?
             }
             ZaiModel::Sub(model) => {
-                self.text_chat_completion(model, system_prompt, history, user_message, max_tokens)
-                    .await?
+                self.text_chat_completion(
+                    model,
+                    system_prompt,
+                    history,
+                    user_message,
+                    max_tokens,
+                    stop,
+                )
+                .await?
             }
             ZaiModel::Vision(model) => {
                 let messages =
@@ -55,7 +70,10 @@ impl ZaiProvider {
                     &self.api_base,
                     max_tokens,
                 )?;
-                client.send().await.map_err(map_zai_error)?
+                with_first_stop(client, stop)
+                    .send()
+                    .await
+                    .map_err(map_zai_error)?
             }
         };
 
@@ -95,14 +113,17 @@ impl ZaiProvider {
         tools: &[ToolDefinition],
         model_id: &str,
         max_tokens: u32,
+        stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         let messages = convert_to_text_messages(system_prompt, history, None);
         let converted_tools = convert_tools(tools);
 
         match select_model(model_id)? {
             ZaiModel::Main(model) => {
-                let mut client =
-                    build_text_request(model, messages, &self.api_key, &self.api_base, max_tokens)?;
+                let mut client = with_first_stop(
+                    build_text_request(model, messages, &self.api_key, &self.api_base, max_tokens)?,
+                    stop,
+                );
                 if !converted_tools.is_empty() {
                     client = client.add_tools(converted_tools);
                 }
@@ -110,8 +131,10 @@ impl ZaiProvider {
                 stream_text_response(client).await
             }
             ZaiModel::Sub(model) => {
-                let mut client =
-                    build_text_request(model, messages, &self.api_key, &self.api_base, max_tokens)?;
+                let mut client = with_first_stop(
+                    build_text_request(model, messages, &self.api_key, &self.api_base, max_tokens)?,
+                    stop,
+                );
                 if !converted_tools.is_empty() {
                     client = client.add_tools(converted_tools);
                 }
@@ -131,6 +154,7 @@ impl ZaiProvider {
         history: &[Message],
         user_message: &str,
         max_tokens: u32,
+        stop: &[String],
     ) -> Result<ChatCompletionResponse, LlmError>
     where
         N: ModelName + Chat + ThinkEnable + Serialize,
@@ -139,7 +163,23 @@ impl ZaiProvider {
         let messages = convert_to_text_messages(system_prompt, history, Some(user_message));
         let client =
             build_text_request(model, messages, &self.api_key, &self.api_base, max_tokens)?;
-        client.send().await.map_err(map_zai_error)
+        with_first_stop(client, stop)
+            .send()
+            .await
+            .map_err(map_zai_error)
+    }
+}
+
+/// ZAI accepts a single stop sequence, so only the first one is sent
+fn with_first_stop<N, M>(client: ChatCompletion<N, M>, stop: &[String]) -> ChatCompletion<N, M>
+where
+    N: ModelName + Chat + Serialize,
+    M: Serialize,
+    (N, M): zai_rs::model::traits::Bounded,
+{
+    match stop.first() {
+        Some(first) => client.with_stop(first.clone()),
+        None => client,
     }
 }
 
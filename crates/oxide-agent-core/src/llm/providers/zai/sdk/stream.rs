@@ -1,7 +1,11 @@
 use super::map_zai_error;
+use crate::agent::recovery::repair_json_arguments;
+use crate::config::get_llm_request_timeout_secs;
 use crate::llm::{ChatResponse, LlmError, TokenUsage, ToolCall, ToolCallFunction};
 use futures_util::StreamExt;
 use serde::Serialize;
+use std::time::Duration;
+use tracing::warn;
 use zai_rs::model::chat::ChatCompletion;
 use zai_rs::model::chat_base_response::{ToolCallMessage, Usage};
 use zai_rs::model::chat_message_types::TextMessage;
@@ -21,14 +25,39 @@ where
     N: ModelName + Chat + Serialize,
     (N, TextMessage): zai_rs::model::traits::Bounded,
 {
-    let mut stream = client.to_stream().await.map_err(map_zai_error)?;
+    // Long generations must not be cut off, so instead of an overall deadline
+    // both the first response and every later chunk get the request timeout.
+    // The SDK opens the connection itself, so the wait for the first response
+    // includes the model's time to first token, not just connection setup.
+    let idle_timeout = Duration::from_secs(get_llm_request_timeout_secs());
+
+    let mut stream = tokio::time::timeout(idle_timeout, client.to_stream())
+        .await
+        .map_err(|_| {
+            LlmError::NetworkError(format!(
+                "ZAI stream did not respond within {}s (LLM_REQUEST_TIMEOUT_SECS)",
+                idle_timeout.as_secs()
+            ))
+        })?
+        .map_err(map_zai_error)?;
     let mut reasoning_content = String::new();
     let mut content = String::new();
     let mut finish_reason = String::from("unknown");
     let mut usage: Option<TokenUsage> = None;
     let mut pending_tool_calls: Vec<PendingToolCall> = Vec::new();
 
-    while let Some(chunk) = stream.next().await {
+    loop {
+        let next = tokio::time::timeout(idle_timeout, stream.next())
+            .await
+            .map_err(|_| {
+                LlmError::NetworkError(format!(
+                    "ZAI stream stalled for {}s (LLM_REQUEST_TIMEOUT_SECS)",
+                    idle_timeout.as_secs()
+                ))
+            })?;
+        let Some(chunk) = next else {
+            break;
+        };
         let chunk = chunk.map_err(map_zai_error)?;
         if let Some(choice) = chunk.choices.first() {
             if let Some(delta) = &choice.delta {
@@ -124,16 +153,41 @@ fn finalize_tool_calls(pending: Vec<PendingToolCall>) -> Vec<ToolCall> {
         .filter_map(|(idx, call)| {
             let name = call.name?;
             let id = call.id.unwrap_or_else(|| format!("zai-tool-{idx}"));
-            let arguments = if call.arguments.trim().is_empty() {
-                "{}".to_string()
-            } else {
-                call.arguments
-            };
+            let (arguments, is_recovered) = validate_arguments(&name, call.arguments);
             Some(ToolCall {
                 id,
                 function: ToolCallFunction { name, arguments },
-                is_recovered: false,
+                is_recovered,
             })
         })
         .collect()
 }
+
+/// Check that assembled arguments are JSON, repairing them once if not
+///
+/// Trailing garbage is dropped. A stream that ends mid-call leaves truncated
+/// arguments, which keep their raw text so the runner rejects the call and
+/// asks the model to resend it. Both cases are flagged as recovered.
+fn validate_arguments(name: &str, arguments: String) -> (String, bool) {
+    if arguments.trim().is_empty() {
+        return ("{}".to_string(), false);
+    }
+    if serde_json::from_str::<serde_json::Value>(&arguments).is_ok() {
+        return (arguments, false);
+    }
+
+    match repair_json_arguments(&arguments) {
+        Some(repaired) => {
+            warn!(tool = %name, "ZAI stream produced invalid tool arguments, repaired them");
+            (repaired, true)
+        }
+        None => {
+            warn!(
+                tool = %name,
+                arguments_len = arguments.len(),
+                "ZAI stream produced truncated tool arguments, returning them to the model"
+            );
+            (arguments, true)
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Prometheus metrics (behind the `metrics` feature)
+//!
+//! Recording helpers are always available; without the `metrics` feature they
+//! compile to no-ops so call sites don't need their own `cfg` gates.
+
+use std::time::Duration;
+
+#[cfg(feature = "metrics")]
+use metrics::{counter, gauge, histogram};
+
+/// Start the Prometheus HTTP exporter if `METRICS_ADDR` is configured.
+///
+/// Returns the bound address, or `None` when metrics are disabled.
+///
+/// # Errors
+///
+/// Returns an error if the address is invalid or the exporter cannot be installed.
+#[cfg(feature = "metrics")]
+pub fn init_exporter() -> anyhow::Result<Option<std::net::SocketAddr>> {
+    use anyhow::Context;
+
+    let Some(addr) = crate::config::get_metrics_addr() else {
+        return Ok(None);
+    };
+    let addr: std::net::SocketAddr = addr
+        .parse()
+        .with_context(|| format!("Invalid METRICS_ADDR: {addr}"))?;
+
+    metrics_exporter_prometheus::PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .install()
+        .context("Failed to install Prometheus exporter")?;
+
+    Ok(Some(addr))
+}
+
+/// Start the Prometheus HTTP exporter if `METRICS_ADDR` is configured.
+///
+/// This build has no `metrics` feature, so nothing is started.
+///
+/// # Errors
+///
+/// Never fails; the signature matches the feature-enabled variant.
+#[cfg(not(feature = "metrics"))]
+pub fn init_exporter() -> anyhow::Result<Option<std::net::SocketAddr>> {
+    if crate::config::get_metrics_addr().is_some() {
+        tracing::warn!(
+            "METRICS_ADDR is set but the binary was built without the `metrics` feature"
+        );
+    }
+    Ok(None)
+}
+
+/// Record a single LLM request attempt.
+pub fn record_llm_request(provider: &str, duration: Duration, success: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let status = if success { "ok" } else { "error" };
+        counter!("llm_requests_total", "provider" => provider.to_string(), "status" => status)
+            .increment(1);
+        histogram!("llm_request_duration_seconds", "provider" => provider.to_string())
+            .record(duration.as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (provider, duration, success);
+}
+
+/// Record a retried LLM request.
+pub fn record_llm_retry(provider: &str) {
+    #[cfg(feature = "metrics")]
+    counter!("llm_retries_total", "provider" => provider.to_string()).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = provider;
+}
+
+/// Record a tool execution.
+pub fn record_tool_execution(tool: &str, duration: Duration, success: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let status = if success { "ok" } else { "error" };
+        counter!("tool_executions_total", "tool" => tool.to_string(), "status" => status)
+            .increment(1);
+        histogram!("tool_execution_duration_seconds", "tool" => tool.to_string())
+            .record(duration.as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (tool, duration, success);
+}
+
+/// Record a tool call requested by a model; `recovered` marks calls that
+/// were malformed and had to be repaired.
+pub fn record_tool_call(model: &str, recovered: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let recovered = if recovered { "true" } else { "false" };
+        counter!("agent_tool_calls_total", "model" => model.to_string(), "recovered" => recovered)
+            .increment(1);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (model, recovered);
+}
+
+/// Update the number of active agent sessions.
+pub fn set_active_sessions(count: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        #[allow(clippy::cast_precision_loss)]
+        gauge!("agent_active_sessions").set(count as f64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = count;
+}
+
+/// Record the time it took to create and start a sandbox container.
+pub fn record_sandbox_creation(duration: Duration) {
+    #[cfg(feature = "metrics")]
+    histogram!("sandbox_creation_duration_seconds").record(duration.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = duration;
+}
@@ -0,0 +1,71 @@
+//! Sandbox shared by the tool providers of one agent
+//!
+//! The executor hands the same [`SandboxHandle`] to every provider that
+//! touches the sandbox, so `execute_command`, yt-dlp downloads and uploads all
+//! work in one container and see the same `/workspace`.
+
+use super::SandboxManager;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Lazily created sandbox, cheap to clone and shared between providers
+#[derive(Clone)]
+pub struct SandboxHandle {
+    user_id: i64,
+    sandbox: Arc<Mutex<Option<SandboxManager>>>,
+}
+
+impl SandboxHandle {
+    /// Handle for the sandbox of `user_id`; the container starts on first use
+    #[must_use]
+    pub fn new(user_id: i64) -> Self {
+        Self {
+            user_id,
+            sandbox: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Owner of the sandbox
+    #[must_use]
+    pub const fn user_id(&self) -> i64 {
+        self.user_id
+    }
+
+    /// Running sandbox, created on first use
+    ///
+    /// The lock is held while the container starts, so providers calling
+    /// this at the same time wait for one container instead of racing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sandbox cannot be created.
+    pub async fn get(&self) -> Result<SandboxManager> {
+        let mut guard = self.sandbox.lock().await;
+        if let Some(sandbox) = guard.as_ref().filter(|s| s.is_running()) {
+            return Ok(sandbox.clone());
+        }
+
+        debug!(user_id = self.user_id, "Creating shared sandbox");
+        let mut sandbox = SandboxManager::new(self.user_id).await?;
+        sandbox.create_sandbox().await?;
+        *guard = Some(sandbox.clone());
+        Ok(sandbox)
+    }
+
+    /// Running sandbox, if one has been created; never starts a container
+    pub async fn existing(&self) -> Option<SandboxManager> {
+        self.sandbox
+            .lock()
+            .await
+            .as_ref()
+            .filter(|s| s.is_running())
+            .cloned()
+    }
+
+    /// Use a sandbox created elsewhere
+    pub async fn set(&self, sandbox: SandboxManager) {
+        *self.sandbox.lock().await = Some(sandbox);
+    }
+}
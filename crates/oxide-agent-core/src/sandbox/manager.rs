@@ -2,28 +2,41 @@
 //!
 //! Manages Docker containers for isolated code execution.
 
+use super::shell::shell_quote;
+use super::slots::container_slots;
 use anyhow::{anyhow, Context, Result};
 use base64::Engine;
 use bollard::exec::{CreateExecOptions, StartExecResults};
-use bollard::models::{ContainerCreateBody, HostConfig};
+use bollard::models::{ContainerCreateBody, ContainerSummaryStateEnum, HostConfig};
 use bollard::query_parameters::{
-    CreateContainerOptions, DownloadFromContainerOptions, RemoveContainerOptions,
-    StartContainerOptions, UploadToContainerOptions,
+    CreateContainerOptions, DownloadFromContainerOptions, InspectContainerOptions,
+    KillContainerOptions, RemoveContainerOptions, StartContainerOptions, UploadToContainerOptions,
 };
 use bollard::Docker;
 use bytes::Bytes;
 use futures_util::{StreamExt, TryStreamExt};
 use http_body_util::{Either, Full};
-use shell_escape::escape;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio_retry::strategy::{jitter, ExponentialBackoff};
+use tokio_retry::RetryIf;
 use tracing::{debug, info, instrument, warn};
 
 use crate::config::{
-    SANDBOX_CPU_PERIOD, SANDBOX_CPU_QUOTA, SANDBOX_EXEC_TIMEOUT_SECS, SANDBOX_IMAGE,
+    get_sandbox_create_retries, get_sandbox_idle_timeout_secs, get_sandbox_image,
+    get_sandbox_stale_after_secs, SANDBOX_CPU_PERIOD, SANDBOX_CPU_QUOTA, SANDBOX_CREATE_BACKOFF_MS,
+    SANDBOX_EXEC_TIMEOUT_SECS, SANDBOX_EXPECTED_TOOLS, SANDBOX_IDLE_CHECK_SECS,
     SANDBOX_MEMORY_LIMIT,
 };
 
+/// Container name namespace of this process, see [`SandboxManager::set_namespace`]
+static NAMESPACE: OnceLock<String> = OnceLock::new();
+
+/// Label holding the namespace of a namespaced sandbox container
+const NAMESPACE_LABEL: &str = "agent.sandbox.namespace";
+
 /// Result of executing a command in the sandbox
 #[derive(Debug, Clone)]
 pub struct ExecResult {
@@ -53,6 +66,140 @@ impl ExecResult {
             format!("{}\n{}", self.stdout, self.stderr)
         }
     }
+
+    /// Append a hint when the command failed because a tool is missing from the image.
+    ///
+    /// A bare "command not found" tends to confuse the model, so name the image and the
+    /// toolset the agent is expected to rely on.
+    fn annotate_missing_command(&mut self, image_name: &str) {
+        if self.exit_code != 127 {
+            return;
+        }
+        let Some(name) = missing_command_name(&self.stderr) else {
+            return;
+        };
+
+        let mut hint =
+            format!("\n[sandbox] `{name}` is not installed in the sandbox image `{image_name}`.");
+        if SANDBOX_EXPECTED_TOOLS.contains(&name) {
+            hint.push_str(&format!(
+                " The agent expects the image to provide: {}. Ask the operator to fix SANDBOX_IMAGE.",
+                SANDBOX_EXPECTED_TOOLS.join(", ")
+            ));
+        } else {
+            hint.push_str(
+                " Install it first (e.g. `pip install` or `apt-get install`) or use another tool.",
+            );
+        }
+        self.stderr.push_str(&hint);
+    }
+}
+
+/// Extract the command name from a shell "not found" error line.
+///
+/// Handles dash (`sh: 1: foo: not found`), bash (`bash: foo: command not found`)
+/// and busybox (`sh: foo: not found`) formats.
+fn missing_command_name(stderr: &str) -> Option<&str> {
+    stderr.lines().find_map(|line| {
+        let line = line.trim();
+        let rest = line
+            .strip_suffix(": command not found")
+            .or_else(|| line.strip_suffix(": not found"))?;
+        rest.rsplit(": ")
+            .next()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+    })
+}
+
+/// Default working directory of sandbox commands
+const WORKSPACE_DIR: &str = "/workspace";
+
+/// Where and how long a single exec runs
+struct ExecOptions<'a> {
+    working_dir: &'a str,
+    env: &'a BTreeMap<String, String>,
+    timeout: std::time::Duration,
+}
+
+/// Environment variable used to tag every process spawned by a single exec
+const EXEC_TAG_ENV: &str = "OXIDE_EXEC_ID";
+
+/// Kills every process whose environment contains `OXIDE_EXEC_ID=$0`.
+const KILL_TAGGED_SCRIPT: &str = r#"for p in /proc/[0-9]*; do grep -qzxF "OXIDE_EXEC_ID=$0" "$p/environ" 2>/dev/null && kill -9 "${p#/proc/}" 2>/dev/null; done; true"#;
+
+/// Kill all processes spawned by a tagged exec (best effort).
+async fn kill_exec_tree(docker: &Docker, container_id: &str, exec_tag: &str) {
+    let exec_options = CreateExecOptions {
+        attach_stdout: Some(true),
+        attach_stderr: Some(true),
+        cmd: Some(vec!["sh", "-c", KILL_TAGGED_SCRIPT, exec_tag]),
+        ..Default::default()
+    };
+
+    let exec = match docker.create_exec(container_id, exec_options).await {
+        Ok(exec) => exec,
+        Err(e) => {
+            warn!(container_id = %container_id, error = %e, "Failed to create process tree kill exec");
+            return;
+        }
+    };
+
+    let run = async {
+        if let Ok(StartExecResults::Attached { mut output, .. }) =
+            docker.start_exec(&exec.id, None).await
+        {
+            while output.next().await.is_some() {}
+        }
+    };
+    if tokio::time::timeout(std::time::Duration::from_secs(5), run)
+        .await
+        .is_err()
+    {
+        warn!(container_id = %container_id, "Timed out killing exec process tree");
+    } else {
+        info!(container_id = %container_id, exec_tag = %exec_tag, "Exec process tree killed");
+    }
+}
+
+/// Kills the tagged process tree of an exec unless disarmed.
+///
+/// Dropping an armed guard (e.g. when an outer timeout drops the exec future)
+/// schedules the kill on the current Tokio runtime.
+struct ExecTreeGuard {
+    docker: Docker,
+    container_id: String,
+    exec_tag: String,
+    armed: bool,
+}
+
+impl ExecTreeGuard {
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+
+    async fn kill_now(mut self) {
+        self.armed = false;
+        kill_exec_tree(&self.docker, &self.container_id, &self.exec_tag).await;
+    }
+}
+
+impl Drop for ExecTreeGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            warn!(exec_tag = %self.exec_tag, "No runtime available to kill exec process tree");
+            return;
+        };
+        let docker = self.docker.clone();
+        let container_id = std::mem::take(&mut self.container_id);
+        let exec_tag = std::mem::take(&mut self.exec_tag);
+        handle.spawn(async move {
+            kill_exec_tree(&docker, &container_id, &exec_tag).await;
+        });
+    }
 }
 
 /// Docker sandbox manager for isolated code execution
@@ -86,15 +233,195 @@ impl SandboxManager {
         Ok(Self {
             docker,
             container_id: None,
-            image_name: SANDBOX_IMAGE.to_string(),
+            image_name: get_sandbox_image(),
             user_id,
         })
     }
 
+    /// Namespace the sandbox containers of this process.
+    ///
+    /// Transports whose session ids can overlap set distinct namespaces at
+    /// startup, so they never share, stop or remove each other's containers.
+    /// Containers are then named `agent-sandbox-{namespace}-{id}`. Only the
+    /// first call has an effect; without one, names stay `agent-sandbox-{id}`.
+    pub fn set_namespace(namespace: &str) {
+        if NAMESPACE.set(namespace.to_string()).is_err() {
+            warn!(namespace, "Sandbox namespace already set, ignoring");
+        }
+    }
+
+    /// Remove sandbox containers unused for `SANDBOX_STALE_AFTER_SECS`.
+    ///
+    /// Meant to run once on startup, before any session uses a sandbox, to reap
+    /// containers left behind by earlier runs. A container counts as used when
+    /// it was last started or stopped, and the idle reaper stops it once it
+    /// sits unused. Running containers are never removed: those of live
+    /// sessions are skipped, and ones left running by a crashed process are
+    /// stopped so their age counts from now. Containers of other namespaces
+    /// (see [`set_namespace`](Self::set_namespace)) are left alone. Returns
+    /// the number removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Docker daemon is unreachable or listing fails.
+    pub async fn cleanup_stale_sandboxes() -> Result<usize> {
+        let stale_after = get_sandbox_stale_after_secs();
+        if stale_after == 0 {
+            return Ok(0);
+        }
+
+        let docker =
+            Docker::connect_with_local_defaults().context("Failed to connect to Docker daemon")?;
+        let filters =
+            HashMap::from([("label".to_string(), vec!["agent.sandbox=true".to_string()])]);
+        let containers = docker
+            .list_containers(Some(bollard::query_parameters::ListContainersOptions {
+                all: true,
+                filters: Some(filters),
+                ..Default::default()
+            }))
+            .await
+            .context("Failed to list sandbox containers")?;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut removed = 0;
+        for container in containers {
+            let Some(id) = container.id else { continue };
+            let container_namespace = container
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get(NAMESPACE_LABEL))
+                .map_or("", String::as_str);
+            if container_namespace != namespace() {
+                continue;
+            }
+
+            if container.state == Some(ContainerSummaryStateEnum::RUNNING) {
+                let live = container
+                    .names
+                    .iter()
+                    .flatten()
+                    .any(|name| container_slots().is_live(name.trim_start_matches('/')));
+                if !live {
+                    if let Err(e) = docker
+                        .kill_container(&id, None::<KillContainerOptions>)
+                        .await
+                    {
+                        warn!(container_id = %id, error = %e, "Failed to stop orphaned sandbox");
+                    }
+                }
+                continue;
+            }
+
+            let last_active = match docker
+                .inspect_container(&id, None::<InspectContainerOptions>)
+                .await
+            {
+                Ok(details) => details.state.and_then(|state| {
+                    last_active(state.started_at.as_deref(), state.finished_at.as_deref())
+                }),
+                Err(e) => {
+                    warn!(container_id = %id, error = %e, "Failed to inspect sandbox");
+                    continue;
+                }
+            };
+            // Never started: fall back to when it was created
+            if !is_stale(last_active.or(container.created), now, stale_after) {
+                continue;
+            }
+
+            let options = RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            };
+            match docker.remove_container(&id, Some(options)).await {
+                Ok(()) => removed += 1,
+                Err(e) => warn!(container_id = %id, error = %e, "Failed to remove stale sandbox"),
+            }
+        }
+
+        info!(
+            removed,
+            stale_after_secs = stale_after,
+            "Stale sandbox cleanup finished"
+        );
+        Ok(removed)
+    }
+
     /// Check if sandbox container is running
+    ///
+    /// Turns `false` once the container is destroyed by another manager or
+    /// stopped by the idle reaper; [`create_sandbox`](Self::create_sandbox)
+    /// on a fresh manager then starts it again.
     #[must_use]
-    pub const fn is_running(&self) -> bool {
-        self.container_id.is_some()
+    pub fn is_running(&self) -> bool {
+        self.container_id.is_some() && container_slots().is_live(&self.container_name())
+    }
+
+    /// Stop sandbox containers unused for `SANDBOX_IDLE_TIMEOUT_SECS` and free
+    /// their slots. Returns the number stopped.
+    ///
+    /// The containers are kept, so `/workspace` survives and the next use of
+    /// the sandbox starts the container again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Docker daemon is unreachable.
+    pub async fn stop_idle_sandboxes() -> Result<usize> {
+        let idle = Duration::from_secs(get_sandbox_idle_timeout_secs());
+        if idle.is_zero() {
+            return Ok(0);
+        }
+        let names = container_slots().idle(idle);
+        if names.is_empty() {
+            return Ok(0);
+        }
+
+        let docker =
+            Docker::connect_with_local_defaults().context("Failed to connect to Docker daemon")?;
+        let mut stopped = 0;
+        for name in names {
+            // Used again since it was listed
+            if !container_slots().release_if_idle(&name, idle) {
+                continue;
+            }
+            // `sleep infinity` ignores SIGTERM, so a plain stop would wait out its timeout
+            match docker
+                .kill_container(&name, None::<KillContainerOptions>)
+                .await
+            {
+                Ok(()) => {
+                    stopped += 1;
+                    info!(container = %name, idle_secs = idle.as_secs(), "Stopped idle sandbox container");
+                }
+                Err(e) => {
+                    warn!(container = %name, error = %e, "Failed to stop idle sandbox container")
+                }
+            }
+        }
+        Ok(stopped)
+    }
+
+    /// Periodically stop idle sandbox containers, see
+    /// [`stop_idle_sandboxes`](Self::stop_idle_sandboxes)
+    ///
+    /// Does nothing when `SANDBOX_IDLE_TIMEOUT_SECS` is zero.
+    pub fn spawn_idle_reaper() {
+        let idle = get_sandbox_idle_timeout_secs();
+        if idle == 0 {
+            return;
+        }
+        let period = Duration::from_secs(idle.clamp(1, SANDBOX_IDLE_CHECK_SECS));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::stop_idle_sandboxes().await {
+                    warn!(error = %e, "Idle sandbox check failed");
+                }
+            }
+        });
     }
 
     /// Get container ID if running
@@ -103,11 +430,84 @@ impl SandboxManager {
         self.container_id.as_deref()
     }
 
+    /// Name of this user's sandbox container, shared by all its managers
+    fn container_name(&self) -> String {
+        container_name(self.user_id)
+    }
+
+    /// Remove the sandbox container of `user_id` and free its slot
+    ///
+    /// Works without the manager that started the container, so a session can
+    /// be torn down even though its providers each opened their own manager.
+    /// Does nothing when this process holds no container for the user. The
+    /// slot is freed even if the removal fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Docker daemon is unreachable or removal fails.
+    pub async fn destroy_for_user(user_id: i64) -> Result<()> {
+        let container_name = container_name(user_id);
+        if !container_slots().is_live(&container_name) {
+            return Ok(());
+        }
+
+        let removed = async {
+            let docker = Docker::connect_with_local_defaults()
+                .context("Failed to connect to Docker daemon")?;
+            docker
+                .remove_container(
+                    &container_name,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .context("Failed to remove sandbox container")
+        }
+        .await;
+        container_slots().release(&container_name);
+        removed?;
+
+        info!(user_id, container = %container_name, "Sandbox container destroyed");
+        Ok(())
+    }
+
+    /// Id of the container named `container_name`, running or not
+    async fn find_container_id(&self, container_name: &str) -> Result<Option<String>> {
+        let filters = HashMap::from([("name".to_string(), vec![container_name.to_string()])]);
+        let containers = retry_transient("list", || {
+            self.docker
+                .list_containers(Some(bollard::query_parameters::ListContainersOptions {
+                    all: true,
+                    filters: Some(filters.clone()),
+                    ..Default::default()
+                }))
+        })
+        .await
+        .context("Failed to list containers")?;
+
+        // The name filter also matches substrings, so compare exactly
+        let wanted = format!("/{container_name}");
+        Ok(containers
+            .into_iter()
+            .find(|c| {
+                c.names
+                    .as_ref()
+                    .is_some_and(|names| names.contains(&wanted))
+            })
+            .and_then(|c| c.id))
+    }
+
     /// Create and start a new sandbox container
     ///
+    /// Waits for a free slot when `SANDBOX_MAX_CONTAINERS` containers are
+    /// already running on this host.
+    ///
     /// # Errors
     ///
-    /// Returns an error if container creation or starting fails.
+    /// Returns an error if no slot frees up in time, or if container creation
+    /// or starting fails.
     #[instrument(skip(self), fields(user_id = self.user_id))]
     pub async fn create_sandbox(&mut self) -> Result<()> {
         if self.container_id.is_some() {
@@ -115,24 +515,12 @@ impl SandboxManager {
             return Ok(());
         }
 
-        let container_name = format!("agent-sandbox-{}", self.user_id);
+        let container_name = self.container_name();
+        // Dropped on any early error, which frees the slot again
+        let slot = container_slots().acquire(&container_name).await?;
 
         // Check if container already exists
-        let mut filters = HashMap::new();
-        filters.insert("name".to_string(), vec![container_name.clone()]);
-
-        let containers = self
-            .docker
-            .list_containers(Some(bollard::query_parameters::ListContainersOptions {
-                all: true,
-                filters: Some(filters),
-                ..Default::default()
-            }))
-            .await
-            .context("Failed to list containers")?;
-
-        if let Some(container) = containers.first() {
-            let id = container.id.clone().unwrap_or_default();
+        if let Some(id) = self.find_container_id(&container_name).await? {
             info!(user_id = self.user_id, container_id = %id, "Found existing sandbox container");
             self.container_id = Some(id.clone());
 
@@ -146,9 +534,12 @@ impl SandboxManager {
                 // We'll log debug and proceed.
                 debug!(error = %e, "Tried to start existing container (might already be running)");
             }
+            container_slots().register(&container_name, slot);
             return Ok(());
         }
 
+        let started_at = std::time::Instant::now();
+
         // Container configuration with resource limits
         let host_config = HostConfig {
             memory: Some(SANDBOX_MEMORY_LIMIT),
@@ -156,8 +547,8 @@ impl SandboxManager {
             cpu_quota: Some(SANDBOX_CPU_QUOTA),
             // Network access enabled (bridge mode)
             network_mode: Some("bridge".to_string()),
-            // Auto-remove on stop (safety net)
-            auto_remove: Some(true),
+            // Kept when stopped for being idle, so `/workspace` survives
+            auto_remove: Some(false),
             ..Default::default()
         };
 
@@ -166,10 +557,7 @@ impl SandboxManager {
             hostname: Some("sandbox".to_string()),
             working_dir: Some("/workspace".to_string()),
             host_config: Some(host_config),
-            labels: Some(HashMap::from([
-                ("agent.user_id".to_string(), self.user_id.to_string()),
-                ("agent.sandbox".to_string(), "true".to_string()),
-            ])),
+            labels: Some(container_labels(self.user_id)),
             // Keep container running
             cmd: Some(vec!["sleep".to_string(), "infinity".to_string()]),
             ..Default::default()
@@ -181,23 +569,37 @@ impl SandboxManager {
         };
 
         // Create container
-        let response = self
-            .docker
-            .create_container(Some(options), config)
-            .await
-            .context("Failed to create sandbox container")?;
-
-        let container_id = response.id;
-        info!(container_id = %container_id, "Sandbox container created");
+        let created = retry_transient("create", || {
+            self.docker
+                .create_container(Some(options.clone()), config.clone())
+        })
+        .await;
+        let container_id = match created {
+            Ok(response) => {
+                info!(container_id = %response.id, "Sandbox container created");
+                response.id
+            }
+            // A timed-out earlier attempt may have created the container after all
+            Err(e) if is_name_conflict(&e) => self
+                .find_container_id(&container_name)
+                .await?
+                .inspect(|id| info!(container_id = %id, "Adopted sandbox container from an earlier attempt"))
+                .ok_or_else(|| describe_create_error(e, &self.image_name))?,
+            Err(e) => return Err(describe_create_error(e, &self.image_name)),
+        };
 
         // Start container
-        self.docker
-            .start_container(&container_id, None::<StartContainerOptions>)
-            .await
-            .context("Failed to start sandbox container")?;
+        retry_transient("start", || {
+            self.docker
+                .start_container(&container_id, None::<StartContainerOptions>)
+        })
+        .await
+        .context("Failed to start sandbox container")?;
 
         self.container_id = Some(container_id.clone());
+        container_slots().register(&container_name, slot);
         info!(container_id = %container_id, "Sandbox container started");
+        crate::metrics::record_sandbox_creation(started_at.elapsed());
 
         Ok(())
     }
@@ -250,24 +652,102 @@ impl SandboxManager {
     /// # Errors
     ///
     /// Returns an error if sandbox is not running, exec creation fails, execution times out, or is cancelled.
-    #[instrument(skip(self, cancellation_token), fields(container_id = ?self.container_id))]
     pub async fn exec_command(
         &self,
         cmd: &str,
         cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     ) -> Result<ExecResult> {
+        self.exec_command_with_timeout(
+            cmd,
+            std::time::Duration::from_secs(SANDBOX_EXEC_TIMEOUT_SECS),
+            cancellation_token,
+        )
+        .await
+    }
+
+    /// Execute a command in the sandbox with an explicit timeout
+    ///
+    /// Every exec is tagged with a unique `OXIDE_EXEC_ID` environment variable that is
+    /// inherited by the whole process tree. When the command times out (or the returned
+    /// future is dropped, e.g. by the agent tool timeout), all processes carrying the tag
+    /// are killed, including backgrounded children that were re-parented to PID 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if sandbox is not running, exec creation fails, execution times out, or is cancelled.
+    pub async fn exec_command_with_timeout(
+        &self,
+        cmd: &str,
+        timeout: std::time::Duration,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<ExecResult> {
+        let options = ExecOptions {
+            working_dir: WORKSPACE_DIR,
+            env: &BTreeMap::new(),
+            timeout,
+        };
+        self.exec_with_options(cmd, &options, cancellation_token)
+            .await
+    }
+
+    /// Execute a command starting in `working_dir` instead of `/workspace`,
+    /// with extra environment variables
+    ///
+    /// The variables are passed to the exec as `KEY=value` entries, so they
+    /// never appear in the command line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if sandbox is not running, exec creation fails, execution times out, or is cancelled.
+    pub async fn exec_command_in_dir(
+        &self,
+        cmd: &str,
+        working_dir: &str,
+        env: &BTreeMap<String, String>,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<ExecResult> {
+        let options = ExecOptions {
+            working_dir,
+            env,
+            timeout: std::time::Duration::from_secs(SANDBOX_EXEC_TIMEOUT_SECS),
+        };
+        self.exec_with_options(cmd, &options, cancellation_token)
+            .await
+    }
+
+    #[instrument(skip(self, options, cancellation_token), fields(container_id = ?self.container_id))]
+    async fn exec_with_options(
+        &self,
+        cmd: &str,
+        options: &ExecOptions<'_>,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<ExecResult> {
+        let timeout = options.timeout;
         let container_id = self
             .container_id
             .as_ref()
             .ok_or_else(|| anyhow!("Sandbox not running"))?;
+        let container_name = self.container_name();
+        container_slots().touch(&container_name);
+        // Touched again when done, so a long command does not count as idle
+        let _touch_when_done = TouchOnDrop(&container_name);
 
         debug!(cmd = %cmd, "Executing command in sandbox");
 
+        let exec_tag = uuid::Uuid::new_v4().to_string();
+        // The tag goes last so it always wins
+        let env: Vec<String> = options
+            .env
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .chain(std::iter::once(format!("{EXEC_TAG_ENV}={exec_tag}")))
+            .collect();
         let exec_options = CreateExecOptions {
             attach_stdout: Some(true),
             attach_stderr: Some(true),
             cmd: Some(vec!["sh", "-c", cmd]),
-            working_dir: Some("/workspace"),
+            env: Some(env.iter().map(String::as_str).collect()),
+            working_dir: Some(options.working_dir),
             ..Default::default()
         };
 
@@ -277,37 +757,45 @@ impl SandboxManager {
             .await
             .context("Failed to create exec")?;
 
-        // If cancellation_token is provided, use select! to handle cancellation
-        let result = if let Some(token) = cancellation_token {
-            use tokio::select;
-            select! {
-                res = tokio::time::timeout(
-                    std::time::Duration::from_secs(SANDBOX_EXEC_TIMEOUT_SECS),
-                    self.run_exec(&exec.id),
-                ) => {
-                    res.map_err(|_| anyhow!("Command execution timed out after {SANDBOX_EXEC_TIMEOUT_SECS}s"))?
-                        .context("Command execution failed")?
-                },
-                _ = token.cancelled() => {
-                    warn!(exec_id = %exec.id, cmd = %cmd, "Command cancelled by user, killing processes");
-
-                    // Kill all processes in the container
-                    self.kill_processes().await;
-
-                    return Err(anyhow!("Command execution cancelled by user"));
-                }
+        let guard = ExecTreeGuard {
+            docker: self.docker.clone(),
+            container_id: container_id.clone(),
+            exec_tag,
+            armed: true,
+        };
+
+        let cancelled = async {
+            match cancellation_token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
             }
-        } else {
-            // No cancellation token: use original timeout logic
-            tokio::time::timeout(
-                std::time::Duration::from_secs(SANDBOX_EXEC_TIMEOUT_SECS),
-                self.run_exec(&exec.id),
-            )
-            .await
-            .map_err(|_| anyhow!("Command execution timed out after {SANDBOX_EXEC_TIMEOUT_SECS}s"))?
-            .context("Command execution failed")?
         };
 
+        let outcome = tokio::select! {
+            res = tokio::time::timeout(timeout, self.run_exec(&exec.id)) => res,
+            () = cancelled => {
+                warn!(exec_id = %exec.id, cmd = %cmd, "Command cancelled by user, killing processes");
+
+                // Kill all processes in the container
+                self.kill_processes().await;
+                guard.disarm();
+
+                return Err(anyhow!("Command execution cancelled by user"));
+            }
+        };
+
+        let Ok(result) = outcome else {
+            warn!(exec_id = %exec.id, cmd = %cmd, "Command timed out, killing its process tree");
+            guard.kill_now().await;
+            return Err(anyhow!(
+                "Command execution timed out after {}s",
+                timeout.as_secs()
+            ));
+        };
+        guard.disarm();
+        let mut result = result.context("Command execution failed")?;
+        result.annotate_missing_command(&self.image_name);
+
         debug!(
             exit_code = result.exit_code,
             stdout_len = result.stdout.len(),
@@ -364,11 +852,7 @@ impl SandboxManager {
         // Use base64 to safely transfer binary content
         let encoded = base64::engine::general_purpose::STANDARD.encode(content);
 
-        let cmd = format!(
-            "echo '{}' | base64 -d > {}",
-            encoded,
-            shell_escape::escape(path.into())
-        );
+        let cmd = format!("echo '{}' | base64 -d > {}", encoded, shell_quote(path));
 
         let result = self.exec_command(&cmd, None).await?;
 
@@ -387,7 +871,7 @@ impl SandboxManager {
     /// Returns an error if file reading or decoding fails.
     #[instrument(skip(self), fields(path = %path))]
     pub async fn read_file(&self, path: &str) -> Result<Vec<u8>> {
-        let cmd = format!("base64 {}", shell_escape::escape(path.into()));
+        let cmd = format!("base64 {}", shell_quote(path));
 
         let result = self.exec_command(&cmd, None).await?;
 
@@ -418,34 +902,13 @@ impl SandboxManager {
             .as_ref()
             .ok_or_else(|| anyhow!("Sandbox not running"))?;
 
-        let path = std::path::Path::new(container_path);
-        let parent = path.parent().map_or_else(
-            || "/workspace".to_string(),
-            |p| p.to_string_lossy().to_string(),
-        );
-        let file_name = path
-            .file_name()
-            .map_or_else(|| "file".to_string(), |n| n.to_string_lossy().to_string());
-
-        // Ensure parent directory exists
-        self.exec_command(&format!("mkdir -p '{parent}'"), None)
-            .await?;
+        let (parent, file_name) = self.prepare_upload_dir(container_path).await?;
 
         // Create tar archive in memory
         let mut tar_buffer = Vec::new();
         {
             let mut builder = tar::Builder::new(&mut tar_buffer);
-            let mut header = tar::Header::new_gnu();
-            header.set_path(&file_name)?;
-            header.set_size(content.len() as u64);
-            header.set_mode(0o644);
-            header.set_mtime(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs(),
-            );
-            header.set_cksum();
+            let header = upload_tar_header(&file_name, content.len() as u64)?;
             builder.append(&header, content)?;
             builder.finish()?;
         }
@@ -472,6 +935,82 @@ impl SandboxManager {
         Ok(())
     }
 
+    /// Upload a file from the host filesystem without reading it into memory
+    ///
+    /// The tar archive is streamed: header, file content, then padding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if sandbox is not running, the local file cannot be read, or upload fails.
+    #[instrument(skip(self), fields(path = %container_path))]
+    pub async fn upload_local_file(
+        &self,
+        container_path: &str,
+        local_path: &std::path::Path,
+    ) -> Result<()> {
+        let container_id = self
+            .container_id
+            .as_ref()
+            .ok_or_else(|| anyhow!("Sandbox not running"))?;
+
+        let size = tokio::fs::metadata(local_path)
+            .await
+            .with_context(|| format!("Failed to read {}", local_path.display()))?
+            .len();
+        let (parent, file_name) = self.prepare_upload_dir(container_path).await?;
+        let header = upload_tar_header(&file_name, size)?;
+        let file = tokio::fs::File::open(local_path).await?;
+
+        // Content is padded to a 512-byte block, the archive ends with two zero blocks
+        let padding = (512 - size % 512) % 512;
+        let trailer = vec![0u8; usize::try_from(padding).unwrap_or(0) + 1024];
+        let tar_stream =
+            futures_util::stream::once(
+                async move { Ok(Bytes::copy_from_slice(header.as_bytes())) },
+            )
+            .chain(tokio_util::io::ReaderStream::new(file))
+            .chain(futures_util::stream::once(async move {
+                Ok(Bytes::from(trailer))
+            }));
+
+        self.docker
+            .upload_to_container(
+                container_id,
+                Some(UploadToContainerOptions {
+                    path: parent,
+                    ..Default::default()
+                }),
+                bollard::body_try_stream(tar_stream),
+            )
+            .await
+            .context("Failed to upload file to container")?;
+
+        info!(
+            container_id = %container_id,
+            path = %container_path,
+            size,
+            "Local file streamed to sandbox"
+        );
+
+        Ok(())
+    }
+
+    /// Create the parent directory of `container_path`; returns it with the file name
+    async fn prepare_upload_dir(&self, container_path: &str) -> Result<(String, String)> {
+        let path = std::path::Path::new(container_path);
+        let parent = path.parent().map_or_else(
+            || "/workspace".to_string(),
+            |p| p.to_string_lossy().to_string(),
+        );
+        let file_name = path
+            .file_name()
+            .map_or_else(|| "file".to_string(), |n| n.to_string_lossy().to_string());
+
+        self.exec_command(&format!("mkdir -p '{parent}'"), None)
+            .await?;
+        Ok((parent, file_name))
+    }
+
     /// Download a file from the container
     ///
     /// Returns the raw file content as bytes.
@@ -599,11 +1138,12 @@ impl SandboxManager {
                 .remove_container(&container_id, Some(options))
                 .await
             {
-                // Container might already be removed (auto_remove)
+                // Container might already be removed
                 warn!(container_id = %container_id, error = %e, "Failed to remove container (may already be removed)");
             } else {
                 info!(container_id = %container_id, "Sandbox container destroyed");
             }
+            container_slots().release(&self.container_name());
         }
 
         Ok(())
@@ -623,7 +1163,7 @@ impl SandboxManager {
             self.destroy().await?;
         } else {
             // Even if not in memory, check docker for the named container
-            let container_name = format!("agent-sandbox-{}", self.user_id);
+            let container_name = self.container_name();
             // Best effort cleanup by name if we lost the ID
             let _ = self
                 .docker
@@ -635,6 +1175,7 @@ impl SandboxManager {
                     }),
                 )
                 .await;
+            container_slots().release(&container_name);
         }
 
         // Create new one
@@ -652,7 +1193,7 @@ impl SandboxManager {
         container_path: &str,
         cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     ) -> Result<u64> {
-        let escaped_path = escape(container_path.into());
+        let escaped_path = shell_quote(container_path);
 
         let check_cmd = format!("test -f {escaped_path} && echo 'exists'");
         let check = self.exec_command(&check_cmd, cancellation_token).await?;
@@ -672,6 +1213,103 @@ impl SandboxManager {
     }
 }
 
+/// Whether a Docker failure may go away on its own: the daemon is busy,
+/// restarting or unreachable. Client errors such as a missing image are not.
+fn is_transient_docker_error(error: &bollard::errors::Error) -> bool {
+    use bollard::errors::Error;
+    match error {
+        Error::DockerResponseServerError { status_code, .. } => *status_code >= 500,
+        Error::RequestTimeoutError
+        | Error::IOError { .. }
+        | Error::HyperResponseError { .. }
+        | Error::HyperLegacyError { .. }
+        | Error::SocketNotFoundError(_) => true,
+        _ => false,
+    }
+}
+
+/// Run a container lifecycle call, retrying transient Docker failures up to
+/// `SANDBOX_CREATE_RETRIES` times with exponential backoff.
+async fn retry_transient<T, F, Fut>(step: &str, action: F) -> Result<T, bollard::errors::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, bollard::errors::Error>>,
+{
+    let strategy = ExponentialBackoff::from_millis(2)
+        .factor(SANDBOX_CREATE_BACKOFF_MS / 2)
+        .map(jitter)
+        .take(get_sandbox_create_retries());
+    RetryIf::spawn(strategy, action, |e: &bollard::errors::Error| {
+        let transient = is_transient_docker_error(e);
+        if transient {
+            warn!(step, error = %e, "Transient Docker failure, retrying sandbox container");
+        }
+        transient
+    })
+    .await
+}
+
+/// Whether Docker refused to create a container because its name is taken.
+fn is_name_conflict(error: &bollard::errors::Error) -> bool {
+    matches!(
+        error,
+        bollard::errors::Error::DockerResponseServerError {
+            status_code: 409,
+            ..
+        }
+    )
+}
+
+/// Error for a failed container creation; a missing image gets a clear
+/// message instead of the raw Docker response.
+fn describe_create_error(error: bollard::errors::Error, image_name: &str) -> anyhow::Error {
+    match error {
+        bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        } => anyhow!(
+            "Sandbox image '{image_name}' not found. Build it or set SANDBOX_IMAGE to an existing image."
+        ),
+        e => anyhow::Error::new(e).context("Failed to create sandbox container"),
+    }
+}
+
+/// Tar header for a single regular file uploaded to the sandbox
+fn upload_tar_header(file_name: &str, size: u64) -> Result<tar::Header> {
+    let mut header = tar::Header::new_gnu();
+    header.set_path(file_name)?;
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    );
+    header.set_cksum();
+    Ok(header)
+}
+
+/// Whether a container last active at `last_active` (Unix seconds) has been
+/// unused for at least `stale_after` seconds
+fn is_stale(last_active: Option<i64>, now: i64, stale_after: u64) -> bool {
+    last_active.is_some_and(|last_active| {
+        u64::try_from(now.saturating_sub(last_active)).is_ok_and(|age| age >= stale_after)
+    })
+}
+
+/// Latest of a container's Docker `StartedAt`/`FinishedAt` times (Unix seconds)
+///
+/// Docker reports `0001-01-01T00:00:00Z` for times that never happened.
+fn last_active(started_at: Option<&str>, finished_at: Option<&str>) -> Option<i64> {
+    [started_at, finished_at]
+        .into_iter()
+        .flatten()
+        .filter_map(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.timestamp())
+        .filter(|time| *time > 0)
+        .max()
+}
+
 impl Drop for SandboxManager {
     fn drop(&mut self) {
         if let Some(ref id) = self.container_id {
@@ -683,9 +1321,161 @@ impl Drop for SandboxManager {
     }
 }
 
+/// Marks a container as used when dropped
+struct TouchOnDrop<'a>(&'a str);
+
+impl Drop for TouchOnDrop<'_> {
+    fn drop(&mut self) {
+        container_slots().touch(self.0);
+    }
+}
+
+/// Namespace set by [`SandboxManager::set_namespace`], empty if none
+fn namespace() -> &'static str {
+    NAMESPACE.get().map_or("", String::as_str)
+}
+
+/// Name of the sandbox container of `user_id`
+fn container_name(user_id: i64) -> String {
+    namespaced_container_name(namespace(), user_id)
+}
+
+fn namespaced_container_name(namespace: &str, user_id: i64) -> String {
+    if namespace.is_empty() {
+        format!("agent-sandbox-{user_id}")
+    } else {
+        format!("agent-sandbox-{namespace}-{user_id}")
+    }
+}
+
+/// Labels of a new sandbox container of `user_id`
+fn container_labels(user_id: i64) -> HashMap<String, String> {
+    let mut labels = HashMap::from([
+        ("agent.user_id".to_string(), user_id.to_string()),
+        ("agent.sandbox".to_string(), "true".to_string()),
+    ]);
+    if !namespace().is_empty() {
+        labels.insert(NAMESPACE_LABEL.to_string(), namespace().to_string());
+    }
+    labels
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_namespaced_container_name() {
+        assert_eq!(namespaced_container_name("", 42), "agent-sandbox-42");
+        assert_eq!(
+            namespaced_container_name("http", 42),
+            "agent-sandbox-http-42"
+        );
+        assert_ne!(
+            namespaced_container_name("http", -1),
+            namespaced_container_name("", -1)
+        );
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let now = 1_700_000_000;
+        assert!(is_stale(Some(now - 90_000), now, 86_400));
+        assert!(!is_stale(Some(now - 60), now, 86_400));
+        assert!(!is_stale(Some(now + 60), now, 86_400));
+        assert!(!is_stale(None, now, 86_400));
+    }
+
+    #[test]
+    fn test_last_active_uses_latest_start_or_stop() {
+        let started = "2026-01-01T10:00:00.123456789Z";
+        let finished = "2026-01-02T10:00:00Z";
+        assert_eq!(
+            last_active(Some(started), Some(finished)),
+            Some(1_767_348_000)
+        );
+        assert_eq!(
+            last_active(Some(started), Some("0001-01-01T00:00:00Z")),
+            Some(1_767_261_600)
+        );
+        assert_eq!(last_active(None, Some("0001-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_create_errors_are_classified() {
+        use bollard::errors::Error;
+        let busy = Error::DockerResponseServerError {
+            status_code: 503,
+            message: "daemon is restarting".to_string(),
+        };
+        assert!(is_transient_docker_error(&busy));
+        assert!(is_transient_docker_error(&Error::RequestTimeoutError));
+
+        let missing = Error::DockerResponseServerError {
+            status_code: 404,
+            message: "No such image: agent-sandbox:latest".to_string(),
+        };
+        assert!(!is_transient_docker_error(&missing));
+        let message = describe_create_error(missing, "agent-sandbox:latest").to_string();
+        assert!(message.contains("'agent-sandbox:latest' not found"));
+        assert!(message.contains("SANDBOX_IMAGE"));
+
+        let conflict = Error::DockerResponseServerError {
+            status_code: 409,
+            message: "Conflict. The container name \"/agent-sandbox-1\" is already in use"
+                .to_string(),
+        };
+        assert!(is_name_conflict(&conflict));
+        assert!(!is_transient_docker_error(&conflict));
+        assert!(!is_name_conflict(&busy));
+    }
+
+    #[test]
+    fn test_missing_command_name_formats() {
+        assert_eq!(
+            missing_command_name("sh: 1: ffmpeg: not found"),
+            Some("ffmpeg")
+        );
+        assert_eq!(
+            missing_command_name("bash: jq: command not found"),
+            Some("jq")
+        );
+        assert_eq!(
+            missing_command_name("sh: yt-dlp: not found"),
+            Some("yt-dlp")
+        );
+        assert_eq!(missing_command_name("error: file missing"), None);
+    }
+
+    #[test]
+    fn test_annotate_missing_expected_tool() {
+        let mut result = ExecResult {
+            stdout: String::new(),
+            stderr: "sh: 1: ffmpeg: not found".to_string(),
+            exit_code: 127,
+        };
+        result.annotate_missing_command("custom:latest");
+        assert!(result.stderr.contains("`ffmpeg` is not installed"));
+        assert!(result.stderr.contains("custom:latest"));
+        assert!(result.stderr.contains("SANDBOX_IMAGE"));
+    }
+
+    #[tokio::test]
+    async fn test_destroyed_session_sandbox_frees_its_slot() {
+        let session_id = 424_242;
+        let name = container_name(session_id);
+        let permit = container_slots().acquire(&name).await.ok().flatten();
+        assert!(permit.is_some());
+        container_slots().register(&name, permit);
+        assert!(container_slots().is_live(&name));
+
+        // Removing the container may fail without a Docker daemon, the slot is freed anyway
+        let mut session =
+            crate::agent::AgentSession::new(crate::agent::SessionId::from(session_id));
+        let _ = session.destroy_sandbox().await;
+        assert!(!container_slots().is_live(&name));
+    }
 
     // Integration test - requires Docker
     #[tokio::test]
@@ -714,4 +1504,31 @@ mod tests {
         assert!(!sandbox.is_running());
         Ok(())
     }
+
+    // Integration test - requires Docker
+    #[tokio::test]
+    #[ignore = "Requires Docker daemon"]
+    async fn test_timeout_kills_background_children() -> Result<(), Box<dyn std::error::Error>> {
+        let mut sandbox = SandboxManager::new(12346).await?;
+        sandbox.create_sandbox().await?;
+
+        // The backgrounded sleep keeps stdout open, so the exec hangs until timeout
+        let result = sandbox
+            .exec_command_with_timeout("sleep 999 & sleep 998", Duration::from_secs(2), None)
+            .await;
+        assert!(result.is_err());
+
+        // `[9]` keeps pgrep from matching its own shell command line
+        let check = sandbox
+            .exec_command("pgrep -f 'sleep 99[89]' || true", None)
+            .await?;
+        assert!(
+            check.stdout.trim().is_empty(),
+            "processes survived timeout: {}",
+            check.stdout
+        );
+
+        sandbox.destroy().await?;
+        Ok(())
+    }
 }
@@ -2,6 +2,11 @@
 //!
 //! Provides isolated execution environments for agents using Docker containers.
 
+mod handle;
 pub mod manager;
+pub mod shell;
+mod slots;
 
+pub use handle::SandboxHandle;
 pub use manager::{ExecResult, SandboxManager};
+pub use shell::{shell_quote, ShellCommand};
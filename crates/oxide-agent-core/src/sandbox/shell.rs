@@ -0,0 +1,162 @@
+//! Shell quoting for commands run in the sandbox
+//!
+//! Sandbox commands are executed by `sh -c`, so every value that a user or
+//! the model controls (URLs, paths, file names, search queries) must be
+//! quoted before it is interpolated. [`shell_quote`] quotes a single word;
+//! [`ShellCommand`] builds a command line from a program and its arguments.
+
+use std::fmt;
+
+/// Characters that never need quoting
+fn is_safe_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ',' | ':' | '=' | '+' | '@')
+}
+
+/// Quote `value` as one shell word
+///
+/// Plain words are returned unchanged; everything else is wrapped in single
+/// quotes, inside which the shell expands nothing. Embedded single quotes are
+/// written as `'\''`.
+#[must_use]
+pub fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(is_safe_char) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// A command line built from a trusted program and quoted arguments
+///
+/// ```
+/// use oxide_agent_core::sandbox::ShellCommand;
+///
+/// let cmd = ShellCommand::new("yt-dlp")
+///     .arg("-j")
+///     .end_of_options()
+///     .arg("https://example.com/watch?v=1&t=2")
+///     .raw("2>&1");
+/// assert_eq!(
+///     cmd.to_string(),
+///     "yt-dlp -j -- 'https://example.com/watch?v=1&t=2' 2>&1"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommand {
+    line: String,
+}
+
+impl ShellCommand {
+    /// Start a command; `program` is used verbatim and must not come from input
+    #[must_use]
+    pub fn new(program: &str) -> Self {
+        Self {
+            line: program.to_string(),
+        }
+    }
+
+    /// Append one quoted argument
+    #[must_use]
+    pub fn arg(mut self, value: impl AsRef<str>) -> Self {
+        self.line.push(' ');
+        self.line.push_str(&shell_quote(value.as_ref()));
+        self
+    }
+
+    /// Append quoted arguments
+    #[must_use]
+    pub fn args<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        values.into_iter().fold(self, Self::arg)
+    }
+
+    /// Append `--`, so later arguments are not read as options
+    #[must_use]
+    pub fn end_of_options(self) -> Self {
+        self.raw("--")
+    }
+
+    /// Append a trusted fragment verbatim, e.g. a redirection or a pipe
+    #[must_use]
+    pub fn raw(mut self, fragment: &str) -> Self {
+        self.line.push(' ');
+        self.line.push_str(fragment);
+        self
+    }
+}
+
+impl fmt::Display for ShellCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Run `printf '%s'` with the quoted word through `sh` and return what it received
+    fn echo_through_shell(value: &str) -> String {
+        let cmd = format!("printf '%s' {}", shell_quote(value));
+        let output = match Command::new("sh").arg("-c").arg(&cmd).output() {
+            Ok(output) => output,
+            Err(e) => panic!("sh failed: {e}"),
+        };
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[test]
+    fn test_plain_words_are_unchanged() {
+        assert_eq!(shell_quote("video.mp4"), "video.mp4");
+        assert_eq!(shell_quote("/workspace/a-b_c"), "/workspace/a-b_c");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+    }
+
+    #[test]
+    fn test_hostile_values_reach_the_program_literally() {
+        let values = [
+            "it's",
+            "'",
+            "''",
+            "a'b'c",
+            "`id`",
+            "$(id)",
+            "${HOME}",
+            "$HOME",
+            "a\nb",
+            "line\r\n",
+            "tab\there",
+            "; rm -rf / #",
+            "x && echo pwned",
+            "a | b > c < d",
+            "*.mp4",
+            "~",
+            "!event",
+            "back\\slash\\",
+            "\"double\"",
+            "https://youtu.be/x?a=1&b=2#t",
+            "--exec=touch /tmp/pwned",
+            "видео 🎬 ファイル",
+            "\u{202e}rtl",
+        ];
+        for value in values {
+            assert_eq!(echo_through_shell(value), value, "value: {value:?}");
+        }
+    }
+
+    #[test]
+    fn test_command_builder_quotes_each_argument() {
+        let cmd = ShellCommand::new("stat")
+            .args(["-c", "%s"])
+            .arg("/workspace/downloads/it's $(id).mp4")
+            .raw("2>/dev/null");
+        assert_eq!(
+            cmd.to_string(),
+            r"stat -c '%s' '/workspace/downloads/it'\''s $(id).mp4' 2>/dev/null"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Host-wide limit on live sandbox containers
+//!
+//! Every provider of a session opens its own [`SandboxManager`](super::SandboxManager),
+//! but they share one container per name. A container takes a slot when a
+//! manager first creates or adopts it and gives it back when it is destroyed
+//! or stopped for being idle; a slot that was acquired for a container that
+//! never started is returned as soon as the permit is dropped.
+
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+
+use crate::config::{get_sandbox_max_containers, get_sandbox_slot_wait_secs};
+
+/// Slots of this process, sized by `SANDBOX_MAX_CONTAINERS`
+static SLOTS: LazyLock<ContainerSlots> =
+    LazyLock::new(|| ContainerSlots::new(get_sandbox_max_containers()));
+
+/// Slots shared by every sandbox manager of the process
+pub(super) fn container_slots() -> &'static ContainerSlots {
+    &SLOTS
+}
+
+/// A container holding a slot
+struct LiveContainer {
+    _permit: OwnedSemaphorePermit,
+    last_used: Instant,
+}
+
+/// Counting semaphore plus the containers currently holding a permit
+pub(super) struct ContainerSlots {
+    /// Configured limit; zero means unlimited
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+    live: Mutex<HashMap<String, LiveContainer>>,
+}
+
+impl ContainerSlots {
+    fn new(limit: usize) -> Self {
+        let permits = if limit == 0 {
+            Semaphore::MAX_PERMITS
+        } else {
+            limit
+        };
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(permits)),
+            live: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn live(&self) -> std::sync::MutexGuard<'_, HashMap<String, LiveContainer>> {
+        self.live.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Take a slot for `name`, waiting up to `SANDBOX_SLOT_WAIT_SECS`
+    ///
+    /// Returns `None` when the container already holds a slot.
+    pub(super) async fn acquire(&self, name: &str) -> Result<Option<OwnedSemaphorePermit>> {
+        self.acquire_within(name, Duration::from_secs(get_sandbox_slot_wait_secs()))
+            .await
+    }
+
+    async fn acquire_within(
+        &self,
+        name: &str,
+        wait: Duration,
+    ) -> Result<Option<OwnedSemaphorePermit>> {
+        if self.live().contains_key(name) {
+            return Ok(None);
+        }
+        if let Ok(permit) = Arc::clone(&self.semaphore).try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+
+        info!(
+            container = %name,
+            limit = self.limit,
+            wait_secs = wait.as_secs(),
+            "Sandbox limit reached, waiting for a free slot"
+        );
+        let acquired =
+            tokio::time::timeout(wait, Arc::clone(&self.semaphore).acquire_owned()).await;
+        match acquired {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            _ => {
+                warn!(container = %name, limit = self.limit, "No free sandbox slot");
+                bail!(
+                    "Sandbox capacity reached: {} sandboxes are already running on this host. \
+                     Please try again in a few minutes.",
+                    self.limit
+                )
+            }
+        }
+    }
+
+    /// Hand the slot to the started container `name`
+    ///
+    /// If another manager registered the container meanwhile, `permit` is
+    /// dropped and its slot freed.
+    pub(super) fn register(&self, name: &str, permit: Option<OwnedSemaphorePermit>) {
+        if let Some(permit) = permit {
+            self.live()
+                .entry(name.to_string())
+                .or_insert_with(|| LiveContainer {
+                    _permit: permit,
+                    last_used: Instant::now(),
+                });
+        }
+    }
+
+    /// Mark container `name` as used now, keeping it from the idle reaper
+    pub(super) fn touch(&self, name: &str) {
+        if let Some(container) = self.live().get_mut(name) {
+            container.last_used = Instant::now();
+        }
+    }
+
+    /// Containers not used for at least `idle`
+    pub(super) fn idle(&self, idle: Duration) -> Vec<String> {
+        self.idle_at(idle, Instant::now())
+    }
+
+    fn idle_at(&self, idle: Duration, now: Instant) -> Vec<String> {
+        self.live()
+            .iter()
+            .filter(|(_, container)| now.saturating_duration_since(container.last_used) >= idle)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Free the slot of container `name` if it is still idle for `idle`
+    ///
+    /// Returns `false` when the container was used meanwhile.
+    pub(super) fn release_if_idle(&self, name: &str, idle: Duration) -> bool {
+        let mut live = self.live();
+        let is_idle = live
+            .get(name)
+            .is_some_and(|container| container.last_used.elapsed() >= idle);
+        if is_idle {
+            live.remove(name);
+        }
+        is_idle
+    }
+
+    /// Whether container `name` currently holds a slot
+    pub(super) fn is_live(&self, name: &str) -> bool {
+        self.live().contains_key(name)
+    }
+
+    /// Free the slot of container `name` after it was removed
+    pub(super) fn release(&self, name: &str) {
+        self.live().remove(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn acquire(slots: &ContainerSlots, name: &str) -> Result<Option<OwnedSemaphorePermit>> {
+        slots.acquire_within(name, Duration::ZERO).await
+    }
+
+    #[tokio::test]
+    async fn test_slots_are_limited_and_released_on_teardown() {
+        let slots = ContainerSlots::new(1);
+
+        let Ok(permit @ Some(_)) = acquire(&slots, "agent-sandbox-1").await else {
+            panic!("first container should get a slot");
+        };
+        slots.register("agent-sandbox-1", permit);
+
+        // The same container is shared, a second one has to wait
+        assert!(matches!(acquire(&slots, "agent-sandbox-1").await, Ok(None)));
+        let Err(e) = acquire(&slots, "agent-sandbox-2").await else {
+            panic!("second container should be rejected");
+        };
+        assert!(e.to_string().contains("Sandbox capacity reached: 1"));
+
+        slots.release("agent-sandbox-1");
+        assert!(matches!(
+            acquire(&slots, "agent-sandbox-2").await,
+            Ok(Some(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_frees_its_slot() {
+        let slots = ContainerSlots::new(1);
+
+        // Permit dropped without register, e.g. container creation failed
+        drop(acquire(&slots, "agent-sandbox-1").await);
+        assert!(matches!(
+            acquire(&slots, "agent-sandbox-2").await,
+            Ok(Some(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_waiting_task_gets_released_slot() {
+        let slots = Arc::new(ContainerSlots::new(1));
+        let permit = acquire(&slots, "agent-sandbox-1").await.ok().flatten();
+        slots.register("agent-sandbox-1", permit);
+
+        let waiter = tokio::spawn({
+            let slots = Arc::clone(&slots);
+            async move {
+                slots
+                    .acquire_within("agent-sandbox-2", Duration::from_secs(5))
+                    .await
+                    .is_ok_and(|permit| permit.is_some())
+            }
+        });
+        // Let the waiter block on the full semaphore first
+        tokio::task::yield_now().await;
+        slots.release("agent-sandbox-1");
+        assert!(matches!(waiter.await, Ok(true)));
+    }
+
+    #[tokio::test]
+    async fn test_idle_containers_are_found_and_released() {
+        let slots = ContainerSlots::new(2);
+        for name in ["agent-sandbox-1", "agent-sandbox-2"] {
+            let permit = acquire(&slots, name).await.ok().flatten();
+            slots.register(name, permit);
+        }
+        let later = Instant::now() + Duration::from_secs(600);
+        assert_eq!(slots.idle_at(Duration::from_secs(600), later).len(), 2);
+        assert!(slots.idle(Duration::from_secs(600)).is_empty());
+
+        // A used container is not idle yet; an idle one gives its slot back
+        slots.touch("agent-sandbox-1");
+        assert!(!slots.release_if_idle("agent-sandbox-1", Duration::from_secs(600)));
+        assert!(slots.release_if_idle("agent-sandbox-2", Duration::ZERO));
+        assert!(!slots.is_live("agent-sandbox-2"));
+        assert!(matches!(
+            acquire(&slots, "agent-sandbox-3").await,
+            Ok(Some(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_zero_means_unlimited() {
+        let slots = ContainerSlots::new(0);
+        for i in 0..100 {
+            let name = format!("agent-sandbox-{i}");
+            let permit = acquire(&slots, &name).await.ok().flatten();
+            assert!(permit.is_some());
+            slots.register(&name, permit);
+        }
+    }
+}
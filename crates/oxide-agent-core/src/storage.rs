@@ -48,6 +48,9 @@ pub struct UserConfig {
     pub model_name: Option<String>,
     /// Current dialogue state
     pub state: Option<String>,
+    /// Language hint for voice transcription (`None` = autodetect)
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 /// Interface for storage providers
@@ -95,6 +98,12 @@ pub trait StorageProvider: Send + Sync {
     ) -> Result<Vec<Message>, StorageError>;
     /// Clear chat history for a user
     async fn clear_chat_history(&self, user_id: i64) -> Result<(), StorageError>;
+    /// Replace the whole chat history for a user
+    async fn replace_chat_history(
+        &self,
+        user_id: i64,
+        messages: Vec<Message>,
+    ) -> Result<(), StorageError>;
     /// Save agent memory to storage
     async fn save_agent_memory(
         &self,
@@ -120,6 +129,50 @@ pub struct Message {
     pub content: String,
 }
 
+/// Build an S3 client for the configured R2 bucket
+///
+/// Returns the client and the bucket name. Shared by [`R2Storage`] and the
+/// S3 file host.
+///
+/// # Errors
+///
+/// Returns `StorageError::Config` if any of the `R2_*` settings is missing.
+pub async fn build_r2_client(settings: &AgentSettings) -> Result<(Client, String), StorageError> {
+    let endpoint_url = settings
+        .r2_endpoint_url
+        .as_ref()
+        .ok_or_else(|| StorageError::Config("R2_ENDPOINT_URL is missing".into()))?;
+    let access_key = settings
+        .r2_access_key_id
+        .as_ref()
+        .ok_or_else(|| StorageError::Config("R2_ACCESS_KEY_ID is missing".into()))?;
+    let secret_key = settings
+        .r2_secret_access_key
+        .as_ref()
+        .ok_or_else(|| StorageError::Config("R2_SECRET_ACCESS_KEY is missing".into()))?;
+    let bucket = settings
+        .r2_bucket_name
+        .as_ref()
+        .ok_or_else(|| StorageError::Config("R2_BUCKET_NAME is missing".into()))?;
+
+    let credentials = Credentials::new(access_key, secret_key, None, None, "r2-storage");
+
+    let sdk_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .credentials_provider(credentials)
+        .region(Region::new("auto"))
+        .load()
+        .await;
+
+    let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+        .endpoint_url(endpoint_url)
+        .force_path_style(true)
+        .build();
+
+    let client = Client::from_conf(s3_config);
+
+    Ok((client, bucket.clone()))
+}
+
 /// R2-backed storage implementation
 pub struct R2Storage {
     client: Client,
@@ -134,37 +187,7 @@ impl R2Storage {
     ///
     /// Returns an error if R2 configuration is missing.
     pub async fn new(settings: &AgentSettings) -> Result<Self, StorageError> {
-        let endpoint_url = settings
-            .r2_endpoint_url
-            .as_ref()
-            .ok_or_else(|| StorageError::Config("R2_ENDPOINT_URL is missing".into()))?;
-        let access_key = settings
-            .r2_access_key_id
-            .as_ref()
-            .ok_or_else(|| StorageError::Config("R2_ACCESS_KEY_ID is missing".into()))?;
-        let secret_key = settings
-            .r2_secret_access_key
-            .as_ref()
-            .ok_or_else(|| StorageError::Config("R2_SECRET_ACCESS_KEY is missing".into()))?;
-        let bucket = settings
-            .r2_bucket_name
-            .as_ref()
-            .ok_or_else(|| StorageError::Config("R2_BUCKET_NAME is missing".into()))?;
-
-        let credentials = Credentials::new(access_key, secret_key, None, None, "r2-storage");
-
-        let sdk_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-            .credentials_provider(credentials)
-            .region(Region::new("auto"))
-            .load()
-            .await;
-
-        let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
-            .endpoint_url(endpoint_url)
-            .force_path_style(true)
-            .build();
-
-        let client = Client::from_conf(s3_config);
+        let (client, bucket) = build_r2_client(settings).await?;
 
         let cache = Cache::builder()
             .max_capacity(10_000)
@@ -174,7 +197,7 @@ impl R2Storage {
 
         Ok(Self {
             client,
-            bucket: bucket.clone(),
+            bucket,
             cache,
         })
     }
@@ -397,6 +420,15 @@ impl StorageProvider for R2Storage {
         self.delete_object(&user_history_key(user_id)).await
     }
 
+    /// Replace the whole chat history for a user
+    async fn replace_chat_history(
+        &self,
+        user_id: i64,
+        messages: Vec<Message>,
+    ) -> Result<(), StorageError> {
+        self.save_json(&user_history_key(user_id), &messages).await
+    }
+
     /// Save agent memory to storage
     async fn save_agent_memory(
         &self,
@@ -25,11 +25,19 @@ use mockall::predicate::*;
 pub fn mock_llm_simple(response_text: &'static str) -> crate::llm::MockLlmProvider {
     let mut mock = crate::llm::MockLlmProvider::new();
     mock.expect_chat_completion()
-        .with(always(), always(), always(), always(), always())
-        .returning(move |_, _, _, _, _| Ok(response_text.to_string()));
+        .with(
+            always(),
+            always(),
+            always(),
+            always(),
+            always(),
+            always(),
+            always(),
+        )
+        .returning(move |_, _, _, _, _, _, _| Ok(response_text.to_string()));
 
     mock.expect_transcribe_audio()
-        .returning(|_, _, _| Err(LlmError::Unknown("Not implemented".to_string())));
+        .returning(|_, _, _, _| Err(LlmError::Unknown("Not implemented".to_string())));
 
     mock.expect_analyze_image()
         .returning(|_, _, _, _| Err(LlmError::Unknown("Not implemented".to_string())));
@@ -50,7 +58,8 @@ pub fn mock_llm_simple(response_text: &'static str) -> crate::llm::MockLlmProvid
 /// - `get_user_prompt` / `get_user_model` / `get_user_state` return `Ok(None)`
 /// - `save_message` returns `Ok(())`
 /// - `get_chat_history` returns an empty `Vec<Message>`
-/// - `clear_chat_history` / `clear_agent_memory` / `clear_all_context` return `Ok(())`
+/// - `clear_chat_history` / `replace_chat_history` return `Ok(())`
+/// - `clear_agent_memory` / `clear_all_context` return `Ok(())`
 /// - `save_agent_memory` returns `Ok(())`
 /// - `load_agent_memory` returns `Ok(None)`
 /// - `check_connection` returns `Ok(())`
@@ -91,6 +100,8 @@ pub fn mock_storage_noop() -> crate::storage::MockStorageProvider {
 
     mock.expect_clear_chat_history().returning(|_| Ok(()));
 
+    mock.expect_replace_chat_history().returning(|_, _| Ok(()));
+
     mock.expect_save_agent_memory().returning(|_, _| Ok(()));
 
     mock.expect_load_agent_memory().returning(|_| Ok(None));
@@ -380,6 +380,22 @@ pub fn truncate_str(s: impl AsRef<str>, max_chars: usize) -> String {
         .map_or_else(|| s.to_string(), |(pos, _)| s[..pos].to_string())
 }
 
+/// Truncates a tool output to `max_chars`, appending a notice with the original length.
+///
+/// The notice tells the model that data was cut and how much there was.
+#[must_use]
+pub fn truncate_with_notice(s: impl AsRef<str>, max_chars: usize) -> String {
+    let s = s.as_ref();
+    let total = s.chars().count();
+    if total <= max_chars {
+        return s.to_string();
+    }
+    format!(
+        "{}...\n\n(truncated, {total} chars total)",
+        truncate_str(s, max_chars)
+    )
+}
+
 /// Formats a token count into a human-readable string (e.g., 1100 -> 1.1k).
 #[must_use]
 pub fn format_tokens(n: usize) -> String {
@@ -395,6 +411,20 @@ pub fn format_tokens(n: usize) -> String {
     }
 }
 
+/// Formats a duration in milliseconds into a short human-readable string
+/// (e.g., 850 -> 850ms, 2300 -> 2.3s, 65000 -> 1m 5s).
+#[must_use]
+pub fn format_duration_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        let secs = ms / 1000;
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
 /// Retry a transport API operation with exponential backoff.
 ///
 /// This function is designed for file operations that may fail due to transient
@@ -459,6 +489,15 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_truncate_with_notice() {
+        assert_eq!(truncate_with_notice("short", 10), "short");
+        assert_eq!(
+            truncate_with_notice("привет мир", 6),
+            "привет...\n\n(truncated, 10 chars total)"
+        );
+    }
+
     #[test]
     fn test_truncate_str_unicode() {
         let s = "Hello, world!";
@@ -466,6 +505,13 @@ mod tests {
         assert_eq!(truncate_str(s, 50), "Hello, world!");
     }
 
+    #[test]
+    fn test_format_duration_ms() {
+        assert_eq!(format_duration_ms(850), "850ms");
+        assert_eq!(format_duration_ms(2300), "2.3s");
+        assert_eq!(format_duration_ms(65_000), "1m 5s");
+    }
+
     #[test]
     fn test_clean_html_preserves_code_blocks() {
         // We use `< 3` instead of `<tag>` to ensure it's treated as a naked bracket,
@@ -1,5 +1,5 @@
 use oxide_agent_core::agent::providers::{TodoItem, TodoStatus};
-use oxide_agent_core::agent::{AgentExecutor, AgentSession, AgentStatus, SessionId};
+use oxide_agent_core::agent::{AgentExecutor, AgentSession, AgentStatus, Outcome, SessionId};
 use oxide_agent_core::config::AgentSettings;
 use oxide_agent_core::llm::LlmClient;
 use std::sync::Arc;
@@ -57,3 +57,25 @@ async fn cancellation_token_is_not_overwritten_by_task_start() {
         "todos were not cleared on cancellation"
     );
 }
+
+#[tokio::test]
+async fn detailed_result_reports_cancellation() {
+    let settings = Arc::new(settings_without_llm_providers());
+    let llm = Arc::new(LlmClient::new(&settings));
+    let mut session = AgentSession::new(SessionId::from(2));
+    let token = CancellationToken::new();
+    token.cancel();
+    session.cancellation_token = token;
+
+    let mut executor = AgentExecutor::new(llm, session, settings);
+    let result = match executor.execute_detailed("test", None).await {
+        Ok(result) => result,
+        Err(err) => panic!("cancellation should be reported as an outcome: {err}"),
+    };
+
+    assert_eq!(result.outcome, Outcome::Cancelled);
+    assert!(!result.is_answer());
+    assert!(result.tool_calls.is_empty());
+    assert_eq!(result.usage.total_tokens, 0);
+    assert!(result.into_response().is_err());
+}
@@ -16,6 +16,8 @@ impl LlmProvider for SuccessMock {
         _user_message: &str,
         _model_id: &str,
         _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
     ) -> Result<String, LlmError> {
         Ok("Mock Response".to_string())
     }
@@ -24,6 +26,7 @@ impl LlmProvider for SuccessMock {
         &self,
         _audio_bytes: Vec<u8>,
         _mime_type: &str,
+        _language: Option<String>,
         _model_id: &str,
     ) -> Result<String, LlmError> {
         Err(LlmError::Unknown("Not implemented".to_string()))
@@ -47,6 +50,7 @@ impl LlmProvider for SuccessMock {
         _model_id: &str,
         _max_tokens: u32,
         _json_mode: bool,
+        _stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         Ok(ChatResponse {
             content: Some("Success".to_string()),
@@ -89,6 +93,8 @@ impl LlmProvider for RetrySuccessMock {
         _user_message: &str,
         _model_id: &str,
         _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
     ) -> Result<String, LlmError> {
         unimplemented!()
     }
@@ -97,6 +103,7 @@ impl LlmProvider for RetrySuccessMock {
         &self,
         _audio_bytes: Vec<u8>,
         _mime_type: &str,
+        _language: Option<String>,
         _model_id: &str,
     ) -> Result<String, LlmError> {
         unimplemented!()
@@ -120,6 +127,7 @@ impl LlmProvider for RetrySuccessMock {
         _model_id: &str,
         _max_tokens: u32,
         _json_mode: bool,
+        _stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         let count = self.call_count.fetch_add(1, Ordering::SeqCst);
         if count == 0 {
@@ -174,6 +182,8 @@ impl LlmProvider for AlwaysFailMock {
         _user_message: &str,
         _model_id: &str,
         _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
     ) -> Result<String, LlmError> {
         unimplemented!()
     }
@@ -182,6 +192,7 @@ impl LlmProvider for AlwaysFailMock {
         &self,
         _audio_bytes: Vec<u8>,
         _mime_type: &str,
+        _language: Option<String>,
         _model_id: &str,
     ) -> Result<String, LlmError> {
         unimplemented!()
@@ -205,6 +216,7 @@ impl LlmProvider for AlwaysFailMock {
         _model_id: &str,
         _max_tokens: u32,
         _json_mode: bool,
+        _stop: &[String],
     ) -> Result<ChatResponse, LlmError> {
         self.call_count.fetch_add(1, Ordering::SeqCst);
         Err(LlmError::ApiError("500 Internal Server Error".to_string()))
@@ -30,6 +30,17 @@ pub trait AgentTransport: Send + Sync + 'static {
     async fn notify_loop_detected(&self, _loop_type: LoopType, _iteration: usize) -> Result<()> {
         Ok(())
     }
+
+    /// Show a question the agent asked; the answer goes back via
+    /// `oxide_agent_core::agent::providers::clarification::submit_answer`.
+    async fn request_clarification(&self, _question: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Show the model's full reasoning; only emitted when `SHOW_REASONING` enables it.
+    async fn show_reasoning(&self, _reasoning: &str) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Runtime configuration for progress updates.
@@ -50,7 +61,7 @@ impl ProgressRuntimeConfig {
         }
     }
 
-    #[cfg(test)]
+    #[cfg(any(test, feature = "test-utils"))]
     /// Override the throttle interval for tests.
     pub fn with_throttle(mut self, throttle: Duration) -> Self {
         self.throttle = throttle;
@@ -129,6 +140,16 @@ pub async fn run_progress_loop<T: AgentTransport>(
                     warn!(error = %e, "Loop detection notification failed");
                 }
             }
+            AgentEvent::ClarificationNeeded { question } => {
+                if let Err(e) = transport.request_clarification(question).await {
+                    warn!(error = %e, "Clarification request failed");
+                }
+            }
+            AgentEvent::ReasoningDetails { content } => {
+                if let Err(e) = transport.show_reasoning(content).await {
+                    warn!(error = %e, "Reasoning delivery failed");
+                }
+            }
             _ => {}
         }
 
@@ -163,6 +184,7 @@ mod tests {
     struct DummyTransport {
         updates: Arc<Mutex<usize>>,
         delivered: Arc<Mutex<Vec<(DeliveryMode, String, usize)>>>,
+        questions: Arc<Mutex<Vec<String>>>,
         fail_deliver: bool,
     }
 
@@ -188,6 +210,11 @@ mod tests {
             delivered.push((mode, file_name.to_string(), content.len()));
             Ok(())
         }
+
+        async fn request_clarification(&self, question: &str) -> Result<()> {
+            self.questions.lock().await.push(question.to_string());
+            Ok(())
+        }
     }
 
     #[tokio::test]
@@ -286,4 +313,28 @@ mod tests {
             Err(err) => panic!("progress runtime join failed: {err}"),
         };
     }
+
+    #[tokio::test]
+    async fn clarification_reaches_transport() {
+        let (tx, rx) = mpsc::channel(8);
+        let transport = DummyTransport::default();
+
+        let cfg = ProgressRuntimeConfig::new(3).with_throttle(Duration::from_millis(0));
+        let handle = spawn_progress_runtime(transport.clone(), rx, cfg);
+
+        let send_result = tx
+            .send(AgentEvent::ClarificationNeeded {
+                question: "Which format?".to_string(),
+            })
+            .await;
+        assert!(send_result.is_ok(), "failed to send clarification event");
+        drop(tx);
+
+        let state = match handle.await {
+            Ok(state) => state,
+            Err(err) => panic!("progress runtime join failed: {err}"),
+        };
+        assert_eq!(state.pending_question.as_deref(), Some("Which format?"));
+        assert_eq!(*transport.questions.lock().await, vec!["Which format?"]);
+    }
 }
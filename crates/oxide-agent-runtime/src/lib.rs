@@ -7,8 +7,11 @@
 pub mod agent;
 /// Session registry and lifecycle utilities.
 pub mod session_registry;
+/// Recording transport for end-to-end agent tests.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 
 pub use agent::runtime::{
     spawn_progress_runtime, AgentTransport, DeliveryMode, ProgressRuntimeConfig,
 };
-pub use session_registry::SessionRegistry;
+pub use session_registry::{SessionRegistry, TaskGuard};
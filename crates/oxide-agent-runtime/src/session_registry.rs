@@ -3,18 +3,53 @@
 //! Manages global agent sessions and cancellation tokens.
 
 use oxide_agent_core::agent::{AgentExecutor, SessionId};
-use std::collections::HashMap;
+use oxide_agent_core::metrics;
+use oxide_agent_core::redaction;
+use oxide_agent_core::sandbox::SandboxManager;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+/// How often [`SessionRegistry::wait_until_idle`] re-checks running sessions
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Global session registry for agent executors.
 pub struct SessionRegistry {
     sessions: RwLock<HashMap<SessionId, Arc<RwLock<AgentExecutor>>>>,
     cancellation_tokens: RwLock<HashMap<SessionId, Arc<CancellationToken>>>,
+    /// Sessions claimed by [`SessionRegistry::try_start`] whose task has not ended
+    started: Arc<Mutex<HashSet<SessionId>>>,
+}
+
+/// Claim on a session's task slot, released when dropped
+///
+/// Returned by [`SessionRegistry::try_start`]; keep it alive until the task ends.
+pub struct TaskGuard {
+    id: SessionId,
+    started: Arc<Mutex<HashSet<SessionId>>>,
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl TaskGuard {
+    /// Fresh cancellation token of the claimed task
+    #[must_use]
+    pub fn cancellation_token(&self) -> Arc<CancellationToken> {
+        Arc::clone(&self.cancellation_token)
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
 }
 
 impl Default for SessionRegistry {
@@ -30,9 +65,17 @@ impl SessionRegistry {
         Self {
             sessions: RwLock::new(HashMap::new()),
             cancellation_tokens: RwLock::new(HashMap::new()),
+            started: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
+    fn is_claimed(&self, id: &SessionId) -> bool {
+        self.started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(id)
+    }
+
     /// Get existing session or create new one using factory
     pub async fn get_or_create<F>(&self, id: SessionId, factory: F) -> Arc<RwLock<AgentExecutor>>
     where
@@ -53,6 +96,7 @@ impl SessionRegistry {
         {
             let mut sessions = self.sessions.write().await;
             sessions.insert(id, executor.clone());
+            metrics::set_active_sessions(sessions.len());
         }
 
         {
@@ -83,6 +127,7 @@ impl SessionRegistry {
         {
             let mut sessions = self.sessions.write().await;
             sessions.insert(id, executor_arc);
+            metrics::set_active_sessions(sessions.len());
         }
 
         {
@@ -93,6 +138,9 @@ impl SessionRegistry {
 
     /// Check if a task is currently running for this session
     pub async fn is_running(&self, id: &SessionId) -> bool {
+        if self.is_claimed(id) {
+            return true;
+        }
         let executor_arc = {
             let sessions = self.sessions.read().await;
             sessions.get(id).cloned()
@@ -124,6 +172,72 @@ impl SessionRegistry {
         }
     }
 
+    /// Cancel the current task of every session
+    ///
+    /// Returns the number of sessions that had a task running.
+    pub async fn cancel_all(&self) -> usize {
+        let running = self.running_count().await;
+        for token in self.cancellation_tokens.read().await.values() {
+            token.cancel();
+        }
+        info!(running, "Cancellation requested for all sessions");
+        running
+    }
+
+    /// Wait until no session has a running task, polling until `timeout` elapses.
+    ///
+    /// Returns `true` if every session became idle in time.
+    pub async fn wait_until_idle(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.running_count().await > 0 {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+        }
+        true
+    }
+
+    /// IDs of all registered sessions
+    pub async fn session_ids(&self) -> Vec<SessionId> {
+        self.sessions.read().await.keys().copied().collect()
+    }
+
+    async fn running_count(&self) -> usize {
+        let mut running = 0;
+        for id in &self.session_ids().await {
+            if self.is_running(id).await {
+                running += 1;
+            }
+        }
+        running
+    }
+
+    /// Check that no task runs in the session and claim it for a new one,
+    /// in one step so concurrent callers cannot both start a task.
+    ///
+    /// The claim renews the session's cancellation token and lasts until the
+    /// returned guard is dropped. Returns `None` if the session is busy.
+    pub async fn try_start(&self, id: &SessionId) -> Option<TaskGuard> {
+        // Serializes claims with token renewals and cancellations
+        let mut tokens = self.cancellation_tokens.write().await;
+        if self.is_running(id).await {
+            return None;
+        }
+        self.started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(*id);
+
+        let cancellation_token = Arc::new(CancellationToken::new());
+        tokens.insert(*id, Arc::clone(&cancellation_token));
+        Some(TaskGuard {
+            id: *id,
+            started: Arc::clone(&self.started),
+            cancellation_token,
+        })
+    }
+
     /// Renew the cancellation token for a session
     pub async fn renew_cancellation_token(&self, id: &SessionId) {
         let mut tokens = self.cancellation_tokens.write().await;
@@ -178,11 +292,38 @@ impl SessionRegistry {
         Ok(action(&mut executor).await)
     }
 
+    /// Remove the sandbox container of a session and free its slot
+    ///
+    /// Goes through the executor when it is free, and removes the container
+    /// without it while a task still holds it (e.g. one that did not stop
+    /// within the shutdown grace period).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container cannot be removed.
+    pub async fn destroy_sandbox(&self, id: &SessionId) -> anyhow::Result<()> {
+        match self
+            .with_executor_mut(id, |executor| {
+                Box::pin(async move { executor.session_mut().destroy_sandbox().await })
+            })
+            .await
+        {
+            Ok(destroyed) => destroyed,
+            Err(_) => SandboxManager::destroy_for_user(id.as_i64()).await,
+        }
+    }
+
     /// Remove a session from the registry
+    ///
+    /// The sandbox container is kept; the idle reaper stops it and frees its
+    /// slot once it has been unused for `SANDBOX_IDLE_TIMEOUT_SECS`. Credentials
+    /// the user gave the session are no longer masked in logs.
     pub async fn remove(&self, id: &SessionId) {
+        redaction::forget_user_secrets(id.as_i64());
         {
             let mut sessions = self.sessions.write().await;
             sessions.remove(id);
+            metrics::set_active_sessions(sessions.len());
         }
 
         {
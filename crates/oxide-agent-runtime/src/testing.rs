@@ -0,0 +1,153 @@
+//! Test utilities for driving the agent without a chat platform.
+//!
+//! [`MockTransport`] implements [`AgentTransport`] and records every call in
+//! order, so integration tests can run `spawn_progress_runtime` against a
+//! scripted LLM and assert the exact progress sequence and delivered files.
+//! Available in this crate's tests and, for other crates, with the
+//! `test-utils` feature.
+
+use crate::{AgentTransport, DeliveryMode};
+use anyhow::Result;
+use async_trait::async_trait;
+use oxide_agent_core::agent::loop_detection::LoopType;
+use oxide_agent_core::agent::progress::ProgressState;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// A call the runtime made on the transport.
+#[derive(Debug, Clone)]
+pub enum TransportEvent {
+    /// The progress message was sent or edited to show this state.
+    Progress(ProgressState),
+    /// A file was delivered to the user.
+    File {
+        /// Delivery semantics requested by the runtime.
+        mode: DeliveryMode,
+        /// Name of the delivered file.
+        file_name: String,
+        /// File content.
+        content: Vec<u8>,
+    },
+    /// The user was told about a detected loop.
+    LoopDetected {
+        /// Kind of loop.
+        loop_type: LoopType,
+        /// Iteration at which it was detected.
+        iteration: usize,
+    },
+    /// The agent asked the user a question.
+    Clarification(String),
+    /// The model's full reasoning was shown.
+    Reasoning(String),
+}
+
+/// Transport that records calls instead of talking to a chat platform.
+///
+/// Clones share the same record, so a test keeps one clone and hands the
+/// other to the runtime.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    events: Arc<Mutex<Vec<TransportEvent>>>,
+    fail_deliveries: bool,
+}
+
+impl MockTransport {
+    /// Create a transport with an empty record.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make every file delivery fail, e.g. to test confirmation errors.
+    #[must_use]
+    pub fn with_failing_deliveries(mut self) -> Self {
+        self.fail_deliveries = true;
+        self
+    }
+
+    fn record(&self) -> MutexGuard<'_, Vec<TransportEvent>> {
+        self.events.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Every recorded call, in order.
+    #[must_use]
+    pub fn events(&self) -> Vec<TransportEvent> {
+        self.record().clone()
+    }
+
+    /// Progress states the message was updated with, in order.
+    #[must_use]
+    pub fn progress_updates(&self) -> Vec<ProgressState> {
+        self.record()
+            .iter()
+            .filter_map(|event| match event {
+                TransportEvent::Progress(state) => Some(state.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Last progress state shown to the user.
+    #[must_use]
+    pub fn last_progress(&self) -> Option<ProgressState> {
+        self.progress_updates().pop()
+    }
+
+    /// Delivered files as `(file_name, content)`, in order.
+    #[must_use]
+    pub fn files(&self) -> Vec<(String, Vec<u8>)> {
+        self.record()
+            .iter()
+            .filter_map(|event| match event {
+                TransportEvent::File {
+                    file_name, content, ..
+                } => Some((file_name.clone(), content.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AgentTransport for MockTransport {
+    async fn update_progress(&self, state: &ProgressState) -> Result<()> {
+        self.record().push(TransportEvent::Progress(state.clone()));
+        Ok(())
+    }
+
+    async fn deliver_file(
+        &self,
+        mode: DeliveryMode,
+        file_name: &str,
+        content: &[u8],
+    ) -> Result<()> {
+        if self.fail_deliveries {
+            anyhow::bail!("simulated delivery failure");
+        }
+        self.record().push(TransportEvent::File {
+            mode,
+            file_name: file_name.to_string(),
+            content: content.to_vec(),
+        });
+        Ok(())
+    }
+
+    async fn notify_loop_detected(&self, loop_type: LoopType, iteration: usize) -> Result<()> {
+        self.record().push(TransportEvent::LoopDetected {
+            loop_type,
+            iteration,
+        });
+        Ok(())
+    }
+
+    async fn request_clarification(&self, question: &str) -> Result<()> {
+        self.record()
+            .push(TransportEvent::Clarification(question.to_string()));
+        Ok(())
+    }
+
+    async fn show_reasoning(&self, reasoning: &str) -> Result<()> {
+        self.record()
+            .push(TransportEvent::Reasoning(reasoning.to_string()));
+        Ok(())
+    }
+}
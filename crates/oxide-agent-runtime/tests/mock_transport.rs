@@ -0,0 +1,255 @@
+use oxide_agent_core::agent::progress::AgentEvent;
+use oxide_agent_core::agent::{AgentExecutor, AgentSession, Outcome, SessionId};
+use oxide_agent_core::config::AgentSettings;
+use oxide_agent_core::llm::{
+    ChatResponse, LlmClient, LlmError, LlmProvider, Message, ToolDefinition,
+};
+use oxide_agent_runtime::testing::{MockTransport, TransportEvent};
+use oxide_agent_runtime::{spawn_progress_runtime, DeliveryMode, ProgressRuntimeConfig};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Returns the scripted structured outputs in order
+struct ScriptedLlm {
+    replies: Mutex<VecDeque<String>>,
+}
+
+impl ScriptedLlm {
+    fn new(replies: &[serde_json::Value]) -> Self {
+        Self {
+            replies: Mutex::new(replies.iter().map(ToString::to_string).collect()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for ScriptedLlm {
+    async fn chat_completion(
+        &self,
+        _system_prompt: &str,
+        _history: &[Message],
+        _user_message: &str,
+        _model_id: &str,
+        _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
+    ) -> Result<String, LlmError> {
+        Err(LlmError::Unknown("Not scripted".to_string()))
+    }
+
+    async fn transcribe_audio(
+        &self,
+        _audio_bytes: Vec<u8>,
+        _mime_type: &str,
+        _language: Option<String>,
+        _model_id: &str,
+    ) -> Result<String, LlmError> {
+        Err(LlmError::Unknown("Not scripted".to_string()))
+    }
+
+    async fn analyze_image(
+        &self,
+        _image_bytes: Vec<u8>,
+        _text_prompt: &str,
+        _system_prompt: &str,
+        _model_id: &str,
+    ) -> Result<String, LlmError> {
+        Err(LlmError::Unknown("Not scripted".to_string()))
+    }
+
+    async fn chat_with_tools(
+        &self,
+        _system_prompt: &str,
+        _messages: &[Message],
+        _tools: &[ToolDefinition],
+        _model_id: &str,
+        _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
+    ) -> Result<ChatResponse, LlmError> {
+        let reply = self
+            .replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .ok_or_else(|| LlmError::Unknown("Script exhausted".to_string()))?;
+        Ok(ChatResponse {
+            content: Some(reply),
+            tool_calls: vec![],
+            finish_reason: "stop".to_string(),
+            reasoning_content: None,
+            usage: None,
+        })
+    }
+}
+
+fn executor_with_script(session_id: i64, replies: &[serde_json::Value]) -> AgentExecutor {
+    let settings = Arc::new(AgentSettings {
+        chat_model_id: Some("test-model".to_string()),
+        chat_model_provider: Some("mock-provider".to_string()),
+        ..AgentSettings::default()
+    });
+    let mut llm = LlmClient::new(&settings);
+    llm.register_provider(
+        "mock-provider".to_string(),
+        Arc::new(ScriptedLlm::new(replies)),
+    );
+    AgentExecutor::new(
+        Arc::new(llm),
+        AgentSession::new(SessionId::from(session_id)),
+        settings,
+    )
+}
+
+#[tokio::test]
+async fn agent_run_is_rendered_through_the_transport() {
+    let mut executor = executor_with_script(
+        -8_000_006,
+        &[
+            serde_json::json!({
+                "thought": "Plan the work",
+                "tool_call": {
+                    "name": "write_todos",
+                    "arguments": {"todos": [{"description": "Say hello", "status": "completed"}]}
+                },
+                "final_answer": null
+            }),
+            serde_json::json!({
+                "thought": "Done",
+                "tool_call": null,
+                "final_answer": "Hello from the agent"
+            }),
+        ],
+    );
+
+    let transport = MockTransport::new();
+    let (tx, rx) = mpsc::channel(64);
+    let config = ProgressRuntimeConfig::new(10).with_throttle(Duration::ZERO);
+    let progress = spawn_progress_runtime(transport.clone(), rx, config);
+
+    let answer = match executor.execute("Say hello", Some(tx)).await {
+        Ok(answer) => answer,
+        Err(e) => panic!("scripted run should succeed: {e}"),
+    };
+    assert_eq!(answer, "Hello from the agent");
+
+    let Ok(final_state) = progress.await else {
+        panic!("progress runtime panicked");
+    };
+    assert!(final_state.is_finished);
+
+    let updates = transport.progress_updates();
+    assert!(updates.len() >= 2, "expected several edits: {updates:?}");
+    assert!(updates.iter().any(|state| state
+        .steps
+        .iter()
+        .any(|step| step.tool_name.as_deref() == Some("write_todos"))));
+    assert!(
+        updates
+            .windows(2)
+            .all(|pair| pair[0].current_iteration <= pair[1].current_iteration),
+        "iterations went backwards"
+    );
+    let Some(last) = transport.last_progress() else {
+        panic!("no progress was shown");
+    };
+    assert!(last.is_finished);
+    assert!(last.error.is_none());
+    assert!(transport.files().is_empty());
+}
+
+#[tokio::test]
+async fn giving_up_with_finish_task_ends_the_run() {
+    let mut executor = executor_with_script(
+        -8_000_007,
+        &[serde_json::json!({
+            "thought": "The video is gone",
+            "tool_call": {
+                "name": "finish_task",
+                "arguments": {"message": "The video was deleted by its owner.", "success": false}
+            },
+            "final_answer": null
+        })],
+    );
+
+    let transport = MockTransport::new();
+    let (tx, rx) = mpsc::channel(64);
+    let config = ProgressRuntimeConfig::new(10).with_throttle(Duration::ZERO);
+    let progress = spawn_progress_runtime(transport.clone(), rx, config);
+
+    let result = match executor
+        .execute_detailed("Download the video", Some(tx))
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => panic!("giving up is an outcome, not an error: {e}"),
+    };
+    assert_eq!(result.outcome, Outcome::GaveUp);
+    assert_eq!(result.iterations, 1);
+    assert!(result.is_answer());
+    assert_eq!(result.response, "The video was deleted by its owner.");
+
+    let Ok(final_state) = progress.await else {
+        panic!("progress runtime panicked");
+    };
+    assert!(final_state.is_finished);
+    assert!(final_state.error.is_none());
+}
+
+#[tokio::test]
+async fn file_deliveries_are_recorded_and_confirmed() {
+    let transport = MockTransport::new();
+    let (tx, rx) = mpsc::channel(8);
+    let config = ProgressRuntimeConfig::new(10).with_throttle(Duration::ZERO);
+    let progress = spawn_progress_runtime(transport.clone(), rx, config);
+
+    let (confirmation_tx, confirmation_rx) = oneshot::channel();
+    let sent = tx
+        .send(AgentEvent::FileToSendWithConfirmation {
+            file_name: "report.txt".to_string(),
+            content: b"done".to_vec(),
+            sandbox_path: "/workspace/report.txt".to_string(),
+            confirmation_tx,
+        })
+        .await;
+    assert!(sent.is_ok());
+    assert_eq!(confirmation_rx.await.ok(), Some(Ok(())));
+    drop(tx);
+    assert!(progress.await.is_ok());
+
+    assert_eq!(
+        transport.files(),
+        vec![("report.txt".to_string(), b"done".to_vec())]
+    );
+    assert!(matches!(
+        transport.events().first(),
+        Some(TransportEvent::File {
+            mode: DeliveryMode::Confirmed,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn failing_deliveries_are_reported_to_the_agent() {
+    let transport = MockTransport::new().with_failing_deliveries();
+    let (tx, rx) = mpsc::channel(8);
+    let progress = spawn_progress_runtime(transport.clone(), rx, ProgressRuntimeConfig::new(10));
+
+    let (confirmation_tx, confirmation_rx) = oneshot::channel();
+    let sent = tx
+        .send(AgentEvent::FileToSendWithConfirmation {
+            file_name: "report.txt".to_string(),
+            content: Vec::new(),
+            sandbox_path: "/workspace/report.txt".to_string(),
+            confirmation_tx,
+        })
+        .await;
+    assert!(sent.is_ok());
+    assert!(matches!(confirmation_rx.await, Ok(Err(_))));
+    drop(tx);
+    assert!(progress.await.is_ok());
+    assert!(transport.files().is_empty());
+}
@@ -1,76 +1,15 @@
 use dotenvy::dotenv;
+use oxide_agent_core::agent::skills::SkillLoader;
 use oxide_agent_core::config::AgentSettings;
+use oxide_agent_core::redaction::RedactionPatterns;
 use oxide_agent_transport_telegram::config::{BotSettings, TelegramSettings};
 use oxide_agent_transport_telegram::runner::run_bot;
-use regex::Regex;
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{error, info};
 use tracing_subscriber::{prelude::*, EnvFilter};
 
-/// Regex patterns for redacting sensitive data
-struct RedactionPatterns {
-    token1: Regex,
-    token2: Regex,
-    token3: Regex,
-    r2_1: Regex,
-    r2_2: Regex,
-    r2_3: Regex,
-    r2_4: Regex,
-}
-
-impl RedactionPatterns {
-    /// Initialize all regex patterns
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if any regex pattern is invalid
-    fn new() -> Result<Self, regex::Error> {
-        Ok(Self {
-            token1: Regex::new(r"(https?://[^/]+/bot)([0-9]+:[A-Za-z0-9_-]+)(/['\s]*)")?,
-            token2: Regex::new(r"([0-9]{8,10}:[A-Za-z0-9_-]{35})")?,
-            token3: Regex::new(r"(bot[0-9]{8,10}:)[A-Za-z0-9_-]+")?,
-            r2_1: Regex::new(r"R2_ACCESS_KEY_ID=[^\s&]+")?,
-            r2_2: Regex::new(r"R2_SECRET_ACCESS_KEY=[^\s&]+")?,
-            r2_3: Regex::new(r"'aws_access_key_id': '[^']*'")?,
-            r2_4: Regex::new(r"'aws_secret_access_key': '[^']*'")?,
-        })
-    }
-
-    fn redact(&self, input: &str) -> String {
-        let mut output = input.to_string();
-        output = self
-            .token1
-            .replace_all(&output, "$1[TELEGRAM_TOKEN]$3")
-            .to_string();
-        output = self
-            .token2
-            .replace_all(&output, "[TELEGRAM_TOKEN]")
-            .to_string();
-        output = self
-            .token3
-            .replace_all(&output, "$1[TELEGRAM_TOKEN]")
-            .to_string();
-        output = self
-            .r2_1
-            .replace_all(&output, "R2_ACCESS_KEY_ID=[MASKED]")
-            .to_string();
-        output = self
-            .r2_2
-            .replace_all(&output, "R2_SECRET_ACCESS_KEY=[MASKED]")
-            .to_string();
-        output = self
-            .r2_3
-            .replace_all(&output, "'aws_access_key_id': '[MASKED]'")
-            .to_string();
-        output = self
-            .r2_4
-            .replace_all(&output, "'aws_secret_access_key': '[MASKED]'")
-            .to_string();
-        output
-    }
-}
-
 struct RedactingWriter<W: Write> {
     inner: W,
     patterns: Arc<RedactionPatterns>,
@@ -123,11 +62,33 @@ where
     }
 }
 
+/// Log line format (`LOG_FORMAT`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    /// Human-readable lines (default)
+    Pretty,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+impl LogFormat {
+    fn from_env() -> Self {
+        match std::env::var("LOG_FORMAT") {
+            Ok(v) if v.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Pretty,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load .env file
     dotenv().ok();
 
+    if std::env::args().any(|arg| arg == "--validate-skills") {
+        std::process::exit(validate_skills());
+    }
+
     // Initialize redaction patterns early (before logging)
     let patterns = Arc::new(RedactionPatterns::new().map_err(|e| {
         eprintln!("Failed to compile regex patterns: {e}");
@@ -142,11 +103,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load settings
     let settings = init_settings();
 
+    match oxide_agent_core::metrics::init_exporter() {
+        Ok(Some(addr)) => info!("Prometheus metrics exposed on http://{}/metrics", addr),
+        Ok(None) => {}
+        Err(e) => error!("Failed to start metrics exporter: {}", e),
+    }
+
     run_bot(settings).await;
 
     Ok(())
 }
 
+/// Check every skill file and print problems; returns the process exit code.
+fn validate_skills() -> i32 {
+    let skills_dir = PathBuf::from(oxide_agent_core::config::get_skills_dir());
+    let report = match SkillLoader::new(skills_dir.clone()).validate_all() {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Failed to read skills directory: {e}");
+            return 2;
+        }
+    };
+
+    for (path, message) in &report.errors {
+        eprintln!("{}: {message}", path.display());
+    }
+
+    if report.is_ok() {
+        println!(
+            "{} skill file(s) in {} are valid",
+            report.checked,
+            skills_dir.display()
+        );
+        0
+    } else {
+        eprintln!(
+            "{} problem(s) found in {} skill file(s)",
+            report.errors.len(),
+            report.checked
+        );
+        1
+    }
+}
+
 fn init_logging(patterns: Arc<RedactionPatterns>) {
     let make_writer = RedactingMakeWriter::new(io::stderr, patterns);
 
@@ -167,9 +166,20 @@ fn init_logging(patterns: Arc<RedactionPatterns>) {
         })
     };
 
+    // Redaction runs on the formatted output, so JSON fields are masked too
+    let fmt_layer = match LogFormat::from_env() {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_writer(make_writer)
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .with_writer(make_writer)
+            .boxed(),
+    };
+
     tracing_subscriber::registry()
         .with(filter)
-        .with(tracing_subscriber::fmt::layer().with_writer(make_writer))
+        .with(fmt_layer)
         .init();
 }
 
@@ -181,6 +191,10 @@ fn init_settings() -> Arc<BotSettings> {
             std::process::exit(1);
         }
     };
+    if let Err(e) = agent_settings.validate() {
+        error!("Agent configuration is inconsistent: {}", e);
+        std::process::exit(1);
+    }
     let telegram_settings = match TelegramSettings::new() {
         Ok(settings) => settings,
         Err(e) => {
@@ -0,0 +1,37 @@
+//! HTTP transport configuration
+//!
+//! All settings come from environment variables.
+
+/// Default listen address (loopback only)
+pub const HTTP_TRANSPORT_ADDR: &str = "127.0.0.1:8080";
+/// Finished tasks kept in memory for clients to fetch results and files
+pub const HTTP_MAX_FINISHED_TASKS: usize = 256;
+/// Upper bound for the `wait` long-poll parameter (seconds)
+pub const HTTP_MAX_WAIT_SECS: u64 = 120;
+/// Grace period (seconds) for running tasks on shutdown
+pub const HTTP_SHUTDOWN_GRACE_SECS: u64 = 30;
+/// Tasks allowed to run at once across all sessions
+pub const HTTP_MAX_RUNNING_TASKS: usize = 32;
+/// Sandbox container namespace, keeps HTTP sessions apart from Telegram's
+pub const HTTP_SANDBOX_NAMESPACE: &str = "http";
+
+/// Get the address the HTTP transport listens on.
+///
+/// Environment variable: `HTTP_TRANSPORT_ADDR`.
+#[must_use]
+pub fn get_http_transport_addr() -> String {
+    std::env::var("HTTP_TRANSPORT_ADDR")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| HTTP_TRANSPORT_ADDR.to_string())
+}
+
+/// Get the bearer token required on every request (`None` disables auth).
+///
+/// Environment variable: `HTTP_TRANSPORT_TOKEN`.
+#[must_use]
+pub fn get_http_transport_token() -> Option<String> {
+    std::env::var("HTTP_TRANSPORT_TOKEN")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
@@ -0,0 +1,12 @@
+#![deny(missing_docs)]
+//! HTTP transport adapter for Oxide Agent.
+//!
+//! Accepts tasks over HTTP, streams progress as server-sent events and
+//! returns the final result, so the agent can back a web UI without Telegram.
+
+/// HTTP transport configuration.
+pub mod config;
+/// HTTP server and task endpoints.
+pub mod server;
+/// `AgentTransport` implementation backed by per-task state.
+pub mod transport;
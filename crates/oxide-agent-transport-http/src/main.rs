@@ -0,0 +1,39 @@
+use dotenvy::dotenv;
+use oxide_agent_core::config::AgentSettings;
+use oxide_agent_core::llm::LlmClient;
+use oxide_agent_transport_http::config::get_http_transport_addr;
+use oxide_agent_transport_http::server::serve;
+use std::sync::Arc;
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(
+            "oxide_agent_http=info,oxide_agent_core=info,oxide_agent_transport_http=info,hyper=warn",
+        )
+    });
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
+    info!("Starting Oxide Agent HTTP transport...");
+
+    let settings = match AgentSettings::new() {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!("Failed to load agent configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = settings.validate() {
+        error!("Agent configuration is inconsistent: {}", e);
+        std::process::exit(1);
+    }
+    let settings = Arc::new(settings);
+    let llm = Arc::new(LlmClient::new(settings.as_ref()));
+
+    serve(&get_http_transport_addr(), llm, settings).await?;
+    Ok(())
+}
@@ -0,0 +1,508 @@
+//! HTTP server
+//!
+//! Endpoints:
+//! - `POST /tasks` `{"session_id": 1, "task": "..."}` starts a task (202)
+//! - `GET /tasks/{id}[?wait=SECS]` returns status, result and progress (long-poll with `wait`)
+//! - `GET /tasks/{id}/events` streams progress as server-sent events, ending with `done`
+//! - `GET /tasks/{id}/files/{index}` downloads a file the agent delivered
+//! - `POST /tasks/{id}/cancel` cancels a running task
+//! - `POST /tasks/{id}/answer` `{"answer": "..."}` answers the agent's pending question
+//!
+//! When `HTTP_TRANSPORT_TOKEN` is set, every request needs `Authorization: Bearer <token>`.
+//! Without it the server only binds to loopback addresses.
+
+use crate::config::{
+    get_http_transport_token, HTTP_MAX_FINISHED_TASKS, HTTP_MAX_RUNNING_TASKS, HTTP_MAX_WAIT_SECS,
+    HTTP_SANDBOX_NAMESPACE, HTTP_SHUTDOWN_GRACE_SECS,
+};
+use crate::transport::{HttpAgentTransport, ProgressSnapshot, TaskOutcome, TaskState};
+use anyhow::{bail, Result};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::stream::{self, Stream};
+use oxide_agent_core::agent::loop_detection::ContentLoopOverride;
+use oxide_agent_core::agent::providers::clarification;
+use oxide_agent_core::agent::{AgentExecutor, AgentSession, SessionId};
+use oxide_agent_core::config::{AgentSettings, AGENT_MAX_ITERATIONS};
+use oxide_agent_core::llm::LlmClient;
+use oxide_agent_core::sandbox::SandboxManager;
+use oxide_agent_runtime::{spawn_progress_runtime, ProgressRuntimeConfig, SessionRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, RwLock, Semaphore};
+use tracing::{error, info, warn};
+
+/// Shared server state
+pub struct AppState {
+    llm: Arc<LlmClient>,
+    settings: Arc<AgentSettings>,
+    sessions: SessionRegistry,
+    tasks: RwLock<BTreeMap<u64, Arc<TaskState>>>,
+    next_task_id: AtomicU64,
+    /// Caps tasks running at once, see `HTTP_MAX_RUNNING_TASKS`
+    task_slots: Arc<Semaphore>,
+    token: Option<String>,
+}
+
+impl AppState {
+    /// Create server state; the bearer token is read from `HTTP_TRANSPORT_TOKEN`
+    #[must_use]
+    pub fn new(llm: Arc<LlmClient>, settings: Arc<AgentSettings>) -> Self {
+        Self {
+            llm,
+            settings,
+            sessions: SessionRegistry::new(),
+            tasks: RwLock::new(BTreeMap::new()),
+            next_task_id: AtomicU64::new(1),
+            task_slots: Arc::new(Semaphore::new(HTTP_MAX_RUNNING_TASKS)),
+            token: get_http_transport_token(),
+        }
+    }
+
+    async fn task(&self, task_id: u64) -> Result<Arc<TaskState>, ApiError> {
+        self.tasks
+            .read()
+            .await
+            .get(&task_id)
+            .cloned()
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "task not found"))
+    }
+
+    /// Store a task, evicting the oldest finished tasks beyond the limit
+    async fn insert_task(&self, task: Arc<TaskState>) -> u64 {
+        let task_id = self.next_task_id.fetch_add(1, Ordering::SeqCst);
+        let mut tasks = self.tasks.write().await;
+        tasks.insert(task_id, task);
+
+        let finished: Vec<u64> = tasks
+            .iter()
+            .filter(|(_, task)| !task.outcome().is_running())
+            .map(|(id, _)| *id)
+            .collect();
+        let excess = finished.len().saturating_sub(HTTP_MAX_FINISHED_TASKS);
+        for id in finished.into_iter().take(excess) {
+            tasks.remove(&id);
+        }
+        task_id
+    }
+}
+
+/// Error response with a JSON body
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateTaskRequest {
+    session_id: i64,
+    task: String,
+    /// Content loop settings for this task only
+    #[serde(default)]
+    content_loop: Option<ContentLoopOverride>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateTaskResponse {
+    task_id: u64,
+    session_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnswerRequest {
+    answer: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WaitQuery {
+    #[serde(default)]
+    wait: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct FileView {
+    index: usize,
+    name: String,
+    size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct TaskView {
+    task_id: u64,
+    session_id: i64,
+    #[serde(flatten)]
+    outcome: TaskOutcome,
+    progress: ProgressSnapshot,
+    files: Vec<FileView>,
+}
+
+impl TaskView {
+    fn new(task_id: u64, task: &TaskState) -> Self {
+        Self {
+            task_id,
+            session_id: task.session_id().as_i64(),
+            outcome: task.outcome(),
+            progress: task.progress(),
+            files: task
+                .file_list()
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, size))| FileView { index, name, size })
+                .collect(),
+        }
+    }
+}
+
+/// Build the router with all task endpoints
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/tasks", post(create_task))
+        .route("/tasks/{id}", get(get_task))
+        .route("/tasks/{id}/events", get(task_events))
+        .route("/tasks/{id}/files/{index}", get(download_file))
+        .route("/tasks/{id}/cancel", post(cancel_task))
+        .route("/tasks/{id}/answer", post(answer_task))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            require_token,
+        ))
+        .with_state(state)
+}
+
+/// Serve the HTTP transport until Ctrl-C or SIGTERM, then drain running
+/// tasks and remove their sandboxes.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound, if it is not a loopback
+/// address while `HTTP_TRANSPORT_TOKEN` is unset, or if the server fails.
+pub async fn serve(addr: &str, llm: Arc<LlmClient>, settings: Arc<AgentSettings>) -> Result<()> {
+    let state = Arc::new(AppState::new(llm, settings));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    if state.token.is_none() {
+        if !local_addr.ip().is_loopback() {
+            bail!(
+                "HTTP_TRANSPORT_TOKEN must be set to listen on non-loopback address {local_addr}"
+            );
+        }
+        warn!("HTTP_TRANSPORT_TOKEN is not set, the API is unauthenticated");
+    }
+
+    SandboxManager::set_namespace(HTTP_SANDBOX_NAMESPACE);
+    match SandboxManager::cleanup_stale_sandboxes().await {
+        Ok(0) => {}
+        Ok(removed) => info!("Removed {removed} stale sandbox container(s)."),
+        Err(e) => warn!("Stale sandbox cleanup skipped: {e}"),
+    }
+    SandboxManager::spawn_idle_reaper();
+
+    info!("HTTP transport listening on {local_addr}");
+    axum::serve(listener, router(Arc::clone(&state)))
+        .with_graceful_shutdown(async {
+            wait_for_shutdown_signal().await;
+            info!("Shutdown signal received, no longer accepting requests...");
+        })
+        .await?;
+
+    state.sessions.cancel_all().await;
+    if !state
+        .sessions
+        .wait_until_idle(Duration::from_secs(HTTP_SHUTDOWN_GRACE_SECS))
+        .await
+    {
+        warn!("Grace period elapsed with agent tasks still running");
+    }
+    for session_id in state.sessions.session_ids().await {
+        if let Err(e) = state.sessions.destroy_sandbox(&session_id).await {
+            warn!(session_id = %session_id, error = %e, "Failed to destroy sandbox on shutdown");
+        }
+    }
+    info!("Shutdown complete.");
+    Ok(())
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM.
+async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+}
+
+async fn require_token(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(expected) = state.token.as_deref() {
+        let authorized = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| token == expected);
+        if !authorized {
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "missing or invalid bearer token",
+            ));
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+async fn create_task(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateTaskRequest>,
+) -> Result<(StatusCode, Json<CreateTaskResponse>), ApiError> {
+    if request.task.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "task is empty"));
+    }
+
+    let task_slot = Arc::clone(&state.task_slots)
+        .try_acquire_owned()
+        .map_err(|_| {
+            ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "too many tasks running, try again later",
+            )
+        })?;
+
+    let session_id = SessionId::from(request.session_id);
+    let executor = state
+        .sessions
+        .get_or_create(session_id, || {
+            AgentExecutor::new(
+                Arc::clone(&state.llm),
+                AgentSession::new(session_id),
+                Arc::clone(&state.settings),
+            )
+        })
+        .await;
+    // Check and claim at once, so concurrent submits cannot both start a task
+    let guard = state.sessions.try_start(&session_id).await.ok_or_else(|| {
+        ApiError::new(
+            StatusCode::CONFLICT,
+            "a task is already running in this session",
+        )
+    })?;
+    let cancellation_token = guard.cancellation_token();
+
+    let task = Arc::new(TaskState::new(session_id, AGENT_MAX_ITERATIONS));
+    let task_id = state.insert_task(Arc::clone(&task)).await;
+    info!(task_id, session_id = %session_id, "HTTP task accepted");
+
+    tokio::spawn(async move {
+        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        let progress = spawn_progress_runtime(
+            HttpAgentTransport::new(Arc::clone(&task)),
+            rx,
+            ProgressRuntimeConfig::new(AGENT_MAX_ITERATIONS),
+        );
+
+        let result = {
+            let mut executor = executor.write().await;
+            if executor.is_timed_out() {
+                executor.reset();
+            }
+            executor.session_mut().cancellation_token = (*cancellation_token).clone();
+            if let Some(overrides) = request.content_loop {
+                executor.override_content_loop_next_run(overrides);
+            }
+            executor.execute(&request.task, Some(tx)).await
+        };
+
+        match progress.await {
+            Ok(final_state) => task.set_progress(&final_state),
+            Err(e) => warn!(task_id, error = %e, "Progress runtime task failed"),
+        }
+        drop(guard);
+        drop(task_slot);
+        task.finish(result);
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(CreateTaskResponse {
+            task_id,
+            session_id: request.session_id,
+        }),
+    ))
+}
+
+async fn get_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<u64>,
+    Query(query): Query<WaitQuery>,
+) -> Result<Json<TaskView>, ApiError> {
+    let task = state.task(task_id).await?;
+
+    if query.wait > 0 {
+        let wait = Duration::from_secs(query.wait.min(HTTP_MAX_WAIT_SECS));
+        let mut outcome = task.subscribe_outcome();
+        let _ = tokio::time::timeout(wait, outcome.wait_for(|o| !o.is_running())).await;
+    }
+
+    Ok(Json(TaskView::new(task_id, &task)))
+}
+
+async fn task_events(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<u64>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let task = state.task(task_id).await?;
+    let mut progress = task.subscribe_progress();
+    // Send the current snapshot right away
+    progress.mark_changed();
+
+    let stream = stream::unfold(
+        Some((progress, task.subscribe_outcome())),
+        |channels| async move {
+            let (progress, outcome) = channels?;
+            Some(next_task_event(progress, outcome).await)
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+type EventChannels = (
+    watch::Receiver<ProgressSnapshot>,
+    watch::Receiver<TaskOutcome>,
+);
+
+/// Next SSE event: a `progress` snapshot, or the final `done` event (ends the stream)
+async fn next_task_event(
+    mut progress: watch::Receiver<ProgressSnapshot>,
+    mut outcome: watch::Receiver<TaskOutcome>,
+) -> (Result<Event, axum::Error>, Option<EventChannels>) {
+    let finished = {
+        let current = outcome.borrow_and_update().clone();
+        (!current.is_running()).then_some(current)
+    };
+    if finished.is_none() {
+        tokio::select! {
+            changed = progress.changed() => {
+                if changed.is_ok() {
+                    let snapshot = progress.borrow_and_update().clone();
+                    let event = Event::default().event("progress").json_data(snapshot);
+                    return (event, Some((progress, outcome)));
+                }
+            }
+            _ = outcome.changed() => {}
+        }
+    }
+
+    let final_outcome = finished.unwrap_or_else(|| outcome.borrow().clone());
+    let event = Event::default().event("done").json_data(final_outcome);
+    (event, None)
+}
+
+async fn download_file(
+    State(state): State<Arc<AppState>>,
+    Path((task_id, index)): Path<(u64, usize)>,
+) -> Result<Response, ApiError> {
+    let task = state.task(task_id).await?;
+    let file = task
+        .file(index)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "file not found"))?;
+
+    let safe_name: String = file
+        .name
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"' && *c != '\\')
+        .collect();
+    let disposition = HeaderValue::from_str(&format!("attachment; filename=\"{safe_name}\""))
+        .unwrap_or_else(|_| HeaderValue::from_static("attachment"));
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        file.content,
+    )
+        .into_response())
+}
+
+async fn cancel_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<u64>,
+) -> Result<StatusCode, ApiError> {
+    let task = state.task(task_id).await?;
+    if !task.outcome().is_running() {
+        return Err(ApiError::new(StatusCode::CONFLICT, "task already finished"));
+    }
+    state.sessions.cancel(&task.session_id()).await;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn answer_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<u64>,
+    Json(request): Json<AnswerRequest>,
+) -> Result<StatusCode, ApiError> {
+    let task = state.task(task_id).await?;
+    if !task.outcome().is_running() {
+        return Err(ApiError::new(StatusCode::CONFLICT, "task already finished"));
+    }
+    if !clarification::submit_answer(task.session_id().as_i64(), request.answer) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "agent is not waiting for an answer",
+        ));
+    }
+    Ok(StatusCode::ACCEPTED)
+}
@@ -0,0 +1,272 @@
+//! Per-task state and the HTTP `AgentTransport`
+//!
+//! The progress runtime writes into a [`TaskState`]; HTTP handlers read from
+//! it (snapshots, SSE subscriptions, delivered files).
+
+use anyhow::Result;
+use async_trait::async_trait;
+use oxide_agent_core::agent::progress::{ProgressState, Step, StepStatus};
+use oxide_agent_core::agent::{SessionId, TodoList};
+use oxide_agent_runtime::{AgentTransport, DeliveryMode};
+use serde::Serialize;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::watch;
+
+/// Progress of a task as sent to HTTP clients
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProgressSnapshot {
+    /// Current iteration
+    pub iteration: usize,
+    /// Maximum iterations
+    pub max_iterations: usize,
+    /// Steps executed so far
+    pub steps: Vec<StepSnapshot>,
+    /// Current todo list, if the agent keeps one
+    pub todos: Option<TodoList>,
+    /// Latest agent thought
+    pub thought: Option<String>,
+    /// Narrator headline
+    pub headline: Option<String>,
+    /// Question the agent waits for the client to answer
+    pub question: Option<String>,
+    /// Whether the agent has finished
+    pub is_finished: bool,
+    /// Error reported by the agent
+    pub error: Option<String>,
+}
+
+/// A single progress step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepSnapshot {
+    /// Human-readable description
+    pub description: String,
+    /// `pending`, `in_progress`, `completed` or `failed`
+    pub status: &'static str,
+    /// Tool that ran in this step
+    pub tool_name: Option<String>,
+    /// Tool execution time
+    pub duration_ms: Option<u64>,
+}
+
+impl From<&Step> for StepSnapshot {
+    fn from(step: &Step) -> Self {
+        let status = match step.status {
+            StepStatus::Pending => "pending",
+            StepStatus::InProgress => "in_progress",
+            StepStatus::Completed => "completed",
+            StepStatus::Failed => "failed",
+        };
+        Self {
+            description: step.description.clone(),
+            status,
+            tool_name: step.tool_name.clone(),
+            duration_ms: step.duration_ms,
+        }
+    }
+}
+
+impl From<&ProgressState> for ProgressSnapshot {
+    fn from(state: &ProgressState) -> Self {
+        Self {
+            iteration: state.current_iteration,
+            max_iterations: state.max_iterations,
+            steps: state.steps.iter().map(StepSnapshot::from).collect(),
+            todos: state.current_todos.clone(),
+            thought: state.current_thought.clone(),
+            headline: state.narrative_headline.clone(),
+            question: state.pending_question.clone(),
+            is_finished: state.is_finished,
+            error: state.error.clone(),
+        }
+    }
+}
+
+/// Final state of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TaskOutcome {
+    /// The agent is still working
+    Running,
+    /// The agent returned a final answer
+    Completed {
+        /// Final answer
+        result: String,
+    },
+    /// The task failed, timed out or was cancelled
+    Failed {
+        /// Error description
+        error: String,
+    },
+}
+
+impl TaskOutcome {
+    /// Whether the task has not finished yet
+    #[must_use]
+    pub const fn is_running(&self) -> bool {
+        matches!(self, Self::Running)
+    }
+}
+
+/// A file the agent sent to the user
+#[derive(Debug, Clone)]
+pub struct DeliveredFile {
+    /// File name chosen by the agent
+    pub name: String,
+    /// File content
+    pub content: Vec<u8>,
+}
+
+/// Shared state of a single task
+pub struct TaskState {
+    session_id: SessionId,
+    progress: watch::Sender<ProgressSnapshot>,
+    outcome: watch::Sender<TaskOutcome>,
+    files: Mutex<Vec<DeliveredFile>>,
+}
+
+impl TaskState {
+    /// Create the state of a task that has just started
+    #[must_use]
+    pub fn new(session_id: SessionId, max_iterations: usize) -> Self {
+        let progress = ProgressSnapshot {
+            max_iterations,
+            ..ProgressSnapshot::default()
+        };
+        Self {
+            session_id,
+            progress: watch::channel(progress).0,
+            outcome: watch::channel(TaskOutcome::Running).0,
+            files: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Session the task runs in
+    #[must_use]
+    pub const fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Latest progress snapshot
+    #[must_use]
+    pub fn progress(&self) -> ProgressSnapshot {
+        self.progress.borrow().clone()
+    }
+
+    /// Current outcome
+    #[must_use]
+    pub fn outcome(&self) -> TaskOutcome {
+        self.outcome.borrow().clone()
+    }
+
+    /// Subscribe to progress updates
+    #[must_use]
+    pub fn subscribe_progress(&self) -> watch::Receiver<ProgressSnapshot> {
+        self.progress.subscribe()
+    }
+
+    /// Subscribe to the outcome
+    #[must_use]
+    pub fn subscribe_outcome(&self) -> watch::Receiver<TaskOutcome> {
+        self.outcome.subscribe()
+    }
+
+    /// Publish a new progress state
+    pub fn set_progress(&self, state: &ProgressState) {
+        self.progress.send_replace(state.into());
+    }
+
+    /// Record the agent's result
+    pub fn finish(&self, result: Result<String>) {
+        let outcome = match result {
+            Ok(result) => TaskOutcome::Completed { result },
+            Err(e) => TaskOutcome::Failed {
+                error: e.to_string(),
+            },
+        };
+        self.outcome.send_replace(outcome);
+    }
+
+    /// Names and sizes of delivered files, in delivery order
+    #[must_use]
+    pub fn file_list(&self) -> Vec<(String, usize)> {
+        self.lock_files()
+            .iter()
+            .map(|file| (file.name.clone(), file.content.len()))
+            .collect()
+    }
+
+    /// Delivered file by index
+    #[must_use]
+    pub fn file(&self, index: usize) -> Option<DeliveredFile> {
+        self.lock_files().get(index).cloned()
+    }
+
+    fn lock_files(&self) -> std::sync::MutexGuard<'_, Vec<DeliveredFile>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// `AgentTransport` that publishes progress and files into a [`TaskState`]
+pub struct HttpAgentTransport {
+    task: Arc<TaskState>,
+}
+
+impl HttpAgentTransport {
+    /// Create a transport bound to a task
+    #[must_use]
+    pub const fn new(task: Arc<TaskState>) -> Self {
+        Self { task }
+    }
+}
+
+#[async_trait]
+impl AgentTransport for HttpAgentTransport {
+    async fn update_progress(&self, state: &ProgressState) -> Result<()> {
+        self.task.set_progress(state);
+        Ok(())
+    }
+
+    async fn deliver_file(
+        &self,
+        _mode: DeliveryMode,
+        file_name: &str,
+        content: &[u8],
+    ) -> Result<()> {
+        // Files stay in memory until the client downloads them; delivery cannot fail
+        self.task.lock_files().push(DeliveredFile {
+            name: file_name.to_string(),
+            content: content.to_vec(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxide_agent_core::agent::progress::AgentEvent;
+
+    #[tokio::test]
+    async fn test_transport_publishes_progress_and_files() -> Result<()> {
+        let task = Arc::new(TaskState::new(SessionId::from(7), 10));
+        let transport = HttpAgentTransport::new(Arc::clone(&task));
+        let mut updates = task.subscribe_progress();
+
+        let mut state = ProgressState::new(10);
+        state.update(AgentEvent::Thinking { tokens: 100 });
+        transport.update_progress(&state).await?;
+        transport
+            .deliver_file(DeliveryMode::Confirmed, "report.txt", b"done")
+            .await?;
+
+        assert!(updates.has_changed()?);
+        assert_eq!(updates.borrow_and_update().steps.len(), 1);
+        assert_eq!(task.file_list(), vec![("report.txt".to_string(), 4)]);
+
+        task.finish(Ok("answer".to_string()));
+        let json = serde_json::to_value(task.outcome())?;
+        assert_eq!(json["status"], "completed");
+        assert_eq!(json["result"], "answer");
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use axum::body::{to_bytes, Body};
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use oxide_agent_core::config::AgentSettings;
+use oxide_agent_core::llm::{
+    ChatResponse, LlmClient, LlmError, LlmProvider, Message, ToolDefinition,
+};
+use oxide_agent_transport_http::config::HTTP_MAX_RUNNING_TASKS;
+use oxide_agent_transport_http::server::{router, AppState};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tower::ServiceExt;
+
+/// Answers every request with a final answer once the gate lets it through
+struct GatedLlm {
+    gate: Arc<Semaphore>,
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for GatedLlm {
+    async fn chat_completion(
+        &self,
+        _system_prompt: &str,
+        _history: &[Message],
+        _user_message: &str,
+        _model_id: &str,
+        _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
+    ) -> Result<String, LlmError> {
+        Err(LlmError::Unknown("Not scripted".to_string()))
+    }
+
+    async fn transcribe_audio(
+        &self,
+        _audio_bytes: Vec<u8>,
+        _mime_type: &str,
+        _language: Option<String>,
+        _model_id: &str,
+    ) -> Result<String, LlmError> {
+        Err(LlmError::Unknown("Not scripted".to_string()))
+    }
+
+    async fn analyze_image(
+        &self,
+        _image_bytes: Vec<u8>,
+        _text_prompt: &str,
+        _system_prompt: &str,
+        _model_id: &str,
+    ) -> Result<String, LlmError> {
+        Err(LlmError::Unknown("Not scripted".to_string()))
+    }
+
+    async fn chat_with_tools(
+        &self,
+        _system_prompt: &str,
+        _messages: &[Message],
+        _tools: &[ToolDefinition],
+        _model_id: &str,
+        _max_tokens: u32,
+        _json_mode: bool,
+        _stop: &[String],
+    ) -> Result<ChatResponse, LlmError> {
+        let _permit = self
+            .gate
+            .acquire()
+            .await
+            .map_err(|e| LlmError::Unknown(e.to_string()))?;
+        let reply = serde_json::json!({
+            "thought": "Done",
+            "tool_call": null,
+            "final_answer": "Hello over HTTP"
+        });
+        Ok(ChatResponse {
+            content: Some(reply.to_string()),
+            tool_calls: vec![],
+            finish_reason: "stop".to_string(),
+            reasoning_content: None,
+            usage: None,
+        })
+    }
+}
+
+/// Router whose model blocks until permits are added to the returned gate
+fn gated_app() -> (Router, Arc<Semaphore>) {
+    let gate = Arc::new(Semaphore::new(0));
+    let settings = Arc::new(AgentSettings {
+        chat_model_id: Some("test-model".to_string()),
+        chat_model_provider: Some("mock-provider".to_string()),
+        ..AgentSettings::default()
+    });
+    let mut llm = LlmClient::new(&settings);
+    llm.register_provider(
+        "mock-provider".to_string(),
+        Arc::new(GatedLlm {
+            gate: Arc::clone(&gate),
+        }),
+    );
+    let state = Arc::new(AppState::new(Arc::new(llm), settings));
+    (router(state), gate)
+}
+
+async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(body.map_or_else(Body::empty, |json| Body::from(json.to_string())));
+    let Ok(request) = request else {
+        panic!("invalid request for {uri}");
+    };
+    let response = match app.clone().oneshot(request).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    };
+    let status = response.status();
+    let Ok(bytes) = to_bytes(response.into_body(), usize::MAX).await else {
+        panic!("unreadable body from {uri}");
+    };
+    (status, String::from_utf8_lossy(&bytes).into_owned())
+}
+
+async fn submit(app: &Router, session_id: i64) -> (StatusCode, serde_json::Value) {
+    let task = serde_json::json!({"session_id": session_id, "task": "Say hello"});
+    let (status, body) = send(app, Method::POST, "/tasks", Some(task)).await;
+    (status, serde_json::from_str(&body).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn task_runs_to_completion_and_is_reported() {
+    let (app, gate) = gated_app();
+
+    let (status, created) = submit(&app, -9_000_001).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let task_id = created["task_id"].as_u64().unwrap_or_default();
+    assert_eq!(created["session_id"], -9_000_001);
+
+    let (status, body) = send(&app, Method::GET, &format!("/tasks/{task_id}"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let view: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+    assert_eq!(view["status"], "running");
+
+    gate.add_permits(16);
+    let (status, body) = send(
+        &app,
+        Method::GET,
+        &format!("/tasks/{task_id}?wait=10"),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let view: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+    assert_eq!(view["status"], "completed", "unexpected view: {view}");
+    assert_eq!(view["result"], "Hello over HTTP");
+
+    // A finished task's event stream ends with the final outcome
+    let (status, events) = send(&app, Method::GET, &format!("/tasks/{task_id}/events"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(events.contains("event: done"), "events: {events}");
+    assert!(events.contains("Hello over HTTP"));
+
+    let (status, _) = send(&app, Method::GET, "/tasks/999", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn concurrent_submits_to_one_session_start_one_task() {
+    let (app, gate) = gated_app();
+
+    let (first, second) = tokio::join!(submit(&app, -9_000_002), submit(&app, -9_000_002));
+    let mut statuses = [first.0, second.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::ACCEPTED, StatusCode::CONFLICT]);
+
+    // Still busy until the running task ends
+    let (status, body) = submit(&app, -9_000_002).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"]
+        .as_str()
+        .is_some_and(|error| error.contains("already running")));
+
+    // Other sessions are not blocked
+    let (status, _) = submit(&app, -9_000_003).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    gate.add_permits(16);
+    let task_id = [first.1, second.1]
+        .iter()
+        .find_map(|created| created["task_id"].as_u64())
+        .unwrap_or_default();
+    let (_, body) = send(
+        &app,
+        Method::GET,
+        &format!("/tasks/{task_id}?wait=10"),
+        None,
+    )
+    .await;
+    assert!(body.contains("completed"), "task did not finish: {body}");
+
+    let (status, _) = submit(&app, -9_000_002).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn running_tasks_are_capped() {
+    let (app, _gate) = gated_app();
+
+    for offset in 0..HTTP_MAX_RUNNING_TASKS {
+        let session_id = -9_100_000 - i64::try_from(offset).unwrap_or_default();
+        let (status, _) = submit(&app, session_id).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    let (status, body) = submit(&app, -9_200_000).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body["error"]
+        .as_str()
+        .is_some_and(|error| error.contains("too many tasks")));
+}
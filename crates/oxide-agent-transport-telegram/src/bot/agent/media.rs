@@ -2,36 +2,88 @@
 //!
 //! Converts Telegram message types (voice, photo, document) to `AgentInput`.
 
+use crate::bot::download::{
+    download_to_memory, download_to_path, temp_download_path, STREAM_TO_DISK_BYTES,
+};
 use anyhow::Result;
 use oxide_agent_core::agent::preprocessor::AgentInput;
-use teloxide::net::Download;
 use teloxide::prelude::*;
 use tracing::info;
 
-/// Maximum file size for document uploads (20 MB)
-const MAX_FILE_SIZE: u32 = 20 * 1024 * 1024;
+/// Where an incoming file ends up; agent uploads go to the sandbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadTarget {
+    /// Chat mode: the file is passed to the LLM in memory
+    Chat,
+    /// Agent mode: the file is copied into the sandbox
+    Agent,
+}
+
+/// Check an incoming file against the upload limits before downloading it
+///
+/// Applies `MAX_UPLOAD_MB` (chat) or `AGENT_MAX_UPLOAD_MB` (agent) to voice
+/// messages, photos and documents, and `UPLOAD_ALLOWED_TYPES` to documents.
+/// Returns the message for the user when the file is rejected.
+#[must_use]
+pub fn check_incoming_file(msg: &Message, target: UploadTarget) -> Option<String> {
+    let size = if let Some(voice) = msg.voice() {
+        voice.file.size
+    } else if let Some(photo) = msg.photo().and_then(|p| p.last()) {
+        photo.file.size
+    } else if let Some(doc) = msg.document() {
+        doc.file.size
+    } else {
+        return None;
+    };
+
+    let limit = match target {
+        UploadTarget::Chat => oxide_agent_core::config::get_max_upload_bytes(),
+        UploadTarget::Agent => oxide_agent_core::config::get_agent_max_upload_bytes(),
+    };
+    if u64::from(size) > limit {
+        return Some(format!(
+            "📁 File too large: {:.1} MB (max {} MB).",
+            f64::from(size) / 1024.0 / 1024.0,
+            limit / 1024 / 1024
+        ));
+    }
+
+    if let Some(doc) = msg.document() {
+        let mime_type = doc.mime_type.as_ref().map(|m| m.essence_str().to_string());
+        if !oxide_agent_core::config::is_upload_type_allowed(
+            doc.file_name.as_deref(),
+            mime_type.as_deref(),
+        ) {
+            return Some(format!(
+                "📁 This file type is not accepted: {}.",
+                doc.file_name.as_deref().unwrap_or("file")
+            ));
+        }
+    }
+    None
+}
 
 /// Extract agent input from a Telegram message
 ///
 /// Handles:
 /// - Voice messages → `AgentInput::Voice`
 /// - Photos → `AgentInput::Image`
-/// - Documents → `AgentInput::Document`
+/// - Documents → `AgentInput::Document`, or `AgentInput::LocalDocument` when
+///   larger than [`STREAM_TO_DISK_BYTES`]
 /// - Text/Caption → `AgentInput::Text`
 ///
 /// # Errors
 ///
-/// Returns an error if file download fails or file is too large.
+/// Returns an error if file download fails or the file is rejected by
+/// [`check_incoming_file`].
 pub async fn extract_agent_input(bot: &Bot, msg: &Message) -> Result<AgentInput> {
+    if let Some(reason) = check_incoming_file(msg, UploadTarget::Agent) {
+        anyhow::bail!(reason);
+    }
+
     // Voice message
     if let Some(voice) = msg.voice() {
-        let buffer = oxide_agent_core::utils::retry_transport_operation(|| async {
-            let file = bot.get_file(voice.file.id.clone()).await?;
-            let mut buf = Vec::new();
-            bot.download_file(&file.path, &mut buf).await?;
-            Ok(buf)
-        })
-        .await?;
+        let buffer = download_to_memory(bot, &voice.file.id).await?;
 
         let mime_type = voice
             .mime_type
@@ -47,13 +99,7 @@ pub async fn extract_agent_input(bot: &Bot, msg: &Message) -> Result<AgentInput>
     // Photo
     if let Some(photos) = msg.photo() {
         if let Some(photo) = photos.last() {
-            let buffer = oxide_agent_core::utils::retry_transport_operation(|| async {
-                let file = bot.get_file(photo.file.id.clone()).await?;
-                let mut buf = Vec::new();
-                bot.download_file(&file.path, &mut buf).await?;
-                Ok(buf)
-            })
-            .await?;
+            let buffer = download_to_memory(bot, &photo.file.id).await?;
 
             let caption = msg.caption().map(ToString::to_string);
             return Ok(AgentInput::Image {
@@ -65,33 +111,41 @@ pub async fn extract_agent_input(bot: &Bot, msg: &Message) -> Result<AgentInput>
 
     // Document
     if let Some(doc) = msg.document() {
-        if doc.file.size > MAX_FILE_SIZE {
-            anyhow::bail!(
-                "File too large: {:.1} MB (max 20 MB)",
-                f64::from(doc.file.size) / 1024.0 / 1024.0
+        let file_name = doc.file_name.clone().unwrap_or_else(|| "file".to_string());
+        let mime_type = doc.mime_type.as_ref().map(ToString::to_string);
+        let caption = msg.caption().map(String::from);
+
+        if doc.file.size > STREAM_TO_DISK_BYTES {
+            let path = temp_download_path(msg);
+            download_to_path(bot, &doc.file.id, &path).await?;
+            info!(
+                file_name = %file_name,
+                mime_type = ?mime_type,
+                size = doc.file.size,
+                path = %path.display(),
+                "Downloaded document from Telegram to disk"
             );
+            return Ok(AgentInput::LocalDocument {
+                path,
+                file_name,
+                mime_type,
+                caption,
+            });
         }
 
-        let buffer = oxide_agent_core::utils::retry_transport_operation(|| async {
-            let file = bot.get_file(doc.file.id.clone()).await?;
-            let mut buf = Vec::new();
-            bot.download_file(&file.path, &mut buf).await?;
-            Ok(buf)
-        })
-        .await?;
-
+        let buffer = download_to_memory(bot, &doc.file.id).await?;
         info!(
-            file_name = ?doc.file_name,
-            mime_type = ?doc.mime_type,
+            file_name = %file_name,
+            mime_type = ?mime_type,
             size = buffer.len(),
             "Downloaded document from Telegram"
         );
 
         return Ok(AgentInput::Document {
             bytes: buffer,
-            file_name: doc.file_name.clone().unwrap_or_else(|| "file".to_string()),
-            mime_type: doc.mime_type.as_ref().map(ToString::to_string),
-            caption: msg.caption().map(String::from),
+            file_name,
+            mime_type,
+            caption,
         });
     }
 
@@ -5,4 +5,4 @@
 /// Media extraction from Telegram messages
 pub mod media;
 
-pub use media::extract_agent_input;
+pub use media::{check_incoming_file, extract_agent_input, UploadTarget};
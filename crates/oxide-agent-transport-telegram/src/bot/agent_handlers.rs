@@ -3,21 +3,29 @@
 //! Provides handlers for activating agent mode, processing messages,
 //! and managing agent sessions.
 
-use crate::bot::agent::extract_agent_input;
+use crate::bot::agent::{check_incoming_file, extract_agent_input, UploadTarget};
 use crate::bot::agent_transport::TelegramAgentTransport;
-use crate::bot::messaging::send_long_message;
+use crate::bot::group;
+use crate::bot::handlers::{get_sender_id, get_user_id_safe};
+use crate::bot::messaging::{send_long_message_to_thread, send_long_message_with_markup};
 use crate::bot::progress_render::render_progress_html;
+use crate::bot::resilient::{send_markup_to_thread_resilient, send_message_to_thread_resilient};
 use crate::bot::state::{ConfirmationType, State};
 use crate::bot::views::{
-    confirmation_keyboard, get_agent_keyboard, AgentView, DefaultAgentView, LOOP_CALLBACK_CANCEL,
-    LOOP_CALLBACK_RESET, LOOP_CALLBACK_RETRY,
+    confirmation_keyboard, feedback_keyboard, get_agent_keyboard, parse_feedback_callback,
+    running_session_debug_report, session_debug_report, AgentView, DefaultAgentView,
+    LOOP_CALLBACK_CANCEL, LOOP_CALLBACK_RESET, LOOP_CALLBACK_RETRY,
 };
 use crate::config::BotSettings;
 use anyhow::{Error, Result};
 use oxide_agent_core::agent::{
+    audit::{record_feedback, FeedbackRecord},
+    debug,
     executor::AgentExecutor,
-    preprocessor::Preprocessor,
+    instructions,
+    preprocessor::{normalize_task, Preprocessor},
     progress::{AgentEvent, ProgressState},
+    providers::clarification,
     AgentSession, SessionId,
 };
 use oxide_agent_core::config::AGENT_MAX_ITERATIONS;
@@ -27,18 +35,71 @@ use oxide_agent_runtime::SessionRegistry;
 use oxide_agent_runtime::{spawn_progress_runtime, ProgressRuntimeConfig};
 use std::sync::Arc;
 use std::sync::LazyLock;
+use std::time::Duration;
 use teloxide::dispatching::dialogue::InMemStorage;
 use teloxide::prelude::*;
-use teloxide::types::{CallbackQuery, ParseMode};
+use teloxide::types::{CallbackQuery, ParseMode, ReplyMarkup, ThreadId};
 use tracing::{debug, info, warn};
 
 /// Type alias for dialogue
 pub type AgentDialogue = Dialogue<State, InMemStorage<State>>;
 
+/// Where replies go: the chat and forum topic the user wrote in
+#[derive(Clone, Copy)]
+struct ReplyTo {
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    parse_mode: Option<ParseMode>,
+}
+
+impl ReplyTo {
+    /// Reply to where `msg` was sent
+    fn message(msg: &Message) -> Self {
+        Self {
+            chat_id: msg.chat.id,
+            thread_id: group::topic_thread_id(msg),
+            parse_mode: None,
+        }
+    }
+
+    /// Send the text as HTML
+    const fn html(mut self) -> Self {
+        self.parse_mode = Some(ParseMode::Html);
+        self
+    }
+
+    /// Send `text` with retries on network failures
+    async fn text(self, bot: &Bot, text: impl Into<String>) -> Result<()> {
+        send_message_to_thread_resilient(bot, self.chat_id, self.thread_id, text, self.parse_mode)
+            .await?;
+        Ok(())
+    }
+
+    /// Send `text` with `markup` attached, see [`Self::text`]
+    async fn keyboard(
+        self,
+        bot: &Bot,
+        text: impl Into<String>,
+        markup: impl Into<ReplyMarkup>,
+    ) -> Result<()> {
+        send_markup_to_thread_resilient(
+            bot,
+            self.chat_id,
+            self.thread_id,
+            text,
+            self.parse_mode,
+            markup,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
 /// Context for running an agent task without blocking the update handler
 struct AgentTaskContext {
     bot: Bot,
     msg: Message,
+    dialogue: AgentDialogue,
     storage: Arc<dyn StorageProvider>,
     llm: Arc<LlmClient>,
 }
@@ -48,6 +109,20 @@ enum AgentWipeError {
     Recreate(Error),
 }
 
+/// Agent keyboard buttons that control the current session rather than start work
+const AGENT_CONTROL_TEXTS: &[&str] = &[
+    "❌ Cancel Task",
+    "🗑 Clear Memory",
+    "🔄 Recreate Container",
+    "⬅️ Exit Agent Mode",
+];
+
+/// Whether `text` is an agent keyboard control button
+#[must_use]
+pub fn is_agent_control_text(text: &str) -> bool {
+    AGENT_CONTROL_TEXTS.contains(&text)
+}
+
 /// Global session registry for agent executors
 static SESSION_REGISTRY: LazyLock<SessionRegistry> = LazyLock::new(SessionRegistry::new);
 
@@ -64,7 +139,8 @@ pub async fn activate_agent_mode(
     storage: Arc<dyn StorageProvider>,
     settings: Arc<BotSettings>,
 ) -> Result<()> {
-    let user_id = msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed());
+    let user_id = get_user_id_safe(&msg);
+    let reply = ReplyTo::message(&msg);
     let session_id = SessionId::from(user_id);
 
     info!("Activating agent mode for user {user_id}");
@@ -93,9 +169,13 @@ pub async fn activate_agent_mode(
 
     // Send welcome message
     let (model_id, _, _) = settings.agent.get_configured_agent_model();
-    bot.send_message(msg.chat.id, DefaultAgentView::welcome_message(&model_id))
-        .parse_mode(ParseMode::Html)
-        .reply_markup(get_agent_keyboard())
+    reply
+        .html()
+        .keyboard(
+            &bot,
+            DefaultAgentView::welcome_message(&model_id),
+            get_agent_keyboard(),
+        )
         .await?;
 
     Ok(())
@@ -114,8 +194,21 @@ pub async fn handle_agent_message(
     dialogue: AgentDialogue,
     settings: Arc<BotSettings>,
 ) -> Result<()> {
-    let user_id = msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed());
-    let chat_id = msg.chat.id;
+    let user_id = get_user_id_safe(&msg);
+    let reply = ReplyTo::message(&msg);
+
+    // Agent mode is shared by the whole group; every sender needs agent access
+    if group::is_group_chat(&msg.chat)
+        && !settings
+            .telegram
+            .agent_allowed_users()
+            .contains(&get_sender_id(&msg))
+    {
+        reply
+            .text(&bot, "⛔️ You do not have permission to access agent mode.")
+            .await?;
+        return Ok(());
+    }
 
     // Check for control commands
     if let Some(text) = msg.text() {
@@ -148,16 +241,39 @@ pub async fn handle_agent_message(
         }
     }
 
+    if let Some(reason) = check_incoming_file(&msg, UploadTarget::Agent) {
+        reply.keyboard(&bot, reason, get_agent_keyboard()).await?;
+        return Ok(());
+    }
+
+    if clarification::is_awaiting_answer(user_id) {
+        return answer_clarification(&bot, &msg, &dialogue, user_id).await;
+    }
+
+    // A bare text message must carry an instruction; media is checked after preprocessing
+    if msg
+        .text()
+        .is_some_and(|text| normalize_task(text).is_none())
+    {
+        reply
+            .keyboard(&bot, DefaultAgentView::empty_task(), get_agent_keyboard())
+            .await?;
+        return Ok(());
+    }
+
     // Get or create session
     ensure_session_exists(user_id, &llm, &storage, &settings).await;
 
     if is_agent_task_running(user_id).await {
-        bot.send_message(
-            chat_id,
-            "⏳ A task is already running. Press ❌ Cancel Task to stop it.",
-        )
-        .reply_markup(get_agent_keyboard())
-        .await?;
+        // Text during a task is an extra instruction, not a new task
+        let notice = match msg.text().or_else(|| msg.caption()) {
+            Some(text) if instructions::submit_instruction(user_id, text) => {
+                info!(user_id = user_id, "Queued mid-task instruction");
+                DefaultAgentView::instruction_queued()
+            }
+            _ => "⏳ A task is already running. Press ❌ Cancel Task to stop it.",
+        };
+        reply.keyboard(&bot, notice, get_agent_keyboard()).await?;
         return Ok(());
     }
 
@@ -172,20 +288,52 @@ pub async fn handle_agent_message(
         let ctx = AgentTaskContext {
             bot: task_bot.clone(),
             msg: task_msg.clone(),
+            dialogue,
             storage: task_storage,
             llm: task_llm,
         };
 
         if let Err(e) = run_agent_task(ctx).await {
-            let _ = task_bot
-                .send_message(task_msg.chat.id, format!("❌ Error: {e}"))
-                .await;
+            let _ = reply.text(&task_bot, format!("❌ Error: {e}")).await;
         }
     });
 
     Ok(())
 }
 
+/// Hand the message to the agent waiting in `ask_user`
+async fn answer_clarification(
+    bot: &Bot,
+    msg: &Message,
+    dialogue: &AgentDialogue,
+    user_id: i64,
+) -> Result<()> {
+    let reply = ReplyTo::message(msg);
+    let Some(answer) = msg.text() else {
+        reply
+            .text(bot, DefaultAgentView::answer_requires_text())
+            .await?;
+        return Ok(());
+    };
+
+    dialogue.update(State::AgentMode).await?;
+    let notice = if clarification::submit_answer(user_id, answer) {
+        info!(user_id = user_id, "Clarification answer delivered to agent");
+        DefaultAgentView::answer_received()
+    } else {
+        DefaultAgentView::task_already_running()
+    };
+    reply.keyboard(bot, notice, get_agent_keyboard()).await?;
+    Ok(())
+}
+
+/// Leave the answer sub-state once the task is over (e.g. the question timed out)
+async fn restore_agent_mode(dialogue: &AgentDialogue) {
+    if matches!(dialogue.get().await, Ok(Some(State::AgentAwaitingAnswer))) {
+        let _ = dialogue.update(State::AgentMode).await;
+    }
+}
+
 async fn ensure_session_exists(
     user_id: i64,
     llm: &Arc<LlmClient>,
@@ -229,6 +377,41 @@ async fn renew_cancellation_token(user_id: i64) {
     SESSION_REGISTRY.renew_cancellation_token(&session_id).await;
 }
 
+/// Drain agent sessions before the process exits.
+///
+/// Cancels every running task, waits up to `grace` for the tasks to stop
+/// (killing their sandbox processes on the way), saves the memory of every
+/// idle session and then removes every session's sandbox container, so a
+/// deploy leaves no containers behind.
+pub async fn shutdown_agent_sessions(storage: &Arc<dyn StorageProvider>, grace: Duration) {
+    let running = SESSION_REGISTRY.cancel_all().await;
+    if running > 0 {
+        info!(
+            running,
+            grace_secs = grace.as_secs(),
+            "Waiting for agent tasks to stop"
+        );
+    }
+    if !SESSION_REGISTRY.wait_until_idle(grace).await {
+        warn!("Grace period elapsed with agent tasks still running");
+    }
+
+    for session_id in SESSION_REGISTRY.session_ids().await {
+        if !SESSION_REGISTRY.is_running(&session_id).await {
+            save_memory_after_task(session_id.as_i64(), storage).await;
+        }
+        destroy_session_sandbox(session_id).await;
+    }
+    info!("Agent sessions drained");
+}
+
+/// Remove a session's sandbox container, even if its task did not stop in time
+async fn destroy_session_sandbox(session_id: SessionId) {
+    if let Err(e) = SESSION_REGISTRY.destroy_sandbox(&session_id).await {
+        warn!(session_id = %session_id, error = %e, "Failed to destroy sandbox on shutdown");
+    }
+}
+
 async fn save_memory_after_task(user_id: i64, storage: &Arc<dyn StorageProvider>) {
     let session_id = SessionId::from(user_id);
     if let Some(executor_arc) = SESSION_REGISTRY.get(&session_id).await {
@@ -240,28 +423,39 @@ async fn save_memory_after_task(user_id: i64, storage: &Arc<dyn StorageProvider>
 }
 
 async fn run_agent_task(ctx: AgentTaskContext) -> Result<()> {
-    let user_id = ctx.msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed());
+    let user_id = get_user_id_safe(&ctx.msg);
     let chat_id = ctx.msg.chat.id;
+    let thread_id = group::topic_thread_id(&ctx.msg);
 
     // Preprocess input
-    let preprocessor = Preprocessor::new(ctx.llm.clone(), user_id);
+    let language = super::handlers::user_language(&ctx.storage, user_id).await;
+    let preprocessor = Preprocessor::new(ctx.llm.clone(), user_id).with_language(language);
     let input = extract_agent_input(&ctx.bot, &ctx.msg).await?;
     let task_text = match preprocessor.preprocess_input(input).await {
         Ok(text) => text,
         Err(err) => {
             if err.to_string() == "MULTIMODAL_DISABLED" {
-                super::resilient::send_message_resilient(
-                    &ctx.bot,
-                    chat_id,
-                    "🚫 Agent cannot process this file.\nGemini/OpenRouter connection required for vision and audio capabilities.",
-                    None,
-                )
-                .await?;
+                ReplyTo::message(&ctx.msg)
+                    .text(
+                        &ctx.bot,
+                        "🚫 Agent cannot process this file.\nGemini/OpenRouter connection required for vision and audio capabilities.",
+                    )
+                    .await?;
                 return Ok(());
             }
             return Err(err);
         }
     };
+    let Some(task_text) = normalize_task(&task_text) else {
+        ReplyTo::message(&ctx.msg)
+            .keyboard(
+                &ctx.bot,
+                DefaultAgentView::empty_task(),
+                get_agent_keyboard(),
+            )
+            .await?;
+        return Ok(());
+    };
     info!(
         user_id = user_id,
         chat_id = chat_id.0,
@@ -269,22 +463,19 @@ async fn run_agent_task(ctx: AgentTaskContext) -> Result<()> {
     );
 
     // Send initial progress message with retry on network failures
-    let progress_msg = super::resilient::send_message_resilient(
-        &ctx.bot,
-        chat_id,
-        "⏳ Processing task...",
-        Some(ParseMode::Html),
-    )
-    .await?;
+    let progress_msg =
+        super::messaging::send_thinking_placeholder(&ctx.bot, chat_id, thread_id).await?;
 
     // Create progress tracking channel
     let (tx, rx) = tokio::sync::mpsc::channel::<AgentEvent>(100);
-    let transport = TelegramAgentTransport::new(ctx.bot.clone(), chat_id, progress_msg.id);
+    let transport = TelegramAgentTransport::new(ctx.bot.clone(), chat_id, progress_msg.id)
+        .with_thread(thread_id)
+        .with_dialogue(ctx.dialogue.clone());
     let cfg = ProgressRuntimeConfig::new(AGENT_MAX_ITERATIONS);
     let progress_handle = spawn_progress_runtime(transport, rx, cfg);
 
     // Execute the task
-    let result = execute_agent_task(user_id, &task_text, Some(tx)).await;
+    let result = execute_agent_task(user_id, get_sender_id(&ctx.msg), &task_text, Some(tx)).await;
     let state = match progress_handle.await {
         Ok(state) => state,
         Err(err) => {
@@ -292,6 +483,7 @@ async fn run_agent_task(ctx: AgentTaskContext) -> Result<()> {
             ProgressState::new(AGENT_MAX_ITERATIONS)
         }
     };
+    restore_agent_mode(&ctx.dialogue).await;
     let progress_text = render_progress_html(&state);
 
     // Save agent memory after task execution
@@ -299,7 +491,7 @@ async fn run_agent_task(ctx: AgentTaskContext) -> Result<()> {
 
     // Update the message with the result
     match result {
-        Ok(response) => {
+        Ok((response, task_id)) => {
             super::resilient::edit_message_safe_resilient(
                 &ctx.bot,
                 chat_id,
@@ -307,8 +499,7 @@ async fn run_agent_task(ctx: AgentTaskContext) -> Result<()> {
                 &progress_text,
             )
             .await;
-            // Use send_long_message to properly split response if it exceeds Telegram limit
-            send_long_message(&ctx.bot, chat_id, &response).await?;
+            send_final_answer(&ctx.bot, chat_id, thread_id, &response, task_id.as_deref()).await?;
         }
         Err(e) => {
             // Sanitize error text to prevent Telegram HTML parse errors
@@ -331,24 +522,22 @@ async fn run_agent_task(ctx: AgentTaskContext) -> Result<()> {
 async fn run_agent_task_with_text(
     bot: Bot,
     chat_id: ChatId,
+    thread_id: Option<ThreadId>,
     user_id: i64,
+    sender_id: i64,
     task_text: String,
     storage: Arc<dyn StorageProvider>,
 ) -> Result<()> {
-    let progress_msg = super::resilient::send_message_resilient(
-        &bot,
-        chat_id,
-        "⏳ Processing task...",
-        Some(ParseMode::Html),
-    )
-    .await?;
+    let progress_msg =
+        super::messaging::send_thinking_placeholder(&bot, chat_id, thread_id).await?;
 
     let (tx, rx) = tokio::sync::mpsc::channel::<AgentEvent>(100);
-    let transport = TelegramAgentTransport::new(bot.clone(), chat_id, progress_msg.id);
+    let transport =
+        TelegramAgentTransport::new(bot.clone(), chat_id, progress_msg.id).with_thread(thread_id);
     let cfg = ProgressRuntimeConfig::new(AGENT_MAX_ITERATIONS);
     let progress_handle = spawn_progress_runtime(transport, rx, cfg);
 
-    let result = execute_agent_task(user_id, &task_text, Some(tx)).await;
+    let result = execute_agent_task(user_id, sender_id, &task_text, Some(tx)).await;
     let state = match progress_handle.await {
         Ok(state) => state,
         Err(err) => {
@@ -361,7 +550,7 @@ async fn run_agent_task_with_text(
     save_memory_after_task(user_id, &storage).await;
 
     match result {
-        Ok(response) => {
+        Ok((response, task_id)) => {
             super::resilient::edit_message_safe_resilient(
                 &bot,
                 chat_id,
@@ -369,8 +558,7 @@ async fn run_agent_task_with_text(
                 &progress_text,
             )
             .await;
-            // Use send_long_message to properly split response if it exceeds Telegram limit
-            send_long_message(&bot, chat_id, &response).await?;
+            send_final_answer(&bot, chat_id, thread_id, &response, task_id.as_deref()).await?;
         }
         Err(e) => {
             // Sanitize error text to prevent Telegram HTML parse errors
@@ -389,12 +577,40 @@ async fn run_agent_task_with_text(
     Ok(())
 }
 
-/// Execute an agent task and return the result
+/// Send a task's answer, split if it exceeds the Telegram limit, with 👍/👎
+/// buttons for feedback when the task ID is known
+async fn send_final_answer(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    response: &str,
+    task_id: Option<&str>,
+) -> Result<()> {
+    match task_id {
+        Some(task_id) => {
+            send_long_message_with_markup(
+                bot,
+                chat_id,
+                thread_id,
+                response,
+                feedback_keyboard(task_id),
+            )
+            .await
+        }
+        None => send_long_message_to_thread(bot, chat_id, thread_id, response).await,
+    }
+}
+
+/// Execute an agent task and return the answer with the task ID
+///
+/// `sender_id` is the Telegram user who asked; their tool allowlist applies
+/// even when the session belongs to a group.
 async fn execute_agent_task(
     user_id: i64,
+    sender_id: i64,
     task: &str,
     progress_tx: Option<tokio::sync::mpsc::Sender<AgentEvent>>,
-) -> Result<String> {
+) -> Result<(String, Option<String>)> {
     let session_id = SessionId::from(user_id);
     // Get executor from registry
     let executor_arc = SESSION_REGISTRY
@@ -427,12 +643,14 @@ async fn execute_agent_task(
 
     // IMPORTANT: Set the external cancellation token into session
     executor.session_mut().cancellation_token = (*cancellation_token).clone();
+    executor.session_mut().requester_id = Some(sender_id);
 
     // Execute the task (now uses external token that can be cancelled lock-free)
-    executor.execute(task, progress_tx).await
+    let response = executor.execute(task, progress_tx).await?;
+    Ok((response, executor.session().current_task_id.clone()))
 }
 
-/// Handle loop-detection inline keyboard callbacks.
+/// Handle loop-detection and answer feedback inline keyboard callbacks.
 ///
 /// # Errors
 ///
@@ -448,19 +666,32 @@ pub async fn handle_loop_callback(
         return Ok(());
     };
 
+    if let Some((task_id, helpful)) = parse_feedback_callback(data) {
+        handle_feedback_callback(&bot, &q, task_id, helpful).await;
+        return Ok(());
+    }
+
     let _ = bot.answer_callback_query(q.id.clone()).await;
 
-    let user_id = q.from.id.0.cast_signed();
-    let chat_id = q
+    let message = q
         .message
         .as_ref()
-        .map(|msg| msg.chat().id)
         .ok_or_else(|| anyhow::anyhow!("Callback message missing chat id"))?;
+    let chat_id = message.chat().id;
+    let thread_id = message.regular_message().and_then(group::topic_thread_id);
+    let sender_id = q.from.id.0.cast_signed();
+    let user_id = group::conversation_scope_id(message.chat(), thread_id, sender_id);
+    let reply = ReplyTo {
+        chat_id,
+        thread_id,
+        parse_mode: None,
+    };
 
     match data {
         LOOP_CALLBACK_RETRY => {
             if is_agent_task_running(user_id).await {
-                bot.send_message(chat_id, DefaultAgentView::task_already_running())
+                reply
+                    .text(&bot, DefaultAgentView::task_already_running())
                     .await?;
                 return Ok(());
             }
@@ -471,7 +702,8 @@ pub async fn handle_loop_callback(
             let executor_arc = SESSION_REGISTRY.get(&SessionId::from(user_id)).await;
 
             let Some(executor_arc) = executor_arc else {
-                bot.send_message(chat_id, DefaultAgentView::session_not_found())
+                reply
+                    .text(&bot, DefaultAgentView::session_not_found())
                     .await?;
                 return Ok(());
             };
@@ -482,8 +714,7 @@ pub async fn handle_loop_callback(
             };
 
             let Some(task_text) = task_text else {
-                bot.send_message(chat_id, DefaultAgentView::no_saved_task())
-                    .await?;
+                reply.text(&bot, DefaultAgentView::no_saved_task()).await?;
                 return Ok(());
             };
 
@@ -496,12 +727,19 @@ pub async fn handle_loop_callback(
             let task_storage = storage.clone();
             tokio::spawn(async move {
                 let error_bot = task_bot.clone();
-                if let Err(e) =
-                    run_agent_task_with_text(task_bot, chat_id, user_id, task_text, task_storage)
-                        .await
+                if let Err(e) = run_agent_task_with_text(
+                    task_bot,
+                    chat_id,
+                    thread_id,
+                    user_id,
+                    sender_id,
+                    task_text,
+                    task_storage,
+                )
+                .await
                 {
-                    let _ = error_bot
-                        .send_message(chat_id, DefaultAgentView::error_message(&e.to_string()))
+                    let _ = reply
+                        .text(&error_bot, DefaultAgentView::error_message(&e.to_string()))
                         .await;
                 }
             });
@@ -515,22 +753,24 @@ pub async fn handle_loop_callback(
 
             match SESSION_REGISTRY.reset(&SessionId::from(user_id)).await {
                 Ok(()) => {
-                    bot.send_message(chat_id, DefaultAgentView::task_reset())
-                        .reply_markup(get_agent_keyboard())
+                    reply
+                        .keyboard(&bot, DefaultAgentView::task_reset(), get_agent_keyboard())
                         .await?;
                 }
                 Err("Session not found") => {
-                    bot.send_message(chat_id, DefaultAgentView::session_not_found())
+                    reply
+                        .text(&bot, DefaultAgentView::session_not_found())
                         .await?;
                 }
                 Err(_) => {
-                    bot.send_message(chat_id, DefaultAgentView::reset_blocked_by_task())
+                    reply
+                        .text(&bot, DefaultAgentView::reset_blocked_by_task())
                         .await?;
                 }
             }
         }
         LOOP_CALLBACK_CANCEL => {
-            cancel_agent_task_by_id(bot.clone(), user_id, chat_id).await?;
+            cancel_agent_task_by_id(bot.clone(), user_id, reply).await?;
         }
         _ => {}
     }
@@ -538,13 +778,82 @@ pub async fn handle_loop_callback(
     Ok(())
 }
 
+/// Record a 👍/👎 rating and remove the buttons, so each answer is rated once
+async fn handle_feedback_callback(bot: &Bot, q: &CallbackQuery, task_id: &str, helpful: bool) {
+    record_feedback(&FeedbackRecord::new(
+        q.from.id.0.cast_signed(),
+        task_id,
+        helpful,
+    ));
+    let _ = bot
+        .answer_callback_query(q.id.clone())
+        .text(DefaultAgentView::feedback_received())
+        .await;
+    if let Some(message) = q.message.as_ref() {
+        if let Err(e) = bot
+            .edit_message_reply_markup(message.chat().id, message.id())
+            .await
+        {
+            debug!("Failed to remove feedback buttons: {e}");
+        }
+    }
+}
+
+/// Debug handler (`/debug [session_id]`, admins only)
+///
+/// Dumps the caller's agent session, or the given one. While a task runs the
+/// executor is locked, so only the run progress is shown.
+///
+/// # Errors
+///
+/// Returns an error if the reply cannot be sent.
+pub async fn debug_session(
+    bot: Bot,
+    msg: Message,
+    settings: Arc<BotSettings>,
+    arg: String,
+) -> Result<()> {
+    let sender_id = get_sender_id(&msg);
+    let reply = ReplyTo::message(&msg);
+    if !settings.telegram.admin_users().contains(&sender_id) {
+        warn!("User {sender_id} tried to use /debug without admin rights.");
+        reply.text(&bot, "⛔️ Admins only.").await?;
+        return Ok(());
+    }
+
+    let arg = arg.trim();
+    let session_id = if arg.is_empty() {
+        get_user_id_safe(&msg)
+    } else if let Ok(id) = arg.parse::<i64>() {
+        id
+    } else {
+        reply.text(&bot, "Usage: /debug [session_id]").await?;
+        return Ok(());
+    };
+    info!("Admin {sender_id} requested debug info for session {session_id}.");
+
+    let report = match SESSION_REGISTRY.get(&SessionId::from(session_id)).await {
+        None => DefaultAgentView::session_not_found().to_string(),
+        Some(executor_arc) => match executor_arc.try_read() {
+            Ok(executor) => session_debug_report(session_id, &executor.debug_info()),
+            Err(_) => running_session_debug_report(
+                session_id,
+                &debug::run_progress(session_id).unwrap_or_default(),
+            ),
+        },
+    };
+    reply.html().text(&bot, report).await?;
+    Ok(())
+}
+
 /// Cancel the current agent task
 ///
 /// # Errors
 ///
 /// Returns an error if the cancellation message cannot be sent.
 pub async fn cancel_agent_task(bot: Bot, msg: Message, _dialogue: AgentDialogue) -> Result<()> {
-    let user_id = msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed());
+    let user_id = get_user_id_safe(&msg);
+    let reply = ReplyTo::message(&msg);
 
     // Access the cancellation token from registry (lock-free)
     let cancelled = SESSION_REGISTRY.cancel(&SessionId::from(user_id)).await;
@@ -556,31 +865,85 @@ pub async fn cancel_agent_task(bot: Bot, msg: Message, _dialogue: AgentDialogue)
 
     let text = DefaultAgentView::task_cancelled(cleared_todos);
     if !cancelled && !cleared_todos {
-        bot.send_message(msg.chat.id, DefaultAgentView::no_active_task())
-            .reply_markup(get_agent_keyboard())
+        reply
+            .keyboard(
+                &bot,
+                DefaultAgentView::no_active_task(),
+                get_agent_keyboard(),
+            )
             .await?;
     } else {
-        bot.send_message(msg.chat.id, text)
-            .reply_markup(get_agent_keyboard())
-            .await?;
+        reply.keyboard(&bot, text, get_agent_keyboard()).await?;
     }
     Ok(())
 }
 
-async fn cancel_agent_task_by_id(bot: Bot, user_id: i64, chat_id: ChatId) -> Result<()> {
+async fn cancel_agent_task_by_id(bot: Bot, user_id: i64, reply: ReplyTo) -> Result<()> {
     let session_id = SessionId::from(user_id);
     let cancelled = SESSION_REGISTRY.cancel(&session_id).await;
     let cleared_todos = SESSION_REGISTRY.clear_todos(&session_id).await;
 
     let text = DefaultAgentView::task_cancelled(cleared_todos);
     if !cancelled && !cleared_todos {
-        bot.send_message(chat_id, DefaultAgentView::no_active_task())
-            .reply_markup(get_agent_keyboard())
+        reply
+            .keyboard(
+                &bot,
+                DefaultAgentView::no_active_task(),
+                get_agent_keyboard(),
+            )
             .await?;
     } else {
-        bot.send_message(chat_id, text)
-            .reply_markup(get_agent_keyboard())
+        reply.keyboard(&bot, text, get_agent_keyboard()).await?;
+    }
+
+    Ok(())
+}
+
+/// Start a fresh agent task (`/newtask`), clearing memory and todos but keeping the sandbox
+///
+/// # Errors
+///
+/// Returns an error if the dialogue state cannot be read or the reply cannot be sent.
+pub async fn start_new_task(
+    bot: Bot,
+    msg: Message,
+    storage: Arc<dyn StorageProvider>,
+    dialogue: AgentDialogue,
+) -> Result<()> {
+    let user_id = get_user_id_safe(&msg);
+    let reply = ReplyTo::message(&msg);
+
+    if !matches!(
+        dialogue.get().await?,
+        Some(State::AgentMode | State::AgentAwaitingAnswer)
+    ) {
+        reply
+            .text(&bot, DefaultAgentView::new_task_requires_agent_mode())
             .await?;
+        return Ok(());
+    }
+
+    match SESSION_REGISTRY.reset(&SessionId::from(user_id)).await {
+        Ok(()) | Err("Session not found") => {
+            info!(user_id = user_id, "User started a new agent task");
+            let _ = storage.clear_agent_memory(user_id).await;
+            reply
+                .keyboard(
+                    &bot,
+                    DefaultAgentView::new_task_started(),
+                    get_agent_keyboard(),
+                )
+                .await?;
+        }
+        Err(_) => {
+            reply
+                .keyboard(
+                    &bot,
+                    DefaultAgentView::reset_blocked_by_task(),
+                    get_agent_keyboard(),
+                )
+                .await?;
+        }
     }
 
     Ok(())
@@ -597,7 +960,8 @@ pub async fn exit_agent_mode(
     dialogue: AgentDialogue,
     storage: Arc<dyn StorageProvider>,
 ) -> Result<()> {
-    let user_id = msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed());
+    let user_id = get_user_id_safe(&msg);
+    let reply = ReplyTo::message(&msg);
 
     save_memory_after_task(user_id, &storage).await;
     SESSION_REGISTRY.remove(&SessionId::from(user_id)).await;
@@ -608,8 +972,12 @@ pub async fn exit_agent_mode(
     dialogue.update(State::Start).await?;
 
     let keyboard = crate::bot::handlers::get_main_keyboard();
-    bot.send_message(msg.chat.id, "👋 Exited agent mode. Select a working mode:")
-        .reply_markup(keyboard)
+    reply
+        .keyboard(
+            &bot,
+            "👋 Exited agent mode. Select a working mode:",
+            keyboard,
+        )
         .await?;
     Ok(())
 }
@@ -625,6 +993,7 @@ pub async fn confirm_destructive_action(
     msg: Message,
     dialogue: AgentDialogue,
 ) -> Result<()> {
+    let reply = ReplyTo::message(&msg);
     dialogue
         .update(State::AgentConfirmation(action.clone()))
         .await?;
@@ -634,9 +1003,9 @@ pub async fn confirm_destructive_action(
         ConfirmationType::RecreateContainer => DefaultAgentView::container_wipe_confirmation(),
     };
 
-    bot.send_message(msg.chat.id, message_text)
-        .parse_mode(ParseMode::Html)
-        .reply_markup(confirmation_keyboard())
+    reply
+        .html()
+        .keyboard(&bot, message_text, confirmation_keyboard())
         .await?;
     Ok(())
 }
@@ -655,106 +1024,89 @@ pub async fn handle_agent_confirmation(
     llm: Arc<LlmClient>,
     settings: Arc<BotSettings>,
 ) -> Result<()> {
-    let user_id = msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed());
+    let user_id = get_user_id_safe(&msg);
+    let reply = ReplyTo::message(&msg);
     let text = msg.text().unwrap_or("");
-    let chat_id = msg.chat.id;
 
     if text != "✅ Yes" && text != "❌ Cancel" {
-        bot.send_message(chat_id, DefaultAgentView::select_keyboard_option())
+        reply
+            .text(&bot, DefaultAgentView::select_keyboard_option())
             .await?;
         return Ok(());
     }
 
     dialogue.update(State::AgentMode).await?;
-    let keyboard = get_agent_keyboard();
-
-    match text {
+    let notice = match text {
         "✅ Yes" => match action {
-            ConfirmationType::ClearMemory => {
-                info!(user_id = user_id, "User confirmed memory clear");
-                match SESSION_REGISTRY.reset(&SessionId::from(user_id)).await {
-                    Ok(()) => {
-                        let _ = storage.clear_agent_memory(user_id).await;
-                        bot.send_message(chat_id, DefaultAgentView::memory_cleared())
-                            .reply_markup(keyboard)
-                            .await?;
-                    }
-                    Err("Cannot reset while task is running") => {
-                        bot.send_message(chat_id, DefaultAgentView::clear_blocked_by_task())
-                            .reply_markup(keyboard)
-                            .await?;
-                    }
-                    Err(_) => {
-                        // No session — just clear storage
-                        let _ = storage.clear_agent_memory(user_id).await;
-                        bot.send_message(chat_id, DefaultAgentView::memory_cleared())
-                            .reply_markup(keyboard)
-                            .await?;
-                    }
-                }
-            }
+            ConfirmationType::ClearMemory => clear_agent_memory(user_id, &storage).await,
             ConfirmationType::RecreateContainer => {
-                info!(user_id = user_id, "User confirmed container recreation");
-                // Ensure session exists (restores from DB if needs be, or creates new)
-                ensure_session_exists(user_id, &llm, &storage, &settings).await;
-                match SESSION_REGISTRY
-                    .with_executor_mut(&SessionId::from(user_id), |executor| {
-                        Box::pin(async move {
-                            let sandbox = executor
-                                .session_mut()
-                                .ensure_sandbox()
-                                .await
-                                .map_err(AgentWipeError::SandboxAccess)?;
-                            sandbox.recreate().await.map_err(AgentWipeError::Recreate)?;
-                            Ok(())
-                        })
-                    })
-                    .await
-                {
-                    Ok(Ok(())) => {
-                        bot.send_message(chat_id, DefaultAgentView::container_recreated())
-                            .reply_markup(keyboard)
-                            .await?;
-                    }
-                    Ok(Err(AgentWipeError::SandboxAccess(e))) => {
-                        warn!(error = %e, "Sandbox access failed during container recreate");
-                        bot.send_message(chat_id, DefaultAgentView::sandbox_access_error())
-                            .reply_markup(keyboard)
-                            .await?;
-                    }
-                    Ok(Err(AgentWipeError::Recreate(e))) => {
-                        warn!(error = %e, "Container recreation failed");
-                        bot.send_message(
-                            chat_id,
-                            DefaultAgentView::container_error(&e.to_string()),
-                        )
-                        .reply_markup(keyboard)
-                        .await?;
-                    }
-                    Err("Cannot reset while task is running") => {
-                        bot.send_message(
-                            chat_id,
-                            DefaultAgentView::container_recreate_blocked_by_task(),
-                        )
-                        .reply_markup(keyboard)
-                        .await?;
-                    }
-                    Err(_) => {
-                        bot.send_message(chat_id, DefaultAgentView::sandbox_access_error())
-                            .reply_markup(keyboard)
-                            .await?;
-                    }
-                }
+                recreate_agent_container(user_id, &llm, &storage, &settings).await
             }
         },
         "❌ Cancel" => {
             info!(user_id = user_id, action = ?action, "User cancelled destructive action");
-            bot.send_message(chat_id, DefaultAgentView::operation_cancelled())
-                .reply_markup(keyboard)
-                .await?;
+            DefaultAgentView::operation_cancelled().to_string()
         }
         _ => unreachable!(),
+    };
+    reply.keyboard(&bot, notice, get_agent_keyboard()).await
+}
+
+/// Clear the agent memory of a session; returns the reply for the user
+async fn clear_agent_memory(user_id: i64, storage: &Arc<dyn StorageProvider>) -> String {
+    info!(user_id = user_id, "User confirmed memory clear");
+    match SESSION_REGISTRY.reset(&SessionId::from(user_id)).await {
+        Ok(()) => {
+            let _ = storage.clear_agent_memory(user_id).await;
+            DefaultAgentView::memory_cleared().to_string()
+        }
+        Err("Cannot reset while task is running") => {
+            DefaultAgentView::clear_blocked_by_task().to_string()
+        }
+        Err(_) => {
+            // No session — just clear storage
+            let _ = storage.clear_agent_memory(user_id).await;
+            DefaultAgentView::memory_cleared().to_string()
+        }
     }
+}
 
-    Ok(())
+/// Wipe and recreate the sandbox of a session; returns the reply for the user
+async fn recreate_agent_container(
+    user_id: i64,
+    llm: &Arc<LlmClient>,
+    storage: &Arc<dyn StorageProvider>,
+    settings: &Arc<BotSettings>,
+) -> String {
+    info!(user_id = user_id, "User confirmed container recreation");
+    // Ensure session exists (restores from DB if needs be, or creates new)
+    ensure_session_exists(user_id, llm, storage, settings).await;
+    match SESSION_REGISTRY
+        .with_executor_mut(&SessionId::from(user_id), |executor| {
+            Box::pin(async move {
+                let sandbox = executor
+                    .session_mut()
+                    .ensure_sandbox()
+                    .await
+                    .map_err(AgentWipeError::SandboxAccess)?;
+                sandbox.recreate().await.map_err(AgentWipeError::Recreate)?;
+                Ok(())
+            })
+        })
+        .await
+    {
+        Ok(Ok(())) => DefaultAgentView::container_recreated().to_string(),
+        Ok(Err(AgentWipeError::SandboxAccess(e))) => {
+            warn!(error = %e, "Sandbox access failed during container recreate");
+            DefaultAgentView::sandbox_access_error().to_string()
+        }
+        Ok(Err(AgentWipeError::Recreate(e))) => {
+            warn!(error = %e, "Container recreation failed");
+            DefaultAgentView::container_error(&e.to_string())
+        }
+        Err("Cannot reset while task is running") => {
+            DefaultAgentView::container_recreate_blocked_by_task().to_string()
+        }
+        Err(_) => DefaultAgentView::sandbox_access_error().to_string(),
+    }
 }
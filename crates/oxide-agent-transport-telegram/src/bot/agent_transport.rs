@@ -1,19 +1,24 @@
+use crate::bot::agent_handlers::AgentDialogue;
 use crate::bot::progress_render::render_progress_html;
-use crate::bot::views::{loop_action_keyboard, loop_type_label};
+use crate::bot::resilient::{send_markup_to_thread_resilient, send_message_to_thread_resilient};
+use crate::bot::state::State;
+use crate::bot::views::{loop_action_keyboard, loop_type_label, AgentView, DefaultAgentView};
 use anyhow::Result;
 use async_trait::async_trait;
 use oxide_agent_core::agent::loop_detection::LoopType;
 use oxide_agent_core::agent::progress::ProgressState;
 use oxide_agent_runtime::{AgentTransport, DeliveryMode};
 use teloxide::prelude::*;
-use teloxide::types::{ChatId, InputFile, MessageId, ParseMode};
+use teloxide::types::{ChatId, InputFile, MessageId, ParseMode, ThreadId};
 use tracing::warn;
 
 /// Telegram-specific progress runtime transport.
 pub struct TelegramAgentTransport {
     bot: Bot,
     chat_id: ChatId,
+    thread_id: Option<ThreadId>,
     progress_msg_id: MessageId,
+    dialogue: Option<AgentDialogue>,
 }
 
 impl TelegramAgentTransport {
@@ -22,9 +27,25 @@ impl TelegramAgentTransport {
         Self {
             bot,
             chat_id,
+            thread_id: None,
             progress_msg_id,
+            dialogue: None,
         }
     }
+
+    /// Send new messages into a forum topic (`None` keeps the chat itself).
+    #[must_use]
+    pub const fn with_thread(mut self, thread_id: Option<ThreadId>) -> Self {
+        self.thread_id = thread_id;
+        self
+    }
+
+    /// Switch this dialogue to [`State::AgentAwaitingAnswer`] when the agent asks a question.
+    #[must_use]
+    pub fn with_dialogue(mut self, dialogue: AgentDialogue) -> Self {
+        self.dialogue = Some(dialogue);
+        self
+    }
 }
 
 #[async_trait]
@@ -50,7 +71,10 @@ impl AgentTransport for TelegramAgentTransport {
     ) -> Result<()> {
         match mode {
             DeliveryMode::BestEffort => {
-                if let Err(e) = send_file_smart(&self.bot, self.chat_id, file_name, content).await {
+                if let Err(e) =
+                    send_file_smart(&self.bot, self.chat_id, self.thread_id, file_name, content)
+                        .await
+                {
                     warn!(file_name = %file_name, error = %e, "Failed to send file");
                     return Err(e);
                 }
@@ -58,7 +82,7 @@ impl AgentTransport for TelegramAgentTransport {
             }
             DeliveryMode::Confirmed => {
                 oxide_agent_core::utils::retry_transport_operation(|| async {
-                    send_file_smart(&self.bot, self.chat_id, file_name, content)
+                    send_file_smart(&self.bot, self.chat_id, self.thread_id, file_name, content)
                         .await
                         .map(|_| ())
                         .map_err(|e| anyhow::anyhow!("Telegram error: {e}"))
@@ -75,11 +99,48 @@ impl AgentTransport for TelegramAgentTransport {
             iteration
         );
 
-        self.bot
-            .send_message(self.chat_id, text)
-            .parse_mode(ParseMode::Html)
-            .reply_markup(loop_action_keyboard())
-            .await?;
+        send_markup_to_thread_resilient(
+            &self.bot,
+            self.chat_id,
+            self.thread_id,
+            text,
+            Some(ParseMode::Html),
+            loop_action_keyboard(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn request_clarification(&self, question: &str) -> Result<()> {
+        if let Some(dialogue) = &self.dialogue {
+            dialogue
+                .update(State::AgentAwaitingAnswer)
+                .await
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        }
+
+        send_message_to_thread_resilient(
+            &self.bot,
+            self.chat_id,
+            self.thread_id,
+            DefaultAgentView::clarification_question(question),
+            Some(ParseMode::Html),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn show_reasoning(&self, reasoning: &str) -> Result<()> {
+        send_message_to_thread_resilient(
+            &self.bot,
+            self.chat_id,
+            self.thread_id,
+            DefaultAgentView::reasoning_details(reasoning),
+            Some(ParseMode::Html),
+        )
+        .await?;
 
         Ok(())
     }
@@ -94,6 +155,7 @@ static AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "ogg", "m4a", "flac"];
 async fn send_file_smart(
     bot: &Bot,
     chat_id: ChatId,
+    thread_id: Option<ThreadId>,
     file_name: &str,
     content: &[u8],
 ) -> Result<teloxide::types::Message> {
@@ -104,10 +166,21 @@ async fn send_file_smart(
 
     let file_name_owned = file_name.to_string();
     let make_file = || InputFile::memory(content.to_vec()).file_name(file_name_owned.clone());
+    let send_document = || {
+        let mut request = bot.send_document(chat_id, make_file());
+        if let Some(thread_id) = thread_id {
+            request = request.message_thread_id(thread_id);
+        }
+        request
+    };
 
     if let Some(ext) = extension.as_deref() {
         if VIDEO_EXTENSIONS.contains(&ext) {
-            return match bot.send_video(chat_id, make_file()).await {
+            let mut request = bot.send_video(chat_id, make_file());
+            if let Some(thread_id) = thread_id {
+                request = request.message_thread_id(thread_id);
+            }
+            return match request.await {
                 Ok(msg) => Ok(msg),
                 Err(e) => {
                     warn!(
@@ -115,15 +188,17 @@ async fn send_file_smart(
                         error = %e,
                         "Failed to send video as native media; falling back to document"
                     );
-                    bot.send_document(chat_id, make_file())
-                        .await
-                        .map_err(Into::into)
+                    send_document().await.map_err(Into::into)
                 }
             };
         }
 
         if AUDIO_EXTENSIONS.contains(&ext) {
-            return match bot.send_audio(chat_id, make_file()).await {
+            let mut request = bot.send_audio(chat_id, make_file());
+            if let Some(thread_id) = thread_id {
+                request = request.message_thread_id(thread_id);
+            }
+            return match request.await {
                 Ok(msg) => Ok(msg),
                 Err(e) => {
                     warn!(
@@ -131,15 +206,11 @@ async fn send_file_smart(
                         error = %e,
                         "Failed to send audio as native media; falling back to document"
                     );
-                    bot.send_document(chat_id, make_file())
-                        .await
-                        .map_err(Into::into)
+                    send_document().await.map_err(Into::into)
                 }
             };
         }
     }
 
-    bot.send_document(chat_id, make_file())
-        .await
-        .map_err(Into::into)
+    send_document().await.map_err(Into::into)
 }
@@ -0,0 +1,130 @@
+//! `/summarize`: condense a long plain-chat history
+//!
+//! Older messages are replaced by a single summary message produced by the
+//! narrator model, while the most recent exchange is kept verbatim.
+
+use oxide_agent_core::agent::skills::types::count_tokens;
+use oxide_agent_core::storage::Message;
+use oxide_agent_core::utils::truncate_str;
+
+/// Number of most recent messages kept verbatim
+pub const SUMMARY_KEEP_LAST: usize = 4;
+
+/// Upper bound on history loaded for summarization
+pub const SUMMARY_MAX_HISTORY: usize = 500;
+
+/// Per-message character cap in the transcript sent to the summarizer
+const TRANSCRIPT_MESSAGE_CHARS: usize = 2000;
+
+/// Prefix marking the stored summary message
+pub const SUMMARY_PREFIX: &str = "Summary of our earlier conversation:";
+
+/// System prompt for the summarizer model
+pub const SUMMARY_SYSTEM_PROMPT: &str = concat!(
+    "You condense chat transcripts. Write a compact summary of the conversation below ",
+    "that preserves facts, decisions, user preferences, open questions and any names, ",
+    "numbers or code identifiers that may matter later. Use the language of the conversation. ",
+    "Reply with the summary only, without any preamble."
+);
+
+/// Outcome of a history compaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryReport {
+    /// Messages stored before compaction
+    pub before_messages: usize,
+    /// Messages stored after compaction
+    pub after_messages: usize,
+    /// Approximate tokens before compaction
+    pub before_tokens: usize,
+    /// Approximate tokens after compaction
+    pub after_tokens: usize,
+}
+
+impl SummaryReport {
+    /// Approximate tokens saved per request
+    #[must_use]
+    pub const fn saved_tokens(&self) -> usize {
+        self.before_tokens.saturating_sub(self.after_tokens)
+    }
+}
+
+/// Split history into the part to summarize and the tail kept verbatim.
+///
+/// Returns `None` when there is nothing worth summarizing.
+#[must_use]
+pub fn split_for_summary(
+    history: &[Message],
+    keep_last: usize,
+) -> Option<(&[Message], &[Message])> {
+    let split = history.len().saturating_sub(keep_last);
+    // Summarizing one or two messages would not save anything
+    (split > 2).then(|| history.split_at(split))
+}
+
+/// Render messages as a plain transcript for the summarizer.
+#[must_use]
+pub fn build_transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|m| {
+            format!(
+                "{}: {}",
+                m.role,
+                truncate_str(&m.content, TRANSCRIPT_MESSAGE_CHARS)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Build the compacted history: the summary followed by the kept messages.
+#[must_use]
+pub fn compact_history(summary: &str, kept: &[Message]) -> Vec<Message> {
+    let mut compacted = Vec::with_capacity(kept.len() + 1);
+    compacted.push(Message {
+        role: "user".to_string(),
+        content: format!("{SUMMARY_PREFIX}\n{}", summary.trim()),
+    });
+    compacted.extend_from_slice(kept);
+    compacted
+}
+
+/// Approximate token count of a history.
+#[must_use]
+pub fn history_tokens(messages: &[Message]) -> usize {
+    messages.iter().map(|m| count_tokens(&m.content)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact_history, split_for_summary, SUMMARY_PREFIX};
+    use oxide_agent_core::storage::Message;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn short_history_is_not_summarized() {
+        let history = vec![message("user", "hi"), message("assistant", "hello")];
+        assert!(split_for_summary(&history, 4).is_none());
+    }
+
+    #[test]
+    fn keeps_tail_after_summary() {
+        let history: Vec<Message> = (0..10).map(|i| message("user", &i.to_string())).collect();
+        let Some((older, kept)) = split_for_summary(&history, 4) else {
+            panic!("history should be summarized");
+        };
+        assert_eq!(older.len(), 6);
+        assert_eq!(kept.len(), 4);
+
+        let compacted = compact_history("  the gist  ", kept);
+        assert_eq!(compacted.len(), 5);
+        assert_eq!(compacted[0].content, format!("{SUMMARY_PREFIX}\nthe gist"));
+        assert_eq!(compacted[1].content, "6");
+    }
+}
@@ -0,0 +1,66 @@
+//! Downloads of user files from Telegram
+//!
+//! `get_file` and the download itself are retried together with backoff, so
+//! a download that drops midway starts over with a fresh file path. Large
+//! documents are streamed to a temporary file instead of being buffered.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::FileId;
+use tracing::warn;
+
+/// Documents larger than this are streamed to disk instead of kept in memory
+pub const STREAM_TO_DISK_BYTES: u32 = 5 * 1024 * 1024;
+
+/// Shown to the user once all download attempts have failed
+pub const DOWNLOAD_FAILED_MESSAGE: &str =
+    "⚠️ Failed to download the file from Telegram. Please send it again.";
+
+/// Download a file into memory
+///
+/// # Errors
+///
+/// Returns an error with [`DOWNLOAD_FAILED_MESSAGE`] once retries are exhausted.
+pub async fn download_to_memory(bot: &Bot, file_id: &FileId) -> Result<Vec<u8>> {
+    oxide_agent_core::utils::retry_transport_operation(|| async {
+        let file = bot.get_file(file_id.clone()).await?;
+        let mut buf = Vec::new();
+        bot.download_file(&file.path, &mut buf).await?;
+        Ok(buf)
+    })
+    .await
+    .context(DOWNLOAD_FAILED_MESSAGE)
+}
+
+/// Download a file into `path`, replacing a partial file from a failed attempt
+///
+/// The file is removed if every attempt fails.
+///
+/// # Errors
+///
+/// Returns an error with [`DOWNLOAD_FAILED_MESSAGE`] once retries are exhausted.
+pub async fn download_to_path(bot: &Bot, file_id: &FileId, path: &Path) -> Result<()> {
+    let result = oxide_agent_core::utils::retry_transport_operation(|| async {
+        let file = bot.get_file(file_id.clone()).await?;
+        let mut dst = tokio::fs::File::create(path).await?;
+        bot.download_file(&file.path, &mut dst).await?;
+        dst.sync_all().await?;
+        Ok(())
+    })
+    .await;
+
+    if result.is_err() {
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            warn!(path = %path.display(), error = %e, "Failed to remove partial download");
+        }
+    }
+    result.context(DOWNLOAD_FAILED_MESSAGE)
+}
+
+/// Temporary path for a file attached to `msg`
+#[must_use]
+pub fn temp_download_path(msg: &Message) -> PathBuf {
+    std::env::temp_dir().join(format!("oxide-upload-{}-{}", msg.chat.id.0, msg.id.0))
+}
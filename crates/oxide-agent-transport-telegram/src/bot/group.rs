@@ -0,0 +1,142 @@
+//! Group chat support
+//!
+//! With `GROUP_MODE` enabled the bot only reacts to group messages that are
+//! addressed to it (commands, mentions, replies to its messages and keyboard
+//! buttons), and keys conversation storage by `(chat_id, thread_id)` so a
+//! group or forum topic shares one history instead of one per member.
+//! Access checks keep using the sender's own Telegram ID.
+
+use super::handlers::{get_chat_keyboard, get_extra_functions_keyboard, get_main_keyboard};
+use super::views::get_agent_keyboard;
+use crate::config::get_group_mode;
+use teloxide::types::{Chat, KeyboardMarkup, Me, Message, ThreadId};
+
+/// Whether the chat is a group or supergroup
+#[must_use]
+pub fn is_group_chat(chat: &Chat) -> bool {
+    chat.is_group() || chat.is_supergroup()
+}
+
+/// Forum topic of the message, if it was sent inside one
+#[must_use]
+pub fn topic_thread_id(msg: &Message) -> Option<ThreadId> {
+    msg.is_topic_message.then_some(msg.thread_id).flatten()
+}
+
+/// Storage key for a group conversation.
+///
+/// Group chat IDs are negative and never collide with user IDs. Forum topics
+/// get a stable FNV-1a hash of `(chat_id, thread_id)`, also kept negative.
+#[must_use]
+pub fn group_scope_id(chat_id: i64, thread_id: Option<i32>) -> i64 {
+    let Some(thread_id) = thread_id else {
+        return chat_id;
+    };
+
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+    let hash = chat_id
+        .to_le_bytes()
+        .into_iter()
+        .chain(thread_id.to_le_bytes())
+        .fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+
+    // Top bit cleared so the value fits i64; shifted below zero to stay clear of user IDs
+    -(hash >> 1).cast_signed() - 1
+}
+
+/// Conversation key for a chat: the sender in private chats, the group (topic) in group mode
+#[must_use]
+pub fn conversation_scope_id(chat: &Chat, thread_id: Option<ThreadId>, sender_id: i64) -> i64 {
+    if !is_group_chat(chat) || !get_group_mode() {
+        return sender_id;
+    }
+    group_scope_id(chat.id.0, thread_id.map(|thread| thread.0 .0))
+}
+
+/// Whether a group message should be ignored because it is not addressed to the bot.
+///
+/// Always `false` for private chats and when `GROUP_MODE` is off.
+#[must_use]
+pub fn is_unaddressed_group_message(msg: &Message, me: &Me) -> bool {
+    is_group_chat(&msg.chat) && get_group_mode() && !is_addressed_to_bot(msg, me)
+}
+
+fn is_addressed_to_bot(msg: &Message, me: &Me) -> bool {
+    let username = me.username();
+    let text = msg.text().or_else(|| msg.caption()).unwrap_or_default();
+
+    if let Some(command) = text.strip_prefix('/') {
+        let command = command.split_whitespace().next().unwrap_or_default();
+        return command
+            .split_once('@')
+            .is_none_or(|(_, target)| target.eq_ignore_ascii_case(username));
+    }
+
+    if mentions(text, username) || is_keyboard_text(text) {
+        return true;
+    }
+
+    // In forum topics every message "replies" to the topic's opening message
+    msg.reply_to_message().is_some_and(|reply| {
+        let is_topic_root = msg.thread_id.is_some_and(|thread| thread.0 == reply.id);
+        !is_topic_root && reply.from.as_ref().is_some_and(|from| from.id == me.id)
+    })
+}
+
+fn mentions(text: &str, username: &str) -> bool {
+    let mention = format!("@{}", username.to_lowercase());
+    let text = text.to_lowercase();
+    text.match_indices(&mention).any(|(start, _)| {
+        text[start + mention.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || c == '_'))
+    })
+}
+
+fn is_keyboard_text(text: &str) -> bool {
+    let keyboards: [KeyboardMarkup; 4] = [
+        get_main_keyboard(),
+        get_chat_keyboard(),
+        get_extra_functions_keyboard(),
+        get_agent_keyboard(),
+    ];
+    keyboards
+        .iter()
+        .flat_map(|keyboard| keyboard.keyboard.iter().flatten())
+        .any(|button| button.text == text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_scope_id_is_stable_and_negative() {
+        assert_eq!(group_scope_id(-100_123, None), -100_123);
+
+        let topic = group_scope_id(-1_001_847_508_954, Some(4));
+        assert!(topic < 0);
+        assert_eq!(topic, group_scope_id(-1_001_847_508_954, Some(4)));
+        assert_ne!(topic, group_scope_id(-1_001_847_508_954, Some(5)));
+        assert_ne!(topic, -1_001_847_508_954);
+    }
+
+    #[test]
+    fn mentions_require_word_boundary() {
+        assert!(mentions("hey @OxideBot, help", "oxidebot"));
+        assert!(mentions("@oxidebot", "OxideBot"));
+        assert!(!mentions("hey @oxidebot_fan", "oxidebot"));
+        assert!(!mentions("no mention here", "oxidebot"));
+    }
+
+    #[test]
+    fn keyboard_buttons_count_as_addressed() {
+        assert!(is_keyboard_text("🤖 Agent Mode"));
+        assert!(is_keyboard_text("❌ Cancel Task"));
+        assert!(!is_keyboard_text("Agent Mode please"));
+    }
+}
@@ -1,19 +1,23 @@
+use crate::bot::agent::{check_incoming_file, UploadTarget};
+use crate::bot::chat_summary;
+use crate::bot::download::{download_to_memory, DOWNLOAD_FAILED_MESSAGE};
+use crate::bot::group;
+use crate::bot::regenerate;
 use crate::bot::state::State;
-use crate::bot::UnauthorizedCache;
+use crate::bot::{MaintenanceMode, UnauthorizedCache};
 use crate::config::BotSettings;
 use anyhow::{anyhow, Result};
-use oxide_agent_core::llm::{LlmClient, Message as LlmMessage};
-use oxide_agent_core::storage::StorageProvider;
+use oxide_agent_core::llm::{LlmClient, LlmError, Message as LlmMessage};
+use oxide_agent_core::storage::{Message as StoredMessage, StorageProvider};
 use oxide_agent_core::utils::truncate_str;
 use std::sync::Arc;
 use teloxide::{
     dispatching::dialogue::InMemStorage,
-    net::Download,
     prelude::*,
     types::{KeyboardButton, KeyboardMarkup, ParseMode},
     utils::command::BotCommands,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 // Helper function to get user name from Message
 fn get_user_name(msg: &Message) -> String {
@@ -38,9 +42,18 @@ fn resolve_chat_model(settings: &BotSettings, stored_model: Option<String>) -> S
     settings.agent.get_default_chat_model_name()
 }
 
-/// Safe extraction of user ID from a message.
+/// Safe extraction of the conversation ID from a message.
+///
+/// This is the sender's ID, except for group chats in group mode where it is
+/// the group (or forum topic) scope, see [`group::conversation_scope_id`].
+/// Use [`get_sender_id`] for access checks.
 /// Returns 0 if the user information is missing.
 pub fn get_user_id_safe(msg: &Message) -> i64 {
+    group::conversation_scope_id(&msg.chat, group::topic_thread_id(msg), get_sender_id(msg))
+}
+
+/// Telegram ID of the message sender, or 0 if missing.
+pub fn get_sender_id(msg: &Message) -> i64 {
     msg.from.as_ref().map_or(0, |u| u.id.0.cast_signed())
 }
 
@@ -100,6 +113,98 @@ pub enum Command {
     /// Show bot statistics
     #[command(description = "Show bot statistics.")]
     Stats,
+    /// Start a fresh agent task, forgetting the previous conversation
+    #[command(description = "Start a new agent task from scratch.")]
+    NewTask,
+    /// Set the voice transcription language
+    #[command(description = "Set voice language (e.g. /lang ru, /lang auto).")]
+    Lang(String),
+    /// Condense the plain-chat history into a summary
+    #[command(description = "Condense chat history into a short summary.")]
+    Summarize,
+    /// Answer the last chat message again, replacing the previous answer
+    #[command(description = "Regenerate the last answer.")]
+    Regenerate,
+    /// Show the caller's Telegram ID and access level
+    #[command(description = "Show your Telegram ID and access level.")]
+    Whoami,
+    /// Toggle maintenance mode (admins only)
+    #[command(description = "Pause new requests: /maintenance on|off (admins only).")]
+    Maintenance(String),
+    /// Dump an agent session's state (admins only)
+    #[command(description = "Show agent session state: /debug [session_id] (admins only).")]
+    Debug(String),
+    /// Re-read the allowed user lists (admins only)
+    #[command(description = "Reload allowed users from .env and config (admins only).")]
+    ReloadUsers,
+}
+
+/// Commands only admins may run; kept out of the menu and the onboarding
+const ADMIN_COMMANDS: &[&str] = &["maintenance", "debug", "reloadusers"];
+
+/// Commands shown in Telegram's command menu, without admin commands
+///
+/// # Examples
+///
+/// ```
+/// use oxide_agent_transport_telegram::bot::handlers::menu_commands;
+/// let commands = menu_commands();
+/// assert!(commands.iter().any(|c| c.command == "start"));
+/// assert!(!commands.iter().any(|c| c.command == "maintenance"));
+/// ```
+#[must_use]
+pub fn menu_commands() -> Vec<teloxide::types::BotCommand> {
+    Command::bot_commands()
+        .into_iter()
+        .map(|mut cmd| {
+            cmd.command = cmd.command.trim_start_matches('/').to_string();
+            cmd
+        })
+        .filter(|cmd| !ADMIN_COMMANDS.contains(&cmd.command.as_str()))
+        .collect()
+}
+
+/// Welcome message for `/start`: what the modes do and how to use them
+///
+/// # Examples
+///
+/// ```
+/// use oxide_agent_transport_telegram::bot::handlers::onboarding_text;
+/// let text = onboarding_text(true);
+/// assert!(text.contains("/newtask"));
+/// assert!(!text.contains("/reloadusers"));
+/// assert!(onboarding_text(false).contains("/whoami"));
+/// ```
+#[must_use]
+pub fn onboarding_text(agent_access: bool) -> String {
+    let commands: String = menu_commands()
+        .iter()
+        .map(|cmd| {
+            format!(
+                "/{} — {}\n",
+                cmd.command,
+                html_escape::encode_text(&cmd.description)
+            )
+        })
+        .collect();
+    let agent_hint = if agent_access {
+        "Press <b>🤖 Agent Mode</b> below and describe a task in plain words. \
+         Send <b>Exit Agent Mode</b> to come back."
+    } else {
+        "Agent Mode needs separate access: send /whoami and pass your ID to the bot owner."
+    };
+
+    format!(
+        "👋 <b>I am Oxide Agent.</b>\n\n\
+         I am here to automate your routine. I work in two modes:\n\n\
+         💬 <b>Chat Mode</b> — quick questions and answers, voice messages and images. \
+         Pick a model with <i>Change Model</i>.\n\
+         🤖 <b>Agent Mode</b> — I solve tasks on my own: write and run code in a sandbox, \
+         search the web, download and process video and files, and send you the results.\n\n\
+         <b>How to start the agent:</b> {agent_hint}\n\n\
+         <b>Commands:</b>\n{commands}\n\
+         👇 <b>Choose a mode:</b>"
+    )
 }
 
 /// Create the main menu keyboard
@@ -205,15 +310,11 @@ pub async fn start(
     let model = resolve_chat_model(&settings, saved_model);
     info!("User {user_id} ({user_name}) is allowed. Set model to {model}");
 
-    let text = "👋 <b>I am Oxide Agent.</b>\n\n\
-         I am here to automate your routine. Switch me to <b>Agent Mode</b>, and I can:\n\n\
-         • Write and run code\n\
-         • Download and process video/files\n\
-         • Google information for you\n\n\
-         I don't just answer questions — I solve tasks.\n\n\
-         <i>Also available: <b>Chat Mode</b> for simple questions.</i>\n\n\
-         👇 <b>Enable full power:</b>"
-        .to_string();
+    let agent_access = settings
+        .telegram
+        .agent_allowed_users()
+        .contains(&get_sender_id(&msg));
+    let text = onboarding_text(agent_access);
 
     info!("Sending welcome message to user {user_id}.");
     bot.send_message(msg.chat.id, text)
@@ -256,6 +357,281 @@ pub async fn clear(bot: Bot, msg: Message, storage: Arc<dyn StorageProvider>) ->
     Ok(())
 }
 
+/// Voice transcription language handler (`/lang <code>`, `/lang auto`)
+///
+/// # Errors
+///
+/// Returns an error if the user configuration cannot be saved or the reply cannot be sent.
+pub async fn set_language(
+    bot: Bot,
+    msg: Message,
+    storage: Arc<dyn StorageProvider>,
+    language: String,
+) -> Result<()> {
+    let user_id = get_user_id_safe(&msg);
+    let language = language.trim();
+
+    if language.is_empty() {
+        let current = user_language(&storage, user_id).await;
+        let text = format!(
+            "Voice language: <b>{}</b>\nUsage: /lang ru, /lang en or /lang auto",
+            current.as_deref().unwrap_or("auto")
+        );
+        bot.send_message(msg.chat.id, text)
+            .parse_mode(ParseMode::Html)
+            .await?;
+        return Ok(());
+    }
+
+    let is_valid = language.len() <= 32
+        && language
+            .chars()
+            .all(|c| c.is_alphabetic() || c == '-' || c == '_');
+    if !is_valid {
+        bot.send_message(
+            msg.chat.id,
+            "Invalid language. Use a code or name like ru, en-US or Russian.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let new_language = (!language.eq_ignore_ascii_case("auto")).then(|| language.to_string());
+    let mut config = storage.get_user_config(user_id).await?;
+    config.language.clone_from(&new_language);
+    storage.update_user_config(user_id, config).await?;
+    info!("User {user_id} set voice language to {new_language:?}.");
+
+    let reply = new_language.map_or_else(
+        || "Voice language set to autodetect.".to_string(),
+        |l| format!("Voice language set to {l}."),
+    );
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+/// Maintenance toggle handler (`/maintenance on|off`, admins only)
+///
+/// # Errors
+///
+/// Returns an error if the reply cannot be sent.
+pub async fn maintenance(
+    bot: Bot,
+    msg: Message,
+    settings: Arc<BotSettings>,
+    mode: Arc<MaintenanceMode>,
+    arg: String,
+) -> Result<()> {
+    let user_id = get_sender_id(&msg);
+    if !settings.telegram.admin_users().contains(&user_id) {
+        warn!("User {user_id} tried to toggle maintenance mode without admin rights.");
+        bot.send_message(msg.chat.id, "⛔️ Admins only.").await?;
+        return Ok(());
+    }
+
+    let reply = match arg.trim().to_ascii_lowercase().as_str() {
+        "on" => {
+            mode.set(true);
+            info!("Maintenance mode enabled by admin {user_id}.");
+            "🛠 Maintenance mode ON: new requests are rejected, running tasks continue."
+        }
+        "off" => {
+            mode.set(false);
+            info!("Maintenance mode disabled by admin {user_id}.");
+            "✅ Maintenance mode OFF: accepting requests again."
+        }
+        _ if mode.is_enabled() => "Maintenance mode is ON. Usage: /maintenance on|off",
+        _ => "Maintenance mode is OFF. Usage: /maintenance on|off",
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+/// Access list reload handler (`/reloadusers`, admins only)
+///
+/// # Errors
+///
+/// Returns an error if the reply cannot be sent.
+pub async fn reload_users(bot: Bot, msg: Message, settings: Arc<BotSettings>) -> Result<()> {
+    let user_id = get_sender_id(&msg);
+    if !settings.telegram.admin_users().contains(&user_id) {
+        warn!("User {user_id} tried to reload user lists without admin rights.");
+        bot.send_message(msg.chat.id, "⛔️ Admins only.").await?;
+        return Ok(());
+    }
+
+    let reply = match settings.telegram.reload_user_lists() {
+        Ok(sizes) => {
+            info!("User lists reloaded by admin {user_id}.");
+            format!(
+                "✅ User lists reloaded: {} chat, {} agent, {} admin(s).",
+                sizes.allowed, sizes.agent, sizes.admins
+            )
+        }
+        Err(e) => {
+            error!("Failed to reload user lists: {e}");
+            format!("❌ Reload failed, the previous lists stay in effect: {e}")
+        }
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+/// Load the user's voice transcription language (`None` = autodetect)
+pub(crate) async fn user_language(
+    storage: &Arc<dyn StorageProvider>,
+    user_id: i64,
+) -> Option<String> {
+    match storage.get_user_config(user_id).await {
+        Ok(config) => config.language,
+        Err(e) => {
+            warn!("Failed to load language for user {user_id}: {e}");
+            None
+        }
+    }
+}
+
+/// Summarize handler: replaces older chat history with a model-written summary
+///
+/// # Errors
+///
+/// Returns an error if the history cannot be loaded or saved, or the reply cannot be sent.
+pub async fn summarize(
+    bot: Bot,
+    msg: Message,
+    storage: Arc<dyn StorageProvider>,
+    llm: Arc<LlmClient>,
+) -> Result<()> {
+    let user_id = get_user_id_safe(&msg);
+    let history = storage
+        .get_chat_history(user_id, chat_summary::SUMMARY_MAX_HISTORY)
+        .await?;
+
+    let Some((older, kept)) =
+        chat_summary::split_for_summary(&history, chat_summary::SUMMARY_KEEP_LAST)
+    else {
+        bot.send_message(msg.chat.id, "Chat history is too short to summarize.")
+            .await?;
+        return Ok(());
+    };
+
+    bot.send_chat_action(msg.chat.id, teloxide::types::ChatAction::Typing)
+        .await?;
+
+    let transcript = chat_summary::build_transcript(older);
+    let summary = match llm
+        .chat_completion(
+            chat_summary::SUMMARY_SYSTEM_PROMPT,
+            &[],
+            &transcript,
+            &llm.narrator_model,
+        )
+        .await
+    {
+        Ok(summary) if !summary.trim().is_empty() => summary,
+        Ok(_) => {
+            bot.send_message(msg.chat.id, "The model returned an empty summary.")
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            error!("Summarization failed for user {user_id}: {e}");
+            bot.send_message(
+                msg.chat.id,
+                format!("Failed to summarize chat history: {e}"),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let compacted = chat_summary::compact_history(&summary, kept);
+    let report = chat_summary::SummaryReport {
+        before_messages: history.len(),
+        after_messages: compacted.len(),
+        before_tokens: chat_summary::history_tokens(&history),
+        after_tokens: chat_summary::history_tokens(&compacted),
+    };
+    storage.replace_chat_history(user_id, compacted).await?;
+    info!(
+        "Summarized chat history for user {user_id}: {} -> {} messages",
+        report.before_messages, report.after_messages
+    );
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "📝 Chat history summarized: {} → {} messages, ~{} → ~{} tokens (~{} saved).",
+            report.before_messages,
+            report.after_messages,
+            report.before_tokens,
+            report.after_tokens,
+            report.saved_tokens()
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Regenerate handler: re-answers the last chat message and replaces the old answer
+///
+/// # Errors
+///
+/// Returns an error if the history cannot be loaded or saved, or the reply cannot be sent.
+pub async fn regenerate(
+    bot: Bot,
+    msg: Message,
+    storage: Arc<dyn StorageProvider>,
+    llm: Arc<LlmClient>,
+    settings: Arc<BotSettings>,
+) -> Result<()> {
+    let user_id = get_user_id_safe(&msg);
+    let history = storage.get_chat_history(user_id, usize::MAX).await?;
+    let Some((context, question)) = regenerate::split_for_regenerate(&history) else {
+        bot.send_message(msg.chat.id, "There is no answer to regenerate yet.")
+            .await?;
+        return Ok(());
+    };
+
+    let system_prompt = storage
+        .get_user_prompt(user_id)
+        .await?
+        .unwrap_or_else(|| std::env::var("SYSTEM_MESSAGE").unwrap_or_default());
+    let saved_model = storage.get_user_model(user_id).await?;
+    let model = resolve_chat_model(&settings, saved_model);
+    let context = &context[context.len().saturating_sub(regenerate::REGENERATE_CONTEXT)..];
+
+    let placeholder =
+        send_thinking_placeholder(&bot, msg.chat.id, group::topic_thread_id(&msg)).await?;
+
+    match llm
+        .chat_completion(
+            &system_prompt,
+            &to_llm_history(context.to_vec()),
+            &question.content,
+            &model,
+        )
+        .await
+    {
+        Ok(response) => {
+            // Re-read: messages sent during generation must not be overwritten
+            let current = storage.get_chat_history(user_id, usize::MAX).await?;
+            match regenerate::replace_last_answer(&current, &history, response.clone()) {
+                Some(updated) => {
+                    storage.replace_chat_history(user_id, updated).await?;
+                    info!("Regenerated the last answer for user {user_id}");
+                }
+                None => warn!(
+                    "Chat history of user {user_id} changed during regeneration, not saving the new answer"
+                ),
+            }
+            replace_placeholder(&bot, &placeholder, &response).await?;
+        }
+        Err(e) => replace_placeholder_with_error(&bot, &placeholder, &e).await?,
+    }
+    Ok(())
+}
+
 /// Healthcheck handler
 ///
 /// # Errors
@@ -302,6 +678,62 @@ pub async fn stats(bot: Bot, msg: Message, cache: Arc<UnauthorizedCache>) -> Res
     Ok(())
 }
 
+/// Whoami handler - reports the caller's ID, access lists and default models
+///
+/// Also answers users without access, so they can share their ID with an admin.
+///
+/// # Errors
+///
+/// Returns an error if the reply cannot be sent.
+pub async fn whoami(
+    bot: Bot,
+    msg: Message,
+    storage: Arc<dyn StorageProvider>,
+    settings: Arc<BotSettings>,
+) -> Result<()> {
+    let user_id = get_sender_id(&msg);
+    info!("Whoami command received from user {user_id}.");
+
+    let chat_access = settings.telegram.allowed_users().contains(&user_id);
+    let agent_access = settings.telegram.agent_allowed_users().contains(&user_id);
+    let admin = settings.telegram.admin_users().contains(&user_id);
+    let mark = |granted: bool| if granted { "✅" } else { "❌" };
+
+    let mut text = format!(
+        "<b>👤 Who am I</b>\n\n\
+        Telegram ID: <code>{user_id}</code>\n\
+        {} Chat mode (allowed_users)\n\
+        {} Agent mode (agent_allowed_users)",
+        mark(chat_access),
+        mark(agent_access),
+    );
+    if admin {
+        text.push_str("\n👑 Admin");
+    }
+
+    if chat_access {
+        let saved_model = storage
+            .get_user_model(get_user_id_safe(&msg))
+            .await
+            .unwrap_or(None);
+        let model = resolve_chat_model(&settings, saved_model);
+        text.push_str(&format!("\n\nChat model: <b>{model}</b>"));
+        if agent_access {
+            let (agent_model, _, _) = settings.agent.get_configured_agent_model();
+            text.push_str(&format!("\nAgent model: <b>{agent_model}</b>"));
+        }
+    }
+
+    if !chat_access || !agent_access {
+        text.push_str("\n\n<i>Send your ID to the bot administrator to request access.</i>");
+    }
+
+    bot.send_message(msg.chat.id, text)
+        .parse_mode(ParseMode::Html)
+        .await?;
+    Ok(())
+}
+
 /// Text message handler
 ///
 /// # Errors
@@ -401,7 +833,7 @@ async fn handle_menu_commands(
             Ok(true)
         }
         "🤖 Agent Mode" => {
-            if check_agent_access(bot, msg, settings, user_id).await? {
+            if check_agent_access(bot, msg, settings, get_sender_id(msg)).await? {
                 crate::bot::agent_handlers::activate_agent_mode(
                     bot.clone(),
                     msg.clone(),
@@ -541,42 +973,65 @@ async fn process_llm_request(
     storage
         .save_message(user_id, "user".to_string(), text.clone())
         .await?;
-    bot.send_chat_action(msg.chat.id, teloxide::types::ChatAction::Typing)
-        .await?;
-
-    let llm_history: Vec<LlmMessage> = history
-        .into_iter()
-        .map(|m| LlmMessage {
-            role: m.role,
-            content: m.content,
-            tool_call_id: None,
-            name: None,
-            tool_calls: None,
-        })
-        .collect();
+    let placeholder =
+        send_thinking_placeholder(&bot, msg.chat.id, group::topic_thread_id(&msg)).await?;
 
     match llm
-        .chat_completion(&system_prompt, &llm_history, &text, &model)
+        .chat_completion(&system_prompt, &to_llm_history(history), &text, &model)
         .await
     {
         Ok(response) => {
             storage
                 .save_message(user_id, "assistant".to_string(), response.clone())
                 .await?;
-            send_long_message(&bot, msg.chat.id, &response).await?;
-        }
-        Err(e) => {
-            bot.send_message(msg.chat.id, format!("<b>Error:</b> {e}"))
-                .parse_mode(ParseMode::Html)
-                .await?;
+            replace_placeholder(&bot, &placeholder, &response).await?;
         }
+        Err(e) => replace_placeholder_with_error(&bot, &placeholder, &e).await?,
     }
     Ok(())
 }
 
-/// Re-export the shared send_long_message function for convenience.
-/// This function formats text and splits it into multiple messages if needed.
-use super::messaging::send_long_message;
+/// Shows an LLM error in place of the thinking placeholder
+async fn replace_placeholder_with_error(
+    bot: &Bot,
+    placeholder: &Message,
+    error: &LlmError,
+) -> Result<()> {
+    let error = format!(
+        "<b>Error:</b> {}",
+        html_escape::encode_text(&error.to_string())
+    );
+    if !edit_message_safe_resilient(bot, placeholder.chat.id, placeholder.id, &error).await {
+        send_message_to_thread_resilient(
+            bot,
+            placeholder.chat.id,
+            placeholder.thread_id,
+            error,
+            Some(ParseMode::Html),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stored chat history as LLM messages
+fn to_llm_history(history: Vec<StoredMessage>) -> Vec<LlmMessage> {
+    history
+        .into_iter()
+        .map(|m| LlmMessage {
+            role: m.role,
+            content: m.content,
+            tool_call_id: None,
+            name: None,
+            tool_calls: None,
+        })
+        .collect()
+}
+
+/// Shared helper that formats text and splits it into multiple messages if needed.
+use super::messaging::send_long_message_to_thread;
+use super::messaging::{replace_placeholder, send_thinking_placeholder};
+use super::resilient::{edit_message_safe_resilient, send_message_to_thread_resilient};
 
 /// Voice message handler
 ///
@@ -618,6 +1073,10 @@ pub async fn handle_voice(
     }
 
     let voice = msg.voice().ok_or_else(|| anyhow!("No voice found"))?;
+    if let Some(reason) = check_incoming_file(&msg, UploadTarget::Chat) {
+        bot.send_message(msg.chat.id, reason).await?;
+        return Ok(());
+    }
     let saved_model = storage.get_user_model(user_id).await?;
     let model = resolve_chat_model(&settings, saved_model);
 
@@ -628,17 +1087,30 @@ pub async fn handle_voice(
         .await?;
 
     // Download voice file with retry logic
-    let buffer = oxide_agent_core::utils::retry_transport_operation(|| async {
-        let file = bot.get_file(voice.file.id.clone()).await?;
-        let mut buf = Vec::new();
-        bot.download_file(&file.path, &mut buf).await?;
-        Ok(buf)
-    })
-    .await?;
+    let buffer = match download_to_memory(&bot, &voice.file.id).await {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            warn!("Voice download failed: {e:#}");
+            bot.send_message(msg.chat.id, DOWNLOAD_FAILED_MESSAGE)
+                .await?;
+            return Ok(());
+        }
+    };
 
     let model_id = provider_info.as_ref().map_or("unknown", |p| &p.id);
+    let mime_type = voice
+        .mime_type
+        .as_ref()
+        .map_or("audio/ogg", |mime| mime.essence_str());
+    let language = user_language(&storage, user_id).await;
     match llm
-        .transcribe_audio_with_fallback(provider_name, buffer, "audio/wav", model_id)
+        .transcribe_audio_with_fallback(
+            provider_name,
+            buffer,
+            mime_type,
+            language.as_deref(),
+            model_id,
+        )
         .await
     {
         Ok(text) => {
@@ -706,6 +1178,10 @@ pub async fn handle_photo(
         .photo()
         .and_then(|p| p.last())
         .ok_or_else(|| anyhow!("No photo found"))?;
+    if let Some(reason) = check_incoming_file(&msg, UploadTarget::Chat) {
+        bot.send_message(msg.chat.id, reason).await?;
+        return Ok(());
+    }
     let caption = msg.caption().unwrap_or("Describe this image.");
     let saved_model = storage.get_user_model(user_id).await?;
     let model = resolve_chat_model(&settings, saved_model);
@@ -718,13 +1194,15 @@ pub async fn handle_photo(
         .await?;
 
     // Download photo file with retry logic
-    let buffer = oxide_agent_core::utils::retry_transport_operation(|| async {
-        let file = bot.get_file(photo.file.id.clone()).await?;
-        let mut buf = Vec::new();
-        bot.download_file(&file.path, &mut buf).await?;
-        Ok(buf)
-    })
-    .await?;
+    let buffer = match download_to_memory(&bot, &photo.file.id).await {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            warn!("Photo download failed: {e:#}");
+            bot.send_message(msg.chat.id, DOWNLOAD_FAILED_MESSAGE)
+                .await?;
+            return Ok(());
+        }
+    };
 
     bot.send_chat_action(msg.chat.id, teloxide::types::ChatAction::Typing)
         .await?;
@@ -739,7 +1217,8 @@ pub async fn handle_photo(
             storage
                 .save_message(user_id, "assistant".to_string(), response.clone())
                 .await?;
-            send_long_message(&bot, msg.chat.id, &response).await?;
+            send_long_message_to_thread(&bot, msg.chat.id, group::topic_thread_id(&msg), &response)
+                .await?;
         }
         Err(e) => {
             bot.send_message(msg.chat.id, format!("Image analysis error: {e}"))
@@ -765,7 +1244,7 @@ pub async fn handle_document(
 ) -> Result<()> {
     let state = dialogue.get().await?.unwrap_or(State::Start);
 
-    if matches!(state, State::AgentMode) {
+    if matches!(state, State::AgentMode | State::AgentAwaitingAnswer) {
         Box::pin(super::agent_handlers::handle_agent_message(
             bot, msg, storage, llm, dialogue, settings,
         ))
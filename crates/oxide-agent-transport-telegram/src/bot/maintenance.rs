@@ -0,0 +1,61 @@
+//! Global maintenance switch
+//!
+//! While enabled, new messages and commands are answered with a maintenance
+//! notice and dropped. Agent tasks that are already running keep going, so a
+//! deploy can wait for them to drain.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+
+/// Reply sent to users while maintenance mode is on
+pub const MAINTENANCE_MESSAGE: &str =
+    "🛠 The bot is under maintenance. New requests are paused, please try again later.";
+
+/// Shared maintenance flag, toggled by admins via `/maintenance on|off`
+#[derive(Debug, Default)]
+pub struct MaintenanceMode {
+    enabled: AtomicBool,
+}
+
+impl MaintenanceMode {
+    /// Create the switch with an initial state
+    #[must_use]
+    pub const fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+        }
+    }
+
+    /// Whether new work is currently rejected
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turn maintenance mode on or off, returning the previous state
+    pub fn set(&self, enabled: bool) -> bool {
+        let previous = self.enabled.swap(enabled, Ordering::Relaxed);
+        if previous != enabled {
+            info!(
+                "Maintenance mode {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
+        }
+        previous
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MaintenanceMode;
+
+    #[test]
+    fn toggles_and_reports_previous_state() {
+        let mode = MaintenanceMode::new(false);
+        assert!(!mode.is_enabled());
+        assert!(!mode.set(true));
+        assert!(mode.is_enabled());
+        assert!(mode.set(false));
+        assert!(!mode.is_enabled());
+    }
+}
@@ -3,10 +3,12 @@
 //! Contains reusable functions for sending formatted messages,
 //! handling long message splitting, and other Telegram-specific transformations.
 
+use crate::config::get_thinking_placeholder;
 use anyhow::Result;
 use oxide_agent_core::utils;
 use teloxide::prelude::*;
-use teloxide::types::{ChatId, ParseMode};
+use teloxide::types::{ChatId, InlineKeyboardMarkup, ParseMode, ThreadId};
+use tracing::{debug, warn};
 
 /// Maximum message length for Telegram with safety margin.
 /// Telegram's official limit is 4096, but we use 4000 to account for
@@ -39,16 +41,136 @@ pub const TELEGRAM_MESSAGE_LIMIT: usize = 4000;
 /// send_long_message(&bot, chat_id, &very_long_response).await?;
 /// ```
 pub async fn send_long_message(bot: &Bot, chat_id: ChatId, text: &str) -> Result<()> {
+    send_long_message_to_thread(bot, chat_id, None, text).await
+}
+
+/// Sends a long message into a forum topic, see [`send_long_message`].
+///
+/// A `thread_id` of `None` sends to the chat itself.
+///
+/// # Errors
+///
+/// Returns an error if any message fails to send.
+pub async fn send_long_message_to_thread(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    text: &str,
+) -> Result<()> {
+    send_parts(bot, chat_id, thread_id, text).await?;
+    Ok(())
+}
+
+/// Sends a long message like [`send_long_message_to_thread`] and attaches
+/// `markup` (e.g. feedback buttons) to its last part.
+///
+/// # Errors
+///
+/// Returns an error if any message fails to send; a failure to attach the
+/// markup is only logged.
+pub async fn send_long_message_with_markup(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    text: &str,
+    markup: InlineKeyboardMarkup,
+) -> Result<()> {
+    let Some(last) = send_parts(bot, chat_id, thread_id, text).await? else {
+        return Ok(());
+    };
+    if let Err(e) = bot
+        .edit_message_reply_markup(chat_id, last.id)
+        .reply_markup(markup)
+        .await
+    {
+        warn!("Failed to attach inline keyboard: {e}");
+    }
+    Ok(())
+}
+
+/// Sends the parts of a long message and returns the last one sent.
+async fn send_parts(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    text: &str,
+) -> Result<Option<Message>> {
     // Split raw Markdown first - split_long_message correctly handles ``` fences
     let parts = utils::split_long_message(text, TELEGRAM_MESSAGE_LIMIT);
 
+    let mut last = None;
     for part in parts {
         // Format each part to HTML after splitting to ensure proper tag closure
         let formatted = utils::format_text(&part);
         // Use resilient send with automatic retry on network failures
-        super::resilient::send_message_resilient(bot, chat_id, formatted, Some(ParseMode::Html))
-            .await?;
+        last = Some(
+            super::resilient::send_message_to_thread_resilient(
+                bot,
+                chat_id,
+                thread_id,
+                formatted,
+                Some(ParseMode::Html),
+            )
+            .await?,
+        );
     }
 
-    Ok(())
+    Ok(last)
+}
+
+/// Sends the `THINKING_PLACEHOLDER` status message, so the user gets instant
+/// feedback while the model works.
+///
+/// # Errors
+///
+/// Returns an error if the message fails to send.
+pub async fn send_thinking_placeholder(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+) -> Result<Message> {
+    let text = html_escape::encode_text(&get_thinking_placeholder()).into_owned();
+    super::resilient::send_message_to_thread_resilient(
+        bot,
+        chat_id,
+        thread_id,
+        text,
+        Some(ParseMode::Html),
+    )
+    .await
+}
+
+/// Replaces a placeholder with the formatted answer `text`.
+///
+/// An answer that fits in one message is edited into the placeholder;
+/// a longer one (or a failed edit) deletes the placeholder and is sent
+/// with [`send_long_message_to_thread`].
+///
+/// # Errors
+///
+/// Returns an error if the answer fails to send.
+pub async fn replace_placeholder(bot: &Bot, placeholder: &Message, text: &str) -> Result<()> {
+    let parts = utils::split_long_message(text, TELEGRAM_MESSAGE_LIMIT);
+    if let [part] = parts.as_slice() {
+        let formatted = utils::format_text(part);
+        if formatted.chars().count() <= TELEGRAM_MESSAGE_LIMIT
+            && super::resilient::edit_message_safe_resilient(
+                bot,
+                placeholder.chat.id,
+                placeholder.id,
+                &formatted,
+            )
+            .await
+        {
+            return Ok(());
+        }
+    }
+
+    if let Err(e) = bot
+        .delete_message(placeholder.chat.id, placeholder.id)
+        .await
+    {
+        debug!("Failed to delete placeholder message: {e}");
+    }
+    send_long_message_to_thread(bot, placeholder.chat.id, placeholder.thread_id, text).await
 }
@@ -4,12 +4,22 @@ pub mod agent;
 pub mod agent_handlers;
 /// Telegram transport adapter for the agent runtime
 pub mod agent_transport;
+/// `/summarize` history compaction helpers
+pub mod chat_summary;
+/// Telegram file downloads with retry
+pub mod download;
+/// Group chat addressing and conversation scoping
+pub mod group;
 /// General command and message handlers
 pub mod handlers;
+/// Global maintenance switch
+pub mod maintenance;
 /// Common messaging utilities (split long messages, formatting)
 pub mod messaging;
 /// Progress rendering for UI outputs
 pub mod progress_render;
+/// `/regenerate` history helpers
+pub mod regenerate;
 /// Resilient messaging with automatic retry for Telegram API operations
 pub mod resilient;
 /// User state and dialogue management
@@ -19,4 +29,5 @@ pub mod unauthorized_cache;
 /// View layer for UI components (keyboards, messages)
 pub mod views;
 
+pub use maintenance::MaintenanceMode;
 pub use unauthorized_cache::UnauthorizedCache;
@@ -88,27 +88,37 @@ pub fn render_progress_html(state: &ProgressState) -> String {
 fn format_grouped_steps(state: &ProgressState) -> Vec<String> {
     use std::collections::HashMap;
 
-    let mut counts: HashMap<&str, usize> = HashMap::new();
+    // (call count, total execution time in ms)
+    let mut totals: HashMap<&str, (usize, u64)> = HashMap::new();
 
     for step in &state.steps {
         if step.status == StepStatus::Completed {
             if let Some(ref tool_name) = step.tool_name {
-                *counts.entry(tool_name.as_str()).or_insert(0) += 1;
+                let entry = totals.entry(tool_name.as_str()).or_insert((0, 0));
+                entry.0 += 1;
+                entry.1 += step.duration_ms.unwrap_or(0);
             }
         }
     }
 
-    let mut sorted: Vec<_> = counts.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut sorted: Vec<_> = totals.into_iter().collect();
+    sorted.sort_by_key(|entry| std::cmp::Reverse(entry.1 .0));
 
     sorted
         .into_iter()
-        .map(|(name, count)| {
-            if count > 1 {
+        .map(|(name, (count, duration_ms))| {
+            let mut line = if count > 1 {
                 format!("  ✅ {} ×{}", name, count)
             } else {
                 format!("  ✅ {}", name)
+            };
+            if duration_ms > 0 {
+                line.push_str(&format!(
+                    " {}",
+                    oxide_agent_core::utils::format_duration_ms(duration_ms)
+                ));
             }
+            line
         })
         .collect()
 }
@@ -154,6 +164,7 @@ mod tests {
         state.update(AgentEvent::ToolResult {
             name: "web_search".to_string(),
             output: "result1".to_string(),
+            duration_ms: 1200,
         });
         state.update(AgentEvent::ToolCall {
             name: "web_search".to_string(),
@@ -163,6 +174,7 @@ mod tests {
         state.update(AgentEvent::ToolResult {
             name: "web_search".to_string(),
             output: "result2".to_string(),
+            duration_ms: 1100,
         });
         state.update(AgentEvent::ToolCall {
             name: "execute_command".to_string(),
@@ -172,7 +184,7 @@ mod tests {
 
         let output = render_progress_html(&state);
 
-        assert!(output.contains("✅ web_search ×2"));
+        assert!(output.contains("✅ web_search ×2 2.3s"));
         assert!(output.contains("⏳ 🔧 ls -la"));
     }
 
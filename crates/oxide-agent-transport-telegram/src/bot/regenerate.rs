@@ -0,0 +1,108 @@
+//! `/regenerate`: re-answer the last plain-chat message
+//!
+//! The last assistant reply is dropped and the model answers the same user
+//! message again, with the same earlier context the original answer had.
+
+use oxide_agent_core::storage::Message;
+
+/// Number of earlier messages sent as context, as for a regular chat message
+pub const REGENERATE_CONTEXT: usize = 10;
+
+/// Split history into the earlier context and the last user message.
+///
+/// Returns `None` unless the history ends with a user message followed by
+/// the assistant reply to regenerate.
+#[must_use]
+pub fn split_for_regenerate(history: &[Message]) -> Option<(&[Message], &Message)> {
+    let [context @ .., question, answer] = history else {
+        return None;
+    };
+    (question.role == "user" && answer.role == "assistant").then_some((context, question))
+}
+
+/// `current` history with its last reply replaced by `answer`.
+///
+/// `regenerated` is the history the new answer was generated from. Returns
+/// `None` if `current` no longer ends with the same question and reply, e.g.
+/// because a message arrived while the answer was being generated.
+#[must_use]
+pub fn replace_last_answer(
+    current: &[Message],
+    regenerated: &[Message],
+    answer: String,
+) -> Option<Vec<Message>> {
+    let same = |a: &Message, b: &Message| a.role == b.role && a.content == b.content;
+    let unchanged = match (current, regenerated) {
+        ([.., question, reply], [.., old_question, old_reply]) => {
+            split_for_regenerate(current).is_some()
+                && same(question, old_question)
+                && same(reply, old_reply)
+        }
+        _ => false,
+    };
+    if !unchanged {
+        return None;
+    }
+    let mut updated = current.to_vec();
+    if let Some(last) = updated.last_mut() {
+        last.content = answer;
+    }
+    Some(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replace_last_answer, split_for_regenerate};
+    use oxide_agent_core::storage::Message;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn regenerates_only_after_an_answer() {
+        assert!(split_for_regenerate(&[]).is_none());
+        assert!(split_for_regenerate(&[message("assistant", "hello")]).is_none());
+        assert!(
+            split_for_regenerate(&[message("assistant", "hello"), message("user", "hi")]).is_none()
+        );
+    }
+
+    #[test]
+    fn replaces_the_last_answer() {
+        let history = vec![
+            message("user", "hi"),
+            message("assistant", "hello"),
+            message("user", "tell a joke"),
+            message("assistant", "old joke"),
+        ];
+        let Some((context, question)) = split_for_regenerate(&history) else {
+            panic!("history ends with an answer");
+        };
+        assert_eq!(context.len(), 2);
+        assert_eq!(question.content, "tell a joke");
+
+        let Some(updated) = replace_last_answer(&history, &history, "new joke".to_string()) else {
+            panic!("history is unchanged");
+        };
+        assert_eq!(updated.len(), 4);
+        assert_eq!(updated[3].content, "new joke");
+        assert_eq!(updated[2].content, "tell a joke");
+    }
+
+    #[test]
+    fn keeps_history_that_changed_meanwhile() {
+        let history = vec![
+            message("user", "tell a joke"),
+            message("assistant", "old joke"),
+        ];
+        let mut current = history.clone();
+        current.push(message("user", "another one"));
+        current.push(message("assistant", "second joke"));
+        assert!(replace_last_answer(&current, &history, "new joke".to_string()).is_none());
+        assert!(replace_last_answer(&[], &history, "new joke".to_string()).is_none());
+    }
+}
@@ -17,7 +17,7 @@
 
 use anyhow::Result;
 use teloxide::prelude::*;
-use teloxide::types::{ChatId, Message, MessageId, ParseMode};
+use teloxide::types::{ChatId, Message, MessageId, ParseMode, ReplyMarkup, ThreadId};
 use tracing::{debug, warn};
 
 /// Send a message with automatic retry on network failures.
@@ -47,12 +47,72 @@ pub async fn send_message_resilient(
     text: impl Into<String>,
     parse_mode: Option<ParseMode>,
 ) -> Result<Message> {
-    let text = text.into();
+    send_message_to_thread_resilient(bot, chat_id, None, text, parse_mode).await
+}
+
+/// Send a message into a forum topic with automatic retry on network failures.
+///
+/// Same as [`send_message_resilient`]; `thread_id` of `None` sends to the chat itself.
+///
+/// # Errors
+///
+/// Returns an error after all retries are exhausted.
+pub async fn send_message_to_thread_resilient(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    text: impl Into<String>,
+    parse_mode: Option<ParseMode>,
+) -> Result<Message> {
+    send_to_thread_resilient(bot, chat_id, thread_id, text.into(), parse_mode, None).await
+}
+
+/// Send a message with a keyboard into a forum topic with automatic retry on
+/// network failures.
+///
+/// Same as [`send_message_to_thread_resilient`], with `markup` attached.
+///
+/// # Errors
+///
+/// Returns an error after all retries are exhausted.
+pub async fn send_markup_to_thread_resilient(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    text: impl Into<String>,
+    parse_mode: Option<ParseMode>,
+    markup: impl Into<ReplyMarkup>,
+) -> Result<Message> {
+    send_to_thread_resilient(
+        bot,
+        chat_id,
+        thread_id,
+        text.into(),
+        parse_mode,
+        Some(markup.into()),
+    )
+    .await
+}
+
+async fn send_to_thread_resilient(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    text: String,
+    parse_mode: Option<ParseMode>,
+    markup: Option<ReplyMarkup>,
+) -> Result<Message> {
     oxide_agent_core::utils::retry_transport_operation(|| async {
         let mut req = bot.send_message(chat_id, text.clone());
         if let Some(pm) = parse_mode {
             req = req.parse_mode(pm);
         }
+        if let Some(thread_id) = thread_id {
+            req = req.message_thread_id(thread_id);
+        }
+        if let Some(markup) = markup.clone() {
+            req = req.reply_markup(markup);
+        }
         req.await
             .map_err(|e| anyhow::anyhow!("Telegram send error: {e}"))
     })
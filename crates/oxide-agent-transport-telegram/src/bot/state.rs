@@ -23,4 +23,6 @@ pub enum State {
     ChatMode,
     /// Confirmation for destructive agent actions
     AgentConfirmation(ConfirmationType),
+    /// Agent mode while the running task waits for the user's answer to `ask_user`
+    AgentAwaitingAnswer,
 }
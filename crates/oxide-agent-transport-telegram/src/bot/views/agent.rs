@@ -2,7 +2,10 @@
 //!
 //! Contains keyboards, text messages, and formatters for agent mode.
 
+use oxide_agent_core::agent::debug::{RunProgress, SessionDebugInfo};
 use oxide_agent_core::agent::loop_detection::LoopType;
+use oxide_agent_core::agent::AgentStatus;
+use oxide_agent_core::utils::truncate_str;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup};
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -15,6 +18,10 @@ pub const LOOP_CALLBACK_RETRY: &str = "retry_no_loop";
 pub const LOOP_CALLBACK_RESET: &str = "reset_task";
 /// Callback data for cancelling the current task
 pub const LOOP_CALLBACK_CANCEL: &str = "cancel_task";
+/// Callback data prefix for 👍 on an answer, followed by the task ID
+pub const FEEDBACK_CALLBACK_UP: &str = "fb_up:";
+/// Callback data prefix for 👎 on an answer, followed by the task ID
+pub const FEEDBACK_CALLBACK_DOWN: &str = "fb_down:";
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Trait definition
@@ -45,6 +52,15 @@ pub trait AgentView {
     /// Message when task is already running
     fn task_already_running() -> &'static str;
 
+    /// Reply to a message sent while a task runs; it is added at the next step
+    fn instruction_queued() -> &'static str;
+
+    /// Reply to a message without an actual instruction (blank, emoji only)
+    fn empty_task() -> &'static str;
+
+    /// Callback notification after a 👍/👎 rating
+    fn feedback_received() -> &'static str;
+
     /// Message when session not found
     fn session_not_found() -> &'static str;
 
@@ -74,6 +90,12 @@ pub trait AgentView {
     /// Cannot reset while running
     fn reset_blocked_by_task() -> &'static str;
 
+    /// Fresh task started via `/newtask`
+    fn new_task_started() -> &'static str;
+
+    /// `/newtask` used outside agent mode
+    fn new_task_requires_agent_mode() -> &'static str;
+
     /// Format loop detected message
     fn loop_detected_message(loop_type: LoopType, iteration: usize) -> String;
 
@@ -91,6 +113,18 @@ pub trait AgentView {
 
     /// Sandbox access error
     fn sandbox_access_error() -> &'static str;
+
+    /// Question the agent asked mid-task (HTML)
+    fn clarification_question(question: &str) -> String;
+
+    /// Confirmation that the answer was handed to the agent
+    fn answer_received() -> &'static str;
+
+    /// Reply to a non-text message while the agent waits for an answer
+    fn answer_requires_text() -> &'static str;
+
+    /// Model reasoning shown with `SHOW_REASONING` (HTML, collapsed)
+    fn reasoning_details(reasoning: &str) -> String;
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -143,6 +177,18 @@ I work autonomously: I'll create a plan, execute code, and provide the result."#
         "⏳ Task is already running. Press ❌ Cancel Task to stop it."
     }
 
+    fn instruction_queued() -> &'static str {
+        "📝 Got it — the agent will take this into account at its next step.\nPress ❌ Cancel Task to stop the task instead."
+    }
+
+    fn empty_task() -> &'static str {
+        "✍️ Please describe what the agent should do, e.g. \"Find the latest Rust release notes and summarize them\"."
+    }
+
+    fn feedback_received() -> &'static str {
+        "Thanks for the feedback!"
+    }
+
     fn session_not_found() -> &'static str {
         "⚠️ Agent session not found."
     }
@@ -183,6 +229,14 @@ I work autonomously: I'll create a plan, execute code, and provide the result."#
         "⚠️ Cannot reset task while it is running."
     }
 
+    fn new_task_started() -> &'static str {
+        "🆕 Starting a new task: conversation and task list cleared. Sandbox files are kept."
+    }
+
+    fn new_task_requires_agent_mode() -> &'static str {
+        "⚠️ /newtask is only available in agent mode."
+    }
+
     fn loop_detected_message(loop_type: LoopType, iteration: usize) -> String {
         format!(
             "🔁 <b>Loop detected in task execution</b>\nType: {}\nIteration: {}\n\nChoose an action:",
@@ -210,8 +264,38 @@ I work autonomously: I'll create a plan, execute code, and provide the result."#
     fn sandbox_access_error() -> &'static str {
         "Sandbox manager access error."
     }
+
+    fn clarification_question(question: &str) -> String {
+        format!(
+            "❓ <b>The agent needs clarification</b>\n\n{}\n\n<i>Reply with a message to continue.</i>",
+            html_escape::encode_text(question)
+        )
+    }
+
+    fn answer_received() -> &'static str {
+        "✅ Answer sent, continuing the task."
+    }
+
+    fn answer_requires_text() -> &'static str {
+        "✍️ The agent is waiting for your answer. Please reply with a text message."
+    }
+
+    fn reasoning_details(reasoning: &str) -> String {
+        let total = reasoning.chars().count();
+        let mut shown = truncate_str(reasoning, REASONING_MAX_CHARS);
+        if total > REASONING_MAX_CHARS {
+            shown.push_str("\n…");
+        }
+        format!(
+            "🧠 <b>Reasoning</b>\n<blockquote expandable>{}</blockquote>",
+            html_escape::encode_text(&shown)
+        )
+    }
 }
 
+/// Reasoning longer than this is cut so the message stays under Telegram's 4096-char limit
+const REASONING_MAX_CHARS: usize = 3000;
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Helper functions
 // ─────────────────────────────────────────────────────────────────────────────
@@ -223,9 +307,85 @@ pub fn loop_type_label(loop_type: LoopType) -> &'static str {
         LoopType::ToolCallLoop => "Repetitive calls",
         LoopType::ContentLoop => "Repetitive text",
         LoopType::CognitiveLoop => "Stuck",
+        LoopType::FatalErrorLoop => "Repeated fatal error",
     }
 }
 
+/// `/debug` report for an idle session (HTML)
+#[must_use]
+pub fn session_debug_report(session_id: i64, info: &SessionDebugInfo) -> String {
+    let status = match &info.status {
+        AgentStatus::Idle => "idle".to_string(),
+        AgentStatus::Processing { step, .. } => format!("processing ({step})"),
+        AgentStatus::Completed => "completed".to_string(),
+        AgentStatus::TimedOut => "timed out".to_string(),
+        AgentStatus::Error(e) => format!("error: {e}"),
+    };
+    let skills = if info.loaded_skills.is_empty() {
+        "none".to_string()
+    } else {
+        info.loaded_skills.join(", ")
+    };
+    let todos = if info.todos.items.is_empty() {
+        "none".to_string()
+    } else {
+        let items: Vec<String> = info
+            .todos
+            .items
+            .iter()
+            .map(|item| format!("\n{} {}", item.status, truncate_str(&item.description, 80)))
+            .collect();
+        format!(
+            "{}/{} done{}",
+            info.todos.completed_count(),
+            info.todos.items.len(),
+            items.concat()
+        )
+    };
+
+    let text = format!(
+        "Status: {status}\n\
+        {}\n\
+        Messages: {}\n\
+        Tokens: ~{} / {}\n\
+        Skills: {skills}\n\
+        Loop detection: {}\n\
+        Todos: {todos}",
+        run_progress_lines(&info.run),
+        info.messages,
+        info.tokens,
+        info.max_tokens,
+        if info.loop_detection_disabled {
+            "off"
+        } else {
+            "on"
+        },
+    );
+    format!(
+        "🐞 <b>Agent session {session_id}</b>\n\n{}",
+        html_escape::encode_text(&text)
+    )
+}
+
+/// `/debug` report for a session whose task holds the executor (HTML)
+#[must_use]
+pub fn running_session_debug_report(session_id: i64, run: &RunProgress) -> String {
+    format!(
+        "🐞 <b>Agent session {session_id}</b>\n\n\
+        Status: task running\n{}\n\n\
+        <i>Memory, skills and todos are locked by the running task.</i>",
+        html_escape::encode_text(&run_progress_lines(run))
+    )
+}
+
+fn run_progress_lines(run: &RunProgress) -> String {
+    format!(
+        "Iterations: {}\nLast tool: {}",
+        run.iterations,
+        run.last_tool.as_deref().unwrap_or("none")
+    )
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Keyboards
 // ─────────────────────────────────────────────────────────────────────────────
@@ -265,6 +425,36 @@ pub fn loop_action_keyboard() -> InlineKeyboardMarkup {
     ])
 }
 
+/// Get the 👍/👎 inline keyboard attached to a task's answer
+#[must_use]
+pub fn feedback_keyboard(task_id: &str) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("👍", format!("{FEEDBACK_CALLBACK_UP}{task_id}")),
+        InlineKeyboardButton::callback("👎", format!("{FEEDBACK_CALLBACK_DOWN}{task_id}")),
+    ]])
+}
+
+/// Parse feedback callback data into `(task_id, helpful)`
+///
+/// # Examples
+///
+/// ```
+/// use oxide_agent_transport_telegram::bot::views::agent::parse_feedback_callback;
+/// assert_eq!(parse_feedback_callback("fb_up:1234"), Some(("1234", true)));
+/// assert_eq!(parse_feedback_callback("fb_down:1234"), Some(("1234", false)));
+/// assert_eq!(parse_feedback_callback("fb_up:"), None);
+/// assert_eq!(parse_feedback_callback("reset_task"), None);
+/// ```
+#[must_use]
+pub fn parse_feedback_callback(data: &str) -> Option<(&str, bool)> {
+    let (task_id, helpful) = if let Some(task_id) = data.strip_prefix(FEEDBACK_CALLBACK_UP) {
+        (task_id, true)
+    } else {
+        (data.strip_prefix(FEEDBACK_CALLBACK_DOWN)?, false)
+    };
+    (!task_id.is_empty()).then_some((task_id, helpful))
+}
+
 /// Get the confirmation keyboard for destructive actions
 #[must_use]
 pub fn confirmation_keyboard() -> KeyboardMarkup {
@@ -1,9 +1,11 @@
 //! Telegram transport settings.
 
 use config::ConfigError;
+use oxide_agent_core::agent::messages::AgentLanguage;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
+use tracing::info;
 
 /// Telegram transport settings loaded from environment variables.
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -16,6 +18,97 @@ pub struct TelegramSettings {
     /// Comma-separated list of allowed user IDs for agent mode.
     #[serde(rename = "agent_access_ids")]
     pub agent_allowed_users_str: Option<String>,
+    /// Comma-separated list of admin user IDs (may use `/maintenance`).
+    #[serde(rename = "admin_ids")]
+    pub admin_users_str: Option<String>,
+    /// User lists loaded by [`TelegramSettings::reload_user_lists`]; once set
+    /// they replace the lists above. Shared by all clones.
+    #[serde(skip)]
+    reloaded_users: Arc<RwLock<Option<UserLists>>>,
+    /// Access lists the `.env` file held at startup.
+    #[serde(skip)]
+    startup_dotenv: UserLists,
+}
+
+/// Access lists as configured, before parsing.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct UserLists {
+    allowed_users: Option<String>,
+    agent_access_ids: Option<String>,
+    admin_ids: Option<String>,
+}
+
+impl UserLists {
+    /// Access lists from `KEY=value` entries; empty values count as unset.
+    fn from_entries(entries: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut lists = Self::default();
+        for (key, value) in entries {
+            let value = Some(value).filter(|v| !v.is_empty());
+            match key.as_str() {
+                "ALLOWED_USERS" => lists.allowed_users = value,
+                "AGENT_ACCESS_IDS" => lists.agent_access_ids = value,
+                "ADMIN_IDS" => lists.admin_ids = value,
+                _ => {}
+            }
+        }
+        lists
+    }
+
+    /// Access lists from the `.env` file, found the same way startup finds it
+    /// (current directory, then its parents). Empty when there is no file.
+    fn from_dotenv() -> Self {
+        dotenvy::dotenv_iter()
+            .map(|entries| Self::from_entries(entries.flatten()))
+            .unwrap_or_default()
+    }
+
+    /// Merge the loaded configuration with the `.env` file.
+    ///
+    /// `.env` values win. A key removed from `.env` since startup is dropped:
+    /// the process environment still holds the value startup loaded from the
+    /// file, so `config` would otherwise keep the removed IDs.
+    fn reloaded(config: Self, startup: &Self, current: Self) -> Self {
+        fn pick(
+            config: Option<String>,
+            startup: Option<&String>,
+            current: Option<String>,
+        ) -> Option<String> {
+            match current {
+                Some(value) => Some(value),
+                None if startup.is_some() && config.as_ref() == startup => None,
+                None => config,
+            }
+        }
+
+        Self {
+            allowed_users: pick(
+                config.allowed_users,
+                startup.allowed_users.as_ref(),
+                current.allowed_users,
+            ),
+            agent_access_ids: pick(
+                config.agent_access_ids,
+                startup.agent_access_ids.as_ref(),
+                current.agent_access_ids,
+            ),
+            admin_ids: pick(
+                config.admin_ids,
+                startup.admin_ids.as_ref(),
+                current.admin_ids,
+            ),
+        }
+    }
+}
+
+/// Number of users in each access list after a reload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserListSizes {
+    /// Users allowed to chat.
+    pub allowed: usize,
+    /// Users allowed to use agent mode.
+    pub agent: usize,
+    /// Admins.
+    pub admins: usize,
 }
 
 /// Combined settings used by the Telegram transport layer.
@@ -45,38 +138,200 @@ impl TelegramSettings {
     ///
     /// Returns a `ConfigError` if loading fails.
     pub fn new() -> Result<Self, ConfigError> {
-        oxide_agent_core::config::build_config()?.try_deserialize()
+        let mut settings: Self = oxide_agent_core::config::build_config()?.try_deserialize()?;
+        settings.startup_dotenv = UserLists::from_dotenv();
+        Ok(settings)
     }
 
     /// Returns a set of allowed user IDs for normal chat.
     #[must_use]
     pub fn allowed_users(&self) -> HashSet<i64> {
-        self.allowed_users_str
-            .as_ref()
-            .map(|s| {
-                s.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
-                    .filter(|token| !token.is_empty())
-                    .filter_map(|id| id.parse::<i64>().ok())
-                    .collect()
-            })
-            .unwrap_or_default()
+        self.user_list(|lists| &lists.allowed_users, &self.allowed_users_str)
     }
 
     /// Returns a set of allowed user IDs for agent mode.
     #[must_use]
     pub fn agent_allowed_users(&self) -> HashSet<i64> {
-        self.agent_allowed_users_str
-            .as_ref()
-            .map(|s| {
-                s.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
-                    .filter(|token| !token.is_empty())
-                    .filter_map(|id| id.parse::<i64>().ok())
-                    .collect()
-            })
-            .unwrap_or_default()
+        self.user_list(
+            |lists| &lists.agent_access_ids,
+            &self.agent_allowed_users_str,
+        )
+    }
+
+    /// Returns a set of admin user IDs.
+    #[must_use]
+    pub fn admin_users(&self) -> HashSet<i64> {
+        self.user_list(|lists| &lists.admin_ids, &self.admin_users_str)
+    }
+
+    /// Re-read `ALLOWED_USERS`, `AGENT_ACCESS_IDS` and `ADMIN_IDS` so access
+    /// changes apply without a restart.
+    ///
+    /// The `.env` file is parsed directly and its values win over the process
+    /// environment, which still holds what startup loaded from it; IDs removed
+    /// from the file are revoked. Config files are read as at startup.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` if the configuration cannot be loaded; the
+    /// current lists then stay in effect.
+    pub fn reload_user_lists(&self) -> Result<UserListSizes, ConfigError> {
+        let config: UserLists = oxide_agent_core::config::build_config()?.try_deserialize()?;
+        let lists = UserLists::reloaded(config, &self.startup_dotenv, UserLists::from_dotenv());
+
+        *self
+            .reloaded_users
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(lists);
+        let sizes = UserListSizes {
+            allowed: self.allowed_users().len(),
+            agent: self.agent_allowed_users().len(),
+            admins: self.admin_users().len(),
+        };
+        info!(?sizes, "User lists reloaded.");
+        Ok(sizes)
+    }
+
+    /// The reloaded list if there is one, otherwise the startup list.
+    fn user_list(
+        &self,
+        reloaded: impl Fn(&UserLists) -> &Option<String>,
+        startup: &Option<String>,
+    ) -> HashSet<i64> {
+        let lists = self
+            .reloaded_users
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let raw = lists.as_ref().map_or(startup, reloaded);
+        parse_user_ids(raw.as_deref())
     }
 }
 
+/// Parse user IDs separated by commas, semicolons or whitespace.
+fn parse_user_ids(raw: Option<&str>) -> HashSet<i64> {
+    raw.map(|s| {
+        s.split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .filter_map(|id| id.parse::<i64>().ok())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Get the initial maintenance mode state.
+///
+/// Environment variable: `MAINTENANCE_MODE`.
+#[must_use]
+pub fn get_maintenance_mode() -> bool {
+    std::env::var("MAINTENANCE_MODE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Whether group chats use group mode.
+///
+/// In group mode the bot only answers messages addressed to it and shares
+/// one conversation per group (or forum topic).
+///
+/// Environment variable: `GROUP_MODE`.
+#[must_use]
+pub fn get_group_mode() -> bool {
+    std::env::var("GROUP_MODE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Get the status message shown until the model's answer is ready.
+///
+/// Defaults to "🤔 Thinking..." in the `AGENT_LANGUAGE` language.
+///
+/// Environment variable: `THINKING_PLACEHOLDER`.
+#[must_use]
+pub fn get_thinking_placeholder() -> String {
+    std::env::var("THINKING_PLACEHOLDER")
+        .ok()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| AgentLanguage::current().thinking_placeholder().to_string())
+}
+
+/// Default grace period (seconds) for running agent tasks on shutdown.
+pub const SHUTDOWN_GRACE_SECS: u64 = 30;
+
+/// Get how long shutdown waits for cancelled agent tasks to finish.
+///
+/// Environment variable: `SHUTDOWN_GRACE_SECS`.
+#[must_use]
+pub fn get_shutdown_grace_secs() -> u64 {
+    std::env::var("SHUTDOWN_GRACE_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SHUTDOWN_GRACE_SECS)
+}
+
+/// Default local port for the webhook listener.
+pub const TELEGRAM_WEBHOOK_PORT: u16 = 8443;
+
+/// Get the public URL Telegram delivers updates to.
+///
+/// When unset, the bot receives updates with long polling.
+///
+/// Environment variable: `TELEGRAM_WEBHOOK_URL`.
+#[must_use]
+pub fn get_telegram_webhook_url() -> Option<String> {
+    std::env::var("TELEGRAM_WEBHOOK_URL")
+        .ok()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// Get the local port the webhook listener binds to.
+///
+/// Environment variable: `TELEGRAM_WEBHOOK_PORT`.
+#[must_use]
+pub fn get_telegram_webhook_port() -> u16 {
+    std::env::var("TELEGRAM_WEBHOOK_PORT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(TELEGRAM_WEBHOOK_PORT)
+}
+
+/// Get the secret Telegram sends in the `X-Telegram-Bot-Api-Secret-Token`
+/// header; requests without it are rejected.
+///
+/// When unset, a random secret is generated on every start.
+///
+/// Environment variable: `TELEGRAM_WEBHOOK_SECRET`.
+#[must_use]
+pub fn get_telegram_webhook_secret() -> Option<String> {
+    std::env::var("TELEGRAM_WEBHOOK_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+}
+
+/// Whether Telegram accepts `secret` as a webhook secret token: 1-256
+/// characters out of `A-Z`, `a-z`, `0-9`, `_` and `-`.
+#[must_use]
+pub fn is_valid_webhook_secret(secret: &str) -> bool {
+    (1..=256).contains(&secret.len())
+        && secret
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// Get the address of the `/healthz` and `/readyz` probe server.
+///
+/// When unset, no probe server is started.
+///
+/// Environment variable: `HEALTH_ADDR` (e.g. `0.0.0.0:8081`).
+#[must_use]
+pub fn get_health_addr() -> Option<String> {
+    std::env::var("HEALTH_ADDR")
+        .ok()
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty())
+}
+
 /// Cooldown period (seconds) between "Access Denied" messages for same user.
 /// Default: 20 minutes.
 pub const UNAUTHORIZED_COOLDOWN_SECS: u64 = 1200;
@@ -121,7 +376,74 @@ pub fn get_unauthorized_cache_max_size() -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use super::TelegramSettings;
+    use super::{is_valid_webhook_secret, TelegramSettings, UserLists};
+    use std::sync::PoisonError;
+
+    fn lists(entries: &[(&str, &str)]) -> UserLists {
+        UserLists::from_entries(
+            entries
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string())),
+        )
+    }
+
+    #[test]
+    fn test_reload_revokes_ids_removed_from_dotenv() {
+        // The process environment still holds what startup loaded from `.env`
+        let config = lists(&[("ALLOWED_USERS", "1,2"), ("ADMIN_IDS", "1")]);
+        let startup = lists(&[("ALLOWED_USERS", "1,2"), ("ADMIN_IDS", "1")]);
+        let current = lists(&[("ALLOWED_USERS", "1")]);
+
+        let reloaded = UserLists::reloaded(config, &startup, current);
+        assert_eq!(reloaded.allowed_users.as_deref(), Some("1"));
+        assert_eq!(reloaded.admin_ids, None);
+    }
+
+    #[test]
+    fn test_reload_keeps_lists_not_from_dotenv() {
+        // ADMIN_IDS comes from the real environment, not from `.env`
+        let config = lists(&[("ALLOWED_USERS", "1,2"), ("ADMIN_IDS", "9")]);
+        let startup = lists(&[("ALLOWED_USERS", "1,2"), ("ADMIN_IDS", "")]);
+        let current = lists(&[("ALLOWED_USERS", "1,2,3"), ("GROQ_API_KEY", "k")]);
+
+        let reloaded = UserLists::reloaded(config, &startup, current);
+        assert_eq!(reloaded.allowed_users.as_deref(), Some("1,2,3"));
+        assert_eq!(reloaded.admin_ids.as_deref(), Some("9"));
+        assert_eq!(reloaded.agent_access_ids, None);
+    }
+
+    #[test]
+    fn test_reloaded_lists_replace_startup_lists() {
+        let settings = TelegramSettings {
+            allowed_users_str: Some("1,2".to_string()),
+            admin_users_str: Some("1".to_string()),
+            ..TelegramSettings::default()
+        };
+        let shared = settings.clone();
+        assert_eq!(shared.allowed_users().len(), 2);
+
+        *settings
+            .reloaded_users
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(UserLists {
+            allowed_users: Some("1 2 3".to_string()),
+            agent_access_ids: Some("3".to_string()),
+            admin_ids: None,
+        });
+        assert_eq!(shared.allowed_users().len(), 3);
+        assert!(shared.agent_allowed_users().contains(&3));
+        assert!(shared.admin_users().is_empty());
+    }
+
+    #[test]
+    fn test_webhook_secret_charset() {
+        assert!(is_valid_webhook_secret("my-Secret_123"));
+        assert!(is_valid_webhook_secret(&"a".repeat(256)));
+        assert!(!is_valid_webhook_secret(""));
+        assert!(!is_valid_webhook_secret(&"a".repeat(257)));
+        assert!(!is_valid_webhook_secret("with space"));
+        assert!(!is_valid_webhook_secret("päss"));
+    }
 
     #[test]
     fn test_list_parsing() {
@@ -129,8 +451,12 @@ mod tests {
             telegram_token: "dummy".to_string(),
             allowed_users_str: None,
             agent_allowed_users_str: None,
+            admin_users_str: None,
+            ..TelegramSettings::default()
         };
 
+        assert!(settings.admin_users().is_empty());
+
         // Test comma
         settings.allowed_users_str = Some("123,456".to_string());
         let allowed = settings.allowed_users();
@@ -0,0 +1,183 @@
+//! Liveness and readiness probes
+//!
+//! When `HEALTH_ADDR` is set, a small HTTP server answers:
+//! - `GET /healthz`: 200 while the process is up
+//! - `GET /readyz`: 200 when storage answers and at least one LLM provider is
+//!   reachable, 503 with the failing checks otherwise
+//!
+//! The readiness checks run in the background every
+//! [`READINESS_CHECK_INTERVAL`]; `/readyz` answers with the latest result, so
+//! probes never hit storage or a provider themselves.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+use oxide_agent_core::llm::LlmClient;
+use oxide_agent_core::storage::StorageProvider;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// How often the readiness checks run
+pub const READINESS_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Latest readiness result, `None` until the first check finished
+type ReadinessCache = watch::Receiver<Option<Arc<Readiness>>>;
+
+/// Outcome of the readiness checks
+#[derive(Debug)]
+pub struct Readiness {
+    /// Storage connection check
+    pub storage: Result<(), String>,
+    /// First reachable LLM provider
+    pub provider: Result<String, String>,
+}
+
+impl Readiness {
+    /// Run the readiness checks
+    pub async fn check(storage: &dyn StorageProvider, llm: &LlmClient) -> Self {
+        let (storage, provider) = tokio::join!(storage.check_connection(), llm.ping_providers());
+        Self {
+            storage,
+            provider: provider.map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Whether the bot can serve requests
+    #[must_use]
+    pub const fn is_ready(&self) -> bool {
+        self.storage.is_ok() && self.provider.is_ok()
+    }
+
+    /// Plain-text report, one line per check
+    #[must_use]
+    pub fn report(&self) -> String {
+        let storage = match &self.storage {
+            Ok(()) => "ok".to_string(),
+            Err(e) => format!("error: {e}"),
+        };
+        let provider = match &self.provider {
+            Ok(name) => format!("ok ({name})"),
+            Err(e) => format!("error: {e}"),
+        };
+        let status = if self.is_ready() {
+            "ready"
+        } else {
+            "not ready"
+        };
+        format!("{status}\nstorage: {storage}\nllm: {provider}\n")
+    }
+}
+
+fn router(readiness: ReadinessCache) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(readiness)
+}
+
+async fn healthz() -> &'static str {
+    "ok\n"
+}
+
+async fn readyz(State(readiness): State<ReadinessCache>) -> (StatusCode, String) {
+    let Some(readiness) = readiness.borrow().clone() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "not ready\nchecks pending\n".to_string(),
+        );
+    };
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, readiness.report())
+}
+
+/// Run the readiness checks every [`READINESS_CHECK_INTERVAL`] and publish
+/// each result
+fn spawn_readiness_checks(
+    storage: Arc<dyn StorageProvider>,
+    llm: Arc<LlmClient>,
+) -> ReadinessCache {
+    let (tx, rx) = watch::channel(None);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(READINESS_CHECK_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let readiness = Readiness::check(storage.as_ref(), &llm).await;
+            if !readiness.is_ready() {
+                warn!(report = %readiness.report(), "Readiness check failed");
+            }
+            if tx.send(Some(Arc::new(readiness))).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Serve the health endpoints on `addr` until the process exits.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound or the server fails.
+pub async fn serve(
+    addr: &str,
+    storage: Arc<dyn StorageProvider>,
+    llm: Arc<LlmClient>,
+) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Health endpoints listening on {}", listener.local_addr()?);
+    let readiness = spawn_readiness_checks(storage, llm);
+    axum::serve(listener, router(readiness)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{readyz, Readiness};
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use std::sync::Arc;
+    use tokio::sync::watch;
+
+    #[test]
+    fn test_report_lists_failing_checks() {
+        let ready = Readiness {
+            storage: Ok(()),
+            provider: Ok("groq".to_string()),
+        };
+        assert!(ready.is_ready());
+        assert_eq!(ready.report(), "ready\nstorage: ok\nllm: ok (groq)\n");
+
+        let not_ready = Readiness {
+            storage: Err("bucket not found".to_string()),
+            provider: Ok("groq".to_string()),
+        };
+        assert!(!not_ready.is_ready());
+        assert!(not_ready
+            .report()
+            .starts_with("not ready\nstorage: error: bucket not found\n"));
+    }
+
+    #[tokio::test]
+    async fn test_readyz_serves_the_latest_check() {
+        let (tx, rx) = watch::channel(None);
+        let (status, body) = readyz(State(rx.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("checks pending"));
+
+        let _ = tx.send(Some(Arc::new(Readiness {
+            storage: Ok(()),
+            provider: Ok("groq".to_string()),
+        })));
+        let (status, body) = readyz(State(rx)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.starts_with("ready\n"));
+    }
+}
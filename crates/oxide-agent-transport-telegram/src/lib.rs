@@ -5,5 +5,7 @@
 pub mod bot;
 /// Telegram transport configuration.
 pub mod config;
+/// Liveness and readiness HTTP probes.
+pub mod health;
 /// Telegram runtime entrypoint.
 pub mod runner;
@@ -1,19 +1,25 @@
 use crate::bot;
-use crate::bot::handlers::{get_user_id_safe, Command};
+use crate::bot::group::is_unaddressed_group_message;
+use crate::bot::handlers::{get_sender_id, get_user_id_safe, Command};
 use crate::bot::state::State;
-use crate::bot::UnauthorizedCache;
+use crate::bot::{MaintenanceMode, UnauthorizedCache};
 use crate::config::{
-    get_unauthorized_cache_max_size, get_unauthorized_cache_ttl, get_unauthorized_cooldown,
-    BotSettings,
+    get_health_addr, get_maintenance_mode, get_shutdown_grace_secs, get_telegram_webhook_port,
+    get_telegram_webhook_secret, get_telegram_webhook_url, get_unauthorized_cache_max_size,
+    get_unauthorized_cache_ttl, get_unauthorized_cooldown, is_valid_webhook_secret, BotSettings,
 };
+use oxide_agent_core::sandbox::SandboxManager;
 use oxide_agent_core::storage::StorageProvider;
 use oxide_agent_core::{llm, storage};
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 use teloxide::dispatching::dialogue::InMemStorage;
-use teloxide::dispatching::UpdateHandler;
+use teloxide::dispatching::{DefaultKey, UpdateHandler};
 use teloxide::prelude::*;
-use teloxide::types::CallbackQuery;
-use tracing::{error, info};
+use teloxide::types::{CallbackQuery, Me};
+use teloxide::update_listeners::webhooks;
+use tracing::{error, info, warn};
 
 /// Run the Telegram transport runtime.
 pub async fn run_bot(settings: Arc<BotSettings>) {
@@ -21,26 +27,189 @@ pub async fn run_bot(settings: Arc<BotSettings>) {
 
     let llm_client = Arc::new(llm::LlmClient::new(settings.agent.as_ref()));
     info!("LLM Client initialized.");
+    spawn_health_server(&storage, &llm_client);
 
     let bot = Bot::new(settings.telegram.telegram_token.clone());
+    register_command_menu(&bot).await;
     let bot_state = init_bot_state();
     let unauthorized_cache = init_unauthorized_cache();
+    let maintenance = Arc::new(MaintenanceMode::new(get_maintenance_mode()));
+    if maintenance.is_enabled() {
+        info!("Starting in maintenance mode (MAINTENANCE_MODE is set).");
+    }
     let handler = setup_handler();
 
+    match SandboxManager::cleanup_stale_sandboxes().await {
+        Ok(0) => {}
+        Ok(removed) => info!("Removed {removed} stale sandbox container(s)."),
+        Err(e) => warn!("Stale sandbox cleanup skipped: {e}"),
+    }
+    SandboxManager::spawn_idle_reaper();
+
+    #[cfg(unix)]
+    reload_users_on_sighup(Arc::clone(&settings));
+
     info!("Bot is running...");
 
-    Dispatcher::builder(bot, handler)
+    let mut dispatcher = Dispatcher::builder(bot.clone(), handler)
         .dependencies(dptree::deps![
-            storage,
+            storage.clone(),
             llm_client,
             settings,
             bot_state,
-            unauthorized_cache
+            unauthorized_cache,
+            maintenance
         ])
-        .enable_ctrlc_handler()
-        .build()
-        .dispatch()
-        .await;
+        .build();
+
+    let shutdown_token = dispatcher.shutdown_token();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        info!("Shutdown signal received, no longer accepting updates...");
+        match shutdown_token.shutdown() {
+            Ok(stopped) => stopped.await,
+            Err(e) => warn!("Failed to stop dispatcher: {e}"),
+        }
+    });
+
+    dispatch_updates(&mut dispatcher, bot).await;
+
+    let grace = Duration::from_secs(get_shutdown_grace_secs());
+    bot::agent_handlers::shutdown_agent_sessions(&storage, grace).await;
+    info!("Shutdown complete.");
+}
+
+/// Receive updates via webhook when `TELEGRAM_WEBHOOK_URL` is set, otherwise
+/// (or if the webhook cannot be set up) via long polling.
+async fn dispatch_updates(
+    dispatcher: &mut Dispatcher<Bot, teloxide::RequestError, DefaultKey>,
+    bot: Bot,
+) {
+    let options = match webhook_options() {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            info!("Receiving updates via long polling.");
+            return dispatcher.dispatch().await;
+        }
+        Err(e) => {
+            error!("Invalid webhook configuration, falling back to long polling: {e}");
+            return dispatcher.dispatch().await;
+        }
+    };
+
+    let (url, address) = (options.url.clone(), options.address);
+    match webhooks::axum(bot, options).await {
+        Ok(listener) => {
+            info!(%url, %address, "Receiving updates via webhook.");
+            dispatcher
+                .dispatch_with_listener(
+                    listener,
+                    LoggingErrorHandler::with_custom_text("Webhook listener error"),
+                )
+                .await;
+        }
+        Err(e) => {
+            error!("Failed to register webhook, falling back to long polling: {e}");
+            dispatcher.dispatch().await;
+        }
+    }
+}
+
+/// Webhook listener options, or `None` when `TELEGRAM_WEBHOOK_URL` is unset.
+fn webhook_options() -> anyhow::Result<Option<webhooks::Options>> {
+    let Some(url) = get_telegram_webhook_url() else {
+        return Ok(None);
+    };
+    let url = url::Url::parse(&url)
+        .map_err(|e| anyhow::anyhow!("TELEGRAM_WEBHOOK_URL is not a valid URL: {e}"))?;
+    if url.scheme() != "https" {
+        anyhow::bail!("TELEGRAM_WEBHOOK_URL must use https");
+    }
+
+    let address = SocketAddr::from((Ipv4Addr::UNSPECIFIED, get_telegram_webhook_port()));
+    let mut options = webhooks::Options::new(address, url);
+    if let Some(secret) = get_telegram_webhook_secret() {
+        if !is_valid_webhook_secret(&secret) {
+            anyhow::bail!(
+                "TELEGRAM_WEBHOOK_SECRET must be 1-256 characters of A-Z, a-z, 0-9, _ and -"
+            );
+        }
+        options = options.secret_token(secret);
+    }
+    Ok(Some(options))
+}
+
+/// Serve `/healthz` and `/readyz` in the background when `HEALTH_ADDR` is set.
+fn spawn_health_server(storage: &Arc<dyn StorageProvider>, llm: &Arc<llm::LlmClient>) {
+    let Some(addr) = get_health_addr() else {
+        return;
+    };
+    let (storage, llm) = (Arc::clone(storage), Arc::clone(llm));
+    tokio::spawn(async move {
+        if let Err(e) = crate::health::serve(&addr, storage, llm).await {
+            error!("Health server on {addr} stopped: {e}");
+        }
+    });
+}
+
+/// Show the public commands in Telegram's command menu.
+async fn register_command_menu(bot: &Bot) {
+    match bot.set_my_commands(bot::handlers::menu_commands()).await {
+        Ok(_) => info!("Command menu registered."),
+        Err(e) => warn!("Failed to register the command menu: {e}"),
+    }
+}
+
+/// Re-read the allowed user lists on SIGHUP, like `/reloadusers`.
+#[cfg(unix)]
+fn reload_users_on_sighup(settings: Arc<BotSettings>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                warn!("Failed to listen for SIGHUP, user lists reload via /reloadusers only: {e}");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading user lists...");
+            if let Err(e) = settings.telegram.reload_user_lists() {
+                error!("Failed to reload user lists: {e}");
+            }
+        }
+    });
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM.
+async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
 }
 
 async fn init_storage(settings: &BotSettings) -> Arc<dyn storage::StorageProvider> {
@@ -90,6 +259,19 @@ fn setup_handler() -> UpdateHandler<teloxide::RequestError> {
                 })
                 .endpoint(handle_loop_callback),
         )
+        .branch(
+            // Group mode: silently drop group messages not addressed to the bot
+            Update::filter_message()
+                .filter(|msg: Message, me: Me| is_unaddressed_group_message(&msg, &me))
+                .endpoint(|| async { respond(()) }),
+        )
+        .branch(
+            // Available to everyone so users without access can learn their ID
+            Update::filter_message()
+                .filter_command::<Command>()
+                .filter(|cmd: Command| matches!(cmd, Command::Whoami))
+                .endpoint(handle_whoami_command),
+        )
         .branch(
             Update::filter_message().branch(
                 // Main branch for authorized users
@@ -97,60 +279,45 @@ fn setup_handler() -> UpdateHandler<teloxide::RequestError> {
                     settings
                         .telegram
                         .allowed_users()
-                        .contains(&get_user_id_safe(&msg))
+                        .contains(&get_sender_id(&msg))
                 })
-                .enter_dialogue::<Message, InMemStorage<State>, State>()
+                .chain(enter_scoped_dialogue())
                 .branch(
                     dptree::entry()
                         .filter_command::<Command>()
-                        .endpoint(handle_command),
+                        .filter(|cmd: Command| matches!(cmd, Command::Maintenance(_)))
+                        .endpoint(handle_maintenance_command),
+                )
+                .branch(
+                    dptree::entry()
+                        .filter_command::<Command>()
+                        .filter(|cmd: Command| matches!(cmd, Command::ReloadUsers))
+                        .endpoint(handle_reload_users_command),
+                )
+                .branch(
+                    dptree::filter(|msg: Message, maintenance: Arc<MaintenanceMode>| {
+                        is_rejected_by_maintenance(&msg, &maintenance)
+                    })
+                    .endpoint(handle_maintenance_notice),
                 )
                 .branch(
-                    dptree::case![State::Start]
-                        .branch(
-                            Update::filter_message()
-                                .filter(|msg: Message| msg.text().is_some())
-                                .endpoint(handle_start_text),
-                        )
-                        .branch(
-                            Update::filter_message()
-                                .filter(|msg: Message| msg.voice().is_some())
-                                .endpoint(handle_start_voice),
-                        )
-                        .branch(
-                            Update::filter_message()
-                                .filter(|msg: Message| msg.photo().is_some())
-                                .endpoint(handle_start_photo),
-                        )
-                        .branch(
-                            dptree::filter(|msg: Message| msg.document().is_some())
-                                .endpoint(handle_start_document),
-                        ),
+                    dptree::entry()
+                        .filter_command::<Command>()
+                        .filter(|cmd: Command| {
+                            matches!(cmd, Command::Summarize | Command::Regenerate)
+                        })
+                        .endpoint(handle_llm_command),
                 )
                 .branch(
-                    dptree::case![State::ChatMode]
-                        .branch(
-                            Update::filter_message()
-                                .filter(|msg: Message| msg.text().is_some())
-                                .endpoint(handle_start_text),
-                        )
-                        .branch(
-                            Update::filter_message()
-                                .filter(|msg: Message| msg.voice().is_some())
-                                .endpoint(handle_start_voice),
-                        )
-                        .branch(
-                            Update::filter_message()
-                                .filter(|msg: Message| msg.photo().is_some())
-                                .endpoint(handle_start_photo),
-                        )
-                        .branch(
-                            dptree::filter(|msg: Message| msg.document().is_some())
-                                .endpoint(handle_start_document),
-                        ),
+                    dptree::entry()
+                        .filter_command::<Command>()
+                        .endpoint(handle_command),
                 )
+                .branch(dptree::case![State::Start].chain(chat_input_handler()))
+                .branch(dptree::case![State::ChatMode].chain(chat_input_handler()))
                 .branch(dptree::case![State::EditingPrompt].endpoint(handle_editing_prompt))
                 .branch(dptree::case![State::AgentMode].endpoint(handle_agent_message))
+                .branch(dptree::case![State::AgentAwaitingAnswer].endpoint(handle_agent_message))
                 .branch(
                     dptree::case![State::AgentConfirmation(action)]
                         .endpoint(handle_agent_confirmation),
@@ -163,12 +330,56 @@ fn setup_handler() -> UpdateHandler<teloxide::RequestError> {
         )
 }
 
+/// Enters the dialogue of the message's conversation scope.
+///
+/// Like `enter_dialogue`, but keyed by the same scope id that storage and
+/// agent sessions use, so each forum topic keeps its own state.
+fn enter_scoped_dialogue() -> UpdateHandler<teloxide::RequestError> {
+    dptree::filter_map(|storage: Arc<InMemStorage<State>>, msg: Message| {
+        Some(Dialogue::new(storage, ChatId(get_user_id_safe(&msg))))
+    })
+    .filter_map_async(
+        |dialogue: Dialogue<State, InMemStorage<State>>| async move {
+            match dialogue.get_or_default().await {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    error!("Failed to read dialogue state: {e:?}");
+                    None
+                }
+            }
+        },
+    )
+}
+
+/// Text, voice, photo and document handling shared by the chat states
+fn chat_input_handler() -> UpdateHandler<teloxide::RequestError> {
+    dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter(|msg: Message| msg.text().is_some())
+                .endpoint(handle_start_text),
+        )
+        .branch(
+            Update::filter_message()
+                .filter(|msg: Message| msg.voice().is_some())
+                .endpoint(handle_start_voice),
+        )
+        .branch(
+            Update::filter_message()
+                .filter(|msg: Message| msg.photo().is_some())
+                .endpoint(handle_start_photo),
+        )
+        .branch(
+            dptree::filter(|msg: Message| msg.document().is_some()).endpoint(handle_start_document),
+        )
+}
+
 async fn handle_unauthorized(
     bot: Bot,
     msg: Message,
     cache: Arc<UnauthorizedCache>,
 ) -> Result<(), teloxide::RequestError> {
-    let user_id = get_user_id_safe(&msg);
+    let user_id = get_sender_id(&msg);
     let user_name = msg
         .from
         .as_ref()
@@ -208,6 +419,15 @@ async fn handle_command(
         Command::Clear => bot::handlers::clear(bot, msg, storage).await,
         Command::Healthcheck => bot::handlers::healthcheck(bot, msg).await,
         Command::Stats => bot::handlers::stats(bot, msg, cache).await,
+        Command::NewTask => bot::agent_handlers::start_new_task(bot, msg, storage, dialogue).await,
+        Command::Lang(language) => bot::handlers::set_language(bot, msg, storage, language).await,
+        Command::Debug(arg) => bot::agent_handlers::debug_session(bot, msg, settings, arg).await,
+        // Routed to dedicated endpoints before reaching here
+        Command::Maintenance(_)
+        | Command::ReloadUsers
+        | Command::Summarize
+        | Command::Regenerate
+        | Command::Whoami => Ok(()),
     };
     if let Err(e) = res {
         error!("Command error: {}", e);
@@ -215,6 +435,85 @@ async fn handle_command(
     respond(())
 }
 
+/// Commands that need the LLM client
+async fn handle_llm_command(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    storage: Arc<dyn storage::StorageProvider>,
+    llm: Arc<llm::LlmClient>,
+    settings: Arc<BotSettings>,
+) -> Result<(), teloxide::RequestError> {
+    let res = match cmd {
+        Command::Summarize => bot::handlers::summarize(bot, msg, storage, llm).await,
+        Command::Regenerate => bot::handlers::regenerate(bot, msg, storage, llm, settings).await,
+        _ => Ok(()),
+    };
+    if let Err(e) = res {
+        error!("Command error: {}", e);
+    }
+    respond(())
+}
+
+async fn handle_whoami_command(
+    bot: Bot,
+    msg: Message,
+    storage: Arc<dyn storage::StorageProvider>,
+    settings: Arc<BotSettings>,
+) -> Result<(), teloxide::RequestError> {
+    if let Err(e) = bot::handlers::whoami(bot, msg, storage, settings).await {
+        error!("Whoami command error: {}", e);
+    }
+    respond(())
+}
+
+async fn handle_maintenance_command(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    settings: Arc<BotSettings>,
+    maintenance: Arc<MaintenanceMode>,
+) -> Result<(), teloxide::RequestError> {
+    if let Command::Maintenance(arg) = cmd {
+        if let Err(e) = bot::handlers::maintenance(bot, msg, settings, maintenance, arg).await {
+            error!("Maintenance command error: {}", e);
+        }
+    }
+    respond(())
+}
+
+async fn handle_reload_users_command(
+    bot: Bot,
+    msg: Message,
+    settings: Arc<BotSettings>,
+) -> Result<(), teloxide::RequestError> {
+    if let Err(e) = bot::handlers::reload_users(bot, msg, settings).await {
+        error!("Reload users command error: {}", e);
+    }
+    respond(())
+}
+
+/// Drop new work while maintenance mode is on.
+///
+/// Agent control buttons still go through so users can cancel or leave a
+/// running task; the task itself is never interrupted.
+fn is_rejected_by_maintenance(msg: &Message, maintenance: &MaintenanceMode) -> bool {
+    maintenance.is_enabled()
+        && !msg
+            .text()
+            .is_some_and(bot::agent_handlers::is_agent_control_text)
+}
+
+async fn handle_maintenance_notice(bot: Bot, msg: Message) -> Result<(), teloxide::RequestError> {
+    if let Err(e) = bot
+        .send_message(msg.chat.id, bot::maintenance::MAINTENANCE_MESSAGE)
+        .await
+    {
+        error!("Failed to send maintenance notice: {}", e);
+    }
+    respond(())
+}
+
 async fn handle_start_text(
     bot: Bot,
     msg: Message,